@@ -0,0 +1,770 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * element-vosktranscriber:
+ *
+ * Transcribes speech to text offline with a local Vosk model, following the
+ * transcriber contract of `transcriberbin`.
+ *
+ * Each output buffer holds one word. Vosk only settles on its words at the
+ * end of an utterance, words that were recognized for half of the `latency`
+ * are thus committed as stable before that. With `partial-results`, the
+ * remaining words of the current hypothesis are output as well, flagged
+ * with %GST_BUFFER_FLAG_DELTA_UNIT.
+ *
+ * ```console
+ * $ gst-launch-1.0 filesrc location=speech.ogg ! decodebin ! audioconvert ! audioresample ! \
+ *     vosktranscriber model-path=vosk-model-small-en-us-0.15 ! fakesink dump=true
+ * ```
+ */
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst::{element_error, error_msg};
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use super::items::{Item, Stabilizer};
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "vosktranscriber",
+        gst::DebugColorFlags::empty(),
+        Some("Vosk transcriber element"),
+    )
+});
+
+const DEFAULT_LATENCY: gst::ClockTime = gst::ClockTime::from_seconds(4);
+const DEFAULT_PARTIAL_RESULTS: bool = false;
+/// Input timestamps further away than this from the expected ones
+/// start a new utterance
+const DISCONT_THRESHOLD: gst::ClockTime = gst::ClockTime::from_mseconds(40);
+
+#[derive(Debug, Clone)]
+struct Settings {
+    model_path: Option<String>,
+    latency: gst::ClockTime,
+    partial_results: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            model_path: None,
+            latency: DEFAULT_LATENCY,
+            partial_results: DEFAULT_PARTIAL_RESULTS,
+        }
+    }
+}
+
+struct State {
+    model: Option<vosk::Model>,
+    recognizer: Option<vosk::Recognizer>,
+    rate: u32,
+    in_segment: gst::FormattedSegment<gst::ClockTime>,
+    /// PTS of the first sample fed to the recognizer since the last reset
+    base_pts: Option<gst::ClockTime>,
+    /// Samples fed to the recognizer since the last reset
+    samples: u64,
+    /// End of the last stable item or gap pushed downstream
+    out_position: Option<gst::ClockTime>,
+    stabilizer: Stabilizer,
+    discont: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            model: None,
+            recognizer: None,
+            rate: 0,
+            in_segment: gst::FormattedSegment::new(),
+            base_pts: None,
+            samples: 0,
+            out_position: None,
+            stabilizer: Stabilizer::new(DEFAULT_LATENCY.nseconds() / 2),
+            discont: true,
+        }
+    }
+}
+
+impl State {
+    fn position(&self) -> gst::ClockTime {
+        gst::ClockTime::SECOND
+            .mul_div_floor(self.samples, self.rate as u64)
+            .unwrap_or(gst::ClockTime::ZERO)
+    }
+}
+
+fn items_from_words(words: &[vosk::Word]) -> Vec<Item> {
+    words
+        .iter()
+        .map(|word| {
+            Item::new(
+                (word.start as f64 * 1_000_000_000.0) as u64,
+                (word.end as f64 * 1_000_000_000.0) as u64,
+                word.word,
+            )
+        })
+        .collect()
+}
+
+pub struct Transcriber {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl Transcriber {
+    /* Timestamps the items and clamps them to what was already pushed.
+     * Unstable items may be replaced later on, they thus don't advance
+     * the output position */
+    fn items_to_buffers(
+        &self,
+        element: &super::Transcriber,
+        state: &mut State,
+        items: Vec<Item>,
+    ) -> Vec<gst::Buffer> {
+        let base_pts = match state.base_pts {
+            Some(base_pts) => base_pts,
+            None => return vec![],
+        };
+
+        let mut buffers = vec![];
+
+        for item in items {
+            let mut pts = base_pts + gst::ClockTime::from_nseconds(item.start);
+            let mut duration = gst::ClockTime::from_nseconds(item.end - item.start);
+
+            if let Some(out_position) = state.out_position {
+                if pts < out_position {
+                    let delta = out_position - pts;
+
+                    if item.stable {
+                        gst::warning!(
+                            CAT,
+                            obj: element,
+                            "Updating item PTS ({} < {}), consider increasing latency",
+                            pts,
+                            out_position
+                        );
+                    }
+
+                    pts = out_position;
+                    duration = duration.saturating_sub(delta);
+                }
+            }
+
+            gst::debug!(
+                CAT,
+                obj: element,
+                "Item {} ({}): {} -> {}",
+                item.content,
+                if item.stable { "stable" } else { "unstable" },
+                pts,
+                pts + duration
+            );
+
+            let mut buf = gst::Buffer::from_mut_slice(item.content.into_bytes());
+
+            {
+                let buf = buf.get_mut().unwrap();
+
+                buf.set_pts(pts);
+                buf.set_duration(duration);
+
+                if state.discont {
+                    buf.set_flags(gst::BufferFlags::DISCONT);
+                    state.discont = false;
+                }
+
+                if !item.stable {
+                    buf.set_flags(gst::BufferFlags::DELTA_UNIT);
+                }
+            }
+
+            if item.stable {
+                state.out_position = Some(pts + duration);
+            }
+
+            buffers.push(buf);
+        }
+
+        buffers
+    }
+
+    /* Finalizes the current utterance, the next buffer starts a new one */
+    fn drain(&self, element: &super::Transcriber, state: &mut State) -> Vec<gst::Buffer> {
+        let items = match state.recognizer.as_mut() {
+            Some(recognizer) => {
+                let items = match recognizer.final_result().single() {
+                    Some(result) => items_from_words(&result.result),
+                    None => vec![],
+                };
+                recognizer.reset();
+                state.stabilizer.finalize(items)
+            }
+            None => vec![],
+        };
+
+        let buffers = self.items_to_buffers(element, state, items);
+
+        state.base_pts = None;
+        state.samples = 0;
+        state.stabilizer.reset();
+
+        buffers
+    }
+
+    /* Any stable item yet to come will at least start this far behind
+     * the audio we received, let downstream know */
+    fn gap(&self, state: &mut State, latency: gst::ClockTime) -> Option<gst::Event> {
+        let base_pts = state.base_pts?;
+        let out_position = state.out_position?;
+        let horizon = (base_pts + state.position()).saturating_sub(latency);
+
+        if horizon <= out_position {
+            return None;
+        }
+
+        state.out_position = Some(horizon);
+
+        Some(
+            gst::event::Gap::builder(out_position)
+                .duration(horizon - out_position)
+                .build(),
+        )
+    }
+
+    fn push(
+        &self,
+        element: &super::Transcriber,
+        buffers: Vec<gst::Buffer>,
+        gap: Option<gst::Event>,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        for buffer in buffers {
+            self.srcpad.push(buffer)?;
+        }
+
+        if let Some(gap) = gap {
+            gst::log!(CAT, obj: element, "Pushing {:?}", gap);
+            self.srcpad.push_event(gap);
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn sink_chain(
+        &self,
+        pad: &gst::Pad,
+        element: &super::Transcriber,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::log!(CAT, obj: pad, "Handling {:?}", buffer);
+
+        let (latency, partial_results) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.latency, settings.partial_results)
+        };
+
+        let mut state = self.state.lock().unwrap();
+
+        if state.recognizer.is_none() {
+            element_error!(
+                element,
+                gst::CoreError::Negotiation,
+                ["No caps received before the first buffer"]
+            );
+            return Err(gst::FlowError::NotNegotiated);
+        }
+
+        let mut buffers = vec![];
+
+        if let Some(base_pts) = state.base_pts {
+            let expected = base_pts + state.position();
+            let discont = buffer.flags().contains(gst::BufferFlags::DISCONT)
+                || buffer.pts().map_or(false, |pts| {
+                    let diff = if pts > expected {
+                        pts - expected
+                    } else {
+                        expected - pts
+                    };
+                    diff > DISCONT_THRESHOLD
+                });
+
+            if discont {
+                gst::debug!(
+                    CAT,
+                    obj: element,
+                    "Discontinuity at {}, expected {}, finalizing utterance",
+                    buffer.pts().display(),
+                    expected
+                );
+                buffers = self.drain(element, &mut state);
+            }
+        }
+
+        if state.base_pts.is_none() {
+            let base_pts = buffer.pts().or_else(|| state.in_segment.start());
+            state.base_pts = base_pts;
+            if state.out_position.is_none() {
+                state.out_position = base_pts;
+            }
+        }
+
+        let samples = {
+            let data = buffer.map_readable().map_err(|_| {
+                gst::error!(CAT, obj: element, "Failed to map buffer readable");
+                gst::FlowError::Error
+            })?;
+
+            data.chunks_exact(2)
+                .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+                .collect::<Vec<_>>()
+        };
+
+        let recognizer = state.recognizer.as_mut().unwrap();
+        let (finalized, items) = match recognizer.accept_waveform(&samples) {
+            vosk::DecodingState::Failed => {
+                element_error!(
+                    element,
+                    gst::StreamError::Failed,
+                    ["Failed to decode audio"]
+                );
+                return Err(gst::FlowError::Error);
+            }
+            vosk::DecodingState::Finalized => (
+                true,
+                recognizer
+                    .result()
+                    .single()
+                    .map(|result| items_from_words(&result.result))
+                    .unwrap_or_default(),
+            ),
+            vosk::DecodingState::Running => (
+                false,
+                items_from_words(&recognizer.partial_result().partial_result),
+            ),
+        };
+
+        state.samples += samples.len() as u64;
+
+        let items = if finalized {
+            state.stabilizer.finalize(items)
+        } else {
+            let position = state.position().nseconds();
+            state.stabilizer.partial(items, position, partial_results)
+        };
+        buffers.extend(self.items_to_buffers(element, &mut state, items));
+
+        let gap = self.gap(&mut state, latency);
+
+        drop(state);
+
+        self.push(element, buffers, gap)
+    }
+
+    fn setup_recognizer(&self, element: &super::Transcriber, state: &mut State, rate: u32) -> bool {
+        let model = match state.model.as_ref() {
+            Some(model) => model,
+            None => return false,
+        };
+
+        let mut recognizer = match vosk::Recognizer::new(model, rate as f32) {
+            Some(recognizer) => recognizer,
+            None => {
+                element_error!(
+                    element,
+                    gst::LibraryError::Init,
+                    ["Failed to create recognizer for rate {}", rate]
+                );
+                return false;
+            }
+        };
+
+        recognizer.set_words(true);
+        recognizer.set_partial_words(true);
+
+        state.recognizer = Some(recognizer);
+        state.rate = rate;
+
+        true
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, element: &super::Transcriber, event: gst::Event) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad, "Handling event {:?}", event);
+
+        match event.view() {
+            EventView::Caps(e) => {
+                let rate = match e
+                    .caps()
+                    .structure(0)
+                    .and_then(|s| s.get::<i32>("rate").ok())
+                {
+                    Some(rate) if rate > 0 => rate as u32,
+                    _ => {
+                        gst::error!(CAT, obj: pad, "Invalid caps {:?}", e.caps());
+                        return false;
+                    }
+                };
+
+                let mut state = self.state.lock().unwrap();
+
+                if state.recognizer.is_some() && state.rate == rate {
+                    return true;
+                }
+
+                let buffers = self.drain(element, &mut state);
+                if !self.setup_recognizer(element, &mut state, rate) {
+                    return false;
+                }
+                drop(state);
+
+                let _ = self.push(element, buffers, None);
+
+                let caps = gst::Caps::builder("text/x-raw")
+                    .field("format", "utf8")
+                    .build();
+                self.srcpad.push_event(
+                    gst::event::Caps::builder(&caps)
+                        .seqnum(event.seqnum())
+                        .build(),
+                )
+            }
+            EventView::Segment(e) => {
+                let segment = match e.segment().clone().downcast::<gst::ClockTime>() {
+                    Err(segment) => {
+                        element_error!(
+                            element,
+                            gst::StreamError::Format,
+                            ["Only Time segments supported, got {:?}", segment.format(),]
+                        );
+                        return false;
+                    }
+                    Ok(segment) => segment,
+                };
+
+                let mut state = self.state.lock().unwrap();
+                let buffers = self.drain(element, &mut state);
+                state.in_segment = segment;
+                state.out_position = None;
+                drop(state);
+
+                let _ = self.push(element, buffers, None);
+
+                pad.event_default(Some(element), event)
+            }
+            EventView::Gap(e) => {
+                let (timestamp, duration) = e.get();
+
+                let mut state = self.state.lock().unwrap();
+                let buffers = self.drain(element, &mut state);
+                let gap = match (state.out_position, duration) {
+                    (Some(out_position), Some(duration)) if timestamp + duration > out_position => {
+                        state.out_position = Some(timestamp + duration);
+                        Some(
+                            gst::event::Gap::builder(out_position)
+                                .duration(timestamp + duration - out_position)
+                                .seqnum(event.seqnum())
+                                .build(),
+                        )
+                    }
+                    (None, _) => {
+                        state.out_position = duration.map(|duration| timestamp + duration);
+                        Some(event.clone())
+                    }
+                    _ => None,
+                };
+                drop(state);
+
+                self.push(element, buffers, gap).is_ok()
+            }
+            EventView::Eos(_) => {
+                let mut state = self.state.lock().unwrap();
+                let buffers = self.drain(element, &mut state);
+                drop(state);
+
+                let _ = self.push(element, buffers, None);
+
+                pad.event_default(Some(element), event)
+            }
+            EventView::FlushStop(_) => {
+                let mut state = self.state.lock().unwrap();
+                if let Some(recognizer) = state.recognizer.as_mut() {
+                    recognizer.reset();
+                }
+                state.base_pts = None;
+                state.samples = 0;
+                state.out_position = None;
+                state.stabilizer.reset();
+                state.discont = true;
+                drop(state);
+
+                pad.event_default(Some(element), event)
+            }
+            _ => pad.event_default(Some(element), event),
+        }
+    }
+
+    fn src_query(
+        &self,
+        pad: &gst::Pad,
+        element: &super::Transcriber,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        use gst::QueryViewMut;
+
+        gst::log!(CAT, obj: pad, "Handling query {:?}", query);
+
+        match query.view_mut() {
+            QueryViewMut::Latency(q) => {
+                let mut peer_query = gst::query::Latency::new();
+
+                let ret = self.sinkpad.peer_query(&mut peer_query);
+
+                if ret {
+                    let (live, min, _) = peer_query.result();
+                    let our_latency = self.settings.lock().unwrap().latency;
+                    q.set(live, our_latency + min, gst::ClockTime::NONE);
+                }
+                ret
+            }
+            _ => pad.query_default(Some(element), query),
+        }
+    }
+
+    fn start(&self, element: &super::Transcriber) -> Result<(), gst::ErrorMessage> {
+        let settings = self.settings.lock().unwrap().clone();
+
+        let model_path = settings
+            .model_path
+            .ok_or_else(|| error_msg!(gst::ResourceError::Settings, ["No model-path specified"]))?;
+
+        gst::debug!(CAT, obj: element, "Loading model from {}", model_path);
+
+        let model = vosk::Model::new(model_path.as_str()).ok_or_else(|| {
+            error_msg!(
+                gst::ResourceError::OpenRead,
+                ["Failed to load model from {}", model_path]
+            )
+        })?;
+
+        *self.state.lock().unwrap() = State {
+            model: Some(model),
+            stabilizer: Stabilizer::new(settings.latency.nseconds() / 2),
+            ..Default::default()
+        };
+
+        Ok(())
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Transcriber {
+    const NAME: &'static str = "GstVoskTranscriber";
+    type Type = super::Transcriber;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                Transcriber::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |transcriber, element| transcriber.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Transcriber::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |transcriber, element| transcriber.sink_event(pad, element, event),
+                )
+            })
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .query_function(|pad, parent, query| {
+                Transcriber::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |transcriber, element| transcriber.src_query(pad, element, query),
+                )
+            })
+            .flags(gst::PadFlags::FIXED_CAPS)
+            .build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            settings: Mutex::new(Settings::default()),
+            state: Mutex::new(State::default()),
+        }
+    }
+}
+
+impl ObjectImpl for Transcriber {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::builder("model-path")
+                    .nick("Model Path")
+                    .blurb("Path to the directory of the Vosk model to use, this also determines the language")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("latency")
+                    .nick("Latency")
+                    .blurb("Amount of milliseconds to allow for transcription, \
+                    words still unstable after half of it are committed")
+                    .default_value(DEFAULT_LATENCY.mseconds() as u32)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("partial-results")
+                    .nick("Partial Results")
+                    .blurb("Also output unstable words, flagged with DELTA_UNIT")
+                    .default_value(DEFAULT_PARTIAL_RESULTS)
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "model-path" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.model_path = value.get().expect("type checked upstream");
+            }
+            "latency" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.latency = gst::ClockTime::from_mseconds(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
+            "partial-results" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.partial_results = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "model-path" => {
+                let settings = self.settings.lock().unwrap();
+                settings.model_path.to_value()
+            }
+            "latency" => {
+                let settings = self.settings.lock().unwrap();
+                (settings.latency.mseconds() as u32).to_value()
+            }
+            "partial-results" => {
+                let settings = self.settings.lock().unwrap();
+                settings.partial_results.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for Transcriber {}
+
+impl ElementImpl for Transcriber {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Transcriber",
+                "Audio/Text/Filter",
+                "Speech to Text filter, using a local Vosk model",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let src_caps = gst::Caps::builder("text/x-raw")
+                .field("format", "utf8")
+                .build();
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &src_caps,
+            )
+            .unwrap();
+
+            let sink_caps = gst::Caps::builder("audio/x-raw")
+                .field("format", "S16LE")
+                .field("layout", "interleaved")
+                .field("rate", gst::IntRange::new(8000i32, 48000))
+                .field("channels", 1)
+                .build();
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &sink_caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::info!(CAT, obj: element, "Changing state {:?}", transition);
+
+        if transition == gst::StateChange::ReadyToPaused {
+            self.start(element).map_err(|err| {
+                element.post_error_message(err);
+                gst::StateChangeError
+            })?;
+        }
+
+        let success = self.parent_change_state(element, transition)?;
+
+        if transition == gst::StateChange::PausedToReady {
+            *self.state.lock().unwrap() = State::default();
+        }
+
+        Ok(success)
+    }
+}
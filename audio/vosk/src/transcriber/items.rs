@@ -0,0 +1,211 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+// Turns the growing hypotheses of the recognizer into a stream of items.
+//
+// Vosk only settles on its words at the end of an utterance, which can be
+// arbitrarily far away. To honour the latency we advertise, words that have
+// been part of the hypothesis for long enough are committed early: they are
+// output as stable items and skipped once the final result comes in.
+
+/// A recognized word, with times in nanoseconds relative to the start of
+/// the audio fed to the recognizer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub start: u64,
+    pub end: u64,
+    pub content: String,
+    pub stable: bool,
+}
+
+impl Item {
+    pub fn new(start: u64, end: u64, content: &str) -> Self {
+        Self {
+            start,
+            end: end.max(start),
+            content: content.to_string(),
+            stable: false,
+        }
+    }
+
+    fn midpoint(&self) -> u64 {
+        self.start + (self.end - self.start) / 2
+    }
+}
+
+#[derive(Debug)]
+pub struct Stabilizer {
+    /// How long a word must have ended before we commit it
+    stabilization: u64,
+    /// End of the last committed word
+    committed_until: Option<u64>,
+    /// Unstable items output for the current hypothesis
+    unstable: Vec<Item>,
+}
+
+impl Stabilizer {
+    pub fn new(stabilization: u64) -> Self {
+        Self {
+            stabilization,
+            committed_until: None,
+            unstable: vec![],
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.committed_until = None;
+        self.unstable.clear();
+    }
+
+    fn is_committed(&self, item: &Item) -> bool {
+        // Words of the final result are usually re-segmented slightly
+        // compared to the partial hypothesis, compare midpoints
+        self.committed_until
+            .map_or(false, |until| item.midpoint() < until)
+    }
+
+    fn commit(&mut self, mut item: Item) -> Item {
+        self.committed_until = Some(item.end);
+        item.stable = true;
+        item
+    }
+
+    /// Handles a partial hypothesis once `position` nanoseconds of audio
+    /// were fed. Returns the items that became stable, followed by the
+    /// remaining unstable ones if `unstable` is set and they changed since
+    /// the last call.
+    pub fn partial(&mut self, words: Vec<Item>, position: u64, unstable: bool) -> Vec<Item> {
+        let mut ret = vec![];
+        let mut pending = vec![];
+
+        for word in words {
+            if self.is_committed(&word) {
+                continue;
+            }
+
+            if pending.is_empty() && word.end + self.stabilization <= position {
+                ret.push(self.commit(word));
+            } else {
+                pending.push(word);
+            }
+        }
+
+        if unstable && pending != self.unstable {
+            ret.extend(pending.iter().cloned());
+            self.unstable = pending;
+        }
+
+        ret
+    }
+
+    /// Handles the final result of an utterance: all words not committed
+    /// yet are output as stable items.
+    pub fn finalize(&mut self, words: Vec<Item>) -> Vec<Item> {
+        let mut ret = vec![];
+
+        for word in words {
+            if !self.is_committed(&word) {
+                ret.push(self.commit(word));
+            }
+        }
+
+        self.unstable.clear();
+
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u64 = 1_000_000;
+
+    fn words(words: &[(u64, u64, &str)]) -> Vec<Item> {
+        words
+            .iter()
+            .map(|(start, end, content)| Item::new(start * MS, end * MS, content))
+            .collect()
+    }
+
+    fn contents(items: &[Item]) -> Vec<(&str, bool)> {
+        items
+            .iter()
+            .map(|item| (item.content.as_str(), item.stable))
+            .collect()
+    }
+
+    #[test]
+    fn test_final_only() {
+        let mut stabilizer = Stabilizer::new(2000 * MS);
+
+        let items = stabilizer.partial(words(&[(0, 500, "hello")]), 1000 * MS, false);
+        assert!(items.is_empty());
+
+        let items = stabilizer.finalize(words(&[(0, 500, "hello"), (600, 1000, "world")]));
+        assert_eq!(contents(&items), vec![("hello", true), ("world", true)]);
+        assert_eq!(items[1].start, 600 * MS);
+        assert_eq!(items[1].end, 1000 * MS);
+    }
+
+    #[test]
+    fn test_early_commit() {
+        let mut stabilizer = Stabilizer::new(2000 * MS);
+
+        let hypothesis = words(&[(0, 500, "hello"), (600, 1000, "world"), (1100, 1500, "how")]);
+
+        let items = stabilizer.partial(hypothesis.clone(), 2500 * MS, false);
+        assert_eq!(contents(&items), vec![("hello", true)]);
+
+        // Nothing new to commit
+        let items = stabilizer.partial(hypothesis, 2900 * MS, false);
+        assert!(items.is_empty());
+
+        let items = stabilizer.partial(
+            words(&[(0, 500, "hello"), (600, 1000, "world"), (1100, 1500, "are")]),
+            3600 * MS,
+            false,
+        );
+        assert_eq!(contents(&items), vec![("world", true), ("are", true)]);
+
+        // Re-segmented final result, the committed words must not repeat
+        let items = stabilizer.finalize(words(&[
+            (0, 520, "hello"),
+            (580, 1020, "world"),
+            (1080, 1500, "are"),
+            (1600, 1900, "you"),
+        ]));
+        assert_eq!(contents(&items), vec![("you", true)]);
+
+        stabilizer.reset();
+        let items = stabilizer.finalize(words(&[(0, 300, "again")]));
+        assert_eq!(contents(&items), vec![("again", true)]);
+    }
+
+    #[test]
+    fn test_unstable() {
+        let mut stabilizer = Stabilizer::new(2000 * MS);
+
+        let items = stabilizer.partial(words(&[(0, 500, "hello")]), 1000 * MS, true);
+        assert_eq!(contents(&items), vec![("hello", false)]);
+
+        // Same hypothesis, nothing to output
+        let items = stabilizer.partial(words(&[(0, 500, "hello")]), 1200 * MS, true);
+        assert!(items.is_empty());
+
+        let items = stabilizer.partial(
+            words(&[(0, 500, "hello"), (600, 1000, "word")]),
+            2500 * MS,
+            true,
+        );
+        assert_eq!(contents(&items), vec![("hello", true), ("word", false)]);
+
+        let items = stabilizer.finalize(words(&[(0, 500, "hello"), (600, 1000, "world")]));
+        assert_eq!(contents(&items), vec![("world", true)]);
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstvosk::plugin_register_static().expect("Failed to register vosk plugin");
+    });
+}
+
+#[test]
+fn test_no_model() {
+    init();
+
+    let transcriber = gst::ElementFactory::make("vosktranscriber", None).unwrap();
+
+    assert!(transcriber.set_state(gst::State::Paused).is_err());
+    transcriber.set_state(gst::State::Null).unwrap();
+}
+
+/// Silence must not produce any item, but the transcriber has to keep
+/// downstream informed of its progress with gaps, staying within the
+/// configured latency.
+///
+/// Needs a model, pointed to by the VOSK_MODEL_PATH environment variable.
+#[test]
+#[ignore = "needs a Vosk model, set VOSK_MODEL_PATH and run with --ignored"]
+fn test_silence() {
+    init();
+
+    let model_path = std::env::var("VOSK_MODEL_PATH").expect("VOSK_MODEL_PATH not set");
+
+    let transcriber = gst::ElementFactory::make("vosktranscriber", None).unwrap();
+    transcriber.set_property("model-path", &model_path);
+    transcriber.set_property("latency", 2000u32);
+
+    let mut h = gst_check::Harness::with_element(&transcriber, Some("sink"), Some("src"));
+    h.set_src_caps_str("audio/x-raw,format=S16LE,layout=interleaved,rate=16000,channels=1");
+    h.play();
+
+    // 10 seconds of silence, in 100ms buffers
+    for i in 0..100u64 {
+        let mut buf = gst::Buffer::from_mut_slice(vec![0u8; 3200]);
+        {
+            let buf = buf.get_mut().unwrap();
+            buf.set_pts(i * 100 * gst::ClockTime::MSECOND);
+            buf.set_duration(100 * gst::ClockTime::MSECOND);
+        }
+        assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+    }
+
+    h.push_event(gst::event::Eos::new());
+
+    let mut position = gst::ClockTime::ZERO;
+    let mut got_caps = false;
+
+    while let Some(event) = h.try_pull_event() {
+        match event.view() {
+            gst::EventView::Caps(e) => {
+                let s = e.caps().structure(0).unwrap();
+                assert_eq!(s.name(), "text/x-raw");
+                assert_eq!(s.get::<&str>("format").unwrap(), "utf8");
+                got_caps = true;
+            }
+            gst::EventView::Gap(e) => {
+                let (timestamp, duration) = e.get();
+                assert_eq!(timestamp, position);
+                position = timestamp + duration.unwrap();
+            }
+            _ => (),
+        }
+    }
+
+    assert!(got_caps);
+    assert!(position >= gst::ClockTime::from_seconds(8));
+    assert_eq!(h.buffers_in_queue(), 0);
+}
@@ -0,0 +1,12 @@
+set -e
+
+RELEASE=0.3.42
+ARCHIVE=vosk-linux-x86_64-$RELEASE
+
+# libvosk is only distributed as a prebuilt shared library
+curl -L -o $ARCHIVE.zip https://github.com/alphacep/vosk-api/releases/download/v$RELEASE/$ARCHIVE.zip
+python3 -m zipfile -e $ARCHIVE.zip .
+cp $ARCHIVE/libvosk.so /usr/local/lib/
+cp $ARCHIVE/vosk_api.h /usr/local/include/
+ldconfig
+rm -rf $ARCHIVE $ARCHIVE.zip
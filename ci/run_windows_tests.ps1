@@ -4,7 +4,10 @@ $env:ErrorActionPreference='Stop'
     "--exclude",
     "gst-plugin-csound",
     "--exclude",
-    "gst-plugin-webp"
+    "gst-plugin-webp",
+    # libvosk is not installed on the windows images, see the vosk job
+    "--exclude",
+    "gst-plugin-vosk"
 )
 
 [string[]] $features_matrix = @(
@@ -28,6 +28,8 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 const THROUGHPUT_PERIOD: Duration = Duration::from_secs(20);
+// Number of datagrams read / sent per system call in the batched modes
+const BATCH_SIZE: u32 = 64;
 
 pub static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
@@ -62,6 +64,15 @@ fn main() {
         None
     };
     let is_rtp = args.len() > 6 && (args[6] == "rtp");
+    // Forward the received buffers with ts-udpsink instead of discarding them:
+    // - relay: one `send_to` per buffer.
+    // - relay-batch: buffer lists are sent with `sendmmsg`.
+    // - relay-gso: buffer lists are sent with UDP GSO.
+    let relay = if args.len() > 6 && args[6].starts_with("relay") {
+        Some(args[6].as_str())
+    } else {
+        None
+    };
 
     let rtp_caps = gst::Caps::builder("audio/x-rtp")
         .field("media", "audio")
@@ -77,21 +88,55 @@ fn main() {
     for i in 0..n_streams {
         let build_context = || format!("context-{}", (i as u32) % n_groups);
 
-        let sink =
-            gst::ElementFactory::make("fakesink", Some(format!("sink-{}", i).as_str())).unwrap();
-        sink.set_property("sync", false);
-        sink.set_property("async", false);
-        sink.set_property("signal-handoffs", true);
-        sink.connect(
-            "handoff",
-            true,
-            glib::clone!(@strong counter => move |_| {
-                let _ = counter.fetch_add(1, Ordering::SeqCst);
-                None
-            }),
-        );
-
-        let (source, context) = match source.as_str() {
+        let sink = if let Some(relay) = relay {
+            let sink =
+                gst::ElementFactory::make("ts-udpsink", Some(format!("sink-{}", i).as_str()))
+                    .unwrap();
+            sink.set_property("sync", false);
+            sink.set_property("clients", format!("127.0.0.1:{}", 50000 + i as u32));
+            sink.set_property("context", build_context());
+            sink.set_property("context-wait", wait);
+            match relay {
+                "relay" => (),
+                "relay-batch" => sink.set_property("batch-size", BATCH_SIZE),
+                "relay-gso" => sink.set_property("gso", true),
+                _ => unimplemented!(),
+            }
+
+            sink.static_pad("sink").unwrap().add_probe(
+                gst::PadProbeType::BUFFER | gst::PadProbeType::BUFFER_LIST,
+                glib::clone!(@strong counter => move |_, info| {
+                    let n_buffers = match info.data {
+                        Some(gst::PadProbeData::Buffer(_)) => 1,
+                        Some(gst::PadProbeData::BufferList(ref list)) => list.len() as u64,
+                        _ => 0,
+                    };
+                    let _ = counter.fetch_add(n_buffers, Ordering::SeqCst);
+                    gst::PadProbeReturn::Ok
+                }),
+            );
+
+            sink
+        } else {
+            let sink = gst::ElementFactory::make("fakesink", Some(format!("sink-{}", i).as_str()))
+                .unwrap();
+            sink.set_property("sync", false);
+            sink.set_property("async", false);
+            sink.set_property("signal-handoffs", true);
+            sink.connect(
+                "handoff",
+                true,
+                glib::clone!(@strong counter => move |_| {
+                    let _ = counter.fetch_add(1, Ordering::SeqCst);
+                    None
+                }),
+            );
+
+            sink
+        };
+
+        let source_name = source.as_str();
+        let (source, context) = match source_name {
             "udpsrc" => {
                 let source =
                     gst::ElementFactory::make("udpsrc", Some(format!("source-{}", i).as_str()))
@@ -101,7 +146,7 @@ fn main() {
 
                 (source, None)
             }
-            "ts-udpsrc" => {
+            "ts-udpsrc" | "ts-udpsrc-batch" | "ts-udpsrc-gro" => {
                 let context = build_context();
                 let source =
                     gst::ElementFactory::make("ts-udpsrc", Some(format!("source-{}", i).as_str()))
@@ -110,6 +155,16 @@ fn main() {
                 source.set_property("context", &context);
                 source.set_property("context-wait", wait);
 
+                // Batched reception, datagrams are pushed as buffer lists
+                match source_name {
+                    "ts-udpsrc-batch" => source.set_property("batch-size", BATCH_SIZE),
+                    "ts-udpsrc-gro" => {
+                        source.set_property("batch-size", BATCH_SIZE);
+                        source.set_property("gro", true);
+                    }
+                    _ => (),
+                }
+
                 if is_rtp {
                     source.set_property("caps", &rtp_caps);
                 }
@@ -29,11 +29,18 @@ fn main() {
     if args.len() > 2 && args[2] == "rtp" {
         send_rtp_buffers(n_streams);
     } else {
-        send_raw_buffers(n_streams);
+        // Number of buffers sent to each stream every period,
+        // useful to benchmark batched reception
+        let burst: u32 = if args.len() > 2 {
+            args[2].parse().unwrap()
+        } else {
+            1
+        };
+        send_raw_buffers(n_streams, burst);
     }
 }
 
-fn send_raw_buffers(n_streams: u16) {
+fn send_raw_buffers(n_streams: u16, burst: u32) {
     let buffer = [0; 160];
     let socket = net::UdpSocket::bind("0.0.0.0:0").unwrap();
 
@@ -50,7 +57,9 @@ fn send_raw_buffers(n_streams: u16) {
         let now = time::Instant::now();
 
         for dest in &destinations {
-            socket.send_to(&buffer, dest).unwrap();
+            for _ in 0..burst {
+                socket.send_to(&buffer, dest).unwrap();
+            }
         }
 
         let elapsed = now.elapsed();
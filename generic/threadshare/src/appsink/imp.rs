@@ -0,0 +1,802 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use futures::future::BoxFuture;
+use futures::prelude::*;
+use futures::stream::Peekable;
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst::EventView;
+
+use once_cell::sync::Lazy;
+
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::Poll;
+use std::time::Duration;
+
+use crate::runtime::prelude::*;
+use crate::runtime::{self, Context, PadSink, PadSinkRef, Task};
+
+const DEFAULT_CONTEXT: &str = "";
+const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
+const DEFAULT_CAPS: Option<gst::Caps> = None;
+const DEFAULT_SYNC: bool = true;
+const DEFAULT_MAX_BUFFERS: u32 = 0;
+const DEFAULT_DROP: bool = false;
+const DEFAULT_EMIT_SIGNALS: bool = false;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    context: String,
+    context_wait: Duration,
+    caps: Option<gst::Caps>,
+    sync: bool,
+    max_buffers: u32,
+    drop: bool,
+    emit_signals: bool,
+    latency: Option<gst::ClockTime>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            context: DEFAULT_CONTEXT.into(),
+            context_wait: DEFAULT_CONTEXT_WAIT,
+            caps: DEFAULT_CAPS,
+            sync: DEFAULT_SYNC,
+            max_buffers: DEFAULT_MAX_BUFFERS,
+            drop: DEFAULT_DROP,
+            emit_signals: DEFAULT_EMIT_SIGNALS,
+            latency: None,
+        }
+    }
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ts-appsink",
+        gst::DebugColorFlags::empty(),
+        Some("Thread-sharing app sink"),
+    )
+});
+
+#[derive(Debug)]
+enum TaskItem {
+    Buffer(gst::Buffer),
+    BufferList(gst::BufferList),
+    Event(gst::Event),
+}
+
+/// Items queued for the application.
+#[derive(Debug)]
+pub(super) enum SampleItem {
+    Sample(gst::Sample),
+    Eos,
+}
+
+#[derive(Clone, Debug)]
+struct AppSinkPadHandler;
+
+impl PadSinkHandler for AppSinkPadHandler {
+    type ElementImpl = AppSink;
+
+    fn sink_chain(
+        &self,
+        _pad: &PadSinkRef,
+        appsink: &AppSink,
+        element: &gst::Element,
+        buffer: gst::Buffer,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let sender = appsink.clone_item_sender();
+        let element = element.clone().downcast::<super::AppSink>().unwrap();
+
+        async move {
+            if sender.send_async(TaskItem::Buffer(buffer)).await.is_err() {
+                gst::debug!(CAT, obj: &element, "Flushing");
+                return Err(gst::FlowError::Flushing);
+            }
+
+            Ok(gst::FlowSuccess::Ok)
+        }
+        .boxed()
+    }
+
+    fn sink_chain_list(
+        &self,
+        _pad: &PadSinkRef,
+        appsink: &AppSink,
+        element: &gst::Element,
+        list: gst::BufferList,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let sender = appsink.clone_item_sender();
+        let element = element.clone().downcast::<super::AppSink>().unwrap();
+
+        async move {
+            if sender.send_async(TaskItem::BufferList(list)).await.is_err() {
+                gst::debug!(CAT, obj: &element, "Flushing");
+                return Err(gst::FlowError::Flushing);
+            }
+
+            Ok(gst::FlowSuccess::Ok)
+        }
+        .boxed()
+    }
+
+    fn sink_event_serialized(
+        &self,
+        _pad: &PadSinkRef,
+        appsink: &AppSink,
+        element: &gst::Element,
+        event: gst::Event,
+    ) -> BoxFuture<'static, bool> {
+        let sender = appsink.clone_item_sender();
+        let element = element.clone().downcast::<super::AppSink>().unwrap();
+
+        async move {
+            if let EventView::FlushStop(_) = event.view() {
+                let appsink = element.imp();
+                return appsink.task.flush_stop().await_maybe_on_context().is_ok();
+            } else if sender.send_async(TaskItem::Event(event)).await.is_err() {
+                gst::debug!(CAT, obj: &element, "Flushing");
+            }
+
+            true
+        }
+        .boxed()
+    }
+
+    fn sink_event(
+        &self,
+        _pad: &PadSinkRef,
+        appsink: &AppSink,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        if let EventView::FlushStart(..) = event.view() {
+            return appsink.task.flush_start().await_maybe_on_context().is_ok();
+        }
+
+        true
+    }
+
+    fn sink_query(
+        &self,
+        pad: &PadSinkRef,
+        appsink: &AppSink,
+        element: &gst::Element,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        if let gst::QueryViewMut::Caps(q) = query.view_mut() {
+            if let Some(caps) = appsink.settings.lock().unwrap().caps.as_ref() {
+                let caps = q
+                    .filter()
+                    .map(|f| f.intersect_with_mode(caps, gst::CapsIntersectMode::First))
+                    .unwrap_or_else(|| caps.clone());
+                q.set_result(&caps);
+
+                return true;
+            }
+        }
+
+        if query.is_serialized() {
+            gst::log!(CAT, obj: pad.gst_pad(), "Dropping {:?}", query);
+            false
+        } else {
+            gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", query);
+            pad.gst_pad().query_default(Some(element), query)
+        }
+    }
+}
+
+struct AppSinkTask {
+    element: super::AppSink,
+    item_receiver: Peekable<flume::r#async::RecvStream<'static, TaskItem>>,
+    sample_sender: flume::Sender<SampleItem>,
+    sample_receiver: flume::Receiver<SampleItem>,
+    sync: bool,
+    drop: bool,
+    emit_signals: bool,
+    caps: Option<gst::Caps>,
+    segment: Option<gst::Segment>,
+}
+
+impl AppSinkTask {
+    fn new(
+        element: &super::AppSink,
+        item_receiver: flume::Receiver<TaskItem>,
+        sample_sender: flume::Sender<SampleItem>,
+        sample_receiver: flume::Receiver<SampleItem>,
+    ) -> Self {
+        AppSinkTask {
+            element: element.clone(),
+            item_receiver: item_receiver.into_stream().peekable(),
+            sample_sender,
+            sample_receiver,
+            sync: DEFAULT_SYNC,
+            drop: DEFAULT_DROP,
+            emit_signals: DEFAULT_EMIT_SIGNALS,
+            caps: None,
+            segment: None,
+        }
+    }
+
+    async fn flush(&mut self) {
+        // Purge the channels
+        while let Poll::Ready(Some(_item)) = futures::poll!(self.item_receiver.next()) {}
+        while self.sample_receiver.try_recv().is_ok() {}
+    }
+
+    /// Waits until specified time.
+    async fn sync(&self, running_time: gst::ClockTime) {
+        let now = self.element.current_running_time();
+
+        if let Ok(Some(delay)) = running_time.opt_checked_sub(now) {
+            gst::trace!(CAT, obj: &self.element, "sync: waiting {}", delay);
+            runtime::timer::delay_for(delay.into()).await;
+        }
+    }
+
+    async fn deliver(&mut self, item: SampleItem) -> Result<(), gst::FlowError> {
+        if self.emit_signals {
+            match item {
+                SampleItem::Sample(sample) => {
+                    gst::log!(CAT, obj: &self.element, "Emitting new-sample");
+                    let res = self
+                        .element
+                        .emit_by_name::<gst::FlowReturn>("new-sample", &[&sample]);
+                    return res.into_result().map(drop);
+                }
+                SampleItem::Eos => {
+                    gst::log!(CAT, obj: &self.element, "Emitting eos");
+                    self.element.emit_by_name::<()>("eos", &[]);
+                    return Ok(());
+                }
+            }
+        }
+
+        if self.drop {
+            let mut item = item;
+            loop {
+                match self.sample_sender.try_send(item) {
+                    Ok(()) => return Ok(()),
+                    Err(flume::TrySendError::Full(ret)) => {
+                        gst::debug!(CAT, obj: &self.element, "Queue full, dropping oldest sample");
+                        let _ = self.sample_receiver.try_recv();
+                        item = ret;
+                    }
+                    Err(flume::TrySendError::Disconnected(_)) => {
+                        return Err(gst::FlowError::Flushing)
+                    }
+                }
+            }
+        }
+
+        // This can be cancelled by a state transition,
+        // the application is slow to consume the samples.
+        self.sample_sender
+            .send_async(item)
+            .await
+            .map_err(|_| gst::FlowError::Flushing)
+    }
+
+    fn sample(&self, buffer: Option<gst::Buffer>, list: Option<gst::BufferList>) -> gst::Sample {
+        let mut builder = gst::Sample::builder();
+        if let Some(ref buffer) = buffer {
+            builder = builder.buffer(buffer);
+        }
+        if let Some(ref list) = list {
+            builder = builder.buffer_list(list);
+        }
+        if let Some(ref caps) = self.caps {
+            builder = builder.caps(caps);
+        }
+        if let Some(ref segment) = self.segment {
+            builder = builder.segment(segment);
+        }
+
+        builder.build()
+    }
+}
+
+impl TaskImpl for AppSinkTask {
+    type Item = TaskItem;
+
+    fn prepare(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::info!(CAT, obj: &self.element, "Preparing Task");
+
+            let appsink = self.element.imp();
+            let settings = appsink.settings.lock().unwrap();
+            self.sync = settings.sync;
+            self.drop = settings.drop;
+            self.emit_signals = settings.emit_signals;
+
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn try_next(&mut self) -> BoxFuture<'_, Result<TaskItem, gst::FlowError>> {
+        async move {
+            let item_opt = Pin::new(&mut self.item_receiver).peek().await;
+
+            // Check the peeked item in case we need to sync.
+            // The item will still be available in the channel
+            // in case this is cancelled by a state transition.
+            let buffer = match item_opt {
+                Some(TaskItem::Buffer(buffer)) => Some(&**buffer),
+                Some(TaskItem::BufferList(list)) => list.get(0),
+                Some(_) => None,
+                None => {
+                    panic!("Internal channel sender dropped while Task is Started");
+                }
+            };
+
+            if let Some(buffer) = buffer {
+                if self.sync {
+                    let latency = self.element.imp().settings.lock().unwrap().latency;
+                    let rtime = self.segment.as_ref().and_then(|segment| {
+                        segment
+                            .downcast_ref::<gst::format::Time>()
+                            .and_then(|segment| {
+                                segment.to_running_time(buffer.pts()).opt_add(latency)
+                            })
+                    });
+                    if let Some(rtime) = rtime {
+                        // This can be cancelled by a state transition.
+                        self.sync(rtime).await;
+                    }
+                }
+            }
+
+            // An item was peeked above, we can now pop it without losing it.
+            Ok(self.item_receiver.next().await.unwrap())
+        }
+        .boxed()
+    }
+
+    fn handle_item(&mut self, item: TaskItem) -> BoxFuture<'_, Result<(), gst::FlowError>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Handling {:?}", item);
+
+            match item {
+                TaskItem::Buffer(buffer) => {
+                    let sample = self.sample(Some(buffer), None);
+                    self.deliver(SampleItem::Sample(sample)).await?;
+                }
+                TaskItem::BufferList(list) => {
+                    let sample = self.sample(None, Some(list));
+                    self.deliver(SampleItem::Sample(sample)).await?;
+                }
+                TaskItem::Event(event) => match event.view() {
+                    EventView::Eos(_) => {
+                        self.deliver(SampleItem::Eos).await?;
+                        let _ = self
+                            .element
+                            .post_message(gst::message::Eos::builder().src(&self.element).build());
+                    }
+                    EventView::Caps(e) => {
+                        self.caps = Some(e.caps_owned());
+                    }
+                    EventView::Segment(e) => {
+                        self.segment = Some(e.segment().clone());
+                    }
+                    EventView::SinkMessage(e) => {
+                        let _ = self.element.post_message(e.message());
+                    }
+                    _ => (),
+                },
+            }
+
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn stop(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async {
+            gst::info!(CAT, obj: &self.element, "Stopping Task");
+            self.flush().await;
+            self.caps = None;
+            self.segment = None;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn flush_start(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async {
+            gst::info!(CAT, obj: &self.element, "Starting Task Flush");
+            self.flush().await;
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[derive(Debug)]
+pub struct AppSink {
+    sink_pad: PadSink,
+    task: Task,
+    item_sender: Mutex<Option<flume::Sender<TaskItem>>>,
+    sample_receiver: Mutex<Option<flume::Receiver<SampleItem>>>,
+    settings: Mutex<Settings>,
+}
+
+impl AppSink {
+    #[track_caller]
+    fn clone_item_sender(&self) -> flume::Sender<TaskItem> {
+        self.item_sender.lock().unwrap().as_ref().unwrap().clone()
+    }
+
+    pub(super) fn sample_receiver(&self) -> Option<flume::Receiver<SampleItem>> {
+        self.sample_receiver.lock().unwrap().clone()
+    }
+
+    fn pull_sample(&self, timeout: Option<gst::ClockTime>) -> Option<gst::Sample> {
+        let receiver = self.sample_receiver()?;
+
+        let item = match timeout {
+            Some(timeout) => receiver.recv_timeout(timeout.into()).ok()?,
+            None => receiver.recv().ok()?,
+        };
+
+        match item {
+            SampleItem::Sample(sample) => Some(sample),
+            SampleItem::Eos => None,
+        }
+    }
+
+    fn prepare(&self, element: &super::AppSink) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Preparing");
+
+        let (context, max_buffers) = {
+            let settings = self.settings.lock().unwrap();
+
+            let context =
+                Context::acquire(&settings.context, settings.context_wait).map_err(|err| {
+                    gst::error_msg!(
+                        gst::ResourceError::OpenWrite,
+                        ["Failed to acquire Context: {}", err]
+                    )
+                })?;
+
+            (context, settings.max_buffers)
+        };
+
+        // Enable backpressure for items
+        let (item_sender, item_receiver) = flume::bounded(0);
+        let (sample_sender, sample_receiver) = if max_buffers == 0 {
+            flume::unbounded()
+        } else {
+            flume::bounded(max_buffers as usize)
+        };
+
+        let task_impl = AppSinkTask::new(
+            element,
+            item_receiver,
+            sample_sender,
+            sample_receiver.clone(),
+        );
+        self.task.prepare(task_impl, context).block_on()?;
+
+        *self.item_sender.lock().unwrap() = Some(item_sender);
+        *self.sample_receiver.lock().unwrap() = Some(sample_receiver);
+
+        gst::debug!(CAT, obj: element, "Prepared");
+
+        Ok(())
+    }
+
+    fn unprepare(&self, element: &super::AppSink) {
+        gst::debug!(CAT, obj: element, "Unpreparing");
+        *self.sample_receiver.lock().unwrap() = None;
+        self.task.unprepare().block_on().unwrap();
+        gst::debug!(CAT, obj: element, "Unprepared");
+    }
+
+    fn stop(&self, element: &super::AppSink) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Stopping");
+        self.task.stop().block_on()?;
+        gst::debug!(CAT, obj: element, "Stopped");
+        Ok(())
+    }
+
+    fn start(&self, element: &super::AppSink) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Starting");
+        self.task.start().block_on()?;
+        gst::debug!(CAT, obj: element, "Started");
+        Ok(())
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for AppSink {
+    const NAME: &'static str = "RsTsAppSink";
+    type Type = super::AppSink;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        Self {
+            sink_pad: PadSink::new(
+                gst::Pad::from_template(&klass.pad_template("sink").unwrap(), Some("sink")),
+                AppSinkPadHandler,
+            ),
+            task: Task::default(),
+            item_sender: Default::default(),
+            sample_receiver: Default::default(),
+            settings: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for AppSink {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::builder("context")
+                    .nick("Context")
+                    .blurb("Context name to share threads with")
+                    .default_value(Some(DEFAULT_CONTEXT))
+                    .build(),
+                glib::ParamSpecUInt::builder("context-wait")
+                    .nick("Context Wait")
+                    .blurb("Throttle poll loop to run at most once every this many ms")
+                    .maximum(1000)
+                    .default_value(DEFAULT_CONTEXT_WAIT.as_millis() as u32)
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Caps>("caps")
+                    .nick("Caps")
+                    .blurb("The allowed caps for the sink pad")
+                    .build(),
+                glib::ParamSpecBoolean::builder("sync")
+                    .nick("Sync")
+                    .blurb("Sync on the clock")
+                    .default_value(DEFAULT_SYNC)
+                    .build(),
+                glib::ParamSpecUInt::builder("max-buffers")
+                    .nick("Max Buffers")
+                    .blurb("The maximum number of samples to queue for the application (0 = unlimited)")
+                    .default_value(DEFAULT_MAX_BUFFERS)
+                    .build(),
+                glib::ParamSpecBoolean::builder("drop")
+                    .nick("Drop")
+                    .blurb("Drop old samples when the queue is full instead of blocking")
+                    .default_value(DEFAULT_DROP)
+                    .build(),
+                glib::ParamSpecBoolean::builder("emit-signals")
+                    .nick("Emit Signals")
+                    .blurb("Deliver the samples through the new-sample signal instead of queueing them")
+                    .default_value(DEFAULT_EMIT_SIGNALS)
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn signals() -> &'static [glib::subclass::Signal] {
+        static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
+            vec![
+                /**
+                 * ts-appsink::new-sample:
+                 * @self: A ts-appsink
+                 * @sample: The new sample
+                 *
+                 * Emitted from the Context thread for each sample when emit-signals is enabled.
+                 *
+                 * Returns: the #GstFlowReturn to return upstream
+                 */
+                glib::subclass::Signal::builder("new-sample")
+                    .param_types([gst::Sample::static_type()])
+                    .return_type::<gst::FlowReturn>()
+                    .build(),
+                /**
+                 * ts-appsink::eos:
+                 * @self: A ts-appsink
+                 *
+                 * Emitted from the Context thread on EOS when emit-signals is enabled.
+                 */
+                glib::subclass::Signal::builder("eos").build(),
+                /**
+                 * ts-appsink::pull-sample:
+                 * @self: A ts-appsink
+                 *
+                 * Blocks until a sample is available, EOS is reached or the element is shut down.
+                 *
+                 * Returns: the next queued sample or %NULL on EOS
+                 */
+                glib::subclass::Signal::builder("pull-sample")
+                    .return_type::<gst::Sample>()
+                    .action()
+                    .class_handler(|_, args| {
+                        let element = args[0].get::<super::AppSink>().expect("signal arg");
+                        let appsink = element.imp();
+
+                        Some(appsink.pull_sample(None).to_value())
+                    })
+                    .build(),
+                /**
+                 * ts-appsink::try-pull-sample:
+                 * @self: A ts-appsink
+                 * @timeout: the maximum amount of time to wait for a sample
+                 *
+                 * Returns: the next queued sample or %NULL on EOS or timeout
+                 */
+                glib::subclass::Signal::builder("try-pull-sample")
+                    .param_types([u64::static_type()])
+                    .return_type::<gst::Sample>()
+                    .action()
+                    .class_handler(|_, args| {
+                        let element = args[0].get::<super::AppSink>().expect("signal arg");
+                        let timeout = args[1].get::<u64>().expect("signal arg");
+                        let appsink = element.imp();
+
+                        Some(
+                            appsink
+                                .pull_sample(Some(gst::ClockTime::from_nseconds(timeout)))
+                                .to_value(),
+                        )
+                    })
+                    .build(),
+            ]
+        });
+
+        SIGNALS.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "context" => {
+                settings.context = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_else(|| DEFAULT_CONTEXT.into());
+            }
+            "context-wait" => {
+                settings.context_wait = Duration::from_millis(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
+            "caps" => {
+                settings.caps = value.get().expect("type checked upstream");
+            }
+            "sync" => {
+                settings.sync = value.get().expect("type checked upstream");
+            }
+            "max-buffers" => {
+                settings.max_buffers = value.get().expect("type checked upstream");
+            }
+            "drop" => {
+                settings.drop = value.get().expect("type checked upstream");
+            }
+            "emit-signals" => {
+                settings.emit_signals = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "context" => settings.context.to_value(),
+            "context-wait" => (settings.context_wait.as_millis() as u32).to_value(),
+            "caps" => settings.caps.to_value(),
+            "sync" => settings.sync.to_value(),
+            "max-buffers" => settings.max_buffers.to_value(),
+            "drop" => settings.drop.to_value(),
+            "emit-signals" => settings.emit_signals.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(self.sink_pad.gst_pad()).unwrap();
+
+        crate::set_element_flags(obj, gst::ElementFlags::SINK);
+    }
+}
+
+impl GstObjectImpl for AppSink {}
+
+impl ElementImpl for AppSink {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Thread-sharing app sink",
+                "Sink/Generic",
+                "Thread-sharing app sink",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::new_any();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::NullToReady => {
+                self.prepare(element).map_err(|err| {
+                    element.post_error_message(err);
+                    gst::StateChangeError
+                })?;
+            }
+            gst::StateChange::ReadyToPaused => {
+                self.start(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::PausedToReady => {
+                self.stop(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::ReadyToNull => {
+                self.unprepare(element);
+            }
+            _ => (),
+        }
+
+        self.parent_change_state(element, transition)
+    }
+
+    fn send_event(&self, _element: &Self::Type, event: gst::Event) -> bool {
+        match event.view() {
+            EventView::Latency(ev) => {
+                self.settings.lock().unwrap().latency = Some(ev.latency());
+                self.sink_pad.gst_pad().push_event(event)
+            }
+            EventView::Step(..) => false,
+            _ => self.sink_pad.gst_pad().push_event(event),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use futures::prelude::*;
+use futures::stream::FusedStream;
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+mod imp;
+
+glib::wrapper! {
+    pub struct AppSink(ObjectSubclass<imp::AppSink>) @extends gst::Element, gst::Object;
+}
+
+impl AppSink {
+    /// Returns a `Stream` of the samples reaching this sink.
+    ///
+    /// The element must be at least in `Ready` state, `None` is returned otherwise.
+    /// The stream terminates on EOS or when the element is unprepared. It can be
+    /// polled from any executor, including a threadshare `runtime::Context`.
+    ///
+    /// Note that samples are not delivered through the stream when
+    /// `emit-signals` is enabled.
+    pub fn stream(&self) -> Option<AppSinkStream> {
+        self.imp().sample_receiver().map(|receiver| AppSinkStream {
+            stream: receiver.into_stream(),
+            terminated: false,
+        })
+    }
+}
+
+/// A `Stream` of the samples received by an [`AppSink`].
+#[derive(Debug)]
+pub struct AppSinkStream {
+    stream: flume::r#async::RecvStream<'static, imp::SampleItem>,
+    terminated: bool,
+}
+
+impl Stream for AppSinkStream {
+    type Item = gst::Sample;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Option<Self::Item>> {
+        if self.terminated {
+            return Poll::Ready(None);
+        }
+
+        match self.stream.poll_next_unpin(cx) {
+            Poll::Ready(Some(imp::SampleItem::Sample(sample))) => Poll::Ready(Some(sample)),
+            Poll::Ready(Some(imp::SampleItem::Eos)) | Poll::Ready(None) => {
+                self.terminated = true;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl FusedStream for AppSinkStream {
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "ts-appsink",
+        gst::Rank::None,
+        AppSink::static_type(),
+    )
+}
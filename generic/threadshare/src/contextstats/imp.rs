@@ -0,0 +1,320 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+/**
+ * tracer-ts-context-stats:
+ *
+ * This tracer periodically collects the load statistics of all the threadshare
+ * `Context`s running in the process.
+ *
+ * The statistics are computed for each interval and are:
+ *
+ * - logged in the `ts-context-stats` debug category at the `INFO` level,
+ * - posted as `threadshare-context-stats` element messages on the bus of
+ *   every pipeline,
+ * - optionally collected to a CSV file.
+ *
+ * Example:
+ *
+ * ```console
+ * $ GST_TRACERS='ts-context-stats(interval=500,file="/tmp/ts_context_stats.log")' gst-launch-1.0 ts-udpsrc context=ctx ! fakesink
+ * ```
+ *
+ * The generated file is a CSV file of the format
+ *
+ * ```csv
+ * timestamp,context,iterations,busy duration,parked duration,load,tasks,sub tasks,timers fired,mean timer lateness,max timer lateness,reactor wakeups,reactor events
+ * ```
+ *
+ * Note: this tracer is part of the threadshare plugin because the `Context`s
+ * are only visible from within the library which runs them.
+ *
+ * ## Parameters
+ *
+ * ### `interval`
+ *
+ * Specifies the collection interval in milliseconds. Default: 1000.
+ *
+ * ### `file`
+ *
+ * Specifies the path to the file that will collect the CSV file with the
+ * statistics. By default, no file is written.
+ *
+ * ### `post-messages`
+ *
+ * Whether to post the statistics as element messages. Default: true.
+ */
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use once_cell::sync::Lazy;
+
+use crate::runtime::{Context, ContextStats};
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ts-context-stats",
+        gst::DebugColorFlags::empty(),
+        Some("Tracer to collect threadshare Context statistics"),
+    )
+});
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_POST_MESSAGES: bool = true;
+
+#[derive(Debug)]
+struct Settings {
+    interval: Duration,
+    file: Option<PathBuf>,
+    post_messages: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_INTERVAL,
+            file: None,
+            post_messages: DEFAULT_POST_MESSAGES,
+        }
+    }
+}
+
+impl Settings {
+    fn update_from_params(&mut self, obj: &super::ContextStatsTracer, params: String) {
+        let s = match gst::Structure::from_str(&format!("ts-context-stats,{}", params)) {
+            Ok(s) => s,
+            Err(err) => {
+                gst::warning!(CAT, obj: obj, "failed to parse tracer parameters: {}", err);
+                return;
+            }
+        };
+
+        if let Ok(interval) = s.get::<i32>("interval") {
+            gst::log!(CAT, obj: obj, "interval= {}", interval);
+            if interval > 0 {
+                self.interval = Duration::from_millis(interval as u64);
+            } else {
+                gst::warning!(CAT, obj: obj, "Ignoring invalid interval {}", interval);
+            }
+        }
+
+        if let Ok(file) = s.get::<&str>("file") {
+            gst::log!(CAT, obj: obj, "file= {}", file);
+            self.file = Some(PathBuf::from(file));
+        }
+
+        if let Ok(post_messages) = s.get::<bool>("post-messages") {
+            gst::log!(CAT, obj: obj, "post-messages= {}", post_messages);
+            self.post_messages = post_messages;
+        }
+    }
+}
+
+struct LogLine {
+    timestamp: u64,
+    context: String,
+    stats: ContextStats,
+}
+
+#[derive(Default)]
+struct State {
+    settings: Settings,
+    pipelines: Vec<glib::WeakRef<gst::Pipeline>>,
+    prev_stats: HashMap<String, ContextStats>,
+    log: Vec<LogLine>,
+    stop_sender: Option<mpsc::Sender<()>>,
+}
+
+#[derive(Default)]
+pub struct ContextStatsTracer {
+    state: Mutex<State>,
+}
+
+impl ContextStatsTracer {
+    fn collect(&self, obj: &super::ContextStatsTracer) {
+        let mut state = self.state.lock().unwrap();
+
+        state
+            .pipelines
+            .retain(|pipeline| pipeline.upgrade().is_some());
+        let pipelines = if state.settings.post_messages {
+            state
+                .pipelines
+                .iter()
+                .filter_map(glib::WeakRef::upgrade)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let timestamp = gst::util_get_timestamp().nseconds();
+        let mut prev_stats = HashMap::new();
+        let mut messages = Vec::new();
+
+        for context in Context::all() {
+            let stats = context.stats();
+            let interval_stats = match state.prev_stats.get(context.name()) {
+                Some(prev) => stats.since(prev),
+                None => stats.clone(),
+            };
+            prev_stats.insert(context.name().to_string(), stats);
+
+            let s = interval_stats.to_structure(context.name());
+            gst::info!(CAT, obj: obj, "{}", s);
+
+            if !pipelines.is_empty() {
+                messages.push(s);
+            }
+
+            if state.settings.file.is_some() {
+                state.log.push(LogLine {
+                    timestamp,
+                    context: context.name().to_string(),
+                    stats: interval_stats,
+                });
+            }
+        }
+
+        // Forget about the Contexts which are gone
+        state.prev_stats = prev_stats;
+        drop(state);
+
+        for pipeline in pipelines {
+            for s in messages.iter() {
+                let _ = pipeline.post_message(
+                    gst::message::Element::builder(s.clone())
+                        .src(&pipeline)
+                        .build(),
+                );
+            }
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for ContextStatsTracer {
+    const NAME: &'static str = "GstTsContextStats";
+    type Type = super::ContextStatsTracer;
+    type ParentType = gst::Tracer;
+}
+
+impl ObjectImpl for ContextStatsTracer {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        let mut state = self.state.lock().unwrap();
+        if let Some(params) = obj.property::<Option<String>>("params") {
+            state.settings.update_from_params(obj, params);
+        }
+
+        self.register_hook(TracerHook::ElementNew);
+
+        let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+        state.stop_sender = Some(stop_sender);
+
+        let interval = state.settings.interval;
+        let obj_weak = obj.downgrade();
+        thread::Builder::new()
+            .name("ts-context-stats".to_string())
+            .spawn(move || {
+                // Stops when the tracer is disposed
+                while let Err(mpsc::RecvTimeoutError::Timeout) =
+                    stop_receiver.recv_timeout(interval)
+                {
+                    let obj = match obj_weak.upgrade() {
+                        Some(obj) => obj,
+                        None => break,
+                    };
+                    obj.imp().collect(&obj);
+                }
+            })
+            .expect("Failed to spawn stats thread");
+    }
+
+    fn dispose(&self, obj: &Self::Type) {
+        use std::io::prelude::*;
+
+        let mut state = self.state.lock().unwrap();
+        state.stop_sender = None;
+
+        let path = match state.settings.file {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        let mut file = match std::fs::File::create(path) {
+            Ok(file) => file,
+            Err(err) => {
+                gst::error!(CAT, obj: obj, "Failed to create file: {err}");
+                return;
+            }
+        };
+
+        gst::debug!(CAT, obj: obj, "Writing file {}", path.display());
+
+        for LogLine {
+            timestamp,
+            context,
+            stats,
+        } in &state.log
+        {
+            let res = writeln!(
+                &mut file,
+                "{timestamp},{context},{},{},{},{:.4},{},{},{},{},{},{},{}",
+                stats.iterations,
+                stats.busy_duration.as_nanos(),
+                stats.parked_duration.as_nanos(),
+                stats.load(),
+                stats.tasks,
+                stats.sub_tasks,
+                stats.timers_fired,
+                stats.mean_timer_lateness().unwrap_or_default().as_nanos(),
+                stats.max_timer_lateness.as_nanos(),
+                stats.reactor_wakeups,
+                stats.reactor_events,
+            );
+            if let Err(err) = res {
+                gst::error!(CAT, obj: obj, "Failed to write to file: {err}");
+                return;
+            }
+        }
+    }
+}
+
+impl GstObjectImpl for ContextStatsTracer {}
+
+impl TracerImpl for ContextStatsTracer {
+    fn element_new(&self, _ts: u64, element: &gst::Element) {
+        if let Some(pipeline) = element.downcast_ref::<gst::Pipeline>() {
+            gst::debug!(CAT, "new pipeline: {}", pipeline.name());
+            self.state
+                .lock()
+                .unwrap()
+                .pipelines
+                .push(pipeline.downgrade());
+        }
+    }
+}
@@ -19,6 +19,7 @@
 
 use futures::future::{self, abortable, AbortHandle};
 
+use gst::glib;
 use gst::prelude::*;
 
 use once_cell::sync::Lazy;
@@ -36,7 +37,7 @@ static DATA_QUEUE_CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     )
 });
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum DataQueueItem {
     Buffer(gst::Buffer),
     BufferList(gst::BufferList),
@@ -44,7 +45,8 @@ pub enum DataQueueItem {
 }
 
 impl DataQueueItem {
-    fn size(&self) -> (u32, u32) {
+    /// Returns the number of buffers and bytes held by this item.
+    pub fn size(&self) -> (u32, u32) {
         match *self {
             DataQueueItem::Buffer(ref buffer) => (1, buffer.size() as u32),
             DataQueueItem::BufferList(ref list) => (
@@ -70,6 +72,41 @@ pub enum DataQueueState {
     Stopped,
 }
 
+/// What to do with the buffers when the queue is full.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstTsDataQueueLeaky")]
+pub enum DataQueueLeaky {
+    #[enum_value(name = "Not Leaky", nick = "no")]
+    No = 0,
+    #[enum_value(name = "Leaky on upstream (new buffers)", nick = "upstream")]
+    Upstream = 1,
+    #[enum_value(name = "Leaky on downstream (old buffers)", nick = "downstream")]
+    Downstream = 2,
+}
+
+/// Signals emitted on the element when the `DataQueue` is not silent.
+///
+/// These follow the semantics of the C `queue` signals of the same name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Signal {
+    Overrun,
+    Underrun,
+    Running,
+    Pushing,
+}
+
+impl Signal {
+    fn name(self) -> &'static str {
+        match self {
+            Signal::Overrun => "overrun",
+            Signal::Underrun => "underrun",
+            Signal::Running => "running",
+            Signal::Pushing => "pushing",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DataQueue(Arc<StdMutex<DataQueueInner>>);
 
@@ -87,6 +124,14 @@ struct DataQueueInner {
     max_size_bytes: Option<u32>,
     max_size_time: Option<gst::ClockTime>,
 
+    leaky: DataQueueLeaky,
+    silent: bool,
+    dropped: u64,
+    head_needs_discont: bool,
+    tail_needs_discont: bool,
+    is_overrun: bool,
+    is_underrun: bool,
+
     pending_handle: Option<AbortHandle>,
 }
 
@@ -96,6 +141,175 @@ impl DataQueueInner {
             pending_handle.abort();
         }
     }
+
+    fn is_full(&self, item: &DataQueueItem) -> bool {
+        if let Some(max) = self.max_size_buffers {
+            if max <= self.cur_size_buffers {
+                gst::debug!(DATA_QUEUE_CAT, obj: &self.element, "Queue is full (buffers): {} <= {}", max, self.cur_size_buffers);
+                return true;
+            }
+        }
+
+        if let Some(max) = self.max_size_bytes {
+            if max <= self.cur_size_bytes {
+                gst::debug!(DATA_QUEUE_CAT, obj: &self.element, "Queue is full (bytes): {} <= {}", max, self.cur_size_bytes);
+                return true;
+            }
+        }
+
+        // FIXME: Use running time
+        let queue_ts = self.queue.iter().filter_map(|i| i.timestamp()).next();
+        if let (Some(max), Some(queue_ts), Some(ts)) =
+            (self.max_size_time, queue_ts, item.timestamp())
+        {
+            let level = if queue_ts > ts {
+                queue_ts - ts
+            } else {
+                ts - queue_ts
+            };
+
+            if max <= level {
+                gst::debug!(DATA_QUEUE_CAT, obj: &self.element, "Queue is full (time): {} <= {}", max, level);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /* Drops the oldest buffers until there is room for the item.
+     * Events don't take any room, so they are kept in the queue. */
+    fn leak_downstream(&mut self, item: &DataQueueItem) {
+        while self.cur_size_buffers > 0 && self.is_full(item) {
+            let idx = match self
+                .queue
+                .iter()
+                .position(|item| !matches!(item, DataQueueItem::Event(_)))
+            {
+                Some(idx) => idx,
+                None => break,
+            };
+            let leaked = self.queue.remove(idx).unwrap();
+
+            gst::debug!(DATA_QUEUE_CAT, obj: &self.element, "Queue is full, leaking {:?} on downstream end", leaked);
+
+            let (count, bytes) = leaked.size();
+            self.cur_size_buffers -= count;
+            self.cur_size_bytes -= bytes;
+            self.dropped += count as u64;
+
+            self.head_needs_discont = true;
+        }
+    }
+
+    fn push(&mut self, mut item: DataQueueItem) -> (Result<(), DataQueueItem>, Option<Signal>) {
+        if self.state == DataQueueState::Stopped {
+            gst::debug!(
+                DATA_QUEUE_CAT,
+                obj: &self.element,
+                "Rejecting item {:?} in state {:?}",
+                item,
+                self.state
+            );
+            return (Err(item), None);
+        }
+
+        gst::debug!(DATA_QUEUE_CAT, obj: &self.element, "Pushing item {:?}", item);
+
+        let mut signal = None;
+
+        // Serialized events don't take any room, so they are always accepted,
+        // as in the C queue. This also ensures that the consumers of a leaky
+        // queue don't miss any caps or segment change.
+        if !matches!(item, DataQueueItem::Event(_)) {
+            if self.is_full(&item) {
+                self.is_overrun = true;
+                signal = Some(Signal::Overrun);
+
+                match self.leaky {
+                    DataQueueLeaky::No => return (Err(item), signal),
+                    DataQueueLeaky::Upstream => {
+                        gst::debug!(DATA_QUEUE_CAT, obj: &self.element, "Queue is full, leaking {:?} on upstream end", item);
+                        self.dropped += item.size().0 as u64;
+                        self.tail_needs_discont = true;
+                        return (Ok(()), signal);
+                    }
+                    DataQueueLeaky::Downstream => self.leak_downstream(&item),
+                }
+            }
+
+            if self.tail_needs_discont {
+                self.tail_needs_discont = false;
+                set_discont(&mut item);
+            }
+        }
+
+        let (count, bytes) = item.size();
+        self.queue.push_back(item);
+        self.cur_size_buffers += count;
+        self.cur_size_bytes += bytes;
+
+        self.wake();
+
+        (Ok(()), signal)
+    }
+
+    fn pop(&mut self) -> (Option<DataQueueItem>, Vec<Signal>) {
+        let mut signals = Vec::new();
+
+        let mut item = match self.queue.pop_front() {
+            Some(item) => item,
+            None => {
+                gst::debug!(DATA_QUEUE_CAT, obj: &self.element, "Data queue is empty");
+                if !self.is_underrun {
+                    self.is_underrun = true;
+                    signals.push(Signal::Underrun);
+                }
+
+                return (None, signals);
+            }
+        };
+
+        gst::debug!(DATA_QUEUE_CAT, obj: &self.element, "Popped item {:?}", item);
+
+        let (count, bytes) = item.size();
+        self.cur_size_buffers -= count;
+        self.cur_size_bytes -= bytes;
+
+        if count > 0 && self.head_needs_discont {
+            self.head_needs_discont = false;
+            set_discont(&mut item);
+        }
+
+        if self.is_underrun {
+            self.is_underrun = false;
+            signals.push(Signal::Running);
+            signals.push(Signal::Pushing);
+        } else if self.is_overrun && self.leaky == DataQueueLeaky::No {
+            // There's room again for the upstream items
+            signals.push(Signal::Running);
+        }
+        self.is_overrun = false;
+
+        (Some(item), signals)
+    }
+}
+
+fn set_discont(item: &mut DataQueueItem) {
+    match item {
+        DataQueueItem::Buffer(ref mut buffer) => {
+            buffer.make_mut().set_flags(gst::BufferFlags::DISCONT);
+        }
+        DataQueueItem::BufferList(ref mut list) => {
+            let list = list.make_mut();
+            if let Some(mut buffer) = list.get_owned(0) {
+                buffer.make_mut().set_flags(gst::BufferFlags::DISCONT);
+                list.remove(0, 1);
+                list.insert(0, buffer);
+            }
+        }
+        DataQueueItem::Event(_) => (),
+    }
 }
 
 impl DataQueue {
@@ -116,6 +330,13 @@ impl DataQueue {
             max_size_buffers,
             max_size_bytes,
             max_size_time: max_size_time.into(),
+            leaky: DataQueueLeaky::No,
+            silent: true,
+            dropped: 0,
+            head_needs_discont: false,
+            tail_needs_discont: false,
+            is_overrun: false,
+            is_underrun: false,
             pending_handle: None,
         })))
     }
@@ -124,6 +345,23 @@ impl DataQueue {
         self.0.lock().unwrap().state
     }
 
+    pub fn set_leaky(&self, leaky: DataQueueLeaky) {
+        self.0.lock().unwrap().leaky = leaky;
+    }
+
+    /// Sets whether to refrain from emitting the `overrun`, `underrun`,
+    /// `running` & `pushing` signals on the element, which must declare them.
+    ///
+    /// `DataQueue`s are silent by default.
+    pub fn set_silent(&self, silent: bool) {
+        self.0.lock().unwrap().silent = silent;
+    }
+
+    /// Returns the number of buffers dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.0.lock().unwrap().dropped
+    }
+
     pub fn start(&self) {
         let mut inner = self.0.lock().unwrap();
         if inner.state == DataQueueState::Started {
@@ -132,6 +370,7 @@ impl DataQueue {
         }
         gst::debug!(DATA_QUEUE_CAT, obj: &inner.element, "Starting data queue");
         inner.state = DataQueueState::Started;
+        inner.is_underrun = false;
         inner.wake();
     }
 
@@ -163,92 +402,74 @@ impl DataQueue {
             }
         }
 
+        inner.cur_size_buffers = 0;
+        inner.cur_size_bytes = 0;
+        inner.head_needs_discont = false;
+        inner.tail_needs_discont = false;
+        inner.is_overrun = false;
+
         gst::debug!(DATA_QUEUE_CAT, obj: &inner.element, "Data queue cleared");
     }
 
+    /// Pushes the item to the queue.
+    ///
+    /// If the queue is full, the item is returned unless the queue is leaky,
+    /// in which case either this item or the oldest buffers are dropped.
     pub fn push(&self, item: DataQueueItem) -> Result<(), DataQueueItem> {
         let mut inner = self.0.lock().unwrap();
+        let (res, signal) = inner.push(item);
 
-        if inner.state == DataQueueState::Stopped {
-            gst::debug!(
-                DATA_QUEUE_CAT,
-                obj: &inner.element,
-                "Rejecting item {:?} in state {:?}",
-                item,
-                inner.state
-            );
-            return Err(item);
-        }
-
-        gst::debug!(DATA_QUEUE_CAT, obj: &inner.element, "Pushing item {:?}", item);
-
-        let (count, bytes) = item.size();
-        let queue_ts = inner.queue.iter().filter_map(|i| i.timestamp()).next();
-        let ts = item.timestamp();
-
-        if let Some(max) = inner.max_size_buffers {
-            if max <= inner.cur_size_buffers {
-                gst::debug!(DATA_QUEUE_CAT, obj: &inner.element, "Queue is full (buffers): {} <= {}", max, inner.cur_size_buffers);
-                return Err(item);
-            }
-        }
-
-        if let Some(max) = inner.max_size_bytes {
-            if max <= inner.cur_size_bytes {
-                gst::debug!(DATA_QUEUE_CAT, obj: &inner.element, "Queue is full (bytes): {} <= {}", max, inner.cur_size_bytes);
-                return Err(item);
-            }
-        }
-
-        // FIXME: Use running time
-        if let (Some(max), Some(queue_ts), Some(ts)) = (inner.max_size_time, queue_ts, ts) {
-            let level = if queue_ts > ts {
-                queue_ts - ts
-            } else {
-                ts - queue_ts
-            };
-
-            if max <= level {
-                gst::debug!(DATA_QUEUE_CAT, obj: &inner.element, "Queue is full (time): {} <= {}", max, level);
-                return Err(item);
+        if let Some(signal) = signal {
+            if !inner.silent {
+                let element = inner.element.clone();
+                drop(inner);
+                element.emit_by_name::<()>(signal.name(), &[]);
             }
         }
 
-        inner.queue.push_back(item);
-        inner.cur_size_buffers += count;
-        inner.cur_size_bytes += bytes;
-
-        inner.wake();
-
-        Ok(())
+        res
     }
 
     // TODO: implement as a Stream now that we use a StdMutex
     #[allow(clippy::should_implement_trait)]
     pub async fn next(&mut self) -> Option<DataQueueItem> {
         loop {
-            let pending_fut = {
+            let (item, signals, element) = {
                 let mut inner = self.0.lock().unwrap();
                 match inner.state {
-                    DataQueueState::Started => match inner.queue.pop_front() {
-                        None => {
-                            gst::debug!(DATA_QUEUE_CAT, obj: &inner.element, "Data queue is empty");
-                        }
-                        Some(item) => {
-                            gst::debug!(DATA_QUEUE_CAT, obj: &inner.element, "Popped item {:?}", item);
-
-                            let (count, bytes) = item.size();
-                            inner.cur_size_buffers -= count;
-                            inner.cur_size_bytes -= bytes;
-
-                            return Some(item);
-                        }
-                    },
+                    DataQueueState::Started => {
+                        let (item, signals) = inner.pop();
+                        let element = if !signals.is_empty() && !inner.silent {
+                            Some(inner.element.clone())
+                        } else {
+                            None
+                        };
+
+                        (item, signals, element)
+                    }
                     DataQueueState::Stopped => {
                         gst::debug!(DATA_QUEUE_CAT, obj: &inner.element, "Data queue Stopped");
                         return None;
                     }
                 }
+            };
+
+            if let Some(element) = element {
+                for signal in signals {
+                    element.emit_by_name::<()>(signal.name(), &[]);
+                }
+            }
+
+            if item.is_some() {
+                return item;
+            }
+
+            let pending_fut = {
+                let mut inner = self.0.lock().unwrap();
+                if inner.state == DataQueueState::Stopped || !inner.queue.is_empty() {
+                    // Changed while the signals were emitted
+                    continue;
+                }
 
                 let (pending_fut, abort_handle) = abortable(future::pending::<()>());
                 inner.pending_handle = Some(abort_handle);
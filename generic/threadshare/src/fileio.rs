@@ -0,0 +1,88 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+//! Blocking file I/O offloading for `ts-filesrc` & `ts-filesink`.
+//!
+//! Regular files are always reported as readable / writable by `epoll`, so they
+//! can't be driven by the reactor. Instead, file operations are executed on a
+//! small pool of dedicated threads shared by all the elements of the process,
+//! and their result is awaited from the element's `Context`. This way, many
+//! file elements can share a few `Context`s without ever blocking them.
+
+use once_cell::sync::Lazy;
+
+use std::thread;
+
+const POOL_SIZE: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+static POOL: Lazy<flume::Sender<Job>> = Lazy::new(|| {
+    let (sender, receiver) = flume::unbounded::<Job>();
+
+    for i in 0..POOL_SIZE {
+        let receiver = receiver.clone();
+        thread::Builder::new()
+            .name(format!("ts-fileio-{}", i))
+            .spawn(move || {
+                while let Ok(job) = receiver.recv() {
+                    job();
+                }
+            })
+            .expect("Failed to spawn file I/O thread");
+    }
+
+    sender
+});
+
+/// Executes `f` on the file I/O pool.
+///
+/// The result can be awaited using `recv_async` on the returned `Receiver`.
+/// Dropping the `Receiver` doesn't cancel the operation, so callers which need
+/// operations to be executed in sequence must keep the `Receiver` around until
+/// the result is received, even if the awaiting `Future` is cancelled.
+pub fn spawn<F, R>(f: F) -> flume::Receiver<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (sender, receiver) = flume::bounded(1);
+
+    POOL.send(Box::new(move || {
+        let _ = sender.send(f());
+    }))
+    .expect("File I/O pool stopped");
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn spawn() {
+        let receivers = (0..16)
+            .map(|i| super::spawn(move || i * 2))
+            .collect::<Vec<_>>();
+
+        for (i, receiver) in receivers.into_iter().enumerate() {
+            let res = futures::executor::block_on(receiver.recv_async()).unwrap();
+            assert_eq!(res, i * 2);
+        }
+    }
+}
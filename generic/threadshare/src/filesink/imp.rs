@@ -0,0 +1,526 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use futures::future::BoxFuture;
+use futures::prelude::*;
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst::EventView;
+
+use once_cell::sync::Lazy;
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::Duration;
+
+use crate::fileio;
+use crate::runtime::prelude::*;
+use crate::runtime::{Context, PadSink, PadSinkRef, Task};
+
+const DEFAULT_LOCATION: Option<&str> = None;
+const DEFAULT_APPEND: bool = false;
+const DEFAULT_CONTEXT: &str = "";
+const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    location: Option<String>,
+    append: bool,
+    context: String,
+    context_wait: Duration,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            location: DEFAULT_LOCATION.map(Into::into),
+            append: DEFAULT_APPEND,
+            context: DEFAULT_CONTEXT.into(),
+            context_wait: DEFAULT_CONTEXT_WAIT,
+        }
+    }
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ts-filesink",
+        gst::DebugColorFlags::empty(),
+        Some("Thread-sharing file sink"),
+    )
+});
+
+#[derive(Debug)]
+enum TaskItem {
+    Buffer(gst::Buffer),
+    BufferList(gst::BufferList),
+    Event(gst::Event),
+}
+
+fn write_buffer(mut file: &File, buffer: &gst::BufferRef) -> io::Result<()> {
+    let map = buffer
+        .map_readable()
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+    file.write_all(map.as_slice())
+}
+
+#[derive(Clone, Debug)]
+struct FileSinkPadHandler;
+
+impl PadSinkHandler for FileSinkPadHandler {
+    type ElementImpl = FileSink;
+
+    fn sink_chain(
+        &self,
+        _pad: &PadSinkRef,
+        filesink: &FileSink,
+        element: &gst::Element,
+        buffer: gst::Buffer,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let sender = filesink.clone_item_sender();
+        let element = element.clone().downcast::<super::FileSink>().unwrap();
+
+        async move {
+            if sender.send_async(TaskItem::Buffer(buffer)).await.is_err() {
+                gst::debug!(CAT, obj: &element, "Flushing");
+                return Err(gst::FlowError::Flushing);
+            }
+
+            Ok(gst::FlowSuccess::Ok)
+        }
+        .boxed()
+    }
+
+    fn sink_chain_list(
+        &self,
+        _pad: &PadSinkRef,
+        filesink: &FileSink,
+        element: &gst::Element,
+        list: gst::BufferList,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let sender = filesink.clone_item_sender();
+        let element = element.clone().downcast::<super::FileSink>().unwrap();
+
+        async move {
+            if sender.send_async(TaskItem::BufferList(list)).await.is_err() {
+                gst::debug!(CAT, obj: &element, "Flushing");
+                return Err(gst::FlowError::Flushing);
+            }
+
+            Ok(gst::FlowSuccess::Ok)
+        }
+        .boxed()
+    }
+
+    fn sink_event_serialized(
+        &self,
+        _pad: &PadSinkRef,
+        filesink: &FileSink,
+        element: &gst::Element,
+        event: gst::Event,
+    ) -> BoxFuture<'static, bool> {
+        let sender = filesink.clone_item_sender();
+        let element = element.clone().downcast::<super::FileSink>().unwrap();
+
+        async move {
+            if let EventView::FlushStop(_) = event.view() {
+                let filesink = element.imp();
+                return filesink.task.flush_stop().await_maybe_on_context().is_ok();
+            } else if sender.send_async(TaskItem::Event(event)).await.is_err() {
+                gst::debug!(CAT, obj: &element, "Flushing");
+            }
+
+            true
+        }
+        .boxed()
+    }
+
+    fn sink_event(
+        &self,
+        _pad: &PadSinkRef,
+        filesink: &FileSink,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        if let EventView::FlushStart(..) = event.view() {
+            return filesink.task.flush_start().await_maybe_on_context().is_ok();
+        }
+
+        true
+    }
+}
+
+struct FileSinkTask {
+    element: super::FileSink,
+    file: Arc<File>,
+    item_receiver: flume::r#async::RecvStream<'static, TaskItem>,
+}
+
+impl FileSinkTask {
+    fn new(
+        element: &super::FileSink,
+        file: Arc<File>,
+        item_receiver: flume::Receiver<TaskItem>,
+    ) -> Self {
+        FileSinkTask {
+            element: element.clone(),
+            file,
+            item_receiver: item_receiver.into_stream(),
+        }
+    }
+
+    async fn flush(&mut self) {
+        // Purge the channel
+        while let Poll::Ready(Some(_item)) = futures::poll!(self.item_receiver.next()) {}
+    }
+
+    /// Executes `f` with the file on the file I/O pool.
+    async fn run<F>(&self, f: F) -> Result<(), gst::FlowError>
+    where
+        F: FnOnce(&File) -> io::Result<()> + Send + 'static,
+    {
+        let file = self.file.clone();
+        // Items are processed to completion, so operations can't overlap.
+        let res = fileio::spawn(move || f(&file)).recv_async().await.unwrap();
+
+        res.map_err(|err| {
+            gst::error!(CAT, obj: &self.element, "Failed to write: {}", err);
+            gst::element_error!(
+                self.element,
+                gst::ResourceError::Write,
+                ("Failed to write to file"),
+                ["streaming stopped, I/O error {}", err]
+            );
+            gst::FlowError::Error
+        })
+    }
+}
+
+impl TaskImpl for FileSinkTask {
+    type Item = TaskItem;
+
+    fn try_next(&mut self) -> BoxFuture<'_, Result<TaskItem, gst::FlowError>> {
+        async move {
+            Ok(self
+                .item_receiver
+                .next()
+                .await
+                .expect("Internal channel sender dropped while Task is Started"))
+        }
+        .boxed()
+    }
+
+    fn handle_item(&mut self, item: TaskItem) -> BoxFuture<'_, Result<(), gst::FlowError>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Handling {:?}", item);
+
+            match item {
+                TaskItem::Buffer(buffer) => {
+                    self.run(move |file| write_buffer(file, &buffer)).await?;
+                }
+                TaskItem::BufferList(list) => {
+                    self.run(move |file| {
+                        list.iter()
+                            .try_for_each(|buffer| write_buffer(file, buffer))
+                    })
+                    .await?;
+                }
+                TaskItem::Event(event) => match event.view() {
+                    EventView::Eos(_) => {
+                        self.run(|file| file.sync_data()).await?;
+                        let _ = self
+                            .element
+                            .post_message(gst::message::Eos::builder().src(&self.element).build());
+                    }
+                    EventView::SinkMessage(e) => {
+                        let _ = self.element.post_message(e.message());
+                    }
+                    _ => (),
+                },
+            }
+
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn stop(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async {
+            gst::info!(CAT, obj: &self.element, "Stopping Task");
+            self.flush().await;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn flush_start(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async {
+            gst::info!(CAT, obj: &self.element, "Starting Task Flush");
+            self.flush().await;
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[derive(Debug)]
+pub struct FileSink {
+    sink_pad: PadSink,
+    task: Task,
+    item_sender: Mutex<Option<flume::Sender<TaskItem>>>,
+    settings: Mutex<Settings>,
+}
+
+impl FileSink {
+    #[track_caller]
+    fn clone_item_sender(&self) -> flume::Sender<TaskItem> {
+        self.item_sender.lock().unwrap().as_ref().unwrap().clone()
+    }
+
+    fn prepare(&self, element: &super::FileSink) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Preparing");
+
+        let settings = self.settings.lock().unwrap().clone();
+
+        let context =
+            Context::acquire(&settings.context, settings.context_wait).map_err(|err| {
+                gst::error_msg!(
+                    gst::ResourceError::OpenWrite,
+                    ["Failed to acquire Context: {}", err]
+                )
+            })?;
+
+        let location = settings.location.ok_or_else(|| {
+            gst::error_msg!(gst::ResourceError::NotFound, ["No location specified"])
+        })?;
+
+        let mut options = OpenOptions::new();
+        if settings.append {
+            options.append(true);
+        } else {
+            options.write(true).truncate(true);
+        }
+        let file = options.create(true).open(&location).map_err(|err| {
+            gst::error_msg!(
+                gst::ResourceError::OpenWrite,
+                ["Could not open file {} for writing: {}", location, err]
+            )
+        })?;
+
+        // Enable backpressure for items
+        let (item_sender, item_receiver) = flume::bounded(0);
+        let task_impl = FileSinkTask::new(element, Arc::new(file), item_receiver);
+        self.task.prepare(task_impl, context).block_on()?;
+
+        *self.item_sender.lock().unwrap() = Some(item_sender);
+
+        gst::debug!(CAT, obj: element, "Prepared");
+
+        Ok(())
+    }
+
+    fn unprepare(&self, element: &super::FileSink) {
+        gst::debug!(CAT, obj: element, "Unpreparing");
+        self.task.unprepare().block_on().unwrap();
+        gst::debug!(CAT, obj: element, "Unprepared");
+    }
+
+    fn stop(&self, element: &super::FileSink) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Stopping");
+        self.task.stop().block_on()?;
+        gst::debug!(CAT, obj: element, "Stopped");
+        Ok(())
+    }
+
+    fn start(&self, element: &super::FileSink) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Starting");
+        self.task.start().block_on()?;
+        gst::debug!(CAT, obj: element, "Started");
+        Ok(())
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for FileSink {
+    const NAME: &'static str = "RsTsFileSink";
+    type Type = super::FileSink;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        Self {
+            sink_pad: PadSink::new(
+                gst::Pad::from_template(&klass.pad_template("sink").unwrap(), Some("sink")),
+                FileSinkPadHandler,
+            ),
+            task: Task::default(),
+            item_sender: Default::default(),
+            settings: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for FileSink {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::builder("context")
+                    .nick("Context")
+                    .blurb("Context name to share threads with")
+                    .default_value(Some(DEFAULT_CONTEXT))
+                    .build(),
+                glib::ParamSpecUInt::builder("context-wait")
+                    .nick("Context Wait")
+                    .blurb("Throttle poll loop to run at most once every this many ms")
+                    .maximum(1000)
+                    .default_value(DEFAULT_CONTEXT_WAIT.as_millis() as u32)
+                    .build(),
+                glib::ParamSpecString::builder("location")
+                    .nick("File Location")
+                    .blurb("Location of the file to write to")
+                    .default_value(DEFAULT_LOCATION)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("append")
+                    .nick("Append")
+                    .blurb("Append to an already existing file instead of truncating it")
+                    .default_value(DEFAULT_APPEND)
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "context" => {
+                settings.context = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_else(|| DEFAULT_CONTEXT.into());
+            }
+            "context-wait" => {
+                settings.context_wait = Duration::from_millis(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
+            "location" => {
+                settings.location = value.get().expect("type checked upstream");
+            }
+            "append" => {
+                settings.append = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "context" => settings.context.to_value(),
+            "context-wait" => (settings.context_wait.as_millis() as u32).to_value(),
+            "location" => settings.location.to_value(),
+            "append" => settings.append.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(self.sink_pad.gst_pad()).unwrap();
+
+        crate::set_element_flags(obj, gst::ElementFlags::SINK);
+    }
+}
+
+impl GstObjectImpl for FileSink {}
+
+impl ElementImpl for FileSink {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Thread-sharing file sink",
+                "Sink/File",
+                "Writes to a file without blocking the thread-sharing context",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::new_any();
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::NullToReady => {
+                self.prepare(element).map_err(|err| {
+                    element.post_error_message(err);
+                    gst::StateChangeError
+                })?;
+            }
+            gst::StateChange::ReadyToPaused => {
+                self.start(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::PausedToReady => {
+                self.stop(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::ReadyToNull => {
+                self.unprepare(element);
+            }
+            _ => (),
+        }
+
+        self.parent_change_state(element, transition)
+    }
+}
@@ -0,0 +1,632 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use futures::future::BoxFuture;
+use futures::prelude::*;
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+use std::fs::File;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::u32;
+
+use crate::fileio;
+use crate::runtime::prelude::*;
+use crate::runtime::{Context, PadSrc, PadSrcRef, Task};
+
+const DEFAULT_LOCATION: Option<&str> = None;
+const DEFAULT_BLOCKSIZE: u32 = 4096;
+const DEFAULT_CONTEXT: &str = "";
+const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    location: Option<String>,
+    blocksize: u32,
+    context: String,
+    context_wait: Duration,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            location: DEFAULT_LOCATION.map(Into::into),
+            blocksize: DEFAULT_BLOCKSIZE,
+            context: DEFAULT_CONTEXT.into(),
+            context_wait: DEFAULT_CONTEXT_WAIT,
+        }
+    }
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ts-filesrc",
+        gst::DebugColorFlags::empty(),
+        Some("Thread-sharing file source"),
+    )
+});
+
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    use std::os::unix::fs::FileExt;
+    file.read_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    use std::os::windows::fs::FileExt;
+    file.seek_read(buf, offset)
+}
+
+/// Reads up to `blocksize` bytes at `offset` on the file I/O pool.
+///
+/// Returns an empty `Buffer` at the end of the file.
+fn read_buffer(file: &File, blocksize: usize, offset: u64) -> io::Result<gst::Buffer> {
+    let mut buffer = gst::Buffer::with_size(blocksize)
+        .map_err(|err| io::Error::new(io::ErrorKind::OutOfMemory, err))?;
+
+    {
+        let buffer = buffer.get_mut().unwrap();
+        let len = {
+            let mut map = buffer.map_writable().unwrap();
+            loop {
+                match read_at(file, map.as_mut_slice(), offset) {
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    res => break res?,
+                }
+            }
+        };
+
+        buffer.set_size(len);
+        buffer.set_offset(offset);
+        buffer.set_offset_end(offset + len as u64);
+    }
+
+    Ok(buffer)
+}
+
+#[derive(Clone, Debug)]
+struct FileSrcPadHandler;
+
+impl PadSrcHandler for FileSrcPadHandler {
+    type ElementImpl = FileSrc;
+
+    fn src_event(
+        &self,
+        pad: &PadSrcRef,
+        filesrc: &FileSrc,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", event);
+
+        let ret = match event.view() {
+            EventView::FlushStart(..) => {
+                filesrc.task.flush_start().await_maybe_on_context().is_ok()
+            }
+            EventView::FlushStop(..) => filesrc.task.flush_stop().await_maybe_on_context().is_ok(),
+            EventView::Reconfigure(..) => true,
+            EventView::Latency(..) => true,
+            _ => false,
+        };
+
+        if ret {
+            gst::log!(CAT, obj: pad.gst_pad(), "Handled {:?}", event);
+        } else {
+            gst::log!(CAT, obj: pad.gst_pad(), "Didn't handle {:?}", event);
+        }
+
+        ret
+    }
+
+    fn src_query(
+        &self,
+        pad: &PadSrcRef,
+        filesrc: &FileSrc,
+        _element: &gst::Element,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        use gst::QueryViewMut;
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", query);
+        let ret = match query.view_mut() {
+            QueryViewMut::Latency(q) => {
+                q.set(false, gst::ClockTime::ZERO, gst::ClockTime::NONE);
+                true
+            }
+            QueryViewMut::Scheduling(q) => {
+                q.set(gst::SchedulingFlags::SEQUENTIAL, 1, -1, 0);
+                q.add_scheduling_modes(&[gst::PadMode::Push]);
+                true
+            }
+            QueryViewMut::Duration(q) => {
+                if q.format() == gst::Format::Bytes {
+                    match *filesrc.size.lock().unwrap() {
+                        Some(size) => {
+                            q.set(gst::format::Bytes(size));
+                            true
+                        }
+                        None => false,
+                    }
+                } else {
+                    false
+                }
+            }
+            QueryViewMut::Caps(q) => {
+                let caps = q
+                    .filter()
+                    .map(|f| f.to_owned())
+                    .unwrap_or_else(gst::Caps::new_any);
+
+                q.set_result(&caps);
+
+                true
+            }
+            _ => false,
+        };
+
+        if ret {
+            gst::log!(CAT, obj: pad.gst_pad(), "Handled {:?}", query);
+        } else {
+            gst::log!(CAT, obj: pad.gst_pad(), "Didn't handle {:?}", query);
+        }
+        ret
+    }
+}
+
+struct FileSrcTask {
+    element: super::FileSrc,
+    file: Arc<File>,
+    blocksize: usize,
+    offset: u64,
+    pending: Option<flume::Receiver<io::Result<gst::Buffer>>>,
+    need_initial_events: bool,
+    need_segment: bool,
+}
+
+impl FileSrcTask {
+    fn new(element: &super::FileSrc, file: Arc<File>, blocksize: usize) -> Self {
+        FileSrcTask {
+            element: element.clone(),
+            file,
+            blocksize,
+            offset: 0,
+            pending: None,
+            need_initial_events: true,
+            need_segment: true,
+        }
+    }
+
+    async fn push_initial_events(&mut self) {
+        let filesrc = self.element.imp();
+
+        if self.need_initial_events {
+            gst::debug!(CAT, obj: &self.element, "Pushing initial events");
+
+            let stream_id = format!("{:08x}{:08x}", rand::random::<u32>(), rand::random::<u32>());
+            let stream_start_evt = gst::event::StreamStart::builder(&stream_id)
+                .group_id(gst::GroupId::next())
+                .build();
+            filesrc.src_pad.push_event(stream_start_evt).await;
+
+            self.need_initial_events = false;
+        }
+
+        if self.need_segment {
+            let mut segment = gst::FormattedSegment::<gst::format::Bytes>::new();
+            segment.set_start(gst::format::Bytes(self.offset));
+            segment.set_time(gst::format::Bytes(self.offset));
+            if let Some(size) = *filesrc.size.lock().unwrap() {
+                segment.set_duration(gst::format::Bytes(size));
+            }
+            filesrc
+                .src_pad
+                .push_event(gst::event::Segment::new(&segment))
+                .await;
+
+            self.need_segment = false;
+        }
+    }
+}
+
+impl TaskImpl for FileSrcTask {
+    type Item = gst::Buffer;
+
+    fn try_next(&mut self) -> BoxFuture<'_, Result<gst::Buffer, gst::FlowError>> {
+        async move {
+            // A pending read is kept around in case this is cancelled
+            // by a state transition, so that it's not issued twice.
+            let pending = match self.pending {
+                Some(ref pending) => pending.clone(),
+                None => {
+                    let file = self.file.clone();
+                    let blocksize = self.blocksize;
+                    let offset = self.offset;
+                    let pending = fileio::spawn(move || read_buffer(&file, blocksize, offset));
+                    self.pending = Some(pending.clone());
+
+                    pending
+                }
+            };
+
+            let res = pending.recv_async().await.unwrap();
+            self.pending = None;
+
+            res.map_err(|err| {
+                gst::error!(CAT, obj: &self.element, "Failed to read: {}", err);
+                gst::element_error!(
+                    self.element,
+                    gst::ResourceError::Read,
+                    ("Failed to read from file"),
+                    ["streaming stopped, I/O error {}", err]
+                );
+                gst::FlowError::Error
+            })
+        }
+        .boxed()
+    }
+
+    fn handle_item(&mut self, buffer: gst::Buffer) -> BoxFuture<'_, Result<(), gst::FlowError>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Handling {:?}", buffer);
+
+            self.push_initial_events().await;
+
+            let filesrc = self.element.imp();
+
+            if buffer.size() == 0 {
+                gst::debug!(CAT, obj: &self.element, "Reached end of file");
+                filesrc.src_pad.push_event(gst::event::Eos::new()).await;
+                return Err(gst::FlowError::Eos);
+            }
+
+            self.offset += buffer.size() as u64;
+
+            let res = filesrc.src_pad.push(buffer).await;
+            match res {
+                Ok(_) => {
+                    gst::log!(CAT, obj: &self.element, "Successfully pushed buffer");
+                }
+                Err(gst::FlowError::Flushing) => {
+                    gst::debug!(CAT, obj: &self.element, "Flushing");
+                }
+                Err(gst::FlowError::Eos) => {
+                    gst::debug!(CAT, obj: &self.element, "EOS");
+                    filesrc.src_pad.push_event(gst::event::Eos::new()).await;
+                }
+                Err(err) => {
+                    gst::error!(CAT, obj: &self.element, "Got error {}", err);
+                    gst::element_error!(
+                        self.element,
+                        gst::StreamError::Failed,
+                        ("Internal data stream error"),
+                        ["streaming stopped, reason {}", err]
+                    );
+                }
+            }
+
+            res.map(drop)
+        }
+        .boxed()
+    }
+
+    fn stop(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Stopping task");
+
+            // Make sure a read in flight doesn't overlap with the next one.
+            if let Some(pending) = self.pending.take() {
+                let _ = pending.recv_async().await;
+            }
+            self.offset = 0;
+            self.need_initial_events = true;
+            self.need_segment = true;
+
+            gst::log!(CAT, obj: &self.element, "Task stopped");
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn flush_stop(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Stopping task flush");
+            self.need_segment = true;
+            gst::log!(CAT, obj: &self.element, "Task flush stopped");
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[derive(Debug)]
+pub struct FileSrc {
+    src_pad: PadSrc,
+    task: Task,
+    size: Mutex<Option<u64>>,
+    settings: Mutex<Settings>,
+}
+
+impl FileSrc {
+    fn prepare(&self, element: &super::FileSrc) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Preparing");
+
+        let settings = self.settings.lock().unwrap().clone();
+
+        let context =
+            Context::acquire(&settings.context, settings.context_wait).map_err(|err| {
+                gst::error_msg!(
+                    gst::ResourceError::OpenRead,
+                    ["Failed to acquire Context: {}", err]
+                )
+            })?;
+
+        let location = settings.location.ok_or_else(|| {
+            gst::error_msg!(gst::ResourceError::NotFound, ["No location specified"])
+        })?;
+
+        let file = File::open(&location).map_err(|err| {
+            gst::error_msg!(
+                gst::ResourceError::OpenRead,
+                ["Could not open file {} for reading: {}", location, err]
+            )
+        })?;
+
+        let metadata = file.metadata().map_err(|err| {
+            gst::error_msg!(
+                gst::ResourceError::OpenRead,
+                ["Could not get metadata for {}: {}", location, err]
+            )
+        })?;
+        if metadata.is_dir() {
+            return Err(gst::error_msg!(
+                gst::ResourceError::OpenRead,
+                ["{} is a directory", location]
+            ));
+        }
+        *self.size.lock().unwrap() = Some(metadata.len());
+
+        self.task
+            .prepare(
+                FileSrcTask::new(element, Arc::new(file), settings.blocksize as usize),
+                context,
+            )
+            .block_on()?;
+
+        gst::debug!(CAT, obj: element, "Prepared");
+
+        Ok(())
+    }
+
+    fn unprepare(&self, element: &super::FileSrc) {
+        gst::debug!(CAT, obj: element, "Unpreparing");
+        self.task.unprepare().block_on().unwrap();
+        *self.size.lock().unwrap() = None;
+        gst::debug!(CAT, obj: element, "Unprepared");
+    }
+
+    fn stop(&self, element: &super::FileSrc) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Stopping");
+        self.task.stop().block_on()?;
+        gst::debug!(CAT, obj: element, "Stopped");
+        Ok(())
+    }
+
+    fn start(&self, element: &super::FileSrc) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Starting");
+        self.task.start().block_on()?;
+        gst::debug!(CAT, obj: element, "Started");
+        Ok(())
+    }
+
+    fn pause(&self, element: &super::FileSrc) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Pausing");
+        self.task.pause().block_on()?;
+        gst::debug!(CAT, obj: element, "Paused");
+        Ok(())
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for FileSrc {
+    const NAME: &'static str = "RsTsFileSrc";
+    type Type = super::FileSrc;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        Self {
+            src_pad: PadSrc::new(
+                gst::Pad::from_template(&klass.pad_template("src").unwrap(), Some("src")),
+                FileSrcPadHandler,
+            ),
+            task: Task::default(),
+            size: Default::default(),
+            settings: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for FileSrc {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::builder("context")
+                    .nick("Context")
+                    .blurb("Context name to share threads with")
+                    .default_value(Some(DEFAULT_CONTEXT))
+                    .build(),
+                glib::ParamSpecUInt::builder("context-wait")
+                    .nick("Context Wait")
+                    .blurb("Throttle poll loop to run at most once every this many ms")
+                    .maximum(1000)
+                    .default_value(DEFAULT_CONTEXT_WAIT.as_millis() as u32)
+                    .build(),
+                glib::ParamSpecString::builder("location")
+                    .nick("File Location")
+                    .blurb("Location of the file to read from")
+                    .default_value(DEFAULT_LOCATION)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("blocksize")
+                    .nick("Blocksize")
+                    .blurb("Size in bytes to read per buffer")
+                    .minimum(1)
+                    .default_value(DEFAULT_BLOCKSIZE)
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "context" => {
+                settings.context = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_else(|| DEFAULT_CONTEXT.into());
+            }
+            "context-wait" => {
+                settings.context_wait = Duration::from_millis(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
+            "location" => {
+                settings.location = value.get().expect("type checked upstream");
+            }
+            "blocksize" => {
+                settings.blocksize = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "context" => settings.context.to_value(),
+            "context-wait" => (settings.context_wait.as_millis() as u32).to_value(),
+            "location" => settings.location.to_value(),
+            "blocksize" => settings.blocksize.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(self.src_pad.gst_pad()).unwrap();
+
+        crate::set_element_flags(obj, gst::ElementFlags::SOURCE);
+    }
+}
+
+impl GstObjectImpl for FileSrc {}
+
+impl ElementImpl for FileSrc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Thread-sharing file source",
+                "Source/File",
+                "Reads from a file without blocking the thread-sharing context",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::new_any();
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::NullToReady => {
+                self.prepare(element).map_err(|err| {
+                    element.post_error_message(err);
+                    gst::StateChangeError
+                })?;
+            }
+            gst::StateChange::PlayingToPaused => {
+                self.pause(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::ReadyToNull => {
+                self.unprepare(element);
+            }
+            _ => (),
+        }
+
+        let mut success = self.parent_change_state(element, transition)?;
+
+        match transition {
+            gst::StateChange::ReadyToPaused => {
+                success = gst::StateChangeSuccess::NoPreroll;
+            }
+            gst::StateChange::PausedToPlaying => {
+                self.start(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::PlayingToPaused => {
+                success = gst::StateChangeSuccess::NoPreroll;
+            }
+            gst::StateChange::PausedToReady => {
+                self.stop(element).map_err(|_| gst::StateChangeError)?;
+            }
+            _ => (),
+        }
+
+        Ok(success)
+    }
+}
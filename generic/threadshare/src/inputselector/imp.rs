@@ -17,6 +17,7 @@
 //
 // SPDX-License-Identifier: LGPL-2.1-or-later
 
+use futures::channel::oneshot;
 use futures::future::BoxFuture;
 use futures::future::{abortable, AbortHandle};
 use futures::prelude::*;
@@ -27,7 +28,7 @@ use gst::subclass::prelude::*;
 
 use once_cell::sync::Lazy;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::u32;
@@ -35,13 +36,25 @@ use std::u32;
 use crate::runtime::prelude::*;
 use crate::runtime::{self, PadSink, PadSinkRef, PadSrc, PadSrcRef};
 
+use super::InputSelectorSyncMode;
+
 const DEFAULT_CONTEXT: &str = "";
 const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
+const DEFAULT_SYNC_STREAMS: bool = true;
+// Unlike the C input-selector, we default to the clock so that
+// the active stream keeps being paced as it always was.
+const DEFAULT_SYNC_MODE: InputSelectorSyncMode = InputSelectorSyncMode::Clock;
+const DEFAULT_CACHE_BUFFERS: bool = false;
+const DEFAULT_DROP_BACKWARDS: bool = false;
 
 #[derive(Debug, Clone)]
 struct Settings {
     context: String,
     context_wait: Duration,
+    sync_streams: bool,
+    sync_mode: InputSelectorSyncMode,
+    cache_buffers: bool,
+    drop_backwards: bool,
 }
 
 impl Default for Settings {
@@ -49,15 +62,99 @@ impl Default for Settings {
         Settings {
             context: DEFAULT_CONTEXT.into(),
             context_wait: DEFAULT_CONTEXT_WAIT,
+            sync_streams: DEFAULT_SYNC_STREAMS,
+            sync_mode: DEFAULT_SYNC_MODE,
+            cache_buffers: DEFAULT_CACHE_BUFFERS,
+            drop_backwards: DEFAULT_DROP_BACKWARDS,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SinkPadState {
+    running_time: Option<gst::ClockTime>,
+    eos: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct InputSelectorSinkPad {
+    state: Mutex<SinkPadState>,
+}
+
+impl InputSelectorSinkPad {
+    fn from_pad(pad: &gst::Pad) -> &Self {
+        pad.downcast_ref::<super::InputSelectorSinkPad>()
+            .expect("not an input selector sink pad")
+            .imp()
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for InputSelectorSinkPad {
+    const NAME: &'static str = "RsTsInputSelectorSinkPad";
+    type Type = super::InputSelectorSinkPad;
+    type ParentType = gst::Pad;
+}
+
+impl ObjectImpl for InputSelectorSinkPad {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecUInt64::builder("running-time")
+                    .nick("Running time")
+                    .blurb("Running time of the last buffer received on the pad")
+                    .read_only()
+                    .build(),
+                glib::ParamSpecBoolean::builder("active")
+                    .nick("Active")
+                    .blurb("Whether the pad is the active one")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn property(&self, obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "running-time" => {
+                let state = self.state.lock().unwrap();
+                state.running_time.map_or(0, gst::ClockTime::nseconds).to_value()
+            }
+            "active" => {
+                let element = obj
+                    .parent()
+                    .and_then(|parent| parent.downcast::<super::InputSelector>().ok());
+                let is_active = element.map_or(false, |element| {
+                    let state = element.imp().state.lock().unwrap();
+                    state.active_sinkpad.as_ref() == Some(obj.upcast_ref())
+                });
+                is_active.to_value()
+            }
+            _ => unimplemented!(),
         }
     }
 }
 
+impl GstObjectImpl for InputSelectorSinkPad {}
+
+impl PadImpl for InputSelectorSinkPad {}
+
+#[derive(Debug)]
+struct CachedBuffer {
+    buffer: gst::Buffer,
+    running_time: Option<gst::ClockTime>,
+    running_time_end: Option<gst::ClockTime>,
+}
+
 #[derive(Debug)]
 struct InputSelectorPadSinkHandlerInner {
     segment: Option<gst::Segment>,
     send_sticky: bool,
     abort_handle: Option<AbortHandle>,
+    flushing: bool,
+    cache: VecDeque<CachedBuffer>,
 }
 
 impl Default for InputSelectorPadSinkHandlerInner {
@@ -66,6 +163,8 @@ impl Default for InputSelectorPadSinkHandlerInner {
             segment: None,
             send_sticky: true,
             abort_handle: None,
+            flushing: false,
+            cache: VecDeque::new(),
         }
     }
 }
@@ -74,6 +173,17 @@ impl Default for InputSelectorPadSinkHandlerInner {
 struct InputSelectorPadSinkHandler(Arc<Mutex<InputSelectorPadSinkHandlerInner>>);
 
 impl InputSelectorPadSinkHandler {
+    fn set_flushing(&self, flushing: bool) {
+        let mut inner = self.0.lock().unwrap();
+        inner.flushing = flushing;
+
+        if flushing {
+            if let Some(abort_handle) = inner.abort_handle.take() {
+                abort_handle.abort();
+            }
+        }
+    }
+
     /* Wait until specified time */
     async fn sync(
         &self,
@@ -90,71 +200,221 @@ impl InputSelectorPadSinkHandler {
         }
     }
 
+    /* Wait until the active pad reaches the specified running time */
+    async fn sync_active_segment(
+        &self,
+        element: &super::InputSelector,
+        pad: &gst::Pad,
+        running_time: gst::ClockTime,
+    ) {
+        let inputselector = element.imp();
+
+        loop {
+            let receiver = {
+                let mut state = inputselector.state.lock().unwrap();
+
+                let caught_up = match state.active_sinkpad {
+                    Some(ref active_pad) if active_pad != pad => {
+                        let active_state = InputSelectorSinkPad::from_pad(active_pad)
+                            .state
+                            .lock()
+                            .unwrap();
+                        active_state.eos
+                            || active_state
+                                .running_time
+                                .map_or(false, |active_rtime| active_rtime >= running_time)
+                    }
+                    _ => true,
+                };
+
+                if caught_up {
+                    return;
+                }
+
+                let (sender, receiver) = oneshot::channel();
+                state.waiters.push(sender);
+                receiver
+            };
+
+            if receiver.await.is_err() {
+                // State was reset
+                return;
+            }
+        }
+    }
+
     async fn handle_item(
         &self,
         pad: &PadSinkRef<'_>,
         element: &super::InputSelector,
-        mut buffer: gst::Buffer,
+        buffer: gst::Buffer,
     ) -> Result<gst::FlowSuccess, gst::FlowError> {
         let inputselector = element.imp();
+        let gst_pad = pad.gst_pad();
+
+        let (running_time, running_time_end) = {
+            let inner = self.0.lock().unwrap();
+            if inner.flushing {
+                gst::debug!(CAT, obj: gst_pad, "Flushing");
+                return Err(gst::FlowError::Flushing);
+            }
+
+            match inner
+                .segment
+                .as_ref()
+                .and_then(|segment| segment.downcast_ref::<gst::format::Time>())
+            {
+                Some(segment) => {
+                    let running_time = segment.to_running_time(buffer.pts());
+                    let running_time_end = segment
+                        .to_running_time(buffer.pts().opt_add(buffer.duration()))
+                        .or(running_time);
+                    (running_time, running_time_end)
+                }
+                None => (None, None),
+            }
+        };
+
+        if running_time_end.is_some() {
+            let mut pad_state = InputSelectorSinkPad::from_pad(gst_pad)
+                .state
+                .lock()
+                .unwrap();
+            pad_state.running_time = running_time_end;
+        }
+
+        let settings = inputselector.settings.lock().unwrap().clone();
+
+        let sync_future = {
+            let mut state = inputselector.state.lock().unwrap();
+            let is_active = state.active_sinkpad.as_ref() == Some(gst_pad);
+            if is_active {
+                // The inactive pads might be waiting for us to progress
+                state.wake_waiters();
+            }
+
+            match (settings.sync_mode, running_time) {
+                (InputSelectorSyncMode::Clock, _) if is_active || settings.sync_streams => {
+                    Some(self.sync(element, running_time).boxed())
+                }
+                (InputSelectorSyncMode::ActiveSegment, Some(running_time))
+                    if !is_active && settings.sync_streams =>
+                {
+                    Some(
+                        self.sync_active_segment(element, gst_pad, running_time)
+                            .boxed(),
+                    )
+                }
+                _ => None,
+            }
+        };
+
+        if let Some(sync_future) = sync_future {
+            let (sync_future, abort_handle) = abortable(sync_future);
+            {
+                let mut inner = self.0.lock().unwrap();
+                if inner.flushing {
+                    gst::debug!(CAT, obj: gst_pad, "Flushing");
+                    return Err(gst::FlowError::Flushing);
+                }
+                inner.abort_handle = Some(abort_handle);
+            }
+
+            sync_future.await.map_err(|_| gst::FlowError::Flushing)?;
+        }
 
-        let (stickies, is_active, sync_future, switched_pad) = {
+        let (stickies, buffers) = {
             let mut state = inputselector.state.lock().unwrap();
             let mut inner = self.0.lock().unwrap();
             let mut stickies = vec![];
-            let mut sync_future = None;
-            let switched_pad = state.switched_pad;
-
-            if let Some(segment) = &inner.segment {
-                if let Some(segment) = segment.downcast_ref::<gst::format::Time>() {
-                    let rtime = segment.to_running_time(buffer.pts());
-                    let (sync_fut, abort_handle) = abortable(self.sync(element, rtime));
-                    inner.abort_handle = Some(abort_handle);
-                    sync_future = Some(sync_fut.map_err(|_| gst::FlowError::Flushing));
+            let mut buffers = vec![];
+
+            let buffer = CachedBuffer {
+                buffer,
+                running_time,
+                running_time_end,
+            };
+
+            if state.active_sinkpad.as_ref() == Some(gst_pad) {
+                let switched_pad = state.switched_pad;
+                if inner.send_sticky || switched_pad {
+                    gst_pad.sticky_events_foreach(|event| {
+                        use std::ops::ControlFlow;
+                        stickies.push(event.clone());
+                        ControlFlow::Continue(gst::EventForeachAction::Keep)
+                    });
+
+                    inner.send_sticky = false;
+                    state.switched_pad = false;
                 }
-            }
 
-            let is_active = {
-                if state.active_sinkpad.as_ref() == Some(pad.gst_pad()) {
-                    if inner.send_sticky || state.switched_pad {
-                        pad.gst_pad().sticky_events_foreach(|event| {
-                            use std::ops::ControlFlow;
-                            stickies.push(event.clone());
-                            ControlFlow::Continue(gst::EventForeachAction::Keep)
-                        });
+                // Replay what we cached while we were inactive
+                let mut pending = inner.cache.drain(..).collect::<Vec<_>>();
+                pending.push(buffer);
+
+                let mut needs_discont = switched_pad;
+                for CachedBuffer {
+                    mut buffer,
+                    running_time,
+                    ..
+                } in pending
+                {
+                    if settings.drop_backwards {
+                        if let (Some(running_time), Some(last_running_time)) =
+                            (running_time, state.last_output_running_time)
+                        {
+                            if running_time < last_running_time {
+                                gst::log!(CAT, obj: gst_pad, "Dropping backwards {:?}", buffer);
+                                continue;
+                            }
+                        }
+                    }
 
-                        inner.send_sticky = false;
-                        state.switched_pad = false;
+                    if needs_discont {
+                        if !buffer.flags().contains(gst::BufferFlags::DISCONT) {
+                            let buffer = buffer.make_mut();
+                            buffer.set_flags(gst::BufferFlags::DISCONT);
+                        }
+                        needs_discont = false;
                     }
-                    true
-                } else {
-                    false
+
+                    if running_time.is_some() {
+                        state.last_output_running_time = running_time;
+                    }
+
+                    buffers.push(buffer);
                 }
-            };
+            } else if settings.sync_streams && settings.cache_buffers {
+                gst::log!(CAT, obj: gst_pad, "Caching {:?}", buffer.buffer);
+                inner.cache.push_back(buffer);
+
+                // Forget about the buffers which ended before the current output position
+                if let Some(last_running_time) = state.last_output_running_time {
+                    while inner.cache.front().map_or(false, |cached| {
+                        cached
+                            .running_time_end
+                            .map_or(false, |end| end < last_running_time)
+                    }) {
+                        inner.cache.pop_front();
+                    }
+                }
+            } else {
+                gst::log!(CAT, obj: gst_pad, "Dropping {:?}", buffer.buffer);
+            }
 
-            (stickies, is_active, sync_future, switched_pad)
+            (stickies, buffers)
         };
 
-        if let Some(sync_fut) = sync_future {
-            sync_fut.await?;
-        }
-
         for event in stickies {
             inputselector.src_pad.push_event(event).await;
         }
 
-        if is_active {
-            gst::log!(CAT, obj: pad.gst_pad(), "Forwarding {:?}", buffer);
-
-            if switched_pad && !buffer.flags().contains(gst::BufferFlags::DISCONT) {
-                let buffer = buffer.make_mut();
-                buffer.set_flags(gst::BufferFlags::DISCONT);
-            }
-
-            inputselector.src_pad.push(buffer).await
-        } else {
-            Ok(gst::FlowSuccess::Ok)
+        for buffer in buffers {
+            gst::log!(CAT, obj: gst_pad, "Forwarding {:?}", buffer);
+            inputselector.src_pad.push(buffer).await?;
         }
+
+        Ok(gst::FlowSuccess::Ok)
     }
 }
 
@@ -203,14 +463,57 @@ impl PadSinkHandler for InputSelectorPadSinkHandler {
 
     fn sink_event_serialized(
         &self,
-        _pad: &PadSinkRef,
+        pad: &PadSinkRef,
         _inputselector: &InputSelector,
-        _element: &gst::Element,
+        element: &gst::Element,
         event: gst::Event,
     ) -> BoxFuture<'static, bool> {
         let this = self.clone();
+        let element = element.clone().downcast::<super::InputSelector>().unwrap();
+        let pad_weak = pad.downgrade();
 
         async move {
+            let pad = pad_weak.upgrade().expect("PadSink no longer exists");
+            let inputselector = element.imp();
+
+            match event.view() {
+                gst::EventView::FlushStop(..) => {
+                    {
+                        let mut inner = this.0.lock().unwrap();
+                        inner.flushing = false;
+                        inner.cache.clear();
+                    }
+
+                    *InputSelectorSinkPad::from_pad(pad.gst_pad())
+                        .state
+                        .lock()
+                        .unwrap() = SinkPadState::default();
+
+                    let mut state = inputselector.state.lock().unwrap();
+                    if state.active_sinkpad.as_ref() == Some(pad.gst_pad()) {
+                        state.last_output_running_time = None;
+                    }
+                }
+                gst::EventView::StreamStart(..) => {
+                    InputSelectorSinkPad::from_pad(pad.gst_pad())
+                        .state
+                        .lock()
+                        .unwrap()
+                        .eos = false;
+                }
+                gst::EventView::Eos(..) => {
+                    InputSelectorSinkPad::from_pad(pad.gst_pad())
+                        .state
+                        .lock()
+                        .unwrap()
+                        .eos = true;
+
+                    // Don't keep the other pads waiting for us
+                    inputselector.state.lock().unwrap().wake_waiters();
+                }
+                _ => (),
+            }
+
             let mut inner = this.0.lock().unwrap();
 
             // Remember the segment for later use
@@ -218,6 +521,11 @@ impl PadSinkHandler for InputSelectorPadSinkHandler {
                 inner.segment = Some(e.segment().clone());
             }
 
+            // The cached buffers don't match the new sticky events
+            if event.is_sticky() && event.type_() != gst::EventType::Tag {
+                inner.cache.clear();
+            }
+
             // We sent sticky events together with the next buffer once it becomes
             // the active pad.
             //
@@ -246,11 +554,7 @@ impl PadSinkHandler for InputSelectorPadSinkHandler {
             /* Unblock downstream */
             inputselector.src_pad.gst_pad().push_event(event.clone());
 
-            let mut inner = self.0.lock().unwrap();
-
-            if let Some(abort_handle) = inner.abort_handle.take() {
-                abort_handle.abort();
-            }
+            self.set_flushing(true);
         }
         true
     }
@@ -343,6 +647,8 @@ impl PadSrcHandler for InputSelectorPadSrcHandler {
 struct State {
     active_sinkpad: Option<gst::Pad>,
     switched_pad: bool,
+    last_output_running_time: Option<gst::ClockTime>,
+    waiters: Vec<oneshot::Sender<()>>,
 }
 
 impl Default for State {
@@ -350,6 +656,17 @@ impl Default for State {
         State {
             active_sinkpad: None,
             switched_pad: true,
+            last_output_running_time: None,
+            waiters: Vec::new(),
+        }
+    }
+}
+
+impl State {
+    /* Let the pads waiting in active-segment mode check the active pad again */
+    fn wake_waiters(&mut self) {
+        for waiter in self.waiters.drain(..) {
+            let _ = waiter.send(());
         }
     }
 }
@@ -357,7 +674,7 @@ impl Default for State {
 #[derive(Debug, Default)]
 struct Pads {
     pad_serial: u32,
-    sink_pads: HashMap<gst::Pad, PadSink>,
+    sink_pads: HashMap<gst::Pad, (PadSink, InputSelectorPadSinkHandler)>,
 }
 
 #[derive(Debug)]
@@ -377,6 +694,13 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
 });
 
 impl InputSelector {
+    fn set_flushing(&self, flushing: bool) {
+        let pads = self.pads.lock().unwrap();
+        for (_, handler) in pads.sink_pads.values() {
+            handler.set_flushing(flushing);
+        }
+    }
+
     fn unprepare(&self, element: &super::InputSelector) {
         let mut state = self.state.lock().unwrap();
         gst::debug!(CAT, obj: element, "Unpreparing");
@@ -427,6 +751,36 @@ impl ObjectImpl for InputSelector {
                     .readwrite()
                     .mutable_playing()
                     .build(),
+                glib::ParamSpecBoolean::builder("sync-streams")
+                    .nick("Sync Streams")
+                    .blurb("Synchronize inactive streams to the running time of the active stream or to the current clock")
+                    .default_value(DEFAULT_SYNC_STREAMS)
+                    .readwrite()
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecEnum::builder::<InputSelectorSyncMode>(
+                    "sync-mode",
+                    DEFAULT_SYNC_MODE,
+                )
+                .nick("Sync mode")
+                .blurb("Behavior in sync-streams mode")
+                .readwrite()
+                .mutable_ready()
+                .build(),
+                glib::ParamSpecBoolean::builder("cache-buffers")
+                    .nick("Cache Buffers")
+                    .blurb("Cache buffers on inactive pads to replay them when switching (requires sync-streams)")
+                    .default_value(DEFAULT_CACHE_BUFFERS)
+                    .readwrite()
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("drop-backwards")
+                    .nick("Drop Backwards")
+                    .blurb("Drop buffers that go backwards relative to previous output buffer pre switch")
+                    .default_value(DEFAULT_DROP_BACKWARDS)
+                    .readwrite()
+                    .mutable_playing()
+                    .build(),
             ]
         });
 
@@ -470,6 +824,7 @@ impl ObjectImpl for InputSelector {
                 } else {
                     state.active_sinkpad = None;
                 }
+                state.wake_waiters();
 
                 drop(pads);
                 drop(state);
@@ -484,6 +839,22 @@ impl ObjectImpl for InputSelector {
                     let _ = pad.push_event(gst::event::Reconfigure::new());
                 }
             }
+            "sync-streams" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.sync_streams = value.get().expect("type checked upstream");
+            }
+            "sync-mode" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.sync_mode = value.get().expect("type checked upstream");
+            }
+            "cache-buffers" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.cache_buffers = value.get().expect("type checked upstream");
+            }
+            "drop-backwards" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.drop_backwards = value.get().expect("type checked upstream");
+            }
             _ => unimplemented!(),
         }
     }
@@ -503,6 +874,22 @@ impl ObjectImpl for InputSelector {
                 let active_pad = state.active_sinkpad.clone();
                 active_pad.to_value()
             }
+            "sync-streams" => {
+                let settings = self.settings.lock().unwrap();
+                settings.sync_streams.to_value()
+            }
+            "sync-mode" => {
+                let settings = self.settings.lock().unwrap();
+                settings.sync_mode.to_value()
+            }
+            "cache-buffers" => {
+                let settings = self.settings.lock().unwrap();
+                settings.cache_buffers.to_value()
+            }
+            "drop-backwards" => {
+                let settings = self.settings.lock().unwrap();
+                settings.drop_backwards.to_value()
+            }
             _ => unimplemented!(),
         }
     }
@@ -534,11 +921,12 @@ impl ElementImpl for InputSelector {
     fn pad_templates() -> &'static [gst::PadTemplate] {
         static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
             let caps = gst::Caps::new_any();
-            let sink_pad_template = gst::PadTemplate::new(
+            let sink_pad_template = gst::PadTemplate::with_gtype(
                 "sink_%u",
                 gst::PadDirection::Sink,
                 gst::PadPresence::Request,
                 &caps,
+                super::InputSelectorSinkPad::static_type(),
             )
             .unwrap();
 
@@ -563,8 +951,15 @@ impl ElementImpl for InputSelector {
     ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
         gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
 
-        if let gst::StateChange::ReadyToNull = transition {
-            self.unprepare(element);
+        match transition {
+            gst::StateChange::ReadyToPaused => self.set_flushing(false),
+            gst::StateChange::PausedToReady => {
+                // Unblock the pads waiting for the clock or the active pad
+                self.set_flushing(true);
+                self.state.lock().unwrap().wake_waiters();
+            }
+            gst::StateChange::ReadyToNull => self.unprepare(element),
+            _ => (),
         }
 
         let mut success = self.parent_change_state(element, transition)?;
@@ -591,12 +986,16 @@ impl ElementImpl for InputSelector {
     ) -> Option<gst::Pad> {
         let mut state = self.state.lock().unwrap();
         let mut pads = self.pads.lock().unwrap();
-        let sink_pad =
-            gst::Pad::from_template(templ, Some(format!("sink_{}", pads.pad_serial).as_str()));
+        let sink_pad = gst::PadBuilder::<super::InputSelectorSinkPad>::from_template(
+            templ,
+            Some(format!("sink_{}", pads.pad_serial).as_str()),
+        )
+        .build();
         pads.pad_serial += 1;
         sink_pad.set_active(true).unwrap();
         element.add_pad(&sink_pad).unwrap();
-        let sink_pad = PadSink::new(sink_pad, InputSelectorPadSinkHandler::default());
+        let handler = InputSelectorPadSinkHandler::default();
+        let sink_pad = PadSink::new(sink_pad.upcast(), handler.clone());
         let ret = sink_pad.gst_pad().clone();
 
         if state.active_sinkpad.is_none() {
@@ -604,7 +1003,7 @@ impl ElementImpl for InputSelector {
             state.switched_pad = true;
         }
 
-        pads.sink_pads.insert(ret.clone(), sink_pad);
+        pads.sink_pads.insert(ret.clone(), (sink_pad, handler));
         drop(pads);
         drop(state);
 
@@ -615,11 +1014,20 @@ impl ElementImpl for InputSelector {
 
     fn release_pad(&self, element: &Self::Type, pad: &gst::Pad) {
         let mut pads = self.pads.lock().unwrap();
-        let sink_pad = pads.sink_pads.remove(pad).unwrap();
+        let (sink_pad, handler) = pads.sink_pads.remove(pad).unwrap();
+        handler.set_flushing(true);
         drop(sink_pad);
         element.remove_pad(pad).unwrap();
         drop(pads);
 
+        // Don't let the other pads wait for a pad which is gone
+        InputSelectorSinkPad::from_pad(pad)
+            .state
+            .lock()
+            .unwrap()
+            .eos = true;
+        self.state.lock().unwrap().wake_waiters();
+
         let _ = element.post_message(gst::message::Latency::builder().src(element).build());
     }
 
@@ -29,7 +29,7 @@ use gst_rtp::RTPBuffer;
 use once_cell::sync::Lazy;
 
 use std::cmp::{max, min, Ordering};
-use std::collections::{BTreeSet, VecDeque};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::mem;
 use std::sync::Arc;
 use std::sync::Mutex as StdMutex;
@@ -44,15 +44,29 @@ const DEFAULT_LATENCY: gst::ClockTime = gst::ClockTime::from_mseconds(200);
 const DEFAULT_DO_LOST: bool = false;
 const DEFAULT_MAX_DROPOUT_TIME: u32 = 60000;
 const DEFAULT_MAX_MISORDER_TIME: u32 = 2000;
+const DEFAULT_DO_RETRANSMISSION: bool = false;
+const DEFAULT_RTX_DELAY: i32 = -1;
+const DEFAULT_RTX_RETRY_TIMEOUT: i32 = -1;
+const DEFAULT_RTX_RETRY_PERIOD: i32 = -1;
+const DEFAULT_RTX_MAX_RETRIES: i32 = -1;
 const DEFAULT_CONTEXT: &str = "";
 const DEFAULT_CONTEXT_WAIT: gst::ClockTime = gst::ClockTime::ZERO;
 
+// Used when the retransmission delay and timeout can't be derived yet
+const DEFAULT_AUTO_RTX_DELAY: gst::ClockTime = gst::ClockTime::from_mseconds(20);
+const DEFAULT_AUTO_RTX_TIMEOUT: gst::ClockTime = gst::ClockTime::from_mseconds(40);
+
 #[derive(Debug, Clone)]
 struct Settings {
     latency: gst::ClockTime,
     do_lost: bool,
     max_dropout_time: u32,
     max_misorder_time: u32,
+    do_retransmission: bool,
+    rtx_delay: i32,
+    rtx_retry_timeout: i32,
+    rtx_retry_period: i32,
+    rtx_max_retries: i32,
     context: String,
     context_wait: gst::ClockTime,
 }
@@ -64,6 +78,11 @@ impl Default for Settings {
             do_lost: DEFAULT_DO_LOST,
             max_dropout_time: DEFAULT_MAX_DROPOUT_TIME,
             max_misorder_time: DEFAULT_MAX_MISORDER_TIME,
+            do_retransmission: DEFAULT_DO_RETRANSMISSION,
+            rtx_delay: DEFAULT_RTX_DELAY,
+            rtx_retry_timeout: DEFAULT_RTX_RETRY_TIMEOUT,
+            rtx_retry_period: DEFAULT_RTX_RETRY_PERIOD,
+            rtx_max_retries: DEFAULT_RTX_MAX_RETRIES,
             context: DEFAULT_CONTEXT.into(),
             context_wait: DEFAULT_CONTEXT_WAIT,
         }
@@ -106,6 +125,17 @@ impl PartialEq for GapPacket {
     }
 }
 
+/// A packet which is expected but wasn't received yet.
+#[derive(Debug)]
+struct RtxTimer {
+    /// When the packet was expected
+    expected_pts: gst::ClockTime,
+    /// When to send the next retransmission request
+    timeout: gst::ClockTime,
+    num_retries: u32,
+    last_request: Option<gst::ClockTime>,
+}
+
 struct SinkHandlerInner {
     packet_rate_ctx: RTPPacketRateCtx,
     ips_rtptime: Option<u32>,
@@ -117,6 +147,10 @@ struct SinkHandlerInner {
 
     last_in_seqnum: Option<u16>,
     last_rtptime: Option<u32>,
+
+    // Highest seqnum received so far and its pts, used to detect missing packets
+    rtx_last_seqnum: Option<u16>,
+    rtx_last_pts: Option<gst::ClockTime>,
 }
 
 impl Default for SinkHandlerInner {
@@ -129,6 +163,8 @@ impl Default for SinkHandlerInner {
             last_pt: None,
             last_in_seqnum: None,
             last_rtptime: None,
+            rtx_last_seqnum: None,
+            rtx_last_pts: None,
         }
     }
 }
@@ -167,6 +203,10 @@ impl SinkHandler {
         inner.ips_rtptime = None;
         inner.ips_pts = None;
 
+        inner.rtx_last_seqnum = None;
+        inner.rtx_last_pts = None;
+        state.rtx_timers.clear();
+
         mem::take(&mut inner.gap_packets)
     }
 
@@ -308,6 +348,126 @@ impl SinkHandler {
         reset
     }
 
+    // Returns `false` if the retransmitted packet wasn't requested
+    fn handle_rtx_packet(
+        &self,
+        state: &mut State,
+        element: &super::JitterBuffer,
+        seq: u16,
+    ) -> bool {
+        let timer = match state.rtx_timers.get(&seq) {
+            Some(timer) if timer.last_request.is_some() => state.rtx_timers.remove(&seq).unwrap(),
+            _ => return false,
+        };
+
+        state.stats.num_rtx_success += 1;
+
+        // With more than one request, we can't tell which one this packet answers
+        if timer.num_retries == 1 {
+            let rtt = element
+                .current_running_time()
+                .opt_checked_sub(timer.last_request)
+                .ok()
+                .flatten();
+
+            if let Some(rtt) = rtt {
+                state.avg_rtx_rtt = Some(match state.avg_rtx_rtt {
+                    Some(avg_rtx_rtt) => (rtt + 7 * avg_rtx_rtt) / 8,
+                    None => rtt,
+                });
+
+                gst::debug!(
+                    CAT,
+                    obj: element,
+                    "Retransmission of {} took {}, average RTT {}",
+                    seq,
+                    rtt,
+                    state.avg_rtx_rtt.display(),
+                );
+            }
+        }
+
+        true
+    }
+
+    fn schedule_rtx_timers(
+        &self,
+        inner: &mut SinkHandlerInner,
+        state: &mut State,
+        element: &super::JitterBuffer,
+        seq: u16,
+        pts: gst::ClockTime,
+        rtx_delay: i32,
+    ) {
+        // The packet is no longer expected
+        state.rtx_timers.remove(&seq);
+
+        let (last_seq, last_pts) = match (inner.rtx_last_seqnum, inner.rtx_last_pts) {
+            (Some(last_seq), Some(last_pts)) => (last_seq, last_pts),
+            _ => {
+                inner.rtx_last_seqnum = Some(seq);
+                inner.rtx_last_pts = Some(pts);
+                return;
+            }
+        };
+
+        let gap = gst_rtp::compare_seqnum(last_seq, seq);
+        if gap <= 0 {
+            // Reordered packet
+            return;
+        }
+
+        let rtx_delay = if rtx_delay >= 0 {
+            gst::ClockTime::from_mseconds(rtx_delay as u64)
+        } else if state.packet_spacing.is_zero() {
+            DEFAULT_AUTO_RTX_DELAY
+        } else {
+            state.packet_spacing / 2
+        };
+
+        // Packets between the previous highest seqnum and this one are missing
+        let spacing = pts.saturating_sub(last_pts) / gap as u64;
+        for idx in 1..gap as u16 {
+            let lost_seq = last_seq.wrapping_add(idx);
+            let expected_pts = last_pts + idx as u64 * spacing;
+
+            state.rtx_timers.entry(lost_seq).or_insert_with(|| {
+                gst::debug!(
+                    CAT,
+                    obj: element,
+                    "Packet {} missing, expected at {}",
+                    lost_seq,
+                    expected_pts
+                );
+
+                RtxTimer {
+                    expected_pts,
+                    timeout: expected_pts + rtx_delay,
+                    num_retries: 0,
+                    last_request: None,
+                }
+            });
+        }
+
+        // Also expect the next packet so that we don't miss losses
+        // at the end of a burst
+        if !state.packet_spacing.is_zero() {
+            let expected_pts = pts + state.packet_spacing;
+            state
+                .rtx_timers
+                .entry(seq.wrapping_add(1))
+                .or_insert(RtxTimer {
+                    expected_pts,
+                    timeout: expected_pts + rtx_delay,
+                    num_retries: 0,
+                    last_request: None,
+                });
+        }
+
+        inner.rtx_last_seqnum = Some(seq);
+        inner.rtx_last_pts = Some(pts);
+    }
+
     fn store(
         &self,
         inner: &mut SinkHandlerInner,
@@ -318,9 +478,14 @@ impl SinkHandler {
         let jb = element.imp();
         let mut state = jb.state.lock().unwrap();
 
-        let (max_misorder_time, max_dropout_time) = {
+        let (max_misorder_time, max_dropout_time, do_retransmission, rtx_delay) = {
             let settings = jb.settings.lock().unwrap();
-            (settings.max_misorder_time, settings.max_dropout_time)
+            (
+                settings.max_misorder_time,
+                settings.max_dropout_time,
+                settings.do_retransmission,
+                settings.rtx_delay,
+            )
         };
 
         let (seq, rtptime, pt) = {
@@ -333,6 +498,7 @@ impl SinkHandler {
             )
         };
 
+        let is_rtx = buffer.flags().contains(gst::BufferFlags::RETRANSMISSION);
         let mut pts = buffer.pts();
         let mut dts = buffer.dts();
         let mut estimated_dts = false;
@@ -340,10 +506,11 @@ impl SinkHandler {
         gst::log!(
             CAT,
             obj: element,
-            "Storing buffer, seq: {}, rtptime: {}, pt: {}",
+            "Storing buffer, seq: {}, rtptime: {}, pt: {}, rtx: {}",
             seq,
             rtptime,
-            pt
+            pt,
+            is_rtx
         );
 
         if dts.is_none() {
@@ -395,14 +562,26 @@ impl SinkHandler {
             }
         };
 
-        inner.packet_rate_ctx.update(seq, rtptime);
+        if is_rtx && !self.handle_rtx_packet(&mut state, element, seq) {
+            gst::debug!(
+                CAT,
+                obj: element,
+                "Unsolicited retransmission of {}, dropping",
+                seq
+            );
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        if !is_rtx {
+            inner.packet_rate_ctx.update(seq, rtptime);
+        }
 
         let max_dropout = inner.packet_rate_ctx.max_dropout(max_dropout_time as i32);
         let max_misorder = inner.packet_rate_ctx.max_misorder(max_misorder_time as i32);
 
         pts = state
             .jbuf
-            .calculate_pts(dts, estimated_dts, rtptime, element.base_time(), 0, false);
+            .calculate_pts(dts, estimated_dts, rtptime, 0, is_rtx);
 
         if pts.is_none() {
             gst::debug!(
@@ -414,7 +593,9 @@ impl SinkHandler {
             return Ok(gst::FlowSuccess::Ok);
         }
 
-        if let Some(last_in_seqnum) = inner.last_in_seqnum {
+        // Retransmitted packets are expected to be out of order
+        let last_in_seqnum = inner.last_in_seqnum.filter(|_| !is_rtx);
+        if let Some(last_in_seqnum) = last_in_seqnum {
             let gap = gst_rtp::compare_seqnum(last_in_seqnum as u16, seq);
             if gap == 1 {
                 self.calculate_packet_spacing(inner, &mut state, rtptime, pts);
@@ -445,7 +626,15 @@ impl SinkHandler {
             }
         }
 
-        inner.last_in_seqnum = Some(seq);
+        if !is_rtx {
+            inner.last_in_seqnum = Some(seq);
+
+            if do_retransmission {
+                if let Some(pts) = pts {
+                    self.schedule_rtx_timers(inner, &mut state, element, seq, pts, rtx_delay);
+                }
+            }
+        }
 
         let jb_item = if estimated_dts {
             RTPJitterBufferItem::new(buffer, gst::ClockTime::NONE, pts, Some(seq), rtptime)
@@ -821,6 +1010,13 @@ impl SrcHandler {
             }
             state.last_popped_seqnum = seq;
 
+            if let Some(seq) = seq {
+                // Packets up to this one can't be retransmitted in time anymore
+                state
+                    .rtx_timers
+                    .retain(|timer_seq, _| gst_rtp::compare_seqnum(seq, *timer_seq) > 0);
+            }
+
             state.stats.num_pushed += 1;
 
             (lost_events, buffer, seq)
@@ -836,6 +1032,112 @@ impl SrcHandler {
         jb.src_pad.push(buffer).await
     }
 
+    fn take_rtx_requests(
+        &self,
+        element: &super::JitterBuffer,
+        state: &mut State,
+        latency: gst::ClockTime,
+    ) -> Vec<gst::Event> {
+        let now = match element.current_running_time() {
+            Some(now) => now,
+            None => return vec![],
+        };
+
+        let (rtx_retry_timeout, rtx_retry_period, rtx_max_retries) = {
+            let jb = element.imp();
+            let settings = jb.settings.lock().unwrap();
+            (
+                settings.rtx_retry_timeout,
+                settings.rtx_retry_period,
+                settings.rtx_max_retries,
+            )
+        };
+
+        let retry_timeout = if rtx_retry_timeout >= 0 {
+            gst::ClockTime::from_mseconds(rtx_retry_timeout as u64)
+        } else {
+            state
+                .avg_rtx_rtt
+                .map_or(DEFAULT_AUTO_RTX_TIMEOUT, |avg_rtx_rtt| 2 * avg_rtx_rtt)
+        };
+
+        let retry_period = if rtx_retry_period >= 0 {
+            gst::ClockTime::from_mseconds(rtx_retry_period as u64)
+        } else {
+            latency.saturating_sub(retry_timeout)
+        };
+
+        let mut events = vec![];
+        let mut expired = vec![];
+
+        for (seq, timer) in state.rtx_timers.iter_mut() {
+            if timer.timeout > now {
+                continue;
+            }
+
+            let out_of_retries =
+                rtx_max_retries >= 0 && timer.num_retries >= rtx_max_retries as u32;
+            // Don't ask for a packet which can't arrive before its deadline
+            let too_late = now >= timer.expected_pts + retry_period
+                || state.avg_rtx_rtt.map_or(false, |avg_rtx_rtt| {
+                    now + avg_rtx_rtt > timer.expected_pts + latency
+                });
+
+            if out_of_retries || too_late {
+                expired.push(*seq);
+                continue;
+            }
+
+            let s = gst::Structure::builder("GstRTPRetransmissionRequest")
+                .field("seqnum", *seq as u32)
+                .field("running-time", timer.expected_pts.nseconds())
+                .field(
+                    "delay",
+                    now.saturating_sub(timer.expected_pts).mseconds() as u32,
+                )
+                .field("retry", timer.num_retries)
+                .field("frequency", retry_timeout.mseconds() as u32)
+                .field("period", retry_period.mseconds() as u32)
+                .field("deadline", latency.mseconds() as u32)
+                .field("packet-spacing", state.packet_spacing.nseconds())
+                .field(
+                    "avg-rtt",
+                    state
+                        .avg_rtx_rtt
+                        .map_or(0, |avg_rtx_rtt| avg_rtx_rtt.mseconds() as u32),
+                )
+                .build();
+
+            events.push(gst::event::CustomUpstream::new(s));
+
+            timer.num_retries += 1;
+            timer.last_request = Some(now);
+            timer.timeout = now + retry_timeout;
+
+            state.stats.num_rtx_requests += 1;
+        }
+
+        for seq in expired {
+            gst::debug!(CAT, obj: element, "Giving up on retransmission of {}", seq);
+            state.rtx_timers.remove(&seq);
+        }
+
+        events
+    }
+
+    fn next_pop_wakeup(
+        &self,
+        state: &State,
+        latency: gst::ClockTime,
+        context_wait: gst::ClockTime,
+    ) -> Option<gst::ClockTime> {
+        state.earliest_pts.map(|earliest_pts| {
+            (earliest_pts + latency)
+                .saturating_sub(state.packet_spacing)
+                .saturating_sub(context_wait / 2)
+        })
+    }
+
     fn next_wakeup(
         &self,
         element: &super::JitterBuffer,
@@ -864,17 +1166,20 @@ impl SrcHandler {
             return (now, Some((now, Duration::ZERO)));
         }
 
-        if state.earliest_pts.is_none() {
-            return (now, None);
-        }
-
-        let next_wakeup = state.earliest_pts.map(|earliest_pts| {
-            (earliest_pts + latency)
-                .saturating_sub(state.packet_spacing)
-                .saturating_sub(context_wait / 2)
-        });
+        let next_pop = self.next_pop_wakeup(state, latency, context_wait);
+        let next_rtx = state
+            .rtx_timers
+            .values()
+            .map(|timer| timer.timeout.saturating_sub(context_wait / 2))
+            .min();
+
+        let next_wakeup = match (next_pop, next_rtx) {
+            (Some(next_pop), Some(next_rtx)) => min(next_pop, next_rtx),
+            (Some(next_wakeup), None) | (None, Some(next_wakeup)) => next_wakeup,
+            (None, None) => return (now, None),
+        };
 
-        let delay = next_wakeup
+        let delay = Some(next_wakeup)
             .opt_saturating_sub(now)
             .unwrap_or(gst::ClockTime::ZERO);
 
@@ -882,11 +1187,11 @@ impl SrcHandler {
             CAT,
             obj: element,
             "Next wakeup at {} with delay {}",
-            next_wakeup.display(),
+            next_wakeup,
             delay
         );
 
-        (now, Some((next_wakeup, delay.into())))
+        (now, Some((Some(next_wakeup), delay.into())))
     }
 }
 
@@ -984,6 +1289,8 @@ struct Stats {
     num_pushed: u64,
     num_lost: u64,
     num_late: u64,
+    num_rtx_requests: u64,
+    num_rtx_success: u64,
 }
 
 // Shared state between element, sink and source pad
@@ -1010,6 +1317,9 @@ struct State {
     earliest_pts: Option<gst::ClockTime>,
     earliest_seqnum: Option<u16>,
 
+    rtx_timers: HashMap<u16, RtxTimer>,
+    avg_rtx_rtt: Option<gst::ClockTime>,
+
     wait_handle: Option<(Option<gst::ClockTime>, AbortHandle)>,
 }
 
@@ -1038,6 +1348,9 @@ impl Default for State {
             earliest_pts: None,
             earliest_seqnum: None,
 
+            rtx_timers: HashMap::new(),
+            avg_rtx_rtt: None,
+
             wait_handle: None,
         }
     }
@@ -1076,7 +1389,7 @@ impl TaskImpl for JitterBufferTask {
             let jb = self.element.imp();
 
             let latency = jb.settings.lock().unwrap().latency;
-            let state = State::default();
+            let mut state = State::default();
 
             state.jbuf.set_delay(latency);
             *jb.state.lock().unwrap() = state;
@@ -1148,16 +1461,22 @@ impl TaskImpl for JitterBufferTask {
                     }
                 }
 
+                let rtx_requests = {
+                    let mut state = jb.state.lock().unwrap();
+                    self.src_pad_handler
+                        .take_rtx_requests(&self.element, &mut state, latency)
+                };
+
+                for event in rtx_requests {
+                    gst::debug!(CAT, obj: &self.element, "Requesting {:?}", event);
+                    jb.sink_pad.gst_pad().push_event(event);
+                }
+
                 let (head_pts, head_seq) = {
                     let state = jb.state.lock().unwrap();
                     //
                     // Check earliest PTS as we have just taken the lock
-                    let (now, next_wakeup) = self.src_pad_handler.next_wakeup(
-                        &self.element,
-                        &state,
-                        latency,
-                        context_wait,
-                    );
+                    let now = self.element.current_running_time();
 
                     gst::debug!(
                         CAT,
@@ -1167,13 +1486,18 @@ impl TaskImpl for JitterBufferTask {
                         state.earliest_pts.display()
                     );
 
-                    if let Some((next_wakeup, _)) = next_wakeup {
-                        if next_wakeup.opt_gt(now).unwrap_or(false) {
-                            // Reschedule and wait a bit longer in the next iteration
-                            return Ok(());
+                    if !state.eos {
+                        match self
+                            .src_pad_handler
+                            .next_pop_wakeup(&state, latency, context_wait)
+                        {
+                            Some(next_pop) if Some(next_pop).opt_gt(now).unwrap_or(false) => {
+                                // Reschedule and wait a bit longer in the next iteration
+                                return Ok(());
+                            }
+                            None => return Ok(()),
+                            _ => (),
                         }
-                    } else {
-                        return Ok(());
                     }
 
                     let (head_pts, head_seq) = state.jbuf.peek();
@@ -1194,22 +1518,20 @@ impl TaskImpl for JitterBufferTask {
                         state.earliest_seqnum = earliest_seqnum;
                     }
 
-                    if res.is_ok() {
+                    if res.is_ok() && !state.eos {
                         // Return and reschedule if the next packet would be in the future
                         // Check earliest PTS as we have just taken the lock
-                        let (now, next_wakeup) = self.src_pad_handler.next_wakeup(
-                            &self.element,
-                            &state,
-                            latency,
-                            context_wait,
-                        );
-                        if let Some((Some(next_wakeup), _)) = next_wakeup {
-                            if now.map_or(false, |now| next_wakeup > now) {
+                        let now = self.element.current_running_time();
+                        match self
+                            .src_pad_handler
+                            .next_pop_wakeup(&state, latency, context_wait)
+                        {
+                            Some(next_pop) if now.map_or(false, |now| next_pop > now) => {
                                 // Reschedule and wait a bit longer in the next iteration
                                 return Ok(());
                             }
-                        } else {
-                            return Ok(());
+                            None => return Ok(()),
+                            _ => (),
                         }
                     }
                 }
@@ -1391,6 +1713,35 @@ impl ObjectImpl for JitterBuffer {
                     .blurb("The maximum time (milliseconds) of misordered packets tolerated.")
                     .default_value(DEFAULT_MAX_MISORDER_TIME)
                     .build(),
+                glib::ParamSpecBoolean::builder("do-retransmission")
+                    .nick("Do Retransmission")
+                    .blurb("Send retransmission events upstream when a packet is late")
+                    .default_value(DEFAULT_DO_RETRANSMISSION)
+                    .build(),
+                glib::ParamSpecInt::builder("rtx-delay")
+                    .nick("RTX Delay")
+                    .blurb("Extra time in ms to wait before sending retransmission event (-1 automatic)")
+                    .minimum(-1)
+                    .default_value(DEFAULT_RTX_DELAY)
+                    .build(),
+                glib::ParamSpecInt::builder("rtx-retry-timeout")
+                    .nick("RTX Retry Timeout")
+                    .blurb("Retry sending a transmission event after this timeout in ms (-1 automatic)")
+                    .minimum(-1)
+                    .default_value(DEFAULT_RTX_RETRY_TIMEOUT)
+                    .build(),
+                glib::ParamSpecInt::builder("rtx-retry-period")
+                    .nick("RTX Retry Period")
+                    .blurb("Try to get a retransmission for this many ms (-1 automatic)")
+                    .minimum(-1)
+                    .default_value(DEFAULT_RTX_RETRY_PERIOD)
+                    .build(),
+                glib::ParamSpecInt::builder("rtx-max-retries")
+                    .nick("RTX Max Retries")
+                    .blurb("The maximum number of retries to request a retransmission (-1 not limited)")
+                    .minimum(-1)
+                    .default_value(DEFAULT_RTX_MAX_RETRIES)
+                    .build(),
                 glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
                     .nick("Statistics")
                     .blurb("Various statistics")
@@ -1441,7 +1792,7 @@ impl ObjectImpl for JitterBuffer {
                     settings.latency
                 };
 
-                let state = self.state.lock().unwrap();
+                let mut state = self.state.lock().unwrap();
                 state.jbuf.set_delay(latency);
 
                 let _ = obj.post_message(gst::message::Latency::builder().src(obj).build());
@@ -1458,6 +1809,26 @@ impl ObjectImpl for JitterBuffer {
                 let mut settings = self.settings.lock().unwrap();
                 settings.max_misorder_time = value.get().expect("type checked upstream");
             }
+            "do-retransmission" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.do_retransmission = value.get().expect("type checked upstream");
+            }
+            "rtx-delay" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.rtx_delay = value.get().expect("type checked upstream");
+            }
+            "rtx-retry-timeout" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.rtx_retry_timeout = value.get().expect("type checked upstream");
+            }
+            "rtx-retry-period" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.rtx_retry_period = value.get().expect("type checked upstream");
+            }
+            "rtx-max-retries" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.rtx_max_retries = value.get().expect("type checked upstream");
+            }
             "context" => {
                 let mut settings = self.settings.lock().unwrap();
                 settings.context = value
@@ -1493,12 +1864,38 @@ impl ObjectImpl for JitterBuffer {
                 let settings = self.settings.lock().unwrap();
                 settings.max_misorder_time.to_value()
             }
+            "do-retransmission" => {
+                let settings = self.settings.lock().unwrap();
+                settings.do_retransmission.to_value()
+            }
+            "rtx-delay" => {
+                let settings = self.settings.lock().unwrap();
+                settings.rtx_delay.to_value()
+            }
+            "rtx-retry-timeout" => {
+                let settings = self.settings.lock().unwrap();
+                settings.rtx_retry_timeout.to_value()
+            }
+            "rtx-retry-period" => {
+                let settings = self.settings.lock().unwrap();
+                settings.rtx_retry_period.to_value()
+            }
+            "rtx-max-retries" => {
+                let settings = self.settings.lock().unwrap();
+                settings.rtx_max_retries.to_value()
+            }
             "stats" => {
                 let state = self.state.lock().unwrap();
                 let s = gst::Structure::builder("application/x-rtp-jitterbuffer-stats")
                     .field("num-pushed", state.stats.num_pushed)
                     .field("num-lost", state.stats.num_lost)
                     .field("num-late", state.stats.num_late)
+                    .field("num-rtx-requests", state.stats.num_rtx_requests)
+                    .field("num-rtx-success", state.stats.num_rtx_success)
+                    .field(
+                        "avg-rtx-rtt",
+                        state.avg_rtx_rtt.map_or(0, gst::ClockTime::nseconds),
+                    )
                     .build();
                 s.to_value()
             }
@@ -17,55 +17,73 @@
 //
 // SPDX-License-Identifier: LGPL-2.1-or-later
 
-use super::ffi;
+use gst::prelude::*;
 
-use std::ptr;
+use once_cell::sync::Lazy;
 
-use glib::translate::*;
-use gst::glib;
+use std::cmp::max;
+use std::collections::VecDeque;
 
-use std::mem;
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ts-rtpjitterbuffer",
+        gst::DebugColorFlags::empty(),
+        Some("Thread-sharing RTP jitterbuffer"),
+    )
+});
 
-glib::wrapper! {
-    pub struct RTPJitterBuffer(Object<ffi::RTPJitterBuffer>);
+const MAX_WINDOW: usize = 512;
+const MAX_TIME: gst::ClockTime = gst::ClockTime::from_seconds(2);
 
-    match fn {
-        type_ => || ffi::rtp_jitter_buffer_get_type(),
-    }
-}
+const RTP_DEF_DROPOUT: u32 = 3000;
+const RTP_MIN_DROPOUT: u32 = 30;
+const RTP_DEF_MISORDER: u32 = 100;
+const RTP_MIN_MISORDER: u32 = 10;
 
-// SAFETY: We ensure that we never get another reference to the jitterbuffer
-unsafe impl Send for RTPJitterBuffer {}
+/// Extends the 32 bits RTP `timestamp` to 64 bits based on the previously
+/// extended timestamp `ext`, taking care of wrap-arounds in both directions.
+///
+/// `ext` is updated with the result, except when the timestamp went backwards
+/// before any wrap-around happened, in which case `0` is returned.
+pub fn ext_timestamp(ext: &mut Option<u64>, timestamp: u32) -> u64 {
+    const WRAP: u64 = 1 << 32;
 
-impl IntoGlib for RTPJitterBufferMode {
-    type GlibType = ffi::RTPJitterBufferMode;
+    let result = match *ext {
+        None => timestamp as u64,
+        Some(ext) => {
+            let mut result = timestamp as u64 + (ext & !(WRAP - 1));
 
-    fn into_glib(self) -> ffi::RTPJitterBufferMode {
-        match self {
-            RTPJitterBufferMode::None => ffi::RTP_JITTER_BUFFER_MODE_NONE,
-            RTPJitterBufferMode::Slave => ffi::RTP_JITTER_BUFFER_MODE_SLAVE,
-            RTPJitterBufferMode::Buffer => ffi::RTP_JITTER_BUFFER_MODE_BUFFER,
-            RTPJitterBufferMode::Synced => ffi::RTP_JITTER_BUFFER_MODE_SYNCED,
-            RTPJitterBufferMode::__Unknown(value) => value,
-        }
-    }
-}
+            if result < ext {
+                if ext - result > i32::MAX as u64 {
+                    // Wrapped forward
+                    result += WRAP;
+                }
+            } else if result - ext > i32::MAX as u64 {
+                // Wrapped backward
+                if result < WRAP {
+                    gst::debug!(CAT, "Cannot unwrap timestamp {}", timestamp);
+                    return 0;
+                }
+                result -= WRAP;
+            }
 
-impl FromGlib<ffi::RTPJitterBufferMode> for RTPJitterBufferMode {
-    unsafe fn from_glib(value: ffi::RTPJitterBufferMode) -> Self {
-        match value {
-            0 => RTPJitterBufferMode::None,
-            1 => RTPJitterBufferMode::Slave,
-            2 => RTPJitterBufferMode::Buffer,
-            4 => RTPJitterBufferMode::Synced,
-            value => RTPJitterBufferMode::__Unknown(value),
+            result
         }
-    }
-}
+    };
+
+    *ext = Some(result);
 
-pub struct RTPJitterBufferItem(Option<ptr::NonNull<ffi::RTPJitterBufferItem>>);
+    result
+}
 
-unsafe impl Send for RTPJitterBufferItem {}
+#[derive(Debug)]
+pub struct RTPJitterBufferItem {
+    buffer: gst::Buffer,
+    dts: Option<gst::ClockTime>,
+    pts: Option<gst::ClockTime>,
+    seqnum: Option<u16>,
+    rtptime: u32,
+}
 
 impl RTPJitterBufferItem {
     pub fn new(
@@ -75,128 +93,132 @@ impl RTPJitterBufferItem {
         seqnum: Option<u16>,
         rtptime: u32,
     ) -> RTPJitterBufferItem {
-        unsafe {
-            let ptr = ptr::NonNull::new(glib::ffi::g_slice_alloc0(mem::size_of::<
-                ffi::RTPJitterBufferItem,
-            >()) as *mut ffi::RTPJitterBufferItem)
-            .expect("Allocation failed");
-            ptr::write(
-                ptr.as_ptr(),
-                ffi::RTPJitterBufferItem {
-                    data: buffer.into_glib_ptr() as *mut _,
-                    next: ptr::null_mut(),
-                    prev: ptr::null_mut(),
-                    r#type: 0,
-                    dts: dts.into().into_glib(),
-                    pts: pts.into().into_glib(),
-                    seqnum: seqnum.map(|s| s as u32).unwrap_or(std::u32::MAX),
-                    count: 1,
-                    rtptime,
-                },
-            );
-
-            RTPJitterBufferItem(Some(ptr))
+        RTPJitterBufferItem {
+            buffer,
+            dts: dts.into(),
+            pts: pts.into(),
+            seqnum,
+            rtptime,
         }
     }
 
-    pub fn into_buffer(mut self) -> gst::Buffer {
-        unsafe {
-            let item = self.0.take().expect("Invalid wrapper");
-            let buf = item.as_ref().data as *mut gst::ffi::GstBuffer;
-            glib::ffi::g_slice_free1(
-                mem::size_of::<ffi::RTPJitterBufferItem>(),
-                item.as_ptr() as *mut _,
-            );
-            from_glib_full(buf)
-        }
+    pub fn into_buffer(self) -> gst::Buffer {
+        self.buffer
     }
 
     pub fn dts(&self) -> Option<gst::ClockTime> {
-        unsafe {
-            let item = self.0.as_ref().expect("Invalid wrapper");
-            if item.as_ref().dts == gst::ffi::GST_CLOCK_TIME_NONE {
-                None
-            } else {
-                Some(gst::ClockTime::from_nseconds(item.as_ref().dts))
-            }
-        }
+        self.dts
     }
 
     pub fn pts(&self) -> Option<gst::ClockTime> {
-        unsafe {
-            let item = self.0.as_ref().expect("Invalid wrapper");
-            if item.as_ref().pts == gst::ffi::GST_CLOCK_TIME_NONE {
-                None
-            } else {
-                Some(gst::ClockTime::from_nseconds(item.as_ref().pts))
-            }
-        }
+        self.pts
     }
 
     pub fn seqnum(&self) -> Option<u16> {
-        unsafe {
-            let item = self.0.as_ref().expect("Invalid wrapper");
-            if item.as_ref().seqnum == std::u32::MAX {
-                None
-            } else {
-                Some(item.as_ref().seqnum as u16)
-            }
-        }
+        self.seqnum
     }
 
     #[allow(dead_code)]
     pub fn rtptime(&self) -> u32 {
-        unsafe {
-            let item = self.0.as_ref().expect("Invalid wrapper");
-            item.as_ref().rtptime
-        }
+        self.rtptime
     }
-}
-
-impl Drop for RTPJitterBufferItem {
-    fn drop(&mut self) {
-        unsafe {
-            if let Some(ref item) = self.0 {
-                if !item.as_ref().data.is_null() {
-                    gst::ffi::gst_mini_object_unref(item.as_ref().data as *mut _);
-                }
 
-                glib::ffi::g_slice_free1(
-                    mem::size_of::<ffi::RTPJitterBufferItem>(),
-                    item.as_ptr() as *mut _,
-                );
-            }
-        }
+    /// Returns the DTS if any, the PTS otherwise.
+    fn timestamp(&self) -> Option<gst::ClockTime> {
+        self.dts.or(self.pts)
     }
 }
 
-pub struct RTPPacketRateCtx(Box<ffi::RTPPacketRateCtx>);
-
-unsafe impl Send for RTPPacketRateCtx {}
+/// Keeps track of the average packet rate so that the dropout and misorder
+/// thresholds can be expressed in time.
+#[derive(Debug)]
+pub struct RTPPacketRateCtx {
+    probed: bool,
+    clock_rate: i32,
+    last_seqnum: u16,
+    last_ts: Option<u64>,
+    avg_packet_rate: Option<u32>,
+}
 
 impl RTPPacketRateCtx {
     pub fn new() -> RTPPacketRateCtx {
-        unsafe {
-            let mut ptr = std::mem::MaybeUninit::uninit();
-            ffi::gst_rtp_packet_rate_ctx_reset(ptr.as_mut_ptr(), -1);
-            RTPPacketRateCtx(Box::new(ptr.assume_init()))
+        RTPPacketRateCtx {
+            probed: false,
+            clock_rate: -1,
+            last_seqnum: 0,
+            last_ts: None,
+            avg_packet_rate: None,
         }
     }
 
     pub fn reset(&mut self, clock_rate: i32) {
-        unsafe { ffi::gst_rtp_packet_rate_ctx_reset(&mut *self.0, clock_rate) }
+        self.clock_rate = clock_rate;
+        self.probed = false;
+        self.avg_packet_rate = None;
+        self.last_ts = None;
     }
 
-    pub fn update(&mut self, seqnum: u16, ts: u32) -> u32 {
-        unsafe { ffi::gst_rtp_packet_rate_ctx_update(&mut *self.0, seqnum, ts) }
+    pub fn update(&mut self, seqnum: u16, ts: u32) -> Option<u32> {
+        if self.clock_rate <= 0 {
+            return self.avg_packet_rate;
+        }
+
+        let mut new_ts = self.last_ts;
+        let new_ts = ext_timestamp(&mut new_ts, ts);
+
+        if !self.probed {
+            self.probed = true;
+        } else {
+            let diff_seqnum = gst_rtp::compare_seqnum(self.last_seqnum, seqnum);
+
+            // Only consecutive packets with increasing timestamps are taken into account
+            if diff_seqnum == 1 && self.last_ts.map_or(false, |last_ts| new_ts > last_ts) {
+                let diff_ts = (new_ts - self.last_ts.unwrap())
+                    .mul_div_floor(*gst::ClockTime::SECOND, self.clock_rate as u64)
+                    .unwrap();
+
+                if diff_ts > 0 {
+                    let new_packet_rate = (diff_seqnum as u64)
+                        .mul_div_floor(*gst::ClockTime::SECOND, diff_ts)
+                        .unwrap()
+                        .min(u32::MAX as u64) as u32;
+
+                    // Higher packet rates "win": the average goes up fast on
+                    // bursts, but it goes down slowly. Round up the new average.
+                    self.avg_packet_rate = Some(match self.avg_packet_rate {
+                        None => new_packet_rate,
+                        Some(avg) if avg > new_packet_rate => {
+                            ((7 * avg as u64 + new_packet_rate as u64 + 7) / 8) as u32
+                        }
+                        Some(avg) => ((avg as u64 + new_packet_rate as u64 + 1) / 2) as u32,
+                    });
+                }
+            }
+        }
+
+        self.last_seqnum = seqnum;
+        self.last_ts = Some(new_ts);
+
+        self.avg_packet_rate
+    }
+
+    fn packets_for(&self, time_ms: i32) -> Option<u32> {
+        if time_ms <= 0 || !self.probed {
+            return None;
+        }
+
+        self.avg_packet_rate
+            .map(|rate| (rate as u64 * time_ms as u64 / 1000).min(u32::MAX as u64) as u32)
     }
 
-    pub fn max_dropout(&mut self, time_ms: i32) -> u32 {
-        unsafe { ffi::gst_rtp_packet_rate_ctx_get_max_dropout(&mut *self.0, time_ms) }
+    pub fn max_dropout(&self, time_ms: i32) -> u32 {
+        self.packets_for(time_ms)
+            .map_or(RTP_DEF_DROPOUT, |packets| max(RTP_MIN_DROPOUT, packets))
     }
 
-    pub fn max_misorder(&mut self, time_ms: i32) -> u32 {
-        unsafe { ffi::gst_rtp_packet_rate_ctx_get_max_misorder(&mut *self.0, time_ms) }
+    pub fn max_misorder(&self, time_ms: i32) -> u32 {
+        self.packets_for(time_ms)
+            .map_or(RTP_DEF_MISORDER, |packets| max(RTP_MIN_MISORDER, packets))
     }
 }
 
@@ -206,160 +228,627 @@ impl Default for RTPPacketRateCtx {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
 pub enum RTPJitterBufferMode {
+    /// No skew correction, timestamps are interpolated from the RTP timestamps.
     r#None,
+    /// Estimate the skew between the sender and the receiver clocks.
+    #[default]
     Slave,
+    /// Buffer packets between the low and high watermarks.
     Buffer,
+    /// Sender and receiver clocks are synchronized, the skew is assumed to be 0.
     Synced,
-    __Unknown(i32),
+}
+
+/// Orders the packets by seqnum and computes their output timestamps.
+///
+/// RFC 7273 media clocks are not supported.
+#[derive(Debug)]
+pub struct RTPJitterBuffer {
+    packets: VecDeque<RTPJitterBufferItem>,
+
+    mode: RTPJitterBufferMode,
+
+    delay: gst::ClockTime,
+
+    // For buffering
+    buffering: bool,
+    low_level: gst::ClockTime,
+    high_level: gst::ClockTime,
+
+    // For calculating the skew
+    need_resync: bool,
+    base_time: Option<gst::ClockTime>,
+    base_rtptime: Option<gst::ClockTime>,
+    base_extrtp: Option<u64>,
+    clock_rate: u32,
+    prev_out_time: Option<gst::ClockTime>,
+    ext_rtptime: Option<u64>,
+    last_rtptime: Option<u64>,
+    window: Box<[i64; MAX_WINDOW]>,
+    window_pos: usize,
+    window_size: usize,
+    window_filling: bool,
+    window_min: i64,
+    skew: i64,
+    prev_send_diff: Option<i64>,
 }
 
 impl RTPJitterBuffer {
     pub fn new() -> RTPJitterBuffer {
-        unsafe { from_glib_full(ffi::rtp_jitter_buffer_new()) }
+        let mut jbuf = RTPJitterBuffer {
+            packets: VecDeque::new(),
+            mode: RTPJitterBufferMode::default(),
+            delay: gst::ClockTime::ZERO,
+            buffering: false,
+            low_level: gst::ClockTime::ZERO,
+            high_level: gst::ClockTime::ZERO,
+            need_resync: true,
+            base_time: None,
+            base_rtptime: None,
+            base_extrtp: None,
+            clock_rate: 0,
+            prev_out_time: None,
+            ext_rtptime: None,
+            last_rtptime: None,
+            window: Box::new([0; MAX_WINDOW]),
+            window_pos: 0,
+            window_size: 0,
+            window_filling: true,
+            window_min: 0,
+            skew: 0,
+            prev_send_diff: None,
+        };
+
+        jbuf.reset_skew();
+
+        jbuf
     }
 
     #[allow(dead_code)]
     pub fn mode(&self) -> RTPJitterBufferMode {
-        unsafe { from_glib(ffi::rtp_jitter_buffer_get_mode(self.to_glib_none().0)) }
+        self.mode
     }
 
     #[allow(dead_code)]
-    pub fn set_mode(&self, mode: RTPJitterBufferMode) {
-        unsafe { ffi::rtp_jitter_buffer_set_mode(self.to_glib_none().0, mode.into_glib()) }
+    pub fn set_mode(&mut self, mode: RTPJitterBufferMode) {
+        self.mode = mode;
     }
 
     #[allow(dead_code)]
     pub fn delay(&self) -> gst::ClockTime {
-        unsafe {
-            try_from_glib(ffi::rtp_jitter_buffer_get_delay(self.to_glib_none().0))
-                .expect("undefined delay")
-        }
+        self.delay
     }
 
-    pub fn set_delay(&self, delay: gst::ClockTime) {
-        unsafe { ffi::rtp_jitter_buffer_set_delay(self.to_glib_none().0, delay.into_glib()) }
+    pub fn set_delay(&mut self, delay: gst::ClockTime) {
+        self.delay = delay;
+        self.low_level = delay * 15 / 100;
+        // The high level is at 90% in order to release packets
+        // before we fill up the buffer up to the latency
+        self.high_level = delay * 90 / 100;
+
+        gst::debug!(
+            CAT,
+            "delay {}, min {}, max {}",
+            self.delay,
+            self.low_level,
+            self.high_level,
+        );
     }
 
-    pub fn set_clock_rate(&self, clock_rate: u32) {
-        unsafe { ffi::rtp_jitter_buffer_set_clock_rate(self.to_glib_none().0, clock_rate) }
+    pub fn set_clock_rate(&mut self, clock_rate: u32) {
+        if self.clock_rate != clock_rate {
+            gst::debug!(
+                CAT,
+                "Clock rate changed from {} to {}",
+                self.clock_rate,
+                clock_rate
+            );
+            self.clock_rate = clock_rate;
+            self.reset_skew();
+        }
     }
 
     #[allow(dead_code)]
     pub fn clock_rate(&self) -> u32 {
-        unsafe { ffi::rtp_jitter_buffer_get_clock_rate(self.to_glib_none().0) }
+        self.clock_rate
+    }
+
+    pub fn reset_skew(&mut self) {
+        self.base_time = None;
+        self.base_rtptime = None;
+        self.base_extrtp = None;
+        self.ext_rtptime = None;
+        self.last_rtptime = None;
+        self.window_pos = 0;
+        self.window_filling = true;
+        self.window_min = 0;
+        self.skew = 0;
+        self.prev_send_diff = None;
+        self.prev_out_time = None;
+        self.need_resync = true;
+
+        gst::debug!(CAT, "reset skew correction");
+    }
+
+    fn resync(
+        &mut self,
+        time: gst::ClockTime,
+        gstrtptime: gst::ClockTime,
+        ext_rtptime: u64,
+        reset_skew: bool,
+    ) {
+        self.base_time = Some(time);
+        self.base_rtptime = Some(gstrtptime);
+        self.base_extrtp = Some(ext_rtptime);
+        self.prev_out_time = None;
+        self.prev_send_diff = None;
+        if reset_skew {
+            self.window_filling = true;
+            self.window_pos = 0;
+            self.window_min = 0;
+            self.window_size = 0;
+            self.skew = 0;
+        }
+        self.need_resync = false;
     }
 
+    /// Elapsed time at the sender.
+    fn send_diff(&self, gstrtptime: gst::ClockTime) -> i64 {
+        gstrtptime.nseconds() as i64 - self.base_rtptime.map_or(0, |t| t.nseconds() as i64)
+    }
+
+    fn buffer_level(&self) -> gst::ClockTime {
+        let high = self
+            .packets
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(idx, item)| item.timestamp().map(|ts| (idx, ts)));
+        let low = self
+            .packets
+            .iter()
+            .enumerate()
+            .find_map(|(idx, item)| item.timestamp().map(|ts| (idx, ts)));
+
+        match (low, high) {
+            (Some((low_idx, low_ts)), Some((high_idx, high_ts))) if low_idx != high_idx => {
+                let level = high_ts.saturating_sub(low_ts);
+                gst::log!(CAT, "low {} high {} level {}", low_ts, high_ts, level);
+                level
+            }
+            _ => gst::ClockTime::ZERO,
+        }
+    }
+
+    fn update_buffer_level(&mut self) -> i32 {
+        let level = self.buffer_level();
+        gst::debug!(CAT, "buffer level {}", level);
+
+        let post = if self.buffering {
+            if level >= self.high_level {
+                gst::debug!(CAT, "buffering finished");
+                self.buffering = false;
+            }
+            true
+        } else if level < self.low_level {
+            gst::debug!(CAT, "buffering started");
+            self.buffering = true;
+            true
+        } else {
+            false
+        };
+
+        if !post {
+            return -1;
+        }
+
+        let percent = if self.buffering && !self.high_level.is_zero() {
+            (level.nseconds() * 100 / self.high_level.nseconds()).min(100) as i32
+        } else {
+            100
+        };
+
+        gst::debug!(CAT, "buffering {}", percent);
+
+        percent
+    }
+
+    // For the clock skew we use a windowed low point averaging algorithm as can be
+    // found in Fober, Orlarey and Letz, 2005, "Real Time Clock Skew Estimation
+    // over Network Delays".
+    //
+    // The drift between the elapsed time at the receiver and the elapsed time at
+    // the sender is measured for each packet. The minimum of the W latest values
+    // is the one which is the least affected by the network jitter, and this
+    // minimum is averaged to smooth out the resulting skew.
+    //
+    // We use a 2 seconds window or up to 512 data points. While filling the
+    // window, a parabolic weighting factor is used: the more the window is
+    // filled, the faster we move to the detected skew. Once the window is filled,
+    // a large weighting factor (125) is used to adapt smoothly.
+    //
+    // Returns the output time adjusted with the clock skew.
+    fn calculate_skew(
+        &mut self,
+        ext_rtptime: u64,
+        gstrtptime: gst::ClockTime,
+        time: Option<gst::ClockTime>,
+        mut gap: i32,
+        is_rtx: bool,
+    ) -> Option<gst::ClockTime> {
+        let mut send_diff = self.send_diff(gstrtptime);
+
+        // Without an arrival time, we can't do skew detection. We still
+        // apply a timestamp based on the RTP timestamp and the base time.
+        if let (Some(time), Some(base_time), false) = (time, self.base_time, is_rtx) {
+            // Elapsed time at the receiver, includes the jitter
+            let recv_diff = time.nseconds() as i64 - base_time.nseconds() as i64;
+            let mut delta = recv_diff - send_diff;
+
+            gst::debug!(
+                CAT,
+                "time {}, base {}, recv_diff {}, send_diff {}",
+                time,
+                base_time,
+                recv_diff,
+                send_diff,
+            );
+
+            // If the difference between the sender timeline and the receiver
+            // timeline changed too quickly, the server likely restarted its timestamps.
+            if (delta - self.skew).abs() > gst::ClockTime::SECOND.nseconds() as i64 {
+                gst::warning!(
+                    CAT,
+                    "delta - skew: {} too big, reset skew",
+                    (delta - self.skew).abs()
+                );
+                self.resync(time, gstrtptime, ext_rtptime, true);
+                send_diff = 0;
+                delta = 0;
+                gap = 0;
+            }
+
+            // Only do skew calculations if we didn't have a gap. If too much
+            // time has elapsed despite there being a gap, we resynced already.
+            if gap == 0 {
+                self.update_window(delta, send_diff);
+            }
+        }
+
+        // The output time is defined as the base timestamp plus
+        // the RTP time adjusted for the clock skew.
+        let out_time = self.base_time.map(|base_time| {
+            let out_time = base_time.nseconds() as i64 + send_diff + self.skew;
+            // The skew can be negative and we don't want to produce invalid timestamps
+            gst::ClockTime::from_nseconds(max(out_time, 0) as u64)
+        });
+
+        gst::debug!(CAT, "skew {}, out {}", self.skew, out_time.display());
+
+        out_time
+    }
+
+    fn update_window(&mut self, delta: i64, send_diff: i64) {
+        let mut pos = self.window_pos;
+
+        if self.window_filling {
+            gst::debug!(CAT, "filling {}, delta {}", pos, delta);
+
+            self.window[pos] = delta;
+            pos += 1;
+            if pos == 1 || delta < self.window_min {
+                self.window_min = delta;
+            }
+
+            if send_diff >= MAX_TIME.nseconds() as i64 || pos >= MAX_WINDOW {
+                self.window_size = pos;
+
+                // Window filled, the skew is now the min
+                gst::debug!(CAT, "min {}", self.window_min);
+                self.skew = self.window_min;
+                self.window_filling = false;
+            } else {
+                // Figure out how much we filled the window, this depends on the
+                // amount of time we have or the max number of points we keep.
+                let perc_time = send_diff * 100 / MAX_TIME.nseconds() as i64;
+                let perc_window = (pos * 100 / MAX_WINDOW) as i64;
+                let perc = max(perc_time, perc_window);
+
+                // Parabolic function: quickly go to the min value when we are
+                // filling up, slowly when we are just starting because we're
+                // not sure it's a good value yet.
+                let perc = perc * perc;
+
+                self.skew = (perc * self.window_min + (10000 - perc) * self.skew) / 10000;
+                self.window_size = pos + 1;
+            }
+        } else {
+            // Keep the previous value in order to quickly check if the min changed
+            let old = self.window[pos];
+            self.window[pos] = delta;
+            pos += 1;
+
+            if delta <= self.window_min {
+                self.window_min = delta;
+            } else if old == self.window_min {
+                // We removed the old min, find the new one
+                self.window_min = self.window[..self.window_size]
+                    .iter()
+                    .copied()
+                    .min()
+                    .unwrap_or(i64::MAX);
+            }
+
+            // Average the min values
+            self.skew = (self.window_min + 124 * self.skew) / 125;
+
+            gst::debug!(CAT, "delta {}, new min: {}", delta, self.window_min);
+        }
+
+        // Wrap around in the window
+        if pos >= self.window_size {
+            pos = 0;
+        }
+        self.window_pos = pos;
+    }
+
+    /// Computes the output PTS for a packet with the provided `rtptime`
+    /// received at running time `dts`.
+    ///
+    /// Retransmitted packets (`is_rtx`) don't contribute to the skew
+    /// estimation and never trigger a resync.
     pub fn calculate_pts(
-        &self,
+        &mut self,
         dts: impl Into<Option<gst::ClockTime>>,
         estimated_dts: bool,
         rtptime: u32,
-        base_time: impl Into<Option<gst::ClockTime>>,
         gap: i32,
         is_rtx: bool,
     ) -> Option<gst::ClockTime> {
-        unsafe {
-            from_glib(ffi::rtp_jitter_buffer_calculate_pts(
-                self.to_glib_none().0,
-                dts.into().into_glib(),
-                estimated_dts.into_glib(),
-                rtptime,
-                base_time.into().into_glib(),
-                gap,
-                is_rtx.into_glib(),
-            ))
+        let mut dts = dts.into();
+
+        if self.clock_rate == 0 {
+            gst::warning!(CAT, "No clock rate, can't calculate pts");
+            return None;
         }
-    }
 
-    pub fn insert(&self, mut item: RTPJitterBufferItem) -> (bool, bool, i32) {
-        unsafe {
-            let mut head = mem::MaybeUninit::uninit();
-            let mut percent = mem::MaybeUninit::uninit();
-            let ptr = item.0.take().expect("Invalid wrapper");
-            let ret: bool = from_glib(ffi::rtp_jitter_buffer_insert(
-                self.to_glib_none().0,
-                ptr.as_ptr(),
-                head.as_mut_ptr(),
-                percent.as_mut_ptr(),
-            ));
-            if !ret {
-                item.0 = Some(ptr);
+        // RTP time jumps are checked for during the skew calculation, but are
+        // bypassed in other modes: check them here and reset if needed.
+        // Only reset on valid input times: these are likely with UDP input where
+        // such jumps can happen due to seek and state change cycles.
+        if dts.is_some()
+            && !estimated_dts
+            && self.mode != RTPJitterBufferMode::Slave
+            && self.base_time.is_some()
+        {
+            if let Some(last_rtptime) = self.last_rtptime {
+                let mut ext_rtptime = self.ext_rtptime;
+                let ext_rtptime = ext_timestamp(&mut ext_rtptime, rtptime);
+                let max_delta = 3 * self.clock_rate as u64;
+
+                if ext_rtptime > last_rtptime + max_delta || ext_rtptime + max_delta < last_rtptime
+                {
+                    if is_rtx {
+                        gst::warning!(CAT, "rtp delta too big: ignore rtx packet");
+                        return None;
+                    }
+
+                    gst::warning!(CAT, "rtp delta too big, reset skew");
+                    self.reset_skew();
+                }
             }
-            (ret, from_glib(head.assume_init()), percent.assume_init())
         }
-    }
 
-    pub fn find_earliest(&self) -> (Option<gst::ClockTime>, Option<u16>) {
-        unsafe {
-            let mut pts = mem::MaybeUninit::uninit();
-            let mut seqnum = mem::MaybeUninit::uninit();
-
-            ffi::rtp_jitter_buffer_find_earliest(
-                self.to_glib_none().0,
-                pts.as_mut_ptr(),
-                seqnum.as_mut_ptr(),
-            );
-            let pts = from_glib(pts.assume_init());
-            let seqnum = seqnum.assume_init();
+        // Return the last time if we got the same RTP timestamp again
+        let ext_rtptime = ext_timestamp(&mut self.ext_rtptime, rtptime);
+        if self.last_rtptime == Some(ext_rtptime) {
+            return self.prev_out_time;
+        }
 
-            let seqnum = if seqnum == std::u32::MAX {
-                None
-            } else {
-                Some(seqnum as u16)
-            };
+        // Keep track of the last extended rtptime
+        self.last_rtptime = Some(ext_rtptime);
 
-            (pts, seqnum)
-        }
-    }
+        let gstrtptime = gst::ClockTime::from_nseconds(
+            ext_rtptime
+                .mul_div_floor(*gst::ClockTime::SECOND, self.clock_rate as u64)
+                .unwrap(),
+        );
 
-    pub fn pop(&self) -> (Option<RTPJitterBufferItem>, i32) {
-        unsafe {
-            let mut percent = mem::MaybeUninit::uninit();
-            let item = ffi::rtp_jitter_buffer_pop(self.to_glib_none().0, percent.as_mut_ptr());
+        if let Some(base_rtptime) = self.base_rtptime {
+            // Timestamps can go backwards at the sender, schedule
+            // a new base time in that case.
+            if gstrtptime < base_rtptime {
+                if is_rtx {
+                    gst::warning!(CAT, "backward timestamps: ignore rtx packet");
+                    return None;
+                }
 
-            (
-                if item.is_null() {
-                    None
-                } else {
-                    Some(RTPJitterBufferItem(Some(ptr::NonNull::new_unchecked(item))))
-                },
-                percent.assume_init(),
-            )
+                gst::warning!(CAT, "backward timestamps at server, schedule resync");
+                self.need_resync = true;
+            }
         }
-    }
 
-    pub fn peek(&self) -> (Option<gst::ClockTime>, Option<u16>) {
-        unsafe {
-            let item = ffi::rtp_jitter_buffer_peek(self.to_glib_none().0);
-            if item.is_null() {
-                (None, None)
-            } else {
-                let seqnum = (*item).seqnum;
-                let seqnum = if seqnum == std::u32::MAX {
-                    None
+        match self.mode {
+            RTPJitterBufferMode::None | RTPJitterBufferMode::Buffer => {
+                // Use 0 as the first timestamp and interpolate the other ones
+                // from the RTP timestamps.
+                dts = if self.base_time.is_none() {
+                    Some(gst::ClockTime::ZERO)
                 } else {
-                    Some(seqnum as u16)
+                    None
                 };
-                (from_glib((*item).pts), seqnum)
+            }
+            RTPJitterBufferMode::Synced => {
+                // Synchronized clocks, take the first timestamp as base
+                // and use the RTP timestamps to interpolate.
+                if self.base_time.is_some() && !self.need_resync {
+                    dts = None;
+                }
+            }
+            RTPJitterBufferMode::Slave => (),
+        }
+
+        // Lock on to time and gstrtptime if we can,
+        // otherwise we do with the previous values
+        if self.need_resync {
+            if let Some(dts) = dts {
+                if is_rtx {
+                    gst::debug!(CAT, "not resyncing on rtx packet, discard");
+                    return None;
+                }
+
+                gst::info!(CAT, "resync to time {}, rtptime {}", dts, gstrtptime);
+                self.resync(dts, gstrtptime, ext_rtptime, false);
+            }
+        }
+
+        gst::debug!(
+            CAT,
+            "extrtp {}, gstrtp {}, base {}, send_diff {}",
+            ext_rtptime,
+            gstrtptime,
+            self.base_rtptime.display(),
+            self.send_diff(gstrtptime),
+        );
+
+        let mut pts = self.calculate_skew(ext_rtptime, gstrtptime, dts, gap, is_rtx);
+
+        // Check that timestamps are not going backwards
+        let send_diff = self.send_diff(gstrtptime);
+        if let (Some(out_time), Some(prev_out_time), Some(prev_send_diff)) =
+            (pts, self.prev_out_time, self.prev_send_diff)
+        {
+            if (send_diff > prev_send_diff && out_time < prev_out_time)
+                || (send_diff < prev_send_diff && out_time > prev_out_time)
+                || send_diff == prev_send_diff
+            {
+                gst::debug!(CAT, "backwards timestamps, using previous time");
+                pts = Some(prev_out_time);
+            }
+        }
+
+        if gap == 0 {
+            if let (Some(dts), Some(out_time)) = (dts, pts) {
+                if out_time + self.delay < dts {
+                    // The output timestamp would be later than the input timestamp:
+                    // likely the server paused temporarily.
+                    gst::debug!(
+                        CAT,
+                        "out {} + {} < time {}, reset jitterbuffer and discard",
+                        out_time,
+                        self.delay,
+                        dts
+                    );
+                    self.reset_skew();
+                    return None;
+                }
             }
         }
+
+        self.prev_out_time = pts;
+        self.prev_send_diff = Some(send_diff);
+
+        pts
     }
 
-    pub fn flush(&self) {
-        unsafe extern "C" fn free_item(item: glib::ffi::gpointer, _: glib::ffi::gpointer) {
-            let _ =
-                RTPJitterBufferItem(Some(ptr::NonNull::new(item as *mut _).expect("NULL item")));
+    /// Inserts `item` sorted by seqnum. Items without seqnum are appended.
+    ///
+    /// Returns whether the item was inserted (`false` for a duplicate),
+    /// whether it was inserted at the head and the buffering percent,
+    /// or `-1` if it didn't change.
+    pub fn insert(&mut self, item: RTPJitterBufferItem) -> (bool, bool, i32) {
+        let mut insert_idx = self.packets.len();
+
+        if let Some(seqnum) = item.seqnum {
+            let mut event_idx = None;
+            insert_idx = 0;
+
+            // Skip strictly larger seqnum packets
+            for (idx, qitem) in self.packets.iter().enumerate().rev() {
+                let qseq = match qitem.seqnum {
+                    Some(qseq) => qseq,
+                    None => {
+                        // Keep the position after the first consecutive event: the
+                        // packet will be inserted after the event if we can't
+                        // find a packet with a lower seqnum before it.
+                        if event_idx.is_none() {
+                            event_idx = Some(idx + 1);
+                        }
+                        continue;
+                    }
+                };
+
+                let gap = gst_rtp::compare_seqnum(seqnum, qseq);
+                if gap == 0 {
+                    gst::debug!(CAT, "duplicate packet {} found", seqnum);
+                    return (false, false, -1);
+                }
+
+                if gap < 0 {
+                    insert_idx = idx + 1;
+                    break;
+                }
+
+                // Found a packet with a greater seqnum:
+                // the packet will be inserted before the event
+                event_idx = None;
+            }
+
+            if let Some(event_idx) = event_idx {
+                insert_idx = event_idx;
+            }
         }
 
-        unsafe {
-            ffi::rtp_jitter_buffer_flush(self.to_glib_none().0, Some(free_item));
+        self.packets.insert(insert_idx, item);
+
+        let percent = if self.mode == RTPJitterBufferMode::Buffer {
+            self.update_buffer_level()
+        } else {
+            -1
+        };
+
+        (true, insert_idx == 0, percent)
+    }
+
+    pub fn find_earliest(&self) -> (Option<gst::ClockTime>, Option<u16>) {
+        let mut earliest: Option<&RTPJitterBufferItem> = None;
+
+        for item in self.packets.iter() {
+            let is_earlier = earliest.map_or(true, |earliest| {
+                item.pts.map_or(u64::MAX, gst::ClockTime::nseconds)
+                    <= earliest.pts.map_or(u64::MAX, gst::ClockTime::nseconds)
+            });
+
+            if is_earlier {
+                earliest = Some(item);
+            }
         }
+
+        earliest.map_or((None, None), |item| (item.pts, item.seqnum))
     }
 
-    pub fn reset_skew(&self) {
-        unsafe { ffi::rtp_jitter_buffer_reset_skew(self.to_glib_none().0) }
+    pub fn pop(&mut self) -> (Option<RTPJitterBufferItem>, i32) {
+        let item = self.packets.pop_front();
+
+        let percent = if self.mode == RTPJitterBufferMode::Buffer {
+            self.update_buffer_level()
+        } else {
+            -1
+        };
+
+        (item, percent)
+    }
+
+    pub fn peek(&self) -> (Option<gst::ClockTime>, Option<u16>) {
+        self.packets
+            .front()
+            .map_or((None, None), |item| (item.pts, item.seqnum))
+    }
+
+    pub fn flush(&mut self) {
+        self.packets.clear();
     }
 }
 
@@ -368,3 +857,359 @@ impl Default for RTPJitterBuffer {
         RTPJitterBuffer::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOCK_RATE: u32 = 8000;
+    const PACKET_DURATION: gst::ClockTime = gst::ClockTime::from_mseconds(20);
+    const PACKET_RTP_DURATION: u32 = 160;
+
+    fn init() {
+        use std::sync::Once;
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            gst::init().unwrap();
+        });
+    }
+
+    fn item(seqnum: Option<u16>, pts: impl Into<Option<gst::ClockTime>>) -> RTPJitterBufferItem {
+        RTPJitterBufferItem::new(gst::Buffer::new(), gst::ClockTime::NONE, pts, seqnum, 0)
+    }
+
+    fn pop_seqnums(jbuf: &mut RTPJitterBuffer) -> Vec<Option<u16>> {
+        let mut seqnums = vec![];
+        while let (Some(item), _) = jbuf.pop() {
+            seqnums.push(item.seqnum());
+        }
+
+        seqnums
+    }
+
+    #[test]
+    fn test_ext_timestamp() {
+        let mut ext = None;
+        assert_eq!(ext_timestamp(&mut ext, 1000), 1000);
+        assert_eq!(ext_timestamp(&mut ext, 0x7fff_0000), 0x7fff_0000);
+        assert_eq!(ext_timestamp(&mut ext, 0xc000_0000), 0xc000_0000);
+
+        // Forward wrap-around
+        assert_eq!(ext_timestamp(&mut ext, 0xffff_ff00), 0xffff_ff00);
+        assert_eq!(ext_timestamp(&mut ext, 0x100), 0x1_0000_0100);
+        assert_eq!(ext, Some(0x1_0000_0100));
+
+        // Backward wrap-around
+        assert_eq!(ext_timestamp(&mut ext, 0xffff_fff0), 0xffff_fff0);
+        assert_eq!(ext, Some(0xffff_fff0));
+
+        // Going backward before any wrap-around
+        let mut ext = Some(0x100);
+        assert_eq!(ext_timestamp(&mut ext, 0xffff_fff0), 0);
+        assert_eq!(ext, Some(0x100));
+    }
+
+    #[test]
+    fn test_packet_rate_ctx() {
+        let mut ctx = RTPPacketRateCtx::new();
+
+        // Not probed: defaults
+        assert_eq!(ctx.max_dropout(60000), RTP_DEF_DROPOUT);
+        assert_eq!(ctx.max_misorder(2000), RTP_DEF_MISORDER);
+
+        // No clock rate: no estimation
+        assert_eq!(ctx.update(0, 0), None);
+
+        ctx.reset(CLOCK_RATE as i32);
+        assert_eq!(ctx.update(0, 0), None);
+        for seqnum in 1..10u16 {
+            assert_eq!(
+                ctx.update(seqnum, seqnum as u32 * PACKET_RTP_DURATION),
+                Some(50)
+            );
+        }
+
+        assert_eq!(ctx.max_dropout(1000), 50);
+        assert_eq!(ctx.max_dropout(100), RTP_MIN_DROPOUT);
+        assert_eq!(ctx.max_misorder(500), 25);
+        assert_eq!(ctx.max_misorder(100), RTP_MIN_MISORDER);
+        assert_eq!(ctx.max_misorder(0), RTP_DEF_MISORDER);
+
+        // Bursts go up fast
+        assert_eq!(ctx.update(10, 9 * PACKET_RTP_DURATION + 80), Some(75));
+        // ... and down slowly
+        assert_eq!(ctx.update(11, 10 * PACKET_RTP_DURATION + 80), Some(72));
+
+        // Gaps and reordered packets are ignored
+        assert_eq!(ctx.update(13, 13 * PACKET_RTP_DURATION), Some(72));
+        assert_eq!(ctx.update(12, 12 * PACKET_RTP_DURATION), Some(72));
+    }
+
+    #[test]
+    fn test_insert_order() {
+        init();
+
+        let mut jbuf = RTPJitterBuffer::new();
+
+        assert_eq!(jbuf.insert(item(Some(2), None)), (true, true, -1));
+        assert_eq!(jbuf.insert(item(Some(0), None)), (true, true, -1));
+        assert_eq!(jbuf.insert(item(Some(1), None)), (true, false, -1));
+        assert_eq!(jbuf.insert(item(Some(1), None)), (false, false, -1));
+        assert_eq!(jbuf.insert(item(Some(3), None)), (true, false, -1));
+        assert_eq!(jbuf.packets.len(), 4);
+
+        assert_eq!(jbuf.peek(), (None, Some(0)));
+        assert_eq!(
+            pop_seqnums(&mut jbuf),
+            vec![Some(0), Some(1), Some(2), Some(3)]
+        );
+
+        // Wrapping seqnums
+        jbuf.insert(item(Some(1), None));
+        jbuf.insert(item(Some(65535), None));
+        jbuf.insert(item(Some(0), None));
+        assert_eq!(pop_seqnums(&mut jbuf), vec![Some(65535), Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn test_insert_events() {
+        init();
+
+        let mut jbuf = RTPJitterBuffer::new();
+
+        jbuf.insert(item(Some(1), None));
+        assert_eq!(jbuf.insert(item(None, None)), (true, false, -1));
+        jbuf.insert(item(Some(3), None));
+        // Lower than the packet after the event, higher than the one before
+        jbuf.insert(item(Some(2), None));
+        // Higher than all packets, after the event
+        jbuf.insert(item(None, None));
+        jbuf.insert(item(Some(4), None));
+
+        assert_eq!(
+            pop_seqnums(&mut jbuf),
+            vec![Some(1), None, Some(2), Some(3), None, Some(4)]
+        );
+    }
+
+    #[test]
+    fn test_find_earliest() {
+        init();
+
+        let mut jbuf = RTPJitterBuffer::new();
+        assert_eq!(jbuf.find_earliest(), (None, None));
+
+        jbuf.insert(item(Some(0), gst::ClockTime::from_mseconds(40)));
+        jbuf.insert(item(Some(1), gst::ClockTime::from_mseconds(20)));
+        jbuf.insert(item(Some(2), gst::ClockTime::NONE));
+        jbuf.insert(item(Some(3), gst::ClockTime::from_mseconds(20)));
+        assert_eq!(
+            jbuf.find_earliest(),
+            (Some(gst::ClockTime::from_mseconds(20)), Some(3))
+        );
+
+        jbuf.flush();
+        assert_eq!(jbuf.peek(), (None, None));
+        assert_eq!(jbuf.find_earliest(), (None, None));
+    }
+
+    #[test]
+    fn test_calculate_pts_slave() {
+        let mut jbuf = RTPJitterBuffer::new();
+        jbuf.set_delay(gst::ClockTime::from_mseconds(200));
+
+        // No clock rate
+        assert_eq!(
+            jbuf.calculate_pts(gst::ClockTime::ZERO, false, 0, 0, false),
+            None
+        );
+
+        jbuf.set_clock_rate(CLOCK_RATE);
+
+        let base = gst::ClockTime::from_seconds(1);
+        let rtp_base = 10_000u32;
+        for idx in 0..100u32 {
+            // Positive network jitter
+            let jitter = gst::ClockTime::from_mseconds(5 * (idx % 3) as u64);
+            let dts = base + idx as u64 * PACKET_DURATION + jitter;
+            let pts =
+                jbuf.calculate_pts(dts, false, rtp_base + idx * PACKET_RTP_DURATION, 0, false);
+
+            // The minimum delay is locked on
+            assert_eq!(pts, Some(base + idx as u64 * PACKET_DURATION));
+        }
+
+        // Same RTP timestamp again: same pts
+        let pts = jbuf.calculate_pts(
+            base + gst::ClockTime::from_seconds(3),
+            false,
+            rtp_base + 99 * PACKET_RTP_DURATION,
+            0,
+            false,
+        );
+        assert_eq!(pts, Some(base + 99 * PACKET_DURATION));
+    }
+
+    #[test]
+    fn test_calculate_pts_skew() {
+        let mut jbuf = RTPJitterBuffer::new();
+        jbuf.set_delay(gst::ClockTime::from_mseconds(200));
+        jbuf.set_clock_rate(CLOCK_RATE);
+
+        // The receiver clock runs 1‰ faster than the sender clock
+        let mut last_pts = gst::ClockTime::ZERO;
+        for idx in 0..500u64 {
+            let dts = gst::ClockTime::from_nseconds(idx * PACKET_DURATION.nseconds() * 1001 / 1000);
+            let pts = jbuf
+                .calculate_pts(dts, false, idx as u32 * PACKET_RTP_DURATION, 0, false)
+                .unwrap();
+
+            assert!(pts >= last_pts);
+            last_pts = pts;
+        }
+
+        // The skew is being compensated for
+        let last_dts =
+            gst::ClockTime::from_nseconds(499 * PACKET_DURATION.nseconds() * 1001 / 1000);
+        let diff = last_dts.nseconds() as i64 - last_pts.nseconds() as i64;
+        // Without skew correction, the pts would drift by 10ms
+        assert!(last_pts > 499 * PACKET_DURATION);
+        assert!(diff.abs() < gst::ClockTime::from_mseconds(5).nseconds() as i64);
+    }
+
+    #[test]
+    fn test_calculate_pts_resync() {
+        let mut jbuf = RTPJitterBuffer::new();
+        jbuf.set_delay(gst::ClockTime::from_mseconds(200));
+        jbuf.set_clock_rate(CLOCK_RATE);
+
+        for idx in 0..10u32 {
+            let dts = idx as u64 * PACKET_DURATION;
+            assert_eq!(
+                jbuf.calculate_pts(dts, false, idx * PACKET_RTP_DURATION, 0, false),
+                Some(dts)
+            );
+        }
+
+        // The sender restarted its timestamps 10s ahead: resync
+        let dts = 10 * PACKET_DURATION;
+        assert_eq!(
+            jbuf.calculate_pts(dts, false, 10 * CLOCK_RATE, 0, false),
+            Some(dts)
+        );
+
+        // Packet arriving way later than its expected time: reset and discard
+        let dts = 11 * PACKET_DURATION + gst::ClockTime::from_mseconds(500);
+        assert_eq!(
+            jbuf.calculate_pts(dts, false, 10 * CLOCK_RATE + PACKET_RTP_DURATION, 0, false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_calculate_pts_rtx() {
+        let mut jbuf = RTPJitterBuffer::new();
+        jbuf.set_delay(gst::ClockTime::from_mseconds(200));
+        jbuf.set_clock_rate(CLOCK_RATE);
+
+        // Can't resync on a rtx packet
+        assert_eq!(
+            jbuf.calculate_pts(gst::ClockTime::ZERO, false, 0, 0, true),
+            None
+        );
+
+        for idx in [0u32, 1, 3] {
+            let dts = idx as u64 * PACKET_DURATION;
+            jbuf.calculate_pts(dts, false, idx * PACKET_RTP_DURATION, 0, false);
+        }
+
+        // The retransmitted packet arrives late but is timestamped
+        // according to its RTP time and doesn't affect the skew
+        let pts = jbuf.calculate_pts(
+            gst::ClockTime::from_mseconds(150),
+            false,
+            2 * PACKET_RTP_DURATION,
+            0,
+            true,
+        );
+        assert_eq!(pts, Some(2 * PACKET_DURATION));
+
+        let pts = jbuf.calculate_pts(
+            4 * PACKET_DURATION,
+            false,
+            4 * PACKET_RTP_DURATION,
+            0,
+            false,
+        );
+        assert_eq!(pts, Some(4 * PACKET_DURATION));
+    }
+
+    #[test]
+    fn test_calculate_pts_mode_none() {
+        let mut jbuf = RTPJitterBuffer::new();
+        jbuf.set_mode(RTPJitterBufferMode::None);
+        jbuf.set_delay(gst::ClockTime::from_mseconds(200));
+        jbuf.set_clock_rate(CLOCK_RATE);
+
+        for idx in 0..10u32 {
+            // Arrival times are ignored
+            let dts = gst::ClockTime::from_seconds(5) + idx as u64 * 2 * PACKET_DURATION;
+            assert_eq!(
+                jbuf.calculate_pts(dts, false, 1000 + idx * PACKET_RTP_DURATION, 0, false),
+                Some(idx as u64 * PACKET_DURATION)
+            );
+        }
+    }
+
+    #[test]
+    fn test_buffering() {
+        init();
+
+        let mut jbuf = RTPJitterBuffer::new();
+        jbuf.set_mode(RTPJitterBufferMode::Buffer);
+        jbuf.set_delay(gst::ClockTime::from_mseconds(100));
+
+        // Below the low level: buffering starts
+        assert_eq!(
+            jbuf.insert(RTPJitterBufferItem::new(
+                gst::Buffer::new(),
+                gst::ClockTime::ZERO,
+                gst::ClockTime::NONE,
+                Some(0),
+                0
+            )),
+            (true, true, 0)
+        );
+        assert!(jbuf.buffering);
+
+        for idx in 1..5u16 {
+            let (_, _, percent) = jbuf.insert(RTPJitterBufferItem::new(
+                gst::Buffer::new(),
+                idx as u64 * PACKET_DURATION,
+                gst::ClockTime::NONE,
+                Some(idx),
+                0,
+            ));
+            // 90ms high level
+            assert_eq!(percent, (idx as i32 * 20 * 100 / 90).min(100));
+        }
+
+        // 80ms buffered
+        assert!(jbuf.buffering);
+
+        // Above the high level: buffering finished
+        let (_, _, percent) = jbuf.insert(RTPJitterBufferItem::new(
+            gst::Buffer::new(),
+            5 * PACKET_DURATION,
+            gst::ClockTime::NONE,
+            Some(5),
+            0,
+        ));
+        assert_eq!(percent, 100);
+        assert!(!jbuf.buffering);
+
+        // Above the low level: no change
+        assert_eq!(jbuf.pop().1, -1);
+    }
+}
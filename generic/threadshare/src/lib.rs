@@ -18,15 +18,27 @@ pub mod runtime;
 
 pub mod socket;
 mod tcpclientsrc;
+mod tcpserversink;
+mod tcpserversrc;
 mod udpsink;
 mod udpsrc;
 
+pub mod appsink;
 mod appsrc;
+mod contextstats;
 pub mod dataqueue;
+mod fileio;
+mod filesink;
+mod filesrc;
 mod inputselector;
 mod jitterbuffer;
+#[cfg(target_os = "linux")]
+mod mmsg;
+mod multicast;
 mod proxy;
 mod queue;
+mod rtpsession;
+mod tee;
 
 use glib::translate::*;
 use gst::glib;
@@ -35,11 +47,19 @@ fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     udpsrc::register(plugin)?;
     udpsink::register(plugin)?;
     tcpclientsrc::register(plugin)?;
+    tcpserversrc::register(plugin)?;
+    tcpserversink::register(plugin)?;
     queue::register(plugin)?;
     proxy::register(plugin)?;
     appsrc::register(plugin)?;
+    appsink::register(plugin)?;
+    filesrc::register(plugin)?;
+    filesink::register(plugin)?;
     jitterbuffer::register(plugin)?;
     inputselector::register(plugin)?;
+    rtpsession::register(plugin)?;
+    tee::register(plugin)?;
+    contextstats::register(plugin)?;
 
     Ok(())
 }
@@ -0,0 +1,547 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+//! Batched UDP I/O for Linux.
+//!
+//! [`RecvBatch`] reads up to `batch-size` datagrams per `recvmmsg` call and
+//! optionally splits UDP GRO coalesced datagrams back into individual packets.
+//! [`send`] and [`send_segmented`] are the `sendmmsg` and UDP GSO counterparts
+//! used by the sink.
+
+use gst::glib;
+use gst::prelude::*;
+
+use once_cell::sync::Lazy;
+
+use std::io;
+use std::mem;
+use std::net::{SocketAddr, UdpSocket};
+use std::ops::DerefMut;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+
+use crate::runtime::Async;
+use crate::socket::SocketError;
+
+static MMSG_CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ts-mmsg",
+        gst::DebugColorFlags::empty(),
+        Some("Thread-sharing batched UDP I/O"),
+    )
+});
+
+/// Maximum number of messages the kernel accepts in one `recvmmsg` / `sendmmsg` call.
+pub const MAX_BATCH_SIZE: u32 = 1024;
+
+/// Receive buffer size needed to hold a GRO coalesced datagram.
+pub const GRO_BUFFER_SIZE: u32 = 65535;
+
+/// Maximum number of segments the kernel accepts for a single GSO send.
+const MAX_GSO_SEGMENTS: usize = 64;
+
+/// Maximum UDP payload of a single GSO send.
+const MAX_GSO_PAYLOAD: usize = 65507;
+
+// Large enough for one `cmsghdr` carrying an `int`, properly aligned.
+type CmsgBuffer = [u64; 4];
+
+fn setsockopt_int(
+    socket: &UdpSocket,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: libc::c_int,
+) -> io::Result<()> {
+    let res = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &value as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+
+    if res < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Enables UDP generic receive offload on `socket`.
+pub fn enable_gro(socket: &UdpSocket) -> io::Result<()> {
+    setsockopt_int(socket, libc::SOL_UDP, libc::UDP_GRO, 1)
+}
+
+#[derive(Debug)]
+struct Received {
+    len: usize,
+    addr: Option<SocketAddr>,
+    segment_size: Option<usize>,
+}
+
+fn recv_mmsg<T: DerefMut<Target = [u8]>>(
+    socket: &UdpSocket,
+    slots: &mut [T],
+    gro: bool,
+) -> io::Result<Vec<Received>> {
+    let n = slots.len();
+
+    let mut addrs: Vec<libc::sockaddr_storage> = vec![unsafe { mem::zeroed() }; n];
+    let mut cmsgs: Vec<CmsgBuffer> = vec![Default::default(); if gro { n } else { 0 }];
+    let mut iovs: Vec<libc::iovec> = slots
+        .iter_mut()
+        .map(|slot| libc::iovec {
+            iov_base: slot.as_mut_ptr() as *mut libc::c_void,
+            iov_len: slot.len(),
+        })
+        .collect();
+
+    let mut msgs: Vec<libc::mmsghdr> = (0..n)
+        .map(|idx| {
+            let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+            msg.msg_hdr.msg_name = &mut addrs[idx] as *mut _ as *mut libc::c_void;
+            msg.msg_hdr.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as _;
+            msg.msg_hdr.msg_iov = &mut iovs[idx];
+            msg.msg_hdr.msg_iovlen = 1;
+            if gro {
+                msg.msg_hdr.msg_control = cmsgs[idx].as_mut_ptr() as *mut libc::c_void;
+                msg.msg_hdr.msg_controllen = mem::size_of::<CmsgBuffer>() as _;
+            }
+
+            msg
+        })
+        .collect();
+
+    let res = unsafe {
+        libc::recvmmsg(
+            socket.as_raw_fd(),
+            msgs.as_mut_ptr(),
+            n as libc::c_uint,
+            libc::MSG_DONTWAIT,
+            ptr::null_mut(),
+        )
+    };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let received = msgs[..res as usize]
+        .iter()
+        .zip(addrs.iter())
+        .map(|(msg, storage)| {
+            let addr = unsafe { socket2::SockAddr::new(*storage, msg.msg_hdr.msg_namelen) };
+
+            let mut segment_size = None;
+            if gro {
+                unsafe {
+                    let mut cmsg = libc::CMSG_FIRSTHDR(&msg.msg_hdr);
+                    while !cmsg.is_null() {
+                        if (*cmsg).cmsg_level == libc::SOL_UDP && (*cmsg).cmsg_type == libc::UDP_GRO
+                        {
+                            let size =
+                                ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const libc::c_int);
+                            segment_size = Some(size as usize);
+                        }
+                        cmsg = libc::CMSG_NXTHDR(&msg.msg_hdr, cmsg);
+                    }
+                }
+            }
+
+            Received {
+                len: msg.msg_len as usize,
+                addr: addr.as_socket(),
+                segment_size,
+            }
+        })
+        .collect();
+
+    Ok(received)
+}
+
+/// Reads batches of datagrams from a UDP socket with `recvmmsg`.
+///
+/// This is the batched counterpart of [`Socket`](crate::socket::Socket).
+pub struct RecvBatch {
+    element: gst::Element,
+    buffer_pool: gst::BufferPool,
+    socket: Async<UdpSocket>,
+    slots: Vec<gst::MappedBuffer<gst::buffer::Writable>>,
+    batch_size: usize,
+    gro: bool,
+    clock: Option<gst::Clock>,
+    base_time: Option<gst::ClockTime>,
+}
+
+impl RecvBatch {
+    pub fn try_new(
+        element: gst::Element,
+        buffer_pool: gst::BufferPool,
+        socket: Async<UdpSocket>,
+        batch_size: u32,
+        gro: bool,
+    ) -> Result<Self, glib::BoolError> {
+        buffer_pool.set_active(true).map_err(|err| {
+            gst::error!(
+                MMSG_CAT,
+                obj: &element,
+                "Failed to prepare batch socket: {}",
+                err
+            );
+
+            err
+        })?;
+
+        Ok(RecvBatch {
+            element,
+            buffer_pool,
+            socket,
+            slots: Vec::new(),
+            batch_size: batch_size.clamp(1, MAX_BATCH_SIZE) as usize,
+            gro,
+            clock: None,
+            base_time: None,
+        })
+    }
+
+    pub fn set_clock(&mut self, clock: Option<gst::Clock>, base_time: Option<gst::ClockTime>) {
+        self.clock = clock;
+        self.base_time = base_time;
+    }
+
+    /// Reads the next batch of datagrams.
+    ///
+    /// GRO coalesced datagrams are split into one buffer per segment. The
+    /// buffers share the memory of the datagram they were received in.
+    pub async fn try_next(
+        &mut self,
+    ) -> Result<Vec<(gst::Buffer, Option<SocketAddr>)>, SocketError> {
+        gst::log!(MMSG_CAT, obj: &self.element, "Trying to read batch");
+
+        while self.slots.len() < self.batch_size {
+            match self.buffer_pool.acquire_buffer(None) {
+                Ok(buffer) => {
+                    self.slots
+                        .push(buffer.into_mapped_buffer_writable().unwrap());
+                }
+                Err(err) => {
+                    gst::debug!(MMSG_CAT, obj: &self.element, "Failed to acquire buffer {:?}", err);
+                    return Err(SocketError::Gst(err));
+                }
+            }
+        }
+
+        let gro = self.gro;
+        let slots = &mut self.slots;
+        let received = self
+            .socket
+            .read_with(|socket| recv_mmsg(socket, slots, gro))
+            .await
+            .map_err(|err| {
+                gst::debug!(MMSG_CAT, obj: &self.element, "Read error {:?}", err);
+                SocketError::Io(err)
+            })?;
+
+        let dts = self
+            .clock
+            .as_ref()
+            .and_then(|clock| clock.time().opt_checked_sub(self.base_time).ok().flatten());
+        gst::debug!(
+            MMSG_CAT,
+            obj: &self.element,
+            "Read {} datagrams at {}",
+            received.len(),
+            dts.display(),
+        );
+
+        let mut res = Vec::with_capacity(received.len());
+        for (slot, received) in self.slots.drain(..received.len()).zip(received) {
+            let mut buffer = slot.into_buffer();
+            {
+                let buffer = buffer.get_mut().unwrap();
+                if received.len < buffer.size() {
+                    buffer.set_size(received.len);
+                }
+                buffer.set_dts(dts);
+            }
+
+            match received.segment_size {
+                Some(segment_size) if segment_size > 0 && segment_size < received.len => {
+                    gst::trace!(
+                        MMSG_CAT,
+                        obj: &self.element,
+                        "Splitting {} bytes in segments of {} bytes",
+                        received.len,
+                        segment_size,
+                    );
+
+                    let mut offset = 0;
+                    while offset < received.len {
+                        let size = segment_size.min(received.len - offset);
+                        let mut segment = buffer
+                            .copy_region(
+                                gst::BufferCopyFlags::MEMORY
+                                    | gst::BufferCopyFlags::TIMESTAMPS
+                                    | gst::BufferCopyFlags::META,
+                                offset,
+                                Some(size),
+                            )
+                            .map_err(|_| SocketError::Gst(gst::FlowError::Error))?;
+                        // Timestamps are only copied for the region at offset 0
+                        segment.get_mut().unwrap().set_dts(dts);
+                        res.push((segment, received.addr));
+                        offset += size;
+                    }
+                }
+                _ => res.push((buffer, received.addr)),
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+impl Drop for RecvBatch {
+    fn drop(&mut self) {
+        self.slots.clear();
+        if let Err(err) = self.buffer_pool.set_active(false) {
+            gst::error!(MMSG_CAT, obj: &self.element, "Failed to unprepare batch socket: {}", err);
+        }
+    }
+}
+
+/// Sends `datagrams` to `addr` with a single `sendmmsg` call.
+///
+/// Returns the number of datagrams actually sent, which can be less than
+/// `datagrams.len()`.
+pub fn send(socket: &UdpSocket, datagrams: &[&[u8]], addr: &SocketAddr) -> io::Result<usize> {
+    let n = datagrams.len().min(MAX_BATCH_SIZE as usize);
+    if n == 0 {
+        return Ok(0);
+    }
+
+    let addr = socket2::SockAddr::from(*addr);
+    let mut iovs: Vec<libc::iovec> = datagrams[..n]
+        .iter()
+        .map(|data| libc::iovec {
+            iov_base: data.as_ptr() as *mut libc::c_void,
+            iov_len: data.len(),
+        })
+        .collect();
+
+    let mut msgs: Vec<libc::mmsghdr> = iovs
+        .iter_mut()
+        .map(|iov| {
+            let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+            msg.msg_hdr.msg_name = addr.as_ptr() as *mut libc::c_void;
+            msg.msg_hdr.msg_namelen = addr.len();
+            msg.msg_hdr.msg_iov = iov;
+            msg.msg_hdr.msg_iovlen = 1;
+
+            msg
+        })
+        .collect();
+
+    let res = unsafe {
+        libc::sendmmsg(
+            socket.as_raw_fd(),
+            msgs.as_mut_ptr(),
+            n as libc::c_uint,
+            libc::MSG_DONTWAIT,
+        )
+    };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(res as usize)
+}
+
+/// Groups `sizes` for UDP GSO.
+///
+/// Returns the number of leading datagrams which can be sent as one GSO send:
+/// all but the last one must have the same size and the last one can't be
+/// larger.
+pub fn gso_segment_count(sizes: impl Iterator<Item = usize>) -> usize {
+    let mut count = 0;
+    let mut total = 0;
+    let mut segment_size = None;
+
+    for size in sizes {
+        if count == MAX_GSO_SEGMENTS || total + size > MAX_GSO_PAYLOAD {
+            break;
+        }
+
+        match segment_size {
+            None => segment_size = Some(size),
+            Some(segment_size) if size == segment_size => (),
+            Some(segment_size) if size < segment_size => {
+                // A shorter segment terminates the send
+                count += 1;
+                break;
+            }
+            Some(_) => break,
+        }
+
+        count += 1;
+        total += size;
+    }
+
+    count
+}
+
+/// Sends `segments` to `addr` as one UDP GSO datagram.
+///
+/// All but the last segment must have the same size, see [`gso_segment_count`].
+pub fn send_segmented(socket: &UdpSocket, segments: &[&[u8]], addr: &SocketAddr) -> io::Result<()> {
+    assert!(!segments.is_empty() && segments.len() <= MAX_GSO_SEGMENTS);
+
+    let addr = socket2::SockAddr::from(*addr);
+    let mut iovs: Vec<libc::iovec> = segments
+        .iter()
+        .map(|data| libc::iovec {
+            iov_base: data.as_ptr() as *mut libc::c_void,
+            iov_len: data.len(),
+        })
+        .collect();
+
+    let mut cmsg_buf = CmsgBuffer::default();
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_name = addr.as_ptr() as *mut libc::c_void;
+    msg.msg_namelen = addr.len();
+    msg.msg_iov = iovs.as_mut_ptr();
+    msg.msg_iovlen = iovs.len() as _;
+
+    if segments.len() > 1 {
+        let segment_size = segments[0].len() as u16;
+        unsafe {
+            let space = libc::CMSG_SPACE(mem::size_of::<u16>() as u32) as usize;
+            assert!(space <= mem::size_of::<CmsgBuffer>());
+
+            msg.msg_control = cmsg_buf.as_mut_ptr() as *mut libc::c_void;
+            msg.msg_controllen = space as _;
+
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_UDP;
+            (*cmsg).cmsg_type = libc::UDP_SEGMENT;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(mem::size_of::<u16>() as u32) as _;
+            ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut u16, segment_size);
+        }
+    }
+
+    let res = unsafe { libc::sendmsg(socket.as_raw_fd(), &msg, libc::MSG_DONTWAIT) };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gso_segments() {
+        assert_eq!(gso_segment_count([].into_iter()), 0);
+        assert_eq!(gso_segment_count([1200].into_iter()), 1);
+        assert_eq!(gso_segment_count([1200, 1200, 1200].into_iter()), 3);
+        assert_eq!(gso_segment_count([1200, 1200, 500, 1200].into_iter()), 3);
+        assert_eq!(gso_segment_count([1200, 1300, 1200].into_iter()), 1);
+        assert_eq!(
+            gso_segment_count(std::iter::repeat(100).take(100)),
+            MAX_GSO_SEGMENTS
+        );
+        assert_eq!(gso_segment_count(std::iter::repeat(1400).take(64)), 46);
+    }
+
+    #[test]
+    fn send_recv() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = receiver.local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let datagrams: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 100 + i as usize]).collect();
+        let datagrams: Vec<&[u8]> = datagrams.iter().map(Vec::as_slice).collect();
+        assert_eq!(send(&sender, &datagrams, &addr).unwrap(), 8);
+
+        let mut buf = [0u8; 1500];
+        for i in 0..8u8 {
+            let (len, from) = receiver.recv_from(&mut buf).unwrap();
+            assert_eq!(len, 100 + i as usize);
+            assert_eq!(from, sender.local_addr().unwrap());
+            assert!(buf[..len].iter().all(|b| *b == i));
+        }
+    }
+
+    #[test]
+    fn batch_recv() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = receiver.local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        for i in 0..4u8 {
+            sender.send_to(&[i; 200], addr).unwrap();
+        }
+
+        let mut slots = vec![vec![0u8; 1500]; 8];
+        let received = recv_mmsg(&receiver, &mut slots, false).unwrap();
+        assert_eq!(received.len(), 4);
+        for (i, received) in received.iter().enumerate() {
+            assert_eq!(received.len, 200);
+            assert_eq!(received.addr, Some(sender.local_addr().unwrap()));
+            assert_eq!(received.segment_size, None);
+            assert!(slots[i][..200].iter().all(|b| *b == i as u8));
+        }
+
+        let err = recv_mmsg(&receiver, &mut slots, false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    }
+
+    #[test]
+    fn gso_gro() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = receiver.local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        if enable_gro(&receiver).is_err() {
+            // Not supported by this kernel
+            return;
+        }
+
+        let segments: Vec<Vec<u8>> = vec![vec![1; 1000], vec![2; 1000], vec![3; 400]];
+        let segments: Vec<&[u8]> = segments.iter().map(Vec::as_slice).collect();
+        if send_segmented(&sender, &segments, &addr).is_err() {
+            return;
+        }
+
+        let mut slots = vec![vec![0u8; GRO_BUFFER_SIZE as usize]; 4];
+        let mut total = 0;
+        while total < 2400 {
+            for received in recv_mmsg(&receiver, &mut slots, true).unwrap() {
+                if received.len > 1000 {
+                    assert_eq!(received.segment_size, Some(1000));
+                }
+                total += received.len;
+            }
+        }
+        assert_eq!(total, 2400);
+    }
+}
@@ -0,0 +1,415 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+//! Multicast group membership helpers shared by `ts-udpsrc` and `ts-udpsink`.
+//!
+//! Interfaces are handled by index, which works the same way for IPv4 and IPv6.
+//! Source-specific membership (IGMPv3 / MLDv2) uses the protocol independent
+//! `MCAST_*` socket options from RFC 3678.
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+
+/// Source filter to apply when joining a multicast group.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SourceFilter {
+    /// Any-source multicast.
+    #[default]
+    Any,
+    /// Only receive from these sources (IGMPv3 / MLDv2 `INCLUDE` mode).
+    Include(Vec<IpAddr>),
+    /// Receive from any source but these (IGMPv3 / MLDv2 `EXCLUDE` mode).
+    Exclude(Vec<IpAddr>),
+}
+
+impl SourceFilter {
+    /// Parses a source list in the format of the C `udpsrc` `multicast-source` property.
+    ///
+    /// Each source is prefixed with `+` (include) or `-` (exclude), e.g.
+    /// `"+192.168.1.10+192.168.1.11"`. A leading source without prefix is an
+    /// include. Include and exclude sources can't be mixed for the same group.
+    pub fn parse(sources: &str) -> Result<Self, String> {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+
+        let mut is_include = true;
+        let mut start = 0;
+        let mut parse_one = |is_include: bool, s: &str| -> Result<(), String> {
+            let s = s.trim_matches(|c: char| c == ',' || c.is_whitespace());
+            if s.is_empty() {
+                return Ok(());
+            }
+
+            let addr = s
+                .parse::<IpAddr>()
+                .map_err(|err| format!("Invalid source address '{}': {}", s, err))?;
+            if addr.is_multicast() || addr.is_unspecified() {
+                return Err(format!("Invalid source address '{}'", s));
+            }
+
+            if is_include {
+                include.push(addr);
+            } else {
+                exclude.push(addr);
+            }
+
+            Ok(())
+        };
+
+        for (idx, c) in sources.char_indices() {
+            if c == '+' || c == '-' {
+                parse_one(is_include, &sources[start..idx])?;
+                is_include = c == '+';
+                start = idx + 1;
+            }
+        }
+        parse_one(is_include, &sources[start..])?;
+
+        match (include.is_empty(), exclude.is_empty()) {
+            (true, true) => Ok(SourceFilter::Any),
+            (false, true) => Ok(SourceFilter::Include(include)),
+            (true, false) => Ok(SourceFilter::Exclude(exclude)),
+            (false, false) => Err("Can't mix included and excluded multicast sources".to_string()),
+        }
+    }
+
+    fn sources(&self) -> &[IpAddr] {
+        match self {
+            SourceFilter::Any => &[],
+            SourceFilter::Include(sources) | SourceFilter::Exclude(sources) => sources,
+        }
+    }
+
+    /// Checks that all the sources are from the same family as the `group`.
+    pub fn check_family(&self, group: &IpAddr) -> Result<(), String> {
+        if let Some(source) = self
+            .sources()
+            .iter()
+            .find(|source| source.is_ipv4() != group.is_ipv4())
+        {
+            return Err(format!(
+                "Source address {} doesn't match multicast group {} family",
+                source, group
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves a comma separated list of interface names or indices.
+///
+/// An empty list means the interface is chosen by the system, which is
+/// represented by the single index 0.
+pub fn parse_interfaces(ifaces: Option<&str>) -> Result<Vec<u32>, String> {
+    let mut res = Vec::new();
+
+    for iface in ifaces
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|iface| !iface.is_empty())
+    {
+        res.push(interface_index(iface)?);
+    }
+
+    if res.is_empty() {
+        res.push(0);
+    }
+
+    Ok(res)
+}
+
+fn interface_index(iface: &str) -> Result<u32, String> {
+    if let Ok(idx) = iface.parse::<u32>() {
+        return Ok(idx);
+    }
+
+    #[cfg(unix)]
+    {
+        let name = std::ffi::CString::new(iface)
+            .map_err(|_| format!("Invalid interface name '{}'", iface))?;
+        let idx = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if idx == 0 {
+            return Err(format!(
+                "Unknown interface '{}': {}",
+                iface,
+                io::Error::last_os_error()
+            ));
+        }
+
+        Ok(idx)
+    }
+
+    #[cfg(not(unix))]
+    {
+        Err(format!(
+            "Unknown interface '{}': only interface indices are supported on this platform",
+            iface
+        ))
+    }
+}
+
+/// Joins the multicast `group` on the interface with index `iface`.
+pub fn join(
+    socket: &UdpSocket,
+    group: &IpAddr,
+    iface: u32,
+    filter: &SourceFilter,
+) -> io::Result<()> {
+    match filter {
+        SourceFilter::Any => join_any_source(socket, group, iface),
+        SourceFilter::Include(sources) => {
+            for source in sources {
+                sys::source_group_op(socket, sys::MCAST_JOIN_SOURCE_GROUP, group, source, iface)?;
+            }
+
+            Ok(())
+        }
+        SourceFilter::Exclude(sources) => {
+            join_any_source(socket, group, iface)?;
+            for source in sources {
+                sys::source_group_op(socket, sys::MCAST_BLOCK_SOURCE, group, source, iface)?;
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Leaves the multicast `group` previously joined with [`join`].
+pub fn leave(
+    socket: &UdpSocket,
+    group: &IpAddr,
+    iface: u32,
+    filter: &SourceFilter,
+) -> io::Result<()> {
+    match filter {
+        SourceFilter::Include(sources) => {
+            for source in sources {
+                sys::source_group_op(socket, sys::MCAST_LEAVE_SOURCE_GROUP, group, source, iface)?;
+            }
+
+            Ok(())
+        }
+        // Leaving the group also drops the blocked sources
+        SourceFilter::Any | SourceFilter::Exclude(_) => match group {
+            IpAddr::V4(group) => {
+                socket2::SockRef::from(socket).leave_multicast_v4_n(group, &v4_interface(iface))
+            }
+            IpAddr::V6(group) => socket.leave_multicast_v6(group, iface),
+        },
+    }
+}
+
+/// Selects the interface with index `iface` for outgoing multicast packets.
+pub fn set_interface(socket: &UdpSocket, ipv4: bool, iface: u32) -> io::Result<()> {
+    if ipv4 {
+        sys::set_multicast_if_v4(socket, iface)
+    } else {
+        socket2::SockRef::from(socket).set_multicast_if_v6(iface)
+    }
+}
+
+fn join_any_source(socket: &UdpSocket, group: &IpAddr, iface: u32) -> io::Result<()> {
+    match group {
+        IpAddr::V4(group) => {
+            socket2::SockRef::from(socket).join_multicast_v4_n(group, &v4_interface(iface))
+        }
+        IpAddr::V6(group) => socket.join_multicast_v6(group, iface),
+    }
+}
+
+fn v4_interface(iface: u32) -> socket2::InterfaceIndexOrAddress {
+    if iface == 0 {
+        socket2::InterfaceIndexOrAddress::Address(Ipv4Addr::UNSPECIFIED)
+    } else {
+        socket2::InterfaceIndexOrAddress::Index(iface)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod sys {
+    use std::io;
+    use std::mem;
+    use std::net::{IpAddr, SocketAddr, UdpSocket};
+    use std::os::unix::io::AsRawFd;
+
+    // From <linux/in.h>
+    pub const MCAST_BLOCK_SOURCE: libc::c_int = 43;
+    pub const MCAST_JOIN_SOURCE_GROUP: libc::c_int = 46;
+    pub const MCAST_LEAVE_SOURCE_GROUP: libc::c_int = 47;
+
+    #[repr(C)]
+    struct GroupSourceReq {
+        gsr_interface: u32,
+        gsr_group: libc::sockaddr_storage,
+        gsr_source: libc::sockaddr_storage,
+    }
+
+    fn to_storage(addr: &IpAddr) -> libc::sockaddr_storage {
+        let addr = socket2::SockAddr::from(SocketAddr::new(*addr, 0));
+        unsafe {
+            let mut storage: libc::sockaddr_storage = mem::zeroed();
+            std::ptr::copy_nonoverlapping(
+                addr.as_ptr() as *const u8,
+                &mut storage as *mut libc::sockaddr_storage as *mut u8,
+                addr.len() as usize,
+            );
+            storage
+        }
+    }
+
+    fn setsockopt<T>(
+        socket: &UdpSocket,
+        level: libc::c_int,
+        name: libc::c_int,
+        value: &T,
+    ) -> io::Result<()> {
+        let res = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                level,
+                name,
+                value as *const T as *const libc::c_void,
+                mem::size_of::<T>() as libc::socklen_t,
+            )
+        };
+
+        if res < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn source_group_op(
+        socket: &UdpSocket,
+        op: libc::c_int,
+        group: &IpAddr,
+        source: &IpAddr,
+        iface: u32,
+    ) -> io::Result<()> {
+        let req = GroupSourceReq {
+            gsr_interface: iface,
+            gsr_group: to_storage(group),
+            gsr_source: to_storage(source),
+        };
+
+        let level = if group.is_ipv4() {
+            libc::IPPROTO_IP
+        } else {
+            libc::IPPROTO_IPV6
+        };
+
+        setsockopt(socket, level, op, &req)
+    }
+
+    pub fn set_multicast_if_v4(socket: &UdpSocket, iface: u32) -> io::Result<()> {
+        let mreqn = libc::ip_mreqn {
+            imr_multiaddr: libc::in_addr { s_addr: 0 },
+            imr_address: libc::in_addr { s_addr: 0 },
+            imr_ifindex: iface as libc::c_int,
+        };
+
+        setsockopt(socket, libc::IPPROTO_IP, libc::IP_MULTICAST_IF, &mreqn)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+mod sys {
+    use std::io;
+    use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+
+    pub const MCAST_BLOCK_SOURCE: i32 = 0;
+    pub const MCAST_JOIN_SOURCE_GROUP: i32 = 1;
+    pub const MCAST_LEAVE_SOURCE_GROUP: i32 = 2;
+
+    pub fn source_group_op(
+        _socket: &UdpSocket,
+        _op: i32,
+        _group: &IpAddr,
+        _source: &IpAddr,
+        _iface: u32,
+    ) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "source-specific multicast is not supported on this platform",
+        ))
+    }
+
+    pub fn set_multicast_if_v4(socket: &UdpSocket, iface: u32) -> io::Result<()> {
+        if iface == 0 {
+            return socket2::SockRef::from(socket).set_multicast_if_v4(&Ipv4Addr::UNSPECIFIED);
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "selecting the IPv4 multicast interface by index is not supported on this platform",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sources() {
+        assert_eq!(SourceFilter::parse("").unwrap(), SourceFilter::Any);
+        assert_eq!(
+            SourceFilter::parse("+10.0.0.1+10.0.0.2").unwrap(),
+            SourceFilter::Include(vec![
+                "10.0.0.1".parse().unwrap(),
+                "10.0.0.2".parse().unwrap()
+            ])
+        );
+        assert_eq!(
+            SourceFilter::parse("10.0.0.1").unwrap(),
+            SourceFilter::Include(vec!["10.0.0.1".parse().unwrap()])
+        );
+        assert_eq!(
+            SourceFilter::parse("-fd00::1, -fd00::2").unwrap(),
+            SourceFilter::Exclude(vec!["fd00::1".parse().unwrap(), "fd00::2".parse().unwrap()])
+        );
+
+        assert!(SourceFilter::parse("+10.0.0.1-10.0.0.2").is_err());
+        assert!(SourceFilter::parse("+239.1.1.1").is_err());
+        assert!(SourceFilter::parse("+not-an-address").is_err());
+    }
+
+    #[test]
+    fn check_family() {
+        let filter = SourceFilter::parse("+10.0.0.1").unwrap();
+        assert!(filter.check_family(&"239.1.1.1".parse().unwrap()).is_ok());
+        assert!(filter.check_family(&"ff3e::1".parse().unwrap()).is_err());
+        assert!(SourceFilter::Any
+            .check_family(&"ff3e::1".parse().unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn parse_ifaces() {
+        assert_eq!(parse_interfaces(None).unwrap(), vec![0]);
+        assert_eq!(parse_interfaces(Some("")).unwrap(), vec![0]);
+        assert_eq!(parse_interfaces(Some("2, 3")).unwrap(), vec![2, 3]);
+        #[cfg(unix)]
+        assert!(parse_interfaces(Some("this-iface-does-not-exist0")).is_err());
+    }
+}
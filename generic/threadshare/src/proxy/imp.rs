@@ -38,7 +38,7 @@ use crate::runtime::{
     Context, PadSink, PadSinkRef, PadSinkWeak, PadSrc, PadSrcRef, PadSrcWeak, Task,
 };
 
-use crate::dataqueue::{DataQueue, DataQueueItem};
+use crate::dataqueue::{DataQueue, DataQueueItem, DataQueueLeaky};
 
 static PROXY_CONTEXTS: Lazy<Mutex<HashMap<String, Weak<Mutex<ProxyContextInner>>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
@@ -48,22 +48,27 @@ static PROXY_SINK_PADS: Lazy<Mutex<HashMap<String, PadSinkWeak>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
 const DEFAULT_PROXY_CONTEXT: &str = "";
+const DEFAULT_BROADCAST: bool = false;
 
 const DEFAULT_MAX_SIZE_BUFFERS: u32 = 200;
 const DEFAULT_MAX_SIZE_BYTES: u32 = 1024 * 1024;
 const DEFAULT_MAX_SIZE_TIME: gst::ClockTime = gst::ClockTime::SECOND;
 const DEFAULT_CONTEXT: &str = "";
 const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
+const DEFAULT_LEAKY: DataQueueLeaky = DataQueueLeaky::No;
+const DEFAULT_SILENT: bool = false;
 
 #[derive(Debug, Clone)]
 struct SettingsSink {
     proxy_context: String,
+    broadcast: bool,
 }
 
 impl Default for SettingsSink {
     fn default() -> Self {
         SettingsSink {
             proxy_context: DEFAULT_PROXY_CONTEXT.into(),
+            broadcast: DEFAULT_BROADCAST,
         }
     }
 }
@@ -76,6 +81,9 @@ struct SettingsSrc {
     context: String,
     context_wait: Duration,
     proxy_context: String,
+    broadcast: bool,
+    leaky: DataQueueLeaky,
+    silent: bool,
 }
 
 impl Default for SettingsSrc {
@@ -87,6 +95,20 @@ impl Default for SettingsSrc {
             context: DEFAULT_CONTEXT.into(),
             context_wait: DEFAULT_CONTEXT_WAIT,
             proxy_context: DEFAULT_PROXY_CONTEXT.into(),
+            broadcast: DEFAULT_BROADCAST,
+            leaky: DEFAULT_LEAKY,
+            silent: DEFAULT_SILENT,
+        }
+    }
+}
+
+impl SettingsSrc {
+    fn dataqueue_leaky(&self) -> DataQueueLeaky {
+        // A subscriber must not block the producer
+        if self.broadcast && self.leaky == DataQueueLeaky::No {
+            DataQueueLeaky::Upstream
+        } else {
+            self.leaky
         }
     }
 }
@@ -106,6 +128,13 @@ impl PendingQueue {
     }
 }
 
+/* A ts-proxysrc subscribed to a broadcasting ts-proxysink */
+#[derive(Debug)]
+struct Subscriber {
+    dataqueue: DataQueue,
+    src_pad: PadSrcWeak,
+}
+
 #[derive(Debug)]
 struct ProxyContextInner {
     name: String,
@@ -114,6 +143,37 @@ struct ProxyContextInner {
     pending_queue: Option<PendingQueue>,
     have_sink: bool,
     have_src: bool,
+    broadcast: bool,
+    subscribers: HashMap<u64, Subscriber>,
+    subscriber_serial: u64,
+    /* Latest sticky events from the producer, for late subscribers */
+    sticky_events: Vec<gst::Event>,
+}
+
+impl ProxyContextInner {
+    /* Dispatches the item to every subscriber. The subscribers are leaky,
+     * so that a slow subscriber never blocks the producer nor the other
+     * subscribers. */
+    fn broadcast_item(&mut self, item: DataQueueItem) {
+        if let DataQueueItem::Event(ref event) = item {
+            match event.type_() {
+                gst::EventType::StreamStart => self.sticky_events.clear(),
+                gst::EventType::FlushStop => self.sticky_events.retain(|ev| {
+                    ev.type_() != gst::EventType::Segment && ev.type_() != gst::EventType::Eos
+                }),
+                _ => (),
+            }
+
+            if event.is_sticky() {
+                self.sticky_events.retain(|ev| ev.type_() != event.type_());
+                self.sticky_events.push(event.clone());
+            }
+        }
+
+        for subscriber in self.subscribers.values() {
+            let _ = subscriber.dataqueue.push(item.clone());
+        }
+    }
 }
 
 impl Drop for ProxyContextInner {
@@ -128,6 +188,7 @@ struct ProxyContext {
     shared: Arc<Mutex<ProxyContextInner>>,
     as_sink: bool,
     name: String,
+    subscriber_id: Option<u64>,
 }
 
 impl ProxyContext {
@@ -136,7 +197,7 @@ impl ProxyContext {
         self.shared.lock().unwrap()
     }
 
-    fn get(name: &str, as_sink: bool) -> Option<Self> {
+    fn get(name: &str, as_sink: bool, broadcast: bool) -> Option<Self> {
         let mut proxy_ctxs = PROXY_CONTEXTS.lock().unwrap();
 
         let mut proxy_ctx = None;
@@ -144,7 +205,10 @@ impl ProxyContext {
             if let Some(shared) = shared_weak.upgrade() {
                 {
                     let shared = shared.lock().unwrap();
-                    if (shared.have_sink && as_sink) || (shared.have_src && !as_sink) {
+                    if shared.broadcast != broadcast
+                        || (shared.have_sink && as_sink)
+                        || (shared.have_src && !as_sink)
+                    {
                         return None;
                     }
                 }
@@ -154,12 +218,13 @@ impl ProxyContext {
                         shared,
                         as_sink,
                         name: name.into(),
+                        subscriber_id: None,
                     };
                     {
                         let mut shared = proxy_ctx.lock_shared();
                         if as_sink {
                             shared.have_sink = true;
-                        } else {
+                        } else if !broadcast {
                             shared.have_src = true;
                         }
                     }
@@ -176,7 +241,11 @@ impl ProxyContext {
                 last_res: Err(gst::FlowError::Flushing),
                 pending_queue: None,
                 have_sink: as_sink,
-                have_src: !as_sink,
+                have_src: !as_sink && !broadcast,
+                broadcast,
+                subscribers: HashMap::new(),
+                subscriber_serial: 0,
+                sticky_events: Vec::new(),
             }));
 
             proxy_ctxs.insert(name.into(), Arc::downgrade(&shared));
@@ -185,11 +254,25 @@ impl ProxyContext {
                 shared,
                 as_sink,
                 name: name.into(),
+                subscriber_id: None,
             });
         }
 
         proxy_ctx
     }
+
+    fn subscribe(&mut self, dataqueue: DataQueue, src_pad: PadSrcWeak) {
+        let mut shared_ctx = self.shared.lock().unwrap();
+        assert!(shared_ctx.broadcast && !self.as_sink);
+
+        let id = shared_ctx.subscriber_serial;
+        shared_ctx.subscriber_serial += 1;
+        shared_ctx
+            .subscribers
+            .insert(id, Subscriber { dataqueue, src_pad });
+
+        self.subscriber_id = Some(id);
+    }
 }
 
 impl Drop for ProxyContext {
@@ -199,6 +282,10 @@ impl Drop for ProxyContext {
             assert!(shared_ctx.have_sink);
             shared_ctx.have_sink = false;
             let _ = shared_ctx.pending_queue.take();
+        } else if shared_ctx.broadcast {
+            if let Some(id) = self.subscriber_id {
+                shared_ctx.subscribers.remove(&id);
+            }
         } else {
             assert!(shared_ctx.have_src);
             shared_ctx.have_src = false;
@@ -265,22 +352,43 @@ impl PadSinkHandler for ProxySinkPadHandler {
 
         gst::debug!(SINK_CAT, obj: pad.gst_pad(), "Handling non-serialized {:?}", event);
 
-        let src_pad = {
+        let (src_pad, subscriber_pads) = {
             let proxy_ctx = proxysink.proxy_ctx.lock().unwrap();
-
-            PROXY_SRC_PADS
-                .lock()
-                .unwrap()
-                .get(&proxy_ctx.as_ref().unwrap().name)
-                .and_then(|src_pad| src_pad.upgrade())
-                .map(|src_pad| src_pad.gst_pad().clone())
+            let proxy_ctx = proxy_ctx.as_ref().unwrap();
+            let shared_ctx = proxy_ctx.lock_shared();
+
+            if shared_ctx.broadcast {
+                let subscriber_pads = shared_ctx
+                    .subscribers
+                    .values()
+                    .filter_map(|subscriber| subscriber.src_pad.upgrade())
+                    .map(|src_pad| src_pad.gst_pad().clone())
+                    .collect::<Vec<_>>();
+
+                (None, Some(subscriber_pads))
+            } else {
+                let src_pad = PROXY_SRC_PADS
+                    .lock()
+                    .unwrap()
+                    .get(&proxy_ctx.name)
+                    .and_then(|src_pad| src_pad.upgrade())
+                    .map(|src_pad| src_pad.gst_pad().clone());
+
+                (src_pad, None)
+            }
         };
 
         if let EventView::FlushStart(..) = event.view() {
             proxysink.stop(element.downcast_ref::<super::ProxySink>().unwrap());
         }
 
-        if let Some(src_pad) = src_pad {
+        if let Some(subscriber_pads) = subscriber_pads {
+            gst::log!(SINK_CAT, obj: pad.gst_pad(), "Forwarding non-serialized {:?} to {} subscribers", event, subscriber_pads.len());
+            for src_pad in subscriber_pads {
+                let _ = src_pad.push_event(event.clone());
+            }
+            true
+        } else if let Some(src_pad) = src_pad {
             gst::log!(SINK_CAT, obj: pad.gst_pad(), "Forwarding non-serialized {:?}", event);
             src_pad.push_event(event)
         } else {
@@ -407,6 +515,11 @@ impl ProxySink {
              * a pending queue if tearing down */
             shared_ctx.last_res?;
 
+            if shared_ctx.broadcast {
+                shared_ctx.broadcast_item(item);
+                return Ok(gst::FlowSuccess::Ok);
+            }
+
             let item = {
                 let ProxyContextInner {
                     ref mut pending_queue,
@@ -510,9 +623,12 @@ impl ProxySink {
     fn prepare(&self, element: &super::ProxySink) -> Result<(), gst::ErrorMessage> {
         gst::debug!(SINK_CAT, obj: element, "Preparing");
 
-        let proxy_context = self.settings.lock().unwrap().proxy_context.to_string();
+        let (proxy_context, broadcast) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.proxy_context.to_string(), settings.broadcast)
+        };
 
-        let proxy_ctx = ProxyContext::get(&proxy_context, true).ok_or_else(|| {
+        let proxy_ctx = ProxyContext::get(&proxy_context, true, broadcast).ok_or_else(|| {
             gst::error_msg!(
                 gst::ResourceError::OpenRead,
                 ["Failed to create or get ProxyContext"]
@@ -589,11 +705,18 @@ impl ObjectSubclass for ProxySink {
 impl ObjectImpl for ProxySink {
     fn properties() -> &'static [glib::ParamSpec] {
         static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
-            vec![glib::ParamSpecString::builder("proxy-context")
-                .nick("Proxy Context")
-                .blurb("Context name of the proxy to share with")
-                .default_value(Some(DEFAULT_PROXY_CONTEXT))
-                .build()]
+            vec![
+                glib::ParamSpecString::builder("proxy-context")
+                    .nick("Proxy Context")
+                    .blurb("Context name of the proxy to share with")
+                    .default_value(Some(DEFAULT_PROXY_CONTEXT))
+                    .build(),
+                glib::ParamSpecBoolean::builder("broadcast")
+                    .nick("Broadcast")
+                    .blurb("Let several ts-proxysrc with broadcast=true subscribe to the proxy context")
+                    .default_value(DEFAULT_BROADCAST)
+                    .build(),
+            ]
         });
 
         PROPERTIES.as_ref()
@@ -614,6 +737,9 @@ impl ObjectImpl for ProxySink {
                     .expect("type checked upstream")
                     .unwrap_or_else(|| DEFAULT_PROXY_CONTEXT.into());
             }
+            "broadcast" => {
+                settings.broadcast = value.get().expect("type checked upstream");
+            }
             _ => unimplemented!(),
         }
     }
@@ -622,6 +748,7 @@ impl ObjectImpl for ProxySink {
         let settings = self.settings.lock().unwrap();
         match pspec.name() {
             "proxy-context" => settings.proxy_context.to_value(),
+            "broadcast" => settings.broadcast.to_value(),
             _ => unimplemented!(),
         }
     }
@@ -758,6 +885,17 @@ impl PadSrcHandler for ProxySrcPadHandler {
             _ => (),
         }
 
+        if proxysrc.settings.lock().unwrap().broadcast
+            && matches!(
+                event.type_(),
+                gst::EventType::FlushStart | gst::EventType::FlushStop
+            )
+        {
+            // A subscriber must not flush the producer nor the other subscribers
+            gst::log!(SRC_CAT, obj: pad.gst_pad(), "Not forwarding {:?} to broadcasting producer", event);
+            return true;
+        }
+
         if let Some(sink_pad) = sink_pad {
             gst::log!(SRC_CAT, obj: pad.gst_pad(), "Forwarding {:?}", event);
             sink_pad.push_event(event)
@@ -819,11 +957,27 @@ impl PadSrcHandler for ProxySrcPadHandler {
 struct ProxySrcTask {
     element: super::ProxySrc,
     dataqueue: DataQueue,
+    broadcast: bool,
 }
 
 impl ProxySrcTask {
-    fn new(element: super::ProxySrc, dataqueue: DataQueue) -> Self {
-        ProxySrcTask { element, dataqueue }
+    fn new(element: super::ProxySrc, dataqueue: DataQueue, broadcast: bool) -> Self {
+        ProxySrcTask {
+            element,
+            dataqueue,
+            broadcast,
+        }
+    }
+
+    fn set_last_res(
+        &self,
+        shared_ctx: &mut ProxyContextInner,
+        res: Result<gst::FlowSuccess, gst::FlowError>,
+    ) {
+        // A broadcasting producer doesn't depend on the outcome of its subscribers
+        if !self.broadcast {
+            shared_ctx.last_res = res;
+        }
     }
 
     async fn push_item(&self, item: DataQueueItem) -> Result<(), gst::FlowError> {
@@ -866,7 +1020,7 @@ impl TaskImpl for ProxySrcTask {
             let proxy_ctx = proxysrc.proxy_ctx.lock().unwrap();
             let mut shared_ctx = proxy_ctx.as_ref().unwrap().lock_shared();
 
-            shared_ctx.last_res = Ok(gst::FlowSuccess::Ok);
+            self.set_last_res(&mut shared_ctx, Ok(gst::FlowSuccess::Ok));
 
             if let Some(pending_queue) = shared_ctx.pending_queue.as_mut() {
                 pending_queue.notify_more_queue_space();
@@ -874,6 +1028,13 @@ impl TaskImpl for ProxySrcTask {
 
             self.dataqueue.start();
 
+            if self.broadcast {
+                // Catch up with the producer's stream
+                for event in shared_ctx.sticky_events.iter() {
+                    let _ = self.dataqueue.push(DataQueueItem::Event(event.clone()));
+                }
+            }
+
             gst::log!(SRC_CAT, obj: &self.element, "Task started");
             Ok(())
         }
@@ -899,19 +1060,19 @@ impl TaskImpl for ProxySrcTask {
                     gst::log!(SRC_CAT, obj: &self.element, "Successfully pushed item");
                     let proxy_ctx = proxysrc.proxy_ctx.lock().unwrap();
                     let mut shared_ctx = proxy_ctx.as_ref().unwrap().lock_shared();
-                    shared_ctx.last_res = Ok(gst::FlowSuccess::Ok);
+                    self.set_last_res(&mut shared_ctx, Ok(gst::FlowSuccess::Ok));
                 }
                 Err(gst::FlowError::Flushing) => {
                     gst::debug!(SRC_CAT, obj: &self.element, "Flushing");
                     let proxy_ctx = proxysrc.proxy_ctx.lock().unwrap();
                     let mut shared_ctx = proxy_ctx.as_ref().unwrap().lock_shared();
-                    shared_ctx.last_res = Err(gst::FlowError::Flushing);
+                    self.set_last_res(&mut shared_ctx, Err(gst::FlowError::Flushing));
                 }
                 Err(gst::FlowError::Eos) => {
                     gst::debug!(SRC_CAT, obj: &self.element, "EOS");
                     let proxy_ctx = proxysrc.proxy_ctx.lock().unwrap();
                     let mut shared_ctx = proxy_ctx.as_ref().unwrap().lock_shared();
-                    shared_ctx.last_res = Err(gst::FlowError::Eos);
+                    self.set_last_res(&mut shared_ctx, Err(gst::FlowError::Eos));
                 }
                 Err(err) => {
                     gst::error!(SRC_CAT, obj: &self.element, "Got error {}", err);
@@ -923,7 +1084,7 @@ impl TaskImpl for ProxySrcTask {
                     );
                     let proxy_ctx = proxysrc.proxy_ctx.lock().unwrap();
                     let mut shared_ctx = proxy_ctx.as_ref().unwrap().lock_shared();
-                    shared_ctx.last_res = Err(err);
+                    self.set_last_res(&mut shared_ctx, Err(err));
                 }
             }
 
@@ -943,7 +1104,7 @@ impl TaskImpl for ProxySrcTask {
             self.dataqueue.clear();
             self.dataqueue.stop();
 
-            shared_ctx.last_res = Err(gst::FlowError::Flushing);
+            self.set_last_res(&mut shared_ctx, Err(gst::FlowError::Flushing));
 
             if let Some(mut pending_queue) = shared_ctx.pending_queue.take() {
                 pending_queue.notify_more_queue_space();
@@ -965,7 +1126,7 @@ impl TaskImpl for ProxySrcTask {
 
             self.dataqueue.clear();
 
-            shared_ctx.last_res = Err(gst::FlowError::Flushing);
+            self.set_last_res(&mut shared_ctx, Err(gst::FlowError::Flushing));
 
             gst::log!(SRC_CAT, obj: &self.element, "Task flush started");
             Ok(())
@@ -997,7 +1158,8 @@ impl ProxySrc {
 
         let settings = self.settings.lock().unwrap().clone();
 
-        let proxy_ctx = ProxyContext::get(&settings.proxy_context, false).ok_or_else(|| {
+        let mut proxy_ctx = ProxyContext::get(&settings.proxy_context, false, settings.broadcast)
+            .ok_or_else(|| {
             gst::error_msg!(
                 gst::ResourceError::OpenRead,
                 ["Failed to create get shared_state"]
@@ -1030,8 +1192,12 @@ impl ProxySrc {
                 Some(settings.max_size_time)
             },
         );
+        dataqueue.set_leaky(settings.dataqueue_leaky());
+        dataqueue.set_silent(settings.silent);
 
-        {
+        if settings.broadcast {
+            proxy_ctx.subscribe(dataqueue.clone(), self.src_pad.downgrade());
+        } else {
             let mut shared_ctx = proxy_ctx.lock_shared();
             shared_ctx.dataqueue = Some(dataqueue.clone());
 
@@ -1044,7 +1210,10 @@ impl ProxySrc {
         *self.dataqueue.lock().unwrap() = Some(dataqueue.clone());
 
         self.task
-            .prepare(ProxySrcTask::new(element.clone(), dataqueue), ts_ctx)
+            .prepare(
+                ProxySrcTask::new(element.clone(), dataqueue, settings.broadcast),
+                ts_ctx,
+            )
             .block_on()?;
 
         gst::debug!(SRC_CAT, obj: element, "Prepared");
@@ -1057,8 +1226,10 @@ impl ProxySrc {
 
         {
             let settings = self.settings.lock().unwrap();
-            let mut proxy_src_pads = PROXY_SRC_PADS.lock().unwrap();
-            proxy_src_pads.remove(&settings.proxy_context);
+            if !settings.broadcast {
+                let mut proxy_src_pads = PROXY_SRC_PADS.lock().unwrap();
+                proxy_src_pads.remove(&settings.proxy_context);
+            }
         }
 
         self.task.unprepare().block_on().unwrap();
@@ -1147,12 +1318,73 @@ impl ObjectImpl for ProxySrc {
                     .maximum(u64::MAX - 1)
                     .default_value(DEFAULT_MAX_SIZE_TIME.nseconds())
                     .build(),
+                glib::ParamSpecBoolean::builder("broadcast")
+                    .nick("Broadcast")
+                    .blurb("Subscribe to a ts-proxysink with broadcast=true, along with other ts-proxysrc")
+                    .default_value(DEFAULT_BROADCAST)
+                    .build(),
+                glib::ParamSpecEnum::builder::<DataQueueLeaky>("leaky", DEFAULT_LEAKY)
+                    .nick("Leaky")
+                    .blurb("Where the queue leaks, if at all (upstream is used when no is set in broadcast mode)")
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("silent")
+                    .nick("Silent")
+                    .blurb("Don't emit queue signals")
+                    .default_value(DEFAULT_SILENT)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt64::builder("dropped")
+                    .nick("Dropped")
+                    .blurb("Number of buffers dropped because the queue was full")
+                    .read_only()
+                    .build(),
             ]
         });
 
         PROPERTIES.as_ref()
     }
 
+    fn signals() -> &'static [glib::subclass::Signal] {
+        static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
+            vec![
+                /**
+                 * ts-proxysrc::overrun:
+                 * @self: A ts-proxysrc
+                 *
+                 * Reports that the queue became full, i.e. one of the max-size limits was reached.
+                 * Emitted from the ts-proxysink streaming thread.
+                 */
+                glib::subclass::Signal::builder("overrun").build(),
+                /**
+                 * ts-proxysrc::underrun:
+                 * @self: A ts-proxysrc
+                 *
+                 * Reports that the queue became empty. Emitted from the Context thread.
+                 */
+                glib::subclass::Signal::builder("underrun").build(),
+                /**
+                 * ts-proxysrc::running:
+                 * @self: A ts-proxysrc
+                 *
+                 * Reports that data is available again after an underrun or that there is
+                 * room again after an overrun. Emitted from the Context thread.
+                 */
+                glib::subclass::Signal::builder("running").build(),
+                /**
+                 * ts-proxysrc::pushing:
+                 * @self: A ts-proxysrc
+                 *
+                 * Reports that the queue starts pushing data again after an underrun.
+                 * Emitted from the Context thread.
+                 */
+                glib::subclass::Signal::builder("pushing").build(),
+            ]
+        });
+
+        SIGNALS.as_ref()
+    }
+
     fn set_property(
         &self,
         _obj: &Self::Type,
@@ -1189,11 +1421,36 @@ impl ObjectImpl for ProxySrc {
                     .expect("type checked upstream")
                     .unwrap_or_else(|| DEFAULT_PROXY_CONTEXT.into());
             }
+            "broadcast" => {
+                settings.broadcast = value.get().expect("type checked upstream");
+            }
+            "leaky" => {
+                settings.leaky = value.get().expect("type checked upstream");
+                if let Some(dataqueue) = self.dataqueue.lock().unwrap().as_ref() {
+                    dataqueue.set_leaky(settings.dataqueue_leaky());
+                }
+            }
+            "silent" => {
+                settings.silent = value.get().expect("type checked upstream");
+                if let Some(dataqueue) = self.dataqueue.lock().unwrap().as_ref() {
+                    dataqueue.set_silent(settings.silent);
+                }
+            }
             _ => unimplemented!(),
         }
     }
 
     fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        if pspec.name() == "dropped" {
+            return self
+                .dataqueue
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map_or(0, DataQueue::dropped)
+                .to_value();
+        }
+
         let settings = self.settings.lock().unwrap();
         match pspec.name() {
             "max-size-buffers" => settings.max_size_buffers.to_value(),
@@ -1202,6 +1459,9 @@ impl ObjectImpl for ProxySrc {
             "context" => settings.context.to_value(),
             "context-wait" => (settings.context_wait.as_millis() as u32).to_value(),
             "proxy-context" => settings.proxy_context.to_value(),
+            "broadcast" => settings.broadcast.to_value(),
+            "leaky" => settings.leaky.to_value(),
+            "silent" => settings.silent.to_value(),
             _ => unimplemented!(),
         }
     }
@@ -35,13 +35,15 @@ use std::{u32, u64};
 use crate::runtime::prelude::*;
 use crate::runtime::{Context, PadSink, PadSinkRef, PadSrc, PadSrcRef, Task};
 
-use crate::dataqueue::{DataQueue, DataQueueItem};
+use crate::dataqueue::{DataQueue, DataQueueItem, DataQueueLeaky};
 
 const DEFAULT_MAX_SIZE_BUFFERS: u32 = 200;
 const DEFAULT_MAX_SIZE_BYTES: u32 = 1024 * 1024;
 const DEFAULT_MAX_SIZE_TIME: gst::ClockTime = gst::ClockTime::SECOND;
 const DEFAULT_CONTEXT: &str = "";
 const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
+const DEFAULT_LEAKY: DataQueueLeaky = DataQueueLeaky::No;
+const DEFAULT_SILENT: bool = false;
 
 #[derive(Debug, Clone)]
 struct Settings {
@@ -50,6 +52,8 @@ struct Settings {
     max_size_time: gst::ClockTime,
     context: String,
     context_wait: Duration,
+    leaky: DataQueueLeaky,
+    silent: bool,
 }
 
 impl Default for Settings {
@@ -60,6 +64,8 @@ impl Default for Settings {
             max_size_time: DEFAULT_MAX_SIZE_TIME,
             context: DEFAULT_CONTEXT.into(),
             context_wait: DEFAULT_CONTEXT_WAIT,
+            leaky: DEFAULT_LEAKY,
+            silent: DEFAULT_SILENT,
         }
     }
 }
@@ -477,6 +483,7 @@ impl Queue {
                 while let Some(item) = items.pop_front() {
                     if let Err(item) = dataqueue.push(item) {
                         failed_item = Some(item);
+                        break;
                     }
                 }
 
@@ -498,10 +505,11 @@ impl Queue {
     async fn schedule_pending_queue(&self, element: &super::Queue) {
         loop {
             let more_queue_space_receiver = {
-                let dataqueue = self.dataqueue.lock().unwrap();
-                if dataqueue.is_none() {
-                    return;
-                }
+                // Don't keep the lock while pushing, the DataQueue may emit signals
+                let dataqueue = match self.dataqueue.lock().unwrap().clone() {
+                    Some(dataqueue) => dataqueue,
+                    None => return,
+                };
                 let mut pending_queue_grd = self.pending_queue.lock().unwrap();
 
                 gst::log!(CAT, obj: element, "Trying to empty pending queue");
@@ -509,8 +517,9 @@ impl Queue {
                 if let Some(pending_queue) = pending_queue_grd.as_mut() {
                     let mut failed_item = None;
                     while let Some(item) = pending_queue.items.pop_front() {
-                        if let Err(item) = dataqueue.as_ref().unwrap().push(item) {
+                        if let Err(item) = dataqueue.push(item) {
                             failed_item = Some(item);
+                            break;
                         }
                     }
 
@@ -542,15 +551,15 @@ impl Queue {
         item: DataQueueItem,
     ) -> Result<gst::FlowSuccess, gst::FlowError> {
         let wait_fut = {
-            let dataqueue = self.dataqueue.lock().unwrap();
-            let dataqueue = dataqueue.as_ref().ok_or_else(|| {
+            // Don't keep the lock while pushing, the DataQueue may emit signals
+            let dataqueue = self.dataqueue.lock().unwrap().clone().ok_or_else(|| {
                 gst::error!(CAT, obj: element, "No DataQueue");
                 gst::FlowError::Error
             })?;
 
             let mut pending_queue = self.pending_queue.lock().unwrap();
 
-            if let Err(item) = self.queue_until_full(dataqueue, &mut pending_queue, item) {
+            if let Err(item) = self.queue_until_full(&dataqueue, &mut pending_queue, item) {
                 if pending_queue
                     .as_ref()
                     .map(|pq| !pq.scheduled)
@@ -628,6 +637,8 @@ impl Queue {
                 Some(settings.max_size_time)
             },
         );
+        dataqueue.set_leaky(settings.leaky);
+        dataqueue.set_silent(settings.silent);
 
         *self.dataqueue.lock().unwrap() = Some(dataqueue.clone());
 
@@ -732,12 +743,67 @@ impl ObjectImpl for Queue {
                     .maximum(u64::MAX - 1)
                     .default_value(DEFAULT_MAX_SIZE_TIME.nseconds())
                     .build(),
+                glib::ParamSpecEnum::builder::<DataQueueLeaky>("leaky", DEFAULT_LEAKY)
+                    .nick("Leaky")
+                    .blurb("Where the queue leaks, if at all")
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("silent")
+                    .nick("Silent")
+                    .blurb("Don't emit queue signals")
+                    .default_value(DEFAULT_SILENT)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt64::builder("dropped")
+                    .nick("Dropped")
+                    .blurb("Number of buffers dropped because the queue was full")
+                    .read_only()
+                    .build(),
             ]
         });
 
         PROPERTIES.as_ref()
     }
 
+    fn signals() -> &'static [glib::subclass::Signal] {
+        static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
+            vec![
+                /**
+                 * ts-queue::overrun:
+                 * @self: A ts-queue
+                 *
+                 * Reports that the queue became full, i.e. one of the max-size limits was reached.
+                 */
+                glib::subclass::Signal::builder("overrun").build(),
+                /**
+                 * ts-queue::underrun:
+                 * @self: A ts-queue
+                 *
+                 * Reports that the queue became empty. Emitted from the Context thread.
+                 */
+                glib::subclass::Signal::builder("underrun").build(),
+                /**
+                 * ts-queue::running:
+                 * @self: A ts-queue
+                 *
+                 * Reports that data is available again after an underrun or that there is
+                 * room again after an overrun. Emitted from the Context thread.
+                 */
+                glib::subclass::Signal::builder("running").build(),
+                /**
+                 * ts-queue::pushing:
+                 * @self: A ts-queue
+                 *
+                 * Reports that the queue starts pushing data again after an underrun.
+                 * Emitted from the Context thread.
+                 */
+                glib::subclass::Signal::builder("pushing").build(),
+            ]
+        });
+
+        SIGNALS.as_ref()
+    }
+
     fn set_property(
         &self,
         _obj: &Self::Type,
@@ -768,11 +834,33 @@ impl ObjectImpl for Queue {
                     value.get::<u32>().expect("type checked upstream").into(),
                 );
             }
+            "leaky" => {
+                settings.leaky = value.get().expect("type checked upstream");
+                if let Some(dataqueue) = self.dataqueue.lock().unwrap().as_ref() {
+                    dataqueue.set_leaky(settings.leaky);
+                }
+            }
+            "silent" => {
+                settings.silent = value.get().expect("type checked upstream");
+                if let Some(dataqueue) = self.dataqueue.lock().unwrap().as_ref() {
+                    dataqueue.set_silent(settings.silent);
+                }
+            }
             _ => unimplemented!(),
         }
     }
 
     fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        if pspec.name() == "dropped" {
+            return self
+                .dataqueue
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map_or(0, DataQueue::dropped)
+                .to_value();
+        }
+
         let settings = self.settings.lock().unwrap();
         match pspec.name() {
             "max-size-buffers" => settings.max_size_buffers.to_value(),
@@ -780,6 +868,8 @@ impl ObjectImpl for Queue {
             "max-size-time" => settings.max_size_time.nseconds().to_value(),
             "context" => settings.context.to_value(),
             "context-wait" => (settings.context_wait.as_millis() as u32).to_value(),
+            "leaky" => settings.leaky.to_value(),
+            "silent" => settings.silent.to_value(),
             _ => unimplemented!(),
         }
     }
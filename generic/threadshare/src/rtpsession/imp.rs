@@ -0,0 +1,1407 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use futures::future::BoxFuture;
+use futures::prelude::*;
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_rtp::RTPBuffer;
+
+use once_cell::sync::Lazy;
+
+use rand::Rng;
+
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::runtime::prelude::*;
+use crate::runtime::{self, Context, PadSink, PadSinkRef, PadSrc, PadSrcRef, PadSrcWeak, Task};
+
+use super::rtcp::{self, Packet, ReportBlock};
+use super::source::{self, LocalSender, ReceiverSource};
+
+const DEFAULT_CONTEXT: &str = "";
+const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
+const DEFAULT_RTCP_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_ADD_REFERENCE_TIMESTAMP_META: bool = false;
+
+const MAX_CNAME_LEN: usize = 255;
+
+static NTP_CAPS: Lazy<gst::Caps> = Lazy::new(|| gst::Caps::builder("timestamp/x-ntp").build());
+
+#[derive(Debug, Clone)]
+struct Settings {
+    context: String,
+    context_wait: Duration,
+    rtcp_interval: Duration,
+    cname: String,
+    internal_ssrc: u32,
+    add_reference_timestamp_meta: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            context: DEFAULT_CONTEXT.into(),
+            context_wait: DEFAULT_CONTEXT_WAIT,
+            rtcp_interval: DEFAULT_RTCP_INTERVAL,
+            cname: format!(
+                "user{}@host-{:x}",
+                rand::random::<u32>(),
+                rand::random::<u32>()
+            ),
+            internal_ssrc: rand::random(),
+            add_reference_timestamp_meta: DEFAULT_ADD_REFERENCE_TIMESTAMP_META,
+        }
+    }
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ts-rtpsession",
+        gst::DebugColorFlags::empty(),
+        Some("Thread-sharing RTP session"),
+    )
+});
+
+fn clock_rate_from_caps(caps: &gst::CapsRef) -> Option<u32> {
+    caps.structure(0)?
+        .get::<i32>("clock-rate")
+        .ok()
+        .filter(|rate| *rate > 0)
+        .map(|rate| rate as u32)
+}
+
+/// Adapts an event received on `recv_rtp_sink` to the `recv_rtp_src` pad for `ssrc`.
+fn event_for_ssrc(event: &gst::Event, ssrc: u32) -> gst::Event {
+    use gst::EventView;
+
+    match event.view() {
+        EventView::StreamStart(e) => {
+            let stream_id = format!("{}/{}", e.stream_id(), ssrc);
+            let mut builder = gst::event::StreamStart::builder(&stream_id);
+            if let Some(group_id) = e.group_id() {
+                builder = builder.group_id(group_id);
+            }
+            builder.build()
+        }
+        EventView::Caps(e) => {
+            let mut caps = e.caps().to_owned();
+            if let Some(s) = caps.make_mut().structure_mut(0) {
+                s.set("ssrc", ssrc);
+            }
+            gst::event::Caps::new(&caps)
+        }
+        _ => event.clone(),
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RecvRtpSinkPadHandler;
+
+impl PadSinkHandler for RecvRtpSinkPadHandler {
+    type ElementImpl = RtpSession;
+
+    fn sink_chain(
+        &self,
+        _pad: &PadSinkRef,
+        _rtpsession: &RtpSession,
+        element: &gst::Element,
+        buffer: gst::Buffer,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let element = element.clone().downcast::<super::RtpSession>().unwrap();
+        async move { element.imp().handle_recv_rtp(&element, buffer).await }.boxed()
+    }
+
+    fn sink_chain_list(
+        &self,
+        _pad: &PadSinkRef,
+        _rtpsession: &RtpSession,
+        element: &gst::Element,
+        list: gst::BufferList,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let element = element.clone().downcast::<super::RtpSession>().unwrap();
+        async move {
+            let rtpsession = element.imp();
+            for buffer in list.iter_owned() {
+                rtpsession.handle_recv_rtp(&element, buffer).await?;
+            }
+
+            Ok(gst::FlowSuccess::Ok)
+        }
+        .boxed()
+    }
+
+    fn sink_event(
+        &self,
+        pad: &PadSinkRef,
+        rtpsession: &RtpSession,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling non-serialized {:?}", event);
+
+        let pads = rtpsession.recv_rtp_src_pads();
+        for pad in pads {
+            if let Some(pad) = pad.upgrade() {
+                let _ = pad.gst_pad().push_event(event.clone());
+            }
+        }
+
+        true
+    }
+
+    fn sink_event_serialized(
+        &self,
+        pad: &PadSinkRef,
+        _rtpsession: &RtpSession,
+        element: &gst::Element,
+        event: gst::Event,
+    ) -> BoxFuture<'static, bool> {
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling serialized {:?}", event);
+
+        let element = element.clone().downcast::<super::RtpSession>().unwrap();
+        async move {
+            let rtpsession = element.imp();
+
+            if let gst::EventView::Caps(e) = event.view() {
+                rtpsession.state.lock().unwrap().recv_clock_rate = clock_rate_from_caps(e.caps());
+            }
+
+            let ssrcs = rtpsession
+                .recv_rtp_src_pads
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(ssrc, pad)| (*ssrc, pad.downgrade()))
+                .collect::<Vec<_>>();
+
+            for (ssrc, pad) in ssrcs {
+                if let Some(pad) = pad.upgrade() {
+                    pad.push_event(event_for_ssrc(&event, ssrc)).await;
+                }
+            }
+
+            true
+        }
+        .boxed()
+    }
+
+    fn sink_query(
+        &self,
+        pad: &PadSinkRef,
+        _rtpsession: &RtpSession,
+        _element: &gst::Element,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", query);
+
+        match query.view_mut() {
+            gst::QueryViewMut::Caps(q) => {
+                let caps = pad.gst_pad().pad_template_caps();
+                let caps = q
+                    .filter()
+                    .map(|f| f.intersect_with_mode(&caps, gst::CapsIntersectMode::First))
+                    .unwrap_or(caps);
+                q.set_result(&caps);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RecvRtpSrcPadHandler;
+
+impl PadSrcHandler for RecvRtpSrcPadHandler {
+    type ElementImpl = RtpSession;
+
+    fn src_event(
+        &self,
+        pad: &PadSrcRef,
+        rtpsession: &RtpSession,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        gst::log!(CAT, obj: pad.gst_pad(), "Forwarding {:?} upstream", event);
+        rtpsession.recv_rtp_sink.gst_pad().push_event(event)
+    }
+
+    fn src_query(
+        &self,
+        pad: &PadSrcRef,
+        rtpsession: &RtpSession,
+        _element: &gst::Element,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", query);
+
+        if query.is_serialized() {
+            false
+        } else {
+            rtpsession.recv_rtp_sink.gst_pad().peer_query(query)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RecvRtcpSinkPadHandler;
+
+impl PadSinkHandler for RecvRtcpSinkPadHandler {
+    type ElementImpl = RtpSession;
+
+    fn sink_chain(
+        &self,
+        _pad: &PadSinkRef,
+        rtpsession: &RtpSession,
+        element: &gst::Element,
+        buffer: gst::Buffer,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let element = element.clone().downcast::<super::RtpSession>().unwrap();
+        let res = rtpsession.handle_recv_rtcp(&element, buffer);
+        future::ready(res).boxed()
+    }
+
+    fn sink_chain_list(
+        &self,
+        _pad: &PadSinkRef,
+        rtpsession: &RtpSession,
+        element: &gst::Element,
+        list: gst::BufferList,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let element = element.clone().downcast::<super::RtpSession>().unwrap();
+        let res = list
+            .iter_owned()
+            .try_for_each(|buffer| rtpsession.handle_recv_rtcp(&element, buffer).map(drop))
+            .map(|_| gst::FlowSuccess::Ok);
+        future::ready(res).boxed()
+    }
+
+    fn sink_event(
+        &self,
+        pad: &PadSinkRef,
+        _rtpsession: &RtpSession,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        gst::log!(CAT, obj: pad.gst_pad(), "Dropping {:?}", event);
+        true
+    }
+
+    fn sink_event_serialized(
+        &self,
+        pad: &PadSinkRef,
+        _rtpsession: &RtpSession,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> BoxFuture<'static, bool> {
+        // RTCP is consumed by the session
+        gst::log!(CAT, obj: pad.gst_pad(), "Dropping {:?}", event);
+        future::ready(true).boxed()
+    }
+
+    fn sink_query(
+        &self,
+        pad: &PadSinkRef,
+        _rtpsession: &RtpSession,
+        _element: &gst::Element,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", query);
+
+        match query.view_mut() {
+            gst::QueryViewMut::Caps(q) => {
+                let caps = pad.gst_pad().pad_template_caps();
+                let caps = q
+                    .filter()
+                    .map(|f| f.intersect_with_mode(&caps, gst::CapsIntersectMode::First))
+                    .unwrap_or(caps);
+                q.set_result(&caps);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SendRtpSinkPadHandler;
+
+impl PadSinkHandler for SendRtpSinkPadHandler {
+    type ElementImpl = RtpSession;
+
+    fn sink_chain(
+        &self,
+        _pad: &PadSinkRef,
+        rtpsession: &RtpSession,
+        element: &gst::Element,
+        buffer: gst::Buffer,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let element = element.clone().downcast::<super::RtpSession>().unwrap();
+        rtpsession.handle_send_rtp(&element, &buffer);
+
+        let pad_weak = rtpsession.send_rtp_src.downgrade();
+        async move {
+            let pad = pad_weak.upgrade().ok_or(gst::FlowError::Flushing)?;
+            pad.push(buffer).await
+        }
+        .boxed()
+    }
+
+    fn sink_chain_list(
+        &self,
+        _pad: &PadSinkRef,
+        rtpsession: &RtpSession,
+        element: &gst::Element,
+        list: gst::BufferList,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let element = element.clone().downcast::<super::RtpSession>().unwrap();
+        for buffer in list.iter() {
+            rtpsession.handle_send_rtp(&element, buffer);
+        }
+
+        let pad_weak = rtpsession.send_rtp_src.downgrade();
+        async move {
+            let pad = pad_weak.upgrade().ok_or(gst::FlowError::Flushing)?;
+            pad.push_list(list).await
+        }
+        .boxed()
+    }
+
+    fn sink_event(
+        &self,
+        pad: &PadSinkRef,
+        rtpsession: &RtpSession,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        gst::log!(CAT, obj: pad.gst_pad(), "Forwarding non-serialized {:?}", event);
+        rtpsession.send_rtp_src.gst_pad().push_event(event)
+    }
+
+    fn sink_event_serialized(
+        &self,
+        pad: &PadSinkRef,
+        rtpsession: &RtpSession,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> BoxFuture<'static, bool> {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Forwarding serialized {:?}", event);
+
+        {
+            let mut state = rtpsession.state.lock().unwrap();
+            match event.view() {
+                EventView::Caps(e) => state.send_clock_rate = clock_rate_from_caps(e.caps()),
+                EventView::Segment(e) => {
+                    state.send_segment = e.segment().downcast_ref::<gst::format::Time>().cloned();
+                }
+                EventView::Eos(..) => state.send_bye = state.sender.ssrc().is_some(),
+                _ => (),
+            }
+        }
+
+        let pad_weak = rtpsession.send_rtp_src.downgrade();
+        async move {
+            match pad_weak.upgrade() {
+                Some(pad) => pad.push_event(event).await,
+                None => false,
+            }
+        }
+        .boxed()
+    }
+
+    fn sink_query(
+        &self,
+        pad: &PadSinkRef,
+        rtpsession: &RtpSession,
+        _element: &gst::Element,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        gst::log!(CAT, obj: pad.gst_pad(), "Forwarding {:?}", query);
+
+        if query.is_serialized() {
+            false
+        } else {
+            rtpsession.send_rtp_src.gst_pad().peer_query(query)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SendRtpSrcPadHandler;
+
+impl PadSrcHandler for SendRtpSrcPadHandler {
+    type ElementImpl = RtpSession;
+
+    fn src_event(
+        &self,
+        pad: &PadSrcRef,
+        rtpsession: &RtpSession,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        gst::log!(CAT, obj: pad.gst_pad(), "Forwarding {:?} upstream", event);
+        rtpsession.send_rtp_sink.gst_pad().push_event(event)
+    }
+
+    fn src_query(
+        &self,
+        pad: &PadSrcRef,
+        rtpsession: &RtpSession,
+        _element: &gst::Element,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        gst::log!(CAT, obj: pad.gst_pad(), "Forwarding {:?}", query);
+
+        if query.is_serialized() {
+            false
+        } else {
+            rtpsession.send_rtp_sink.gst_pad().peer_query(query)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SendRtcpSrcPadHandler;
+
+impl PadSrcHandler for SendRtcpSrcPadHandler {
+    type ElementImpl = RtpSession;
+
+    fn src_event(
+        &self,
+        pad: &PadSrcRef,
+        _rtpsession: &RtpSession,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", event);
+
+        matches!(
+            event.view(),
+            EventView::Reconfigure(..) | EventView::Latency(..)
+        )
+    }
+
+    fn src_query(
+        &self,
+        pad: &PadSrcRef,
+        _rtpsession: &RtpSession,
+        _element: &gst::Element,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        use gst::QueryViewMut;
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", query);
+
+        match query.view_mut() {
+            QueryViewMut::Latency(q) => {
+                q.set(true, gst::ClockTime::ZERO, gst::ClockTime::NONE);
+                true
+            }
+            QueryViewMut::Scheduling(q) => {
+                q.set(gst::SchedulingFlags::SEQUENTIAL, 1, -1, 0);
+                q.add_scheduling_modes(&[gst::PadMode::Push]);
+                true
+            }
+            QueryViewMut::Caps(q) => {
+                let caps = pad.gst_pad().pad_template_caps();
+                let caps = q
+                    .filter()
+                    .map(|f| f.intersect_with_mode(&caps, gst::CapsIntersectMode::First))
+                    .unwrap_or(caps);
+                q.set_result(&caps);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Periodically generates the RTCP reports on the session's `Context`.
+#[derive(Debug)]
+struct RtcpTask {
+    element: super::RtpSession,
+    need_initial_events: bool,
+    is_first: bool,
+}
+
+impl RtcpTask {
+    fn new(element: &super::RtpSession) -> Self {
+        RtcpTask {
+            element: element.clone(),
+            need_initial_events: true,
+            is_first: true,
+        }
+    }
+
+    async fn push_report(&mut self) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let rtpsession = self.element.imp();
+
+        if self.need_initial_events {
+            gst::debug!(CAT, obj: &self.element, "Pushing initial events");
+
+            let stream_id = format!("{:08x}{:08x}", rand::random::<u32>(), rand::random::<u32>());
+            let stream_start_evt = gst::event::StreamStart::builder(&stream_id)
+                .group_id(gst::GroupId::next())
+                .build();
+            rtpsession.send_rtcp_src.push_event(stream_start_evt).await;
+
+            let caps = gst::Caps::builder("application/x-rtcp").build();
+            rtpsession
+                .send_rtcp_src
+                .push_event(gst::event::Caps::new(&caps))
+                .await;
+
+            let segment_evt =
+                gst::event::Segment::new(&gst::FormattedSegment::<gst::format::Time>::new());
+            rtpsession.send_rtcp_src.push_event(segment_evt).await;
+
+            self.need_initial_events = false;
+        }
+
+        let (buffer, is_bye) = rtpsession.generate_rtcp(&self.element);
+        gst::log!(CAT, obj: &self.element, "Pushing RTCP {:?}", buffer);
+
+        match rtpsession.send_rtcp_src.push(buffer).await {
+            Ok(_) | Err(gst::FlowError::NotLinked) if is_bye => Err(gst::FlowError::Eos),
+            Err(gst::FlowError::NotLinked) => Ok(gst::FlowSuccess::Ok),
+            res => res,
+        }
+    }
+}
+
+impl TaskImpl for RtcpTask {
+    type Item = ();
+
+    fn try_next(&mut self) -> BoxFuture<'_, Result<(), gst::FlowError>> {
+        async move {
+            let interval = self.element.imp().settings.lock().unwrap().rtcp_interval;
+
+            // RFC 3550 § 6.3.1: randomize the interval to avoid synchronization
+            // between participants & halve the initial interval.
+            let mut delay = interval.mul_f64(rand::thread_rng().gen_range(0.5..1.5));
+            if self.is_first {
+                delay /= 2;
+                self.is_first = false;
+            }
+
+            runtime::timer::delay_for(delay).await;
+
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn handle_item(&mut self, _item: ()) -> BoxFuture<'_, Result<(), gst::FlowError>> {
+        async move {
+            let res = self.push_report().await;
+            match res {
+                Ok(_) => {
+                    gst::log!(CAT, obj: &self.element, "Successfully pushed RTCP");
+                }
+                Err(gst::FlowError::Eos) => {
+                    gst::debug!(CAT, obj: &self.element, "Sent BYE, pushing EOS");
+                    let rtpsession = self.element.imp();
+                    rtpsession
+                        .send_rtcp_src
+                        .push_event(gst::event::Eos::new())
+                        .await;
+                }
+                Err(gst::FlowError::Flushing) => {
+                    gst::debug!(CAT, obj: &self.element, "Flushing");
+                }
+                Err(err) => {
+                    gst::error!(CAT, obj: &self.element, "Got error {}", err);
+                    gst::element_error!(
+                        &self.element,
+                        gst::StreamError::Failed,
+                        ("Internal data stream error"),
+                        ["streaming stopped, reason {}", err]
+                    );
+                }
+            }
+
+            res.map(drop)
+        }
+        .boxed()
+    }
+
+    fn stop(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Stopping task");
+
+            self.need_initial_events = true;
+            self.is_first = true;
+
+            gst::log!(CAT, obj: &self.element, "Task stopped");
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    sources: HashMap<u32, ReceiverSource>,
+    sender: LocalSender,
+    recv_clock_rate: Option<u32>,
+    send_clock_rate: Option<u32>,
+    send_segment: Option<gst::FormattedSegment<gst::format::Time>>,
+    send_bye: bool,
+    round_trip: Option<Duration>,
+    rtcp_sent: u64,
+    rtcp_received: u64,
+}
+
+#[derive(Debug)]
+pub struct RtpSession {
+    recv_rtp_sink: PadSink,
+    recv_rtcp_sink: PadSink,
+    send_rtp_sink: PadSink,
+    send_rtp_src: PadSrc,
+    send_rtcp_src: PadSrc,
+    recv_rtp_src_pads: Mutex<HashMap<u32, PadSrc>>,
+    task: Task,
+    state: Mutex<State>,
+    settings: Mutex<Settings>,
+}
+
+impl RtpSession {
+    fn recv_rtp_src_pads(&self) -> Vec<PadSrcWeak> {
+        self.recv_rtp_src_pads
+            .lock()
+            .unwrap()
+            .values()
+            .map(PadSrc::downgrade)
+            .collect()
+    }
+
+    /// Returns the `recv_rtp_src` pad for `ssrc`, creating it if needed.
+    ///
+    /// The initial events are returned if the pad was just created.
+    fn recv_rtp_src_pad(
+        &self,
+        element: &super::RtpSession,
+        ssrc: u32,
+    ) -> (PadSrcWeak, Option<Vec<gst::Event>>) {
+        let mut pads = self.recv_rtp_src_pads.lock().unwrap();
+        if let Some(pad) = pads.get(&ssrc) {
+            return (pad.downgrade(), None);
+        }
+
+        gst::debug!(CAT, obj: element, "Creating pad for SSRC {:08x}", ssrc);
+
+        let templ = element.pad_template("recv_rtp_src_%u").unwrap();
+        let pad = PadSrc::new(
+            gst::Pad::from_template(&templ, Some(format!("recv_rtp_src_{}", ssrc).as_str())),
+            RecvRtpSrcPadHandler,
+        );
+        let pad_weak = pad.downgrade();
+        let gst_pad = pad.gst_pad().clone();
+        pads.insert(ssrc, pad);
+        drop(pads);
+
+        let mut events = Vec::new();
+        self.recv_rtp_sink.gst_pad().sticky_events_foreach(|event| {
+            events.push(event_for_ssrc(event, ssrc));
+            ControlFlow::Continue(gst::EventForeachAction::Keep)
+        });
+
+        gst_pad.set_active(true).unwrap();
+        element.add_pad(&gst_pad).unwrap();
+
+        (pad_weak, Some(events))
+    }
+
+    fn remove_recv_rtp_src_pads(&self, element: &super::RtpSession) {
+        let pads = std::mem::take(&mut *self.recv_rtp_src_pads.lock().unwrap());
+        for (_, pad) in pads {
+            let gst_pad = pad.gst_pad().clone();
+            drop(pad);
+            let _ = gst_pad.set_active(false);
+            let _ = element.remove_pad(&gst_pad);
+        }
+    }
+
+    async fn handle_recv_rtp(
+        &self,
+        element: &super::RtpSession,
+        mut buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let (ssrc, seq, rtp_time, payload_len) = match RTPBuffer::from_buffer_readable(&buffer) {
+            Ok(rtp) => (
+                rtp.ssrc(),
+                rtp.seq(),
+                rtp.timestamp(),
+                rtp.payload_size() as usize,
+            ),
+            Err(_) => {
+                gst::warning!(CAT, obj: element, "Dropping invalid RTP packet");
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        };
+
+        let arrival: Duration = buffer
+            .dts_or_pts()
+            .or_else(|| element.current_running_time())
+            .unwrap_or(gst::ClockTime::ZERO)
+            .into();
+
+        let add_meta = self.settings.lock().unwrap().add_reference_timestamp_meta;
+
+        let (is_new, ntp_time) = {
+            let mut state = self.state.lock().unwrap();
+            let clock_rate = state.recv_clock_rate;
+
+            let is_new = !state.sources.contains_key(&ssrc);
+            let source = state
+                .sources
+                .entry(ssrc)
+                .or_insert_with(|| ReceiverSource::new(ssrc));
+            source.process_rtp(seq, rtp_time, payload_len, arrival, clock_rate);
+
+            let ntp_time = if add_meta {
+                clock_rate.and_then(|clock_rate| source.ntp_time_for(rtp_time, clock_rate))
+            } else {
+                None
+            };
+
+            (is_new, ntp_time)
+        };
+
+        if is_new {
+            gst::debug!(CAT, obj: element, "New SSRC {:08x}", ssrc);
+            element.emit_by_name::<()>("on-new-ssrc", &[&ssrc]);
+        }
+
+        if let Some(ntp_time) = ntp_time {
+            gst::ReferenceTimestampMeta::add(
+                buffer.make_mut(),
+                &NTP_CAPS,
+                gst::ClockTime::from_nseconds(ntp_time.as_nanos() as u64),
+                gst::ClockTime::NONE,
+            );
+        }
+
+        let (pad_weak, initial_events) = self.recv_rtp_src_pad(element, ssrc);
+        let pad = pad_weak.upgrade().ok_or(gst::FlowError::Flushing)?;
+
+        if let Some(events) = initial_events {
+            for event in events {
+                pad.push_event(event).await;
+            }
+        }
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Forwarding {:?}", buffer);
+        match pad.push(buffer).await {
+            // Don't stop the whole session because one SSRC is not linked
+            Err(gst::FlowError::NotLinked) => Ok(gst::FlowSuccess::Ok),
+            res => res,
+        }
+    }
+
+    fn handle_recv_rtcp(
+        &self,
+        element: &super::RtpSession,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let packets = {
+            let map = buffer.map_readable().map_err(|_| {
+                gst::element_error!(
+                    element,
+                    gst::CoreError::Failed,
+                    ["Failed to map buffer readable"]
+                );
+                gst::FlowError::Error
+            })?;
+
+            match rtcp::parse_compound(&map) {
+                Ok(packets) => packets,
+                Err(err) => {
+                    gst::warning!(CAT, obj: element, "Dropping invalid RTCP packet: {}", err);
+                    return Ok(gst::FlowSuccess::Ok);
+                }
+            }
+        };
+
+        gst::log!(CAT, obj: element, "Handling RTCP {:?}", packets);
+
+        let arrival: Duration = element
+            .current_running_time()
+            .unwrap_or(gst::ClockTime::ZERO)
+            .into();
+        let arrival_ntp = source::ntp_now();
+        let internal_ssrc = self.settings.lock().unwrap().internal_ssrc;
+
+        let mut new_ssrcs = Vec::new();
+        let mut bye_ssrcs = Vec::new();
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.rtcp_received += 1;
+            let local_ssrc = state.sender.ssrc().unwrap_or(internal_ssrc);
+
+            for packet in packets {
+                match packet {
+                    Packet::SenderReport { ssrc, info, blocks } => {
+                        let source = state.sources.entry(ssrc).or_insert_with(|| {
+                            new_ssrcs.push(ssrc);
+                            ReceiverSource::new(ssrc)
+                        });
+                        source.set_sender_report(info, arrival);
+                        state.handle_report_blocks(local_ssrc, &blocks, arrival_ntp);
+                    }
+                    Packet::ReceiverReport { blocks, .. } => {
+                        state.handle_report_blocks(local_ssrc, &blocks, arrival_ntp);
+                    }
+                    Packet::Sdes { chunks } => {
+                        for (ssrc, cname) in chunks {
+                            if let Some(source) = state.sources.get_mut(&ssrc) {
+                                source.cname = Some(cname);
+                            }
+                        }
+                    }
+                    Packet::Bye { ssrcs } => {
+                        for ssrc in ssrcs {
+                            if state.sources.remove(&ssrc).is_some() {
+                                bye_ssrcs.push(ssrc);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for ssrc in new_ssrcs {
+            gst::debug!(CAT, obj: element, "New SSRC {:08x} from SR", ssrc);
+            element.emit_by_name::<()>("on-new-ssrc", &[&ssrc]);
+        }
+
+        for ssrc in bye_ssrcs {
+            gst::debug!(CAT, obj: element, "BYE from SSRC {:08x}", ssrc);
+            element.emit_by_name::<()>("on-bye-ssrc", &[&ssrc]);
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn handle_send_rtp(&self, element: &super::RtpSession, buffer: &gst::BufferRef) {
+        let (ssrc, rtp_time, payload_len) = match RTPBuffer::from_buffer_readable(buffer) {
+            Ok(rtp) => (rtp.ssrc(), rtp.timestamp(), rtp.payload_size() as usize),
+            Err(_) => {
+                gst::warning!(CAT, obj: element, "Not accounting for invalid RTP packet");
+                return;
+            }
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let running_time = state
+            .send_segment
+            .as_ref()
+            .and_then(|segment| segment.to_running_time(buffer.pts()))
+            .or_else(|| element.current_running_time())
+            .unwrap_or(gst::ClockTime::ZERO);
+
+        state
+            .sender
+            .process_rtp(ssrc, rtp_time, payload_len, running_time.into());
+    }
+
+    /// Generates the next RTCP compound packet.
+    ///
+    /// Also returns `true` if the packet contains a BYE.
+    fn generate_rtcp(&self, element: &super::RtpSession) -> (gst::Buffer, bool) {
+        let now: Duration = element
+            .current_running_time()
+            .unwrap_or(gst::ClockTime::ZERO)
+            .into();
+        let settings = self.settings.lock().unwrap().clone();
+
+        let mut state = self.state.lock().unwrap();
+
+        let blocks = state
+            .sources
+            .values_mut()
+            .filter(|source| source.is_valid() && source.received_since_report())
+            .take(rtcp::MAX_COUNT)
+            .map(|source| source.report_block(now))
+            .collect::<Vec<_>>();
+
+        let ssrc = state.sender.ssrc().unwrap_or(settings.internal_ssrc);
+        let mut packets = Vec::with_capacity(3);
+
+        if state.sender.sent_since_report() {
+            let clock_rate = state.send_clock_rate;
+            let info = state.sender.sender_info(now, source::ntp_now(), clock_rate);
+            packets.push(Packet::SenderReport { ssrc, info, blocks });
+        } else {
+            packets.push(Packet::ReceiverReport { ssrc, blocks });
+        }
+
+        packets.push(Packet::Sdes {
+            chunks: vec![(ssrc, settings.cname)],
+        });
+
+        let is_bye = state.send_bye;
+        if is_bye {
+            packets.push(Packet::Bye { ssrcs: vec![ssrc] });
+        }
+
+        state.rtcp_sent += 1;
+
+        (
+            gst::Buffer::from_mut_slice(rtcp::write_compound(&packets)),
+            is_bye,
+        )
+    }
+
+    fn stats(&self) -> gst::Structure {
+        let internal_ssrc = self.settings.lock().unwrap().internal_ssrc;
+        let state = self.state.lock().unwrap();
+
+        let source_stats = state
+            .sources
+            .values()
+            .map(|source| {
+                let mut s = gst::Structure::builder("application/x-rtp-source-stats")
+                    .field("ssrc", source.ssrc())
+                    .field("is-validated", source.is_valid())
+                    .field("packets-received", source.received())
+                    .field("octets-received", source.bytes_received())
+                    .field("packets-lost", source.lost())
+                    .field("jitter", source.jitter())
+                    .field("have-sr", source.sender_info().is_some())
+                    .build();
+
+                if let Some(ref cname) = source.cname {
+                    s.set("cname", cname);
+                }
+
+                if let Some(info) = source.sender_info() {
+                    s.set("sr-ntptime", info.ntp_time);
+                    s.set("sr-rtptime", info.rtp_time);
+                    s.set("sr-packet-count", info.packet_count);
+                    s.set("sr-octet-count", info.octet_count);
+                }
+
+                s.to_send_value()
+            })
+            .collect::<Vec<_>>();
+
+        gst::Structure::builder("application/x-rtp-session-stats")
+            .field("ssrc", state.sender.ssrc().unwrap_or(internal_ssrc))
+            .field("packets-sent", state.sender.packet_count())
+            .field("octets-sent", state.sender.octet_count())
+            .field("rtcp-packets-sent", state.rtcp_sent)
+            .field("rtcp-packets-received", state.rtcp_received)
+            .field(
+                "round-trip",
+                state.round_trip.map_or(0, |rtt| rtt.as_nanos() as u64),
+            )
+            .field("source-stats", gst::Array::from(source_stats))
+            .build()
+    }
+
+    fn prepare(&self, element: &super::RtpSession) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Preparing");
+
+        let context = {
+            let settings = self.settings.lock().unwrap();
+            Context::acquire(&settings.context, settings.context_wait).map_err(|err| {
+                gst::error_msg!(
+                    gst::ResourceError::OpenRead,
+                    ["Failed to acquire Context: {}", err]
+                )
+            })?
+        };
+
+        self.task
+            .prepare(RtcpTask::new(element), context)
+            .block_on()?;
+
+        gst::debug!(CAT, obj: element, "Prepared");
+
+        Ok(())
+    }
+
+    fn unprepare(&self, element: &super::RtpSession) {
+        gst::debug!(CAT, obj: element, "Unpreparing");
+        self.task.unprepare().block_on().unwrap();
+        gst::debug!(CAT, obj: element, "Unprepared");
+    }
+
+    fn stop(&self, element: &super::RtpSession) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Stopping");
+        self.task.stop().block_on()?;
+        *self.state.lock().unwrap() = State::default();
+        gst::debug!(CAT, obj: element, "Stopped");
+        Ok(())
+    }
+
+    fn start(&self, element: &super::RtpSession) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Starting");
+        self.task.start().block_on()?;
+        gst::debug!(CAT, obj: element, "Started");
+        Ok(())
+    }
+
+    fn pause(&self, element: &super::RtpSession) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Pausing");
+        self.task.pause().block_on()?;
+        gst::debug!(CAT, obj: element, "Paused");
+        Ok(())
+    }
+}
+
+impl State {
+    /// Handles the report blocks from a remote, looking for the ones about us.
+    fn handle_report_blocks(&mut self, local_ssrc: u32, blocks: &[ReportBlock], arrival_ntp: u64) {
+        for block in blocks.iter().filter(|block| block.ssrc == local_ssrc) {
+            if let Some(rtt) = source::round_trip_time(block, arrival_ntp) {
+                self.round_trip = Some(rtt);
+            }
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpSession {
+    const NAME: &'static str = "RsTsRtpSession";
+    type Type = super::RtpSession;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        Self {
+            recv_rtp_sink: PadSink::new(
+                gst::Pad::from_template(
+                    &klass.pad_template("recv_rtp_sink").unwrap(),
+                    Some("recv_rtp_sink"),
+                ),
+                RecvRtpSinkPadHandler,
+            ),
+            recv_rtcp_sink: PadSink::new(
+                gst::Pad::from_template(
+                    &klass.pad_template("recv_rtcp_sink").unwrap(),
+                    Some("recv_rtcp_sink"),
+                ),
+                RecvRtcpSinkPadHandler,
+            ),
+            send_rtp_sink: PadSink::new(
+                gst::Pad::from_template(
+                    &klass.pad_template("send_rtp_sink").unwrap(),
+                    Some("send_rtp_sink"),
+                ),
+                SendRtpSinkPadHandler,
+            ),
+            send_rtp_src: PadSrc::new(
+                gst::Pad::from_template(
+                    &klass.pad_template("send_rtp_src").unwrap(),
+                    Some("send_rtp_src"),
+                ),
+                SendRtpSrcPadHandler,
+            ),
+            send_rtcp_src: PadSrc::new(
+                gst::Pad::from_template(
+                    &klass.pad_template("send_rtcp_src").unwrap(),
+                    Some("send_rtcp_src"),
+                ),
+                SendRtcpSrcPadHandler,
+            ),
+            recv_rtp_src_pads: Default::default(),
+            task: Task::default(),
+            state: Default::default(),
+            settings: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for RtpSession {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::builder("context")
+                    .nick("Context")
+                    .blurb("Context name to share threads with")
+                    .default_value(Some(DEFAULT_CONTEXT))
+                    .build(),
+                glib::ParamSpecUInt::builder("context-wait")
+                    .nick("Context Wait")
+                    .blurb("Throttle poll loop to run at most once every this many ms")
+                    .maximum(1000)
+                    .default_value(DEFAULT_CONTEXT_WAIT.as_millis() as u32)
+                    .build(),
+                glib::ParamSpecUInt::builder("rtcp-interval")
+                    .nick("RTCP Interval")
+                    .blurb("Mean interval between RTCP reports in ms")
+                    .minimum(1)
+                    .default_value(DEFAULT_RTCP_INTERVAL.as_millis() as u32)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecString::builder("cname")
+                    .nick("CNAME")
+                    .blurb("Canonical name sent in the RTCP SDES")
+                    .build(),
+                glib::ParamSpecUInt::builder("internal-ssrc")
+                    .nick("Internal SSRC")
+                    .blurb("SSRC used in RTCP reports when not sending RTP")
+                    .build(),
+                glib::ParamSpecBoolean::builder("add-reference-timestamp-meta")
+                    .nick("Add Reference Timestamp Meta")
+                    .blurb(
+                        "Add NTP reference timestamp meta to received buffers \
+                         once a Sender Report was received for their SSRC",
+                    )
+                    .default_value(DEFAULT_ADD_REFERENCE_TIMESTAMP_META)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Session & per SSRC statistics")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn signals() -> &'static [glib::subclass::Signal] {
+        static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
+            vec![
+                glib::subclass::Signal::builder("on-new-ssrc")
+                    .param_types([u32::static_type()])
+                    .build(),
+                glib::subclass::Signal::builder("on-bye-ssrc")
+                    .param_types([u32::static_type()])
+                    .build(),
+            ]
+        });
+
+        SIGNALS.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "context" => {
+                settings.context = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_else(|| DEFAULT_CONTEXT.into());
+            }
+            "context-wait" => {
+                settings.context_wait = Duration::from_millis(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
+            "rtcp-interval" => {
+                settings.rtcp_interval = Duration::from_millis(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
+            "cname" => {
+                let cname = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_default();
+                if cname.len() > MAX_CNAME_LEN {
+                    gst::warning!(
+                        CAT,
+                        obj: obj,
+                        "Ignoring CNAME longer than {} bytes",
+                        MAX_CNAME_LEN
+                    );
+                } else {
+                    settings.cname = cname;
+                }
+            }
+            "internal-ssrc" => {
+                settings.internal_ssrc = value.get().expect("type checked upstream");
+            }
+            "add-reference-timestamp-meta" => {
+                settings.add_reference_timestamp_meta = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        if pspec.name() == "stats" {
+            return self.stats().to_value();
+        }
+
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "context" => settings.context.to_value(),
+            "context-wait" => (settings.context_wait.as_millis() as u32).to_value(),
+            "rtcp-interval" => (settings.rtcp_interval.as_millis() as u32).to_value(),
+            "cname" => settings.cname.to_value(),
+            "internal-ssrc" => settings.internal_ssrc.to_value(),
+            "add-reference-timestamp-meta" => settings.add_reference_timestamp_meta.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(self.recv_rtp_sink.gst_pad()).unwrap();
+        obj.add_pad(self.recv_rtcp_sink.gst_pad()).unwrap();
+        obj.add_pad(self.send_rtp_sink.gst_pad()).unwrap();
+        obj.add_pad(self.send_rtp_src.gst_pad()).unwrap();
+        obj.add_pad(self.send_rtcp_src.gst_pad()).unwrap();
+
+        obj.set_element_flags(gst::ElementFlags::PROVIDE_CLOCK | gst::ElementFlags::REQUIRE_CLOCK);
+    }
+}
+
+impl GstObjectImpl for RtpSession {}
+
+impl ElementImpl for RtpSession {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Thread-sharing RTP session",
+                "Filter/Network/RTP",
+                "RTP session with SSRC demuxing and RTCP reports",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let rtp_caps = gst::Caps::builder("application/x-rtp").build();
+            let rtcp_caps = gst::Caps::builder("application/x-rtcp").build();
+
+            vec![
+                gst::PadTemplate::new(
+                    "recv_rtp_sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &rtp_caps,
+                )
+                .unwrap(),
+                gst::PadTemplate::new(
+                    "recv_rtp_src_%u",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Sometimes,
+                    &rtp_caps,
+                )
+                .unwrap(),
+                gst::PadTemplate::new(
+                    "recv_rtcp_sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &rtcp_caps,
+                )
+                .unwrap(),
+                gst::PadTemplate::new(
+                    "send_rtp_sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &rtp_caps,
+                )
+                .unwrap(),
+                gst::PadTemplate::new(
+                    "send_rtp_src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &rtp_caps,
+                )
+                .unwrap(),
+                gst::PadTemplate::new(
+                    "send_rtcp_src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &rtcp_caps,
+                )
+                .unwrap(),
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::NullToReady => {
+                self.prepare(element).map_err(|err| {
+                    element.post_error_message(err);
+                    gst::StateChangeError
+                })?;
+            }
+            gst::StateChange::PlayingToPaused => {
+                self.pause(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::ReadyToNull => {
+                self.unprepare(element);
+            }
+            _ => (),
+        }
+
+        let mut success = self.parent_change_state(element, transition)?;
+
+        match transition {
+            gst::StateChange::ReadyToPaused => {
+                success = gst::StateChangeSuccess::NoPreroll;
+            }
+            gst::StateChange::PausedToPlaying => {
+                self.start(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::PlayingToPaused => {
+                success = gst::StateChangeSuccess::NoPreroll;
+            }
+            gst::StateChange::PausedToReady => {
+                self.stop(element).map_err(|_| gst::StateChangeError)?;
+                self.remove_recv_rtp_src_pads(element);
+            }
+            _ => (),
+        }
+
+        Ok(success)
+    }
+
+    fn provide_clock(&self, _element: &Self::Type) -> Option<gst::Clock> {
+        Some(gst::SystemClock::obtain())
+    }
+}
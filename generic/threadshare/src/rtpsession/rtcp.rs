@@ -0,0 +1,443 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+//! Minimal RTCP (RFC 3550 § 6) compound packet reader & writer.
+//!
+//! Only the packet types needed for session handling are supported:
+//! SR, RR, SDES (CNAME) & BYE. Other packet types are skipped.
+
+use std::fmt;
+
+const VERSION: u8 = 2;
+
+const PT_SR: u8 = 200;
+const PT_RR: u8 = 201;
+const PT_SDES: u8 = 202;
+const PT_BYE: u8 = 203;
+
+const SDES_END: u8 = 0;
+const SDES_CNAME: u8 = 1;
+
+const HEADER_LEN: usize = 4;
+const SENDER_INFO_LEN: usize = 20;
+const REPORT_BLOCK_LEN: usize = 24;
+
+/// Maximum number of items in an RTCP packet (5 bits count).
+pub const MAX_COUNT: usize = 31;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtcpError {
+    TooShort,
+    InvalidVersion(u8),
+    InvalidLength,
+    NotCompound,
+}
+
+impl fmt::Display for RtcpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RtcpError::TooShort => write!(f, "RTCP packet too short"),
+            RtcpError::InvalidVersion(v) => write!(f, "Invalid RTCP version {}", v),
+            RtcpError::InvalidLength => write!(f, "Invalid RTCP packet length"),
+            RtcpError::NotCompound => write!(f, "RTCP compound must start with SR or RR"),
+        }
+    }
+}
+
+impl std::error::Error for RtcpError {}
+
+/// Sender information of an SR.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SenderInfo {
+    /// 64 bits NTP timestamp.
+    pub ntp_time: u64,
+    pub rtp_time: u32,
+    pub packet_count: u32,
+    pub octet_count: u32,
+}
+
+/// Reception report block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReportBlock {
+    pub ssrc: u32,
+    pub fraction_lost: u8,
+    /// Cumulative number of packets lost (24 bits signed).
+    pub cumulative_lost: i32,
+    pub extended_highest_seq: u32,
+    pub jitter: u32,
+    /// Middle 32 bits of the NTP timestamp of the last SR received.
+    pub last_sr: u32,
+    /// Delay since last SR in units of 1/65536 seconds.
+    pub delay_since_last_sr: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packet {
+    SenderReport {
+        ssrc: u32,
+        info: SenderInfo,
+        blocks: Vec<ReportBlock>,
+    },
+    ReceiverReport {
+        ssrc: u32,
+        blocks: Vec<ReportBlock>,
+    },
+    /// Source descriptions, only the CNAMEs are retained.
+    Sdes {
+        chunks: Vec<(u32, String)>,
+    },
+    Bye {
+        ssrcs: Vec<u32>,
+    },
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_be_bytes([data[0], data[1], data[2], data[3]])
+}
+
+fn read_report_blocks(data: &[u8], count: usize) -> Result<Vec<ReportBlock>, RtcpError> {
+    if data.len() < count * REPORT_BLOCK_LEN {
+        return Err(RtcpError::InvalidLength);
+    }
+
+    Ok(data
+        .chunks_exact(REPORT_BLOCK_LEN)
+        .take(count)
+        .map(|block| {
+            // Sign extend the 24 bits cumulative lost
+            let cumulative_lost = (read_u32(&block[4..]) << 8) as i32 >> 8;
+            ReportBlock {
+                ssrc: read_u32(block),
+                fraction_lost: block[4],
+                cumulative_lost,
+                extended_highest_seq: read_u32(&block[8..]),
+                jitter: read_u32(&block[12..]),
+                last_sr: read_u32(&block[16..]),
+                delay_since_last_sr: read_u32(&block[20..]),
+            }
+        })
+        .collect())
+}
+
+fn read_sdes(data: &[u8], count: usize) -> Result<Vec<(u32, String)>, RtcpError> {
+    let mut chunks = Vec::with_capacity(count);
+    let mut pos = 0;
+
+    for _ in 0..count {
+        if data.len() < pos + 4 {
+            return Err(RtcpError::InvalidLength);
+        }
+        let ssrc = read_u32(&data[pos..]);
+        pos += 4;
+
+        let mut cname = None;
+        loop {
+            let item_type = *data.get(pos).ok_or(RtcpError::InvalidLength)?;
+            if item_type == SDES_END {
+                // Skip to the next 32 bits boundary
+                pos = (pos + 4) & !3;
+                break;
+            }
+
+            let len = *data.get(pos + 1).ok_or(RtcpError::InvalidLength)? as usize;
+            let value = data
+                .get(pos + 2..pos + 2 + len)
+                .ok_or(RtcpError::InvalidLength)?;
+            if item_type == SDES_CNAME {
+                cname = Some(String::from_utf8_lossy(value).into_owned());
+            }
+            pos += 2 + len;
+        }
+
+        if let Some(cname) = cname {
+            chunks.push((ssrc, cname));
+        }
+    }
+
+    Ok(chunks)
+}
+
+/// Parses an RTCP compound packet.
+///
+/// Unsupported packet types are skipped.
+pub fn parse_compound(data: &[u8]) -> Result<Vec<Packet>, RtcpError> {
+    let mut packets = Vec::new();
+    let mut data = data;
+    let mut first = true;
+
+    while !data.is_empty() {
+        if data.len() < HEADER_LEN {
+            return Err(RtcpError::TooShort);
+        }
+
+        let version = data[0] >> 6;
+        if version != VERSION {
+            return Err(RtcpError::InvalidVersion(version));
+        }
+
+        let padding = data[0] & 0x20 != 0;
+        let count = (data[0] & 0x1f) as usize;
+        let pt = data[1];
+        let len = (u16::from_be_bytes([data[2], data[3]]) as usize + 1) * 4;
+        if data.len() < len {
+            return Err(RtcpError::InvalidLength);
+        }
+
+        if first && pt != PT_SR && pt != PT_RR {
+            return Err(RtcpError::NotCompound);
+        }
+        first = false;
+
+        let mut payload = &data[HEADER_LEN..len];
+        if padding {
+            let pad_len = *payload.last().ok_or(RtcpError::InvalidLength)? as usize;
+            if pad_len == 0 || pad_len > payload.len() {
+                return Err(RtcpError::InvalidLength);
+            }
+            payload = &payload[..payload.len() - pad_len];
+        }
+
+        match pt {
+            PT_SR => {
+                if payload.len() < 4 + SENDER_INFO_LEN {
+                    return Err(RtcpError::InvalidLength);
+                }
+                let info = SenderInfo {
+                    ntp_time: ((read_u32(&payload[4..]) as u64) << 32)
+                        | read_u32(&payload[8..]) as u64,
+                    rtp_time: read_u32(&payload[12..]),
+                    packet_count: read_u32(&payload[16..]),
+                    octet_count: read_u32(&payload[20..]),
+                };
+                packets.push(Packet::SenderReport {
+                    ssrc: read_u32(payload),
+                    info,
+                    blocks: read_report_blocks(&payload[4 + SENDER_INFO_LEN..], count)?,
+                });
+            }
+            PT_RR => {
+                if payload.len() < 4 {
+                    return Err(RtcpError::InvalidLength);
+                }
+                packets.push(Packet::ReceiverReport {
+                    ssrc: read_u32(payload),
+                    blocks: read_report_blocks(&payload[4..], count)?,
+                });
+            }
+            PT_SDES => {
+                packets.push(Packet::Sdes {
+                    chunks: read_sdes(payload, count)?,
+                });
+            }
+            PT_BYE => {
+                if payload.len() < count * 4 {
+                    return Err(RtcpError::InvalidLength);
+                }
+                packets.push(Packet::Bye {
+                    ssrcs: payload.chunks_exact(4).take(count).map(read_u32).collect(),
+                });
+            }
+            _ => (),
+        }
+
+        data = &data[len..];
+    }
+
+    Ok(packets)
+}
+
+fn write_header(out: &mut Vec<u8>, count: usize, pt: u8) -> usize {
+    assert!(count <= MAX_COUNT);
+
+    let start = out.len();
+    out.push((VERSION << 6) | count as u8);
+    out.push(pt);
+    // Length is updated in `finish_packet`
+    out.extend_from_slice(&[0, 0]);
+
+    start
+}
+
+fn finish_packet(out: &mut [u8], start: usize) {
+    let len = (out.len() - start) / 4 - 1;
+    out[start + 2..start + 4].copy_from_slice(&(len as u16).to_be_bytes());
+}
+
+fn write_report_blocks(out: &mut Vec<u8>, blocks: &[ReportBlock]) {
+    for block in blocks {
+        out.extend_from_slice(&block.ssrc.to_be_bytes());
+        let lost =
+            ((block.fraction_lost as u32) << 24) | (block.cumulative_lost as u32 & 0x00ff_ffff);
+        out.extend_from_slice(&lost.to_be_bytes());
+        out.extend_from_slice(&block.extended_highest_seq.to_be_bytes());
+        out.extend_from_slice(&block.jitter.to_be_bytes());
+        out.extend_from_slice(&block.last_sr.to_be_bytes());
+        out.extend_from_slice(&block.delay_since_last_sr.to_be_bytes());
+    }
+}
+
+/// Writes an RTCP compound packet.
+///
+/// # Panics
+///
+/// Panics if a packet has more than [`MAX_COUNT`] items or
+/// if a CNAME is longer than 255 bytes.
+pub fn write_compound(packets: &[Packet]) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    for packet in packets {
+        match packet {
+            Packet::SenderReport { ssrc, info, blocks } => {
+                let start = write_header(&mut out, blocks.len(), PT_SR);
+                out.extend_from_slice(&ssrc.to_be_bytes());
+                out.extend_from_slice(&info.ntp_time.to_be_bytes());
+                out.extend_from_slice(&info.rtp_time.to_be_bytes());
+                out.extend_from_slice(&info.packet_count.to_be_bytes());
+                out.extend_from_slice(&info.octet_count.to_be_bytes());
+                write_report_blocks(&mut out, blocks);
+                finish_packet(&mut out, start);
+            }
+            Packet::ReceiverReport { ssrc, blocks } => {
+                let start = write_header(&mut out, blocks.len(), PT_RR);
+                out.extend_from_slice(&ssrc.to_be_bytes());
+                write_report_blocks(&mut out, blocks);
+                finish_packet(&mut out, start);
+            }
+            Packet::Sdes { chunks } => {
+                let start = write_header(&mut out, chunks.len(), PT_SDES);
+                for (ssrc, cname) in chunks {
+                    assert!(cname.len() <= 255);
+                    out.extend_from_slice(&ssrc.to_be_bytes());
+                    out.push(SDES_CNAME);
+                    out.push(cname.len() as u8);
+                    out.extend_from_slice(cname.as_bytes());
+                    // At least one null octet terminates the item list,
+                    // then pad to the next 32 bits boundary.
+                    out.push(SDES_END);
+                    while out.len() % 4 != 0 {
+                        out.push(0);
+                    }
+                }
+                finish_packet(&mut out, start);
+            }
+            Packet::Bye { ssrcs } => {
+                let start = write_header(&mut out, ssrcs.len(), PT_BYE);
+                for ssrc in ssrcs {
+                    out.extend_from_slice(&ssrc.to_be_bytes());
+                }
+                finish_packet(&mut out, start);
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let packets = vec![
+            Packet::SenderReport {
+                ssrc: 0x1234_5678,
+                info: SenderInfo {
+                    ntp_time: 0xe6a1_2345_8000_0000,
+                    rtp_time: 90_000,
+                    packet_count: 10,
+                    octet_count: 12_000,
+                },
+                blocks: vec![ReportBlock {
+                    ssrc: 0xdead_beef,
+                    fraction_lost: 64,
+                    cumulative_lost: -3,
+                    extended_highest_seq: 0x0001_0010,
+                    jitter: 42,
+                    last_sr: 0x2345_8000,
+                    delay_since_last_sr: 65536,
+                }],
+            },
+            Packet::Sdes {
+                chunks: vec![(0x1234_5678, "user@host".to_string())],
+            },
+            Packet::Bye {
+                ssrcs: vec![0x1234_5678],
+            },
+        ];
+
+        let data = write_compound(&packets);
+        assert_eq!(data.len() % 4, 0);
+        assert_eq!(parse_compound(&data).unwrap(), packets);
+    }
+
+    #[test]
+    fn receiver_report() {
+        let packets = vec![
+            Packet::ReceiverReport {
+                ssrc: 1,
+                blocks: vec![],
+            },
+            Packet::Sdes {
+                // CNAME length such that no padding is needed after the null octet
+                chunks: vec![(1, "a".to_string()), (2, "abcde".to_string())],
+            },
+        ];
+
+        let data = write_compound(&packets);
+        // RR header + ssrc
+        assert_eq!(&data[..8], &[0x80, 201, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(parse_compound(&data).unwrap(), packets);
+    }
+
+    #[test]
+    fn skip_unknown() {
+        let mut data = write_compound(&[Packet::ReceiverReport {
+            ssrc: 1,
+            blocks: vec![],
+        }]);
+        // APP packet
+        data.extend_from_slice(&[0x80, 204, 0, 2, 0, 0, 0, 1, b'n', b'a', b'm', b'e']);
+
+        assert_eq!(
+            parse_compound(&data).unwrap(),
+            vec![Packet::ReceiverReport {
+                ssrc: 1,
+                blocks: vec![]
+            }]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_compound(&[0x80, 201]), Err(RtcpError::TooShort));
+        assert_eq!(
+            parse_compound(&[0x40, 201, 0, 1, 0, 0, 0, 1]),
+            Err(RtcpError::InvalidVersion(1))
+        );
+        assert_eq!(
+            parse_compound(&[0x80, 201, 0, 2, 0, 0, 0, 1]),
+            Err(RtcpError::InvalidLength)
+        );
+        assert_eq!(
+            parse_compound(&[0x81, 203, 0, 1, 0, 0, 0, 1]),
+            Err(RtcpError::NotCompound)
+        );
+    }
+}
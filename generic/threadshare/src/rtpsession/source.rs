@@ -0,0 +1,589 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+//! Per SSRC statistics as defined in RFC 3550 Appendix A.
+//!
+//! All the times are expressed as running times in the form of `Duration`s,
+//! except for NTP timestamps which use the 64 bits fixed point format.
+
+use std::time::{Duration, SystemTime};
+
+use super::rtcp::{ReportBlock, SenderInfo};
+
+const MAX_DROPOUT: u16 = 3000;
+const MAX_MISORDER: u16 = 100;
+const MIN_SEQUENTIAL: u32 = 2;
+const RTP_SEQ_MOD: u32 = 1 << 16;
+
+/// Seconds between the NTP epoch (1900) and the UNIX epoch (1970).
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// Converts a `Duration` since the NTP epoch to a 64 bits NTP timestamp.
+pub fn ntp_from_duration(d: Duration) -> u64 {
+    (d.as_secs() << 32) | (((d.subsec_nanos() as u64) << 32) / 1_000_000_000)
+}
+
+/// Converts a 64 bits NTP timestamp to a `Duration` since the NTP epoch.
+pub fn ntp_to_duration(ntp: u64) -> Duration {
+    let frac = ((ntp & 0xffff_ffff) * 1_000_000_000) >> 32;
+    Duration::new(ntp >> 32, frac as u32)
+}
+
+/// Returns the current system time as a 64 bits NTP timestamp.
+pub fn ntp_now() -> u64 {
+    let since_unix = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    ntp_from_duration(since_unix + Duration::from_secs(NTP_UNIX_OFFSET))
+}
+
+/// Returns the middle 32 bits of an NTP timestamp, as used in LSR fields.
+pub fn ntp_middle(ntp: u64) -> u32 {
+    (ntp >> 16) as u32
+}
+
+/// Converts a `Duration` to units of 1/65536 seconds, as used in DLSR fields.
+fn to_ntp_short(d: Duration) -> u32 {
+    (d.as_nanos() * 65536 / 1_000_000_000).min(u32::MAX as u128) as u32
+}
+
+fn from_ntp_short(v: u32) -> Duration {
+    Duration::from_nanos(v as u64 * 1_000_000_000 / 65536)
+}
+
+/// Converts a running time to RTP units for the given clock rate.
+fn to_rtp_units(d: Duration, clock_rate: u32) -> u32 {
+    (d.as_nanos() * clock_rate as u128 / 1_000_000_000) as u32
+}
+
+/// A remote source we receive RTP packets from.
+#[derive(Debug)]
+pub struct ReceiverSource {
+    ssrc: u32,
+    pub cname: Option<String>,
+
+    probation: u32,
+    max_seq: u16,
+    cycles: u32,
+    base_seq: u32,
+    bad_seq: u32,
+
+    received: u64,
+    received_prior: u64,
+    expected_prior: u64,
+    bytes_received: u64,
+
+    transit: Option<u32>,
+    /// Interarrival jitter, scaled by 16 as in RFC 3550 A.8.
+    jitter: u32,
+
+    last_sr: Option<(SenderInfo, Duration)>,
+}
+
+impl ReceiverSource {
+    pub fn new(ssrc: u32) -> Self {
+        ReceiverSource {
+            ssrc,
+            cname: None,
+            probation: MIN_SEQUENTIAL,
+            max_seq: 0,
+            cycles: 0,
+            base_seq: 0,
+            bad_seq: RTP_SEQ_MOD + 1,
+            received: 0,
+            received_prior: 0,
+            expected_prior: 0,
+            bytes_received: 0,
+            transit: None,
+            jitter: 0,
+            last_sr: None,
+        }
+    }
+
+    pub fn ssrc(&self) -> u32 {
+        self.ssrc
+    }
+
+    fn init_seq(&mut self, seq: u16) {
+        self.base_seq = seq as u32;
+        self.max_seq = seq;
+        self.bad_seq = RTP_SEQ_MOD + 1;
+        self.cycles = 0;
+        self.received = 0;
+        self.received_prior = 0;
+        self.expected_prior = 0;
+    }
+
+    /// Updates the sequence number state, see RFC 3550 A.1.
+    ///
+    /// Returns `false` if the packet must not be accounted for.
+    fn update_seq(&mut self, seq: u16) -> bool {
+        let udelta = seq.wrapping_sub(self.max_seq);
+
+        if self.probation > 0 {
+            if self.received > 0 && seq == self.max_seq.wrapping_add(1) {
+                self.probation -= 1;
+                self.max_seq = seq;
+                if self.probation == 0 {
+                    let received = self.received;
+                    self.init_seq(seq);
+                    self.received = received;
+                    // The packets received during probation are part of the sequence.
+                    // This can wrap, which is fine since `expected` wraps too.
+                    self.base_seq = (seq as u32).wrapping_sub(received as u32);
+                    return true;
+                }
+            } else {
+                self.probation = MIN_SEQUENTIAL - 1;
+                self.received = 0;
+                self.max_seq = seq;
+            }
+            return true;
+        }
+
+        if udelta < MAX_DROPOUT {
+            if seq < self.max_seq {
+                // Sequence number wrapped
+                self.cycles = self.cycles.wrapping_add(RTP_SEQ_MOD);
+            }
+            self.max_seq = seq;
+        } else if udelta as u32 <= RTP_SEQ_MOD - MAX_MISORDER as u32 {
+            // Large jump
+            if seq as u32 == self.bad_seq {
+                // Two sequential packets: assume the other side restarted
+                self.init_seq(seq);
+            } else {
+                self.bad_seq = (seq as u32 + 1) & (RTP_SEQ_MOD - 1);
+                return false;
+            }
+        }
+        // else: duplicate or reordered packet
+
+        true
+    }
+
+    /// Accounts for an incoming RTP packet.
+    ///
+    /// `arrival` is the running time at which the packet was received.
+    /// The jitter is only computed when the `clock_rate` is known.
+    pub fn process_rtp(
+        &mut self,
+        seq: u16,
+        rtp_time: u32,
+        payload_len: usize,
+        arrival: Duration,
+        clock_rate: Option<u32>,
+    ) {
+        if !self.update_seq(seq) {
+            return;
+        }
+
+        self.received += 1;
+        self.bytes_received += payload_len as u64;
+
+        if let Some(clock_rate) = clock_rate.filter(|rate| *rate > 0) {
+            let transit = to_rtp_units(arrival, clock_rate).wrapping_sub(rtp_time);
+            if let Some(prev_transit) = self.transit {
+                let d = (transit.wrapping_sub(prev_transit) as i32).unsigned_abs();
+                self.jitter = self
+                    .jitter
+                    .wrapping_add(d)
+                    .wrapping_sub((self.jitter + 8) >> 4);
+            }
+            self.transit = Some(transit);
+        }
+    }
+
+    /// Whether enough sequential packets were received to validate the source.
+    pub fn is_valid(&self) -> bool {
+        self.probation == 0
+    }
+
+    pub fn extended_max_seq(&self) -> u32 {
+        self.cycles.wrapping_add(self.max_seq as u32)
+    }
+
+    pub fn expected(&self) -> u64 {
+        if !self.is_valid() {
+            return self.received;
+        }
+
+        (self.extended_max_seq().wrapping_sub(self.base_seq) as u64) + 1
+    }
+
+    /// Cumulative number of packets lost, negative when duplicates were received.
+    pub fn lost(&self) -> i64 {
+        self.expected() as i64 - self.received as i64
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    /// Interarrival jitter in RTP units.
+    pub fn jitter(&self) -> u32 {
+        self.jitter >> 4
+    }
+
+    /// Whether packets were received since last report block was generated.
+    pub fn received_since_report(&self) -> bool {
+        self.received > self.received_prior
+    }
+
+    /// Builds a report block for this source & starts a new reporting interval.
+    pub fn report_block(&mut self, now: Duration) -> ReportBlock {
+        let expected = self.expected();
+        let expected_interval = expected.saturating_sub(self.expected_prior);
+        let received_interval = self.received.saturating_sub(self.received_prior);
+        self.expected_prior = expected;
+        self.received_prior = self.received;
+
+        let lost_interval = expected_interval as i64 - received_interval as i64;
+        let fraction_lost = if expected_interval == 0 || lost_interval <= 0 {
+            0
+        } else {
+            ((lost_interval << 8) / expected_interval as i64).min(255) as u8
+        };
+
+        let (last_sr, delay_since_last_sr) = match self.last_sr {
+            Some((ref info, received_at)) => (
+                ntp_middle(info.ntp_time),
+                to_ntp_short(now.saturating_sub(received_at)),
+            ),
+            None => (0, 0),
+        };
+
+        ReportBlock {
+            ssrc: self.ssrc,
+            fraction_lost,
+            // Clamp to the 24 bits signed range
+            cumulative_lost: self.lost().clamp(-0x80_0000, 0x7f_ffff) as i32,
+            extended_highest_seq: self.extended_max_seq(),
+            jitter: self.jitter(),
+            last_sr,
+            delay_since_last_sr,
+        }
+    }
+
+    /// Records a Sender Report received at running time `received_at`.
+    pub fn set_sender_report(&mut self, info: SenderInfo, received_at: Duration) {
+        self.last_sr = Some((info, received_at));
+    }
+
+    pub fn sender_info(&self) -> Option<&SenderInfo> {
+        self.last_sr.as_ref().map(|(info, _)| info)
+    }
+
+    /// Returns the time since the NTP epoch matching `rtp_time`, using the mapping
+    /// from the last Sender Report.
+    pub fn ntp_time_for(&self, rtp_time: u32, clock_rate: u32) -> Option<Duration> {
+        let (info, _) = self.last_sr.as_ref()?;
+        if clock_rate == 0 {
+            return None;
+        }
+
+        let sr_time = ntp_to_duration(info.ntp_time);
+        let delta = rtp_time.wrapping_sub(info.rtp_time) as i32 as i64;
+        let delta = Duration::from_nanos(
+            (delta.unsigned_abs() as u128 * 1_000_000_000 / clock_rate as u128) as u64,
+        );
+
+        if rtp_time.wrapping_sub(info.rtp_time) as i32 >= 0 {
+            Some(sr_time + delta)
+        } else {
+            sr_time.checked_sub(delta)
+        }
+    }
+}
+
+/// Our own sending side.
+#[derive(Debug, Default)]
+pub struct LocalSender {
+    ssrc: Option<u32>,
+    packet_count: u32,
+    octet_count: u32,
+    /// Last RTP timestamp sent and its running time.
+    last_rtp: Option<(u32, Duration)>,
+    sent_since_report: bool,
+}
+
+impl LocalSender {
+    /// The SSRC of the sent stream, if any packet was sent.
+    pub fn ssrc(&self) -> Option<u32> {
+        self.ssrc
+    }
+
+    pub fn packet_count(&self) -> u32 {
+        self.packet_count
+    }
+
+    pub fn octet_count(&self) -> u32 {
+        self.octet_count
+    }
+
+    /// Accounts for an outgoing RTP packet sent at `running_time`.
+    pub fn process_rtp(
+        &mut self,
+        ssrc: u32,
+        rtp_time: u32,
+        payload_len: usize,
+        running_time: Duration,
+    ) {
+        if self.ssrc != Some(ssrc) {
+            // New or changed SSRC: counters are per SSRC
+            self.ssrc = Some(ssrc);
+            self.packet_count = 0;
+            self.octet_count = 0;
+        }
+
+        self.packet_count = self.packet_count.wrapping_add(1);
+        self.octet_count = self.octet_count.wrapping_add(payload_len as u32);
+        self.last_rtp = Some((rtp_time, running_time));
+        self.sent_since_report = true;
+    }
+
+    /// Whether packets were sent since the last report, in which case an SR is due.
+    pub fn sent_since_report(&self) -> bool {
+        self.sent_since_report
+    }
+
+    /// Builds the sender info for a report generated at running time `now`
+    /// matching NTP timestamp `ntp_time` & starts a new reporting interval.
+    pub fn sender_info(
+        &mut self,
+        now: Duration,
+        ntp_time: u64,
+        clock_rate: Option<u32>,
+    ) -> SenderInfo {
+        self.sent_since_report = false;
+
+        let rtp_time = match (self.last_rtp, clock_rate) {
+            (Some((rtp_time, running_time)), Some(clock_rate)) => {
+                // Extrapolate the RTP timestamp for `now`
+                if now >= running_time {
+                    rtp_time.wrapping_add(to_rtp_units(now - running_time, clock_rate))
+                } else {
+                    rtp_time.wrapping_sub(to_rtp_units(running_time - now, clock_rate))
+                }
+            }
+            (Some((rtp_time, _)), None) => rtp_time,
+            (None, _) => 0,
+        };
+
+        SenderInfo {
+            ntp_time,
+            rtp_time,
+            packet_count: self.packet_count,
+            octet_count: self.octet_count,
+        }
+    }
+}
+
+/// Computes the round trip time from a report block received at NTP time `arrival`.
+///
+/// Returns `None` if the remote didn't receive any SR from us yet.
+pub fn round_trip_time(block: &ReportBlock, arrival: u64) -> Option<Duration> {
+    if block.last_sr == 0 {
+        return None;
+    }
+
+    let rtt = ntp_middle(arrival)
+        .wrapping_sub(block.last_sr)
+        .wrapping_sub(block.delay_since_last_sr);
+    if rtt as i32 >= 0 {
+        Some(from_ntp_short(rtt))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receive(source: &mut ReceiverSource, seqs: impl IntoIterator<Item = u16>) {
+        for seq in seqs {
+            source.process_rtp(seq, 0, 100, Duration::ZERO, None);
+        }
+    }
+
+    #[test]
+    fn ntp_conversions() {
+        let d = Duration::new(3_000_000_000, 500_000_000);
+        let ntp = ntp_from_duration(d);
+        assert_eq!(ntp, (3_000_000_000u64 << 32) | 0x8000_0000);
+        assert_eq!(ntp_to_duration(ntp), d);
+        assert_eq!(
+            ntp_middle(ntp),
+            (((3_000_000_000u64 & 0xffff) << 16) as u32) | 0x8000
+        );
+        assert_eq!(to_ntp_short(Duration::from_secs(1)), 65536);
+        assert_eq!(from_ntp_short(32768), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn probation() {
+        let mut source = ReceiverSource::new(1);
+        receive(&mut source, [10]);
+        assert!(!source.is_valid());
+        receive(&mut source, [11, 12]);
+        assert!(source.is_valid());
+        assert_eq!(source.received(), 3);
+        assert_eq!(source.expected(), 3);
+        assert_eq!(source.lost(), 0);
+        assert_eq!(source.extended_max_seq(), 12);
+    }
+
+    #[test]
+    fn losses_and_wrap() {
+        let mut source = ReceiverSource::new(1);
+        receive(&mut source, [65532, 65533, 65534]);
+        assert!(source.is_valid());
+
+        // Loose 65535 & 1
+        receive(&mut source, [0, 2, 3]);
+        assert_eq!(source.extended_max_seq(), 65536 + 3);
+        assert_eq!(source.expected(), 8);
+        assert_eq!(source.received(), 6);
+        assert_eq!(source.lost(), 2);
+
+        let block = source.report_block(Duration::ZERO);
+        assert_eq!(block.cumulative_lost, 2);
+        assert_eq!(block.fraction_lost, 64);
+        assert_eq!(block.extended_highest_seq, 65536 + 3);
+        assert_eq!(block.last_sr, 0);
+
+        // No losses in the next interval
+        receive(&mut source, [4, 5]);
+        let block = source.report_block(Duration::ZERO);
+        assert_eq!(block.cumulative_lost, 2);
+        assert_eq!(block.fraction_lost, 0);
+        assert!(!source.received_since_report());
+    }
+
+    #[test]
+    fn wrap_during_probation() {
+        let mut source = ReceiverSource::new(1);
+        receive(&mut source, [65535, 0, 1]);
+        assert!(source.is_valid());
+        assert_eq!(source.expected(), 3);
+        assert_eq!(source.lost(), 0);
+    }
+
+    #[test]
+    fn restart() {
+        let mut source = ReceiverSource::new(1);
+        receive(&mut source, [100, 101, 102]);
+
+        // A large jump is ignored unless it is followed by a sequential packet
+        receive(&mut source, [20000]);
+        assert_eq!(source.extended_max_seq(), 102);
+        receive(&mut source, [20001]);
+        assert_eq!(source.extended_max_seq(), 20001);
+        assert_eq!(source.expected(), 1);
+    }
+
+    #[test]
+    fn jitter() {
+        let mut source = ReceiverSource::new(1);
+        // 8kHz stream, 20ms packets received on time
+        for i in 0..10u16 {
+            source.process_rtp(
+                i,
+                i as u32 * 160,
+                160,
+                Duration::from_millis(20 * i as u64),
+                Some(8000),
+            );
+        }
+        assert_eq!(source.jitter(), 0);
+
+        // One packet 10ms late
+        source.process_rtp(10, 1600, 160, Duration::from_millis(210), Some(8000));
+        assert_eq!(source.jitter(), 80 / 16);
+    }
+
+    #[test]
+    fn sender_report_mapping() {
+        let mut source = ReceiverSource::new(1);
+        assert_eq!(source.ntp_time_for(0, 90_000), None);
+
+        let ntp = ntp_from_duration(Duration::from_secs(10_000));
+        source.set_sender_report(
+            SenderInfo {
+                ntp_time: ntp,
+                rtp_time: 90_000,
+                ..Default::default()
+            },
+            Duration::from_secs(1),
+        );
+
+        assert_eq!(
+            source.ntp_time_for(180_000, 90_000),
+            Some(Duration::from_secs(10_001))
+        );
+        assert_eq!(
+            source.ntp_time_for(45_000, 90_000),
+            Some(Duration::from_millis(9_999_500))
+        );
+
+        let block = source.report_block(Duration::from_millis(1500));
+        assert_eq!(block.last_sr, ntp_middle(ntp));
+        assert_eq!(block.delay_since_last_sr, 32768);
+    }
+
+    #[test]
+    fn local_sender() {
+        let mut sender = LocalSender::default();
+        assert!(!sender.sent_since_report());
+
+        sender.process_rtp(42, 1000, 100, Duration::from_secs(1));
+        sender.process_rtp(42, 1160, 100, Duration::from_millis(1020));
+        assert_eq!(sender.ssrc(), Some(42));
+        assert!(sender.sent_since_report());
+
+        let info = sender.sender_info(Duration::from_millis(1040), 1234, Some(8000));
+        assert_eq!(info.ntp_time, 1234);
+        assert_eq!(info.rtp_time, 1320);
+        assert_eq!(info.packet_count, 2);
+        assert_eq!(info.octet_count, 200);
+        assert!(!sender.sent_since_report());
+    }
+
+    #[test]
+    fn rtt() {
+        let sent = ntp_from_duration(Duration::from_secs(100));
+        let arrival = ntp_from_duration(Duration::from_millis(100_300));
+        let block = ReportBlock {
+            last_sr: ntp_middle(sent),
+            delay_since_last_sr: to_ntp_short(Duration::from_millis(100)),
+            ..Default::default()
+        };
+
+        let rtt = round_trip_time(&block, arrival).unwrap();
+        assert!(rtt >= Duration::from_millis(199) && rtt <= Duration::from_millis(201));
+
+        assert_eq!(round_trip_time(&ReportBlock::default(), arrival), None);
+    }
+}
@@ -14,7 +14,7 @@ use std::sync::{Arc, Mutex};
 use std::task::{self, Poll};
 use std::time::Duration;
 
-use super::{Handle, HandleWeak, JoinHandle, Scheduler, SubTaskOutput, TaskId};
+use super::{ContextStats, Handle, HandleWeak, JoinHandle, Scheduler, SubTaskOutput, TaskId};
 use crate::runtime::RUNTIME_CAT;
 
 // We are bound to using `sync` for the `runtime` `Mutex`es. Attempts to use `async` `Mutex`es
@@ -172,6 +172,16 @@ impl Context {
         Ok(context)
     }
 
+    /// Returns all the `Context`s currently running.
+    pub fn all() -> Vec<Context> {
+        CONTEXTS
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(ContextWeak::upgrade)
+            .collect()
+    }
+
     pub fn downgrade(&self) -> ContextWeak {
         ContextWeak(self.0.downgrade())
     }
@@ -195,6 +205,13 @@ impl Context {
         self.0.parked_duration()
     }
 
+    /// Returns load statistics for this `Context`.
+    ///
+    /// See [`ContextStats`].
+    pub fn stats(&self) -> ContextStats {
+        self.0.stats()
+    }
+
     /// Returns `true` if a `Context` is running on current thread.
     pub fn is_context_thread() -> bool {
         Scheduler::is_scheduler_thread()
@@ -411,6 +428,29 @@ mod tests {
         futures::executor::block_on(join_handle).unwrap();
     }
 
+    #[test]
+    fn stats() {
+        gst::init().unwrap();
+
+        let context = Context::acquire("context_stats", SLEEP_DURATION).unwrap();
+        assert!(Context::all().contains(&context));
+
+        let initial = context.stats();
+
+        let join_handle = context.spawn(async {
+            crate::runtime::timer::delay_for(DELAY).await;
+        });
+        futures::executor::block_on(join_handle).unwrap();
+
+        let stats = context.stats().since(&initial);
+        assert!(stats.iterations > 0);
+        assert!(stats.timers_fired >= 1);
+        assert!(stats.reactor_wakeups > 0);
+        assert!(stats.parked_duration > Duration::ZERO);
+        assert_eq!(stats.tasks, 0);
+        assert_eq!(stats.sub_tasks, 0);
+    }
+
     #[test]
     fn drain_sub_tasks() {
         // Setup
@@ -37,6 +37,9 @@ use reactor::{Reactor, Readable, ReadableOwned, Source, Writable, WritableOwned}
 mod scheduler;
 use scheduler::{Handle, HandleWeak, Scheduler};
 
+mod stats;
+pub use stats::ContextStats;
+
 mod task;
 pub use task::{SubTaskOutput, TaskId};
 
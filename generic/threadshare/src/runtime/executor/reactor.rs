@@ -29,6 +29,7 @@ use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll, Waker};
 use std::time::{Duration, Instant};
 
+use super::stats::Reaction;
 use crate::runtime::{Async, RUNTIME_CAT};
 
 const READ: usize = 0;
@@ -304,7 +305,9 @@ impl Reactor {
     }
 
     /// Processes ready timers and extends the list of wakers to wake.
-    fn process_timers(&mut self, now: Instant) {
+    ///
+    /// Accounts for the fired timers in `reaction`.
+    fn process_timers(&mut self, now: Instant, reaction: &mut Reaction) {
         self.process_timer_ops();
 
         self.timers_check_instant = now;
@@ -328,7 +331,8 @@ impl Reactor {
                 ready.len()
             );
 
-            for (_, waker) in ready {
+            for ((when, _), waker) in ready {
+                reaction.add_fired_timer(now.saturating_duration_since(when));
                 self.wakers.push(waker);
             }
         }
@@ -350,7 +354,8 @@ impl Reactor {
                 ready.len()
             );
 
-            for (_, waker) in ready {
+            for ((when, _), waker) in ready {
+                reaction.add_fired_timer(now.saturating_duration_since(when));
                 self.wakers.push(waker);
             }
         }
@@ -380,11 +385,13 @@ impl Reactor {
     }
 
     /// Processes new events.
-    pub fn react(&mut self, now: Instant) -> io::Result<()> {
+    pub fn react(&mut self, now: Instant) -> io::Result<Reaction> {
         debug_assert!(self.wakers.is_empty());
 
+        let mut reaction = Reaction::default();
+
         // Process ready timers.
-        self.process_timers(now);
+        self.process_timers(now, &mut reaction);
 
         // Bump the ticker before polling I/O.
         let tick = self.ticker.fetch_add(1, Ordering::SeqCst).wrapping_add(1);
@@ -396,7 +403,9 @@ impl Reactor {
             // No I/O events occurred.
             Ok(0) => Ok(()),
             // At least one I/O event occurred.
-            Ok(_) => {
+            Ok(events) => {
+                reaction.events = events;
+
                 for ev in self.events.iter() {
                     // Check if there is a source in the table with this key.
                     if let Some(source) = self.sources.get(ev.key) {
@@ -446,7 +455,7 @@ impl Reactor {
             }
         }
 
-        res
+        res.map(|_| reaction)
     }
 }
 
@@ -11,8 +11,6 @@ use gio::glib::clone::Downgrade;
 use std::cell::RefCell;
 use std::future::Future;
 use std::panic;
-#[cfg(feature = "tuning")]
-use std::sync::atomic::AtomicU64;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc as sync_mpsc;
 use std::sync::{Arc, Condvar, Mutex, Weak};
@@ -22,6 +20,7 @@ use std::time::{Duration, Instant};
 
 use waker_fn::waker_fn;
 
+use super::stats::{ContextStats, StatsCounters};
 use super::task::{SubTaskOutput, TaskId, TaskQueue};
 use super::{CallOnDrop, JoinHandle, Reactor};
 use crate::runtime::RUNTIME_CAT;
@@ -37,8 +36,7 @@ pub(super) struct Scheduler {
     tasks: TaskQueue,
     must_unpark: Mutex<bool>,
     must_unpark_cvar: Condvar,
-    #[cfg(feature = "tuning")]
-    parked_duration: AtomicU64,
+    stats: StatsCounters,
 }
 
 impl Scheduler {
@@ -119,8 +117,7 @@ impl Scheduler {
                 tasks: TaskQueue::new(context_name),
                 must_unpark: Mutex::new(false),
                 must_unpark_cvar: Condvar::new(),
-                #[cfg(feature = "tuning")]
-                parked_duration: AtomicU64::new(0),
+                stats: StatsCounters::default(),
             }));
 
             *cur_scheduler = Some(handle.downgrade());
@@ -204,11 +201,15 @@ impl Scheduler {
         let mut last_react = Instant::now() - self.max_throttling;
         let mut tasks_checked;
         'main: loop {
+            self.stats.add_iteration();
+
             // Only check I/O and timers every `max_throttling`.
             now = Instant::now();
             if now - last_react >= self.max_throttling {
                 last_react = now;
-                Reactor::with_mut(|reactor| reactor.react(now).ok());
+                if let Ok(reaction) = Reactor::with_mut(|reactor| reactor.react(now)) {
+                    self.stats.add_reaction(&reaction);
+                }
             }
 
             if let Poll::Ready(t) = termination_future.as_mut().poll(cx) {
@@ -240,16 +241,12 @@ impl Scheduler {
                         if let Some(parking_duration) =
                             self.max_throttling.checked_sub(last_react.elapsed())
                         {
-                            #[cfg(feature = "tuning")]
-                            self.parked_duration.fetch_add(
-                                parking_duration.subsec_nanos() as u64,
-                                Ordering::Relaxed,
-                            );
-
+                            let parking_start = Instant::now();
                             let result = self
                                 .must_unpark_cvar
                                 .wait_timeout(must_unpark, parking_duration)
                                 .unwrap();
+                            self.stats.add_parked(parking_start.elapsed());
 
                             must_unpark = result.0;
                         } else {
@@ -389,7 +386,12 @@ impl Handle {
 
     #[cfg(feature = "tuning")]
     pub fn parked_duration(&self) -> Duration {
-        Duration::from_nanos(self.0.scheduler.parked_duration.load(Ordering::Relaxed))
+        self.0.scheduler.stats.parked_duration()
+    }
+
+    pub fn stats(&self) -> ContextStats {
+        let (tasks, sub_tasks) = self.0.scheduler.tasks.counts();
+        self.0.scheduler.stats.snapshot(tasks, sub_tasks)
     }
 
     /// Executes the provided function relatively to this [`Scheduler`]'s [`Reactor`].
@@ -0,0 +1,284 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// Take a look at the license at the top of the repository in the LICENSE file.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Load statistics for a [`Context`].
+///
+/// Counters are cumulative since the [`Context`] was started. Use
+/// [`ContextStats::since`] to compute the statistics for an interval.
+///
+/// [`Context`]: super::Context
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContextStats {
+    /// Number of scheduler loop iterations.
+    pub iterations: u64,
+    /// Time spent running tasks and reacting to i/o & timers.
+    pub busy_duration: Duration,
+    /// Time spent parked, waiting for a task to be woken up.
+    pub parked_duration: Duration,
+    /// Number of tasks currently spawned on the `Context`.
+    pub tasks: usize,
+    /// Number of sub tasks currently pending on the `Context`.
+    pub sub_tasks: usize,
+    /// Number of timers which fired.
+    pub timers_fired: u64,
+    /// Accumulated lateness of the fired timers.
+    ///
+    /// Regular timers can fire up to half the `Context` wait duration before
+    /// their expected time, they are then not accounted as late.
+    pub timer_lateness: Duration,
+    /// Maximum lateness of a fired timer.
+    pub max_timer_lateness: Duration,
+    /// Number of times the reactor was polled.
+    pub reactor_wakeups: u64,
+    /// Number of i/o events returned by the reactor.
+    pub reactor_events: u64,
+}
+
+impl ContextStats {
+    /// Returns the ratio of time the `Context` spent being busy, between 0 and 1.
+    pub fn load(&self) -> f64 {
+        let total = self.busy_duration + self.parked_duration;
+        if total.is_zero() {
+            return 0.0;
+        }
+
+        self.busy_duration.as_secs_f64() / total.as_secs_f64()
+    }
+
+    /// Returns the mean lateness of the fired timers.
+    pub fn mean_timer_lateness(&self) -> Option<Duration> {
+        if self.timers_fired == 0 {
+            return None;
+        }
+
+        Some(Duration::from_nanos(
+            self.timer_lateness.as_nanos() as u64 / self.timers_fired,
+        ))
+    }
+
+    /// Returns the mean number of i/o events per reactor wakeup.
+    pub fn events_per_wakeup(&self) -> f64 {
+        if self.reactor_wakeups == 0 {
+            return 0.0;
+        }
+
+        self.reactor_events as f64 / self.reactor_wakeups as f64
+    }
+
+    /// Computes the statistics for the interval since `prev` was collected.
+    ///
+    /// The current number of tasks and sub tasks are kept as is. Since it
+    /// can't be computed for the interval, `max_timer_lateness` is the
+    /// maximum since the `Context` was started.
+    pub fn since(&self, prev: &ContextStats) -> ContextStats {
+        ContextStats {
+            iterations: self.iterations.saturating_sub(prev.iterations),
+            busy_duration: self.busy_duration.saturating_sub(prev.busy_duration),
+            parked_duration: self.parked_duration.saturating_sub(prev.parked_duration),
+            tasks: self.tasks,
+            sub_tasks: self.sub_tasks,
+            timers_fired: self.timers_fired.saturating_sub(prev.timers_fired),
+            timer_lateness: self.timer_lateness.saturating_sub(prev.timer_lateness),
+            max_timer_lateness: self.max_timer_lateness,
+            reactor_wakeups: self.reactor_wakeups.saturating_sub(prev.reactor_wakeups),
+            reactor_events: self.reactor_events.saturating_sub(prev.reactor_events),
+        }
+    }
+
+    /// Builds a `Structure` with the statistics for the `Context` named `context_name`.
+    pub fn to_structure(&self, context_name: &str) -> gst::Structure {
+        gst::Structure::builder("threadshare-context-stats")
+            .field("context", context_name)
+            .field("iterations", self.iterations)
+            .field("busy-duration", self.busy_duration.as_nanos() as u64)
+            .field("parked-duration", self.parked_duration.as_nanos() as u64)
+            .field("load", self.load())
+            .field("tasks", self.tasks as u32)
+            .field("sub-tasks", self.sub_tasks as u32)
+            .field("timers-fired", self.timers_fired)
+            .field(
+                "mean-timer-lateness",
+                self.mean_timer_lateness().unwrap_or_default().as_nanos() as u64,
+            )
+            .field(
+                "max-timer-lateness",
+                self.max_timer_lateness.as_nanos() as u64,
+            )
+            .field("reactor-wakeups", self.reactor_wakeups)
+            .field("reactor-events", self.reactor_events)
+            .field("events-per-wakeup", self.events_per_wakeup())
+            .build()
+    }
+}
+
+/// Outcome of a `Reactor` reaction.
+#[derive(Debug, Default)]
+pub(super) struct Reaction {
+    pub events: usize,
+    pub timers_fired: usize,
+    pub timer_lateness: Duration,
+    pub max_timer_lateness: Duration,
+}
+
+impl Reaction {
+    pub fn add_fired_timer(&mut self, lateness: Duration) {
+        self.timers_fired += 1;
+        self.timer_lateness += lateness;
+        self.max_timer_lateness = self.max_timer_lateness.max(lateness);
+    }
+}
+
+/// Counters updated by the `Scheduler` thread.
+#[derive(Debug)]
+pub(super) struct StatsCounters {
+    start: Instant,
+    iterations: AtomicU64,
+    parked_ns: AtomicU64,
+    timers_fired: AtomicU64,
+    timer_lateness_ns: AtomicU64,
+    max_timer_lateness_ns: AtomicU64,
+    reactor_wakeups: AtomicU64,
+    reactor_events: AtomicU64,
+}
+
+impl Default for StatsCounters {
+    fn default() -> Self {
+        StatsCounters {
+            start: Instant::now(),
+            iterations: AtomicU64::new(0),
+            parked_ns: AtomicU64::new(0),
+            timers_fired: AtomicU64::new(0),
+            timer_lateness_ns: AtomicU64::new(0),
+            max_timer_lateness_ns: AtomicU64::new(0),
+            reactor_wakeups: AtomicU64::new(0),
+            reactor_events: AtomicU64::new(0),
+        }
+    }
+}
+
+impl StatsCounters {
+    pub fn add_iteration(&self) {
+        self.iterations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_parked(&self, duration: Duration) {
+        self.parked_ns
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_reaction(&self, reaction: &Reaction) {
+        self.reactor_wakeups.fetch_add(1, Ordering::Relaxed);
+        self.reactor_events
+            .fetch_add(reaction.events as u64, Ordering::Relaxed);
+
+        if reaction.timers_fired > 0 {
+            self.timers_fired
+                .fetch_add(reaction.timers_fired as u64, Ordering::Relaxed);
+            self.timer_lateness_ns
+                .fetch_add(reaction.timer_lateness.as_nanos() as u64, Ordering::Relaxed);
+            self.max_timer_lateness_ns.fetch_max(
+                reaction.max_timer_lateness.as_nanos() as u64,
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    pub fn parked_duration(&self) -> Duration {
+        Duration::from_nanos(self.parked_ns.load(Ordering::Relaxed))
+    }
+
+    pub fn snapshot(&self, tasks: usize, sub_tasks: usize) -> ContextStats {
+        let parked_duration = self.parked_duration();
+
+        ContextStats {
+            iterations: self.iterations.load(Ordering::Relaxed),
+            busy_duration: self.start.elapsed().saturating_sub(parked_duration),
+            parked_duration,
+            tasks,
+            sub_tasks,
+            timers_fired: self.timers_fired.load(Ordering::Relaxed),
+            timer_lateness: Duration::from_nanos(self.timer_lateness_ns.load(Ordering::Relaxed)),
+            max_timer_lateness: Duration::from_nanos(
+                self.max_timer_lateness_ns.load(Ordering::Relaxed),
+            ),
+            reactor_wakeups: self.reactor_wakeups.load(Ordering::Relaxed),
+            reactor_events: self.reactor_events.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derived_values() {
+        let stats = ContextStats {
+            busy_duration: Duration::from_millis(250),
+            parked_duration: Duration::from_millis(750),
+            timers_fired: 4,
+            timer_lateness: Duration::from_millis(2),
+            reactor_wakeups: 10,
+            reactor_events: 25,
+            ..Default::default()
+        };
+
+        assert!((stats.load() - 0.25).abs() < f64::EPSILON);
+        assert_eq!(
+            stats.mean_timer_lateness(),
+            Some(Duration::from_micros(500))
+        );
+        assert!((stats.events_per_wakeup() - 2.5).abs() < f64::EPSILON);
+
+        let empty = ContextStats::default();
+        assert_eq!(empty.load(), 0.0);
+        assert_eq!(empty.mean_timer_lateness(), None);
+        assert_eq!(empty.events_per_wakeup(), 0.0);
+    }
+
+    #[test]
+    fn counters() {
+        let counters = StatsCounters::default();
+        counters.add_iteration();
+        counters.add_iteration();
+        counters.add_parked(Duration::from_millis(1));
+        counters.add_reaction(&Reaction {
+            events: 3,
+            timers_fired: 2,
+            timer_lateness: Duration::from_micros(300),
+            max_timer_lateness: Duration::from_micros(200),
+        });
+        counters.add_reaction(&Reaction::default());
+
+        let prev = counters.snapshot(2, 1);
+        assert_eq!(prev.iterations, 2);
+        assert_eq!(prev.parked_duration, Duration::from_millis(1));
+        assert_eq!((prev.tasks, prev.sub_tasks), (2, 1));
+        assert_eq!(prev.timers_fired, 2);
+        assert_eq!(prev.max_timer_lateness, Duration::from_micros(200));
+        assert_eq!(prev.reactor_wakeups, 2);
+        assert_eq!(prev.reactor_events, 3);
+
+        counters.add_iteration();
+        counters.add_reaction(&Reaction {
+            events: 1,
+            timers_fired: 1,
+            timer_lateness: Duration::from_micros(100),
+            max_timer_lateness: Duration::from_micros(100),
+        });
+
+        let delta = counters.snapshot(1, 0).since(&prev);
+        assert_eq!(delta.iterations, 1);
+        assert_eq!(delta.parked_duration, Duration::ZERO);
+        assert_eq!((delta.tasks, delta.sub_tasks), (1, 0));
+        assert_eq!(delta.timers_fired, 1);
+        assert_eq!(delta.timer_lateness, Duration::from_micros(100));
+        assert_eq!(delta.max_timer_lateness, Duration::from_micros(200));
+        assert_eq!(delta.reactor_wakeups, 1);
+        assert_eq!(delta.reactor_events, 1);
+    }
+}
@@ -232,6 +232,14 @@ impl TaskQueue {
         task
     }
 
+    /// Returns the number of tasks and pending sub tasks.
+    pub fn counts(&self) -> (usize, usize) {
+        let tasks = self.tasks.lock().unwrap();
+        let sub_tasks = tasks.iter().map(|(_, task)| task.sub_tasks.len()).sum();
+
+        (tasks.len(), sub_tasks)
+    }
+
     pub fn pop_runnable(&self) -> Result<Runnable, concurrent_queue::PopError> {
         self.runnables.pop()
     }
@@ -0,0 +1,904 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use futures::future::BoxFuture;
+use futures::prelude::*;
+use futures::stream::Peekable;
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst::EventView;
+use gst::{element_error, error_msg};
+
+use once_cell::sync::Lazy;
+
+use crate::runtime::prelude::*;
+use crate::runtime::{self, Async, Context, JoinHandle, PadSink, PadSinkRef, Task};
+
+use std::collections::BTreeMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::Poll;
+use std::time::Duration;
+use std::u16;
+
+const DEFAULT_HOST: Option<&str> = Some("0.0.0.0");
+const DEFAULT_PORT: i32 = 4953;
+const DEFAULT_SYNC: bool = true;
+const DEFAULT_MAX_CLIENT_BACKLOG: u32 = 200;
+const DEFAULT_CONTEXT: &str = "";
+const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    host: Option<String>,
+    port: i32,
+    current_port: i32,
+    sync: bool,
+    max_client_backlog: u32,
+    num_clients: u32,
+    context: String,
+    context_wait: Duration,
+    latency: Option<gst::ClockTime>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            host: DEFAULT_HOST.map(Into::into),
+            port: DEFAULT_PORT,
+            current_port: 0,
+            sync: DEFAULT_SYNC,
+            max_client_backlog: DEFAULT_MAX_CLIENT_BACKLOG,
+            num_clients: 0,
+            context: DEFAULT_CONTEXT.into(),
+            context_wait: DEFAULT_CONTEXT_WAIT,
+            latency: None,
+        }
+    }
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ts-tcpserversink",
+        gst::DebugColorFlags::empty(),
+        Some("Thread-sharing TCP Server sink"),
+    )
+});
+
+#[derive(Debug)]
+enum TaskItem {
+    Buffer(gst::Buffer),
+    Event(gst::Event),
+}
+
+#[derive(Clone, Debug)]
+struct TcpServerSinkPadHandler;
+
+impl PadSinkHandler for TcpServerSinkPadHandler {
+    type ElementImpl = TcpServerSink;
+
+    fn sink_chain(
+        &self,
+        _pad: &PadSinkRef,
+        tcpserversink: &TcpServerSink,
+        element: &gst::Element,
+        buffer: gst::Buffer,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let sender = tcpserversink.clone_item_sender();
+        let element = element.clone().downcast::<super::TcpServerSink>().unwrap();
+
+        async move {
+            if sender.send_async(TaskItem::Buffer(buffer)).await.is_err() {
+                gst::debug!(CAT, obj: &element, "Flushing");
+                return Err(gst::FlowError::Flushing);
+            }
+
+            Ok(gst::FlowSuccess::Ok)
+        }
+        .boxed()
+    }
+
+    fn sink_chain_list(
+        &self,
+        _pad: &PadSinkRef,
+        tcpserversink: &TcpServerSink,
+        element: &gst::Element,
+        list: gst::BufferList,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let sender = tcpserversink.clone_item_sender();
+        let element = element.clone().downcast::<super::TcpServerSink>().unwrap();
+
+        async move {
+            for buffer in list.iter_owned() {
+                if sender.send_async(TaskItem::Buffer(buffer)).await.is_err() {
+                    gst::debug!(CAT, obj: &element, "Flushing");
+                    return Err(gst::FlowError::Flushing);
+                }
+            }
+
+            Ok(gst::FlowSuccess::Ok)
+        }
+        .boxed()
+    }
+
+    fn sink_event_serialized(
+        &self,
+        _pad: &PadSinkRef,
+        tcpserversink: &TcpServerSink,
+        element: &gst::Element,
+        event: gst::Event,
+    ) -> BoxFuture<'static, bool> {
+        let sender = tcpserversink.clone_item_sender();
+        let element = element.clone().downcast::<super::TcpServerSink>().unwrap();
+
+        async move {
+            if let EventView::FlushStop(_) = event.view() {
+                let tcpserversink = element.imp();
+                return tcpserversink
+                    .task
+                    .flush_stop()
+                    .await_maybe_on_context()
+                    .is_ok();
+            } else if sender.send_async(TaskItem::Event(event)).await.is_err() {
+                gst::debug!(CAT, obj: &element, "Flushing");
+            }
+
+            true
+        }
+        .boxed()
+    }
+
+    fn sink_event(
+        &self,
+        _pad: &PadSinkRef,
+        tcpserversink: &TcpServerSink,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        if let EventView::FlushStart(..) = event.view() {
+            return tcpserversink
+                .task
+                .flush_start()
+                .await_maybe_on_context()
+                .is_ok();
+        }
+
+        true
+    }
+}
+
+#[derive(Debug)]
+enum Command {
+    /// A client writer terminated, with the error which caused it, if any.
+    ClientGone(SocketAddr, Option<io::Error>),
+    SetLatency(Option<gst::ClockTime>),
+    SetSync(bool),
+}
+
+/// A connected client.
+///
+/// Buffers are queued for each client and written to its socket by a dedicated
+/// future spawned on the task's `Context`, so a slow client doesn't hold back the others.
+struct Client {
+    sender: flume::Sender<gst::Buffer>,
+    writer: JoinHandle<()>,
+}
+
+/// Writes the buffers queued for a client until the queue is closed or an error occurs.
+async fn client_writer(
+    mut socket: Async<TcpStream>,
+    saddr: SocketAddr,
+    receiver: flume::Receiver<gst::Buffer>,
+    cmd_sender: flume::Sender<Command>,
+) {
+    let res = async {
+        while let Ok(buffer) = receiver.recv_async().await {
+            let data = buffer.map_readable().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "Failed to map buffer readable")
+            })?;
+            socket.write_all(&data).await?;
+        }
+
+        Ok(())
+    }
+    .await;
+
+    let _ = cmd_sender.send(Command::ClientGone(saddr, res.err()));
+}
+
+enum Event {
+    Command(Command),
+    Accepted(io::Result<(Async<TcpStream>, SocketAddr)>),
+    Item(Option<gst::ClockTime>),
+}
+
+struct TcpServerSinkTask {
+    element: super::TcpServerSink,
+    saddr: SocketAddr,
+    item_receiver: Peekable<flume::r#async::RecvStream<'static, TaskItem>>,
+    cmd_receiver: flume::Receiver<Command>,
+    cmd_sender: flume::Sender<Command>,
+    listener: Option<Async<TcpListener>>,
+    clients: BTreeMap<SocketAddr, Client>,
+    streamheader: Vec<gst::Buffer>,
+    max_client_backlog: u32,
+    sync: bool,
+    latency: Option<gst::ClockTime>,
+    segment: Option<gst::Segment>,
+}
+
+impl TcpServerSinkTask {
+    fn new(
+        element: &super::TcpServerSink,
+        saddr: SocketAddr,
+        item_receiver: flume::Receiver<TaskItem>,
+        cmd_receiver: flume::Receiver<Command>,
+        cmd_sender: flume::Sender<Command>,
+    ) -> Self {
+        TcpServerSinkTask {
+            element: element.clone(),
+            saddr,
+            item_receiver: item_receiver.into_stream().peekable(),
+            cmd_receiver,
+            cmd_sender,
+            listener: None,
+            clients: BTreeMap::new(),
+            streamheader: Vec::new(),
+            max_client_backlog: DEFAULT_MAX_CLIENT_BACKLOG,
+            sync: false,
+            latency: None,
+            segment: None,
+        }
+    }
+
+    async fn flush(&mut self) {
+        // Purge the channel
+        while let Poll::Ready(Some(_item)) = futures::poll!(self.item_receiver.next()) {}
+    }
+
+    fn process_command(&mut self, cmd: Command) {
+        use Command::*;
+        match cmd {
+            ClientGone(saddr, err) => {
+                if let Some(err) = err {
+                    gst::warning!(CAT, obj: &self.element, "Client {:?} failed: {}", saddr, err);
+                }
+                self.remove_client(&saddr);
+            }
+            SetSync(sync) => self.sync = sync,
+            SetLatency(latency) => self.latency = latency,
+        }
+    }
+
+    fn update_num_clients(&self) {
+        self.element.imp().settings.lock().unwrap().num_clients = self.clients.len() as u32;
+    }
+}
+
+/// Client management.
+impl TcpServerSinkTask {
+    fn add_client(&mut self, socket: Async<TcpStream>, saddr: SocketAddr) {
+        let (sender, receiver) = if self.max_client_backlog > 0 {
+            flume::bounded(self.max_client_backlog as usize)
+        } else {
+            flume::unbounded()
+        };
+
+        // New clients need the stream headers before they can make sense of the data
+        for buffer in self.streamheader.iter() {
+            let _ = sender.try_send(buffer.clone());
+        }
+
+        let writer = Context::current().unwrap().spawn(client_writer(
+            socket,
+            saddr,
+            receiver,
+            self.cmd_sender.clone(),
+        ));
+
+        self.clients.insert(saddr, Client { sender, writer });
+        self.update_num_clients();
+
+        gst::info!(CAT, obj: &self.element, "Added client {:?}", saddr);
+        self.element.emit_by_name::<()>(
+            "client-added",
+            &[&saddr.ip().to_string(), &(saddr.port() as i32)],
+        );
+    }
+
+    fn remove_client(&mut self, saddr: &SocketAddr) {
+        let client = match self.clients.remove(saddr) {
+            Some(client) => client,
+            None => {
+                gst::trace!(CAT, obj: &self.element, "Client {:?} already removed", saddr);
+                return;
+            }
+        };
+
+        // Dropping the socket closes the connection
+        client.writer.cancel();
+        self.update_num_clients();
+
+        gst::info!(CAT, obj: &self.element, "Removed client {:?}", saddr);
+        self.element.emit_by_name::<()>(
+            "client-removed",
+            &[&saddr.ip().to_string(), &(saddr.port() as i32)],
+        );
+    }
+
+    fn remove_all_clients(&mut self) {
+        let clients: Vec<SocketAddr> = self.clients.keys().cloned().collect();
+        for saddr in clients.iter() {
+            self.remove_client(saddr);
+        }
+    }
+
+    fn set_streamheader(&mut self, caps: &gst::CapsRef) {
+        self.streamheader = caps
+            .structure(0)
+            .and_then(|s| s.get::<gst::Array>("streamheader").ok())
+            .map(|streamheader| {
+                streamheader
+                    .iter()
+                    .filter_map(|value| value.get::<gst::Buffer>().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        gst::debug!(
+            CAT,
+            obj: &self.element,
+            "Got {} streamheader buffers",
+            self.streamheader.len()
+        );
+    }
+}
+
+/// Buffer handling.
+impl TcpServerSinkTask {
+    fn render(&mut self, buffer: gst::Buffer) {
+        let mut to_remove = Vec::new();
+
+        for (saddr, client) in self.clients.iter() {
+            match client.sender.try_send(buffer.clone()) {
+                Ok(()) => {
+                    gst::log!(CAT, obj: &self.element, "Queued buffer for {:?}", saddr);
+                }
+                Err(flume::TrySendError::Full(_)) => {
+                    gst::warning!(
+                        CAT,
+                        obj: &self.element,
+                        "Client {:?} exceeded the backlog of {} buffers, dropping",
+                        saddr,
+                        self.max_client_backlog,
+                    );
+                    to_remove.push(*saddr);
+                }
+                Err(flume::TrySendError::Disconnected(_)) => {
+                    to_remove.push(*saddr);
+                }
+            }
+        }
+
+        for saddr in to_remove.iter() {
+            self.remove_client(saddr);
+        }
+
+        gst::log!(
+            CAT,
+            obj: &self.element,
+            "Queued buffer {:?} for all clients",
+            &buffer
+        );
+    }
+
+    /// Waits until specified time.
+    async fn sync(&self, running_time: gst::ClockTime) {
+        let now = self.element.current_running_time();
+
+        if let Ok(Some(delay)) = running_time.opt_checked_sub(now) {
+            gst::trace!(CAT, obj: &self.element, "sync: waiting {}", delay);
+            runtime::timer::delay_for(delay.into()).await;
+        }
+    }
+}
+
+impl TaskImpl for TcpServerSinkTask {
+    type Item = TaskItem;
+
+    fn prepare(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::info!(CAT, obj: &self.element, "Preparing Task listening on {:?}", self.saddr);
+
+            let listener = Async::<TcpListener>::bind(self.saddr).map_err(|err| {
+                error_msg!(
+                    gst::ResourceError::OpenWrite,
+                    ["Failed to bind to {:?}: {}", self.saddr, err]
+                )
+            })?;
+
+            let current_port = listener
+                .get_ref()
+                .local_addr()
+                .map_err(|err| {
+                    error_msg!(
+                        gst::ResourceError::OpenWrite,
+                        ["Failed to retrieve bound address: {}", err]
+                    )
+                })?
+                .port();
+
+            {
+                let tcpserversink = self.element.imp();
+                let mut settings = tcpserversink.settings.lock().unwrap();
+                settings.current_port = current_port as i32;
+                self.sync = settings.sync;
+                self.latency = settings.latency;
+                self.max_client_backlog = settings.max_client_backlog;
+            }
+
+            self.listener = Some(listener);
+
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn unprepare(&mut self) -> BoxFuture<'_, ()> {
+        async move {
+            gst::info!(CAT, obj: &self.element, "Unpreparing Task");
+
+            self.remove_all_clients();
+            self.listener = None;
+            self.element.imp().settings.lock().unwrap().current_port = 0;
+        }
+        .boxed()
+    }
+
+    fn try_next(&mut self) -> BoxFuture<'_, Result<TaskItem, gst::FlowError>> {
+        async move {
+            loop {
+                gst::info!(CAT, obj: &self.element, "Awaiting next item, command or client");
+
+                let event = {
+                    let accept = self.listener.as_ref().unwrap().accept().fuse();
+                    futures::pin_mut!(accept);
+
+                    futures::select_biased! {
+                        cmd = self.cmd_receiver.recv_async() => {
+                            Event::Command(cmd.unwrap())
+                        }
+                        res = accept => Event::Accepted(res),
+                        item_opt = Pin::new(&mut self.item_receiver).peek() => {
+                            // Check the peeked item in case we need to sync.
+                            // The item will still be available in the channel
+                            // in case this is cancelled by a state transition.
+                            match item_opt {
+                                Some(TaskItem::Buffer(buffer)) => Event::Item(buffer.pts()),
+                                Some(_) => Event::Item(None),
+                                None => {
+                                    panic!("Internal channel sender dropped while Task is Started");
+                                }
+                            }
+                        }
+                    }
+                };
+
+                match event {
+                    Event::Command(cmd) => self.process_command(cmd),
+                    Event::Accepted(Ok((socket, saddr))) => self.add_client(socket, saddr),
+                    Event::Accepted(Err(err)) => {
+                        gst::warning!(CAT, obj: &self.element, "Failed to accept client: {}", err);
+                    }
+                    Event::Item(pts) => {
+                        if self.sync {
+                            let rtime = self.segment.as_ref().and_then(|segment| {
+                                segment
+                                    .downcast_ref::<gst::format::Time>()
+                                    .and_then(|segment| {
+                                        segment.to_running_time(pts).opt_add(self.latency)
+                                    })
+                            });
+                            if let Some(rtime) = rtime {
+                                // This can be cancelled by a state transition.
+                                self.sync(rtime).await;
+                            }
+                        }
+
+                        // An item was peeked above, we can now pop it without losing it.
+                        return Ok(self.item_receiver.next().await.unwrap());
+                    }
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn handle_item(&mut self, item: TaskItem) -> BoxFuture<'_, Result<(), gst::FlowError>> {
+        async move {
+            gst::info!(CAT, obj: &self.element, "Handling {:?}", item);
+
+            match item {
+                TaskItem::Buffer(buffer) => self.render(buffer),
+                TaskItem::Event(event) => match event.view() {
+                    EventView::Eos(_) => {
+                        let _ = self
+                            .element
+                            .post_message(gst::message::Eos::builder().src(&self.element).build());
+                    }
+                    EventView::Caps(e) => {
+                        self.set_streamheader(e.caps());
+                    }
+                    EventView::Segment(e) => {
+                        self.segment = Some(e.segment().clone());
+                    }
+                    EventView::SinkMessage(e) => {
+                        let _ = self.element.post_message(e.message());
+                    }
+                    _ => (),
+                },
+            }
+
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn stop(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async {
+            gst::info!(CAT, obj: &self.element, "Stopping Task");
+            self.flush().await;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn flush_start(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async {
+            gst::info!(CAT, obj: &self.element, "Starting Task Flush");
+            self.flush().await;
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[derive(Debug)]
+pub struct TcpServerSink {
+    sink_pad: PadSink,
+    task: Task,
+    item_sender: Mutex<Option<flume::Sender<TaskItem>>>,
+    cmd_sender: Mutex<Option<flume::Sender<Command>>>,
+    settings: Mutex<Settings>,
+}
+
+impl TcpServerSink {
+    #[track_caller]
+    fn clone_item_sender(&self) -> flume::Sender<TaskItem> {
+        self.item_sender.lock().unwrap().as_ref().unwrap().clone()
+    }
+
+    fn prepare(&self, element: &super::TcpServerSink) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Preparing");
+
+        let (context, saddr) = {
+            let settings = self.settings.lock().unwrap();
+
+            let context =
+                Context::acquire(&settings.context, settings.context_wait).map_err(|err| {
+                    error_msg!(
+                        gst::ResourceError::OpenWrite,
+                        ["Failed to acquire Context: {}", err]
+                    )
+                })?;
+
+            let host: IpAddr = match settings.host {
+                None => {
+                    return Err(error_msg!(gst::ResourceError::Settings, ["No host set"]));
+                }
+                Some(ref host) => host.parse().map_err(|err| {
+                    error_msg!(
+                        gst::ResourceError::Settings,
+                        ["Invalid host '{}' set: {}", host, err]
+                    )
+                })?,
+            };
+
+            (context, SocketAddr::new(host, settings.port as u16))
+        };
+
+        // Enable backpressure for items
+        let (item_sender, item_receiver) = flume::bounded(0);
+        let (cmd_sender, cmd_receiver) = flume::unbounded();
+        let task_impl = TcpServerSinkTask::new(
+            element,
+            saddr,
+            item_receiver,
+            cmd_receiver,
+            cmd_sender.clone(),
+        );
+        self.task.prepare(task_impl, context).block_on()?;
+
+        *self.item_sender.lock().unwrap() = Some(item_sender);
+        *self.cmd_sender.lock().unwrap() = Some(cmd_sender);
+
+        gst::debug!(CAT, obj: element, "Prepared");
+
+        Ok(())
+    }
+
+    fn unprepare(&self, element: &super::TcpServerSink) {
+        gst::debug!(CAT, obj: element, "Unpreparing");
+        self.task.unprepare().block_on().unwrap();
+        *self.cmd_sender.lock().unwrap() = None;
+        gst::debug!(CAT, obj: element, "Unprepared");
+    }
+
+    fn stop(&self, element: &super::TcpServerSink) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Stopping");
+        self.task.stop().block_on()?;
+        gst::debug!(CAT, obj: element, "Stopped");
+        Ok(())
+    }
+
+    fn start(&self, element: &super::TcpServerSink) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Starting");
+        self.task.start().block_on()?;
+        gst::debug!(CAT, obj: element, "Started");
+        Ok(())
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for TcpServerSink {
+    const NAME: &'static str = "RsTsTcpServerSink";
+    type Type = super::TcpServerSink;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        Self {
+            sink_pad: PadSink::new(
+                gst::Pad::from_template(&klass.pad_template("sink").unwrap(), Some("sink")),
+                TcpServerSinkPadHandler,
+            ),
+            task: Task::default(),
+            item_sender: Default::default(),
+            cmd_sender: Default::default(),
+            settings: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for TcpServerSink {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::builder("context")
+                    .nick("Context")
+                    .blurb("Context name to share threads with")
+                    .default_value(Some(DEFAULT_CONTEXT))
+                    .build(),
+                glib::ParamSpecUInt::builder("context-wait")
+                    .nick("Context Wait")
+                    .blurb("Throttle poll loop to run at most once every this many ms")
+                    .maximum(1000)
+                    .default_value(DEFAULT_CONTEXT_WAIT.as_millis() as u32)
+                    .build(),
+                glib::ParamSpecBoolean::builder("sync")
+                    .nick("Sync")
+                    .blurb("Sync on the clock")
+                    .default_value(DEFAULT_SYNC)
+                    .build(),
+                glib::ParamSpecString::builder("host")
+                    .nick("Host")
+                    .blurb("The host IP address to listen on")
+                    .default_value(DEFAULT_HOST)
+                    .build(),
+                glib::ParamSpecInt::builder("port")
+                    .nick("Port")
+                    .blurb("Port to listen on (0 = random available port)")
+                    .minimum(0)
+                    .maximum(u16::MAX as i32)
+                    .default_value(DEFAULT_PORT)
+                    .build(),
+                glib::ParamSpecInt::builder("current-port")
+                    .nick("Current Port")
+                    .blurb("The port number the socket is currently bound to")
+                    .minimum(0)
+                    .maximum(u16::MAX as i32)
+                    .read_only()
+                    .build(),
+                glib::ParamSpecUInt::builder("max-client-backlog")
+                    .nick("Max Client Backlog")
+                    .blurb("Maximum number of buffers queued for a client before it is dropped (0 = unlimited)")
+                    .default_value(DEFAULT_MAX_CLIENT_BACKLOG)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("num-clients")
+                    .nick("Number of Clients")
+                    .blurb("The number of currently connected clients")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn signals() -> &'static [glib::subclass::Signal] {
+        static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
+            vec![
+                glib::subclass::Signal::builder("client-added")
+                    .param_types([String::static_type(), i32::static_type()])
+                    .build(),
+                glib::subclass::Signal::builder("client-removed")
+                    .param_types([String::static_type(), i32::static_type()])
+                    .build(),
+            ]
+        });
+
+        SIGNALS.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "sync" => {
+                let sync = value.get().expect("type checked upstream");
+                settings.sync = sync;
+                if let Some(cmd_sender) = self.cmd_sender.lock().unwrap().as_mut() {
+                    cmd_sender.send(Command::SetSync(sync)).unwrap();
+                }
+            }
+            "host" => {
+                settings.host = value.get().expect("type checked upstream");
+            }
+            "port" => {
+                settings.port = value.get().expect("type checked upstream");
+            }
+            "max-client-backlog" => {
+                settings.max_client_backlog = value.get().expect("type checked upstream");
+            }
+            "context" => {
+                settings.context = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_else(|| DEFAULT_CONTEXT.into());
+            }
+            "context-wait" => {
+                settings.context_wait = Duration::from_millis(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "sync" => settings.sync.to_value(),
+            "host" => settings.host.to_value(),
+            "port" => settings.port.to_value(),
+            "current-port" => settings.current_port.to_value(),
+            "max-client-backlog" => settings.max_client_backlog.to_value(),
+            "num-clients" => settings.num_clients.to_value(),
+            "context" => settings.context.to_value(),
+            "context-wait" => (settings.context_wait.as_millis() as u32).to_value(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(self.sink_pad.gst_pad()).unwrap();
+
+        crate::set_element_flags(obj, gst::ElementFlags::SINK);
+    }
+}
+
+impl GstObjectImpl for TcpServerSink {}
+
+impl ElementImpl for TcpServerSink {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Thread-sharing TCP server sink",
+                "Sink/Network",
+                "Sends data to all the clients connected via TCP",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::new_any();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::NullToReady => {
+                self.prepare(element).map_err(|err| {
+                    element.post_error_message(err);
+                    gst::StateChangeError
+                })?;
+            }
+            gst::StateChange::ReadyToPaused => {
+                self.start(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::PausedToReady => {
+                self.stop(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::ReadyToNull => {
+                self.unprepare(element);
+            }
+            _ => (),
+        }
+
+        self.parent_change_state(element, transition)
+    }
+
+    fn send_event(&self, _element: &Self::Type, event: gst::Event) -> bool {
+        match event.view() {
+            EventView::Latency(ev) => {
+                let latency = Some(ev.latency());
+                if let Some(cmd_sender) = self.cmd_sender.lock().unwrap().as_mut() {
+                    cmd_sender.send(Command::SetLatency(latency)).unwrap();
+                }
+                self.settings.lock().unwrap().latency = latency;
+                self.sink_pad.gst_pad().push_event(event)
+            }
+            EventView::Step(..) => false,
+            _ => self.sink_pad.gst_pad().push_event(event),
+        }
+    }
+}
@@ -0,0 +1,937 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use futures::future::BoxFuture;
+use futures::prelude::*;
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_net::*;
+
+use once_cell::sync::Lazy;
+
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::time::Duration;
+use std::u16;
+
+use crate::runtime::prelude::*;
+use crate::runtime::task;
+use crate::runtime::{Context, PadSrc, PadSrcRef, Task, TaskState};
+
+use crate::runtime::Async;
+use crate::socket::SocketError;
+
+use super::ClientPolicy;
+
+const DEFAULT_HOST: Option<&str> = Some("0.0.0.0");
+const DEFAULT_PORT: i32 = 4953;
+const DEFAULT_CAPS: Option<gst::Caps> = None;
+const DEFAULT_BLOCKSIZE: u32 = 4096;
+const DEFAULT_CLIENT_POLICY: ClientPolicy = ClientPolicy::Single;
+const DEFAULT_CONTEXT: &str = "";
+const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    host: Option<String>,
+    port: i32,
+    current_port: i32,
+    caps: Option<gst::Caps>,
+    blocksize: u32,
+    client_policy: ClientPolicy,
+    context: String,
+    context_wait: Duration,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            host: DEFAULT_HOST.map(Into::into),
+            port: DEFAULT_PORT,
+            current_port: 0,
+            caps: DEFAULT_CAPS,
+            blocksize: DEFAULT_BLOCKSIZE,
+            client_policy: DEFAULT_CLIENT_POLICY,
+            context: DEFAULT_CONTEXT.into(),
+            context_wait: DEFAULT_CONTEXT_WAIT,
+        }
+    }
+}
+
+/// Reads the next buffer from a client connection, `None` if the client disconnected.
+async fn read_buffer(
+    socket: &mut Async<TcpStream>,
+    buffer_pool: &gst::BufferPool,
+) -> Result<Option<gst::Buffer>, SocketError> {
+    let mut buffer = buffer_pool
+        .acquire_buffer(None)
+        .map_err(SocketError::Gst)?
+        .into_mapped_buffer_writable()
+        .unwrap();
+
+    let len = socket
+        .read(buffer.as_mut_slice())
+        .await
+        .map_err(SocketError::Io)?;
+    if len == 0 {
+        return Ok(None);
+    }
+
+    let mut buffer = buffer.into_buffer();
+    {
+        let buffer = buffer.get_mut().unwrap();
+        if len < buffer.size() {
+            buffer.set_size(len);
+        }
+    }
+
+    Ok(Some(buffer))
+}
+
+#[derive(Clone, Debug)]
+struct TcpServerSrcPadHandler;
+
+impl PadSrcHandler for TcpServerSrcPadHandler {
+    type ElementImpl = TcpServerSrc;
+
+    fn src_event(
+        &self,
+        pad: &PadSrcRef,
+        tcpserversrc: &TcpServerSrc,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", event);
+
+        let ret = match event.view() {
+            EventView::FlushStart(..) => tcpserversrc
+                .task
+                .flush_start()
+                .await_maybe_on_context()
+                .is_ok(),
+            EventView::FlushStop(..) => tcpserversrc
+                .task
+                .flush_stop()
+                .await_maybe_on_context()
+                .is_ok(),
+            EventView::Reconfigure(..) => true,
+            EventView::Latency(..) => true,
+            _ => false,
+        };
+
+        if ret {
+            gst::log!(CAT, obj: pad.gst_pad(), "Handled {:?}", event);
+        } else {
+            gst::log!(CAT, obj: pad.gst_pad(), "Didn't handle {:?}", event);
+        }
+
+        ret
+    }
+
+    fn src_query(
+        &self,
+        pad: &PadSrcRef,
+        tcpserversrc: &TcpServerSrc,
+        _element: &gst::Element,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        use gst::QueryViewMut;
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", query);
+        let ret = match query.view_mut() {
+            QueryViewMut::Latency(q) => {
+                q.set(false, gst::ClockTime::ZERO, gst::ClockTime::NONE);
+                true
+            }
+            QueryViewMut::Scheduling(q) => {
+                q.set(gst::SchedulingFlags::SEQUENTIAL, 1, -1, 0);
+                q.add_scheduling_modes(&[gst::PadMode::Push]);
+                true
+            }
+            QueryViewMut::Caps(q) => {
+                let caps = if let Some(caps) = tcpserversrc.configured_caps.lock().unwrap().as_ref()
+                {
+                    q.filter()
+                        .map(|f| f.intersect_with_mode(caps, gst::CapsIntersectMode::First))
+                        .unwrap_or_else(|| caps.clone())
+                } else {
+                    q.filter()
+                        .map(|f| f.to_owned())
+                        .unwrap_or_else(gst::Caps::new_any)
+                };
+
+                q.set_result(&caps);
+
+                true
+            }
+            _ => false,
+        };
+
+        if ret {
+            gst::log!(CAT, obj: pad.gst_pad(), "Handled {:?}", query);
+        } else {
+            gst::log!(CAT, obj: pad.gst_pad(), "Didn't handle {:?}", query);
+        }
+
+        ret
+    }
+}
+
+#[derive(Debug)]
+enum TaskItem {
+    Buffer(gst::Buffer),
+    Eos,
+}
+
+enum Event {
+    Accepted(std::io::Result<(Async<TcpStream>, SocketAddr)>),
+    Read(Result<Option<gst::Buffer>, SocketError>),
+}
+
+/// The client connection currently being read from.
+struct Connection {
+    socket: Async<TcpStream>,
+    saddr: SocketAddr,
+    /// Whether no buffer was pushed for this client yet
+    is_new: bool,
+}
+
+struct TcpServerSrcTask {
+    element: super::TcpServerSrc,
+    saddr: SocketAddr,
+    client_policy: ClientPolicy,
+    buffer_pool: gst::BufferPool,
+    listener: Option<Async<TcpListener>>,
+    connection: Option<Connection>,
+    /// Clients waiting for the current connection to close
+    pending_clients: VecDeque<(Async<TcpStream>, SocketAddr)>,
+    need_initial_events: bool,
+    need_segment: bool,
+}
+
+impl TcpServerSrcTask {
+    fn new(
+        element: super::TcpServerSrc,
+        saddr: SocketAddr,
+        client_policy: ClientPolicy,
+        buffer_pool: gst::BufferPool,
+    ) -> Self {
+        TcpServerSrcTask {
+            element,
+            saddr,
+            client_policy,
+            buffer_pool,
+            listener: None,
+            connection: None,
+            pending_clients: VecDeque::new(),
+            need_initial_events: true,
+            need_segment: true,
+        }
+    }
+
+    fn close_connections(&mut self) {
+        let count = self.connection.iter().count() + self.pending_clients.len();
+        if count > 0 {
+            gst::debug!(
+                CAT,
+                obj: &self.element,
+                "Closing {} client connection(s)",
+                count
+            );
+        }
+
+        self.connection = None;
+        self.pending_clients.clear();
+    }
+
+    fn accept(&mut self, socket: Async<TcpStream>, saddr: SocketAddr) {
+        if self.connection.is_some() {
+            if self.client_policy == ClientPolicy::Single {
+                gst::warning!(
+                    CAT,
+                    obj: &self.element,
+                    "Rejecting client {:?}: already serving a client",
+                    saddr
+                );
+                return;
+            }
+
+            // Data of concurrent clients must not be interleaved on the src pad,
+            // so they are served one after the other
+            gst::info!(
+                CAT,
+                obj: &self.element,
+                "Accepted client {:?}, waiting for current client to disconnect",
+                saddr
+            );
+            self.pending_clients.push_back((socket, saddr));
+            return;
+        }
+
+        gst::info!(CAT, obj: &self.element, "Accepted client {:?}", saddr);
+
+        self.connection = Some(Connection {
+            socket,
+            saddr,
+            is_new: true,
+        });
+    }
+
+    /// Switches to the next pending client once the current one is gone.
+    fn next_connection(&mut self) {
+        self.connection = None;
+
+        if let Some((socket, saddr)) = self.pending_clients.pop_front() {
+            gst::info!(CAT, obj: &self.element, "Serving client {:?}", saddr);
+
+            self.connection = Some(Connection {
+                socket,
+                saddr,
+                is_new: true,
+            });
+        }
+    }
+
+    async fn push_initial_events(&mut self) {
+        let tcpserversrc = self.element.imp();
+
+        if self.need_initial_events {
+            gst::debug!(CAT, obj: &self.element, "Pushing initial events");
+
+            let stream_id = format!("{:08x}{:08x}", rand::random::<u32>(), rand::random::<u32>());
+            let stream_start_evt = gst::event::StreamStart::builder(&stream_id)
+                .group_id(gst::GroupId::next())
+                .build();
+            tcpserversrc.src_pad.push_event(stream_start_evt).await;
+
+            let caps = tcpserversrc.settings.lock().unwrap().caps.clone();
+            if let Some(caps) = caps {
+                tcpserversrc
+                    .src_pad
+                    .push_event(gst::event::Caps::new(&caps))
+                    .await;
+                *tcpserversrc.configured_caps.lock().unwrap() = Some(caps);
+            }
+
+            self.need_initial_events = false;
+        }
+
+        if self.need_segment {
+            let segment_evt =
+                gst::event::Segment::new(&gst::FormattedSegment::<gst::format::Time>::new());
+            tcpserversrc.src_pad.push_event(segment_evt).await;
+
+            self.need_segment = false;
+        }
+    }
+
+    async fn push_buffer(
+        &mut self,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::log!(CAT, obj: &self.element, "Handling {:?}", buffer);
+
+        self.push_initial_events().await;
+
+        let tcpserversrc = self.element.imp();
+        let res = tcpserversrc.src_pad.push(buffer).await;
+        match res {
+            Ok(_) => {
+                gst::log!(CAT, obj: &self.element, "Successfully pushed buffer");
+            }
+            Err(gst::FlowError::Flushing) => {
+                gst::debug!(CAT, obj: &self.element, "Flushing");
+            }
+            Err(gst::FlowError::Eos) => {
+                gst::debug!(CAT, obj: &self.element, "EOS");
+                tcpserversrc
+                    .src_pad
+                    .push_event(gst::event::Eos::new())
+                    .await;
+            }
+            Err(err) => {
+                gst::error!(CAT, obj: &self.element, "Got error {}", err);
+                gst::element_error!(
+                    self.element,
+                    gst::StreamError::Failed,
+                    ("Internal data stream error"),
+                    ["streaming stopped, reason {}", err]
+                );
+            }
+        }
+
+        res
+    }
+}
+
+impl TaskImpl for TcpServerSrcTask {
+    type Item = TaskItem;
+
+    fn prepare(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Preparing task listening on {:?}", self.saddr);
+
+            let listener = Async::<TcpListener>::bind(self.saddr).map_err(|err| {
+                gst::error_msg!(
+                    gst::ResourceError::OpenRead,
+                    ["Failed to bind to {:?}: {:?}", self.saddr, err]
+                )
+            })?;
+
+            let current_port = listener
+                .get_ref()
+                .local_addr()
+                .map_err(|err| {
+                    gst::error_msg!(
+                        gst::ResourceError::OpenRead,
+                        ["Failed to retrieve bound address: {:?}", err]
+                    )
+                })?
+                .port();
+            self.element.imp().settings.lock().unwrap().current_port = current_port as i32;
+
+            self.buffer_pool.set_active(true).map_err(|err| {
+                gst::error_msg!(
+                    gst::ResourceError::OpenRead,
+                    ["Failed to prepare buffer pool {:?}", err]
+                )
+            })?;
+
+            self.listener = Some(listener);
+
+            gst::log!(CAT, obj: &self.element, "Task prepared");
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn unprepare(&mut self) -> BoxFuture<'_, ()> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Unpreparing task");
+
+            self.close_connections();
+            self.listener = None;
+            if let Err(err) = self.buffer_pool.set_active(false) {
+                gst::error!(CAT, obj: &self.element, "Failed to unprepare buffer pool: {}", err);
+            }
+            self.element.imp().settings.lock().unwrap().current_port = 0;
+
+            gst::log!(CAT, obj: &self.element, "Task unprepared");
+        }
+        .boxed()
+    }
+
+    fn handle_action_error(
+        &mut self,
+        trigger: task::Trigger,
+        state: TaskState,
+        err: gst::ErrorMessage,
+    ) -> BoxFuture<'_, task::Trigger> {
+        async move {
+            match trigger {
+                task::Trigger::Prepare => {
+                    gst::error!(CAT, "Task preparation failed: {:?}", err);
+                    self.element.post_error_message(err);
+
+                    task::Trigger::Error
+                }
+                other => unreachable!("Action error for {:?} in state {:?}", other, state),
+            }
+        }
+        .boxed()
+    }
+
+    fn try_next(&mut self) -> BoxFuture<'_, Result<TaskItem, gst::FlowError>> {
+        async move {
+            loop {
+                let event = {
+                    let accept = self.listener.as_ref().unwrap().accept().fuse();
+                    let buffer_pool = &self.buffer_pool;
+                    let connection = &mut self.connection;
+                    let read = async move {
+                        match connection.as_mut() {
+                            Some(connection) => {
+                                read_buffer(&mut connection.socket, buffer_pool).await
+                            }
+                            None => futures::future::pending().await,
+                        }
+                    }
+                    .fuse();
+                    futures::pin_mut!(accept, read);
+
+                    futures::select_biased! {
+                        res = accept => Event::Accepted(res),
+                        res = read => Event::Read(res),
+                    }
+                };
+
+                match event {
+                    Event::Accepted(Ok((socket, saddr))) => self.accept(socket, saddr),
+                    Event::Accepted(Err(err)) => {
+                        gst::error!(CAT, obj: &self.element, "Failed to accept client: {}", err);
+                        gst::element_error!(
+                            self.element,
+                            gst::StreamError::Failed,
+                            ("I/O error"),
+                            ["streaming stopped, failed to accept client: {}", err]
+                        );
+                        return Err(gst::FlowError::Error);
+                    }
+                    Event::Read(Ok(Some(mut buffer))) => {
+                        let connection = self.connection.as_mut().unwrap();
+                        {
+                            let buffer = buffer.make_mut();
+                            if connection.is_new {
+                                gst::debug!(
+                                    CAT,
+                                    obj: &self.element,
+                                    "First buffer from {:?}",
+                                    connection.saddr
+                                );
+                                buffer.set_flags(gst::BufferFlags::DISCONT);
+                                connection.is_new = false;
+                            }
+
+                            NetAddressMeta::add(
+                                buffer,
+                                &gio::InetSocketAddress::from(connection.saddr),
+                            );
+                        }
+
+                        return Ok(TaskItem::Buffer(buffer));
+                    }
+                    Event::Read(res) => {
+                        let saddr = self.connection.as_ref().unwrap().saddr;
+
+                        match res {
+                            Ok(_) => {
+                                gst::info!(CAT, obj: &self.element, "Client {:?} disconnected", saddr);
+                            }
+                            Err(SocketError::Gst(err)) => {
+                                gst::debug!(
+                                    CAT,
+                                    obj: &self.element,
+                                    "Failed to acquire buffer for {:?}: {}",
+                                    saddr,
+                                    err
+                                );
+                                return Err(err);
+                            }
+                            Err(SocketError::Io(err)) => {
+                                if self.client_policy == ClientPolicy::Single {
+                                    gst::element_error!(
+                                        self.element,
+                                        gst::StreamError::Failed,
+                                        ("I/O error"),
+                                        ["streaming stopped, I/O error {}", err]
+                                    );
+                                    return Err(gst::FlowError::Error);
+                                }
+
+                                gst::warning!(
+                                    CAT,
+                                    obj: &self.element,
+                                    "Dropping client {:?} after I/O error {}",
+                                    saddr,
+                                    err
+                                );
+                            }
+                        }
+
+                        if self.client_policy == ClientPolicy::Single {
+                            self.connection = None;
+                            return Ok(TaskItem::Eos);
+                        }
+
+                        self.next_connection();
+                    }
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn handle_item(&mut self, item: TaskItem) -> BoxFuture<'_, Result<(), gst::FlowError>> {
+        async move {
+            match item {
+                TaskItem::Buffer(buffer) => self.push_buffer(buffer).await.map(drop),
+                TaskItem::Eos => {
+                    gst::debug!(CAT, obj: &self.element, "Client gone, pushing EOS");
+
+                    self.push_initial_events().await;
+                    self.element
+                        .imp()
+                        .src_pad
+                        .push_event(gst::event::Eos::new())
+                        .await;
+
+                    Err(gst::FlowError::Eos)
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn stop(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Stopping task");
+            self.close_connections();
+            self.need_initial_events = true;
+            self.need_segment = true;
+            gst::log!(CAT, obj: &self.element, "Task stopped");
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn flush_stop(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Stopping task flush");
+            self.need_segment = true;
+            gst::log!(CAT, obj: &self.element, "Task flush stopped");
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+pub struct TcpServerSrc {
+    src_pad: PadSrc,
+    task: Task,
+    configured_caps: Mutex<Option<gst::Caps>>,
+    settings: Mutex<Settings>,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ts-tcpserversrc",
+        gst::DebugColorFlags::empty(),
+        Some("Thread-sharing TCP Server source"),
+    )
+});
+
+impl TcpServerSrc {
+    fn prepare(&self, element: &super::TcpServerSrc) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Preparing");
+        let settings = self.settings.lock().unwrap().clone();
+
+        let context =
+            Context::acquire(&settings.context, settings.context_wait).map_err(|err| {
+                gst::error_msg!(
+                    gst::ResourceError::OpenRead,
+                    ["Failed to acquire Context: {}", err]
+                )
+            })?;
+
+        *self.configured_caps.lock().unwrap() = None;
+
+        let host: IpAddr = match settings.host {
+            None => {
+                return Err(gst::error_msg!(
+                    gst::ResourceError::Settings,
+                    ["No host set"]
+                ));
+            }
+            Some(ref host) => match host.parse() {
+                Err(err) => {
+                    return Err(gst::error_msg!(
+                        gst::ResourceError::Settings,
+                        ["Invalid host '{}' set: {}", host, err]
+                    ));
+                }
+                Ok(host) => host,
+            },
+        };
+        let port = settings.port;
+
+        let buffer_pool = gst::BufferPool::new();
+        let mut config = buffer_pool.config();
+        config.set_params(None, settings.blocksize, 0, 0);
+        buffer_pool.set_config(config).map_err(|_| {
+            gst::error_msg!(
+                gst::ResourceError::Settings,
+                ["Failed to configure buffer pool"]
+            )
+        })?;
+
+        let saddr = SocketAddr::new(host, port as u16);
+
+        // Binding is immediate, so block on `prepare`
+        // for `current-port` to be available in Ready.
+        self.task
+            .prepare(
+                TcpServerSrcTask::new(element.clone(), saddr, settings.client_policy, buffer_pool),
+                context,
+            )
+            .block_on()?;
+
+        gst::debug!(CAT, obj: element, "Prepared");
+
+        Ok(())
+    }
+
+    fn unprepare(&self, element: &super::TcpServerSrc) {
+        gst::debug!(CAT, obj: element, "Unpreparing");
+        self.task.unprepare().block_on().unwrap();
+        gst::debug!(CAT, obj: element, "Unprepared");
+    }
+
+    fn stop(&self, element: &super::TcpServerSrc) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Stopping");
+        self.task.stop().block_on()?;
+        gst::debug!(CAT, obj: element, "Stopped");
+        Ok(())
+    }
+
+    fn start(&self, element: &super::TcpServerSrc) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Starting");
+        self.task.start().block_on()?;
+        gst::debug!(CAT, obj: element, "Started");
+        Ok(())
+    }
+
+    fn pause(&self, element: &super::TcpServerSrc) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Pausing");
+        self.task.pause().block_on()?;
+        gst::debug!(CAT, obj: element, "Paused");
+        Ok(())
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for TcpServerSrc {
+    const NAME: &'static str = "RsTsTcpServerSrc";
+    type Type = super::TcpServerSrc;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        Self {
+            src_pad: PadSrc::new(
+                gst::Pad::from_template(&klass.pad_template("src").unwrap(), Some("src")),
+                TcpServerSrcPadHandler,
+            ),
+            task: Task::default(),
+            configured_caps: Default::default(),
+            settings: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for TcpServerSrc {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::builder("context")
+                    .nick("Context")
+                    .blurb("Context name to share threads with")
+                    .default_value(Some(DEFAULT_CONTEXT))
+                    .build(),
+                glib::ParamSpecUInt::builder("context-wait")
+                    .nick("Context Wait")
+                    .blurb("Throttle poll loop to run at most once every this many ms")
+                    .maximum(1000)
+                    .default_value(DEFAULT_CONTEXT_WAIT.as_millis() as u32)
+                    .build(),
+                glib::ParamSpecString::builder("host")
+                    .nick("Host")
+                    .blurb("The host IP address to listen on")
+                    .default_value(DEFAULT_HOST)
+                    .build(),
+                glib::ParamSpecInt::builder("port")
+                    .nick("Port")
+                    .blurb("Port to listen on (0 = random available port)")
+                    .minimum(0)
+                    .maximum(u16::MAX as i32)
+                    .default_value(DEFAULT_PORT)
+                    .build(),
+                glib::ParamSpecInt::builder("current-port")
+                    .nick("Current Port")
+                    .blurb("The port number the socket is currently bound to")
+                    .minimum(0)
+                    .maximum(u16::MAX as i32)
+                    .read_only()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Caps>("caps")
+                    .nick("Caps")
+                    .blurb("Caps to use")
+                    .build(),
+                glib::ParamSpecUInt::builder("blocksize")
+                    .nick("Blocksize")
+                    .blurb("Size in bytes to read per buffer (-1 = default)")
+                    .default_value(DEFAULT_BLOCKSIZE)
+                    .build(),
+                glib::ParamSpecEnum::builder::<ClientPolicy>(
+                    "client-policy",
+                    DEFAULT_CLIENT_POLICY,
+                )
+                .nick("Client Policy")
+                .blurb("How to handle multiple client connections")
+                .mutable_ready()
+                .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "host" => {
+                settings.host = value.get().expect("type checked upstream");
+            }
+            "port" => {
+                settings.port = value.get().expect("type checked upstream");
+            }
+            "caps" => {
+                settings.caps = value.get().expect("type checked upstream");
+            }
+            "blocksize" => {
+                settings.blocksize = value.get().expect("type checked upstream");
+            }
+            "client-policy" => {
+                settings.client_policy = value.get().expect("type checked upstream");
+            }
+            "context" => {
+                settings.context = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_else(|| DEFAULT_CONTEXT.into());
+            }
+            "context-wait" => {
+                settings.context_wait = Duration::from_millis(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "host" => settings.host.to_value(),
+            "port" => settings.port.to_value(),
+            "current-port" => settings.current_port.to_value(),
+            "caps" => settings.caps.to_value(),
+            "blocksize" => settings.blocksize.to_value(),
+            "client-policy" => settings.client_policy.to_value(),
+            "context" => settings.context.to_value(),
+            "context-wait" => (settings.context_wait.as_millis() as u32).to_value(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(self.src_pad.gst_pad()).unwrap();
+
+        crate::set_element_flags(obj, gst::ElementFlags::SOURCE);
+    }
+}
+
+impl GstObjectImpl for TcpServerSrc {}
+
+impl ElementImpl for TcpServerSrc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Thread-sharing TCP server source",
+                "Source/Network",
+                "Receives data over the network via TCP from connecting clients",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::new_any();
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::NullToReady => {
+                self.prepare(element).map_err(|err| {
+                    element.post_error_message(err);
+                    gst::StateChangeError
+                })?;
+            }
+            gst::StateChange::PlayingToPaused => {
+                self.pause(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::ReadyToNull => {
+                self.unprepare(element);
+            }
+            _ => (),
+        }
+
+        let mut success = self.parent_change_state(element, transition)?;
+
+        match transition {
+            gst::StateChange::ReadyToPaused => {
+                success = gst::StateChangeSuccess::NoPreroll;
+            }
+            gst::StateChange::PausedToPlaying => {
+                self.start(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::PlayingToPaused => {
+                success = gst::StateChangeSuccess::NoPreroll;
+            }
+            gst::StateChange::PausedToReady => {
+                self.stop(element).map_err(|_| gst::StateChangeError)?;
+            }
+            _ => (),
+        }
+
+        Ok(success)
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstTsTcpServerSrcClientPolicy")]
+pub enum ClientPolicy {
+    #[enum_value(
+        name = "Single: Serve one client at a time, EOS when it disconnects",
+        nick = "single"
+    )]
+    Single,
+    #[enum_value(
+        name = "Per connection: Serve all clients one after the other, each connection starts a discontinuity",
+        nick = "per-connection"
+    )]
+    PerConnection,
+}
+
+glib::wrapper! {
+    pub struct TcpServerSrc(ObjectSubclass<imp::TcpServerSrc>) @extends gst::Element, gst::Object;
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    #[cfg(feature = "doc")]
+    ClientPolicy::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
+
+    gst::Element::register(
+        Some(plugin),
+        "ts-tcpserversrc",
+        gst::Rank::None,
+        TcpServerSrc::static_type(),
+    )
+}
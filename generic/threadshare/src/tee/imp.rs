@@ -0,0 +1,910 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+//! Thread-sharing tee.
+//!
+//! Each src pad is fed by its own leaky [`DataQueue`] which is drained by a
+//! [`Task`] running on the element's [`Context`]. When a branch can't keep
+//! up, buffers are dropped for this branch only, so that a slow consumer
+//! never blocks upstream nor the other branches. Serialized events are never
+//! dropped.
+
+use futures::future::BoxFuture;
+use futures::prelude::*;
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::u64;
+
+use crate::runtime::prelude::*;
+use crate::runtime::{Context, PadSink, PadSinkRef, PadSrc, PadSrcRef, PadSrcWeak, Task};
+
+use crate::dataqueue::{DataQueue, DataQueueItem, DataQueueLeaky};
+
+const DEFAULT_MAX_SIZE_BUFFERS: u32 = 200;
+const DEFAULT_MAX_SIZE_BYTES: u32 = 1024 * 1024;
+const DEFAULT_MAX_SIZE_TIME: gst::ClockTime = gst::ClockTime::SECOND;
+const DEFAULT_CONTEXT: &str = "";
+const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
+const DEFAULT_LEAKY: DataQueueLeaky = DataQueueLeaky::Upstream;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    max_size_buffers: u32,
+    max_size_bytes: u32,
+    max_size_time: gst::ClockTime,
+    context: String,
+    context_wait: Duration,
+    leaky: DataQueueLeaky,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            max_size_buffers: DEFAULT_MAX_SIZE_BUFFERS,
+            max_size_bytes: DEFAULT_MAX_SIZE_BYTES,
+            max_size_time: DEFAULT_MAX_SIZE_TIME,
+            context: DEFAULT_CONTEXT.into(),
+            context_wait: DEFAULT_CONTEXT_WAIT,
+            leaky: DEFAULT_LEAKY,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BranchState {
+    last_res: Result<gst::FlowSuccess, gst::FlowError>,
+    pushed: u64,
+}
+
+impl Default for BranchState {
+    fn default() -> Self {
+        BranchState {
+            last_res: Ok(gst::FlowSuccess::Ok),
+            pushed: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Branch {
+    src_pad: PadSrc,
+    task: Task,
+    dataqueue: DataQueue,
+    state: Arc<Mutex<BranchState>>,
+}
+
+impl Branch {
+    fn task_impl(&self, element: &super::Tee) -> TeeBranchTask {
+        TeeBranchTask {
+            element: element.clone(),
+            src_pad: self.src_pad.downgrade(),
+            dataqueue: self.dataqueue.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    fn stats(&self) -> gst::Structure {
+        gst::Structure::builder("application/x-ts-tee-branch-stats")
+            .field("pad", self.src_pad.gst_pad().name())
+            .field("pushed", self.state.lock().unwrap().pushed)
+            .field("dropped", self.dataqueue.dropped())
+            .build()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Branches {
+    pad_serial: u32,
+    branches: HashMap<gst::Pad, Branch>,
+}
+
+impl Branches {
+    fn tasks(&self) -> Vec<Task> {
+        self.branches
+            .values()
+            .map(|branch| branch.task.clone())
+            .collect()
+    }
+}
+
+/* Also serializes the state changes with the addition of branches, so that new
+ * branches are prepared & started consistently with the element. The `branches`
+ * are never locked while driving the tasks, since the streaming thread needs them. */
+#[derive(Debug, Default)]
+struct State {
+    context: Option<Context>,
+    started: bool,
+}
+
+#[derive(Clone, Debug)]
+struct TeePadSinkHandler;
+
+impl PadSinkHandler for TeePadSinkHandler {
+    type ElementImpl = Tee;
+
+    fn sink_chain(
+        &self,
+        pad: &PadSinkRef,
+        _tee: &Tee,
+        element: &gst::Element,
+        buffer: gst::Buffer,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let pad_weak = pad.downgrade();
+        let element = element.clone().downcast::<super::Tee>().unwrap();
+        async move {
+            let pad = pad_weak.upgrade().expect("PadSink no longer exists");
+            gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", buffer);
+            let tee = element.imp();
+            tee.enqueue_item(&element, DataQueueItem::Buffer(buffer))
+        }
+        .boxed()
+    }
+
+    fn sink_chain_list(
+        &self,
+        pad: &PadSinkRef,
+        _tee: &Tee,
+        element: &gst::Element,
+        list: gst::BufferList,
+    ) -> BoxFuture<'static, Result<gst::FlowSuccess, gst::FlowError>> {
+        let pad_weak = pad.downgrade();
+        let element = element.clone().downcast::<super::Tee>().unwrap();
+        async move {
+            let pad = pad_weak.upgrade().expect("PadSink no longer exists");
+            gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", list);
+            let tee = element.imp();
+            tee.enqueue_item(&element, DataQueueItem::BufferList(list))
+        }
+        .boxed()
+    }
+
+    fn sink_event(
+        &self,
+        pad: &PadSinkRef,
+        tee: &Tee,
+        element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        use gst::EventView;
+
+        gst::debug!(CAT, obj: pad.gst_pad(), "Handling non-serialized {:?}", event);
+
+        let (src_pads, tasks) = {
+            let branches = tee.branches.lock().unwrap();
+            (
+                branches.branches.keys().cloned().collect::<Vec<_>>(),
+                branches.tasks(),
+            )
+        };
+
+        if let EventView::FlushStart(..) = event.view() {
+            for task in tasks {
+                if let Err(err) = task.flush_start().await_maybe_on_context() {
+                    gst::error!(CAT, obj: pad.gst_pad(), "FlushStart failed {:?}", err);
+                    gst::element_error!(
+                        element,
+                        gst::StreamError::Failed,
+                        ("Internal data stream error"),
+                        ["FlushStart failed {:?}", err]
+                    );
+                    return false;
+                }
+            }
+        }
+
+        if src_pads.is_empty() {
+            return true;
+        }
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Forwarding non-serialized {:?}", event);
+        src_pads.iter().fold(false, |res, src_pad| {
+            src_pad.push_event(event.clone()) || res
+        })
+    }
+
+    fn sink_event_serialized(
+        &self,
+        pad: &PadSinkRef,
+        _tee: &Tee,
+        element: &gst::Element,
+        event: gst::Event,
+    ) -> BoxFuture<'static, bool> {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling serialized {:?}", event);
+
+        let pad_weak = pad.downgrade();
+        let element = element.clone().downcast::<super::Tee>().unwrap();
+        async move {
+            let pad = pad_weak.upgrade().expect("PadSink no longer exists");
+            let tee = element.imp();
+
+            if let EventView::FlushStop(..) = event.view() {
+                let tasks = tee.branches.lock().unwrap().tasks();
+                for task in tasks {
+                    if let Err(err) = task.flush_stop().await_maybe_on_context() {
+                        gst::error!(CAT, obj: pad.gst_pad(), "FlushStop failed {:?}", err);
+                        gst::element_error!(
+                            element,
+                            gst::StreamError::Failed,
+                            ("Internal data stream error"),
+                            ["FlushStop failed {:?}", err]
+                        );
+                        return false;
+                    }
+                }
+            }
+
+            gst::log!(CAT, obj: pad.gst_pad(), "Queuing serialized {:?}", event);
+            let _ = tee.enqueue_item(&element, DataQueueItem::Event(event));
+
+            true
+        }
+        .boxed()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TeePadSrcHandler;
+
+impl PadSrcHandler for TeePadSrcHandler {
+    type ElementImpl = Tee;
+
+    fn src_event(
+        &self,
+        pad: &PadSrcRef,
+        tee: &Tee,
+        _element: &gst::Element,
+        event: gst::Event,
+    ) -> bool {
+        gst::log!(CAT, obj: pad.gst_pad(), "Forwarding {:?}", event);
+        // Flushing events come back through the sink pad and flush all the branches
+        tee.sink_pad.gst_pad().push_event(event)
+    }
+
+    fn src_query(
+        &self,
+        pad: &PadSrcRef,
+        tee: &Tee,
+        _element: &gst::Element,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        use gst::QueryViewMut;
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Handling {:?}", query);
+
+        if let QueryViewMut::Scheduling(q) = query.view_mut() {
+            q.set(gst::SchedulingFlags::SEQUENTIAL, 1, -1, 0);
+            q.add_scheduling_modes(&[gst::PadMode::Push]);
+            gst::log!(CAT, obj: pad.gst_pad(), "Returning {:?}", q.query_mut());
+            return true;
+        }
+
+        gst::log!(CAT, obj: pad.gst_pad(), "Forwarding {:?}", query);
+        tee.sink_pad.gst_pad().peer_query(query)
+    }
+}
+
+#[derive(Debug)]
+struct TeeBranchTask {
+    element: super::Tee,
+    src_pad: PadSrcWeak,
+    dataqueue: DataQueue,
+    state: Arc<Mutex<BranchState>>,
+}
+
+impl TeeBranchTask {
+    async fn push_item(&self, item: DataQueueItem) -> Result<u32, gst::FlowError> {
+        let src_pad = self.src_pad.upgrade().ok_or(gst::FlowError::Flushing)?;
+
+        match item {
+            DataQueueItem::Buffer(buffer) => {
+                gst::log!(CAT, obj: src_pad.gst_pad(), "Forwarding {:?}", buffer);
+                src_pad.push(buffer).await.map(|_| 1)
+            }
+            DataQueueItem::BufferList(list) => {
+                gst::log!(CAT, obj: src_pad.gst_pad(), "Forwarding {:?}", list);
+                let count = list.len() as u32;
+                src_pad.push_list(list).await.map(|_| count)
+            }
+            DataQueueItem::Event(event) => {
+                gst::log!(CAT, obj: src_pad.gst_pad(), "Forwarding {:?}", event);
+                src_pad.push_event(event).await;
+                Ok(0)
+            }
+        }
+    }
+}
+
+impl TaskImpl for TeeBranchTask {
+    type Item = DataQueueItem;
+
+    fn start(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Starting branch task");
+
+            self.dataqueue.start();
+            self.state.lock().unwrap().last_res = Ok(gst::FlowSuccess::Ok);
+
+            gst::log!(CAT, obj: &self.element, "Branch task started");
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn try_next(&mut self) -> BoxFuture<'_, Result<DataQueueItem, gst::FlowError>> {
+        async move {
+            self.dataqueue
+                .next()
+                .await
+                .ok_or_else(|| panic!("DataQueue stopped while Task is Started"))
+        }
+        .boxed()
+    }
+
+    fn handle_item(&mut self, item: DataQueueItem) -> BoxFuture<'_, Result<(), gst::FlowError>> {
+        async move {
+            let res = self.push_item(item).await;
+            let mut state = self.state.lock().unwrap();
+            match res {
+                Ok(count) => {
+                    gst::log!(CAT, obj: &self.element, "Successfully pushed item");
+                    state.pushed += count as u64;
+                    state.last_res = Ok(gst::FlowSuccess::Ok);
+                    Ok(())
+                }
+                Err(gst::FlowError::NotLinked) => {
+                    // Keep on draining the queue, the branch might be linked later
+                    gst::log!(CAT, obj: &self.element, "Branch not linked");
+                    state.last_res = Err(gst::FlowError::NotLinked);
+                    Ok(())
+                }
+                Err(gst::FlowError::Flushing) => {
+                    gst::debug!(CAT, obj: &self.element, "Flushing");
+                    state.last_res = Err(gst::FlowError::Flushing);
+                    Err(gst::FlowError::Flushing)
+                }
+                Err(gst::FlowError::Eos) => {
+                    gst::debug!(CAT, obj: &self.element, "EOS");
+                    state.last_res = Err(gst::FlowError::Eos);
+                    Err(gst::FlowError::Eos)
+                }
+                Err(err) => {
+                    gst::error!(CAT, obj: &self.element, "Got error {}", err);
+                    gst::element_error!(
+                        &self.element,
+                        gst::StreamError::Failed,
+                        ("Internal data stream error"),
+                        ["streaming stopped, reason {}", err]
+                    );
+                    state.last_res = Err(err);
+                    Err(err)
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn stop(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Stopping branch task");
+
+            self.dataqueue.stop();
+            self.dataqueue.clear();
+            self.state.lock().unwrap().last_res = Err(gst::FlowError::Flushing);
+
+            gst::log!(CAT, obj: &self.element, "Branch task stopped");
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn flush_start(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Starting branch task flush");
+
+            self.dataqueue.clear();
+            self.state.lock().unwrap().last_res = Err(gst::FlowError::Flushing);
+
+            gst::log!(CAT, obj: &self.element, "Branch task flush started");
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn flush_stop(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
+        async move {
+            gst::log!(CAT, obj: &self.element, "Stopping branch task flush");
+
+            self.state.lock().unwrap().last_res = Ok(gst::FlowSuccess::Ok);
+
+            gst::log!(CAT, obj: &self.element, "Branch task flush stopped");
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[derive(Debug)]
+pub struct Tee {
+    sink_pad: PadSink,
+    branches: Mutex<Branches>,
+    state: Mutex<State>,
+    settings: Mutex<Settings>,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ts-tee",
+        gst::DebugColorFlags::empty(),
+        Some("Thread-sharing tee"),
+    )
+});
+
+impl Tee {
+    /* Dispatches the item to every branch. Buffers are leaked for the branches
+     * which are full, serialized events are always queued. */
+    fn enqueue_item(
+        &self,
+        element: &super::Tee,
+        item: DataQueueItem,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let branches = self.branches.lock().unwrap();
+
+        if branches.branches.is_empty() {
+            gst::log!(CAT, obj: element, "No branches, dropping {:?}", item);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let mut res = Err(gst::FlowError::NotLinked);
+        for branch in branches.branches.values() {
+            // Only fails if the branch is stopped
+            let _ = branch.dataqueue.push(item.clone());
+
+            res = match (res, branch.state.lock().unwrap().last_res) {
+                (Ok(_), _) | (_, Ok(_)) => Ok(gst::FlowSuccess::Ok),
+                (Err(gst::FlowError::NotLinked), branch_res) => branch_res,
+                (res, _) => res,
+            };
+        }
+
+        res
+    }
+
+    /* The pad of the new branch is not added to the element yet. */
+    fn new_branch(&self, element: &super::Tee, templ: &gst::PadTemplate, name: &str) -> Branch {
+        let settings = self.settings.lock().unwrap().clone();
+
+        let gst_pad = gst::Pad::from_template(templ, Some(name));
+        gst_pad.set_active(true).unwrap();
+
+        // Make sure the new branch starts with the current stream-start, caps & segment
+        self.sink_pad.gst_pad().sticky_events_foreach(|event| {
+            if event.type_() != gst::EventType::Eos {
+                let _ = gst_pad.store_sticky_event(event);
+            }
+            ControlFlow::Continue(gst::EventForeachAction::Keep)
+        });
+
+        let src_pad = PadSrc::new(gst_pad, TeePadSrcHandler);
+
+        let dataqueue = DataQueue::new(
+            &element.clone().upcast(),
+            src_pad.gst_pad(),
+            if settings.max_size_buffers == 0 {
+                None
+            } else {
+                Some(settings.max_size_buffers)
+            },
+            if settings.max_size_bytes == 0 {
+                None
+            } else {
+                Some(settings.max_size_bytes)
+            },
+            if settings.max_size_time.is_zero() {
+                None
+            } else {
+                Some(settings.max_size_time)
+            },
+        );
+        // Branches must not block upstream
+        dataqueue.set_leaky(if settings.leaky == DataQueueLeaky::No {
+            DataQueueLeaky::Upstream
+        } else {
+            settings.leaky
+        });
+
+        Branch {
+            src_pad,
+            task: Task::default(),
+            dataqueue,
+            state: Arc::new(Mutex::new(BranchState::default())),
+        }
+    }
+
+    fn stats(&self) -> gst::Structure {
+        let branches = self.branches.lock().unwrap();
+
+        let mut branch_stats = branches
+            .branches
+            .values()
+            .map(|branch| (branch.src_pad.gst_pad().name(), branch.stats()))
+            .collect::<Vec<_>>();
+        branch_stats.sort_by(|a, b| a.0.cmp(&b.0));
+
+        gst::Structure::builder("application/x-ts-tee-stats")
+            .field(
+                "branch-stats",
+                gst::Array::from(
+                    branch_stats
+                        .into_iter()
+                        .map(|(_, s)| s.to_send_value())
+                        .collect::<Vec<_>>(),
+                ),
+            )
+            .build()
+    }
+
+    fn prepare(&self, element: &super::Tee) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Preparing");
+
+        let settings = self.settings.lock().unwrap().clone();
+
+        let context =
+            Context::acquire(&settings.context, settings.context_wait).map_err(|err| {
+                gst::error_msg!(
+                    gst::ResourceError::OpenRead,
+                    ["Failed to acquire Context: {}", err]
+                )
+            })?;
+
+        let mut state = self.state.lock().unwrap();
+
+        let tasks = self
+            .branches
+            .lock()
+            .unwrap()
+            .branches
+            .values()
+            .map(|branch| (branch.task.clone(), branch.task_impl(element)))
+            .collect::<Vec<_>>();
+        for (task, task_impl) in tasks {
+            task.prepare(task_impl, context.clone()).block_on()?;
+        }
+
+        state.context = Some(context);
+
+        gst::debug!(CAT, obj: element, "Prepared");
+
+        Ok(())
+    }
+
+    fn unprepare(&self, element: &super::Tee) {
+        gst::debug!(CAT, obj: element, "Unpreparing");
+
+        let mut state = self.state.lock().unwrap();
+
+        let tasks = self.branches.lock().unwrap().tasks();
+        for task in tasks {
+            task.unprepare().block_on().unwrap();
+        }
+
+        state.context = None;
+
+        gst::debug!(CAT, obj: element, "Unprepared");
+    }
+
+    fn stop(&self, element: &super::Tee) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Stopping");
+
+        let mut state = self.state.lock().unwrap();
+        state.started = false;
+
+        let tasks = self.branches.lock().unwrap().tasks();
+        for task in tasks {
+            task.stop().await_maybe_on_context()?;
+        }
+
+        gst::debug!(CAT, obj: element, "Stopped");
+        Ok(())
+    }
+
+    fn start(&self, element: &super::Tee) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, obj: element, "Starting");
+
+        let mut state = self.state.lock().unwrap();
+        state.started = true;
+
+        let tasks = self.branches.lock().unwrap().tasks();
+        for task in tasks {
+            task.start().await_maybe_on_context()?;
+        }
+
+        gst::debug!(CAT, obj: element, "Started");
+        Ok(())
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Tee {
+    const NAME: &'static str = "RsTsTee";
+    type Type = super::Tee;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        Self {
+            sink_pad: PadSink::new(
+                gst::Pad::from_template(&klass.pad_template("sink").unwrap(), Some("sink")),
+                TeePadSinkHandler,
+            ),
+            branches: Mutex::new(Branches::default()),
+            state: Mutex::new(State::default()),
+            settings: Mutex::new(Settings::default()),
+        }
+    }
+}
+
+impl ObjectImpl for Tee {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::builder("context")
+                    .nick("Context")
+                    .blurb("Context name to share threads with")
+                    .default_value(Some(DEFAULT_CONTEXT))
+                    .build(),
+                glib::ParamSpecUInt::builder("context-wait")
+                    .nick("Context Wait")
+                    .blurb("Throttle poll loop to run at most once every this many ms")
+                    .maximum(1000)
+                    .default_value(DEFAULT_CONTEXT_WAIT.as_millis() as u32)
+                    .build(),
+                glib::ParamSpecUInt::builder("max-size-buffers")
+                    .nick("Max Size Buffers")
+                    .blurb("Maximum number of buffers to queue per branch before dropping (0=unlimited)")
+                    .default_value(DEFAULT_MAX_SIZE_BUFFERS)
+                    .build(),
+                glib::ParamSpecUInt::builder("max-size-bytes")
+                    .nick("Max Size Bytes")
+                    .blurb("Maximum number of bytes to queue per branch before dropping (0=unlimited)")
+                    .default_value(DEFAULT_MAX_SIZE_BYTES)
+                    .build(),
+                glib::ParamSpecUInt64::builder("max-size-time")
+                    .nick("Max Size Time")
+                    .blurb("Maximum number of nanoseconds to queue per branch before dropping (0=unlimited)")
+                    .maximum(u64::MAX - 1)
+                    .default_value(DEFAULT_MAX_SIZE_TIME.nseconds())
+                    .build(),
+                glib::ParamSpecEnum::builder::<DataQueueLeaky>("leaky", DEFAULT_LEAKY)
+                    .nick("Leaky")
+                    .blurb("Where the branch queues leak (no is the same as upstream)")
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Per-branch statistics")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "max-size-buffers" => {
+                settings.max_size_buffers = value.get().expect("type checked upstream");
+            }
+            "max-size-bytes" => {
+                settings.max_size_bytes = value.get().expect("type checked upstream");
+            }
+            "max-size-time" => {
+                settings.max_size_time =
+                    gst::ClockTime::from_nseconds(value.get().expect("type checked upstream"));
+            }
+            "context" => {
+                settings.context = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_else(|| DEFAULT_CONTEXT.into());
+            }
+            "context-wait" => {
+                settings.context_wait = Duration::from_millis(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
+            "leaky" => {
+                settings.leaky = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        if pspec.name() == "stats" {
+            return self.stats().to_value();
+        }
+
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "max-size-buffers" => settings.max_size_buffers.to_value(),
+            "max-size-bytes" => settings.max_size_bytes.to_value(),
+            "max-size-time" => settings.max_size_time.nseconds().to_value(),
+            "context" => settings.context.to_value(),
+            "context-wait" => (settings.context_wait.as_millis() as u32).to_value(),
+            "leaky" => settings.leaky.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(self.sink_pad.gst_pad()).unwrap();
+    }
+}
+
+impl GstObjectImpl for Tee {}
+
+impl ElementImpl for Tee {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Thread-sharing tee",
+                "Generic",
+                "1-to-N pipe fitting with a leaky queue per branch",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::new_any();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src_%u",
+                gst::PadDirection::Src,
+                gst::PadPresence::Request,
+                &caps,
+            )
+            .unwrap();
+
+            vec![sink_pad_template, src_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::NullToReady => {
+                self.prepare(element).map_err(|err| {
+                    element.post_error_message(err);
+                    gst::StateChangeError
+                })?;
+            }
+            gst::StateChange::PausedToReady => {
+                self.stop(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::ReadyToNull => {
+                self.unprepare(element);
+            }
+            _ => (),
+        }
+
+        let success = self.parent_change_state(element, transition)?;
+
+        if transition == gst::StateChange::ReadyToPaused {
+            self.start(element).map_err(|_| gst::StateChangeError)?;
+        }
+
+        Ok(success)
+    }
+
+    fn request_new_pad(
+        &self,
+        element: &Self::Type,
+        templ: &gst::PadTemplate,
+        _name: Option<String>,
+        _caps: Option<&gst::Caps>,
+    ) -> Option<gst::Pad> {
+        let name = {
+            let mut branches = self.branches.lock().unwrap();
+            let name = format!("src_{}", branches.pad_serial);
+            branches.pad_serial += 1;
+            name
+        };
+
+        let branch = self.new_branch(element, templ, &name);
+
+        let state = self.state.lock().unwrap();
+        if let Some(ref context) = state.context {
+            if let Err(err) = branch
+                .task
+                .prepare(branch.task_impl(element), context.clone())
+                .block_on()
+            {
+                gst::error!(CAT, obj: element, "Failed to prepare branch {}: {:?}", name, err);
+                element.post_error_message(err);
+            } else if state.started {
+                if let Err(err) = branch.task.start().await_maybe_on_context() {
+                    gst::error!(CAT, obj: element, "Failed to start branch {}: {:?}", name, err);
+                }
+            }
+        }
+
+        // pad-added is emitted synchronously, don't hold the branches meanwhile
+        let ret = branch.src_pad.gst_pad().clone();
+        element.add_pad(&ret).unwrap();
+
+        self.branches
+            .lock()
+            .unwrap()
+            .branches
+            .insert(ret.clone(), branch);
+        drop(state);
+
+        Some(ret)
+    }
+
+    fn release_pad(&self, element: &Self::Type, pad: &gst::Pad) {
+        let branch = self.branches.lock().unwrap().branches.remove(pad);
+
+        if let Some(branch) = branch {
+            gst::debug!(CAT, obj: element, "Releasing {}", pad.name());
+            let _ = branch.task.unprepare().block_on();
+            drop(branch);
+            let _ = pad.set_active(false);
+            element.remove_pad(pad).unwrap();
+        }
+    }
+}
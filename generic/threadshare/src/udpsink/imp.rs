@@ -29,12 +29,15 @@ use gst::{element_error, error_msg};
 
 use once_cell::sync::Lazy;
 
+#[cfg(target_os = "linux")]
+use crate::mmsg;
+use crate::multicast;
 use crate::runtime::prelude::*;
 use crate::runtime::{self, Async, Context, PadSink, PadSinkRef, Task};
 use crate::socket::{wrap_socket, GioSocketWrapper};
 
 use std::collections::BTreeSet;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
 use std::pin::Pin;
 use std::sync::Mutex;
 use std::task::Poll;
@@ -57,7 +60,11 @@ const DEFAULT_AUTO_MULTICAST: bool = true;
 const DEFAULT_LOOP: bool = true;
 const DEFAULT_TTL: u32 = 64;
 const DEFAULT_TTL_MC: u32 = 1;
+const DEFAULT_MULTICAST_IFACE: Option<&str> = None;
+const DEFAULT_MULTICAST_SOURCE: Option<&str> = None;
 const DEFAULT_QOS_DSCP: i32 = -1;
+const DEFAULT_BATCH_SIZE: u32 = 1;
+const DEFAULT_GSO: bool = false;
 const DEFAULT_CLIENTS: &str = "";
 const DEFAULT_CONTEXT: &str = "";
 const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
@@ -77,7 +84,11 @@ struct Settings {
     multicast_loop: bool,
     ttl: u32,
     ttl_mc: u32,
+    multicast_iface: Option<String>,
+    multicast_source: Option<String>,
     qos_dscp: i32,
+    batch_size: u32,
+    gso: bool,
     context: String,
     context_wait: Duration,
     clients: BTreeSet<SocketAddr>,
@@ -100,7 +111,11 @@ impl Default for Settings {
             multicast_loop: DEFAULT_LOOP,
             ttl: DEFAULT_TTL,
             ttl_mc: DEFAULT_TTL_MC,
+            multicast_iface: DEFAULT_MULTICAST_IFACE.map(Into::into),
+            multicast_source: DEFAULT_MULTICAST_SOURCE.map(Into::into),
             qos_dscp: DEFAULT_QOS_DSCP,
+            batch_size: DEFAULT_BATCH_SIZE,
+            gso: DEFAULT_GSO,
             context: DEFAULT_CONTEXT.into(),
             context_wait: DEFAULT_CONTEXT_WAIT,
             clients: BTreeSet::from([SocketAddr::new(
@@ -112,6 +127,22 @@ impl Default for Settings {
     }
 }
 
+impl Settings {
+    fn multicast_config(
+        &self,
+        group: &IpAddr,
+    ) -> Result<(Vec<u32>, multicast::SourceFilter), gst::ErrorMessage> {
+        let ifaces = multicast::parse_interfaces(self.multicast_iface.as_deref())
+            .map_err(|err| error_msg!(gst::ResourceError::Settings, ["{}", err]))?;
+        let filter =
+            multicast::SourceFilter::parse(self.multicast_source.as_deref().unwrap_or_default())
+                .and_then(|filter| filter.check_family(group).map(|_| filter))
+                .map_err(|err| error_msg!(gst::ResourceError::Settings, ["{}", err]))?;
+
+        Ok((ifaces, filter))
+    }
+}
+
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
         "ts-udpsink",
@@ -123,9 +154,21 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
 #[derive(Debug)]
 enum TaskItem {
     Buffer(gst::Buffer),
+    BufferList(gst::BufferList),
     Event(gst::Event),
 }
 
+/// Running time at which `buffer` is due, including the latency.
+fn running_time(
+    segment: Option<&gst::Segment>,
+    latency: Option<gst::ClockTime>,
+    buffer: &gst::BufferRef,
+) -> Option<gst::ClockTime> {
+    segment
+        .and_then(|segment| segment.downcast_ref::<gst::format::Time>())
+        .and_then(|segment| segment.to_running_time(buffer.pts()).opt_add(latency))
+}
+
 #[derive(Clone, Debug)]
 struct UdpSinkPadHandler;
 
@@ -164,11 +207,9 @@ impl PadSinkHandler for UdpSinkPadHandler {
         let element = element.clone().downcast::<super::UdpSink>().unwrap();
 
         async move {
-            for buffer in list.iter_owned() {
-                if sender.send_async(TaskItem::Buffer(buffer)).await.is_err() {
-                    gst::debug!(CAT, obj: &element, "Flushing");
-                    return Err(gst::FlowError::Flushing);
-                }
+            if sender.send_async(TaskItem::BufferList(list)).await.is_err() {
+                gst::debug!(CAT, obj: &element, "Flushing");
+                return Err(gst::FlowError::Flushing);
             }
 
             Ok(gst::FlowSuccess::Ok)
@@ -231,6 +272,8 @@ struct UdpSinkTask {
     socket: Option<Async<UdpSocket>>,
     socket_v6: Option<Async<UdpSocket>>,
     sync: bool,
+    batch_size: usize,
+    gso: bool,
     latency: Option<gst::ClockTime>,
     segment: Option<gst::Segment>,
 }
@@ -249,6 +292,8 @@ impl UdpSinkTask {
             socket: None,
             socket_v6: None,
             sync: false,
+            batch_size: DEFAULT_BATCH_SIZE as usize,
+            gso: DEFAULT_GSO,
             latency: None,
             segment: None,
         }
@@ -482,90 +527,74 @@ impl UdpSinkTask {
         settings: &Settings,
         client: &SocketAddr,
     ) -> Result<(), gst::ErrorMessage> {
-        if client.ip().is_multicast() {
-            match client.ip() {
-                IpAddr::V4(addr) => {
-                    if let Some(socket) = self.socket.as_ref() {
-                        if settings.auto_multicast {
-                            socket
-                                .as_ref()
-                                .join_multicast_v4(&addr, &Ipv4Addr::new(0, 0, 0, 0))
-                                .map_err(|err| {
-                                    error_msg!(
-                                        gst::ResourceError::OpenWrite,
-                                        [
-                                            "Failed to join multicast group for {:?}: {}",
-                                            client,
-                                            err
-                                        ]
-                                    )
-                                })?;
-                        }
-                        if settings.multicast_loop {
-                            socket.as_ref().set_multicast_loop_v4(true).map_err(|err| {
-                                error_msg!(
-                                    gst::ResourceError::OpenWrite,
-                                    ["Failed to set multicast loop for {:?}: {}", client, err]
-                                )
-                            })?;
-                        }
+        let socket = match client.ip() {
+            IpAddr::V4(_) => self.socket.as_ref(),
+            IpAddr::V6(_) => self.socket_v6.as_ref(),
+        };
+        let socket = match socket {
+            Some(socket) => socket.as_ref(),
+            None => return Ok(()),
+        };
 
-                        socket
-                            .as_ref()
-                            .set_multicast_ttl_v4(settings.ttl_mc)
-                            .map_err(|err| {
-                                error_msg!(
-                                    gst::ResourceError::OpenWrite,
-                                    ["Failed to set multicast ttl for {:?}: {}", client, err]
-                                )
-                            })?;
-                    }
-                }
-                IpAddr::V6(addr) => {
-                    if let Some(socket) = self.socket_v6.as_ref() {
-                        if settings.auto_multicast {
-                            socket.as_ref().join_multicast_v6(&addr, 0).map_err(|err| {
-                                error_msg!(
-                                    gst::ResourceError::OpenWrite,
-                                    ["Failed to join multicast group for {:?}: {}", client, err]
-                                )
-                            })?;
-                        }
-                        if settings.multicast_loop {
-                            socket.as_ref().set_multicast_loop_v6(true).map_err(|err| {
-                                error_msg!(
-                                    gst::ResourceError::OpenWrite,
-                                    ["Failed to set multicast loop for {:?}: {}", client, err]
-                                )
-                            })?;
-                        }
-                        /* FIXME no API for set_multicast_ttl_v6 ? */
-                    }
+        if client.ip().is_multicast() {
+            let group = client.ip();
+            let (ifaces, filter) = settings.multicast_config(&group)?;
+
+            if settings.auto_multicast {
+                for iface in ifaces.iter() {
+                    multicast::join(socket, &group, *iface, &filter).map_err(|err| {
+                        error_msg!(
+                            gst::ResourceError::OpenWrite,
+                            ["Failed to join multicast group for {:?}: {}", client, err]
+                        )
+                    })?;
                 }
             }
-        } else {
-            match client.ip() {
-                IpAddr::V4(_) => {
-                    if let Some(socket) = self.socket.as_ref() {
-                        socket.as_ref().set_ttl(settings.ttl).map_err(|err| {
-                            error_msg!(
-                                gst::ResourceError::OpenWrite,
-                                ["Failed to set unicast ttl for {:?}: {}", client, err]
-                            )
-                        })?;
-                    }
-                }
+
+            // Outgoing multicast packets go through the first configured interface
+            if ifaces[0] != 0 {
+                multicast::set_interface(socket, group.is_ipv4(), ifaces[0]).map_err(|err| {
+                    error_msg!(
+                        gst::ResourceError::OpenWrite,
+                        [
+                            "Failed to set multicast interface for {:?}: {}",
+                            client,
+                            err
+                        ]
+                    )
+                })?;
+            }
+
+            match group {
+                IpAddr::V4(_) => socket.set_multicast_loop_v4(settings.multicast_loop),
+                IpAddr::V6(_) => socket.set_multicast_loop_v6(settings.multicast_loop),
+            }
+            .map_err(|err| {
+                error_msg!(
+                    gst::ResourceError::OpenWrite,
+                    ["Failed to set multicast loop for {:?}: {}", client, err]
+                )
+            })?;
+
+            match group {
+                IpAddr::V4(_) => socket.set_multicast_ttl_v4(settings.ttl_mc),
                 IpAddr::V6(_) => {
-                    if let Some(socket) = self.socket_v6.as_ref() {
-                        socket.as_ref().set_ttl(settings.ttl).map_err(|err| {
-                            error_msg!(
-                                gst::ResourceError::OpenWrite,
-                                ["Failed to set unicast ttl for {:?}: {}", client, err]
-                            )
-                        })?;
-                    }
+                    socket2::SockRef::from(socket).set_multicast_hops_v6(settings.ttl_mc)
                 }
             }
+            .map_err(|err| {
+                error_msg!(
+                    gst::ResourceError::OpenWrite,
+                    ["Failed to set multicast ttl for {:?}: {}", client, err]
+                )
+            })?;
+        } else {
+            socket.set_ttl(settings.ttl).map_err(|err| {
+                error_msg!(
+                    gst::ResourceError::OpenWrite,
+                    ["Failed to set unicast ttl for {:?}: {}", client, err]
+                )
+            })?;
         }
 
         Ok(())
@@ -576,47 +605,28 @@ impl UdpSinkTask {
         settings: &Settings,
         client: &SocketAddr,
     ) -> Result<(), gst::ErrorMessage> {
-        if client.ip().is_multicast() {
-            match client.ip() {
-                IpAddr::V4(addr) => {
-                    if let Some(socket) = self.socket.as_ref() {
-                        if settings.auto_multicast {
-                            socket
-                                .as_ref()
-                                .leave_multicast_v4(&addr, &Ipv4Addr::new(0, 0, 0, 0))
-                                .map_err(|err| {
-                                    error_msg!(
-                                        gst::ResourceError::OpenWrite,
-                                        [
-                                            "Failed to leave multicast group for {:?}: {}",
-                                            client,
-                                            err
-                                        ]
-                                    )
-                                })?;
-                        }
-                    }
-                }
-                IpAddr::V6(addr) => {
-                    if let Some(socket) = self.socket_v6.as_ref() {
-                        if settings.auto_multicast {
-                            socket
-                                .as_ref()
-                                .leave_multicast_v6(&addr, 0)
-                                .map_err(|err| {
-                                    error_msg!(
-                                        gst::ResourceError::OpenWrite,
-                                        [
-                                            "Failed to leave multicast group for {:?}: {}",
-                                            client,
-                                            err
-                                        ]
-                                    )
-                                })?;
-                        }
-                    }
-                }
-            }
+        if !client.ip().is_multicast() || !settings.auto_multicast {
+            return Ok(());
+        }
+
+        let socket = match client.ip() {
+            IpAddr::V4(_) => self.socket.as_ref(),
+            IpAddr::V6(_) => self.socket_v6.as_ref(),
+        };
+        let socket = match socket {
+            Some(socket) => socket.as_ref(),
+            None => return Ok(()),
+        };
+
+        let group = client.ip();
+        let (ifaces, filter) = settings.multicast_config(&group)?;
+        for iface in ifaces {
+            multicast::leave(socket, &group, iface, &filter).map_err(|err| {
+                error_msg!(
+                    gst::ResourceError::OpenWrite,
+                    ["Failed to leave multicast group for {:?}: {}", client, err]
+                )
+            })?;
         }
 
         Ok(())
@@ -673,6 +683,137 @@ impl UdpSinkTask {
         Ok(())
     }
 
+    async fn render_list(&mut self, list: gst::BufferList) -> Result<(), gst::FlowError> {
+        // With sync enabled, the list is sent in groups of consecutive
+        // buffers due at the same running time, each group being sent
+        // once its running time is reached.
+        let buffers = list.iter().collect::<Vec<_>>();
+
+        let mut remaining = &buffers[..];
+        while !remaining.is_empty() {
+            let count = if self.sync {
+                let rtime = running_time(self.segment.as_ref(), self.latency, remaining[0]);
+                if let Some(rtime) = rtime {
+                    self.sync(rtime).await;
+                }
+
+                remaining
+                    .iter()
+                    .position(|buffer| {
+                        running_time(self.segment.as_ref(), self.latency, buffer) != rtime
+                    })
+                    .unwrap_or(remaining.len())
+            } else {
+                remaining.len()
+            };
+
+            self.render_buffers(&remaining[..count]).await?;
+            remaining = &remaining[count..];
+        }
+
+        Ok(())
+    }
+
+    async fn render_buffers(&mut self, buffers: &[&gst::BufferRef]) -> Result<(), gst::FlowError> {
+        #[cfg(target_os = "linux")]
+        if self.batch_size > 1 || self.gso {
+            return self.render_batched(buffers).await;
+        }
+
+        for buffer in buffers {
+            self.render((*buffer).to_owned()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends `buffers` with `sendmmsg` or UDP GSO.
+    #[cfg(target_os = "linux")]
+    async fn render_batched(&mut self, buffers: &[&gst::BufferRef]) -> Result<(), gst::FlowError> {
+        let maps = buffers
+            .iter()
+            .map(|buffer| buffer.map_readable())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                element_error!(
+                    self.element,
+                    gst::StreamError::Format,
+                    ["Failed to map buffer readable"]
+                );
+                gst::FlowError::Error
+            })?;
+        let datagrams = maps.iter().map(|map| map.as_slice()).collect::<Vec<_>>();
+
+        for client in self.clients.iter() {
+            let socket = match client.ip() {
+                IpAddr::V4(_) => self.socket.as_ref(),
+                IpAddr::V6(_) => self.socket_v6.as_ref(),
+            };
+
+            let socket = match socket {
+                Some(socket) => socket,
+                None => {
+                    element_error!(
+                        self.element,
+                        gst::StreamError::Failed,
+                        ("I/O error"),
+                        ["No socket available for sending to {}", client]
+                    );
+                    return Err(gst::FlowError::Error);
+                }
+            };
+
+            gst::log!(
+                CAT,
+                obj: &self.element,
+                "Sending {} buffers to {:?}",
+                datagrams.len(),
+                &client
+            );
+
+            let mut remaining = &datagrams[..];
+            while !remaining.is_empty() {
+                let res = if self.gso {
+                    // A single datagram larger than the GSO payload limit is sent on its own
+                    let count =
+                        mmsg::gso_segment_count(remaining.iter().map(|data| data.len())).max(1);
+                    socket
+                        .write_with(|socket| {
+                            mmsg::send_segmented(socket, &remaining[..count], client)
+                        })
+                        .await
+                        .map(|_| count)
+                } else {
+                    let count = remaining.len().min(self.batch_size);
+                    socket
+                        .write_with(|socket| mmsg::send(socket, &remaining[..count], client))
+                        .await
+                };
+
+                let sent = res.map_err(|err| {
+                    element_error!(
+                        self.element,
+                        gst::StreamError::Failed,
+                        ("I/O error"),
+                        ["streaming stopped, I/O error {}", err]
+                    );
+                    gst::FlowError::Error
+                })?;
+
+                remaining = &remaining[sent..];
+            }
+        }
+
+        gst::log!(
+            CAT,
+            obj: &self.element,
+            "Sent {} buffers to all clients",
+            buffers.len()
+        );
+
+        Ok(())
+    }
+
     /// Waits until specified time.
     async fn sync(&self, running_time: gst::ClockTime) {
         let now = self.element.current_running_time();
@@ -695,6 +836,16 @@ impl TaskImpl for UdpSinkTask {
                 let udpsink = self.element.imp();
                 let mut settings = udpsink.settings.lock().unwrap();
                 self.sync = settings.sync;
+                self.batch_size = settings.batch_size as usize;
+                self.gso = settings.gso;
+                #[cfg(not(target_os = "linux"))]
+                if self.batch_size > 1 || self.gso {
+                    gst::warning!(
+                        CAT,
+                        obj: &self.element,
+                        "Batched sending is only supported on Linux"
+                    );
+                }
                 self.socket = self.prepare_socket(&mut settings, SocketFamily::Ipv4)?;
                 self.socket_v6 = self.prepare_socket(&mut settings, SocketFamily::Ipv6)?;
                 self.latency = settings.latency;
@@ -733,26 +884,24 @@ impl TaskImpl for UdpSinkTask {
                         // Check the peeked item in case we need to sync.
                         // The item will still be available in the channel
                         // in case this is cancelled by a state transition.
-                        match item_opt {
-                            Some(TaskItem::Buffer(buffer)) => {
-                                if self.sync {
-                                    let rtime = self.segment.as_ref().and_then(|segment| {
-                                        segment
-                                            .downcast_ref::<gst::format::Time>()
-                                            .and_then(|segment| {
-                                                segment.to_running_time(buffer.pts()).opt_add(self.latency)
-                                            })
-                                    });
-                                    if let Some(rtime) = rtime {
-                                        // This can be cancelled by a state transition.
-                                        self.sync(rtime).await;
-                                    }
-                                }
-                            }
-                            Some(_) => (),
+                        let buffer = match item_opt {
+                            Some(TaskItem::Buffer(buffer)) => Some(&**buffer),
+                            Some(TaskItem::BufferList(list)) => list.get(0),
+                            Some(_) => None,
                             None => {
                                 panic!("Internal channel sender dropped while Task is Started");
                             }
+                        };
+
+                        if let Some(buffer) = buffer {
+                            if self.sync {
+                                let rtime =
+                                    running_time(self.segment.as_ref(), self.latency, buffer);
+                                if let Some(rtime) = rtime {
+                                    // This can be cancelled by a state transition.
+                                    self.sync(rtime).await;
+                                }
+                            }
                         }
 
                         // An item was peeked above, we can now pop it without losing it.
@@ -777,6 +926,14 @@ impl TaskImpl for UdpSinkTask {
                     );
                     gst::FlowError::Error
                 })?,
+                TaskItem::BufferList(list) => self.render_list(list).await.map_err(|err| {
+                    element_error!(
+                        &self.element,
+                        gst::StreamError::Failed,
+                        ["Failed to render item, stopping task: {}", err]
+                    );
+                    gst::FlowError::Error
+                })?,
                 TaskItem::Event(event) => match event.view() {
                     EventView::Eos(_) => {
                         let _ = self
@@ -1043,6 +1200,16 @@ impl ObjectImpl for UdpSink {
                     .maximum(u8::MAX as u32)
                     .default_value(DEFAULT_TTL_MC)
                     .build(),
+                glib::ParamSpecString::builder("multicast-iface")
+                    .nick("Multicast Interface")
+                    .blurb("The network interface on which to join the multicast group. This allows multiple interfaces separated by comma. (\"eth0,eth1\")")
+                    .default_value(DEFAULT_MULTICAST_IFACE)
+                    .build(),
+                glib::ParamSpecString::builder("multicast-source")
+                    .nick("Multicast source")
+                    .blurb("List of source to receive the stream with '+' or '-' prefix (e.g., \"+SSM_SRC_IP_1+SSM_SRC_IP_2\") when joining multicast groups with auto-multicast. Positive (include) and negative (exclude) filters can't be mixed")
+                    .default_value(DEFAULT_MULTICAST_SOURCE)
+                    .build(),
                 glib::ParamSpecInt::builder("qos-dscp")
                     .nick("QoS DSCP")
                     .blurb("Quality of Service, differentiated services code point (-1 default)")
@@ -1050,6 +1217,18 @@ impl ObjectImpl for UdpSink {
                     .maximum(63)
                     .default_value(DEFAULT_QOS_DSCP)
                     .build(),
+                glib::ParamSpecUInt::builder("batch-size")
+                    .nick("Batch Size")
+                    .blurb("Maximum number of buffers from a buffer list to send per system call (Linux only, 1 = one buffer at a time)")
+                    .minimum(1)
+                    .maximum(1024)
+                    .default_value(DEFAULT_BATCH_SIZE)
+                    .build(),
+                glib::ParamSpecBoolean::builder("gso")
+                    .nick("GSO")
+                    .blurb("Send buffer lists of equally sized buffers using UDP generic segmentation offload (Linux only)")
+                    .default_value(DEFAULT_GSO)
+                    .build(),
                 glib::ParamSpecString::builder("clients")
                     .nick("Clients")
                     .blurb("A comma separated list of host:port pairs with destinations")
@@ -1180,9 +1359,21 @@ impl ObjectImpl for UdpSink {
             "ttl-mc" => {
                 settings.ttl_mc = value.get().expect("type checked upstream");
             }
+            "multicast-iface" => {
+                settings.multicast_iface = value.get().expect("type checked upstream");
+            }
+            "multicast-source" => {
+                settings.multicast_source = value.get().expect("type checked upstream");
+            }
             "qos-dscp" => {
                 settings.qos_dscp = value.get().expect("type checked upstream");
             }
+            "batch-size" => {
+                settings.batch_size = value.get().expect("type checked upstream");
+            }
+            "gso" => {
+                settings.gso = value.get().expect("type checked upstream");
+            }
             "clients" => {
                 let clients = value
                     .get::<Option<String>>()
@@ -1250,11 +1441,15 @@ impl ObjectImpl for UdpSink {
                 .as_ref()
                 .map(GioSocketWrapper::as_socket)
                 .to_value(),
-            "auto-multicast" => settings.sync.to_value(),
+            "auto-multicast" => settings.auto_multicast.to_value(),
             "loop" => settings.multicast_loop.to_value(),
             "ttl" => settings.ttl.to_value(),
             "ttl-mc" => settings.ttl_mc.to_value(),
+            "multicast-iface" => settings.multicast_iface.to_value(),
+            "multicast-source" => settings.multicast_source.to_value(),
             "qos-dscp" => settings.qos_dscp.to_value(),
+            "batch-size" => settings.batch_size.to_value(),
+            "gso" => settings.gso.to_value(),
             "clients" => {
                 let clients = settings.clients.clone();
                 drop(settings);
@@ -37,6 +37,9 @@ use std::u16;
 use crate::runtime::prelude::*;
 use crate::runtime::{Async, Context, PadSrc, PadSrcRef, Task};
 
+#[cfg(target_os = "linux")]
+use crate::mmsg;
+use crate::multicast;
 use crate::socket::{wrap_socket, GioSocketWrapper, Socket, SocketError, SocketRead};
 
 const DEFAULT_ADDRESS: Option<&str> = Some("0.0.0.0");
@@ -49,6 +52,11 @@ const DEFAULT_USED_SOCKET: Option<GioSocketWrapper> = None;
 const DEFAULT_CONTEXT: &str = "";
 const DEFAULT_CONTEXT_WAIT: Duration = Duration::ZERO;
 const DEFAULT_RETRIEVE_SENDER_ADDRESS: bool = true;
+const DEFAULT_MULTICAST_IFACE: Option<&str> = None;
+const DEFAULT_MULTICAST_SOURCE: Option<&str> = None;
+const DEFAULT_LOOP: bool = true;
+const DEFAULT_BATCH_SIZE: u32 = 1;
+const DEFAULT_GRO: bool = false;
 
 #[derive(Debug, Clone)]
 struct Settings {
@@ -62,6 +70,11 @@ struct Settings {
     context: String,
     context_wait: Duration,
     retrieve_sender_address: bool,
+    multicast_iface: Option<String>,
+    multicast_source: Option<String>,
+    multicast_loop: bool,
+    batch_size: u32,
+    gro: bool,
 }
 
 impl Default for Settings {
@@ -77,6 +90,11 @@ impl Default for Settings {
             context: DEFAULT_CONTEXT.into(),
             context_wait: DEFAULT_CONTEXT_WAIT,
             retrieve_sender_address: DEFAULT_RETRIEVE_SENDER_ADDRESS,
+            multicast_iface: DEFAULT_MULTICAST_IFACE.map(Into::into),
+            multicast_source: DEFAULT_MULTICAST_SOURCE.map(Into::into),
+            multicast_loop: DEFAULT_LOOP,
+            batch_size: DEFAULT_BATCH_SIZE,
+            gro: DEFAULT_GRO,
         }
     }
 }
@@ -190,9 +208,17 @@ impl PadSrcHandler for UdpSrcPadHandler {
     }
 }
 
+#[derive(Debug)]
+enum TaskItem {
+    Buffer(gst::Buffer),
+    BufferList(gst::BufferList),
+}
+
 struct UdpSrcTask {
     element: super::UdpSrc,
     socket: Option<Socket<UdpReader>>,
+    #[cfg(target_os = "linux")]
+    batch: Option<mmsg::RecvBatch>,
     retrieve_sender_address: bool,
     need_initial_events: bool,
     need_segment: bool,
@@ -203,6 +229,8 @@ impl UdpSrcTask {
         UdpSrcTask {
             element,
             socket: None,
+            #[cfg(target_os = "linux")]
+            batch: None,
             retrieve_sender_address: DEFAULT_RETRIEVE_SENDER_ADDRESS,
             need_initial_events: true,
             need_segment: true,
@@ -211,7 +239,7 @@ impl UdpSrcTask {
 }
 
 impl TaskImpl for UdpSrcTask {
-    type Item = gst::Buffer;
+    type Item = TaskItem;
 
     fn prepare(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
         async move {
@@ -264,7 +292,6 @@ impl TaskImpl for UdpSrcTask {
                 };
                 let port = settings.port;
 
-                // TODO: TTL, multicast loopback, etc
                 let saddr = if addr.is_multicast() {
                     let bind_addr = if addr.is_ipv4() {
                         IpAddr::V4(Ipv4Addr::UNSPECIFIED)
@@ -341,28 +368,48 @@ impl TaskImpl for UdpSrcTask {
                 })?;
 
                 if addr.is_multicast() {
-                    // TODO: Multicast interface configuration, going to be tricky
+                    let ifaces = multicast::parse_interfaces(settings.multicast_iface.as_deref())
+                        .map_err(|err| {
+                        gst::error_msg!(gst::ResourceError::Settings, ["{}", err])
+                    })?;
+                    let filter = multicast::SourceFilter::parse(
+                        settings.multicast_source.as_deref().unwrap_or_default(),
+                    )
+                    .and_then(|filter| filter.check_family(&addr).map(|_| filter))
+                    .map_err(|err| gst::error_msg!(gst::ResourceError::Settings, ["{}", err]))?;
+
+                    for iface in ifaces {
+                        gst::debug!(
+                            CAT,
+                            obj: &self.element,
+                            "Joining multicast group {:?} on interface {} with {:?}",
+                            addr,
+                            iface,
+                            filter,
+                        );
+
+                        multicast::join(socket.as_ref(), &addr, iface, &filter).map_err(|err| {
+                            gst::error_msg!(
+                                gst::ResourceError::OpenRead,
+                                ["Failed to join multicast group: {}", err]
+                            )
+                        })?;
+                    }
+
                     match addr {
-                        IpAddr::V4(addr) => {
-                            socket
-                                .as_ref()
-                                .join_multicast_v4(&addr, &Ipv4Addr::new(0, 0, 0, 0))
-                                .map_err(|err| {
-                                    gst::error_msg!(
-                                        gst::ResourceError::OpenRead,
-                                        ["Failed to join multicast group: {}", err]
-                                    )
-                                })?;
-                        }
-                        IpAddr::V6(addr) => {
-                            socket.as_ref().join_multicast_v6(&addr, 0).map_err(|err| {
-                                gst::error_msg!(
-                                    gst::ResourceError::OpenRead,
-                                    ["Failed to join multicast group: {}", err]
-                                )
-                            })?;
-                        }
+                        IpAddr::V4(_) => socket
+                            .as_ref()
+                            .set_multicast_loop_v4(settings.multicast_loop),
+                        IpAddr::V6(_) => socket
+                            .as_ref()
+                            .set_multicast_loop_v6(settings.multicast_loop),
                     }
+                    .map_err(|err| {
+                        gst::error_msg!(
+                            gst::ResourceError::OpenRead,
+                            ["Failed to set multicast loop: {}", err]
+                        )
+                    })?;
                 }
 
                 settings.used_socket = Some(wrap_socket(&socket)?);
@@ -379,9 +426,36 @@ impl TaskImpl for UdpSrcTask {
                 settings = udpsrc.settings.lock().unwrap();
             };
 
+            let use_batch = settings.batch_size > 1 || settings.gro;
+            #[cfg(not(target_os = "linux"))]
+            if use_batch {
+                gst::warning!(
+                    CAT,
+                    obj: &self.element,
+                    "Batched reception is only supported on Linux"
+                );
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            let buffer_size = settings.mtu;
+            #[cfg(target_os = "linux")]
+            let buffer_size = if settings.gro {
+                mmsg::enable_gro(socket.as_ref()).map_err(|err| {
+                    gst::error_msg!(
+                        gst::ResourceError::OpenRead,
+                        ["Failed to enable GRO: {}", err]
+                    )
+                })?;
+
+                // Coalesced datagrams are split after reception
+                settings.mtu.max(mmsg::GRO_BUFFER_SIZE)
+            } else {
+                settings.mtu
+            };
+
             let buffer_pool = gst::BufferPool::new();
             let mut config = buffer_pool.config();
-            config.set_params(None, settings.mtu, 0, 0);
+            config.set_params(None, buffer_size, 0, 0);
             buffer_pool.set_config(config).map_err(|err| {
                 gst::error_msg!(
                     gst::ResourceError::Settings,
@@ -389,6 +463,38 @@ impl TaskImpl for UdpSrcTask {
                 )
             })?;
 
+            #[cfg(target_os = "linux")]
+            if use_batch {
+                gst::debug!(
+                    CAT,
+                    obj: &self.element,
+                    "Using batched reception: batch size {}, GRO {}",
+                    settings.batch_size,
+                    settings.gro,
+                );
+
+                self.batch = Some(
+                    mmsg::RecvBatch::try_new(
+                        self.element.clone().upcast(),
+                        buffer_pool,
+                        socket,
+                        settings.batch_size,
+                        settings.gro,
+                    )
+                    .map_err(|err| {
+                        gst::error_msg!(
+                            gst::ResourceError::OpenRead,
+                            ["Failed to prepare socket {:?}", err]
+                        )
+                    })?,
+                );
+
+                drop(settings);
+                self.element.notify("used-socket");
+
+                return Ok(());
+            }
+
             self.socket = Some(
                 Socket::try_new(
                     self.element.clone().upcast(),
@@ -413,6 +519,11 @@ impl TaskImpl for UdpSrcTask {
     fn unprepare(&mut self) -> BoxFuture<'_, ()> {
         async move {
             gst::debug!(CAT, obj: &self.element, "Unpreparing Task");
+            self.socket = None;
+            #[cfg(target_os = "linux")]
+            {
+                self.batch = None;
+            }
             let udpsrc = self.element.imp();
             udpsrc.settings.lock().unwrap().used_socket = None;
             self.element.notify("used-socket");
@@ -423,63 +534,60 @@ impl TaskImpl for UdpSrcTask {
     fn start(&mut self) -> BoxFuture<'_, Result<(), gst::ErrorMessage>> {
         async move {
             gst::log!(CAT, obj: &self.element, "Starting task");
-            self.socket
-                .as_mut()
-                .unwrap()
-                .set_clock(self.element.clock(), self.element.base_time());
+            #[cfg(target_os = "linux")]
+            if let Some(batch) = self.batch.as_mut() {
+                batch.set_clock(self.element.clock(), self.element.base_time());
+            }
+            if let Some(socket) = self.socket.as_mut() {
+                socket.set_clock(self.element.clock(), self.element.base_time());
+            }
             gst::log!(CAT, obj: &self.element, "Task started");
             Ok(())
         }
         .boxed()
     }
 
-    fn try_next(&mut self) -> BoxFuture<'_, Result<gst::Buffer, gst::FlowError>> {
+    fn try_next(&mut self) -> BoxFuture<'_, Result<TaskItem, gst::FlowError>> {
         async move {
-            self.socket
+            #[cfg(target_os = "linux")]
+            if let Some(batch) = self.batch.as_mut() {
+                let mut received = batch
+                    .try_next()
+                    .await
+                    .map_err(|err| self.handle_socket_error(err))?;
+
+                if received.len() == 1 {
+                    let (buffer, saddr) = received.pop().unwrap();
+                    return Ok(TaskItem::Buffer(self.add_net_address_meta(buffer, saddr)));
+                }
+
+                let mut list = gst::BufferList::new_sized(received.len());
+                {
+                    let list = list.get_mut().unwrap();
+                    for (buffer, saddr) in received {
+                        list.add(self.add_net_address_meta(buffer, saddr));
+                    }
+                }
+
+                return Ok(TaskItem::BufferList(list));
+            }
+
+            let (buffer, saddr) = self
+                .socket
                 .as_mut()
                 .unwrap()
                 .try_next()
                 .await
-                .map(|(mut buffer, saddr)| {
-                    if let Some(saddr) = saddr {
-                        if self.retrieve_sender_address {
-                            NetAddressMeta::add(
-                                buffer.get_mut().unwrap(),
-                                &gio::InetSocketAddress::from(saddr),
-                            );
-                        }
-                    }
-                    buffer
-                })
-                .map_err(|err| {
-                    gst::error!(CAT, obj: &self.element, "Got error {:?}", err);
-                    match err {
-                        SocketError::Gst(err) => {
-                            gst::element_error!(
-                                self.element,
-                                gst::StreamError::Failed,
-                                ("Internal data stream error"),
-                                ["streaming stopped, reason {}", err]
-                            );
-                        }
-                        SocketError::Io(err) => {
-                            gst::element_error!(
-                                self.element,
-                                gst::StreamError::Failed,
-                                ("I/O error"),
-                                ["streaming stopped, I/O error {}", err]
-                            );
-                        }
-                    }
-                    gst::FlowError::Error
-                })
+                .map_err(|err| self.handle_socket_error(err))?;
+
+            Ok(TaskItem::Buffer(self.add_net_address_meta(buffer, saddr)))
         }
         .boxed()
     }
 
-    fn handle_item(&mut self, buffer: gst::Buffer) -> BoxFuture<'_, Result<(), gst::FlowError>> {
+    fn handle_item(&mut self, item: TaskItem) -> BoxFuture<'_, Result<(), gst::FlowError>> {
         async {
-            gst::log!(CAT, obj: &self.element, "Handling {:?}", buffer);
+            gst::log!(CAT, obj: &self.element, "Handling {:?}", item);
             let udpsrc = self.element.imp();
 
             if self.need_initial_events {
@@ -512,9 +620,12 @@ impl TaskImpl for UdpSrcTask {
                 self.need_segment = false;
             }
 
-            let res = udpsrc.src_pad.push(buffer).await.map(drop);
+            let res = match item {
+                TaskItem::Buffer(buffer) => udpsrc.src_pad.push(buffer).await.map(drop),
+                TaskItem::BufferList(list) => udpsrc.src_pad.push_list(list).await.map(drop),
+            };
             match res {
-                Ok(_) => gst::log!(CAT, obj: &self.element, "Successfully pushed buffer"),
+                Ok(_) => gst::log!(CAT, obj: &self.element, "Successfully pushed item"),
                 Err(gst::FlowError::Flushing) => gst::debug!(CAT, obj: &self.element, "Flushing"),
                 Err(gst::FlowError::Eos) => {
                     gst::debug!(CAT, obj: &self.element, "EOS");
@@ -558,6 +669,49 @@ impl TaskImpl for UdpSrcTask {
     }
 }
 
+impl UdpSrcTask {
+    fn add_net_address_meta(
+        &self,
+        mut buffer: gst::Buffer,
+        saddr: Option<SocketAddr>,
+    ) -> gst::Buffer {
+        if let Some(saddr) = saddr {
+            if self.retrieve_sender_address {
+                NetAddressMeta::add(
+                    buffer.get_mut().unwrap(),
+                    &gio::InetSocketAddress::from(saddr),
+                );
+            }
+        }
+
+        buffer
+    }
+
+    fn handle_socket_error(&self, err: SocketError) -> gst::FlowError {
+        gst::error!(CAT, obj: &self.element, "Got error {:?}", err);
+        match err {
+            SocketError::Gst(err) => {
+                gst::element_error!(
+                    self.element,
+                    gst::StreamError::Failed,
+                    ("Internal data stream error"),
+                    ["streaming stopped, reason {}", err]
+                );
+            }
+            SocketError::Io(err) => {
+                gst::element_error!(
+                    self.element,
+                    gst::StreamError::Failed,
+                    ("I/O error"),
+                    ["streaming stopped, I/O error {}", err]
+                );
+            }
+        }
+
+        gst::FlowError::Error
+    }
+}
+
 pub struct UdpSrc {
     src_pad: PadSrc,
     task: Task,
@@ -691,6 +845,33 @@ impl ObjectImpl for UdpSrc {
                     .blurb("Whether to retrieve the sender address and add it to buffers as meta. Disabling this might result in minor performance improvements in certain scenarios")
                     .default_value(DEFAULT_RETRIEVE_SENDER_ADDRESS)
                     .build(),
+                glib::ParamSpecString::builder("multicast-iface")
+                    .nick("Multicast Interface")
+                    .blurb("The network interface on which to join the multicast group. This allows multiple interfaces separated by comma. (\"eth0,eth1\")")
+                    .default_value(DEFAULT_MULTICAST_IFACE)
+                    .build(),
+                glib::ParamSpecString::builder("multicast-source")
+                    .nick("Multicast source")
+                    .blurb("List of source to receive the stream with '+' or '-' prefix (e.g., \"+SSM_SRC_IP_1+SSM_SRC_IP_2\"). Negative filters (exclude) require IGMPv3/MLDv2, and can't be mixed with positive filters (include)")
+                    .default_value(DEFAULT_MULTICAST_SOURCE)
+                    .build(),
+                glib::ParamSpecBoolean::builder("loop")
+                    .nick("Multicast Loopback")
+                    .blurb("Set the multicast loop parameter.")
+                    .default_value(DEFAULT_LOOP)
+                    .build(),
+                glib::ParamSpecUInt::builder("batch-size")
+                    .nick("Batch Size")
+                    .blurb("Maximum number of datagrams to read per system call, pushed downstream as buffer lists (Linux only, 1 = one datagram at a time)")
+                    .minimum(1)
+                    .maximum(1024)
+                    .default_value(DEFAULT_BATCH_SIZE)
+                    .build(),
+                glib::ParamSpecBoolean::builder("gro")
+                    .nick("GRO")
+                    .blurb("Enable UDP generic receive offload and split coalesced datagrams into individual buffers (Linux only)")
+                    .default_value(DEFAULT_GRO)
+                    .build(),
             ];
 
             #[cfg(not(windows))]
@@ -763,6 +944,21 @@ impl ObjectImpl for UdpSrc {
             "retrieve-sender-address" => {
                 settings.retrieve_sender_address = value.get().expect("type checked upstream");
             }
+            "multicast-iface" => {
+                settings.multicast_iface = value.get().expect("type checked upstream");
+            }
+            "multicast-source" => {
+                settings.multicast_source = value.get().expect("type checked upstream");
+            }
+            "loop" => {
+                settings.multicast_loop = value.get().expect("type checked upstream");
+            }
+            "batch-size" => {
+                settings.batch_size = value.get().expect("type checked upstream");
+            }
+            "gro" => {
+                settings.gro = value.get().expect("type checked upstream");
+            }
             _ => unimplemented!(),
         }
     }
@@ -788,6 +984,11 @@ impl ObjectImpl for UdpSrc {
             "context" => settings.context.to_value(),
             "context-wait" => (settings.context_wait.as_millis() as u32).to_value(),
             "retrieve-sender-address" => settings.retrieve_sender_address.to_value(),
+            "multicast-iface" => settings.multicast_iface.to_value(),
+            "multicast-source" => settings.multicast_source.to_value(),
+            "loop" => settings.multicast_loop.to_value(),
+            "batch-size" => settings.batch_size.to_value(),
+            "gro" => settings.gro.to_value(),
             _ => unimplemented!(),
         }
     }
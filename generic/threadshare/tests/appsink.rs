@@ -0,0 +1,177 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use futures::prelude::*;
+
+use gst::prelude::*;
+
+use std::sync::mpsc;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstthreadshare::plugin_register_static().expect("gstthreadshare appsink test");
+    });
+}
+
+fn push_buffers(h: &mut gst_check::Harness, n: u8) {
+    h.set_src_caps(gst::Caps::builder("foo/bar").build());
+    for i in 0..n {
+        let mut buffer = gst::Buffer::from_slice(vec![i]);
+        buffer
+            .get_mut()
+            .unwrap()
+            .set_pts(gst::ClockTime::from_mseconds(i as u64));
+        assert_eq!(h.push(buffer), Ok(gst::FlowSuccess::Ok));
+    }
+    assert!(h.push_event(gst::event::Eos::new()));
+}
+
+#[test]
+fn stream() {
+    init();
+
+    let mut h = gst_check::Harness::new("ts-appsink");
+    {
+        let appsink = h.element().unwrap();
+        appsink.set_property("context", "appsink-stream");
+        appsink.set_property("sync", false);
+    }
+
+    h.play();
+
+    let appsink = h
+        .element()
+        .unwrap()
+        .downcast::<gstthreadshare::appsink::AppSink>()
+        .unwrap();
+    let stream = appsink.stream().unwrap();
+
+    push_buffers(&mut h, 3);
+
+    let samples = futures::executor::block_on(stream.collect::<Vec<_>>());
+    assert_eq!(samples.len(), 3);
+    for (i, sample) in samples.iter().enumerate() {
+        let buffer = sample.buffer().unwrap();
+        assert_eq!(buffer.map_readable().unwrap().as_slice(), &[i as u8]);
+        assert_eq!(
+            sample.caps().unwrap().structure(0).unwrap().name(),
+            "foo/bar"
+        );
+        assert!(sample.segment().is_some());
+    }
+}
+
+#[test]
+fn new_sample_signal() {
+    init();
+
+    let mut h = gst_check::Harness::new("ts-appsink");
+    let (sample_sender, sample_receiver) = mpsc::channel();
+    let (eos_sender, eos_receiver) = mpsc::channel();
+    {
+        let appsink = h.element().unwrap();
+        appsink.set_property("context", "appsink-signal");
+        appsink.set_property("sync", false);
+        appsink.set_property("emit-signals", true);
+
+        let sample_sender = std::sync::Mutex::new(sample_sender);
+        appsink.connect("new-sample", false, move |args| {
+            let sample = args[1].get::<gst::Sample>().unwrap();
+            sample_sender.lock().unwrap().send(sample).unwrap();
+            Some(gst::FlowReturn::Ok.to_value())
+        });
+
+        let eos_sender = std::sync::Mutex::new(eos_sender);
+        appsink.connect("eos", false, move |_| {
+            eos_sender.lock().unwrap().send(()).unwrap();
+            None
+        });
+    }
+
+    h.play();
+
+    push_buffers(&mut h, 3);
+
+    eos_receiver.recv().unwrap();
+    assert_eq!(sample_receiver.try_iter().count(), 3);
+}
+
+#[test]
+fn pull_sample() {
+    init();
+
+    let mut h = gst_check::Harness::new("ts-appsink");
+    {
+        let appsink = h.element().unwrap();
+        appsink.set_property("context", "appsink-pull");
+        appsink.set_property("sync", false);
+    }
+
+    h.play();
+
+    push_buffers(&mut h, 2);
+
+    let appsink = h.element().unwrap();
+    for _ in 0..2 {
+        let sample = appsink.emit_by_name::<Option<gst::Sample>>("pull-sample", &[]);
+        assert!(sample.is_some());
+    }
+
+    // EOS
+    let sample = appsink.emit_by_name::<Option<gst::Sample>>(
+        "try-pull-sample",
+        &[&gst::ClockTime::from_mseconds(100).nseconds()],
+    );
+    assert!(sample.is_none());
+}
+
+#[test]
+fn drop_oldest() {
+    init();
+
+    let mut h = gst_check::Harness::new("ts-appsink");
+    {
+        let appsink = h.element().unwrap();
+        appsink.set_property("context", "appsink-drop");
+        appsink.set_property("sync", false);
+        appsink.set_property("max-buffers", 2u32);
+        appsink.set_property("drop", true);
+    }
+
+    h.play();
+
+    let appsink = h
+        .element()
+        .unwrap()
+        .downcast::<gstthreadshare::appsink::AppSink>()
+        .unwrap();
+    let stream = appsink.stream().unwrap();
+
+    push_buffers(&mut h, 4);
+
+    // Only the EOS and the most recent sample fit in the queue
+    let samples = futures::executor::block_on(stream.collect::<Vec<_>>());
+    assert_eq!(samples.len(), 1);
+    let buffer = samples[0].buffer().unwrap();
+    assert_eq!(buffer.map_readable().unwrap().as_slice(), &[3]);
+}
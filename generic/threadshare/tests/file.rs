@@ -0,0 +1,109 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use gst::prelude::*;
+
+use std::fs;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstthreadshare::plugin_register_static().expect("gstthreadshare file test");
+    });
+}
+
+#[test]
+fn filesrc_to_filesink() {
+    init();
+
+    let dir = std::env::temp_dir();
+    let src_path = dir.join(format!("ts-filesrc-{}.bin", std::process::id()));
+    let sink_path = dir.join(format!("ts-filesink-{}.bin", std::process::id()));
+
+    let data = (0..100_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    fs::write(&src_path, &data).unwrap();
+
+    let pipeline = gst::Pipeline::new(None);
+    let src = gst::ElementFactory::make("ts-filesrc", None).unwrap();
+    src.set_property("location", src_path.to_str().unwrap());
+    src.set_property("blocksize", 4000u32);
+    src.set_property("context", "file-test");
+    let sink = gst::ElementFactory::make("ts-filesink", None).unwrap();
+    sink.set_property("location", sink_path.to_str().unwrap());
+    sink.set_property("context", "file-test");
+
+    pipeline.add_many(&[&src, &sink]).unwrap();
+    src.link(&sink).unwrap();
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::from_seconds(10)) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => panic!("{:?}", err),
+            _ => (),
+        }
+    }
+
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    let written = fs::read(&sink_path).unwrap();
+    let _ = fs::remove_file(&src_path);
+    let _ = fs::remove_file(&sink_path);
+
+    assert_eq!(written, data);
+}
+
+#[test]
+fn filesrc_duration() {
+    init();
+
+    let path = std::env::temp_dir().join(format!("ts-filesrc-dur-{}.bin", std::process::id()));
+    fs::write(&path, &[0u8; 1234]).unwrap();
+
+    let mut h = gst_check::Harness::new_parse(&format!(
+        "ts-filesrc location={} context=file-duration",
+        path.to_str().unwrap()
+    ));
+    h.play();
+
+    let src = h.element().unwrap();
+    let srcpad = src.static_pad("src").unwrap();
+    assert_eq!(
+        srcpad.query_duration::<gst::format::Bytes>(),
+        Some(gst::format::Bytes(1234))
+    );
+
+    let mut size = 0;
+    while let Some(buffer) = h.pull() {
+        size += buffer.size();
+        if size == 1234 {
+            break;
+        }
+    }
+    assert_eq!(size, 1234);
+
+    let _ = fs::remove_file(&path);
+}
@@ -91,3 +91,145 @@ fn test_active_pad() {
 
     let _ = is.set_state(gst::State::Null);
 }
+
+#[test]
+fn test_pad_properties() {
+    init();
+
+    let is = gst::ElementFactory::make("ts-input-selector", None).unwrap();
+    is.set_property_from_str("sync-mode", "active-segment");
+
+    let mut h1 = gst_check::Harness::with_element(&is, Some("sink_%u"), Some("src"));
+    let mut h2 = gst_check::Harness::with_element(&is, Some("sink_%u"), None);
+
+    let pad1 = h1.srcpad().unwrap().peer().unwrap();
+    let pad2 = h2.srcpad().unwrap().peer().unwrap();
+    assert!(pad1.property::<bool>("active"));
+    assert!(!pad2.property::<bool>("active"));
+    assert_eq!(pad1.property::<u64>("running-time"), 0);
+
+    h1.set_src_caps_str("foo/bar");
+    h2.set_src_caps_str("foo/bar");
+    h1.play();
+
+    let mut buf = gst::Buffer::new();
+    {
+        let buf = buf.get_mut().unwrap();
+        buf.set_pts(gst::ClockTime::from_mseconds(10));
+        buf.set_duration(gst::ClockTime::from_mseconds(10));
+    }
+    assert_eq!(h1.push(buf), Ok(gst::FlowSuccess::Ok));
+    assert_eq!(
+        pad1.property::<u64>("running-time"),
+        gst::ClockTime::from_mseconds(20).nseconds()
+    );
+
+    is.set_property("active-pad", &pad2);
+    assert!(!pad1.property::<bool>("active"));
+    assert!(pad2.property::<bool>("active"));
+
+    let _ = is.set_state(gst::State::Null);
+}
+
+fn timed_buffer(pts_ms: u64) -> gst::Buffer {
+    let mut buf = gst::Buffer::new();
+    {
+        let buf = buf.get_mut().unwrap();
+        buf.set_pts(gst::ClockTime::from_mseconds(pts_ms));
+        buf.set_duration(gst::ClockTime::from_mseconds(10));
+    }
+    buf
+}
+
+#[test]
+fn test_sync_active_segment() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    init();
+
+    let is = gst::ElementFactory::make("ts-input-selector", None).unwrap();
+    is.set_property_from_str("sync-mode", "active-segment");
+
+    let mut h1 = gst_check::Harness::with_element(&is, Some("sink_%u"), Some("src"));
+    let mut h2 = gst_check::Harness::with_element(&is, Some("sink_%u"), None);
+
+    h1.set_src_caps_str("foo/bar");
+    h2.set_src_caps_str("foo/bar");
+    h1.play();
+
+    /* The inactive pad is ahead of the active one: it must wait */
+    let pushed = Arc::new(AtomicBool::new(false));
+    let inactive_src = h2.srcpad().unwrap();
+    let handle = thread::spawn({
+        let pushed = pushed.clone();
+        move || {
+            let res = inactive_src.push(timed_buffer(30));
+            pushed.store(true, Ordering::SeqCst);
+            res
+        }
+    });
+
+    thread::sleep(Duration::from_millis(50));
+    assert!(!pushed.load(Ordering::SeqCst));
+
+    /* Once the active pad catches up, the inactive buffer is dropped */
+    for pts in [0, 10, 20] {
+        assert_eq!(h1.push(timed_buffer(pts)), Ok(gst::FlowSuccess::Ok));
+    }
+    assert_eq!(handle.join().unwrap(), Ok(gst::FlowSuccess::Ok));
+    assert!(pushed.load(Ordering::SeqCst));
+    assert_eq!(h1.buffers_received(), 3);
+
+    let _ = is.set_state(gst::State::Null);
+}
+
+#[test]
+fn test_cache_buffers_drop_backwards() {
+    init();
+
+    let is = gst::ElementFactory::make("ts-input-selector", None).unwrap();
+    is.set_property_from_str("sync-mode", "active-segment");
+    is.set_property("cache-buffers", true);
+    is.set_property("drop-backwards", true);
+
+    let mut h1 = gst_check::Harness::with_element(&is, Some("sink_%u"), Some("src"));
+    let mut h2 = gst_check::Harness::with_element(&is, Some("sink_%u"), None);
+
+    h1.set_src_caps_str("foo/bar");
+    h2.set_src_caps_str("foo/bar");
+    h1.play();
+
+    for pts in [0, 10, 20] {
+        assert_eq!(h1.push(timed_buffer(pts)), Ok(gst::FlowSuccess::Ok));
+    }
+
+    /* The active pad is already at 30ms, these are cached right away and the
+     * first one is discarded as it ended before the last output buffer */
+    for pts in [0, 10, 20] {
+        assert_eq!(h2.push(timed_buffer(pts)), Ok(gst::FlowSuccess::Ok));
+    }
+    assert_eq!(h1.buffers_received(), 3);
+
+    /* Switching replays the cache, minus the buffer going backwards */
+    is.set_property("active-pad", h2.srcpad().unwrap().peer());
+    assert_eq!(h2.push(timed_buffer(30)), Ok(gst::FlowSuccess::Ok));
+    assert_eq!(h1.buffers_received(), 5);
+
+    for pts in [0, 10, 20] {
+        let buf = h1.pull().unwrap();
+        assert_eq!(buf.pts(), Some(gst::ClockTime::from_mseconds(pts)));
+    }
+
+    let buf = h1.pull().unwrap();
+    assert_eq!(buf.pts(), Some(gst::ClockTime::from_mseconds(20)));
+    assert!(buf.flags().contains(gst::BufferFlags::DISCONT));
+
+    let buf = h1.pull().unwrap();
+    assert_eq!(buf.pts(), Some(gst::ClockTime::from_mseconds(30)));
+    assert!(!buf.flags().contains(gst::BufferFlags::DISCONT));
+
+    let _ = is.set_state(gst::State::Null);
+}
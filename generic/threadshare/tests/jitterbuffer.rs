@@ -18,6 +18,8 @@
 // SPDX-License-Identifier: LGPL-2.1-or-later
 
 use gst::prelude::*;
+use gst_rtp::prelude::*;
+use gst_rtp::RTPBuffer;
 
 use std::sync::mpsc;
 
@@ -162,3 +164,64 @@ fn jb_ts_pipeline() {
 
     pipeline.set_state(gst::State::Null).unwrap();
 }
+
+#[test]
+fn jb_retransmission_request() {
+    init();
+
+    const LATENCY: u32 = 1000;
+    const PACKET_DURATION: gst::ClockTime = gst::ClockTime::from_mseconds(20);
+
+    let mut h = gst_check::Harness::new("ts-jitterbuffer");
+    h.use_systemclock();
+
+    {
+        let jb = h.element().unwrap();
+        jb.set_property("context", "jb_retransmission_request");
+        jb.set_property("latency", LATENCY);
+        jb.set_property("do-retransmission", true);
+    }
+
+    h.play();
+    h.set_src_caps_str(
+        "application/x-rtp,media=audio,clock-rate=8000,encoding-name=PCMA,payload=8",
+    );
+
+    let base = h.element().unwrap().current_running_time().unwrap();
+
+    // Packet 2 is dropped
+    for seq in [0u16, 1, 3] {
+        let mut buf = gst::Buffer::new_rtp_with_sizes(160, 0, 0).unwrap();
+        {
+            let buf_mut = buf.get_mut().unwrap();
+            let pts = base + seq as u64 * PACKET_DURATION;
+            buf_mut.set_pts(pts);
+            buf_mut.set_dts(pts);
+
+            let mut rtpbuf = RTPBuffer::from_buffer_writable(buf_mut).unwrap();
+            rtpbuf.set_seq(seq);
+            rtpbuf.set_timestamp(seq as u32 * 160);
+            rtpbuf.set_payload_type(8);
+        }
+
+        assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+    }
+
+    let event = loop {
+        let event = h.pull_upstream_event().unwrap();
+        if event.type_() == gst::EventType::CustomUpstream {
+            break event;
+        }
+    };
+
+    let s = event.structure().unwrap();
+    assert_eq!(s.name(), "GstRTPRetransmissionRequest");
+    assert_eq!(s.get::<u32>("seqnum").unwrap(), 2);
+    assert_eq!(s.get::<u32>("retry").unwrap(), 0);
+    assert_eq!(s.get::<u32>("deadline").unwrap(), LATENCY);
+
+    // Expected between the packets surrounding it
+    let running_time = s.get::<u64>("running-time").unwrap();
+    assert!(running_time > (base + PACKET_DURATION).nseconds());
+    assert!(running_time < (base + 3 * PACKET_DURATION).nseconds());
+}
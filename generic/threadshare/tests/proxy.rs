@@ -162,3 +162,102 @@ fn test_from_pipeline_to_pipeline_and_back() {
     pipe_1.set_state(gst::State::Null).unwrap();
     pipe_2.set_state(gst::State::Null).unwrap();
 }
+
+#[test]
+fn test_broadcast() {
+    init();
+
+    let producer = gst::Pipeline::new(None);
+    let fakesrc = gst::ElementFactory::make("fakesrc", None).unwrap();
+    let pxsink = gst::ElementFactory::make("ts-proxysink", Some("proxysink::test4")).unwrap();
+
+    producer.add_many(&[&fakesrc, &pxsink]).unwrap();
+    fakesrc.link(&pxsink).unwrap();
+
+    fakesrc.set_property("num-buffers", 3i32);
+    pxsink.set_property("proxy-context", "proxy::test4_proxy");
+    pxsink.set_property("broadcast", true);
+
+    let mut consumers = Vec::new();
+    for idx in 0..2 {
+        let pipeline = gst::Pipeline::new(None);
+        let pxsrc =
+            gst::ElementFactory::make("ts-proxysrc", Some(&format!("proxysrc{}::test4", idx)))
+                .unwrap();
+        let appsink = gst::ElementFactory::make("appsink", None).unwrap();
+
+        pipeline.add_many(&[&pxsrc, &appsink]).unwrap();
+        pxsrc.link(&appsink).unwrap();
+
+        pxsrc.set_property("proxy-context", "proxy::test4_proxy");
+        pxsrc.set_property("context", "proxy::test");
+        pxsrc.set_property("broadcast", true);
+
+        let samples = Arc::new(Mutex::new(Vec::new()));
+
+        let appsink = appsink.dynamic_cast::<gst_app::AppSink>().unwrap();
+        let samples_clone = samples.clone();
+        appsink.set_callbacks(
+            gst_app::AppSinkCallbacks::builder()
+                .new_sample(move |appsink| {
+                    let sample = appsink.pull_sample().unwrap();
+
+                    samples_clone.lock().unwrap().push(sample);
+
+                    Ok(gst::FlowSuccess::Ok)
+                })
+                .build(),
+        );
+
+        // Subscribers must be running before the producer starts
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        consumers.push((pipeline, pxsrc, samples));
+    }
+
+    producer.set_state(gst::State::Playing).unwrap();
+
+    for (pipeline, pxsrc, samples) in consumers.iter() {
+        let mut eos = false;
+        let bus = pipeline.bus().unwrap();
+        while let Some(msg) = bus.timed_pop(5 * gst::ClockTime::SECOND) {
+            use gst::MessageView;
+            match msg.view() {
+                MessageView::Eos(..) => {
+                    eos = true;
+                    break;
+                }
+                MessageView::Error(err) => unreachable!("proxy::test_broadcast {:?}", err),
+                _ => (),
+            }
+        }
+
+        assert!(eos);
+        assert_eq!(samples.lock().unwrap().len(), 3);
+        assert_eq!(pxsrc.property::<u64>("dropped"), 0);
+    }
+
+    producer.set_state(gst::State::Null).unwrap();
+    for (pipeline, _, _) in consumers {
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+}
+
+#[test]
+fn test_broadcast_mode_mismatch() {
+    init();
+
+    let pxsink = gst::ElementFactory::make("ts-proxysink", Some("proxysink::test5")).unwrap();
+    let pxsrc = gst::ElementFactory::make("ts-proxysrc", Some("proxysrc::test5")).unwrap();
+
+    pxsink.set_property("proxy-context", "proxy::test5_proxy");
+    pxsink.set_property("broadcast", true);
+    pxsrc.set_property("proxy-context", "proxy::test5_proxy");
+    pxsrc.set_property("context", "proxy::test");
+
+    pxsink.set_state(gst::State::Ready).unwrap();
+    assert!(pxsrc.set_state(gst::State::Ready).is_err());
+
+    pxsink.set_state(gst::State::Null).unwrap();
+    pxsrc.set_state(gst::State::Null).unwrap();
+}
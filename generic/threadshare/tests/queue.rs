@@ -90,3 +90,121 @@ fn test_push() {
 
     pipeline.set_state(gst::State::Null).unwrap();
 }
+
+fn leaky_harness(leaky: &str) -> (gst_check::Harness, Arc<Mutex<u32>>) {
+    let queue = gst::ElementFactory::make("ts-queue", None).unwrap();
+    queue.set_property("context", format!("queue::test-leaky-{}", leaky));
+    queue.set_property("max-size-buffers", 2u32);
+    queue.set_property("max-size-bytes", 0u32);
+    queue.set_property("max-size-time", 0u64);
+    queue.set_property_from_str("leaky", leaky);
+
+    let overruns = Arc::new(Mutex::new(0));
+    let overruns_clone = overruns.clone();
+    queue.connect("overrun", false, move |_| {
+        *overruns_clone.lock().unwrap() += 1;
+        None
+    });
+
+    let mut h = gst_check::Harness::with_element(&queue, Some("sink"), Some("src"));
+    h.set_src_caps_str("foo/bar");
+    h.play();
+
+    (h, overruns)
+}
+
+/* Blocks the queue output on the first buffer, then pushes 5 more buffers
+ * while the queue can only hold 2 of them, with a serialized event after
+ * the second buffer */
+fn push_while_blocked(h: &mut gst_check::Harness) {
+    let (blocked_tx, blocked_rx) = std::sync::mpsc::sync_channel(1);
+    let src_pad = h.element().unwrap().static_pad("src").unwrap();
+    let probe_id = src_pad
+        .add_probe(
+            gst::PadProbeType::BLOCK | gst::PadProbeType::BUFFER,
+            move |_, _| {
+                let _ = blocked_tx.try_send(());
+                gst::PadProbeReturn::Ok
+            },
+        )
+        .unwrap();
+
+    for offset in 0..6u64 {
+        let mut buf = gst::Buffer::new();
+        buf.get_mut().unwrap().set_offset(offset);
+        assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+
+        if offset == 0 {
+            blocked_rx.recv().unwrap();
+        } else if offset == 1 {
+            let s = gst::Structure::new_empty("test-event");
+            assert!(h.push_event(gst::event::CustomDownstream::new(s)));
+        }
+    }
+
+    src_pad.remove_probe(probe_id);
+}
+
+/* Only buffers are leaked, the event pushed while blocked made it through */
+fn assert_event_kept(h: &mut gst_check::Harness) {
+    let mut got_event = false;
+    while let Some(event) = h.try_pull_event() {
+        if let gst::EventView::CustomDownstream(ev) = event.view() {
+            got_event |= ev.structure().map_or(false, |s| s.name() == "test-event");
+        }
+    }
+    assert!(got_event);
+}
+
+#[test]
+fn test_leaky_upstream() {
+    init();
+
+    let (mut h, overruns) = leaky_harness("upstream");
+    push_while_blocked(&mut h);
+
+    /* The new buffers were dropped */
+    for offset in [0, 1, 2] {
+        let buf = h.pull().unwrap();
+        assert_eq!(buf.offset(), offset);
+        assert!(!buf.flags().contains(gst::BufferFlags::DISCONT));
+    }
+
+    assert_eq!(*overruns.lock().unwrap(), 3);
+    assert_eq!(h.element().unwrap().property::<u64>("dropped"), 3);
+    assert_event_kept(&mut h);
+
+    /* The next buffer is flagged DISCONT */
+    assert_eq!(h.push(gst::Buffer::new()), Ok(gst::FlowSuccess::Ok));
+    let buf = h.pull().unwrap();
+    assert!(buf.flags().contains(gst::BufferFlags::DISCONT));
+
+    let _ = h.element().unwrap().set_state(gst::State::Null);
+}
+
+#[test]
+fn test_leaky_downstream() {
+    init();
+
+    let (mut h, overruns) = leaky_harness("downstream");
+    push_while_blocked(&mut h);
+
+    /* The old buffers were dropped */
+    let buf = h.pull().unwrap();
+    assert_eq!(buf.offset(), 0);
+    assert!(!buf.flags().contains(gst::BufferFlags::DISCONT));
+
+    let buf = h.pull().unwrap();
+    assert_eq!(buf.offset(), 4);
+    assert!(buf.flags().contains(gst::BufferFlags::DISCONT));
+
+    let buf = h.pull().unwrap();
+    assert_eq!(buf.offset(), 5);
+    assert!(!buf.flags().contains(gst::BufferFlags::DISCONT));
+
+    assert_eq!(*overruns.lock().unwrap(), 3);
+    assert_eq!(h.element().unwrap().property::<u64>("dropped"), 3);
+    assert_event_kept(&mut h);
+
+    let _ = h.element().unwrap().set_state(gst::State::Null);
+}
@@ -0,0 +1,342 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use gst::prelude::*;
+use gst_rtp::prelude::*;
+use gst_rtp::RTPBuffer;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const RTP_CAPS: &str = "application/x-rtp,media=audio,clock-rate=8000,encoding-name=PCMA,payload=8";
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstthreadshare::plugin_register_static().expect("gstthreadshare rtpsession test");
+    });
+}
+
+fn rtp_buffer(ssrc: u32, seq: u16, rtp_time: u32) -> gst::Buffer {
+    let mut buffer = gst::Buffer::new_rtp_with_sizes(160, 0, 0).unwrap();
+    {
+        let buffer = buffer.get_mut().unwrap();
+        let mut rtp = RTPBuffer::from_buffer_writable(buffer).unwrap();
+        rtp.set_ssrc(ssrc);
+        rtp.set_seq(seq);
+        rtp.set_timestamp(rtp_time);
+        rtp.set_payload_type(8);
+    }
+
+    buffer
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_be_bytes([data[0], data[1], data[2], data[3]])
+}
+
+type Received = Arc<Mutex<HashMap<String, Vec<gst::Buffer>>>>;
+
+/// Links the `recv_rtp_src_%u` pads to pads collecting the buffers by pad name.
+fn collect_recv_pads(session: &gst::Element) -> (Received, Arc<Mutex<Vec<gst::Pad>>>) {
+    let received = Received::default();
+    let sink_pads = Arc::new(Mutex::new(Vec::new()));
+
+    let received_clone = received.clone();
+    let sink_pads_clone = sink_pads.clone();
+    session.connect_pad_added(move |_, pad| {
+        let name = pad.name().to_string();
+        let received = received_clone.clone();
+        let sink_pad = gst::Pad::builder(Some("sink"), gst::PadDirection::Sink)
+            .chain_function(move |_, _, buffer| {
+                received
+                    .lock()
+                    .unwrap()
+                    .entry(name.clone())
+                    .or_default()
+                    .push(buffer);
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .build();
+        sink_pad.set_active(true).unwrap();
+        pad.link(&sink_pad).unwrap();
+        sink_pads_clone.lock().unwrap().push(sink_pad);
+    });
+
+    (received, sink_pads)
+}
+
+#[test]
+fn ssrc_demux() {
+    init();
+
+    let session = gst::ElementFactory::make("ts-rtpsession", None).unwrap();
+    session.set_property("context", "rtpsession-demux");
+
+    let new_ssrcs = Arc::new(Mutex::new(Vec::new()));
+    let new_ssrcs_clone = new_ssrcs.clone();
+    session.connect("on-new-ssrc", false, move |args| {
+        new_ssrcs_clone
+            .lock()
+            .unwrap()
+            .push(args[1].get::<u32>().unwrap());
+        None
+    });
+
+    let (received, _sink_pads) = collect_recv_pads(&session);
+
+    let mut h = gst_check::Harness::with_element(&session, Some("recv_rtp_sink"), None);
+    h.set_src_caps_str(RTP_CAPS);
+    h.play();
+
+    for seq in 0..3u16 {
+        assert_eq!(
+            h.push(rtp_buffer(0x1111, seq, seq as u32 * 160)),
+            Ok(gst::FlowSuccess::Ok)
+        );
+        assert_eq!(
+            h.push(rtp_buffer(0x2222, 100 + seq, seq as u32 * 160)),
+            Ok(gst::FlowSuccess::Ok)
+        );
+    }
+
+    assert_eq!(*new_ssrcs.lock().unwrap(), vec![0x1111, 0x2222]);
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 2);
+    for ssrc in [0x1111u32, 0x2222] {
+        let pad_name = format!("recv_rtp_src_{}", ssrc);
+        let buffers = received.get(&pad_name).unwrap();
+        assert_eq!(buffers.len(), 3);
+        for buffer in buffers {
+            assert_eq!(
+                RTPBuffer::from_buffer_readable(buffer).unwrap().ssrc(),
+                ssrc
+            );
+        }
+
+        let caps = session
+            .static_pad(&pad_name)
+            .unwrap()
+            .current_caps()
+            .unwrap();
+        let s = caps.structure(0).unwrap();
+        assert_eq!(s.name(), "application/x-rtp");
+        assert_eq!(s.get::<i32>("clock-rate").unwrap(), 8000);
+        assert_eq!(s.get::<u32>("ssrc").unwrap(), ssrc);
+    }
+
+    let stats = session.property::<gst::Structure>("stats");
+    let source_stats = stats.get::<gst::Array>("source-stats").unwrap();
+    assert_eq!(source_stats.len(), 2);
+    for s in source_stats.iter() {
+        let s = s.get::<gst::Structure>().unwrap();
+        assert_eq!(s.get::<u64>("packets-received").unwrap(), 3);
+        assert_eq!(s.get::<i64>("packets-lost").unwrap(), 0);
+        assert!(s.get::<bool>("is-validated").unwrap());
+    }
+
+    let _ = session.set_state(gst::State::Null);
+}
+
+#[test]
+fn sender_report() {
+    init();
+
+    let session = gst::ElementFactory::make("ts-rtpsession", None).unwrap();
+    session.set_property("context", "rtpsession-sr");
+    session.set_property("rtcp-interval", 50u32);
+    session.set_property("cname", "test@rtpsession");
+
+    let mut h =
+        gst_check::Harness::with_element(&session, Some("send_rtp_sink"), Some("send_rtp_src"));
+    let mut h_rtcp = gst_check::Harness::with_element(&session, None, Some("send_rtcp_src"));
+    h.set_src_caps_str(RTP_CAPS);
+    h.play();
+
+    for seq in 0..5u16 {
+        assert_eq!(
+            h.push(rtp_buffer(0x3333, seq, seq as u32 * 160)),
+            Ok(gst::FlowSuccess::Ok)
+        );
+    }
+    assert_eq!(h.buffers_received(), 5);
+
+    let mut got_sr = false;
+    for _ in 0..10 {
+        let buffer = h_rtcp.pull().unwrap();
+        let map = buffer.map_readable().unwrap();
+        let data = map.as_slice();
+
+        assert_eq!(data[0] >> 6, 2);
+        if data[1] != 200 {
+            // RR, no packets sent yet
+            continue;
+        }
+
+        // SR: ssrc, ntp time, rtp time, packet count, octet count
+        assert_eq!(read_u32(&data[4..]), 0x3333);
+        assert_eq!(read_u32(&data[20..]), 5);
+        assert_eq!(read_u32(&data[24..]), 5 * 160);
+
+        // Followed by the SDES
+        let sr_len = (u16::from_be_bytes([data[2], data[3]]) as usize + 1) * 4;
+        let sdes = &data[sr_len..];
+        assert_eq!(sdes[1], 202);
+        assert_eq!(read_u32(&sdes[4..]), 0x3333);
+        assert_eq!(sdes[8], 1);
+        assert_eq!(&sdes[10..10 + sdes[9] as usize], b"test@rtpsession");
+
+        got_sr = true;
+        break;
+    }
+    assert!(got_sr);
+
+    let stats = session.property::<gst::Structure>("stats");
+    assert_eq!(stats.get::<u32>("ssrc").unwrap(), 0x3333);
+    assert_eq!(stats.get::<u32>("packets-sent").unwrap(), 5);
+    assert!(stats.get::<u64>("rtcp-packets-sent").unwrap() > 0);
+
+    let _ = session.set_state(gst::State::Null);
+}
+
+#[test]
+fn receiver_report_and_ntp_sync() {
+    init();
+
+    const SSRC: u32 = 0x4444;
+    const NTP_SECS: u64 = 3_900_000_000;
+
+    let session = gst::ElementFactory::make("ts-rtpsession", None).unwrap();
+    session.set_property("context", "rtpsession-rr");
+    session.set_property("rtcp-interval", 50u32);
+    session.set_property("add-reference-timestamp-meta", true);
+
+    let (bye_sender, bye_receiver) = std::sync::mpsc::channel();
+    let bye_sender = Mutex::new(bye_sender);
+    session.connect("on-bye-ssrc", false, move |args| {
+        bye_sender
+            .lock()
+            .unwrap()
+            .send(args[1].get::<u32>().unwrap())
+            .unwrap();
+        None
+    });
+
+    let (received, _sink_pads) = collect_recv_pads(&session);
+
+    let mut h_rtp = gst_check::Harness::with_element(&session, Some("recv_rtp_sink"), None);
+    let mut h_rtcp_in = gst_check::Harness::with_element(&session, Some("recv_rtcp_sink"), None);
+    let mut h_rtcp_out = gst_check::Harness::with_element(&session, None, Some("send_rtcp_src"));
+    h_rtp.set_src_caps_str(RTP_CAPS);
+    h_rtcp_in.set_src_caps_str("application/x-rtcp");
+    h_rtp.play();
+
+    for seq in 0..3u16 {
+        assert_eq!(
+            h_rtp.push(rtp_buffer(SSRC, seq, seq as u32 * 160)),
+            Ok(gst::FlowSuccess::Ok)
+        );
+    }
+
+    // SR mapping RTP time 8000 to NTP_SECS
+    let mut sr = vec![0x80, 200, 0, 6];
+    sr.extend_from_slice(&SSRC.to_be_bytes());
+    sr.extend_from_slice(&(NTP_SECS << 32).to_be_bytes());
+    sr.extend_from_slice(&8000u32.to_be_bytes());
+    sr.extend_from_slice(&[0; 8]);
+    assert_eq!(
+        h_rtcp_in.push(gst::Buffer::from_slice(sr)),
+        Ok(gst::FlowSuccess::Ok)
+    );
+
+    // One second after the SR
+    assert_eq!(
+        h_rtp.push(rtp_buffer(SSRC, 3, 16_000)),
+        Ok(gst::FlowSuccess::Ok)
+    );
+
+    {
+        let received = received.lock().unwrap();
+        let buffers = received.get(&format!("recv_rtp_src_{}", SSRC)).unwrap();
+        assert_eq!(buffers.len(), 4);
+        assert!(buffers[0].meta::<gst::ReferenceTimestampMeta>().is_none());
+
+        let meta = buffers[3].meta::<gst::ReferenceTimestampMeta>().unwrap();
+        assert_eq!(
+            meta.reference().structure(0).unwrap().name(),
+            "timestamp/x-ntp"
+        );
+        assert_eq!(meta.timestamp(), gst::ClockTime::from_seconds(NTP_SECS + 1));
+    }
+
+    // Look for a report block for SSRC echoing the SR
+    let mut got_rr = false;
+    for seq in 4..20u16 {
+        // Make sure the source is reported
+        assert_eq!(
+            h_rtp.push(rtp_buffer(SSRC, seq, seq as u32 * 160)),
+            Ok(gst::FlowSuccess::Ok)
+        );
+
+        let buffer = h_rtcp_out.pull().unwrap();
+        let map = buffer.map_readable().unwrap();
+        let data = map.as_slice();
+
+        assert_eq!(data[1], 201);
+        if data[0] & 0x1f == 0 {
+            continue;
+        }
+
+        // Report block: ssrc, lost, ext. highest seq, jitter, LSR, DLSR
+        let block = &data[8..];
+        assert_eq!(read_u32(block), SSRC);
+        if read_u32(&block[16..]) == 0 {
+            // Reported before the SR was received
+            continue;
+        }
+        assert_eq!(read_u32(&block[4..]) & 0x00ff_ffff, 0);
+        assert!(read_u32(&block[8..]) >= 4);
+        assert_eq!(read_u32(&block[16..]), ((NTP_SECS & 0xffff) << 16) as u32);
+
+        got_rr = true;
+        break;
+    }
+    assert!(got_rr);
+
+    // RR followed by a BYE
+    let mut bye = vec![0x80, 201, 0, 1];
+    bye.extend_from_slice(&SSRC.to_be_bytes());
+    bye.extend_from_slice(&[0x81, 203, 0, 1]);
+    bye.extend_from_slice(&SSRC.to_be_bytes());
+    assert_eq!(
+        h_rtcp_in.push(gst::Buffer::from_slice(bye)),
+        Ok(gst::FlowSuccess::Ok)
+    );
+    assert_eq!(bye_receiver.recv().unwrap(), SSRC);
+
+    let stats = session.property::<gst::Structure>("stats");
+    assert!(stats.get::<u64>("rtcp-packets-received").unwrap() >= 2);
+    assert!(stats.get::<gst::Array>("source-stats").unwrap().is_empty());
+
+    let _ = session.set_state(gst::State::Null);
+}
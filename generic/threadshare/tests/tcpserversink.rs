@@ -0,0 +1,126 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use gst::prelude::*;
+
+use std::io::Read;
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::thread;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstthreadshare::plugin_register_static().expect("gstthreadshare tcpserversink test");
+    });
+}
+
+#[test]
+fn test_fan_out() {
+    init();
+
+    let pipeline = gst::Pipeline::new(None);
+
+    let appsrc = gst::ElementFactory::make("appsrc", None).unwrap();
+    let tcpserversink = gst::ElementFactory::make("ts-tcpserversink", None).unwrap();
+    pipeline.add_many(&[&appsrc, &tcpserversink]).unwrap();
+    appsrc.link(&tcpserversink).unwrap();
+
+    appsrc.set_property("format", gst::Format::Time);
+    tcpserversink.set_property("sync", false);
+    tcpserversink.set_property("host", "127.0.0.1");
+    tcpserversink.set_property("port", 0i32);
+
+    let (added_tx, added_rx) = mpsc::channel();
+    tcpserversink.connect("client-added", false, move |args| {
+        let host = args[1].get::<String>().unwrap();
+        let port = args[2].get::<i32>().unwrap();
+        added_tx.send((host, port)).unwrap();
+        None
+    });
+
+    let (removed_tx, removed_rx) = mpsc::channel();
+    tcpserversink.connect("client-removed", false, move |args| {
+        let host = args[1].get::<String>().unwrap();
+        let port = args[2].get::<i32>().unwrap();
+        removed_tx.send((host, port)).unwrap();
+        None
+    });
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+    let port = tcpserversink.property::<i32>("current-port");
+    assert_ne!(port, 0);
+
+    let clients: Vec<_> = (0..2)
+        .map(|_| TcpStream::connect(("127.0.0.1", port as u16)).unwrap())
+        .collect();
+
+    let mut added = Vec::new();
+    for _ in 0..2 {
+        let (host, port) = added_rx
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(host, "127.0.0.1");
+        added.push(port);
+    }
+    for client in clients.iter() {
+        assert!(added.contains(&(client.local_addr().unwrap().port() as i32)));
+    }
+    assert_eq!(tcpserversink.property::<u32>("num-clients"), 2);
+
+    let appsrc = appsrc.dynamic_cast::<gst_app::AppSrc>().unwrap();
+    for idx in 0..3u8 {
+        let mut buffer = gst::Buffer::from_slice(vec![idx; 160]);
+        buffer
+            .get_mut()
+            .unwrap()
+            .set_pts(idx as u64 * gst::ClockTime::MSECOND);
+        appsrc.push_buffer(buffer).unwrap();
+    }
+
+    let readers: Vec<_> = clients
+        .into_iter()
+        .map(|mut client| {
+            thread::spawn(move || {
+                let mut data = vec![0; 3 * 160];
+                client.read_exact(&mut data).unwrap();
+                data
+            })
+        })
+        .collect();
+
+    for reader in readers {
+        let data = reader.join().unwrap();
+        for (idx, chunk) in data.chunks(160).enumerate() {
+            assert!(chunk.iter().all(|byte| *byte == idx as u8));
+        }
+    }
+
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    for _ in 0..2 {
+        let (_host, port) = removed_rx
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        assert!(added.contains(&port));
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use gst::prelude::*;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::{Arc, Barrier, Mutex};
+use std::{thread, time};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstthreadshare::plugin_register_static().expect("gstthreadshare tcpserversrc test");
+    });
+}
+
+fn build_pipeline(
+    client_policy: &str,
+) -> (gst::Pipeline, gst::Element, Arc<Mutex<Vec<gst::Sample>>>) {
+    let pipeline = gst::Pipeline::new(None);
+
+    let tcpserversrc = gst::ElementFactory::make("ts-tcpserversrc", None).unwrap();
+    let appsink = gst::ElementFactory::make("appsink", None).unwrap();
+    appsink.set_property("sync", false);
+    appsink.set_property("async", false);
+
+    pipeline.add_many(&[&tcpserversrc, &appsink]).unwrap();
+    tcpserversrc.link(&appsink).unwrap();
+
+    tcpserversrc.set_property("caps", &gst::Caps::builder("foo/bar").build());
+    tcpserversrc.set_property("host", "127.0.0.1");
+    tcpserversrc.set_property("port", 0i32);
+    tcpserversrc.set_property_from_str("client-policy", client_policy);
+
+    let samples = Arc::new(Mutex::new(Vec::new()));
+
+    let appsink = appsink.dynamic_cast::<gst_app::AppSink>().unwrap();
+    let samples_clone = samples.clone();
+    appsink.set_callbacks(
+        gst_app::AppSinkCallbacks::builder()
+            .new_sample(move |appsink| {
+                let sample = appsink.pull_sample().unwrap();
+
+                let mut samples = samples_clone.lock().unwrap();
+                samples.push(sample);
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .build(),
+    );
+
+    (pipeline, tcpserversrc, samples)
+}
+
+fn total_size(samples: &[gst::Sample]) -> usize {
+    samples
+        .iter()
+        .fold(0, |acc, sample| acc + sample.buffer().unwrap().size())
+}
+
+#[test]
+fn test_single_client() {
+    init();
+
+    let (pipeline, tcpserversrc, samples) = build_pipeline("single");
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+    let port = tcpserversrc.property::<i32>("current-port");
+    assert_ne!(port, 0);
+
+    let handler = thread::spawn(move || {
+        let mut socket = TcpStream::connect(("127.0.0.1", port as u16)).unwrap();
+        let buffer = [0; 160];
+        for _ in 0..3 {
+            socket.write_all(&buffer).unwrap();
+            thread::sleep(time::Duration::from_millis(20));
+        }
+    });
+
+    let mut eos = false;
+    let bus = pipeline.bus().unwrap();
+    while let Some(msg) = bus.timed_pop(5 * gst::ClockTime::SECOND) {
+        use gst::MessageView;
+        match msg.view() {
+            MessageView::Eos(..) => {
+                eos = true;
+                break;
+            }
+            MessageView::Error(err) => panic!("{:?}", err),
+            _ => (),
+        }
+    }
+
+    assert!(eos);
+    let samples = samples.lock().unwrap();
+    let caps = gst::Caps::builder("foo/bar").build();
+    for sample in samples.iter() {
+        assert_eq!(Some(caps.as_ref()), sample.caps());
+    }
+    assert_eq!(total_size(&samples), 3 * 160);
+
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    handler.join().unwrap();
+}
+
+#[test]
+fn test_per_connection() {
+    init();
+
+    let (pipeline, tcpserversrc, samples) = build_pipeline("per-connection");
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+    let port = tcpserversrc.property::<i32>("current-port");
+    assert_ne!(port, 0);
+
+    // Both clients are connected before any of them sends data, and then
+    // send their chunks alternately
+    let barrier = Arc::new(Barrier::new(2));
+    let handlers: Vec<_> = (0..2u8)
+        .map(|idx| {
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let mut socket = TcpStream::connect(("127.0.0.1", port as u16)).unwrap();
+                let client_addr = socket.local_addr().unwrap();
+                barrier.wait();
+
+                let buffer = [idx; 160];
+                thread::sleep(time::Duration::from_millis(10 * idx as u64));
+                for _ in 0..3 {
+                    socket.write_all(&buffer).unwrap();
+                    thread::sleep(time::Duration::from_millis(20));
+                }
+
+                (client_addr, idx)
+            })
+        })
+        .collect();
+
+    let clients: HashMap<SocketAddr, u8> = handlers
+        .into_iter()
+        .map(|handler| handler.join().unwrap())
+        .collect();
+
+    // Clients disconnecting must not end the stream
+    let mut received = 0;
+    for _ in 0..50 {
+        received = total_size(&samples.lock().unwrap());
+        if received == 2 * 3 * 160 {
+            break;
+        }
+        thread::sleep(time::Duration::from_millis(100));
+    }
+    assert_eq!(received, 2 * 3 * 160);
+
+    let bus = pipeline.bus().unwrap();
+    while let Some(msg) = bus.pop() {
+        use gst::MessageView;
+        match msg.view() {
+            MessageView::Eos(..) => panic!("unexpected EOS"),
+            MessageView::Error(err) => panic!("{:?}", err),
+            _ => (),
+        }
+    }
+
+    // Each connection is pushed as a whole, starting with a discontinuity, and
+    // each buffer identifies the client it comes from
+    let mut served = Vec::new();
+    for sample in samples.lock().unwrap().iter() {
+        let buffer = sample.buffer().unwrap();
+
+        let meta = buffer.meta::<gst_net::NetAddressMeta>().unwrap();
+        let saddr = SocketAddr::from(meta.addr().downcast::<gio::InetSocketAddress>().unwrap());
+        let idx = clients[&saddr];
+
+        let map = buffer.map_readable().unwrap();
+        assert!(map.iter().all(|byte| *byte == idx));
+
+        if served.last() != Some(&idx) {
+            assert!(buffer.flags().contains(gst::BufferFlags::DISCONT));
+            assert!(!served.contains(&idx));
+            served.push(idx);
+        } else {
+            assert!(!buffer.flags().contains(gst::BufferFlags::DISCONT));
+        }
+    }
+    assert_eq!(served.len(), 2);
+
+    pipeline.set_state(gst::State::Null).unwrap();
+}
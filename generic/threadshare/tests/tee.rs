@@ -0,0 +1,201 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Library General Public
+// License as published by the Free Software Foundation; either
+// version 2 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Library General Public License for more details.
+//
+// You should have received a copy of the GNU Library General Public
+// License along with this library; if not, write to the
+// Free Software Foundation, Inc., 51 Franklin Street, Suite 500,
+// Boston, MA 02110-1335, USA.
+//
+// SPDX-License-Identifier: LGPL-2.1-or-later
+
+use gst::prelude::*;
+
+use std::sync::mpsc;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstthreadshare::plugin_register_static().expect("gstthreadshare tee test");
+    });
+}
+
+fn branch_stats(tee: &gst::Element) -> Vec<(String, u64, u64)> {
+    let stats = tee.property::<gst::Structure>("stats");
+    stats
+        .get::<gst::Array>("branch-stats")
+        .unwrap()
+        .iter()
+        .map(|s| {
+            let s = s.get::<gst::Structure>().unwrap();
+            (
+                s.get::<String>("pad").unwrap(),
+                s.get::<u64>("pushed").unwrap(),
+                s.get::<u64>("dropped").unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_fan_out() {
+    init();
+
+    let tee = gst::ElementFactory::make("ts-tee", None).unwrap();
+    tee.set_property("context", "tee::test");
+
+    let mut h1 = gst_check::Harness::with_element(&tee, Some("sink"), Some("src_%u"));
+    let mut h2 = gst_check::Harness::with_element(&tee, None, Some("src_%u"));
+
+    h1.set_src_caps_str("foo/bar");
+    h1.play();
+    h2.play();
+
+    for idx in 0..3u64 {
+        let mut buf = gst::Buffer::new();
+        buf.get_mut().unwrap().set_offset(idx);
+        assert_eq!(h1.push(buf), Ok(gst::FlowSuccess::Ok));
+    }
+
+    for h in [&mut h1, &mut h2] {
+        for idx in 0..3u64 {
+            let buf = h.pull().unwrap();
+            assert_eq!(buf.offset(), idx);
+        }
+
+        let event = h.pull_event().unwrap();
+        assert_eq!(event.type_(), gst::EventType::StreamStart);
+        let event = h.pull_event().unwrap();
+        assert_eq!(event.type_(), gst::EventType::Caps);
+        let event = h.pull_event().unwrap();
+        assert_eq!(event.type_(), gst::EventType::Segment);
+    }
+
+    let stats = branch_stats(&tee);
+    assert_eq!(
+        stats.iter().map(|s| s.0.as_str()).collect::<Vec<_>>(),
+        vec!["src_0", "src_1"]
+    );
+    assert!(stats.iter().all(|s| s.2 == 0));
+
+    /* A branch requested while streaming starts with the current sticky events */
+    let mut h3 = gst_check::Harness::with_element(&tee, None, Some("src_%u"));
+    h3.play();
+
+    assert_eq!(h1.push(gst::Buffer::new()), Ok(gst::FlowSuccess::Ok));
+    let _ = h3.pull().unwrap();
+
+    let event = h3.pull_event().unwrap();
+    assert_eq!(event.type_(), gst::EventType::StreamStart);
+    let event = h3.pull_event().unwrap();
+    assert_eq!(event.type_(), gst::EventType::Caps);
+    let event = h3.pull_event().unwrap();
+    assert_eq!(event.type_(), gst::EventType::Segment);
+
+    let _ = tee.set_state(gst::State::Null);
+}
+
+#[test]
+fn test_leaky_branch() {
+    init();
+
+    let tee = gst::ElementFactory::make("ts-tee", None).unwrap();
+    tee.set_property("context", "tee::test-leaky");
+    tee.set_property("max-size-buffers", 2u32);
+    tee.set_property("max-size-bytes", 0u32);
+    tee.set_property("max-size-time", 0u64);
+
+    let mut h1 = gst_check::Harness::with_element(&tee, Some("sink"), Some("src_%u"));
+    let mut h2 = gst_check::Harness::with_element(&tee, None, Some("src_%u"));
+
+    h1.set_src_caps_str("foo/bar");
+    h1.play();
+    h2.play();
+
+    /* Block the second branch on its first buffer */
+    let (blocked_tx, blocked_rx) = mpsc::sync_channel(1);
+    let src_1 = h2.sinkpad().unwrap().peer().unwrap();
+    let probe_id = src_1
+        .add_probe(
+            gst::PadProbeType::BLOCK | gst::PadProbeType::BUFFER,
+            move |_, _| {
+                let _ = blocked_tx.try_send(());
+                gst::PadProbeReturn::Ok
+            },
+        )
+        .unwrap();
+
+    assert_eq!(h1.push(gst::Buffer::new()), Ok(gst::FlowSuccess::Ok));
+    blocked_rx.recv().unwrap();
+
+    /* The blocked branch can only queue 2 buffers, the others are dropped.
+     * Upstream is never blocked. */
+    for _ in 0..5 {
+        assert_eq!(h1.push(gst::Buffer::new()), Ok(gst::FlowSuccess::Ok));
+    }
+
+    let stats = branch_stats(&tee);
+    assert_eq!(stats[1].0, "src_1");
+    assert_eq!(stats[1].2, 3);
+
+    src_1.remove_probe(probe_id);
+
+    for _ in 0..3 {
+        let _ = h2.pull().unwrap();
+    }
+
+    /* The branch is back to normal once unblocked */
+    assert_eq!(h1.push(gst::Buffer::new()), Ok(gst::FlowSuccess::Ok));
+    let _ = h2.pull().unwrap();
+    assert_eq!(branch_stats(&tee)[1].2, 3);
+
+    let _ = tee.set_state(gst::State::Null);
+}
+
+#[test]
+fn test_request_pad_while_playing() {
+    init();
+
+    let tee = gst::ElementFactory::make("ts-tee", None).unwrap();
+    tee.set_property("context", "tee::test-request");
+
+    /* pad-added handlers can use the element while the pad is being added */
+    let (added_tx, added_rx) = mpsc::channel();
+    tee.connect_pad_added(move |tee, pad| {
+        let stats = branch_stats(tee);
+        added_tx
+            .send((pad.name().to_string(), stats.len()))
+            .unwrap();
+    });
+
+    let mut h1 = gst_check::Harness::with_element(&tee, Some("sink"), Some("src_%u"));
+    h1.set_src_caps_str("foo/bar");
+    h1.play();
+    assert_eq!(added_rx.recv().unwrap(), ("src_0".to_string(), 0));
+
+    assert_eq!(h1.push(gst::Buffer::new()), Ok(gst::FlowSuccess::Ok));
+    let _ = h1.pull().unwrap();
+
+    let mut h2 = gst_check::Harness::with_element(&tee, None, Some("src_%u"));
+    h2.play();
+    assert_eq!(added_rx.recv().unwrap(), ("src_1".to_string(), 1));
+
+    assert_eq!(h1.push(gst::Buffer::new()), Ok(gst::FlowSuccess::Ok));
+    let _ = h1.pull().unwrap();
+    let _ = h2.pull().unwrap();
+
+    assert_eq!(branch_stats(&tee).len(), 2);
+
+    let _ = tee.set_state(gst::State::Null);
+}
@@ -103,3 +103,211 @@ fn test_chain() {
     let buf = gst::Buffer::from_slice(&[42, 43, 44, 45]);
     assert!(h.push(buf) == Ok(gst::FlowSuccess::Ok));
 }
+
+#[test]
+fn test_multicast_properties() {
+    init();
+
+    let h = gst_check::Harness::new("ts-udpsink");
+    let udpsink = h.element().unwrap();
+
+    assert_eq!(udpsink.property::<Option<String>>("multicast-iface"), None);
+    assert_eq!(udpsink.property::<Option<String>>("multicast-source"), None);
+    assert!(udpsink.property::<bool>("loop"));
+
+    udpsink.set_property("multicast-iface", "eth0");
+    udpsink.set_property("multicast-source", "-192.168.1.10");
+    udpsink.set_property("loop", false);
+
+    assert_eq!(
+        udpsink.property::<Option<String>>("multicast-iface"),
+        Some("eth0".to_string())
+    );
+    assert_eq!(
+        udpsink.property::<Option<String>>("multicast-source"),
+        Some("-192.168.1.10".to_string())
+    );
+    assert!(!udpsink.property::<bool>("loop"));
+}
+
+// Sends a few buffers to a multicast group joined by a ts-udpsrc on the same
+// host and returns whether they looped back to it
+#[cfg(target_os = "linux")]
+fn multicast_loopback(multicast_loop: bool, port: i32) -> bool {
+    init();
+
+    const GROUP: &str = "239.255.42.42";
+
+    let mut h_src = gst_check::Harness::new("ts-udpsrc");
+    {
+        let udpsrc = h_src.element().unwrap();
+        udpsrc.set_property("address", GROUP);
+        udpsrc.set_property("port", port);
+        udpsrc.set_property("context", "test-multicast-loopback");
+    }
+    h_src.play();
+
+    let mut h_sink = gst_check::Harness::new("ts-udpsink");
+    {
+        let udpsink = h_sink.element().unwrap();
+        udpsink.set_property("clients", format!("{}:{}", GROUP, port));
+        udpsink.set_property("loop", multicast_loop);
+        udpsink.set_property("context", "test-multicast-loopback");
+    }
+    h_sink.set_src_caps_str("foo/bar");
+
+    // Let the udpsrc join the group before sending
+    thread::sleep(std::time::Duration::from_millis(50));
+
+    for _ in 0..3 {
+        let buf = gst::Buffer::from_slice([42u8; 100]);
+        assert_eq!(h_sink.push(buf), Ok(gst::FlowSuccess::Ok));
+    }
+
+    thread::sleep(std::time::Duration::from_millis(200));
+
+    match h_src.try_pull() {
+        Some(buffer) => {
+            assert_eq!(buffer.size(), 100);
+            true
+        }
+        None => false,
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_multicast_loop() {
+    assert!(multicast_loopback(true, 5020));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_multicast_no_loop() {
+    assert!(!multicast_loopback(false, 5021));
+}
+
+#[test]
+fn test_sync_list() {
+    init();
+
+    const PORT: u16 = 5022;
+    const SPACING: gst::ClockTime = gst::ClockTime::from_mseconds(100);
+
+    let socket = std::net::UdpSocket::bind(("127.0.0.1", PORT)).unwrap();
+    socket
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+
+    let mut h = gst_check::Harness::new("ts-udpsink");
+    h.use_systemclock();
+    {
+        let udpsink = h.element().unwrap();
+        udpsink.set_property("clients", format!("127.0.0.1:{}", PORT));
+        udpsink.set_property("sync", true);
+        udpsink.set_property("context", "test-sync-list");
+    }
+    h.play();
+    h.set_src_caps_str("foo/bar");
+
+    let udpsink = h.element().unwrap();
+    assert!(udpsink.send_event(gst::event::Latency::new(gst::ClockTime::ZERO)));
+
+    let base = udpsink.current_running_time().unwrap();
+
+    let mut list = gst::BufferList::new();
+    {
+        let list = list.get_mut().unwrap();
+        for i in 0..3u8 {
+            let mut buffer = gst::Buffer::from_slice([i; 4]);
+            buffer
+                .get_mut()
+                .unwrap()
+                .set_pts(base + (i as u64 + 1) * SPACING);
+            list.add(buffer);
+        }
+    }
+    assert_eq!(
+        h.srcpad().unwrap().push_list(list),
+        Ok(gst::FlowSuccess::Ok)
+    );
+
+    // Each buffer of the list is only sent once its running time is reached
+    let mut buf = [0; 5];
+    for i in 0..3u8 {
+        let (amt, _) = socket.recv_from(&mut buf).unwrap();
+        assert_eq!(amt, 4);
+        assert_eq!(buf[..4], [i; 4]);
+
+        let now = udpsink.current_running_time().unwrap();
+        assert!(now >= base + (i as u64 + 1) * SPACING);
+    }
+}
+
+// Sends a list with a batched ts-udpsink to a batched ts-udpsrc
+#[cfg(target_os = "linux")]
+fn batch_round_trip(batch_size: u32, gso: bool, port: i32, context: &str) {
+    init();
+
+    let mut h_src = gst_check::Harness::new("ts-udpsrc");
+    {
+        let udpsrc = h_src.element().unwrap();
+        udpsrc.set_property("port", port);
+        udpsrc.set_property("batch-size", batch_size);
+        udpsrc.set_property("gro", gso);
+        udpsrc.set_property("context", context);
+    }
+    h_src.play();
+
+    let mut h_sink = gst_check::Harness::new("ts-udpsink");
+    {
+        let udpsink = h_sink.element().unwrap();
+        udpsink.set_property("clients", format!("127.0.0.1:{}", port));
+        udpsink.set_property("batch-size", batch_size);
+        udpsink.set_property("gso", gso);
+        udpsink.set_property("context", context);
+    }
+    h_sink.set_src_caps_str("foo/bar");
+
+    // Let the udpsrc be ready to receive data
+    thread::sleep(std::time::Duration::from_millis(50));
+
+    // All buffers have the same size but the last one, so that GSO can
+    // send them together
+    let size = |i: u8| if i == 19 { 400 } else { 1000 };
+
+    let mut list = gst::BufferList::new();
+    {
+        let list = list.get_mut().unwrap();
+        for i in 0..20u8 {
+            list.add(gst::Buffer::from_slice(vec![i; size(i)]));
+        }
+    }
+    assert_eq!(
+        h_sink.srcpad().unwrap().push_list(list),
+        Ok(gst::FlowSuccess::Ok)
+    );
+
+    for i in 0..20u8 {
+        let buffer = h_src.pull().unwrap();
+        assert_eq!(buffer.size(), size(i));
+
+        let map = buffer.map_readable().unwrap();
+        assert!(map.iter().all(|b| *b == i));
+
+        // Segments split from GRO coalesced datagrams keep the timestamps
+        assert!(buffer.dts().is_some());
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_batch_round_trip() {
+    batch_round_trip(8, false, 5023, "test-batch-round-trip");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_gso_round_trip() {
+    batch_round_trip(8, true, 5024, "test-gso-round-trip");
+}
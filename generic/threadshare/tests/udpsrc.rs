@@ -19,6 +19,8 @@
 
 use gst::prelude::*;
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::thread;
 
 fn init() {
@@ -97,6 +99,72 @@ fn test_push() {
     assert!(n_events >= 2);
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_push_batch() {
+    init();
+
+    let mut h = gst_check::Harness::new("ts-udpsrc");
+
+    // Count the buffers pushed in lists
+    let n_lists = Arc::new(AtomicUsize::new(0));
+    let n_list_buffers = Arc::new(AtomicUsize::new(0));
+
+    {
+        let udpsrc = h.element().unwrap();
+        udpsrc.set_property("port", 5002i32);
+        udpsrc.set_property("batch-size", 8u32);
+        udpsrc.set_property("context", "test-push-batch");
+
+        let n_lists = n_lists.clone();
+        let n_list_buffers = n_list_buffers.clone();
+        udpsrc.static_pad("src").unwrap().add_probe(
+            gst::PadProbeType::BUFFER_LIST,
+            move |_pad, info| {
+                if let Some(gst::PadProbeData::BufferList(ref list)) = info.data {
+                    n_lists.fetch_add(1, Ordering::SeqCst);
+                    n_list_buffers.fetch_add(list.len(), Ordering::SeqCst);
+                }
+                gst::PadProbeReturn::Ok
+            },
+        );
+    }
+
+    h.play();
+
+    thread::spawn(move || {
+        use std::net;
+        use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+        use std::time;
+
+        // Sleep 50ms to allow for the udpsrc to be ready to actually receive data
+        thread::sleep(time::Duration::from_millis(50));
+
+        let socket = net::UdpSocket::bind("0.0.0.0:0").unwrap();
+
+        let ipaddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let dest = SocketAddr::new(ipaddr, 5002u16);
+
+        for i in 0..20u8 {
+            let buffer = vec![i; 100 + i as usize];
+            socket.send_to(&buffer, dest).unwrap();
+        }
+    });
+
+    for i in 0..20u8 {
+        let buffer = h.pull().unwrap();
+        assert_eq!(buffer.size(), 100 + i as usize);
+
+        let map = buffer.map_readable().unwrap();
+        assert!(map.iter().all(|b| *b == i));
+        assert!(buffer.dts().is_some());
+    }
+
+    // All the buffers were pushed in lists of at most batch-size buffers
+    assert!(n_lists.load(Ordering::SeqCst) >= 3);
+    assert_eq!(n_list_buffers.load(Ordering::SeqCst), 20);
+}
+
 #[test]
 #[cfg(not(windows))]
 fn test_socket_reuse() {
@@ -159,3 +227,29 @@ fn test_socket_reuse() {
         assert_eq!(buffer.size(), 160);
     }
 }
+
+#[test]
+fn test_multicast_properties() {
+    init();
+
+    let h = gst_check::Harness::new("ts-udpsrc");
+    let udpsrc = h.element().unwrap();
+
+    assert_eq!(udpsrc.property::<Option<String>>("multicast-iface"), None);
+    assert_eq!(udpsrc.property::<Option<String>>("multicast-source"), None);
+    assert!(udpsrc.property::<bool>("loop"));
+
+    udpsrc.set_property("multicast-iface", "eth0,eth1");
+    udpsrc.set_property("multicast-source", "+192.168.1.10+192.168.1.11");
+    udpsrc.set_property("loop", false);
+
+    assert_eq!(
+        udpsrc.property::<Option<String>>("multicast-iface"),
+        Some("eth0,eth1".to_string())
+    );
+    assert_eq!(
+        udpsrc.property::<Option<String>>("multicast-source"),
+        Some("+192.168.1.10+192.168.1.11".to_string())
+    );
+    assert!(!udpsrc.property::<bool>("loop"));
+}
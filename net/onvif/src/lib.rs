@@ -17,6 +17,7 @@ use once_cell::sync::Lazy;
 
 mod onvifmetadatacombiner;
 mod onvifmetadatadepay;
+mod onvifmetadatagenerator;
 mod onvifmetadataoverlay;
 mod onvifmetadataparse;
 mod onvifmetadatapay;
@@ -43,6 +44,42 @@ pub(crate) fn lookup_reference_timestamp(buffer: &gst::Buffer) -> Option<gst::Cl
     None
 }
 
+pub(crate) fn utc_time_to_pts(
+    segment: &gst::FormattedSegment<gst::ClockTime>,
+    utc_time_running_time_mapping: (gst::ClockTime, gst::Signed<gst::ClockTime>),
+    utc_time: gst::ClockTime,
+) -> Option<gst::ClockTime> {
+    let running_time =
+        utc_time_to_running_time(utc_time_running_time_mapping, utc_time)?.positive()?;
+    segment.position_from_running_time(running_time)
+}
+
+pub(crate) fn utc_time_to_running_time(
+    utc_time_running_time_mapping: (gst::ClockTime, gst::Signed<gst::ClockTime>),
+    utc_time: gst::ClockTime,
+) -> Option<gst::Signed<gst::ClockTime>> {
+    if utc_time < utc_time_running_time_mapping.0 {
+        let diff = utc_time_running_time_mapping.0 - utc_time;
+        utc_time_running_time_mapping.1.checked_sub_unsigned(diff)
+    } else {
+        let diff = utc_time - utc_time_running_time_mapping.0;
+        utc_time_running_time_mapping.1.checked_add_unsigned(diff)
+    }
+}
+
+pub(crate) fn running_time_to_utc_time(
+    utc_time_running_time_mapping: (gst::ClockTime, gst::Signed<gst::ClockTime>),
+    running_time: gst::Signed<gst::ClockTime>,
+) -> Option<gst::ClockTime> {
+    let diff = running_time.checked_sub(utc_time_running_time_mapping.1)?;
+
+    use gst::Signed::*;
+    match diff {
+        Positive(diff) => utc_time_running_time_mapping.0.checked_add(diff),
+        Negative(diff) => utc_time_running_time_mapping.0.checked_sub(diff),
+    }
+}
+
 pub(crate) fn xml_from_buffer(buffer: &gst::Buffer) -> Result<minidom::Element, gst::ErrorMessage> {
     let map = buffer.map_readable().map_err(|_| {
         gst::error_msg!(gst::ResourceError::Read, ["Failed to map buffer readable"])
@@ -111,6 +148,7 @@ fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     onvifmetadatacombiner::register(plugin)?;
     onvifmetadataoverlay::register(plugin)?;
     onvifmetadataparse::register(plugin)?;
+    onvifmetadatagenerator::register(plugin)?;
 
     gst::meta::CustomMeta::register("OnvifXMLFrameMeta", &[]);
 
@@ -0,0 +1,498 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+use std::sync::Mutex;
+
+use chrono::{Datelike, Timelike};
+use minidom::Element;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "onvifmetadatagenerator",
+        gst::DebugColorFlags::empty(),
+        Some("ONVIF metadata generator element"),
+    )
+});
+
+const ONVIF_SCHEMA_NS: &str = "http://www.onvif.org/ver10/schema";
+
+// Name of the custom downstream events applications can send to describe
+// an object detected in the next buffer
+const OBJECT_EVENT_NAME: &str = "OnvifMetadataObject";
+
+// Object description in ONVIF coordinates, (-1, -1) is bottom left and
+// (1, 1) is top right
+#[derive(Debug, Clone)]
+struct Object {
+    id: String,
+    left: f64,
+    top: f64,
+    right: f64,
+    bottom: f64,
+    class: Option<String>,
+    likelihood: Option<f64>,
+}
+
+impl Object {
+    fn from_structure(s: &gst::StructureRef) -> Option<Object> {
+        let id = s
+            .get::<String>("object-id")
+            .ok()
+            .or_else(|| s.get::<u32>("object-id").ok().map(|id| id.to_string()))?;
+
+        Some(Object {
+            id,
+            left: s.get::<f64>("left").ok()?,
+            top: s.get::<f64>("top").ok()?,
+            right: s.get::<f64>("right").ok()?,
+            bottom: s.get::<f64>("bottom").ok()?,
+            class: s.get::<Option<String>>("class").ok().flatten(),
+            likelihood: s.get::<f64>("likelihood").ok(),
+        })
+    }
+
+    fn from_roi_meta(
+        meta: &gst_video::VideoRegionOfInterestMeta,
+        video_info: &gst_video::VideoInfo,
+    ) -> Object {
+        let width = video_info.width() as f64;
+        let height = video_info.height() as f64;
+        let (x, y, w, h) = meta.rect();
+
+        let likelihood = meta
+            .params()
+            .find(|s| s.name() == "detection")
+            .and_then(|s| s.get::<f64>("confidence").ok());
+
+        Object {
+            id: meta.id().to_string(),
+            left: 2.0 * x as f64 / width - 1.0,
+            top: 1.0 - 2.0 * y as f64 / height,
+            right: 2.0 * (x + w) as f64 / width - 1.0,
+            bottom: 1.0 - 2.0 * (y + h) as f64 / height,
+            class: Some(meta.roi_type().to_string()),
+            likelihood,
+        }
+    }
+
+    fn to_xml(&self) -> Element {
+        let bbox = Element::builder("BoundingBox", ONVIF_SCHEMA_NS)
+            .attr("left", self.left.to_string())
+            .attr("top", self.top.to_string())
+            .attr("right", self.right.to_string())
+            .attr("bottom", self.bottom.to_string())
+            .build();
+
+        let center_of_gravity = Element::builder("CenterOfGravity", ONVIF_SCHEMA_NS)
+            .attr("x", ((self.left + self.right) / 2.0).to_string())
+            .attr("y", ((self.top + self.bottom) / 2.0).to_string())
+            .build();
+
+        let shape = Element::builder("Shape", ONVIF_SCHEMA_NS)
+            .append(bbox)
+            .append(center_of_gravity)
+            .build();
+
+        let mut appearance = Element::builder("Appearance", ONVIF_SCHEMA_NS)
+            .append(shape)
+            .build();
+
+        if let Some(ref class) = self.class {
+            let mut class_type = Element::builder("Type", ONVIF_SCHEMA_NS);
+            if let Some(likelihood) = self.likelihood {
+                class_type = class_type.attr("Likelihood", likelihood.to_string());
+            }
+
+            appearance.append_child(
+                Element::builder("Class", ONVIF_SCHEMA_NS)
+                    .append(class_type.append(class.clone()).build())
+                    .build(),
+            );
+        }
+
+        Element::builder("Object", ONVIF_SCHEMA_NS)
+            .attr("ObjectId", self.id.as_str())
+            .append(appearance)
+            .build()
+    }
+}
+
+// Formats an NTP timestamp as an xs:dateTime in UTC, as expected in the
+// UtcTime attribute of ONVIF frames
+fn utc_time_to_xml_date_time(utc_time: gst::ClockTime) -> Option<String> {
+    let unix_time = utc_time.checked_sub(crate::PRIME_EPOCH_OFFSET)?;
+
+    let dt = chrono::NaiveDateTime::from_timestamp_opt(
+        unix_time.seconds() as i64,
+        (unix_time.nseconds() % gst::ClockTime::SECOND.nseconds()) as u32,
+    )?;
+
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+        dt.year(),
+        dt.month(),
+        dt.day(),
+        dt.hour(),
+        dt.minute(),
+        dt.second(),
+        dt.nanosecond(),
+    ))
+}
+
+#[derive(Default)]
+struct State {
+    video_info: Option<gst_video::VideoInfo>,
+    segment: gst::FormattedSegment<gst::ClockTime>,
+    /// Mapping of UTC time to running time.
+    utc_time_running_time_mapping: Option<(gst::ClockTime, gst::Signed<gst::ClockTime>)>,
+    /// Objects received via custom events for the next buffer.
+    pending_objects: Vec<Object>,
+}
+
+pub struct OnvifMetadataGenerator {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    state: Mutex<State>,
+}
+
+impl OnvifMetadataGenerator {
+    fn sink_chain(
+        &self,
+        pad: &gst::Pad,
+        element: &super::OnvifMetadataGenerator,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::log!(CAT, obj: pad, "Handling buffer {:?}", buffer);
+
+        let mut state = self.state.lock().unwrap();
+
+        let pts = match buffer.pts() {
+            Some(pts) => pts,
+            None => {
+                gst::error!(CAT, obj: pad, "Need buffers with PTS");
+                return Err(gst::FlowError::Error);
+            }
+        };
+
+        let running_time = state.segment.to_running_time_full(pts).unwrap();
+
+        // Upstream-provided UTC times always take precedence, otherwise map the
+        // first running time to the current system time
+        if let Some(utc_time) = crate::lookup_reference_timestamp(&buffer) {
+            state.utc_time_running_time_mapping = Some((utc_time, running_time));
+        } else if state.utc_time_running_time_mapping.is_none() {
+            let now =
+                gst::ClockTime::from_useconds(glib::real_time() as u64) + crate::PRIME_EPOCH_OFFSET;
+            let current_running_time = element
+                .current_running_time()
+                .map(|running_time| running_time.into_positive())
+                .unwrap_or(running_time);
+
+            gst::info!(
+                CAT,
+                obj: pad,
+                "No UTC time on buffers, using system time {} for running time {:?}",
+                now,
+                current_running_time,
+            );
+
+            state.utc_time_running_time_mapping = Some((now, current_running_time));
+        }
+
+        let utc_time = match crate::running_time_to_utc_time(
+            state.utc_time_running_time_mapping.unwrap(),
+            running_time,
+        ) {
+            Some(utc_time) => utc_time,
+            None => {
+                gst::warning!(
+                    CAT,
+                    obj: pad,
+                    "Can't calculate UTC time for running time {:?}, dropping buffer",
+                    running_time
+                );
+                state.pending_objects.clear();
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        };
+
+        let mut objects = std::mem::take(&mut state.pending_objects);
+
+        if let Some(ref video_info) = state.video_info {
+            for meta in buffer.iter_meta::<gst_video::VideoRegionOfInterestMeta>() {
+                objects.push(Object::from_roi_meta(&meta, video_info));
+            }
+        } else if buffer
+            .iter_meta::<gst_video::VideoRegionOfInterestMeta>()
+            .next()
+            .is_some()
+        {
+            gst::warning!(
+                CAT,
+                obj: pad,
+                "Ignoring region of interest metas on non-video stream"
+            );
+        }
+        drop(state);
+
+        if objects.is_empty() {
+            gst::trace!(CAT, obj: pad, "No objects for UTC time {}", utc_time);
+
+            self.srcpad.push_event(
+                gst::event::Gap::builder(pts)
+                    .duration(buffer.duration())
+                    .build(),
+            );
+
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let date_time = utc_time_to_xml_date_time(utc_time).ok_or_else(|| {
+            gst::element_error!(
+                element,
+                gst::StreamError::Format,
+                ["Can't represent UTC time {} as date", utc_time]
+            );
+
+            gst::FlowError::Error
+        })?;
+
+        gst::trace!(
+            CAT,
+            obj: pad,
+            "Producing frame with {} objects at UTC time {} / PTS {}",
+            objects.len(),
+            date_time,
+            pts
+        );
+
+        let mut frame = Element::builder("Frame", ONVIF_SCHEMA_NS)
+            .attr("UtcTime", date_time)
+            .build();
+        for object in objects {
+            frame.append_child(object.to_xml());
+        }
+
+        let xml = Element::builder("MetadataStream", ONVIF_SCHEMA_NS)
+            .prefix(Some("tt".into()), ONVIF_SCHEMA_NS)
+            .unwrap()
+            .append(
+                Element::builder("VideoAnalytics", ONVIF_SCHEMA_NS)
+                    .append(frame)
+                    .build(),
+            )
+            .build();
+
+        let mut vec = Vec::new();
+        if let Err(err) = xml.write_to_decl(&mut vec) {
+            gst::element_error!(
+                element,
+                gst::ResourceError::Write,
+                ["Can't serialize XML element: {}", err]
+            );
+
+            return Err(gst::FlowError::Error);
+        }
+
+        let mut outbuf = gst::Buffer::from_mut_slice(vec);
+        {
+            let outbuf = outbuf.get_mut().unwrap();
+            outbuf.set_pts(pts);
+            outbuf.set_duration(buffer.duration());
+
+            gst::ReferenceTimestampMeta::add(
+                outbuf,
+                &crate::NTP_CAPS,
+                utc_time,
+                gst::ClockTime::NONE,
+            );
+        }
+
+        self.srcpad.push(outbuf)
+    }
+
+    fn sink_event(
+        &self,
+        pad: &gst::Pad,
+        element: &super::OnvifMetadataGenerator,
+        event: gst::Event,
+    ) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad, "Handling event {:?}", event);
+
+        match event.view() {
+            EventView::CustomDownstream(ev) => {
+                let s = match ev.structure() {
+                    Some(s) if s.name() == OBJECT_EVENT_NAME => s,
+                    _ => return pad.event_default(Some(element), event),
+                };
+
+                match Object::from_structure(s) {
+                    Some(object) => {
+                        gst::trace!(CAT, obj: pad, "Queueing object {:?}", object);
+                        self.state.lock().unwrap().pending_objects.push(object);
+                    }
+                    None => {
+                        gst::warning!(CAT, obj: pad, "Invalid object event {:?}", s);
+                    }
+                }
+
+                true
+            }
+            EventView::Caps(ev) => {
+                let mut state = self.state.lock().unwrap();
+                state.video_info = gst_video::VideoInfo::from_caps(ev.caps()).ok();
+                drop(state);
+
+                self.srcpad.push_event(
+                    gst::event::Caps::builder(&self.srcpad.pad_template_caps())
+                        .seqnum(ev.seqnum())
+                        .build(),
+                )
+            }
+            EventView::Segment(ev) => {
+                let segment = match ev.segment().downcast_ref::<gst::ClockTime>() {
+                    Some(segment) => segment.clone(),
+                    None => {
+                        gst::error!(CAT, obj: pad, "Non-TIME segment {:?}", ev.segment());
+                        return false;
+                    }
+                };
+
+                self.state.lock().unwrap().segment = segment;
+
+                pad.event_default(Some(element), event)
+            }
+            EventView::FlushStop(_) => {
+                let mut state = self.state.lock().unwrap();
+                state.segment = gst::FormattedSegment::default();
+                state.utc_time_running_time_mapping = None;
+                state.pending_objects.clear();
+                drop(state);
+
+                pad.event_default(Some(element), event)
+            }
+            _ => pad.event_default(Some(element), event),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for OnvifMetadataGenerator {
+    const NAME: &'static str = "GstOnvifMetadataGenerator";
+    type Type = super::OnvifMetadataGenerator;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                OnvifMetadataGenerator::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |generator, element| generator.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                OnvifMetadataGenerator::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |generator, element| generator.sink_event(pad, element, event),
+                )
+            })
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src")).build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            state: Mutex::new(State::default()),
+        }
+    }
+}
+
+impl ObjectImpl for OnvifMetadataGenerator {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+}
+
+impl GstObjectImpl for OnvifMetadataGenerator {}
+
+impl ElementImpl for OnvifMetadataGenerator {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "ONVIF Metadata Generator",
+                "Metadata/Generator",
+                "Generates ONVIF analytics metadata from region of interest metas or custom events",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::new_any(),
+            )
+            .unwrap();
+
+            let src_caps = gst::Caps::builder("application/x-onvif-metadata")
+                .field("parsed", true)
+                .build();
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &src_caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::ReadyToPaused | gst::StateChange::PausedToReady => {
+                // Reset the whole state
+                let mut state = self.state.lock().unwrap();
+                *state = State::default();
+            }
+            _ => (),
+        }
+
+        self.parent_change_state(element, transition)
+    }
+}
@@ -15,42 +15,6 @@ use once_cell::sync::Lazy;
 use std::collections::BTreeMap;
 use std::sync::{Condvar, Mutex};
 
-fn utc_time_to_pts(
-    segment: &gst::FormattedSegment<gst::ClockTime>,
-    utc_time_running_time_mapping: (gst::ClockTime, gst::Signed<gst::ClockTime>),
-    utc_time: gst::ClockTime,
-) -> Option<gst::ClockTime> {
-    let running_time =
-        utc_time_to_running_time(utc_time_running_time_mapping, utc_time)?.positive()?;
-    segment.position_from_running_time(running_time)
-}
-
-fn utc_time_to_running_time(
-    utc_time_running_time_mapping: (gst::ClockTime, gst::Signed<gst::ClockTime>),
-    utc_time: gst::ClockTime,
-) -> Option<gst::Signed<gst::ClockTime>> {
-    if utc_time < utc_time_running_time_mapping.0 {
-        let diff = utc_time_running_time_mapping.0 - utc_time;
-        utc_time_running_time_mapping.1.checked_sub_unsigned(diff)
-    } else {
-        let diff = utc_time - utc_time_running_time_mapping.0;
-        utc_time_running_time_mapping.1.checked_add_unsigned(diff)
-    }
-}
-
-fn running_time_to_utc_time(
-    utc_time_running_time_mapping: (gst::ClockTime, gst::Signed<gst::ClockTime>),
-    running_time: gst::Signed<gst::ClockTime>,
-) -> Option<gst::ClockTime> {
-    let diff = running_time.checked_sub(utc_time_running_time_mapping.1)?;
-
-    use gst::Signed::*;
-    match diff {
-        Positive(diff) => utc_time_running_time_mapping.0.checked_add(diff),
-        Negative(diff) => utc_time_running_time_mapping.0.checked_sub(diff),
-    }
-}
-
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
         "onvifmetadataparse",
@@ -333,9 +297,11 @@ impl OnvifMetadataParse {
         {
             let (running_time, buffer) = match buffer_or_event {
                 TimedBufferOrEvent::Event(running_time, event) => {
-                    let current_utc_time =
-                        running_time_to_utc_time(utc_time_running_time_mapping, running_time)
-                            .unwrap_or(gst::ClockTime::ZERO);
+                    let current_utc_time = crate::running_time_to_utc_time(
+                        utc_time_running_time_mapping,
+                        running_time,
+                    )
+                    .unwrap_or(gst::ClockTime::ZERO);
 
                     let frame = queued_frames
                         .entry(current_utc_time)
@@ -377,8 +343,9 @@ impl OnvifMetadataParse {
                 frame.video_analytics.append_child(el.clone());
             }
 
-            let utc_time = running_time_to_utc_time(utc_time_running_time_mapping, running_time)
-                .unwrap_or(gst::ClockTime::ZERO);
+            let utc_time =
+                crate::running_time_to_utc_time(utc_time_running_time_mapping, running_time)
+                    .unwrap_or(gst::ClockTime::ZERO);
 
             for child in root.children() {
                 let frame = queued_frames.entry(utc_time).or_insert_with(Frame::default);
@@ -481,7 +448,7 @@ impl OnvifMetadataParse {
             None => return None,
         };
 
-        let earliest_running_time = utc_time_to_running_time(
+        let earliest_running_time = crate::utc_time_to_running_time(
             state.utc_time_running_time_mapping.unwrap(),
             earliest_utc_time,
         );
@@ -522,7 +489,7 @@ impl OnvifMetadataParse {
             _ => return false,
         };
 
-        let earliest_running_time = utc_time_to_running_time(
+        let earliest_running_time = crate::utc_time_to_running_time(
             state.utc_time_running_time_mapping.unwrap(),
             earliest_utc_time,
         );
@@ -601,7 +568,7 @@ impl OnvifMetadataParse {
             "Draining up to UTC time {} / running time {} from current position {} / running time {}",
             drain_utc_time.display(),
             drain_utc_time
-                .and_then(|drain_utc_time| utc_time_to_running_time(
+                .and_then(|drain_utc_time| crate::utc_time_to_running_time(
                     utc_time_running_time_mapping,
                     drain_utc_time
                 ))
@@ -691,14 +658,17 @@ impl OnvifMetadataParse {
                 }
             }
 
-            let mut frame_pts =
-                match utc_time_to_pts(out_segment, utc_time_running_time_mapping, utc_time) {
-                    Some(frame_pts) => frame_pts,
-                    None => {
-                        gst::warning!(CAT, obj: element, "UTC time {} outside segment", utc_time);
-                        gst::ClockTime::ZERO
-                    }
-                };
+            let mut frame_pts = match crate::utc_time_to_pts(
+                out_segment,
+                utc_time_running_time_mapping,
+                utc_time,
+            ) {
+                Some(frame_pts) => frame_pts,
+                None => {
+                    gst::warning!(CAT, obj: element, "UTC time {} outside segment", utc_time);
+                    gst::ClockTime::ZERO
+                }
+            };
 
             if frame.video_analytics.children().next().is_none() && frame.other_elements.is_empty()
             {
@@ -979,7 +949,7 @@ impl OnvifMetadataParse {
                             obj: element,
                             "Queueing EOS event with UTC time {} / running time {}",
                             eos_utc_time,
-                            utc_time_to_running_time(*utc_time_running_time_mapping, eos_utc_time)
+                            crate::utc_time_to_running_time(*utc_time_running_time_mapping, eos_utc_time)
                                 .display(),
                         );
 
@@ -988,7 +958,7 @@ impl OnvifMetadataParse {
                         let current_running_time = in_segment
                             .to_running_time_full(in_segment.position())
                             .unwrap_or(gst::ClockTime::MIN_SIGNED);
-                        let current_utc_time = running_time_to_utc_time(
+                        let current_utc_time = crate::running_time_to_utc_time(
                             *utc_time_running_time_mapping,
                             current_running_time,
                         )
@@ -1327,13 +1297,13 @@ impl OnvifMetadataParse {
             // And drain up to that running time now, or everything if EOS
             let data = if self.sinkpad.pad_flags().contains(gst::PadFlags::EOS) {
                 self.drain(element, &mut state, None)?
-            } else if let Some(drain_utc_time) =
-                Option::zip(drain_running_time, state.utc_time_running_time_mapping).and_then(
-                    |(drain_running_time, utc_time_running_time_mapping)| {
-                        running_time_to_utc_time(utc_time_running_time_mapping, drain_running_time)
-                    },
-                )
-            {
+            } else if let Some(drain_utc_time) = Option::zip(
+                drain_running_time,
+                state.utc_time_running_time_mapping,
+            )
+            .and_then(|(drain_running_time, utc_time_running_time_mapping)| {
+                crate::running_time_to_utc_time(utc_time_running_time_mapping, drain_running_time)
+            }) {
                 self.drain(element, &mut state, Some(drain_utc_time))?
             } else {
                 vec![]
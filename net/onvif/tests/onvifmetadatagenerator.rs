@@ -0,0 +1,194 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+use gst_check::Harness;
+use minidom::Element;
+
+const ONVIF_SCHEMA_NS: &str = "http://www.onvif.org/ver10/schema";
+
+// 2022-01-01T00:00:00Z in NTP time
+const NTP_2022: gst::ClockTime = gst::ClockTime::from_seconds(3_849_984_000);
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsonvif::plugin_register_static().expect("onvif test");
+    });
+}
+
+fn input_buffer(pts: gst::ClockTime, utc_time: Option<gst::ClockTime>) -> gst::Buffer {
+    let mut buffer = gst::Buffer::with_size(1).unwrap();
+    {
+        let buffer = buffer.get_mut().unwrap();
+        buffer.set_pts(pts);
+        buffer.set_duration(gst::ClockTime::from_mseconds(40));
+        if let Some(utc_time) = utc_time {
+            gst::ReferenceTimestampMeta::add(
+                buffer,
+                &gst::Caps::builder("timestamp/x-ntp").build(),
+                utc_time,
+                gst::ClockTime::NONE,
+            );
+        }
+    }
+    buffer
+}
+
+fn object_event(id: u32, class: &str) -> gst::Event {
+    gst::event::CustomDownstream::new(
+        gst::Structure::builder("OnvifMetadataObject")
+            .field("object-id", id)
+            .field("left", -0.5f64)
+            .field("top", 0.5f64)
+            .field("right", 0.25f64)
+            .field("bottom", -0.25f64)
+            .field("class", class)
+            .field("likelihood", 0.75f64)
+            .build(),
+    )
+}
+
+fn parse_frame(buffer: &gst::Buffer) -> Element {
+    let map = buffer.map_readable().unwrap();
+    let root = std::str::from_utf8(&map)
+        .unwrap()
+        .parse::<Element>()
+        .unwrap();
+
+    assert!(root.is("MetadataStream", ONVIF_SCHEMA_NS));
+
+    root.get_child("VideoAnalytics", ONVIF_SCHEMA_NS)
+        .and_then(|analytics| analytics.get_child("Frame", ONVIF_SCHEMA_NS))
+        .expect("No frame")
+        .clone()
+}
+
+#[test]
+fn test_generate_frame() {
+    init();
+
+    let mut h = Harness::new("onvifmetadatagenerator");
+    h.set_src_caps_str("application/x-test");
+
+    assert!(h.push_event(object_event(1, "Human")));
+    h.push(input_buffer(
+        gst::ClockTime::ZERO,
+        Some(NTP_2022 + gst::ClockTime::from_mseconds(500)),
+    ))
+    .unwrap();
+
+    let buffer = h.pull().unwrap();
+    assert_eq!(buffer.pts(), Some(gst::ClockTime::ZERO));
+    assert_eq!(buffer.duration(), Some(gst::ClockTime::from_mseconds(40)));
+
+    let meta = buffer
+        .meta::<gst::ReferenceTimestampMeta>()
+        .expect("No reference timestamp meta");
+    assert_eq!(
+        meta.timestamp(),
+        NTP_2022 + gst::ClockTime::from_mseconds(500)
+    );
+
+    let frame = parse_frame(&buffer);
+    assert_eq!(
+        frame.attr("UtcTime"),
+        Some("2022-01-01T00:00:00.500000000Z")
+    );
+
+    let objects = frame
+        .children()
+        .filter(|el| el.is("Object", ONVIF_SCHEMA_NS))
+        .collect::<Vec<_>>();
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].attr("ObjectId"), Some("1"));
+
+    let appearance = objects[0].get_child("Appearance", ONVIF_SCHEMA_NS).unwrap();
+
+    let bbox = appearance
+        .get_child("Shape", ONVIF_SCHEMA_NS)
+        .and_then(|shape| shape.get_child("BoundingBox", ONVIF_SCHEMA_NS))
+        .unwrap();
+    assert_eq!(bbox.attr("left"), Some("-0.5"));
+    assert_eq!(bbox.attr("top"), Some("0.5"));
+    assert_eq!(bbox.attr("right"), Some("0.25"));
+    assert_eq!(bbox.attr("bottom"), Some("-0.25"));
+
+    let class_type = appearance
+        .get_child("Class", ONVIF_SCHEMA_NS)
+        .and_then(|class| class.get_child("Type", ONVIF_SCHEMA_NS))
+        .unwrap();
+    assert_eq!(class_type.text(), "Human");
+    assert_eq!(class_type.attr("Likelihood"), Some("0.75"));
+}
+
+#[test]
+fn test_utc_time_follows_running_time() {
+    init();
+
+    let mut h = Harness::new("onvifmetadatagenerator");
+    h.set_src_caps_str("application/x-test");
+
+    // Buffers without reference timestamp are mapped to UTC through the
+    // last known UTC / running time pair
+    assert!(h.push_event(object_event(1, "Vehicle")));
+    h.push(input_buffer(gst::ClockTime::ZERO, Some(NTP_2022)))
+        .unwrap();
+    assert!(h.push_event(object_event(2, "Vehicle")));
+    h.push(input_buffer(gst::ClockTime::from_mseconds(1_250), None))
+        .unwrap();
+
+    let frame = parse_frame(&h.pull().unwrap());
+    assert_eq!(
+        frame.attr("UtcTime"),
+        Some("2022-01-01T00:00:00.000000000Z")
+    );
+
+    let buffer = h.pull().unwrap();
+    assert_eq!(
+        buffer
+            .meta::<gst::ReferenceTimestampMeta>()
+            .unwrap()
+            .timestamp(),
+        NTP_2022 + gst::ClockTime::from_mseconds(1_250)
+    );
+    let frame = parse_frame(&buffer);
+    assert_eq!(
+        frame.attr("UtcTime"),
+        Some("2022-01-01T00:00:01.250000000Z")
+    );
+}
+
+#[test]
+fn test_no_objects_gap() {
+    init();
+
+    let mut h = Harness::new("onvifmetadatagenerator");
+    h.set_src_caps_str("application/x-test");
+
+    h.push(input_buffer(gst::ClockTime::ZERO, Some(NTP_2022)))
+        .unwrap();
+    assert_eq!(h.buffers_in_queue(), 0);
+
+    loop {
+        let ev = h.pull_event().unwrap();
+        if let gst::EventView::Gap(gap) = ev.view() {
+            assert_eq!(
+                gap.get(),
+                (
+                    gst::ClockTime::ZERO,
+                    Some(gst::ClockTime::from_mseconds(40))
+                )
+            );
+            break;
+        }
+    }
+}
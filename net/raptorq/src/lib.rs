@@ -17,10 +17,16 @@ use gst::glib;
 mod fecscheme;
 mod raptorqdec;
 mod raptorqenc;
+mod recovery;
+mod st2022fec;
+mod st2022fecdec;
+mod st2022fecenc;
 
 fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     raptorqdec::register(plugin)?;
     raptorqenc::register(plugin)?;
+    st2022fecdec::register(plugin)?;
+    st2022fecenc::register(plugin)?;
 
     Ok(())
 }
@@ -21,6 +21,7 @@ use std::sync::Mutex;
 use raptorq::{EncodingPacket, ObjectTransmissionInformation, PayloadId, SourceBlockDecoder};
 
 use crate::fecscheme::{self, DataUnitHeader, RepairPayloadId};
+use crate::recovery::{self, RepairWindow, Stats};
 
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
@@ -48,13 +49,6 @@ impl Default for Settings {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy)]
-struct Stats {
-    recv: u64,
-    lost: u64,
-    recovered: u64,
-}
-
 #[derive(Debug, Clone, Copy)]
 struct SourceBlockInfo {
     initial_seq: u64,
@@ -93,14 +87,12 @@ struct MediaPacketItem {
 struct State {
     media_packets: BTreeMap<u64, MediaPacketItem>,
     repair_packets: BTreeMap<u64, Vec<RepairPacketItem>>,
-    expirations: BTreeMap<u64, Option<gst::ClockTime>>,
     source_block_info: BTreeMap<u64, SourceBlockInfo>,
     extended_media_seq: Option<u64>,
     extended_repair_seq: Option<u64>,
     symbol_size: usize,
     media_packets_reset_threshold: usize,
-    repair_window: Option<gst::ClockTime>,
-    max_arrival_time: Option<gst::ClockTime>,
+    repair_window: RepairWindow,
     stats: Stats,
 }
 
@@ -112,22 +104,12 @@ impl State {
             self.media_packets.retain(|&k, _| k >= seq_hi);
             self.repair_packets.remove(&seq_lo);
             self.source_block_info.remove(&seq_lo);
-            self.expirations.remove(&seq_lo);
+            self.repair_window.remove(seq_lo);
         }
     }
 
     fn expire_packets(&mut self) -> Vec<u64> {
-        let expired = self
-            .expirations
-            .iter()
-            .filter_map(|(&seq, &expiration)| {
-                if self.max_arrival_time.opt_gt(expiration) == Some(true) {
-                    Some(seq)
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
+        let expired = self.repair_window.expired();
 
         for seq in &expired {
             self.drop_source_block(*seq);
@@ -286,7 +268,7 @@ impl RaptorqDec {
                             let mut buf = gst::Buffer::from_slice(data_unit);
 
                             let buf_mut = buf.get_mut().unwrap();
-                            buf_mut.set_dts(state.max_arrival_time);
+                            buf_mut.set_dts(state.repair_window.max_arrival_time());
 
                             return Some(buf);
                         }
@@ -341,17 +323,7 @@ impl RaptorqDec {
                 rtpbuf.timestamp()
             );
 
-            // Expand cyclic sequence numbers to u64, start from u16::MAX so we
-            // never overflow substraction.
-            let seq = rtpbuf.seq();
-            let prev_seq = state.extended_media_seq.unwrap_or(65_535 + seq as u64);
-
-            let delta = gst_rtp::compare_seqnum(prev_seq as u16, seq);
-
-            match delta.is_negative() {
-                true => prev_seq - delta.unsigned_abs() as u64,
-                false => prev_seq + delta.unsigned_abs() as u64,
-            }
+            recovery::extend_seqnum(state.extended_media_seq, rtpbuf.seq())
         };
 
         let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
@@ -374,8 +346,7 @@ impl RaptorqDec {
         state.stats.recv += 1;
         state.extended_media_seq = Some(this_seq);
 
-        let now = buffer.dts_or_pts();
-        state.max_arrival_time = state.max_arrival_time.opt_max(now).or(now);
+        state.repair_window.update_arrival_time(buffer.dts_or_pts());
 
         Ok(gst::FlowSuccess::Ok)
     }
@@ -454,23 +425,9 @@ impl RaptorqDec {
             lb,
         );
 
-        // Expand cyclic sequence numbers to u64, start from u16::MAX so we
-        // never overflow substraction.
-        let prev_seq = state.extended_repair_seq.unwrap_or(65_535 + i as u64);
-        let delta = gst_rtp::compare_seqnum(prev_seq as u16, i);
-
-        let this_seq = match delta.is_negative() {
-            true => prev_seq - delta.unsigned_abs() as u64,
-            false => prev_seq + delta.unsigned_abs() as u64,
-        };
-
+        let this_seq = recovery::extend_seqnum(state.extended_repair_seq, i);
         state.extended_repair_seq = Some(this_seq);
-
-        let expire_at = state.max_arrival_time.opt_add(state.repair_window);
-        let scheduled = state.expirations.entry(this_seq).or_insert(expire_at);
-
-        // Update already scheduled expiration if a new value happens to be earlier
-        *scheduled = scheduled.opt_min(expire_at);
+        state.repair_window.schedule(this_seq);
 
         state
             .source_block_info
@@ -491,7 +448,7 @@ impl RaptorqDec {
             });
 
         assert_eq!(state.repair_packets.len(), state.source_block_info.len());
-        assert_eq!(state.repair_packets.len(), state.expirations.len());
+        assert_eq!(state.repair_packets.len(), state.repair_window.len());
 
         Ok(gst::FlowSuccess::Ok)
     }
@@ -578,7 +535,7 @@ impl RaptorqDec {
         let mut state = self.state.lock().unwrap();
 
         state.symbol_size = symbol_size;
-        state.repair_window = repair_window;
+        state.repair_window.set_window(repair_window);
         state.media_packets_reset_threshold = media_packets_reset_threshold;
 
         Ok(())
@@ -594,10 +551,9 @@ impl RaptorqDec {
         state.media_packets.clear();
         state.repair_packets.clear();
         state.source_block_info.clear();
-        state.expirations.clear();
         state.extended_media_seq = None;
         state.extended_repair_seq = None;
-        state.max_arrival_time = gst::ClockTime::NONE;
+        state.repair_window.reset();
         state.stats = Default::default();
     }
 }
@@ -733,15 +689,13 @@ impl ObjectImpl for RaptorqDec {
                         .fold(0, |acc, x| acc + x.len() as u64),
                 );
 
-                let s = gst::Structure::builder("application/x-rtp-raptorqdec-stats")
-                    .field("received-packets", stats.recv)
-                    .field("lost-packets", stats.lost)
-                    .field("recovered-packets", stats.recovered)
-                    .field("buffered-media-packets", media_packets)
-                    .field("buffered-repair-packets", repair_packets)
-                    .build();
-
-                s.to_value()
+                stats
+                    .to_structure(
+                        "application/x-rtp-raptorqdec-stats",
+                        media_packets,
+                        repair_packets,
+                    )
+                    .to_value()
             }
             _ => unimplemented!(),
         }
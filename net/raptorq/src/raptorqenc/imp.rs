@@ -38,6 +38,16 @@ const DEFAULT_REPAIR_WINDOW: u32 = 50;
 const DEFAULT_SYMBOL_SIZE: u32 = 1408;
 const DEFAULT_MTU: u32 = 1400;
 const DEFAULT_PT: u32 = 97;
+const DEFAULT_ADAPTIVE_REPAIR: bool = false;
+const DEFAULT_MIN_OVERHEAD: u32 = 4;
+const DEFAULT_MAX_OVERHEAD: u32 = 50;
+
+// Weight of a new loss report in the smoothed fraction lost
+const LOSS_SMOOTHING: f64 = 0.5;
+
+// Custom upstream event carrying the fraction of lost packets, expressed
+// in 1/256 units as in RTCP receiver report blocks (RFC3550, section 6.4.1)
+const LOSS_REPORT_EVENT: &str = "GstRaptorqLossReport";
 
 const SYMBOL_ALIGNMENT: usize = 8;
 
@@ -49,6 +59,9 @@ struct Settings {
     symbol_size: u32,
     mtu: u32,
     pt: u32,
+    adaptive_repair: bool,
+    min_overhead: u32,
+    max_overhead: u32,
 }
 
 impl Default for Settings {
@@ -60,6 +73,9 @@ impl Default for Settings {
             symbol_size: DEFAULT_SYMBOL_SIZE,
             mtu: DEFAULT_MTU,
             pt: DEFAULT_PT,
+            adaptive_repair: DEFAULT_ADAPTIVE_REPAIR,
+            min_overhead: DEFAULT_MIN_OVERHEAD,
+            max_overhead: DEFAULT_MAX_OVERHEAD,
         }
     }
 }
@@ -81,6 +97,10 @@ struct State {
     segment: gst::FormattedSegment<gst::ClockTime>,
     repair_packets_num: usize,
     protected_packets_num: usize,
+    adaptive_repair: bool,
+    min_repair_packets_num: usize,
+    max_repair_packets_num: usize,
+    fraction_lost: Option<f64>,
     repair_window: usize,
     symbol_size: usize,
     symbols_per_packet: usize,
@@ -101,9 +121,158 @@ pub struct RaptorqEnc {
     settings: Mutex<Settings>,
     state: Mutex<Option<State>>,
     pending_timers: Mutex<HashSet<gst::ClockId>>,
+    rtp_session: Mutex<Option<(glib::WeakRef<glib::Object>, glib::SignalHandlerId)>>,
+}
+
+// Smallest number of repair packets that allows recovering a source block
+// of k packets at the given loss rate. RaptorQ decoding succeeds with very
+// high probability when k + 2 packets are received, so we need (k + r) *
+// (1 - loss) >= k + 2.
+fn repair_packets_for_loss(protected_packets: usize, fraction_lost: f64) -> usize {
+    let k = protected_packets as f64;
+    let received = 1.0 - fraction_lost.clamp(0.0, 0.99);
+
+    ((k + 2.0) / received - k).ceil().max(0.0) as usize
 }
 
 impl RaptorqEnc {
+    fn handle_loss_report(&self, element: &super::RaptorqEnc, report: u32) {
+        let mut state_guard = self.state.lock().unwrap();
+        let state = match state_guard.as_mut() {
+            Some(state) => state,
+            None => return,
+        };
+
+        if !state.adaptive_repair {
+            return;
+        }
+
+        let sample = report.min(255) as f64 / 256.0;
+        let fraction_lost = match state.fraction_lost {
+            Some(prev) => prev + (sample - prev) * LOSS_SMOOTHING,
+            None => sample,
+        };
+
+        gst::debug!(
+            CAT,
+            obj: element,
+            "Got loss report {}/256, smoothed fraction lost {:.4}",
+            report,
+            fraction_lost
+        );
+
+        state.fraction_lost = Some(fraction_lost);
+    }
+
+    // Picks the number of repair packets for the next source block from the
+    // observed loss, returns the message to post if it has changed.
+    fn update_repair_packets(
+        element: &super::RaptorqEnc,
+        state: &mut State,
+    ) -> Option<gst::Message> {
+        let fraction_lost = state.fraction_lost?;
+
+        let repair_packets_num =
+            repair_packets_for_loss(state.protected_packets_num, fraction_lost)
+                .clamp(state.min_repair_packets_num, state.max_repair_packets_num);
+
+        if repair_packets_num == state.repair_packets_num {
+            return None;
+        }
+
+        gst::info!(
+            CAT,
+            obj: element,
+            "Changing repair packets from {} to {}, fraction lost {:.4}",
+            state.repair_packets_num,
+            repair_packets_num,
+            fraction_lost
+        );
+
+        let s = gst::Structure::builder("raptorqenc-repair-packets")
+            .field("repair-packets", repair_packets_num as u32)
+            .field("previous-repair-packets", state.repair_packets_num as u32)
+            .field("protected-packets", state.protected_packets_num as u32)
+            .field(
+                "overhead",
+                repair_packets_num as f64 * 100.0 / state.protected_packets_num as f64,
+            )
+            .field("fraction-lost", fraction_lost)
+            .build();
+
+        state.repair_packets_num = repair_packets_num;
+
+        Some(gst::message::Element::builder(s).src(element).build())
+    }
+
+    fn on_ssrc_active(&self, element: &super::RaptorqEnc, source: &glib::Object) {
+        let stats = source.property::<gst::Structure>("stats");
+
+        // Only remote sources that sent us a receiver report block
+        if !stats.get::<bool>("have-rb").unwrap_or(false) {
+            return;
+        }
+
+        if let Ok(fraction_lost) = stats.get::<u32>("rb-fractionlost") {
+            self.handle_loss_report(element, fraction_lost);
+        }
+    }
+
+    fn set_rtp_session(&self, element: &super::RaptorqEnc, session: Option<glib::Object>) {
+        let mut rtp_session = self.rtp_session.lock().unwrap();
+
+        if let Some((session, handler_id)) = rtp_session.take() {
+            if let Some(session) = session.upgrade() {
+                session.disconnect(handler_id);
+            }
+        }
+
+        let session = match session {
+            Some(session) => session,
+            None => return,
+        };
+
+        // RTPSession emits on-ssrc-active for every RTCP packet received from
+        // a source, its stats hold the last report block it sent us.
+        let element_weak = element.downgrade();
+        let handler_id = session.connect("on-ssrc-active", false, move |args| {
+            let element = element_weak.upgrade()?;
+            let source = args[1].get::<glib::Object>().expect("signal arg");
+
+            element.imp().on_ssrc_active(&element, &source);
+            None
+        });
+
+        *rtp_session = Some((session.downgrade(), handler_id));
+    }
+
+    fn src_event(&self, pad: &gst::Pad, element: &super::RaptorqEnc, event: gst::Event) -> bool {
+        gst::log!(CAT, obj: pad, "Handling event {:?}", event);
+        use gst::EventView;
+
+        if let EventView::CustomUpstream(ev) = event.view() {
+            if let Some(s) = ev.structure() {
+                if s.name() == LOSS_REPORT_EVENT {
+                    match s.get::<u32>("fraction-lost") {
+                        Ok(fraction_lost) => self.handle_loss_report(element, fraction_lost),
+                        Err(err) => {
+                            gst::warning!(CAT, obj: pad, "Invalid loss report: {}", err)
+                        }
+                    }
+
+                    return true;
+                }
+            }
+        }
+
+        if pad == &self.srcpad {
+            pad.event_default(Some(element), event)
+        } else {
+            // Nothing to forward upstream from the FEC pad
+            true
+        }
+    }
+
     fn process_source_block(
         element: &super::RaptorqEnc,
         state: &mut State,
@@ -445,15 +614,26 @@ impl RaptorqEnc {
 
         assert_eq!(state.packets.len(), state.seqnums.len());
 
+        let mut msg = None;
+
         if state.packets.len() == state.protected_packets_num as usize {
             // We use current buffer timing as a base for repair packets timestamps
             let now_pts = buffer.pts();
             let now_dts = buffer.dts_or_pts();
 
+            if state.adaptive_repair {
+                msg = Self::update_repair_packets(element, state);
+            }
+
             Self::process_source_block(element, state, now_pts, now_dts, now_rtpts)?;
         }
 
         drop(state_guard);
+
+        if let Some(msg) = msg {
+            let _ = element.post_message(msg);
+        }
+
         self.srcpad.push(buffer)
     }
 
@@ -584,7 +764,7 @@ impl RaptorqEnc {
         let settings = self.settings.lock().unwrap();
 
         let protected_packets_num = settings.protected_packets as usize;
-        let repair_packets_num = settings.repair_packets as usize;
+        let mut repair_packets_num = settings.repair_packets as usize;
         let repair_window = settings.repair_window as usize;
         let symbol_size = settings.symbol_size as usize;
         let mtu = settings.mtu as usize;
@@ -627,6 +807,19 @@ impl RaptorqEnc {
             return Err(error_msg!(gst::CoreError::Failed, [&details]));
         }
 
+        // Repair packets budget for the adaptive mode, at least one repair
+        // packet is always sent
+        let adaptive_repair = settings.adaptive_repair;
+        let min_repair_packets_num =
+            ((protected_packets_num * settings.min_overhead as usize + 99) / 100).max(1);
+        let max_repair_packets_num = (protected_packets_num * settings.max_overhead as usize / 100)
+            .max(min_repair_packets_num);
+
+        if adaptive_repair {
+            repair_packets_num =
+                repair_packets_num.clamp(min_repair_packets_num, max_repair_packets_num);
+        }
+
         gst::info!(
             CAT,
             obj: element,
@@ -645,6 +838,10 @@ impl RaptorqEnc {
             plan,
             repair_packets_num,
             protected_packets_num,
+            adaptive_repair,
+            min_repair_packets_num,
+            max_repair_packets_num,
+            fraction_lost: None,
             repair_window,
             symbol_size,
             symbols_per_packet,
@@ -710,6 +907,13 @@ impl ObjectSubclass for RaptorqEnc {
 
         let templ = klass.pad_template("src").unwrap();
         let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this, element| this.src_event(pad, element, event),
+                )
+            })
             .iterate_internal_links_function(|pad, parent| {
                 Self::catch_panic_pad_function(
                     parent,
@@ -729,6 +933,13 @@ impl ObjectSubclass for RaptorqEnc {
                     |this, element| this.src_activatemode(pad, element, mode, active),
                 )
             })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this, element| this.src_event(pad, element, event),
+                )
+            })
             .iterate_internal_links_function(|pad, parent| {
                 Self::catch_panic_pad_function(
                     parent,
@@ -745,6 +956,7 @@ impl ObjectSubclass for RaptorqEnc {
             settings: Mutex::new(Default::default()),
             state: Mutex::new(None),
             pending_timers: Mutex::new(HashSet::new()),
+            rtp_session: Mutex::new(None),
         }
     }
 }
@@ -799,6 +1011,35 @@ impl ObjectImpl for RaptorqEnc {
                     .default_value(DEFAULT_PT)
                     .mutable_ready()
                     .build(),
+                glib::ParamSpecBoolean::builder("adaptive-repair")
+                    .nick("Adaptive Repair")
+                    .blurb("Adjust the number of repair packets per block to the reported loss")
+                    .default_value(DEFAULT_ADAPTIVE_REPAIR)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("min-overhead")
+                    .nick("Minimum Overhead")
+                    .blurb(
+                        "Minimum repair packets in percent of protected packets in adaptive mode",
+                    )
+                    .maximum(1000)
+                    .default_value(DEFAULT_MIN_OVERHEAD)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("max-overhead")
+                    .nick("Maximum Overhead")
+                    .blurb(
+                        "Maximum repair packets in percent of protected packets in adaptive mode",
+                    )
+                    .maximum(1000)
+                    .default_value(DEFAULT_MAX_OVERHEAD)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecObject::builder::<glib::Object>("rtp-session")
+                    .nick("RTP Session")
+                    .blurb("RTPSession to take receiver reports from in adaptive mode")
+                    .write_only()
+                    .build(),
             ]
         });
 
@@ -807,7 +1048,7 @@ impl ObjectImpl for RaptorqEnc {
 
     fn set_property(
         &self,
-        _obj: &Self::Type,
+        obj: &Self::Type,
         _id: usize,
         value: &glib::Value,
         pspec: &glib::ParamSpec,
@@ -843,6 +1084,25 @@ impl ObjectImpl for RaptorqEnc {
                 let pt = value.get().expect("type checked upstream");
                 settings.pt = pt;
             }
+            "adaptive-repair" => {
+                let mut settings = self.settings.lock().unwrap();
+                let adaptive_repair = value.get().expect("type checked upstream");
+                settings.adaptive_repair = adaptive_repair;
+            }
+            "min-overhead" => {
+                let mut settings = self.settings.lock().unwrap();
+                let min_overhead = value.get().expect("type checked upstream");
+                settings.min_overhead = min_overhead;
+            }
+            "max-overhead" => {
+                let mut settings = self.settings.lock().unwrap();
+                let max_overhead = value.get().expect("type checked upstream");
+                settings.max_overhead = max_overhead;
+            }
+            "rtp-session" => {
+                let session = value.get().expect("type checked upstream");
+                self.set_rtp_session(obj, session);
+            }
             _ => unimplemented!(),
         }
     }
@@ -873,6 +1133,18 @@ impl ObjectImpl for RaptorqEnc {
                 let settings = self.settings.lock().unwrap();
                 settings.pt.to_value()
             }
+            "adaptive-repair" => {
+                let settings = self.settings.lock().unwrap();
+                settings.adaptive_repair.to_value()
+            }
+            "min-overhead" => {
+                let settings = self.settings.lock().unwrap();
+                settings.min_overhead.to_value()
+            }
+            "max-overhead" => {
+                let settings = self.settings.lock().unwrap();
+                settings.max_overhead.to_value()
+            }
             _ => unimplemented!(),
         }
     }
@@ -884,6 +1156,10 @@ impl ObjectImpl for RaptorqEnc {
         obj.add_pad(&self.srcpad).unwrap();
         obj.add_pad(&self.srcpad_fec).unwrap();
     }
+
+    fn dispose(&self, obj: &Self::Type) {
+        self.set_rtp_session(obj, None);
+    }
 }
 
 impl GstObjectImpl for RaptorqEnc {}
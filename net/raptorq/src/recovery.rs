@@ -0,0 +1,108 @@
+// Copyright (C) 2022 Tomasz Andrzejak <andreiltd@gmail.com>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use gst::prelude::*;
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Stats {
+    pub recv: u64,
+    pub lost: u64,
+    pub recovered: u64,
+}
+
+impl Stats {
+    pub fn to_structure(
+        self,
+        name: &str,
+        media_packets: u64,
+        repair_packets: u64,
+    ) -> gst::Structure {
+        gst::Structure::builder(name)
+            .field("received-packets", self.recv)
+            .field("lost-packets", self.lost)
+            .field("recovered-packets", self.recovered)
+            .field("buffered-media-packets", media_packets)
+            .field("buffered-repair-packets", repair_packets)
+            .build()
+    }
+}
+
+// Keeps track of how long repair packets are allowed to wait for the
+// media packets they protect. Expiration is driven by the arrival time
+// of the media packets, so that it also works when the pipeline is not
+// running in real time.
+#[derive(Debug, Default)]
+pub struct RepairWindow {
+    window: Option<gst::ClockTime>,
+    max_arrival_time: Option<gst::ClockTime>,
+    expirations: BTreeMap<u64, Option<gst::ClockTime>>,
+}
+
+impl RepairWindow {
+    pub fn set_window(&mut self, window: Option<gst::ClockTime>) {
+        self.window = window;
+    }
+
+    pub fn window(&self) -> Option<gst::ClockTime> {
+        self.window
+    }
+
+    pub fn max_arrival_time(&self) -> Option<gst::ClockTime> {
+        self.max_arrival_time
+    }
+
+    pub fn update_arrival_time(&mut self, now: Option<gst::ClockTime>) {
+        self.max_arrival_time = self.max_arrival_time.opt_max(now).or(now);
+    }
+
+    pub fn schedule(&mut self, key: u64) {
+        let expire_at = self.max_arrival_time.opt_add(self.window);
+        let scheduled = self.expirations.entry(key).or_insert(expire_at);
+
+        // Update already scheduled expiration if a new value happens to be earlier
+        *scheduled = scheduled.opt_min(expire_at);
+    }
+
+    pub fn remove(&mut self, key: u64) {
+        self.expirations.remove(&key);
+    }
+
+    pub fn expired(&self) -> Vec<u64> {
+        self.expirations
+            .iter()
+            .filter_map(|(&key, &expiration)| {
+                if self.max_arrival_time.opt_gt(expiration) == Some(true) {
+                    Some(key)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.expirations.len()
+    }
+
+    pub fn reset(&mut self) {
+        self.expirations.clear();
+        self.max_arrival_time = gst::ClockTime::NONE;
+    }
+}
+
+// Expand cyclic sequence numbers to u64, start from u16::MAX so we
+// never overflow substraction.
+pub fn extend_seqnum(prev_seq: Option<u64>, seq: u16) -> u64 {
+    let prev_seq = prev_seq.unwrap_or(65_535 + seq as u64);
+    let delta = gst_rtp::compare_seqnum(prev_seq as u16, seq);
+
+    match delta.is_negative() {
+        true => prev_seq - delta.unsigned_abs() as u64,
+        false => prev_seq + delta.unsigned_abs() as u64,
+    }
+}
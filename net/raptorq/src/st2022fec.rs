@@ -0,0 +1,242 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+pub const RTP_HEADER_LEN: usize = 12;
+pub const FEC_HEADER_LEN: usize = 16;
+
+// SMPTE 2022-1, section 8.3: 1 <= L <= 20, 4 <= D <= 20 and L * D <= 100
+pub const MIN_COLUMNS: u32 = 1;
+pub const MAX_COLUMNS: u32 = 20;
+pub const MIN_ROWS: u32 = 4;
+pub const MAX_ROWS: u32 = 20;
+pub const MAX_MATRIX_SIZE: u32 = 100;
+
+// SMPTE 2022-1, section 7.3, FEC header based on RFC2733 with the
+// extension bit set:
+//
+//  0                   1                   2                   3
+//  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+// |      SNBase low bits          |        Length Recovery        |
+// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+// |E| PT recovery |                    Mask                       |
+// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+// |                          TS recovery                          |
+// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+// |X|D|type |index|    Offset     |      NA       |SNBase ext bits|
+// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FecHeader {
+    pub sn_base: u16,
+    pub length_recovery: u16,
+    pub pt_recovery: u8,
+    pub ts_recovery: u32,
+    pub row: bool, // D bit, set for the row FEC stream
+    pub offset: u8,
+    pub na: u8,
+}
+
+impl FecHeader {
+    pub fn encode(&self) -> [u8; FEC_HEADER_LEN] {
+        let mut bytes: [u8; FEC_HEADER_LEN] = [0; FEC_HEADER_LEN];
+
+        bytes[0..2].copy_from_slice(&self.sn_base.to_be_bytes());
+        bytes[2..4].copy_from_slice(&self.length_recovery.to_be_bytes());
+        // E bit is always set, mask is always zero
+        bytes[4] = 0x80 | (self.pt_recovery & 0x7f);
+        bytes[8..12].copy_from_slice(&self.ts_recovery.to_be_bytes());
+        // X bit is zero, type is XOR (0) and index is zero
+        bytes[12] = (self.row as u8) << 6;
+        bytes[13] = self.offset;
+        bytes[14] = self.na;
+        bytes
+    }
+
+    pub fn decode(bytes: [u8; FEC_HEADER_LEN]) -> Self {
+        Self {
+            sn_base: u16::from_be_bytes([bytes[0], bytes[1]]),
+            length_recovery: u16::from_be_bytes([bytes[2], bytes[3]]),
+            pt_recovery: bytes[4] & 0x7f,
+            ts_recovery: u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+            row: bytes[12] & 0x40 != 0,
+            offset: bytes[13],
+            na: bytes[14],
+        }
+    }
+
+    // Extended sequence numbers of the media packets protected by this
+    // FEC packet, relative to the extended SNBase
+    pub fn protected_seqnums(&self, sn_base: u64) -> impl Iterator<Item = u64> {
+        let offset = self.offset as u64;
+        (0..self.na as u64).map(move |n| sn_base + n * offset)
+    }
+}
+
+// XOR protection of a set of RTP packets. The marker bit recovery is
+// carried in the marker bit of the FEC packet as in RFC2733, the padding,
+// extension and CSRC count bits are not protected by SMPTE 2022-1 and are
+// always zero in recovered packets.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Protection {
+    pub marker_recovery: bool,
+    pub pt_recovery: u8,
+    pub length_recovery: u16,
+    pub ts_recovery: u32,
+    pub payload_recovery: Vec<u8>,
+}
+
+impl Protection {
+    pub fn from_fec_packet(header: &FecHeader, marker: bool, payload: &[u8]) -> Self {
+        Self {
+            marker_recovery: marker,
+            pt_recovery: header.pt_recovery,
+            length_recovery: header.length_recovery,
+            ts_recovery: header.ts_recovery,
+            payload_recovery: payload.to_vec(),
+        }
+    }
+
+    // Adds a complete RTP packet, including its header, to the protection.
+    // As in RFC2733 everything after the fixed header is protected as
+    // payload, including the CSRC list and the header extension, so their
+    // bytes are recovered but the CC and X bits announcing them are not.
+    // A recovered packet is thus only identical to the lost one for media
+    // streams without CSRCs or header extensions, as SMPTE 2022-1 expects.
+    pub fn add(&mut self, packet: &[u8]) {
+        assert!(packet.len() >= RTP_HEADER_LEN);
+
+        let payload = &packet[RTP_HEADER_LEN..];
+
+        self.marker_recovery ^= packet[1] & 0x80 != 0;
+        self.pt_recovery ^= packet[1] & 0x7f;
+        self.length_recovery ^= payload.len() as u16;
+        self.ts_recovery ^= u32::from_be_bytes([packet[4], packet[5], packet[6], packet[7]]);
+
+        if self.payload_recovery.len() < payload.len() {
+            self.payload_recovery.resize(payload.len(), 0);
+        }
+
+        for (r, p) in Iterator::zip(self.payload_recovery.iter_mut(), payload.iter()) {
+            *r ^= *p;
+        }
+    }
+
+    // Rebuilds the single packet missing from the protection, all other
+    // protected packets must have been added already
+    pub fn recover(&self, seq: u16, ssrc: u32) -> Option<Vec<u8>> {
+        let len = self.length_recovery as usize;
+        if len > self.payload_recovery.len() {
+            return None;
+        }
+
+        let mut packet = vec![0; RTP_HEADER_LEN + len];
+
+        packet[0] = 0x80;
+        packet[1] = ((self.marker_recovery as u8) << 7) | self.pt_recovery;
+        packet[2..4].copy_from_slice(&seq.to_be_bytes());
+        packet[4..8].copy_from_slice(&self.ts_recovery.to_be_bytes());
+        packet[8..12].copy_from_slice(&ssrc.to_be_bytes());
+        packet[RTP_HEADER_LEN..].copy_from_slice(&self.payload_recovery[..len]);
+
+        Some(packet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rtp_packet(seq: u16, ts: u32, marker: bool, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0; RTP_HEADER_LEN];
+
+        packet[0] = 0x80;
+        packet[1] = ((marker as u8) << 7) | 33;
+        packet[2..4].copy_from_slice(&seq.to_be_bytes());
+        packet[4..8].copy_from_slice(&ts.to_be_bytes());
+        packet[8..12].copy_from_slice(&42u32.to_be_bytes());
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    #[test]
+    fn test_fec_header_encode() {
+        let header = FecHeader {
+            sn_base: 42,
+            length_recovery: 43,
+            pt_recovery: 44,
+            ts_recovery: 45,
+            row: true,
+            offset: 1,
+            na: 10,
+        };
+
+        let encoded = header.encode();
+        assert_eq!(encoded.len(), FEC_HEADER_LEN);
+        assert_eq!(encoded[4] & 0x80, 0x80);
+
+        let decoded = FecHeader::decode(encoded);
+        assert_eq!(header, decoded);
+    }
+
+    #[test]
+    fn test_fec_header_protected_seqnums() {
+        let header = FecHeader {
+            sn_base: 0,
+            length_recovery: 0,
+            pt_recovery: 0,
+            ts_recovery: 0,
+            row: false,
+            offset: 5,
+            na: 4,
+        };
+
+        assert_eq!(
+            header.protected_seqnums(100).collect::<Vec<_>>(),
+            vec![100, 105, 110, 115]
+        );
+    }
+
+    #[test]
+    fn test_protection_recover() {
+        let packets = vec![
+            rtp_packet(10, 1000, false, &[1, 2, 3, 4, 5]),
+            rtp_packet(11, 2000, true, &[6, 7]),
+            rtp_packet(12, 3000, false, &[8, 9, 10, 11, 12, 13, 14]),
+        ];
+
+        let mut protection = Protection::default();
+        for packet in &packets {
+            protection.add(packet);
+        }
+
+        let header = FecHeader {
+            sn_base: 10,
+            length_recovery: protection.length_recovery,
+            pt_recovery: protection.pt_recovery,
+            ts_recovery: protection.ts_recovery,
+            row: true,
+            offset: 1,
+            na: 3,
+        };
+
+        for lost in 0..packets.len() {
+            let mut recovery = Protection::from_fec_packet(
+                &header,
+                protection.marker_recovery,
+                &protection.payload_recovery,
+            );
+
+            for (i, packet) in packets.iter().enumerate() {
+                if i != lost {
+                    recovery.add(packet);
+                }
+            }
+
+            let recovered = recovery.recover(10 + lost as u16, 42).unwrap();
+            assert_eq!(recovered, packets[lost]);
+        }
+    }
+}
@@ -0,0 +1,743 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use gst::glib;
+
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use gst_rtp::RTPBuffer;
+
+use once_cell::sync::Lazy;
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::recovery::{self, RepairWindow, Stats};
+use crate::st2022fec::{self, FecHeader, Protection};
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "st2022fecdec",
+        gst::DebugColorFlags::empty(),
+        Some("RTP SMPTE 2022-1 FEC Decoder"),
+    )
+});
+
+const DEFAULT_REPAIR_WINDOW: u32 = 1000;
+const DEFAULT_MEDIA_PACKETS_RESET_THRESHOLD: u32 = 5000;
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    repair_window: u32,
+    media_packets_reset_threshold: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            repair_window: DEFAULT_REPAIR_WINDOW,
+            media_packets_reset_threshold: DEFAULT_MEDIA_PACKETS_RESET_THRESHOLD,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MediaPacketItem {
+    arrival_time: Option<gst::ClockTime>,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+struct RepairPacketItem {
+    header: FecHeader,
+    marker: bool,
+    payload: Vec<u8>,
+}
+
+// Row and column FEC packets can share the same SNBase, so both the
+// extended SNBase and the direction make the key of a repair packet
+fn repair_packet_key(sn_base: u64, row: bool) -> u64 {
+    (sn_base << 1) | row as u64
+}
+
+#[derive(Default)]
+struct State {
+    media_packets: BTreeMap<u64, MediaPacketItem>,
+    repair_packets: BTreeMap<u64, RepairPacketItem>,
+    extended_media_seq: Option<u64>,
+    ssrc: Option<u32>,
+    media_packets_reset_threshold: usize,
+    repair_window: RepairWindow,
+    stats: Stats,
+}
+
+impl State {
+    fn drop_repair_packet(&mut self, key: u64) {
+        self.repair_packets.remove(&key);
+        self.repair_window.remove(key);
+    }
+
+    fn expire_packets(&mut self) -> Vec<u64> {
+        let expired = self.repair_window.expired();
+
+        for key in &expired {
+            self.drop_repair_packet(*key);
+        }
+
+        // Media packets are only kept for as long as a repair packet
+        // referencing them could still arrive
+        if let Some(oldest) = self
+            .repair_window
+            .max_arrival_time()
+            .opt_checked_sub(self.repair_window.window())
+            .ok()
+            .flatten()
+        {
+            self.media_packets.retain(|_, packet| {
+                packet
+                    .arrival_time
+                    .map_or(true, |arrival_time| arrival_time >= oldest)
+            });
+        }
+
+        expired
+    }
+}
+
+pub struct St2022FecDec {
+    sinkpad: gst::Pad,
+    srcpad: gst::Pad,
+    sinkpads_fec: Mutex<Vec<gst::Pad>>,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl St2022FecDec {
+    fn process_repair_packets(
+        &self,
+        element: &super::St2022FecDec,
+        state: &mut State,
+    ) -> Vec<gst::Buffer> {
+        let mut recovered_packets = Vec::new();
+
+        let ssrc = match state.ssrc {
+            Some(ssrc) => ssrc,
+            None => return recovered_packets,
+        };
+
+        // With 2D FEC a packet recovered from a row can make a column
+        // recoverable and the other way around, so iterate until we
+        // can't recover anything anymore.
+        loop {
+            let mut recovered = None;
+
+            for (&key, packet) in state.repair_packets.iter() {
+                let sn_base = key >> 1;
+
+                let missing = packet
+                    .header
+                    .protected_seqnums(sn_base)
+                    .filter(|seq| !state.media_packets.contains_key(seq))
+                    .collect::<Vec<_>>();
+
+                match missing.len() {
+                    0 => {
+                        recovered = Some((key, None));
+                        break;
+                    }
+                    1 => (),
+                    // XOR parity can only recover a single missing packet
+                    _ => continue,
+                }
+
+                let mut protection =
+                    Protection::from_fec_packet(&packet.header, packet.marker, &packet.payload);
+
+                for seq in packet.header.protected_seqnums(sn_base) {
+                    if let Some(media_packet) = state.media_packets.get(&seq) {
+                        protection.add(&media_packet.data);
+                    }
+                }
+
+                let seq = missing[0];
+                recovered = Some((key, protection.recover(seq as u16, ssrc).map(|d| (seq, d))));
+                break;
+            }
+
+            let (key, data) = match recovered {
+                Some(recovered) => recovered,
+                None => break,
+            };
+
+            state.drop_repair_packet(key);
+
+            let (seq, data) = match data {
+                Some(data) => data,
+                None => {
+                    gst::trace!(
+                        CAT,
+                        obj: element,
+                        "All packets received or recovery failed, dropping repair packet ({})",
+                        key >> 1
+                    );
+                    continue;
+                }
+            };
+
+            let arrival_time = state.repair_window.max_arrival_time();
+
+            state.media_packets.insert(
+                seq,
+                MediaPacketItem {
+                    arrival_time,
+                    data: data.clone(),
+                },
+            );
+
+            let mut buf = gst::Buffer::from_slice(data);
+            let buf_mut = buf.get_mut().unwrap();
+            buf_mut.set_dts(arrival_time);
+
+            {
+                let rtpbuf = RTPBuffer::from_buffer_readable(&buf).unwrap();
+
+                gst::debug!(
+                    CAT,
+                    obj: element,
+                    "Succesfully recovered packet: seqnum: {}, len: {}, ts: {}",
+                    rtpbuf.seq(),
+                    rtpbuf.payload_size(),
+                    rtpbuf.timestamp(),
+                );
+            }
+
+            state.stats.lost += 1;
+            state.stats.recovered += 1;
+            recovered_packets.push(buf);
+        }
+
+        recovered_packets
+    }
+
+    fn push_recovered_packets(
+        &self,
+        recovered_packets: Vec<gst::Buffer>,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        for buf in recovered_packets {
+            self.srcpad.push(buf)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn store_media_packet(
+        &self,
+        element: &super::St2022FecDec,
+        state: &mut State,
+        buffer: &gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let (this_seq, ssrc) = {
+            let rtpbuf = RTPBuffer::from_buffer_readable(buffer).map_err(|err| {
+                gst::error!(CAT, obj: element, "Failed to map rtp buffer : {}", err);
+                gst::FlowError::Error
+            })?;
+
+            gst::trace!(
+                CAT,
+                obj: element,
+                "New data packet, seq {}, ts {}",
+                rtpbuf.seq(),
+                rtpbuf.timestamp()
+            );
+
+            (
+                recovery::extend_seqnum(state.extended_media_seq, rtpbuf.seq()),
+                rtpbuf.ssrc(),
+            )
+        };
+
+        let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
+        let arrival_time = buffer.dts_or_pts();
+
+        state.media_packets.insert(
+            this_seq,
+            MediaPacketItem {
+                arrival_time,
+                data: map.to_vec(),
+            },
+        );
+
+        state.stats.recv += 1;
+        state.extended_media_seq = Some(this_seq);
+        state.ssrc = Some(ssrc);
+        state.repair_window.update_arrival_time(arrival_time);
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn sink_chain(
+        &self,
+        _pad: &gst::Pad,
+        element: &super::St2022FecDec,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state = self.state.lock().unwrap();
+        self.store_media_packet(element, &mut state, &buffer)?;
+
+        // Retire the packets that have been around for too long
+        let expired = state.expire_packets();
+        for key in expired {
+            gst::trace!(
+                CAT,
+                obj: element,
+                "Repair packet ({}) dropped, because max wait time has been exceeded",
+                (key >> 1) as u16
+            );
+        }
+
+        // This is the fuse to make sure we are not growing RTP storage indefinitely.
+        let thresh = state.media_packets_reset_threshold;
+        if thresh > 0 && state.media_packets.len() >= thresh {
+            gst::warning!(
+                CAT,
+                obj: element,
+                "Too many buffered media packets, resetting decoder. This might \
+                 be because media packets have no valid timestamps.",
+            );
+
+            Self::reset_state(&mut state);
+        }
+
+        let recovered_packets = self.process_repair_packets(element, &mut state);
+        drop(state);
+
+        self.push_recovered_packets(recovered_packets)?;
+        self.srcpad.push(buffer)
+    }
+
+    fn fec_sink_chain(
+        &self,
+        _pad: &gst::Pad,
+        element: &super::St2022FecDec,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let rtpbuf = RTPBuffer::from_buffer_readable(&buffer).map_err(|err| {
+            gst::error!(CAT, obj: element, "Failed to map rtp buffer : {}", err);
+            gst::FlowError::Error
+        })?;
+
+        let payload = rtpbuf.payload().unwrap();
+        if payload.len() < st2022fec::FEC_HEADER_LEN {
+            gst::error!(CAT, obj: element, "Unexpected rtp fec payload size");
+            return Err(gst::FlowError::Error);
+        }
+
+        let header = FecHeader::decode(
+            payload[0..st2022fec::FEC_HEADER_LEN]
+                .try_into()
+                .expect("checked size above"),
+        );
+
+        gst::trace!(
+            CAT,
+            obj: element,
+            "New repair packet, SNBase: {}, row: {}, offset: {}, NA: {}",
+            header.sn_base,
+            header.row,
+            header.offset,
+            header.na,
+        );
+
+        if header.offset == 0 || header.na == 0 {
+            gst::warning!(CAT, obj: element, "Invalid repair packet {:?}", header);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        // SNBase is a media sequence number, so it has to be extended
+        // against the same reference as the media packets, otherwise
+        // the two would disagree around a 16-bit wrap
+        let this_seq = recovery::extend_seqnum(state.extended_media_seq, header.sn_base);
+        if state.extended_media_seq.is_none() {
+            state.extended_media_seq = Some(this_seq);
+        }
+
+        let key = repair_packet_key(this_seq, header.row);
+        state.repair_window.schedule(key);
+
+        state.repair_packets.insert(
+            key,
+            RepairPacketItem {
+                header,
+                marker: rtpbuf.is_marker(),
+                payload: payload[st2022fec::FEC_HEADER_LEN..].to_vec(),
+            },
+        );
+
+        assert_eq!(state.repair_packets.len(), state.repair_window.len());
+
+        drop(rtpbuf);
+
+        let recovered_packets = self.process_repair_packets(element, &mut state);
+        drop(state);
+
+        self.push_recovered_packets(recovered_packets)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, element: &super::St2022FecDec, event: gst::Event) -> bool {
+        gst::debug!(CAT, "Handling event {:?}", event);
+        use gst::EventView;
+
+        if let EventView::FlushStop(_) = event.view() {
+            self.reset(element);
+        }
+
+        pad.event_default(Some(element), event)
+    }
+
+    fn fec_sink_event(
+        &self,
+        pad: &gst::Pad,
+        element: &super::St2022FecDec,
+        event: gst::Event,
+    ) -> bool {
+        gst::debug!(CAT, "Handling event {:?}", event);
+
+        pad.event_default(Some(element), event)
+    }
+
+    fn iterate_internal_links(
+        &self,
+        pad: &gst::Pad,
+        _element: &super::St2022FecDec,
+    ) -> gst::Iterator<gst::Pad> {
+        if pad == &self.srcpad {
+            gst::Iterator::from_vec(vec![self.sinkpad.clone()])
+        } else if pad == &self.sinkpad {
+            gst::Iterator::from_vec(vec![self.srcpad.clone()])
+        } else {
+            gst::Iterator::from_vec(vec![])
+        }
+    }
+
+    fn start(&self, element: &super::St2022FecDec) {
+        let settings = self.settings.lock().unwrap();
+
+        let repair_window = gst::ClockTime::from_mseconds(settings.repair_window as u64);
+        let media_packets_reset_threshold = settings.media_packets_reset_threshold as usize;
+
+        gst::debug!(
+            CAT,
+            obj: element,
+            "Starting with repair window {}",
+            repair_window
+        );
+
+        let mut state = self.state.lock().unwrap();
+
+        state.repair_window.set_window(Some(repair_window));
+        state.media_packets_reset_threshold = media_packets_reset_threshold;
+        Self::reset_state(&mut state);
+    }
+
+    fn stop(&self, element: &super::St2022FecDec) {
+        self.reset(element);
+    }
+
+    fn reset(&self, _element: &super::St2022FecDec) {
+        let mut state = self.state.lock().unwrap();
+        Self::reset_state(&mut state);
+    }
+
+    fn reset_state(state: &mut State) {
+        state.media_packets.clear();
+        state.repair_packets.clear();
+        state.extended_media_seq = None;
+        state.ssrc = None;
+        state.repair_window.reset();
+        state.stats = Default::default();
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for St2022FecDec {
+    const NAME: &'static str = "GstSt2022FecDec";
+    type Type = super::St2022FecDec;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this, element| this.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this, element| this.sink_event(pad, element, event),
+                )
+            })
+            .iterate_internal_links_function(|pad, parent| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || gst::Iterator::from_vec(vec![]),
+                    |this, element| this.iterate_internal_links(pad, element),
+                )
+            })
+            .flags(gst::PadFlags::PROXY_CAPS)
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .iterate_internal_links_function(|pad, parent| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || gst::Iterator::from_vec(vec![]),
+                    |this, element| this.iterate_internal_links(pad, element),
+                )
+            })
+            .flags(gst::PadFlags::PROXY_CAPS)
+            .build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            sinkpads_fec: Mutex::new(Vec::new()),
+            settings: Mutex::new(Default::default()),
+            state: Mutex::new(Default::default()),
+        }
+    }
+}
+
+impl ObjectImpl for St2022FecDec {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("repair-window")
+                    .nick("Repair Window (ms)")
+                    .blurb("The amount of time to keep media and repair packets around for recovery (in ms)")
+                    .maximum(u32::MAX - 1)
+                    .default_value(DEFAULT_REPAIR_WINDOW)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("media-packets-reset-threshold")
+                    .nick("Media Packets Reset Threshold")
+                    .blurb("This is the maximum allowed number of buffered packets, before we reset the decoder. \
+                     It can only be triggered if packets have no valid timestamps, (0 - disable)")
+                    .maximum(u32::MAX - 1)
+                    .default_value(DEFAULT_MEDIA_PACKETS_RESET_THRESHOLD)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Various statistics")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "repair-window" => {
+                let mut settings = self.settings.lock().unwrap();
+                let val = value.get().expect("type checked upstream");
+                settings.repair_window = val;
+            }
+            "media-packets-reset-threshold" => {
+                let mut settings = self.settings.lock().unwrap();
+                let val = value.get().expect("type checked upstream");
+                settings.media_packets_reset_threshold = val;
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "repair-window" => {
+                let settings = self.settings.lock().unwrap();
+                settings.repair_window.to_value()
+            }
+            "media-packets-reset-threshold" => {
+                let settings = self.settings.lock().unwrap();
+                settings.media_packets_reset_threshold.to_value()
+            }
+            "stats" => {
+                let state = self.state.lock().unwrap();
+
+                state
+                    .stats
+                    .to_structure(
+                        "application/x-rtp-st2022fecdec-stats",
+                        state.media_packets.len() as u64,
+                        state.repair_packets.len() as u64,
+                    )
+                    .to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+}
+
+impl GstObjectImpl for St2022FecDec {}
+
+impl ElementImpl for St2022FecDec {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP SMPTE 2022-1 FEC Decoder",
+                "RTP SMPTE 2022-1 FEC Decoding",
+                "Performs row/column parity FEC as defined in SMPTE 2022-1",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::builder("application/x-rtp").build();
+
+            let srcpad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let sinkpad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let sinkpad_fec_template = gst::PadTemplate::new(
+                "fec_%u",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Request,
+                &caps,
+            )
+            .unwrap();
+
+            vec![srcpad_template, sinkpad_template, sinkpad_fec_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::ReadyToPaused => {
+                self.start(element);
+            }
+            gst::StateChange::PausedToReady => {
+                self.stop(element);
+            }
+            _ => (),
+        }
+
+        self.parent_change_state(element, transition)
+    }
+
+    fn request_new_pad(
+        &self,
+        element: &Self::Type,
+        templ: &gst::PadTemplate,
+        name: Option<String>,
+        _caps: Option<&gst::Caps>,
+    ) -> Option<gst::Pad> {
+        let mut sinkpads_fec = self.sinkpads_fec.lock().unwrap();
+
+        // One column and one row FEC stream
+        if sinkpads_fec.len() >= 2 {
+            gst::element_error!(
+                element,
+                gst::CoreError::Pad,
+                ["Not accepting more than two FEC streams"]
+            );
+
+            return None;
+        }
+
+        let sinkpad_fec = gst::Pad::builder_with_template(templ, name.as_deref())
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this, element| this.fec_sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this, element| this.fec_sink_event(pad, element, event),
+                )
+            })
+            .iterate_internal_links_function(|pad, parent| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || gst::Iterator::from_vec(vec![]),
+                    |this, element| this.iterate_internal_links(pad, element),
+                )
+            })
+            .build();
+
+        sinkpad_fec.set_active(true).unwrap();
+        sinkpads_fec.push(sinkpad_fec.clone());
+
+        drop(sinkpads_fec);
+
+        element.add_pad(&sinkpad_fec).unwrap();
+
+        Some(sinkpad_fec)
+    }
+
+    fn release_pad(&self, element: &Self::Type, pad: &gst::Pad) {
+        let mut sinkpads_fec = self.sinkpads_fec.lock().unwrap();
+
+        if let Some(idx) = sinkpads_fec.iter().position(|p| p == pad) {
+            let pad = sinkpads_fec.remove(idx);
+            drop(sinkpads_fec);
+
+            pad.set_active(false).unwrap();
+            element.remove_pad(&pad).unwrap();
+        }
+    }
+}
@@ -0,0 +1,693 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use gst::{element_error, error_msg, glib};
+
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use gst_rtp::rtp_buffer::*;
+use gst_rtp::RTPBuffer;
+
+use once_cell::sync::Lazy;
+
+use std::sync::Mutex;
+
+use crate::st2022fec::{self, FecHeader, Protection};
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "st2022fecenc",
+        gst::DebugColorFlags::empty(),
+        Some("RTP SMPTE 2022-1 FEC Encoder"),
+    )
+});
+
+const DEFAULT_COLUMNS: u32 = 10;
+const DEFAULT_ROWS: u32 = 10;
+const DEFAULT_ENABLE_COLUMN_FEC: bool = true;
+const DEFAULT_ENABLE_ROW_FEC: bool = true;
+const DEFAULT_PT: u32 = 96;
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    columns: u32,
+    rows: u32,
+    enable_column_fec: bool,
+    enable_row_fec: bool,
+    pt: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            columns: DEFAULT_COLUMNS,
+            rows: DEFAULT_ROWS,
+            enable_column_fec: DEFAULT_ENABLE_COLUMN_FEC,
+            enable_row_fec: DEFAULT_ENABLE_ROW_FEC,
+            pt: DEFAULT_PT,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    columns: usize,
+    rows: usize,
+    enable_column_fec: bool,
+    enable_row_fec: bool,
+    pt: u8,
+    // Position of the next media packet in the L x D matrix
+    position: usize,
+    last_seq: Option<u16>,
+    column_seq: u16,
+    row_seq: u16,
+    // Protection of every column of the matrix, together with the
+    // sequence number of the first packet in the column
+    column_protections: Vec<Option<(u16, Protection)>>,
+    row_protection: Option<(u16, Protection)>,
+    clock_rate: Option<u32>,
+}
+
+pub struct St2022FecEnc {
+    sinkpad: gst::Pad,
+    srcpad: gst::Pad,
+    srcpad_fec_column: gst::Pad,
+    srcpad_fec_row: gst::Pad,
+    settings: Mutex<Settings>,
+    state: Mutex<Option<State>>,
+}
+
+impl St2022FecEnc {
+    fn build_fec_packet(
+        state: &State,
+        seq: u16,
+        sn_base: u16,
+        protection: Protection,
+        row: bool,
+        buffer: &gst::Buffer,
+        rtpts: u32,
+    ) -> gst::Buffer {
+        let header = FecHeader {
+            sn_base,
+            length_recovery: protection.length_recovery,
+            pt_recovery: protection.pt_recovery,
+            ts_recovery: protection.ts_recovery,
+            row,
+            offset: if row { 1 } else { state.columns as u8 },
+            na: if row {
+                state.columns as u8
+            } else {
+                state.rows as u8
+            },
+        }
+        .encode();
+
+        let fecsz = header.len() + protection.payload_recovery.len();
+        let mut buf = gst::Buffer::new_rtp_with_sizes(fecsz as u32, 0, 0).unwrap();
+
+        {
+            let buf_mut = buf.get_mut().unwrap();
+            buf_mut.set_pts(buffer.pts());
+            buf_mut.set_dts(buffer.dts());
+
+            let mut rtpbuf = RTPBuffer::from_buffer_writable(buf_mut).unwrap();
+
+            rtpbuf.set_payload_type(state.pt);
+            rtpbuf.set_seq(seq);
+            rtpbuf.set_timestamp(rtpts);
+            rtpbuf.set_marker(protection.marker_recovery);
+
+            let payload = rtpbuf.payload_mut().unwrap();
+            payload[0..header.len()].copy_from_slice(&header);
+            payload[header.len()..].copy_from_slice(&protection.payload_recovery);
+        }
+
+        buf
+    }
+
+    fn sink_chain(
+        &self,
+        _pad: &gst::Pad,
+        element: &super::St2022FecEnc,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state_guard = self.state.lock().unwrap();
+        let state = state_guard.as_mut().ok_or(gst::FlowError::NotNegotiated)?;
+
+        let (curr_seq, rtpts) = match RTPBuffer::from_buffer_readable(&buffer) {
+            Ok(rtpbuf) => (rtpbuf.seq(), rtpbuf.timestamp()),
+            Err(_) => {
+                gst::error!(CAT, obj: element, "Mapping to RTP packet failed");
+                return Err(gst::FlowError::NotSupported);
+            }
+        };
+
+        if let Some(last_seq) = state.last_seq {
+            if last_seq.overflowing_add(1).0 != curr_seq {
+                gst::error!(CAT, obj: element, "Got out of sequence packets");
+                return Err(gst::FlowError::NotSupported);
+            }
+        }
+
+        state.last_seq = Some(curr_seq);
+
+        let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
+
+        let column = state.position % state.columns;
+        let row = state.position / state.columns;
+
+        let mut column_packet = None;
+        let mut row_packet = None;
+
+        if state.enable_column_fec {
+            let (_, protection) = state.column_protections[column]
+                .get_or_insert_with(|| (curr_seq, Protection::default()));
+            protection.add(&map);
+
+            // Last packet of the column, SMPTE 2022-1, section 7.4
+            if row == state.rows - 1 {
+                let (sn_base, protection) = state.column_protections[column].take().unwrap();
+                let seq = state.column_seq;
+                state.column_seq = seq.overflowing_add(1).0;
+
+                gst::trace!(
+                    CAT,
+                    obj: element,
+                    "Column {} complete, SNBase {}",
+                    column,
+                    sn_base
+                );
+
+                column_packet = Some(Self::build_fec_packet(
+                    state, seq, sn_base, protection, false, &buffer, rtpts,
+                ));
+            }
+        }
+
+        if state.enable_row_fec {
+            let (_, protection) = state
+                .row_protection
+                .get_or_insert_with(|| (curr_seq, Protection::default()));
+            protection.add(&map);
+
+            // Last packet of the row, SMPTE 2022-1, section 7.4
+            if column == state.columns - 1 {
+                let (sn_base, protection) = state.row_protection.take().unwrap();
+                let seq = state.row_seq;
+                state.row_seq = seq.overflowing_add(1).0;
+
+                gst::trace!(CAT, obj: element, "Row {} complete, SNBase {}", row, sn_base);
+
+                row_packet = Some(Self::build_fec_packet(
+                    state, seq, sn_base, protection, true, &buffer, rtpts,
+                ));
+            }
+        }
+
+        state.position = (state.position + 1) % (state.columns * state.rows);
+
+        drop(map);
+        drop(state_guard);
+
+        let res = self.srcpad.push(buffer);
+
+        for (pad, packet) in [
+            (&self.srcpad_fec_column, column_packet),
+            (&self.srcpad_fec_row, row_packet),
+        ] {
+            if let Some(packet) = packet {
+                // Not linking one of the FEC pads is fine, e.g. for 1D FEC
+                match pad.push(packet) {
+                    Ok(_) | Err(gst::FlowError::NotLinked) => (),
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        res
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, element: &super::St2022FecEnc, event: gst::Event) -> bool {
+        gst::debug!(CAT, "Handling event {:?}", event);
+        use gst::EventView;
+
+        match event.view() {
+            EventView::FlushStop(_) => {
+                if let Err(err) = self.start(element) {
+                    element_error!(
+                        element,
+                        gst::CoreError::Event,
+                        ["Failed to start encoder after flush stop {:?}", err]
+                    );
+                    return false;
+                }
+            }
+            EventView::Caps(ev) => {
+                let caps = ev.caps();
+                gst::info!(CAT, obj: pad, "Got caps {:?}", caps);
+
+                let mut state_guard = self.state.lock().unwrap();
+
+                if let Some(state) = state_guard.as_mut() {
+                    let s = caps.structure(0).unwrap();
+
+                    if let Ok(clock_rate) = s.get::<i32>("clock-rate") {
+                        if clock_rate <= 0 {
+                            element_error!(element, gst::CoreError::Event, ["Invalid clock rate"]);
+                            return false;
+                        }
+
+                        state.clock_rate = Some(clock_rate as u32);
+                    }
+                }
+            }
+            EventView::Segment(ev) => {
+                let state_guard = self.state.lock().unwrap();
+
+                if let Some(state) = state_guard.as_ref() {
+                    let segment = ev.segment().clone();
+                    if segment.downcast_ref::<gst::ClockTime>().is_none() {
+                        element_error!(
+                            element,
+                            gst::CoreError::Event,
+                            ["Only time segments are supported"]
+                        );
+                        return false;
+                    }
+
+                    // Push stream events on FEC srcpads as well
+                    let caps = gst::Caps::builder("application/x-rtp")
+                        .field("payload", state.pt as i32)
+                        .field("ssrc", 0i32)
+                        .field("clock-rate", state.clock_rate.unwrap_or(0) as i32)
+                        .field("encoding-name", "SMPTE2022-1-FEC")
+                        .field("columns", state.columns.to_string())
+                        .field("rows", state.rows.to_string())
+                        .build();
+
+                    drop(state_guard);
+
+                    for (pad, name) in [
+                        (&self.srcpad_fec_column, "fec-column"),
+                        (&self.srcpad_fec_row, "fec-row"),
+                    ] {
+                        let stream_id = pad.create_stream_id(element, Some(name)).to_string();
+
+                        pad.push_event(gst::event::StreamStart::new(&stream_id));
+                        pad.push_event(gst::event::Caps::new(&caps));
+                        pad.push_event(gst::event::Segment::new(&segment));
+                    }
+                }
+            }
+            EventView::Eos(_) => {
+                self.srcpad_fec_column.push_event(gst::event::Eos::new());
+                self.srcpad_fec_row.push_event(gst::event::Eos::new());
+            }
+            _ => (),
+        }
+
+        pad.event_default(Some(element), event)
+    }
+
+    fn iterate_internal_links(
+        &self,
+        pad: &gst::Pad,
+        _element: &super::St2022FecEnc,
+    ) -> gst::Iterator<gst::Pad> {
+        if pad == &self.sinkpad {
+            gst::Iterator::from_vec(vec![self.srcpad.clone()])
+        } else if pad == &self.srcpad {
+            gst::Iterator::from_vec(vec![self.sinkpad.clone()])
+        } else {
+            gst::Iterator::from_vec(vec![])
+        }
+    }
+
+    fn start(&self, element: &super::St2022FecEnc) -> Result<(), gst::ErrorMessage> {
+        let settings = self.settings.lock().unwrap();
+
+        let columns = settings.columns as usize;
+        let rows = settings.rows as usize;
+
+        if !settings.enable_column_fec && !settings.enable_row_fec {
+            gst::element_error!(
+                element,
+                gst::CoreError::Failed,
+                ["At least one of column or row FEC must be enabled"]
+            );
+            return Err(error_msg!(
+                gst::CoreError::Failed,
+                ["At least one of column or row FEC must be enabled"]
+            ));
+        }
+
+        if settings.columns * settings.rows > st2022fec::MAX_MATRIX_SIZE {
+            gst::element_error!(
+                element,
+                gst::LibraryError::Settings,
+                [
+                    "FEC matrix of {}x{} packets exceeds the maximum of {} packets",
+                    columns,
+                    rows,
+                    st2022fec::MAX_MATRIX_SIZE
+                ]
+            );
+            return Err(error_msg!(
+                gst::LibraryError::Settings,
+                [
+                    "FEC matrix of {}x{} packets exceeds the maximum of {} packets",
+                    columns,
+                    rows,
+                    st2022fec::MAX_MATRIX_SIZE
+                ]
+            ));
+        }
+
+        gst::info!(
+            CAT,
+            obj: element,
+            "Starting SMPTE 2022-1 FEC Encoder, L: {}, D: {}, column FEC: {}, row FEC: {}",
+            columns,
+            rows,
+            settings.enable_column_fec,
+            settings.enable_row_fec,
+        );
+
+        let clock_rate = self
+            .state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|state| state.clock_rate);
+
+        *self.state.lock().unwrap() = Some(State {
+            columns,
+            rows,
+            enable_column_fec: settings.enable_column_fec,
+            enable_row_fec: settings.enable_row_fec,
+            pt: settings.pt as u8,
+            position: 0,
+            last_seq: None,
+            column_seq: 0,
+            row_seq: 0,
+            column_protections: vec![None; columns],
+            row_protection: None,
+            clock_rate,
+        });
+
+        Ok(())
+    }
+
+    fn stop(&self, _element: &super::St2022FecEnc) -> Result<(), gst::ErrorMessage> {
+        // Drop state
+        let _ = self.state.lock().unwrap().take();
+        Ok(())
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for St2022FecEnc {
+    const NAME: &'static str = "GstSt2022FecEnc";
+    type Type = super::St2022FecEnc;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this, element| this.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this, element| this.sink_event(pad, element, event),
+                )
+            })
+            .iterate_internal_links_function(|pad, parent| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || gst::Iterator::from_vec(vec![]),
+                    |this, element| this.iterate_internal_links(pad, element),
+                )
+            })
+            .flags(gst::PadFlags::PROXY_CAPS)
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .iterate_internal_links_function(|pad, parent| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || gst::Iterator::from_vec(vec![]),
+                    |this, element| this.iterate_internal_links(pad, element),
+                )
+            })
+            .flags(gst::PadFlags::PROXY_CAPS)
+            .build();
+
+        let templ = klass.pad_template("fec_0").unwrap();
+        let srcpad_fec_column = gst::Pad::builder_with_template(&templ, Some("fec_0"))
+            .iterate_internal_links_function(|pad, parent| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || gst::Iterator::from_vec(vec![]),
+                    |this, element| this.iterate_internal_links(pad, element),
+                )
+            })
+            .build();
+
+        let templ = klass.pad_template("fec_1").unwrap();
+        let srcpad_fec_row = gst::Pad::builder_with_template(&templ, Some("fec_1"))
+            .iterate_internal_links_function(|pad, parent| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || gst::Iterator::from_vec(vec![]),
+                    |this, element| this.iterate_internal_links(pad, element),
+                )
+            })
+            .build();
+
+        Self {
+            sinkpad,
+            srcpad,
+            srcpad_fec_column,
+            srcpad_fec_row,
+            settings: Mutex::new(Default::default()),
+            state: Mutex::new(None),
+        }
+    }
+}
+
+impl ObjectImpl for St2022FecEnc {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("columns")
+                    .nick("Columns")
+                    .blurb("Number of columns (L) of the FEC matrix")
+                    .minimum(st2022fec::MIN_COLUMNS)
+                    .maximum(st2022fec::MAX_COLUMNS)
+                    .default_value(DEFAULT_COLUMNS)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("rows")
+                    .nick("Rows")
+                    .blurb("Number of rows (D) of the FEC matrix")
+                    .minimum(st2022fec::MIN_ROWS)
+                    .maximum(st2022fec::MAX_ROWS)
+                    .default_value(DEFAULT_ROWS)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("enable-column-fec")
+                    .nick("Enable Column FEC")
+                    .blurb("Whether to send column FEC packets on the fec_0 pad")
+                    .default_value(DEFAULT_ENABLE_COLUMN_FEC)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("enable-row-fec")
+                    .nick("Enable Row FEC")
+                    .blurb("Whether to send row FEC packets on the fec_1 pad")
+                    .default_value(DEFAULT_ENABLE_ROW_FEC)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("pt")
+                    .nick("Payload Type")
+                    .blurb("The payload type of FEC packets")
+                    .minimum(96)
+                    .maximum(255)
+                    .default_value(DEFAULT_PT)
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "columns" => {
+                let mut settings = self.settings.lock().unwrap();
+                let columns = value.get().expect("type checked upstream");
+                settings.columns = columns;
+            }
+            "rows" => {
+                let mut settings = self.settings.lock().unwrap();
+                let rows = value.get().expect("type checked upstream");
+                settings.rows = rows;
+            }
+            "enable-column-fec" => {
+                let mut settings = self.settings.lock().unwrap();
+                let enable_column_fec = value.get().expect("type checked upstream");
+                settings.enable_column_fec = enable_column_fec;
+            }
+            "enable-row-fec" => {
+                let mut settings = self.settings.lock().unwrap();
+                let enable_row_fec = value.get().expect("type checked upstream");
+                settings.enable_row_fec = enable_row_fec;
+            }
+            "pt" => {
+                let mut settings = self.settings.lock().unwrap();
+                let pt = value.get().expect("type checked upstream");
+                settings.pt = pt;
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "columns" => {
+                let settings = self.settings.lock().unwrap();
+                settings.columns.to_value()
+            }
+            "rows" => {
+                let settings = self.settings.lock().unwrap();
+                settings.rows.to_value()
+            }
+            "enable-column-fec" => {
+                let settings = self.settings.lock().unwrap();
+                settings.enable_column_fec.to_value()
+            }
+            "enable-row-fec" => {
+                let settings = self.settings.lock().unwrap();
+                settings.enable_row_fec.to_value()
+            }
+            "pt" => {
+                let settings = self.settings.lock().unwrap();
+                settings.pt.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+        obj.add_pad(&self.srcpad_fec_column).unwrap();
+        obj.add_pad(&self.srcpad_fec_row).unwrap();
+    }
+}
+
+impl GstObjectImpl for St2022FecEnc {}
+
+impl ElementImpl for St2022FecEnc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP SMPTE 2022-1 FEC Encoder",
+                "RTP SMPTE 2022-1 FEC Encoding",
+                "Performs row/column parity FEC as defined in SMPTE 2022-1",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::builder("application/x-rtp")
+                .field("clock-rate", gst::IntRange::new(0, std::i32::MAX))
+                .build();
+
+            let srcpad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let sinkpad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let srcpad_fec_column_template = gst::PadTemplate::new(
+                "fec_0",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let srcpad_fec_row_template = gst::PadTemplate::new(
+                "fec_1",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![
+                srcpad_template,
+                sinkpad_template,
+                srcpad_fec_column_template,
+                srcpad_fec_row_template,
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::ReadyToPaused => {
+                self.start(element).map_err(|_| gst::StateChangeError)?;
+            }
+            gst::StateChange::PausedToReady => {
+                self.stop(element).map_err(|_| gst::StateChangeError)?;
+            }
+            _ => (),
+        }
+
+        self.parent_change_state(element, transition)
+    }
+}
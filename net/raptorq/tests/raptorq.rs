@@ -616,3 +616,84 @@ fn test_raptorq_repair_window_tolerance() {
         0
     );
 }
+
+fn adaptive_repair_packets(
+    protected_packets: u32,
+    repair_packets: u32,
+    min_overhead: u32,
+    fraction_lost: u32,
+) -> u32 {
+    init();
+
+    let enc = gst::ElementFactory::make("raptorqenc", None).unwrap();
+
+    enc.set_property("protected-packets", protected_packets);
+    enc.set_property("repair-packets", repair_packets);
+    enc.set_property("repair-window", 0u32);
+    enc.set_property("adaptive-repair", true);
+    enc.set_property("min-overhead", min_overhead);
+    enc.set_property("max-overhead", 50u32);
+
+    let bus = gst::Bus::new();
+    enc.set_bus(Some(&bus));
+
+    let mut h_enc = gst_check::Harness::with_element(&enc, Some("sink"), Some("src"));
+    let _h_enc_fec = gst_check::Harness::with_element(&enc, None, Some("fec_0"));
+
+    h_enc.set_src_caps_str("application/x-rtp,clock-rate=8000");
+
+    let report = gst::Structure::builder("GstRaptorqLossReport")
+        .field("fraction-lost", fraction_lost)
+        .build();
+    assert!(h_enc.push_upstream_event(gst::event::CustomUpstream::new(report)));
+
+    for i in 0..protected_packets {
+        let mut buf = gst::Buffer::new_rtp_with_sizes(42, 0, 0).unwrap();
+
+        let buf_mut = buf.get_mut().unwrap();
+        buf_mut.set_pts(gst::ClockTime::ZERO);
+
+        let mut rtpbuf = RTPBuffer::from_buffer_writable(buf_mut).unwrap();
+        rtpbuf.set_seq(i as u16);
+
+        drop(rtpbuf);
+
+        assert!(h_enc.push(buf).is_ok());
+    }
+
+    let msg = bus
+        .pop_filtered(&[gst::MessageType::Element])
+        .expect("No repair packets message");
+
+    let s = msg.structure().unwrap();
+    assert_eq!(s.name(), "raptorqenc-repair-packets");
+    assert_eq!(
+        s.get::<u32>("previous-repair-packets").unwrap(),
+        repair_packets
+    );
+    assert_eq!(
+        s.get::<u32>("protected-packets").unwrap(),
+        protected_packets
+    );
+
+    s.get::<u32>("repair-packets").unwrap()
+}
+
+#[test]
+fn test_raptorq_adaptive_repair_max_overhead() {
+    // 25% loss requires 6 repair packets for 10 protected packets, which
+    // is above the 50% budget
+    assert_eq!(adaptive_repair_packets(10, 2, 10, 64), 5);
+}
+
+#[test]
+fn test_raptorq_adaptive_repair_min_overhead() {
+    // No loss requires 2 repair packets, which is below the 30% budget
+    assert_eq!(adaptive_repair_packets(10, 5, 30, 0), 3);
+}
+
+#[test]
+fn test_raptorq_adaptive_repair_loss() {
+    // About 10% loss requires 5 repair packets for 20 protected packets
+    assert_eq!(adaptive_repair_packets(20, 2, 0, 26), 5);
+}
@@ -0,0 +1,401 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+
+use gst_rtp::rtp_buffer::*;
+use gst_rtp::RTPBuffer;
+
+use rand::Rng;
+
+#[must_use]
+struct St2022FecTest {
+    columns: usize,
+    rows: usize,
+    enable_column_fec: bool,
+    enable_row_fec: bool,
+    initial_seq: u16,
+    lost_buffers: Vec<usize>,
+    lost_repair_matrices: Vec<usize>,
+    input_buffers: usize,
+    expect_output_buffers: usize,
+}
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstraptorq::plugin_register_static().expect("Failed to register raptorq plugin");
+    });
+}
+
+impl St2022FecTest {
+    fn new() -> Self {
+        init();
+
+        let enc = gst::ElementFactory::make("st2022fecenc", None).unwrap();
+
+        let columns = enc.property::<u32>("columns") as usize;
+        let rows = enc.property::<u32>("rows") as usize;
+
+        Self {
+            columns,
+            rows,
+            enable_column_fec: true,
+            enable_row_fec: true,
+            initial_seq: 42,
+            lost_buffers: vec![0],
+            lost_repair_matrices: vec![],
+            input_buffers: columns * rows,
+            expect_output_buffers: columns * rows,
+        }
+    }
+
+    fn columns(mut self, columns: usize) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    fn rows(mut self, rows: usize) -> Self {
+        self.rows = rows;
+        self
+    }
+
+    fn enable_column_fec(mut self, enable_column_fec: bool) -> Self {
+        self.enable_column_fec = enable_column_fec;
+        self
+    }
+
+    fn enable_row_fec(mut self, enable_row_fec: bool) -> Self {
+        self.enable_row_fec = enable_row_fec;
+        self
+    }
+
+    fn initial_seq(mut self, initial_seq: u16) -> Self {
+        self.initial_seq = initial_seq;
+        self
+    }
+
+    fn lost_buffers(mut self, lost_buffers: Vec<usize>) -> Self {
+        self.lost_buffers = lost_buffers;
+        self
+    }
+
+    fn lost_repair_matrices(mut self, lost_repair_matrices: Vec<usize>) -> Self {
+        self.lost_repair_matrices = lost_repair_matrices;
+        self
+    }
+
+    fn input_buffers(mut self, input_buffers: usize) -> Self {
+        self.input_buffers = input_buffers;
+        self
+    }
+
+    fn expect_output_buffers(mut self, expect_output_buffers: usize) -> Self {
+        self.expect_output_buffers = expect_output_buffers;
+        self
+    }
+
+    fn run(self) {
+        assert_eq!(self.input_buffers % (self.columns * self.rows), 0);
+
+        // 1. Encoder Setup:
+        let enc = gst::ElementFactory::make("st2022fecenc", None).unwrap();
+
+        enc.set_property("columns", self.columns as u32);
+        enc.set_property("rows", self.rows as u32);
+        enc.set_property("enable-column-fec", self.enable_column_fec);
+        enc.set_property("enable-row-fec", self.enable_row_fec);
+
+        let mut h_enc = gst_check::Harness::with_element(&enc, Some("sink"), Some("src"));
+        let mut h_enc_column = gst_check::Harness::with_element(&enc, None, Some("fec_0"));
+        let mut h_enc_row = gst_check::Harness::with_element(&enc, None, Some("fec_1"));
+
+        h_enc.set_src_caps_str("application/x-rtp,clock-rate=8000");
+
+        // 2. Decoder Setup:
+        let dec = gst::ElementFactory::make("st2022fecdec", None).unwrap();
+
+        let mut h_dec = gst_check::Harness::with_element(&dec, Some("sink"), Some("src"));
+        let mut h_dec_column = gst_check::Harness::with_element(&dec, Some("fec_0"), None);
+        let mut h_dec_row = gst_check::Harness::with_element(&dec, Some("fec_1"), None);
+
+        h_dec.set_src_caps_str("application/x-rtp");
+        h_dec_column.set_src_caps_str("application/x-rtp");
+        h_dec_row.set_src_caps_str("application/x-rtp");
+
+        let mut rng = rand::thread_rng();
+
+        let input_buffers = (0..self.input_buffers)
+            .map(|i| {
+                let size = rng.gen_range(1..1300);
+                let data = (0..size).map(|_| rng.gen()).collect::<Vec<u8>>();
+
+                let mut buf = gst::Buffer::new_rtp_with_sizes(size as u32, 0, 0).unwrap();
+                {
+                    let buf_mut = buf.get_mut().unwrap();
+                    buf_mut.set_pts(gst::ClockTime::ZERO);
+                    buf_mut.set_dts(gst::ClockTime::ZERO);
+
+                    let mut rtpbuf = RTPBuffer::from_buffer_writable(buf_mut).unwrap();
+                    let payload = rtpbuf.payload_mut().unwrap();
+
+                    payload.copy_from_slice(data.as_slice());
+                    rtpbuf.set_seq(self.initial_seq.wrapping_add(i as u16));
+                    rtpbuf.set_timestamp(i as u32 * 160);
+                    rtpbuf.set_marker(rng.gen());
+                }
+
+                buf
+            })
+            .collect::<Vec<_>>();
+
+        // 3. Encoder Operations:
+
+        // Do not consume buffers here so we can compare it with the output
+        for buf in &input_buffers {
+            let result = h_enc.push(buf.clone());
+            assert!(result.is_ok());
+        }
+
+        assert_eq!(h_enc.buffers_in_queue(), self.input_buffers as u32);
+
+        let media_packets = (0..self.input_buffers)
+            .map(|_| {
+                let result = h_enc.pull();
+                assert!(result.is_ok());
+                result.unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // One column FEC packet per column of every matrix, and one
+        // row FEC packet per row of every matrix
+        let matrices = self.input_buffers / (self.columns * self.rows);
+
+        let expect_column_packets = match self.enable_column_fec {
+            true => matrices * self.columns,
+            false => 0,
+        };
+
+        let expect_row_packets = match self.enable_row_fec {
+            true => matrices * self.rows,
+            false => 0,
+        };
+
+        assert_eq!(
+            h_enc_column.buffers_in_queue(),
+            expect_column_packets as u32
+        );
+        assert_eq!(h_enc_row.buffers_in_queue(), expect_row_packets as u32);
+
+        // Repair packets are produced matrix by matrix, drop those of
+        // the matrices whose protection is lost
+        let column_packets = (0..expect_column_packets)
+            .map(|i| (i / self.columns, h_enc_column.pull().unwrap()))
+            .filter(|(matrix, _)| !self.lost_repair_matrices.contains(matrix))
+            .map(|(_, x)| x)
+            .collect::<Vec<_>>();
+
+        let row_packets = (0..expect_row_packets)
+            .map(|i| (i / self.rows, h_enc_row.pull().unwrap()))
+            .filter(|(matrix, _)| !self.lost_repair_matrices.contains(matrix))
+            .map(|(_, x)| x)
+            .collect::<Vec<_>>();
+
+        // 4. Decoder Operations:
+
+        // remove media packets to simulate packet loss
+        let media_packets = media_packets
+            .iter()
+            .cloned()
+            .enumerate()
+            .filter(|(i, _)| !self.lost_buffers.contains(i))
+            .map(|(_, x)| x)
+            .collect::<Vec<_>>();
+
+        // Push media packets to decoder
+        for buf in media_packets {
+            assert!(h_dec.push(buf).is_ok());
+        }
+
+        // Push repair packets to decoder, recovery happens as soon as
+        // the decoder has enough packets for it
+        for buf in row_packets {
+            assert!(h_dec_row.push(buf).is_ok());
+        }
+
+        for buf in column_packets {
+            assert!(h_dec_column.push(buf).is_ok());
+        }
+
+        assert_eq!(h_dec.buffers_in_queue(), self.expect_output_buffers as u32);
+
+        let mut output_buffers = (0..self.expect_output_buffers)
+            .map(|_| {
+                let result = h_dec.pull();
+                assert!(result.is_ok());
+                result.unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // Output buffers are out of sequence, we should sort it by
+        // seqnum so we can compare them with input buffers.
+        output_buffers.sort_unstable_by(|a, b| {
+            let aa = RTPBuffer::from_buffer_readable(a).unwrap();
+            let bb = RTPBuffer::from_buffer_readable(b).unwrap();
+
+            match gst_rtp::compare_seqnum(bb.seq(), aa.seq()) {
+                x if x > 0 => std::cmp::Ordering::Greater,
+                x if x < 0 => std::cmp::Ordering::Less,
+                _ => std::cmp::Ordering::Equal,
+            }
+        });
+
+        if self.input_buffers == self.expect_output_buffers {
+            for (inbuf, outbuf) in Iterator::zip(input_buffers.iter(), output_buffers.iter()) {
+                let rtp1 = RTPBuffer::from_buffer_readable(inbuf).unwrap();
+                let rtp2 = RTPBuffer::from_buffer_readable(outbuf).unwrap();
+
+                assert_eq!(rtp1.seq(), rtp2.seq());
+                assert_eq!(rtp1.timestamp(), rtp2.timestamp());
+                assert_eq!(rtp1.payload_type(), rtp2.payload_type());
+                assert_eq!(rtp1.is_marker(), rtp2.is_marker());
+                assert_eq!(rtp1.payload().unwrap(), rtp2.payload().unwrap());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_st2022fec_all_default() {
+    St2022FecTest::new().run();
+}
+
+#[test]
+fn test_st2022fec_no_loss() {
+    St2022FecTest::new().lost_buffers(vec![]).run();
+}
+
+#[test]
+fn test_st2022fec_column_only_burst_loss() {
+    St2022FecTest::new()
+        .columns(8)
+        .rows(4)
+        .enable_row_fec(false)
+        .input_buffers(64)
+        .lost_buffers(vec![3, 4, 5, 6, 7, 8, 9, 10, 40])
+        .expect_output_buffers(64)
+        .run();
+}
+
+#[test]
+fn test_st2022fec_row_only() {
+    St2022FecTest::new()
+        .columns(5)
+        .rows(4)
+        .enable_column_fec(false)
+        .input_buffers(20)
+        .lost_buffers(vec![1, 7, 14, 19])
+        .expect_output_buffers(20)
+        .run();
+}
+
+#[test]
+fn test_st2022fec_column_only_unrecoverable() {
+    // Two packets lost in the same column can't be recovered with 1D FEC
+    St2022FecTest::new()
+        .columns(4)
+        .rows(4)
+        .enable_row_fec(false)
+        .input_buffers(16)
+        .lost_buffers(vec![0, 4])
+        .expect_output_buffers(14)
+        .run();
+}
+
+#[test]
+fn test_st2022fec_2d_iterative_recovery() {
+    // Neither row nor column FEC alone can recover those, but recovering
+    // packet 1 from its column allows recovering packet 0 from its row,
+    // which in turn allows recovering packet 5 from its column.
+    St2022FecTest::new()
+        .columns(5)
+        .rows(5)
+        .input_buffers(25)
+        .lost_buffers(vec![0, 1, 5])
+        .expect_output_buffers(25)
+        .run();
+}
+
+#[test]
+fn test_st2022fec_2d_multiple_matrices() {
+    St2022FecTest::new()
+        .columns(4)
+        .rows(4)
+        .input_buffers(48)
+        .lost_buffers(vec![0, 5, 17, 18, 19, 36])
+        .expect_output_buffers(48)
+        .run();
+}
+
+#[test]
+fn test_st2022fec_wrapping_sequence_number() {
+    St2022FecTest::new()
+        .columns(4)
+        .rows(4)
+        .initial_seq(u16::MAX - 5)
+        .input_buffers(16)
+        .lost_buffers(vec![4, 5, 6])
+        .expect_output_buffers(16)
+        .run();
+}
+
+#[test]
+fn test_st2022fec_wrapping_sequence_number_sn_base() {
+    // The first repair packet the decoder sees has its SNBase after the
+    // wrap, while the media packets started before it
+    St2022FecTest::new()
+        .columns(4)
+        .rows(4)
+        .initial_seq(u16::MAX - 7)
+        .input_buffers(32)
+        .lost_repair_matrices(vec![0])
+        .lost_buffers(vec![17, 22])
+        .expect_output_buffers(32)
+        .run();
+}
+
+#[test]
+fn test_st2022fec_matrix_limits() {
+    init();
+
+    let enc = gst::ElementFactory::make("st2022fecenc", None).unwrap();
+
+    // Outside of the L and D ranges allowed by SMPTE 2022-1
+    let pspec = enc.find_property("columns").unwrap();
+    let pspec = pspec.downcast_ref::<glib::ParamSpecUInt>().unwrap();
+    assert_eq!((pspec.minimum(), pspec.maximum()), (1, 20));
+
+    let pspec = enc.find_property("rows").unwrap();
+    let pspec = pspec.downcast_ref::<glib::ParamSpecUInt>().unwrap();
+    assert_eq!((pspec.minimum(), pspec.maximum()), (4, 20));
+
+    // L and D within range but L * D above 100
+    enc.set_property("columns", 20u32);
+    enc.set_property("rows", 10u32);
+    assert!(enc.set_state(gst::State::Paused).is_err());
+    enc.set_state(gst::State::Null).unwrap();
+
+    enc.set_property("columns", 20u32);
+    enc.set_property("rows", 5u32);
+    assert!(enc.set_state(gst::State::Paused).is_ok());
+    enc.set_state(gst::State::Null).unwrap();
+}
@@ -0,0 +1,382 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::common::{in_operating_point, OperatingPoints, ENDIANNESS};
+use bitstream_io::{BitWrite, BitWriter, Endianness};
+use std::io::{self, Cursor, Seek, Write};
+
+pub const DEPENDENCY_DESCRIPTOR_URI: &str =
+    "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension";
+
+/// Frame dependency templates are identified by 6 bit IDs
+const MAX_TEMPLATES: usize = 64;
+/// Frame diffs in templates are written as 4 bit values
+const MAX_TEMPLATE_FDIFF: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeTargetIndication {
+    NotPresent,
+    Discardable,
+    Switch,
+    Required,
+}
+
+impl From<DecodeTargetIndication> for u8 {
+    fn from(dti: DecodeTargetIndication) -> Self {
+        match dti {
+            DecodeTargetIndication::NotPresent => 0,
+            DecodeTargetIndication::Discardable => 1,
+            DecodeTargetIndication::Switch => 2,
+            DecodeTargetIndication::Required => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameDependencyTemplate {
+    pub spatial_id: u8,
+    pub temporal_id: u8,
+    /// one indication per decode target
+    pub dtis: Vec<DecodeTargetIndication>,
+    /// distances in frame numbers to the frames this one depends on
+    pub fdiffs: Vec<u32>,
+}
+
+/// Template dependency structure, see section A.8.3 of the RTP AV1 spec.
+/// Chains and render resolutions are not signalled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameDependencyStructure {
+    pub template_id_offset: u8,
+    pub decode_target_count: u8,
+    /// ordered by spatial ID, then temporal ID
+    pub templates: Vec<FrameDependencyTemplate>,
+}
+
+impl FrameDependencyStructure {
+    /// Build the structure of an L*T* SVC stream with one decode target per
+    /// operating point and one template per layer.
+    ///
+    /// The actual references of each frame are not known without parsing
+    /// frame headers, so the frame diffs assume the usual full SVC pattern:
+    /// every temporal unit contains a frame for all spatial layers, each
+    /// frame depends on the previous frame of its spatial layer with a lower
+    /// temporal ID (or of the base temporal layer), and on the frame of the
+    /// spatial layer below it in the same temporal unit.
+    pub fn from_operating_points(ops: &OperatingPoints, template_id_offset: u8) -> Self {
+        let max_spatial_id = ops.max_spatial_id();
+        let max_temporal_id = ops.max_temporal_id();
+        let idc = if ops.idc.is_empty() {
+            vec![0]
+        } else {
+            ops.idc.clone()
+        };
+
+        let frames_per_tu = max_spatial_id as u32 + 1;
+        let mut templates = Vec::new();
+
+        for spatial_id in 0..=max_spatial_id {
+            for temporal_id in 0..=max_temporal_id {
+                let dtis = idc
+                    .iter()
+                    .map(|&idc| {
+                        let (dt_spatial_id, dt_temporal_id) = if idc == 0 {
+                            (max_spatial_id, max_temporal_id)
+                        } else {
+                            let op = OperatingPoints { idc: vec![idc] };
+                            (op.max_spatial_id(), op.max_temporal_id())
+                        };
+
+                        if !in_operating_point(idc, spatial_id, temporal_id) {
+                            DecodeTargetIndication::NotPresent
+                        } else if temporal_id == 0 {
+                            DecodeTargetIndication::Switch
+                        } else if temporal_id == dt_temporal_id && spatial_id == dt_spatial_id {
+                            DecodeTargetIndication::Discardable
+                        } else {
+                            DecodeTargetIndication::Required
+                        }
+                    })
+                    .collect();
+
+                let temporal_distance = if temporal_id == 0 {
+                    1 << max_temporal_id
+                } else {
+                    1 << (max_temporal_id - temporal_id)
+                };
+
+                let mut fdiffs = vec![frames_per_tu * temporal_distance];
+                if spatial_id > 0 {
+                    fdiffs.push(1);
+                }
+                fdiffs.retain(|&fdiff| fdiff <= MAX_TEMPLATE_FDIFF);
+
+                templates.push(FrameDependencyTemplate {
+                    spatial_id,
+                    temporal_id,
+                    dtis,
+                    fdiffs,
+                });
+            }
+        }
+
+        // more layers than templates can't be described
+        templates.truncate(MAX_TEMPLATES);
+
+        Self {
+            template_id_offset: template_id_offset % MAX_TEMPLATES as u8,
+            decode_target_count: idc.len() as u8,
+            templates,
+        }
+    }
+
+    /// The template ID to use for frames of the given layer.
+    pub fn template_id(&self, spatial_id: u8, temporal_id: u8) -> Option<u8> {
+        self.templates
+            .iter()
+            .position(|t| t.spatial_id == spatial_id && t.temporal_id == temporal_id)
+            .map(|idx| (idx as u8 + self.template_id_offset) % MAX_TEMPLATES as u8)
+    }
+
+    /// The template ID offset a structure replacing this one should use, so
+    /// that receivers can tell their template IDs apart.
+    pub fn next_template_id_offset(&self) -> u8 {
+        ((self.template_id_offset as usize + self.templates.len()) % MAX_TEMPLATES) as u8
+    }
+
+    fn write<W, E>(&self, writer: &mut BitWriter<W, E>) -> io::Result<()>
+    where
+        W: Write + Seek,
+        E: Endianness,
+    {
+        writer.write(6, self.template_id_offset)?;
+        writer.write(5, self.decode_target_count - 1)?;
+
+        // template_layers()
+        for (idx, template) in self.templates.iter().enumerate() {
+            let next_layer_idc: u8 = match self.templates.get(idx + 1) {
+                None => 3,
+                Some(next) if next.spatial_id != template.spatial_id => 2,
+                Some(next) if next.temporal_id != template.temporal_id => 1,
+                Some(_) => 0,
+            };
+            writer.write(2, next_layer_idc)?;
+        }
+
+        // template_dtis()
+        for template in &self.templates {
+            for dti in &template.dtis {
+                writer.write(2, u8::from(*dti))?;
+            }
+        }
+
+        // template_fdiffs()
+        for template in &self.templates {
+            for fdiff in &template.fdiffs {
+                writer.write_bit(true)?;
+                writer.write(4, fdiff - 1)?;
+            }
+            writer.write_bit(false)?;
+        }
+
+        // template_chains(), chain_cnt is written as ns(decode_target_count + 1)
+        // and a value of 0 takes up the width of the decode target count
+        let width = u8::BITS - (self.decode_target_count + 1).leading_zeros();
+        writer.write(width - 1, 0u8)?;
+
+        // resolutions_present_flag
+        writer.write_bit(false)
+    }
+}
+
+/// Dependency descriptor RTP header extension, see appendix A of the RTP AV1 spec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyDescriptor<'a> {
+    pub start_of_frame: bool,
+    pub end_of_frame: bool,
+    pub template_id: u8,
+    pub frame_number: u16,
+    /// the structure to attach, usually to the first packet of a key frame
+    pub structure: Option<&'a FrameDependencyStructure>,
+    /// frame diffs overriding the ones from the template
+    pub custom_fdiffs: Option<&'a [u32]>,
+}
+
+impl<'a> DependencyDescriptor<'a> {
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut writer = BitWriter::endian(Cursor::new(Vec::new()), ENDIANNESS);
+
+        // mandatory_descriptor_fields()
+        writer.write_bit(self.start_of_frame)?;
+        writer.write_bit(self.end_of_frame)?;
+        writer.write(6, self.template_id)?;
+        writer.write(16, self.frame_number)?;
+
+        if self.structure.is_some() || self.custom_fdiffs.is_some() {
+            // extended_descriptor_fields()
+            writer.write_bit(self.structure.is_some())?;
+            writer.write_bit(false)?; // active_decode_targets_present_flag
+            writer.write_bit(false)?; // custom_dtis_flag
+            writer.write_bit(self.custom_fdiffs.is_some())?;
+            writer.write_bit(false)?; // custom_chains_flag
+
+            if let Some(structure) = self.structure {
+                structure.write(&mut writer)?;
+            }
+
+            // frame_dependency_definition()
+            if let Some(fdiffs) = self.custom_fdiffs {
+                for &fdiff in fdiffs {
+                    let fdiff_minus_one = fdiff.saturating_sub(1);
+                    let size = match fdiff_minus_one {
+                        0..=0xf => 1,
+                        0x10..=0xff => 2,
+                        _ => 3,
+                    };
+
+                    writer.write(2, size as u8)?;
+                    writer.write(4 * size, fdiff_minus_one.min(0xfff))?;
+                }
+                writer.write(2, 0u8)?;
+            }
+        }
+
+        writer.byte_align()?;
+        Ok(writer.into_writer().into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DecodeTargetIndication::*;
+
+    #[test]
+    fn test_structure_l1t1() {
+        let structure =
+            FrameDependencyStructure::from_operating_points(&OperatingPoints { idc: vec![0] }, 0);
+
+        assert_eq!(
+            structure,
+            FrameDependencyStructure {
+                template_id_offset: 0,
+                decode_target_count: 1,
+                templates: vec![FrameDependencyTemplate {
+                    spatial_id: 0,
+                    temporal_id: 0,
+                    dtis: vec![Switch],
+                    fdiffs: vec![1],
+                }],
+            }
+        );
+        assert_eq!(structure.template_id(0, 0), Some(0));
+        assert_eq!(structure.template_id(0, 1), None);
+        assert_eq!(structure.next_template_id_offset(), 1);
+    }
+
+    #[test]
+    fn test_structure_l2t2() {
+        let ops = OperatingPoints {
+            idc: vec![0x303, 0x301, 0x103, 0x101],
+        };
+        let structure = FrameDependencyStructure::from_operating_points(&ops, 62);
+
+        assert_eq!(structure.decode_target_count, 4);
+        assert_eq!(
+            structure
+                .templates
+                .iter()
+                .map(|t| (
+                    t.spatial_id,
+                    t.temporal_id,
+                    t.dtis.clone(),
+                    t.fdiffs.clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 0, vec![Switch, Switch, Switch, Switch], vec![4]),
+                (
+                    0,
+                    1,
+                    vec![Required, NotPresent, Discardable, NotPresent],
+                    vec![2]
+                ),
+                (
+                    1,
+                    0,
+                    vec![Switch, Switch, NotPresent, NotPresent],
+                    vec![4, 1]
+                ),
+                (
+                    1,
+                    1,
+                    vec![Discardable, NotPresent, NotPresent, NotPresent],
+                    vec![2, 1]
+                ),
+            ]
+        );
+
+        // template IDs wrap around
+        assert_eq!(structure.template_id(0, 0), Some(62));
+        assert_eq!(structure.template_id(1, 0), Some(0));
+        assert_eq!(structure.template_id(1, 1), Some(1));
+        assert_eq!(structure.next_template_id_offset(), 2);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_write_descriptor() {
+        let structure =
+            FrameDependencyStructure::from_operating_points(&OperatingPoints { idc: vec![0] }, 0);
+
+        // mandatory fields only
+        let dd = DependencyDescriptor {
+            start_of_frame: true,
+            end_of_frame: false,
+            template_id: 5,
+            frame_number: 0x1234,
+            structure: None,
+            custom_fdiffs: None,
+        };
+        assert_eq!(dd.to_bytes().unwrap(), vec![0b1000_0101, 0x12, 0x34]);
+
+        // key frame with attached structure and no references
+        let dd = DependencyDescriptor {
+            start_of_frame: true,
+            end_of_frame: true,
+            template_id: 0,
+            frame_number: 1,
+            structure: Some(&structure),
+            custom_fdiffs: Some(&[]),
+        };
+        assert_eq!(
+            dd.to_bytes().unwrap(),
+            vec![
+                0b1100_0000, 0x00, 0x01,
+                0b1001_0000, 0b0000_0000, 0b1110_1000, 0b0000_0000,
+            ]
+        );
+
+        // custom frame diffs of different sizes
+        let dd = DependencyDescriptor {
+            start_of_frame: false,
+            end_of_frame: true,
+            template_id: 1,
+            frame_number: 2,
+            structure: None,
+            custom_fdiffs: Some(&[1, 20]),
+        };
+        assert_eq!(
+            dd.to_bytes().unwrap(),
+            vec![
+                0b0100_0001, 0x00, 0x02,
+                0b0001_0010, 0b0001_0000, 0b1001_1000,
+            ]
+        );
+    }
+}
@@ -45,6 +45,13 @@ macro_rules! err_flow {
             "Failed to write OBU bytes to the payload: {}"
         )
     };
+    ($element:ident, ext_write) => {
+        err_flow!(
+            $element,
+            write,
+            "Failed to write RTP header extension to the packet: {}"
+        )
+    };
     ($element:ident, outbuf_alloc) => {
         err_flow!($element, write, "Failed to allocate output buffer: {}")
     };
@@ -13,11 +13,13 @@ pub const CLOCK_RATE: u32 = 90000;
 pub const ENDIANNESS: BigEndian = BigEndian;
 
 mod aggr_header;
+mod dependency_descriptor;
 mod error;
 mod integers;
 mod obu;
 
 pub use aggr_header::*;
+pub use dependency_descriptor::*;
 pub(crate) use error::*;
 pub use integers::*;
 pub use obu::*;
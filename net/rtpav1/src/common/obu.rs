@@ -18,6 +18,9 @@ pub struct UnsizedObu {
     pub temporal_id: u8,
     pub spatial_id: u8,
     pub header_len: u32,
+    /// If the OBU header is followed by an internal leb128 size field.
+    /// RTP OBU elements should not have one, but some senders keep it.
+    pub has_size_field: bool,
     /// indicates that only part of this OBU has been processed so far
     pub is_fragment: bool,
 }
@@ -80,14 +83,7 @@ impl UnsizedObu {
 
         let obu_type = reader.read::<u8>(4)?.into();
         let has_extension = reader.read_bit()?;
-
-        // make sure there is no size field
-        if reader.read_bit()? {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "did not expect size field",
-            ));
-        }
+        let has_size_field = reader.read_bit()?;
 
         // ignore the reserved bit
         let _ = reader.read_bit()?;
@@ -106,16 +102,18 @@ impl UnsizedObu {
             temporal_id,
             spatial_id,
             header_len: has_extension as u32 + 1,
+            has_size_field,
             is_fragment: false,
         })
     }
 
-    /// Convert to a `SizedObu` without internal size field and the given sizes.
+    /// Convert to a `SizedObu` with the given sizes. The internal size field,
+    /// if present, is not part of the header and has to be skipped separately.
     pub fn as_sized(&self, size: u32, leb_size: u32) -> SizedObu {
         SizedObu {
             obu_type: self.obu_type,
             has_extension: self.has_extension,
-            has_size_field: false,
+            has_size_field: self.has_size_field,
             temporal_id: self.temporal_id,
             spatial_id: self.spatial_id,
             size,
@@ -193,6 +191,129 @@ impl SizedObu {
     }
 }
 
+/// Scalability information carried in a sequence header OBU
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct OperatingPoints {
+    /// `operating_point_idc` of each operating point, the lower 8 bits
+    /// indicate the included temporal layers and the next 4 bits the
+    /// included spatial layers. Zero means that the stream is not scalable.
+    pub idc: Vec<u16>,
+}
+
+impl OperatingPoints {
+    /// Parse the operating points from the payload of a sequence header OBU,
+    /// see section 5.5 of the AV1 specification.
+    pub fn parse<R, E>(reader: &mut BitReader<R, E>) -> io::Result<Self>
+    where
+        R: Read + Seek,
+        E: Endianness,
+    {
+        // seq_profile and still_picture
+        reader.skip(4)?;
+
+        let reduced_still_picture_header = reader.read_bit()?;
+        if reduced_still_picture_header {
+            return Ok(Self { idc: vec![0] });
+        }
+
+        let mut buffer_delay_length = 0;
+        let mut decoder_model_info_present = false;
+
+        let timing_info_present = reader.read_bit()?;
+        if timing_info_present {
+            // num_units_in_display_tick and time_scale
+            reader.skip(64)?;
+
+            let equal_picture_interval = reader.read_bit()?;
+            if equal_picture_interval {
+                // num_ticks_per_picture_minus_1
+                skip_uvlc(reader)?;
+            }
+
+            decoder_model_info_present = reader.read_bit()?;
+            if decoder_model_info_present {
+                buffer_delay_length = reader.read::<u32>(5)? + 1;
+                // num_units_in_decoding_tick, buffer_removal_time_length_minus_1
+                // and frame_presentation_time_length_minus_1
+                reader.skip(42)?;
+            }
+        }
+
+        let initial_display_delay_present = reader.read_bit()?;
+        let count = reader.read::<u8>(5)? as usize + 1;
+
+        let mut idc = Vec::with_capacity(count);
+        for _ in 0..count {
+            idc.push(reader.read::<u16>(12)?);
+
+            let seq_level_idx = reader.read::<u8>(5)?;
+            if seq_level_idx > 7 {
+                // seq_tier
+                reader.skip(1)?;
+            }
+
+            if decoder_model_info_present && reader.read_bit()? {
+                // decoder_buffer_delay, encoder_buffer_delay and low_delay_mode_flag
+                reader.skip(2 * buffer_delay_length + 1)?;
+            }
+
+            if initial_display_delay_present && reader.read_bit()? {
+                // initial_display_delay_minus_1
+                reader.skip(4)?;
+            }
+        }
+
+        Ok(Self { idc })
+    }
+
+    /// The highest spatial layer ID used by any operating point.
+    pub fn max_spatial_id(&self) -> u8 {
+        self.idc
+            .iter()
+            .map(|idc| highest_bit((idc >> 8) & 0x0f))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The highest temporal layer ID used by any operating point.
+    pub fn max_temporal_id(&self) -> u8 {
+        self.idc
+            .iter()
+            .map(|idc| highest_bit(idc & 0xff))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Whether OBUs of the given layer are part of the operating point
+/// with the given `operating_point_idc`.
+pub fn in_operating_point(idc: u16, spatial_id: u8, temporal_id: u8) -> bool {
+    idc == 0 || ((idc >> temporal_id) & 1 != 0 && (idc >> (spatial_id + 8)) & 1 != 0)
+}
+
+fn highest_bit(bits: u16) -> u8 {
+    (u16::BITS - bits.leading_zeros()).saturating_sub(1) as u8
+}
+
+fn skip_uvlc<R, E>(reader: &mut BitReader<R, E>) -> io::Result<()>
+where
+    R: Read + Seek,
+    E: Endianness,
+{
+    let mut leading_zeros = 0;
+    while !reader.read_bit()? {
+        leading_zeros += 1;
+        if leading_zeros >= 32 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid uvlc value",
+            ));
+        }
+    }
+
+    reader.skip(leading_zeros)
+}
+
 impl From<u8> for ObuType {
     fn from(n: u8) -> Self {
         assert!(n < 16);
@@ -259,6 +380,7 @@ mod tests {
                     temporal_id: 0,
                     spatial_id: 0,
                     header_len: 1,
+                    has_size_field: false,
                     is_fragment: false,
                 },
                 vec![0b0001_0000],
@@ -283,6 +405,7 @@ mod tests {
                     temporal_id: 0,
                     spatial_id: 0,
                     header_len: 1,
+                    has_size_field: false,
                     is_fragment: false,
                 },
                 vec![0b0111_1000, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
@@ -307,6 +430,7 @@ mod tests {
                     temporal_id: 4,
                     spatial_id: 3,
                     header_len: 2,
+                    has_size_field: false,
                     is_fragment: false,
                 },
                 vec![0b0011_0100, 0b1001_1000, 1, 2, 3, 4, 5],
@@ -356,6 +480,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_operating_points() {
+        let test_data = [
+            // reduced still picture header
+            (vec![0b0000_1000, 0], vec![0], 0, 0),
+            // single operating point, no timing info
+            (
+                vec![0b0000_0000, 0b0000_0000, 0b0000_0000, 0b1000_0000],
+                vec![0],
+                0,
+                0,
+            ),
+            // L2T2 with 4 operating points, seq_level_idx 8 for the first one
+            (
+                vec![
+                    0b0000_0000,
+                    0b0011_0011,
+                    0b0000_0011,
+                    0b0100_0000,
+                    0b1100_0000,
+                    0b0100_0000,
+                    0b0010_0000,
+                    0b0110_0000,
+                    0b0001_0000,
+                    0b0001_0000,
+                    0b0100_0000,
+                ],
+                vec![0x303, 0x301, 0x103, 0x101],
+                1,
+                1,
+            ),
+        ];
+
+        for (idx, (bytes, idc, max_spatial_id, max_temporal_id)) in
+            test_data.into_iter().enumerate()
+        {
+            println!("running test {}...", idx);
+
+            let mut reader = BitReader::endian(Cursor::new(&bytes), BigEndian);
+            let ops = OperatingPoints::parse(&mut reader).unwrap();
+
+            assert_eq!(ops.idc, idc);
+            assert_eq!(ops.max_spatial_id(), max_spatial_id);
+            assert_eq!(ops.max_temporal_id(), max_temporal_id);
+        }
+
+        assert!(in_operating_point(0, 1, 2));
+        assert!(in_operating_point(0x103, 0, 1));
+        assert!(!in_operating_point(0x103, 1, 1));
+        assert!(!in_operating_point(0x301, 1, 1));
+    }
+
     #[test]
     fn test_parse_rtp_obu() {
         let obus = [
@@ -401,6 +577,20 @@ mod tests {
                 },
                 vec![0b0011_0100, 0b1001_1000, 1, 2, 3, 4, 5],
             ),
+            (
+                SizedObu {
+                    obu_type: ObuType::Frame,
+                    has_extension: true,
+                    has_size_field: true,
+                    temporal_id: 4,
+                    spatial_id: 3,
+                    size: 5,
+                    leb_size: 1,
+                    header_len: 2,
+                    is_fragment: false,
+                },
+                vec![0b0011_0110, 0b1001_1000, 0b0000_0101, 1, 2, 3, 4, 5],
+            ),
         ];
 
         for (idx, (sized_obu, rtp_bytes)) in obus.into_iter().enumerate() {
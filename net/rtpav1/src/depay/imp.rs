@@ -23,8 +23,6 @@ use crate::common::{
     UnsizedObu, CLOCK_RATE, ENDIANNESS,
 };
 
-// TODO: handle internal size fields in RTP OBUs
-
 #[derive(Debug, Default)]
 struct State {
     /// used to store outgoing OBUs until the TU is complete
@@ -35,6 +33,8 @@ struct State {
     marked_packet: bool,
     /// holds data for a fragment
     obu_fragment: Option<(UnsizedObu, Vec<u8>)>,
+    /// highest spatial and temporal IDs of the OBUs stored for the current TU
+    tu_layers: Option<(u8, u8)>,
 }
 
 #[derive(Debug, Default)]
@@ -53,6 +53,20 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
 static TEMPORAL_DELIMITER: Lazy<gst::Memory> =
     Lazy::new(|| gst::Memory::from_slice(&[0b0001_0010, 0]));
 
+/// Custom meta attached to outgoing temporal units containing OBUs with
+/// extension headers, with the highest `spatial-id` and `temporal-id` of
+/// those OBUs.
+const LAYER_META_NAME: &str = "GstRtpAv1LayerMeta";
+
+static LAYER_META: Lazy<()> = Lazy::new(|| gst::meta::CustomMeta::register(LAYER_META_NAME, &[]));
+
+fn merge_layers(a: Option<(u8, u8)>, b: Option<(u8, u8)>) -> Option<(u8, u8)> {
+    match (a, b) {
+        (Some((sid_a, tid_a)), Some((sid_b, tid_b))) => Some((sid_a.max(sid_b), tid_a.max(tid_b))),
+        (a, b) => a.or(b),
+    }
+}
+
 impl RTPAv1Depay {
     fn reset(&self, element: &<Self as ObjectSubclass>::Type, state: &mut State) {
         gst::debug!(CAT, obj: element, "resetting state");
@@ -179,6 +193,8 @@ impl RTPBaseDepayloadImpl for RTPAv1Depay {
 
         // number of bytes that can be used in the next outgoing buffer
         let mut bytes_ready = 0;
+        // layers of the OBUs in these bytes
+        let mut layers_ready = None;
         let mut reader = Cursor::new(payload);
         let mut ready_obus = gst::Buffer::new();
 
@@ -211,6 +227,7 @@ impl RTPBaseDepayloadImpl for RTPAv1Depay {
 
             // all the currently stored bytes can be packed into the next outgoing buffer
             bytes_ready = state.adapter.available();
+            layers_ready = state.tu_layers.take();
 
             // the next temporal unit starts with a temporal delimiter OBU
             ready_obus
@@ -248,13 +265,10 @@ impl RTPBaseDepayloadImpl for RTPAv1Depay {
 
             // if this OBU is complete, it can be appended to the adapter
             if !(is_last_obu && aggr_header.trailing_fragment) {
-                let full_obu = {
-                    let size = bytes.len() as u32 - obu.header_len;
-                    let leb_size = leb128_size(size) as u32;
-                    obu.as_sized(size, leb_size)
-                };
+                let mut reader = Cursor::new(bytes.as_slice());
+                let full_obu = sized_obu(element, &mut reader, obu, bytes.len() as u32)?;
 
-                let buffer = translate_obu(element, &mut Cursor::new(bytes.as_slice()), &full_obu)?;
+                let buffer = translate_obu(element, &mut reader, &full_obu)?;
 
                 state.adapter.push(buffer);
                 state.obu_fragment = None;
@@ -277,15 +291,22 @@ impl RTPBaseDepayloadImpl for RTPAv1Depay {
                 .map_err(err_opt!(element, buf_read))
                 .ok()?;
 
+            if obu.has_extension {
+                state.tu_layers =
+                    merge_layers(state.tu_layers, Some((obu.spatial_id, obu.temporal_id)));
+            }
+
             // ignore these OBU types
-            if matches!(obu.obu_type, ObuType::TemporalDelimiter | ObuType::TileList) {
+            if matches!(obu.obu_type, ObuType::TemporalDelimiter | ObuType::TileList)
+                && !(is_last_obu && aggr_header.trailing_fragment)
+            {
                 reader
                     .seek(SeekFrom::Current(element_size as i64))
                     .map_err(err_opt!(element, buf_read))
                     .ok()?;
             }
             // trailing OBU fragments are stored in the state
-            if is_last_obu && aggr_header.trailing_fragment {
+            else if is_last_obu && aggr_header.trailing_fragment {
                 let bytes_left = rtp.payload_size() - (reader.position() as u32);
                 let mut bytes = vec![0; bytes_left as usize];
                 reader
@@ -297,13 +318,15 @@ impl RTPBaseDepayloadImpl for RTPAv1Depay {
             }
             // full OBUs elements are translated and appended to the adapter
             else {
-                let full_obu = {
-                    let size = element_size - obu.header_len;
-                    let leb_size = leb128_size(size) as u32;
-                    obu.as_sized(size, leb_size)
-                };
+                let full_obu = sized_obu(element, &mut reader, &obu, element_size)?;
 
                 ready_obus.append(translate_obu(element, &mut reader, &full_obu)?);
+
+                // an internal size field may leave trailing bytes in the element
+                reader
+                    .seek(SeekFrom::Start(header_pos + element_size as u64))
+                    .map_err(err_opt!(element, buf_read))
+                    .ok()?;
             }
 
             idx += 1;
@@ -326,6 +349,7 @@ impl RTPBaseDepayloadImpl for RTPAv1Depay {
             }
 
             bytes_ready = state.adapter.available();
+            layers_ready = merge_layers(layers_ready, state.tu_layers.take());
         }
 
         // now push all the complete temporal units
@@ -336,13 +360,24 @@ impl RTPBaseDepayloadImpl for RTPAv1Depay {
                 "creating buffer containing {} bytes of data...",
                 bytes_ready
             );
-            Some(
-                state
-                    .adapter
-                    .take_buffer(bytes_ready)
-                    .map_err(err_opt!(element, buf_take))
-                    .ok()?,
-            )
+            let mut buffer = state
+                .adapter
+                .take_buffer(bytes_ready)
+                .map_err(err_opt!(element, buf_take))
+                .ok()?;
+
+            if let Some((spatial_id, temporal_id)) = layers_ready {
+                Lazy::force(&LAYER_META);
+
+                if let Ok(mut meta) = gst::meta::CustomMeta::add(buffer.make_mut(), LAYER_META_NAME)
+                {
+                    let s = meta.mut_structure();
+                    s.set("spatial-id", spatial_id as u32);
+                    s.set("temporal-id", temporal_id as u32);
+                }
+            }
+
+            Some(buffer)
         } else {
             None
         }
@@ -397,6 +432,71 @@ fn find_element_info(
     Some((element_size, is_last_obu))
 }
 
+/// Find out the payload size of a complete OBU element of `element_size` bytes.
+/// If the OBU keeps its internal size field, that one is used instead of the
+/// element size, and is skipped by `translate_obu()`.
+/// The reader is expected to be at the first byte of the OBU header,
+/// and will be there again afterwards.
+fn sized_obu(
+    element: &<RTPAv1Depay as ObjectSubclass>::Type,
+    reader: &mut Cursor<&[u8]>,
+    obu: &UnsizedObu,
+    element_size: u32,
+) -> Option<SizedObu> {
+    let available = match element_size.checked_sub(obu.header_len) {
+        Some(available) => available,
+        None => {
+            gst::error!(
+                CAT,
+                obj: element,
+                "invalid packet: OBU element is smaller than its header"
+            );
+            return None;
+        }
+    };
+
+    if !obu.has_size_field {
+        return Some(obu.as_sized(available, leb128_size(available) as u32));
+    }
+
+    let header_pos = reader.position();
+    reader
+        .seek(SeekFrom::Current(obu.header_len as i64))
+        .map_err(err_opt!(element, buf_read))
+        .ok()?;
+
+    let size = parse_leb128(&mut BitReader::endian(&mut *reader, ENDIANNESS))
+        .map_err(err_opt!(element, leb_read))
+        .ok()?;
+    let internal_leb_size = (reader.position() - header_pos) as u32 - obu.header_len;
+
+    reader
+        .seek(SeekFrom::Start(header_pos))
+        .map_err(err_opt!(element, buf_read))
+        .ok()?;
+
+    if size
+        .checked_add(internal_leb_size)
+        .map_or(true, |total| total > available)
+    {
+        gst::error!(
+            CAT,
+            obj: element,
+            "invalid packet: internal size field gives impossibly large OBU size"
+        );
+        return None;
+    }
+
+    gst::trace!(
+        CAT,
+        obj: element,
+        "OBU element has internal size field with size {}",
+        size
+    );
+
+    Some(obu.as_sized(size, leb128_size(size) as u32))
+}
+
 /// Using OBU data from an RTP packet, construct a buffer containing that OBU in AV1 bitstream format
 fn translate_obu(
     element: &<RTPAv1Depay as ObjectSubclass>::Type,
@@ -519,6 +619,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sized_obu() {
+        gst::init().unwrap();
+
+        let test_data: [(Vec<u8>, Option<(bool, u32)>); 5] = [
+            (   // no internal size field
+                vec![0b0011_0100, 0b0111_0000, 1, 2, 3, 4, 5],
+                Some((false, 5)),
+            ), (
+                // internal size field matching the element
+                vec![0b0011_0110, 0b0111_0000, 0b0000_0101, 1, 2, 3, 4, 5],
+                Some((true, 5)),
+            ), (
+                // internal size field smaller than the element
+                vec![0b0011_0010, 0b1000_0011, 0b0000_0000, 1, 2, 3, 0, 0],
+                Some((true, 3)),
+            ), (
+                // internal size field larger than the element
+                vec![0b0011_0010, 0b0000_1000, 1, 2, 3],
+                None,
+            ), (
+                // internal size field overflowing with the size of the field itself
+                vec![0b0011_0010, 0xff, 0xff, 0xff, 0xff, 0x0f, 1, 2, 3],
+                None,
+            )
+        ];
+
+        let element = <RTPAv1Depay as ObjectSubclass>::Type::new();
+        for (idx, (rtp_bytes, expected)) in test_data.into_iter().enumerate() {
+            println!("running test {}...", idx);
+            let mut reader = Cursor::new(rtp_bytes.as_slice());
+            let obu = UnsizedObu::parse(&mut BitReader::endian(&mut reader, ENDIANNESS)).unwrap();
+            reader.set_position(0);
+
+            let actual = sized_obu(&element, &mut reader, &obu, rtp_bytes.len() as u32);
+            assert_eq!(actual.map(|obu| (obu.has_size_field, obu.size)), expected);
+            assert_eq!(reader.position(), 0);
+        }
+    }
+
     #[test]
     #[allow(clippy::type_complexity)]
     fn test_find_element_info() {
@@ -18,7 +18,8 @@ use bitstream_io::{BitReader, BitWriter};
 use once_cell::sync::Lazy;
 
 use crate::common::{
-    err_flow, leb128_size, write_leb128, ObuType, SizedObu, CLOCK_RATE, ENDIANNESS,
+    err_flow, leb128_size, write_leb128, DependencyDescriptor, FrameDependencyStructure, ObuType,
+    OperatingPoints, SizedObu, CLOCK_RATE, DEPENDENCY_DESCRIPTOR_URI, ENDIANNESS,
 };
 
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
@@ -29,7 +30,28 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     )
 });
 
-// TODO: properly handle `max_ptime` and `min_ptime`
+// Packets never span multiple temporal units, so `max-ptime` and `min-ptime` are
+// used to bound the time OBUs are held back waiting for more OBUs of the same
+// temporal unit: once the OBUs held back span more than `max-ptime` (and at least
+// `min-ptime`), up to the end of the last input buffer, they are sent right away.
+// If the end of the temporal unit is not signalled by the input, such packets
+// can't have the marker bit set.
+
+const DEFAULT_DEPENDENCY_DESCRIPTOR_EXT_ID: u32 = 0;
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    /// ID of the dependency descriptor header extension, 0 if disabled
+    dependency_descriptor_ext_id: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            dependency_descriptor_ext_id: DEFAULT_DEPENDENCY_DESCRIPTOR_EXT_ID,
+        }
+    }
+}
 
 /// Information about the OBUs intended to be grouped into one packet
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
@@ -76,10 +98,30 @@ struct State {
     first_packet_in_seq: bool,
 
     temp_packet_data: Option<TempPacketData>,
+
+    /// Template structure used for the dependency descriptors
+    dd_structure: Option<FrameDependencyStructure>,
+
+    /// Template structure taking effect with the packet containing
+    /// the next sequence header
+    dd_next_structure: Option<FrameDependencyStructure>,
+
+    /// Spatial and temporal IDs of the frame the last packet belonged to,
+    /// if that packet did not end the frame
+    dd_frame_layer: Option<(u8, u8)>,
+
+    dd_frame_number: u16,
+
+    /// Indicates that the current temporal unit started with a sequence header
+    dd_key_frame: bool,
+
+    /// Bytes reserved in every packet for the header extension
+    dd_ext_size: u32,
 }
 
 #[derive(Debug, Default)]
 pub struct RTPAv1Pay {
+    settings: Mutex<Settings>,
     state: Mutex<State>,
 }
 
@@ -90,6 +132,12 @@ impl Default for State {
             open_obu_fragment: false,
             first_packet_in_seq: true,
             temp_packet_data: None,
+            dd_structure: None,
+            dd_next_structure: None,
+            dd_frame_layer: None,
+            dd_frame_number: 0,
+            dd_key_frame: false,
+            dd_ext_size: 0,
         }
     }
 }
@@ -110,9 +158,24 @@ impl RTPAv1Pay {
         data: &[u8],
         dts: Option<gst::ClockTime>,
         pts: Option<gst::ClockTime>,
+        marker: bool,
     ) -> Result<gst::BufferList, gst::FlowError> {
         let mut reader = Cursor::new(data);
 
+        // a new timestamp means a new temporal unit, even if the stream
+        // does not contain temporal delimiters
+        if let Some(last) = state.obus.last() {
+            if last.info.obu_type != ObuType::TemporalDelimiter
+                && last.pts.is_some()
+                && pts.is_some()
+                && last.pts != pts
+            {
+                gst::log!(CAT, obj: element, "timestamp changed, ending temporal unit");
+                let (dts, pts) = (last.dts, last.pts);
+                self.end_temporal_unit(state, dts, pts);
+            }
+        }
+
         while reader.position() < data.len() as u64 {
             let obu_start = reader.position();
             let obu = SizedObu::parse(&mut BitReader::endian(&mut reader, ENDIANNESS))
@@ -172,6 +235,14 @@ impl RTPAv1Pay {
                         .read_exact(&mut bytes[(obu.header_len as usize)..bytes_total])
                         .map_err(err_flow!(element, buf_read))?;
 
+                    if obu.obu_type == ObuType::SequenceHeader {
+                        self.handle_sequence_header(
+                            element,
+                            state,
+                            &bytes[(obu.header_len as usize)..],
+                        );
+                    }
+
                     state.obus.push(ObuData {
                         info: obu,
                         bytes,
@@ -182,6 +253,12 @@ impl RTPAv1Pay {
             }
         }
 
+        // the marker flag is set on the last buffer of a temporal unit,
+        // so there is no need to wait for the next temporal delimiter
+        if marker {
+            self.end_temporal_unit(state, dts, pts);
+        }
+
         let mut list = gst::BufferList::new();
         {
             let list = list.get_mut().unwrap();
@@ -194,6 +271,217 @@ impl RTPAv1Pay {
         Ok(list)
     }
 
+    /// Store an empty temporal delimiter, so the packet containing the last
+    /// OBUs received so far will be marked as the end of the temporal unit.
+    fn end_temporal_unit(
+        &self,
+        state: &mut State,
+        dts: Option<gst::ClockTime>,
+        pts: Option<gst::ClockTime>,
+    ) {
+        state.obus.push(ObuData {
+            info: SizedObu {
+                obu_type: ObuType::TemporalDelimiter,
+                header_len: 1,
+                leb_size: 1,
+                ..SizedObu::default()
+            },
+            bytes: Vec::new(),
+            dts,
+            pts,
+        });
+    }
+
+    /// Construct packets for all remaining OBUs, regardless of their size.
+    fn flush_obus(
+        &self,
+        element: &<Self as ObjectSubclass>::Type,
+        state: &mut State,
+        list: &mut gst::BufferListRef,
+    ) -> Result<(), gst::FlowError> {
+        while let Some(packet_data) = self.consider_new_packet(element, state, true) {
+            let buffer = self.generate_new_packet(element, state, packet_data)?;
+            list.add(buffer);
+        }
+
+        Ok(())
+    }
+
+    /// Time covered by the OBUs held back for aggregation, from the PTS of the
+    /// oldest one to the end of the last received buffer.
+    fn pending_span(
+        state: &State,
+        pts: Option<gst::ClockTime>,
+        duration: Option<gst::ClockTime>,
+    ) -> Option<gst::ClockTime> {
+        let first_pts = state.obus.first()?.pts?;
+        let end = pts? + duration.unwrap_or(gst::ClockTime::ZERO);
+
+        end.checked_sub(first_pts)
+    }
+
+    /// Prepare the dependency descriptor template structure for the
+    /// operating points signalled in a sequence header.
+    fn handle_sequence_header(
+        &self,
+        element: &<Self as ObjectSubclass>::Type,
+        state: &mut State,
+        payload: &[u8],
+    ) {
+        if self.settings.lock().unwrap().dependency_descriptor_ext_id == 0 {
+            return;
+        }
+
+        let ops = match OperatingPoints::parse(&mut BitReader::endian(
+            Cursor::new(payload),
+            ENDIANNESS,
+        )) {
+            Ok(ops) => ops,
+            Err(err) => {
+                gst::warning!(CAT, obj: element, "Failed to parse sequence header: {}", err);
+                return;
+            }
+        };
+
+        let current = state
+            .dd_next_structure
+            .as_ref()
+            .or(state.dd_structure.as_ref());
+        let offset = current.map_or(0, |structure| structure.template_id_offset);
+
+        let mut structure = FrameDependencyStructure::from_operating_points(&ops, offset);
+
+        // use new template IDs if the structure changed
+        if let Some(current) = current {
+            if *current != structure {
+                structure = FrameDependencyStructure::from_operating_points(
+                    &ops,
+                    current.next_template_id_offset(),
+                );
+            }
+        }
+
+        gst::debug!(
+            CAT,
+            obj: element,
+            "operating points {:?}, using {} templates for {} decode targets",
+            ops.idc,
+            structure.templates.len(),
+            structure.decode_target_count
+        );
+
+        // the largest descriptor carries the structure and frame diffs
+        let dd_size = DependencyDescriptor {
+            start_of_frame: true,
+            end_of_frame: true,
+            template_id: 0,
+            frame_number: 0,
+            structure: Some(&structure),
+            custom_fdiffs: Some(&[1]),
+        }
+        .to_bytes()
+        .map_or(0, |bytes| bytes.len() as u32);
+
+        // extension header, element header and padding
+        let element_header = if dd_size <= 16 { 1 } else { 2 };
+        state.dd_ext_size = 4 + (element_header + dd_size + 3) / 4 * 4;
+        state.dd_next_structure = Some(structure);
+    }
+
+    /// Construct the dependency descriptor for the packet described by
+    /// `packet`, before its OBUs are removed from the state.
+    fn dependency_descriptor(
+        &self,
+        element: &<Self as ObjectSubclass>::Type,
+        state: &mut State,
+        packet: &PacketOBUData,
+    ) -> Option<(u8, Vec<u8>)> {
+        let ext_id = self.settings.lock().unwrap().dependency_descriptor_ext_id;
+        if ext_id == 0 {
+            return None;
+        }
+
+        let obus = &state.obus[..packet.obu_count];
+        let layer = obus
+            .iter()
+            .find(|obu| obu.info.has_extension)
+            .map_or((0, 0), |obu| (obu.info.spatial_id, obu.info.temporal_id));
+        let has_sequence_header = obus
+            .iter()
+            .any(|obu| obu.info.obu_type == ObuType::SequenceHeader);
+
+        // the structure is attached to the first packet of every key frame
+        let mut attach_structure = false;
+        if has_sequence_header {
+            if let Some(structure) = state.dd_next_structure.take() {
+                state.dd_structure = Some(structure);
+            }
+            state.dd_key_frame = true;
+            attach_structure = true;
+        }
+
+        // a frame is only known to end here if the next OBU is already
+        // available, or if this packet ends the temporal unit
+        let end_of_frame = packet.ends_temporal_unit
+            || (packet.last_obu_fragment_size.is_none()
+                && state.obus.get(packet.obu_count).map_or(false, |next| {
+                    matches!(
+                        next.info.obu_type,
+                        ObuType::TemporalDelimiter | ObuType::SequenceHeader
+                    ) || (next.info.has_extension
+                        && (next.info.spatial_id, next.info.temporal_id) != layer)
+                }));
+
+        let start_of_frame = state.dd_frame_layer != Some(layer);
+        if start_of_frame {
+            state.dd_frame_number = state.dd_frame_number.wrapping_add(1);
+        }
+        state.dd_frame_layer = if end_of_frame { None } else { Some(layer) };
+
+        let key_frame = state.dd_key_frame;
+        if packet.ends_temporal_unit {
+            state.dd_key_frame = false;
+        }
+
+        let structure = state.dd_structure.as_ref()?;
+        let template_id = match structure.template_id(layer.0, layer.1) {
+            Some(template_id) => template_id,
+            None => {
+                gst::warning!(
+                    CAT,
+                    obj: element,
+                    "no template for spatial ID {} and temporal ID {}",
+                    layer.0,
+                    layer.1
+                );
+                return None;
+            }
+        };
+
+        // frames of a key frame only depend on the spatial layer below
+        let custom_fdiffs: Option<&[u32]> = match (key_frame, layer.0) {
+            (false, _) => None,
+            (true, 0) => Some(&[]),
+            (true, _) => Some(&[1]),
+        };
+
+        DependencyDescriptor {
+            start_of_frame,
+            end_of_frame,
+            template_id,
+            frame_number: state.dd_frame_number,
+            structure: if attach_structure && start_of_frame {
+                Some(structure)
+            } else {
+                None
+            },
+            custom_fdiffs,
+        }
+        .to_bytes()
+        .ok()
+        .map(|bytes| (ext_id as u8, bytes))
+    }
+
     /// Look at the size the currently stored OBUs would require,
     /// as well as their temportal IDs to decide if it is time to construct a
     /// new packet, and what OBUs to include in it.
@@ -216,7 +504,8 @@ impl RTPAv1Pay {
 
         let mut data = state.temp_packet_data.take().unwrap_or_else(|| {
             TempPacketData {
-                payload_limit: gst_rtp::RTPBuffer::calc_payload_len(element.mtu(), 0, 0),
+                payload_limit: gst_rtp::RTPBuffer::calc_payload_len(element.mtu(), 0, 0)
+                    .saturating_sub(state.dd_ext_size),
                 packet: PacketOBUData {
                     payload_size: 1, // 1 byte is used for the aggregation header
                     omit_last_size_field: true,
@@ -343,6 +632,8 @@ impl RTPAv1Pay {
             packet.obu_count
         );
 
+        let dependency_descriptor = self.dependency_descriptor(element, state, &packet);
+
         // prepare the outgoing buffer
         let mut outbuf =
             gst::Buffer::new_rtp_with_sizes(packet.payload_size, 0, 0).map_err(|err| {
@@ -367,6 +658,15 @@ impl RTPAv1Pay {
                 .expect("Failed to create RTPBuffer");
             rtp.set_marker(packet.ends_temporal_unit);
 
+            if let Some((ext_id, data)) = dependency_descriptor {
+                let res = if ext_id <= 14 && data.len() <= 16 {
+                    rtp.add_extension_onebyte_header(ext_id, &data)
+                } else {
+                    rtp.add_extension_twobytes_header(0, ext_id, &data)
+                };
+                res.map_err(err_flow!(element, ext_write))?;
+            }
+
             let payload = rtp
                 .payload_mut()
                 .expect("Failed to get mutable reference to RTP payload");
@@ -476,7 +776,47 @@ impl ObjectSubclass for RTPAv1Pay {
     type ParentType = gst_rtp::RTPBasePayload;
 }
 
-impl ObjectImpl for RTPAv1Pay {}
+impl ObjectImpl for RTPAv1Pay {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![glib::ParamSpecUInt::builder("dependency-descriptor-ext-id")
+                .nick("Dependency Descriptor Extension ID")
+                .blurb("ID of the AV1 dependency descriptor RTP header extension (0 = disabled)")
+                .maximum(255)
+                .default_value(DEFAULT_DEPENDENCY_DESCRIPTOR_EXT_ID)
+                .mutable_ready()
+                .build()]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "dependency-descriptor-ext-id" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.dependency_descriptor_ext_id = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "dependency-descriptor-ext-id" => {
+                let settings = self.settings.lock().unwrap();
+                settings.dependency_descriptor_ext_id.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
 
 impl GstObjectImpl for RTPAv1Pay {}
 
@@ -556,6 +896,17 @@ impl RTPBasePayloadImpl for RTPAv1Pay {
 
         gst::debug!(CAT, obj: element, "setting caps");
 
+        let ext_id = self.settings.lock().unwrap().dependency_descriptor_ext_id;
+        if ext_id != 0 {
+            let s = gst::Structure::builder("application/x-rtp")
+                .field(&format!("extmap-{}", ext_id), DEPENDENCY_DESCRIPTOR_URI)
+                .build();
+
+            element
+                .set_outcaps(Some(&s))
+                .map_err(|_| gst::loggable_error!(CAT, "Failed to set output caps"))?;
+        }
+
         Ok(())
     }
 
@@ -571,6 +922,9 @@ impl RTPBasePayloadImpl for RTPAv1Pay {
             buffer.size()
         );
 
+        let max_ptime = element.property::<i64>("max-ptime");
+        let min_ptime = element.property::<i64>("min-ptime");
+
         let mut state = self.state.lock().unwrap();
 
         if buffer.flags().contains(gst::BufferFlags::DISCONT) {
@@ -580,6 +934,8 @@ impl RTPBasePayloadImpl for RTPAv1Pay {
 
         let dts = buffer.dts();
         let pts = buffer.pts();
+        let duration = buffer.duration();
+        let marker = buffer.flags().contains(gst::BufferFlags::MARKER);
 
         let buffer = buffer.into_mapped_buffer_readable().map_err(|_| {
             gst::element_error!(
@@ -591,7 +947,28 @@ impl RTPBasePayloadImpl for RTPAv1Pay {
             gst::FlowError::Error
         })?;
 
-        let list = self.handle_new_obus(element, &mut state, buffer.as_slice(), dts, pts)?;
+        let mut list =
+            self.handle_new_obus(element, &mut state, buffer.as_slice(), dts, pts, marker)?;
+
+        // with a packet duration limit, don't hold back OBUs until the next buffer
+        // arrives if that would make the packet span more than allowed
+        if max_ptime >= 0 {
+            if let Some(span) = Self::pending_span(&state, pts, duration) {
+                let max_ptime = gst::ClockTime::from_nseconds(max_ptime as u64);
+                let min_ptime = gst::ClockTime::from_nseconds(min_ptime as u64);
+
+                if span > max_ptime && span >= min_ptime {
+                    gst::log!(
+                        CAT,
+                        obj: element,
+                        "pending OBUs span {}, exceeding max-ptime {}",
+                        span,
+                        max_ptime
+                    );
+                    self.flush_obus(element, &mut state, list.get_mut().unwrap())?;
+                }
+            }
+        }
         drop(state);
 
         if !list.is_empty() {
@@ -610,16 +987,7 @@ impl RTPBasePayloadImpl for RTPAv1Pay {
                 let mut list = gst::BufferList::new();
                 {
                     let mut state = self.state.lock().unwrap();
-                    let list = list.get_mut().unwrap();
-
-                    while let Some(packet_data) =
-                        self.consider_new_packet(element, &mut state, true)
-                    {
-                        match self.generate_new_packet(element, &mut state, packet_data) {
-                            Ok(buffer) => list.add(buffer),
-                            Err(_) => break,
-                        }
-                    }
+                    let _ = self.flush_obus(element, &mut state, list.get_mut().unwrap());
 
                     self.reset(element, &mut state);
                 }
@@ -222,3 +222,454 @@ fn test_payloader() {
         assert_eq!(packet.timestamp(), base_ts.unwrap() + ts_offset);
     }
 }
+
+#[test]
+#[rustfmt::skip]
+fn test_dependency_descriptor() {
+    let test_buffers: [(u64, Vec<u8>); 3] = [
+        (
+            0,
+            vec![
+                0b0001_0010, 0,
+                // sequence header with a single operating point for 2 temporal layers
+                0b0000_1010, 0b0000_0100, 0, 0b0000_0001, 0b0000_0011, 0b0000_0100,
+                0b0011_0110, 0b0000_0000, 0b0000_0100, 1, 2, 3, 4,
+            ],
+        ), (
+            1_000_000_000,
+            vec![
+                0b0001_0010, 0,
+                0b0011_0110, 0b0010_0000, 0b0000_0011, 1, 2, 3,
+            ],
+        ), (
+            2_000_000_000,
+            vec![
+                0b0001_0010, 0,
+                0b0011_0110, 0b0000_0000, 0b0000_0010, 1, 2,
+            ],
+        )
+    ];
+
+    let expected: [Vec<u8>; 3] = [
+        vec![   // key frame with template structure and no frame diffs
+            0b1100_0000, 0, 1,
+            0b1001_0000, 0b0000_0000, 0b0111_1001, 0b1000_1010, 0b0000_0000,
+        ],
+        vec![   // temporal layer 1
+            0b1100_0001, 0, 2,
+        ],
+        vec![   // end of the frame is not known when flushing
+            0b1000_0000, 0, 3,
+        ],
+    ];
+
+    init();
+
+    let mut h_pay = Harness::new("rtpav1pay");
+    h_pay
+        .element()
+        .unwrap()
+        .set_property("dependency-descriptor-ext-id", 1u32);
+    h_pay.play();
+
+    h_pay.set_src_caps(
+        Caps::builder("video/x-av1")
+            .field("parsed", true)
+            .field("stream-format", "obu-stream")
+            .field("alignment", "obu")
+            .build(),
+    );
+
+    for (pts, bytes) in &test_buffers {
+        let mut buffer = Buffer::from_slice(bytes.clone());
+        buffer.get_mut().unwrap().set_pts(ClockTime::from_nseconds(*pts));
+
+        h_pay.push(buffer).unwrap();
+    }
+    h_pay.push_event(Eos::new());
+
+    let caps = h_pay.sinkpad().unwrap().current_caps().unwrap();
+    assert_eq!(
+        caps.structure(0).unwrap().get::<&str>("extmap-1").unwrap(),
+        "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension"
+    );
+
+    let mut h_depay = Harness::new("rtpav1depay");
+    h_depay.play();
+    h_depay.set_src_caps(caps);
+
+    for (idx, ex) in expected.iter().enumerate() {
+        println!("checking packet {}...", idx);
+
+        let buffer = h_pay.pull().unwrap();
+        {
+            let packet = RTPBuffer::from_buffer_readable(&buffer).unwrap();
+            assert_eq!(packet.extension_onebyte_header(1, 0).unwrap(), ex.as_slice());
+        }
+
+        h_depay.push(buffer).unwrap();
+    }
+
+    // the last temporal unit is never completed
+    for (idx, ex) in [(0, 0), (0, 1)].iter().enumerate() {
+        println!("checking layer meta {}...", idx);
+
+        let buffer = h_depay.pull().unwrap();
+        let meta = gst::meta::CustomMeta::from_buffer(&buffer, "GstRtpAv1LayerMeta").unwrap();
+        let s = meta.structure();
+        assert_eq!(s.get::<u32>("spatial-id").unwrap(), ex.0);
+        assert_eq!(s.get::<u32>("temporal-id").unwrap(), ex.1);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_depayloader_interop() {
+    // packetization as done by senders that keep the internal size fields
+    // of the OBUs and transmit temporal delimiters
+    let test_packets: [(Vec<u8>, bool, u32); 3] = [
+        ( // size fields for all elements, temporal delimiter included
+            vec![
+                0b0000_1000,
+                0b0000_0010, 0b0001_0010, 0,
+                0b0000_0101, 0b0011_0010, 0b0000_0011, 1, 2, 3,
+            ],
+            true,
+            100_000,
+        ), ( // OBU fragment with internal size field
+            vec![
+                0b0101_0000,
+                0b0011_0110, 0b0010_1000, 0b0000_0110, 1, 2, 3,
+            ],
+            false,
+            190_000,
+        ), ( // last fragment of that OBU
+            vec![
+                0b1001_0000,
+                4, 5, 6,
+            ],
+            true,
+            190_000,
+        )
+    ];
+
+    let expected: [Vec<u8>; 2] = [
+        vec![
+            0b0001_0010, 0,
+            0b0011_0010, 0b0000_0011, 1, 2, 3,
+        ],
+        vec![
+            0b0001_0010, 0,
+            0b0011_0110, 0b0010_1000, 0b0000_0110, 1, 2, 3, 4, 5, 6,
+        ],
+    ];
+
+    init();
+
+    let mut h = Harness::new("rtpav1depay");
+    h.play();
+
+    let caps = Caps::builder("application/x-rtp")
+        .field("media", "video")
+        .field("payload", 96)
+        .field("clock-rate", 90000)
+        .field("encoding-name", "AV1")
+        .build();
+    h.set_src_caps(caps);
+
+    for (idx, (bytes, marker, timestamp)) in test_packets.iter().enumerate() {
+        let mut buf = Buffer::new_rtp_with_sizes(bytes.len() as u32, 0, 0).unwrap();
+        {
+            let buf_mut = buf.get_mut().unwrap();
+            let mut rtp_mut = RTPBuffer::from_buffer_writable(buf_mut).unwrap();
+            rtp_mut.set_marker(*marker);
+            rtp_mut.set_timestamp(*timestamp);
+            rtp_mut.set_payload_type(96);
+            rtp_mut.set_seq(idx as u16);
+            rtp_mut.payload_mut().unwrap().copy_from_slice(bytes);
+        }
+
+        h.push(buf).unwrap();
+    }
+
+    for (idx, ex) in expected.iter().enumerate() {
+        println!("checking buffer {}...", idx);
+
+        let buffer = h.pull().unwrap();
+        let actual = buffer.into_mapped_buffer_readable().unwrap();
+        assert_eq!(actual.as_slice(), ex.as_slice());
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_depayloader_libwebrtc() {
+    // payloads from the AV1 depacketizer tests of libwebrtc, see
+    // modules/rtp_rtcp/source/video_rtp_depacketizer_av1_unittest.cc
+    // ("AssembleFrameSetsOBUPayloadSize*" tests)
+    let test_packets: [(Vec<u8>, bool, u32); 3] = [
+        ( // OBU without size field
+            vec![
+                0b0001_0000,
+                0b0011_0000, 20, 30, 40,
+            ],
+            true,
+            0,
+        ), ( // OBU keeping its size field
+            vec![
+                0b0001_0000,
+                0b0011_0010, 3, 20, 30, 40,
+            ],
+            true,
+            3_000,
+        ), ( // OBU with extension header, keeping its size field
+            vec![
+                0b0001_0000,
+                0b0011_0110, 0b0100_1000, 3, 20, 30, 40,
+            ],
+            true,
+            6_000,
+        )
+    ];
+
+    let expected: [Vec<u8>; 3] = [
+        vec![
+            0b0001_0010, 0,
+            0b0011_0010, 3, 20, 30, 40,
+        ],
+        vec![
+            0b0001_0010, 0,
+            0b0011_0010, 3, 20, 30, 40,
+        ],
+        vec![
+            0b0001_0010, 0,
+            0b0011_0110, 0b0100_1000, 3, 20, 30, 40,
+        ],
+    ];
+
+    init();
+
+    let mut h = Harness::new("rtpav1depay");
+    h.play();
+
+    let caps = Caps::builder("application/x-rtp")
+        .field("media", "video")
+        .field("payload", 96)
+        .field("clock-rate", 90000)
+        .field("encoding-name", "AV1")
+        .build();
+    h.set_src_caps(caps);
+
+    for (idx, (bytes, marker, timestamp)) in test_packets.iter().enumerate() {
+        let mut buf = Buffer::new_rtp_with_sizes(bytes.len() as u32, 0, 0).unwrap();
+        {
+            let buf_mut = buf.get_mut().unwrap();
+            let mut rtp_mut = RTPBuffer::from_buffer_writable(buf_mut).unwrap();
+            rtp_mut.set_marker(*marker);
+            rtp_mut.set_timestamp(*timestamp);
+            rtp_mut.set_payload_type(96);
+            rtp_mut.set_seq(idx as u16);
+            rtp_mut.payload_mut().unwrap().copy_from_slice(bytes);
+        }
+
+        h.push(buf).unwrap();
+    }
+
+    for (idx, ex) in expected.iter().enumerate() {
+        println!("checking buffer {}...", idx);
+
+        let buffer = h.pull().unwrap();
+        let actual = buffer.into_mapped_buffer_readable().unwrap();
+        assert_eq!(actual.as_slice(), ex.as_slice());
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_payloader_aggregation() {
+    let test_buffers: [(u64, bool, Vec<u8>); 4] = [
+        (
+            0,
+            true,   // marker flag, the temporal unit ends with this buffer
+            vec![
+                0b0001_0010, 0,
+                0b0011_0010, 0b0000_0100, 1, 2, 3, 4,
+            ],
+        ), (
+            1_000_000_000,
+            false,
+            vec![   // no temporal delimiters from here on
+                0b0011_0010, 0b0000_0100, 5, 6, 7, 8,
+            ],
+        ), (
+            2_000_000_000,
+            false,
+            vec![
+                0b0011_0010, 0b0000_0001, 9,
+            ],
+        ), (
+            3_000_000_000,
+            false,
+            vec![
+                0b0011_0010, 0b0000_0001, 10,
+            ],
+        )
+    ];
+
+    let expected = [
+        (
+            true,   // marker bit
+            0,      // relative RTP timestamp
+            vec![   // payload bytes
+                0b0001_1000,
+                0b0011_0000, 1, 2, 3, 4,
+            ],
+        ), (
+            true,
+            90_000,
+            vec![
+                0b0001_0000,
+                0b0011_0000, 5, 6, 7, 8,
+            ],
+        ), (
+            true,
+            180_000,
+            vec![
+                0b0001_0000,
+                0b0011_0000, 9,
+            ],
+        ), (
+            false,
+            270_000,
+            vec![
+                0b0001_0000,
+                0b0011_0000, 10,
+            ],
+        )
+    ];
+
+    // number of packets available after pushing each buffer
+    let packets_ready = [1, 1, 2, 4];
+
+    init();
+
+    let mut h = Harness::new("rtpav1pay");
+    h.play();
+
+    let caps = Caps::builder("video/x-av1")
+        .field("parsed", true)
+        .field("stream-format", "obu-stream")
+        .field("alignment", "obu")
+        .build();
+    h.set_src_caps(caps);
+
+    for (idx, (pts, marker, bytes)) in test_buffers.iter().enumerate() {
+        // don't hold back the last buffer's OBUs for the duration of the buffer
+        if idx == 3 {
+            h.element().unwrap().set_property("max-ptime", 0i64);
+        }
+
+        let mut buffer = Buffer::from_slice(bytes.clone());
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(ClockTime::from_nseconds(*pts));
+            buffer.set_duration(ClockTime::SECOND);
+            if *marker {
+                buffer.set_flags(gst::BufferFlags::MARKER);
+            }
+        }
+
+        h.push(buffer).unwrap();
+        assert_eq!(h.buffers_received(), packets_ready[idx]);
+    }
+
+    let mut base_ts = None;
+    for (idx, (marker, ts_offset, payload)) in expected.iter().enumerate() {
+        println!("checking packet {}...", idx);
+
+        let buffer = h.pull().unwrap();
+        let packet = RTPBuffer::from_buffer_readable(&buffer).unwrap();
+        if base_ts.is_none() {
+            base_ts = Some(packet.timestamp());
+        }
+
+        assert_eq!(packet.payload().unwrap(), payload.as_slice());
+        assert_eq!(packet.is_marker(), *marker);
+        assert_eq!(packet.timestamp(), base_ts.unwrap() + ts_offset);
+    }
+}
+
+#[test]
+fn test_payloader_ptime() {
+    // (PTS, duration, number of packets available after pushing the buffer)
+    let test_buffers = [
+        // OBUs held back for 10ms are within max-ptime
+        (0, 10, 0),
+        // new temporal unit, the previous one is complete
+        (10, 10, 1),
+        // OBUs held back for 30ms would exceed max-ptime
+        (20, 30, 3),
+        // exceeding max-ptime, but below min-ptime
+        (50, 30, 3),
+        // both exceeded once the next OBU of the same temporal unit arrives
+        (50, 60, 4),
+    ];
+
+    init();
+
+    let mut h = Harness::new("rtpav1pay");
+    h.play();
+
+    let caps = Caps::builder("video/x-av1")
+        .field("parsed", true)
+        .field("stream-format", "obu-stream")
+        .field("alignment", "obu")
+        .build();
+    h.set_src_caps(caps);
+
+    h.element()
+        .unwrap()
+        .set_property("max-ptime", ClockTime::from_mseconds(20).nseconds() as i64);
+
+    for (idx, (pts, duration, packets_ready)) in test_buffers.iter().enumerate() {
+        if idx == 3 {
+            h.element()
+                .unwrap()
+                .set_property("min-ptime", ClockTime::from_mseconds(40).nseconds() as i64);
+        }
+
+        let mut buffer = Buffer::from_slice([0b0011_0010, 0b0000_0001, idx as u8]);
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(ClockTime::from_mseconds(*pts));
+            buffer.set_duration(ClockTime::from_mseconds(*duration));
+        }
+
+        h.push(buffer).unwrap();
+        assert_eq!(h.buffers_received(), *packets_ready, "buffer {}", idx);
+    }
+
+    // (marker bit, payload)
+    let expected = [
+        (true, vec![0b0001_1000, 0b0011_0000, 0]),
+        (true, vec![0b0001_0000, 0b0011_0000, 1]),
+        (false, vec![0b0001_0000, 0b0011_0000, 2]),
+        (
+            false,
+            vec![0b0010_0000, 0b0000_0010, 0b0011_0000, 3, 0b0011_0000, 4],
+        ),
+    ];
+
+    for (idx, (marker, payload)) in expected.iter().enumerate() {
+        let buffer = h.pull().unwrap();
+        let packet = RTPBuffer::from_buffer_readable(&buffer).unwrap();
+
+        assert_eq!(
+            packet.payload().unwrap(),
+            payload.as_slice(),
+            "packet {}",
+            idx
+        );
+        assert_eq!(packet.is_marker(), *marker, "packet {}", idx);
+    }
+}
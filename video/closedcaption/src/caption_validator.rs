@@ -0,0 +1,899 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Validation of the timecodes and caption data written to or read from
+//! SCC and MCC files, as done by broadcast QC tools.
+
+use gst::glib;
+use gst::prelude::*;
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::ccutils::cdp_frame_rate;
+use crate::parser_utils::TimeCode;
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstCaptionValidation")]
+pub enum Validation {
+    #[enum_value(name = "Disabled: Don't validate the stream", nick = "disabled")]
+    Disabled,
+    #[enum_value(name = "Report: Post a message for each issue", nick = "report")]
+    Report,
+    #[enum_value(
+        name = "Repair: Post a message for each issue and fix it when possible",
+        nick = "repair"
+    )]
+    Repair,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    /// The timecode went backwards or, for streams that must be contiguous,
+    /// skipped frames
+    TimecodeDiscontinuity,
+    /// Drop frame timecode on a non-drop frame rate, or a frame number that
+    /// is dropped
+    DropFrame,
+    /// CEA-608 control code that is not transmitted twice in a row
+    ControlCodeDoubling,
+    /// More caption data than fits in the frames it is sent in
+    BandwidthOverrun,
+}
+
+impl IssueKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueKind::TimecodeDiscontinuity => "timecode-discontinuity",
+            IssueKind::DropFrame => "drop-frame",
+            IssueKind::ControlCodeDoubling => "control-code-doubling",
+            IssueKind::BandwidthOverrun => "bandwidth-overrun",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub kind: IssueKind,
+    /// Timecode of the frame the issue was detected at, as received
+    pub timecode: TimeCode,
+    pub details: String,
+    pub repaired: bool,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}: {}{}",
+            self.kind.as_str(),
+            self.timecode,
+            self.details,
+            if self.repaired { " (repaired)" } else { "" }
+        )
+    }
+}
+
+impl Issue {
+    /// The element message posted for the issue
+    pub fn structure(&self) -> gst::Structure {
+        gst::Structure::builder("caption-validation")
+            .field("issue", self.kind.as_str())
+            .field("timecode", self.timecode.to_string())
+            .field("details", &self.details)
+            .field("repaired", self.repaired)
+            .build()
+    }
+}
+
+/// Logs and posts the issues as element messages
+pub fn post_issues<E: IsA<gst::Element>>(element: &E, cat: gst::DebugCategory, issues: Vec<Issue>) {
+    for issue in issues {
+        gst::warning!(cat, obj: element, "{}", issue);
+
+        let _ = element.post_message(
+            gst::message::Element::builder(issue.structure())
+                .src(element)
+                .build(),
+        );
+    }
+}
+
+impl From<&gst_video::ValidVideoTimeCode> for TimeCode {
+    fn from(tc: &gst_video::ValidVideoTimeCode) -> Self {
+        TimeCode {
+            hours: tc.hours(),
+            minutes: tc.minutes(),
+            seconds: tc.seconds(),
+            frames: tc.frames(),
+            drop_frame: tc
+                .flags()
+                .contains(gst_video::VideoTimeCodeFlags::DROP_FRAME),
+        }
+    }
+}
+
+/// Replaces the timecode meta of a buffer with a repaired timecode
+pub fn set_timecode_meta(buffer: &mut gst::BufferRef, tc: &TimeCode) {
+    let mut meta = match buffer.meta_mut::<gst_video::VideoTimeCodeMeta>() {
+        Some(meta) => meta,
+        None => return,
+    };
+
+    let old = meta.tc();
+    let timecode = gst_video::VideoTimeCode::new(
+        old.fps(),
+        old.latest_daily_jam().as_ref(),
+        if tc.drop_frame {
+            old.flags() | gst_video::VideoTimeCodeFlags::DROP_FRAME
+        } else {
+            old.flags() - gst_video::VideoTimeCodeFlags::DROP_FRAME
+        },
+        tc.hours,
+        tc.minutes,
+        tc.seconds,
+        tc.frames,
+        old.field_count(),
+    );
+
+    if let Ok(timecode) = gst_video::ValidVideoTimeCode::try_from(timecode) {
+        meta.set_tc(timecode);
+    }
+}
+
+/// One CEA-608 byte pair of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cea608Slot {
+    pub field: usize,
+    pub valid: bool,
+    pub pair: [u8; 2],
+}
+
+impl Cea608Slot {
+    fn is_padding(&self) -> bool {
+        !self.valid || (self.pair[0] & 0x7f == 0 && self.pair[1] & 0x7f == 0)
+    }
+
+    /// Control codes, including special and extended characters, which
+    /// have to be sent twice
+    fn is_control(&self) -> bool {
+        (0x10..=0x1f).contains(&(self.pair[0] & 0x7f)) && self.pair[1] & 0x7f >= 0x20
+    }
+}
+
+fn nominal_fps(fps_n: i32, fps_d: i32) -> u32 {
+    ((fps_n + fps_d - 1) / fps_d) as u32
+}
+
+/// Frames dropped every minute but every tenth minute in drop frame mode
+fn dropped_frames(fps: u32) -> u32 {
+    fps / 15
+}
+
+/// Frame count since midnight of a timecode
+pub fn frame_number(tc: &TimeCode, fps: u32) -> u64 {
+    let minutes = (tc.hours % 24) as u64 * 60 + tc.minutes as u64;
+    let frames = (minutes * 60 + tc.seconds as u64) * fps as u64 + tc.frames as u64;
+
+    if tc.drop_frame {
+        frames - dropped_frames(fps) as u64 * (minutes - minutes / 10)
+    } else {
+        frames
+    }
+}
+
+/// Inverse of `frame_number()`
+pub fn timecode_from_frame_number(frame_number: u64, fps: u32, drop_frame: bool) -> TimeCode {
+    let mut frames = frame_number;
+
+    if drop_frame {
+        let dropped = dropped_frames(fps) as u64;
+        let per_minute = fps as u64 * 60 - dropped;
+        let per_ten_minutes = fps as u64 * 600 - 9 * dropped;
+
+        let tens = frames / per_ten_minutes;
+        let rem = frames % per_ten_minutes;
+        frames += 9 * dropped * tens;
+        if rem > dropped {
+            frames += dropped * ((rem - dropped) / per_minute);
+        }
+    }
+
+    let fps = fps as u64;
+    TimeCode {
+        hours: (frames / (fps * 3600) % 24) as u32,
+        minutes: (frames / (fps * 60) % 60) as u32,
+        seconds: (frames / fps % 60) as u32,
+        frames: (frames % fps) as u32,
+        drop_frame,
+    }
+}
+
+/// Validates, and optionally repairs, the timecodes and caption data of the
+/// frames of a stream in order
+#[derive(Debug)]
+pub struct Validator {
+    repair: bool,
+    fps_n: i32,
+    fps_d: i32,
+    fps: u32,
+    /// Whether every frame must have a timecode, as in MCC files
+    contiguous: bool,
+    /// Whether the caller fills the frames missing from contiguous streams
+    fill_missing: bool,
+    /// Frame numbers missing before the last frame, and whether their
+    /// timecodes are drop frame
+    missing: Option<(Range<u64>, bool)>,
+    line_start: Option<u64>,
+    next_frame: Option<u64>,
+    /// Control code waiting for its repetition, per field
+    pending: [Option<[u8; 2]>; 2],
+}
+
+impl Validator {
+    pub fn new(fps_n: i32, fps_d: i32, contiguous: bool, repair: bool) -> Self {
+        Self {
+            repair,
+            fps_n,
+            fps_d,
+            fps: nominal_fps(fps_n, fps_d),
+            contiguous,
+            fill_missing: false,
+            missing: None,
+            line_start: None,
+            next_frame: None,
+            pending: [None, None],
+        }
+    }
+
+    /// Makes the gaps of contiguous streams repairable, the caller then
+    /// fills them with the frames of `take_missing_frames()`
+    pub fn set_fill_missing(&mut self, fill_missing: bool) {
+        self.fill_missing = fill_missing;
+    }
+
+    /// The timecodes of the frames missing before the last checked frame,
+    /// when filling them
+    pub fn take_missing_frames(&mut self) -> Vec<TimeCode> {
+        match self.missing.take() {
+            Some((numbers, drop_frame)) => numbers
+                .map(|number| timecode_from_frame_number(number, self.fps, drop_frame))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.missing = None;
+        self.line_start = None;
+        self.next_frame = None;
+        self.pending = [None, None];
+    }
+
+    /// Checks the timecode of a run of `frames` consecutive frames, as in
+    /// SCC lines. Repairing moves the timecode to the next expected frame.
+    pub fn timecode(&mut self, tc: &mut TimeCode, frames: usize, issues: &mut Vec<Issue>) {
+        let received = tc.clone();
+
+        if tc.drop_frame && self.fps_d == 1 {
+            issues.push(Issue {
+                kind: IssueKind::DropFrame,
+                timecode: received.clone(),
+                details: format!("drop frame timecode at {} fps", self.fps_n),
+                repaired: self.repair,
+            });
+            if self.repair {
+                tc.drop_frame = false;
+            }
+        }
+
+        let dropped = dropped_frames(self.fps);
+        if tc.drop_frame
+            && self.fps_d != 1
+            && tc.seconds == 0
+            && tc.minutes % 10 != 0
+            && tc.frames < dropped
+        {
+            issues.push(Issue {
+                kind: IssueKind::DropFrame,
+                timecode: received.clone(),
+                details: format!("frame {} is dropped in minute {}", tc.frames, tc.minutes),
+                repaired: self.repair,
+            });
+            if self.repair {
+                tc.frames = dropped;
+            }
+        }
+
+        if tc.frames >= self.fps || tc.hours >= 24 {
+            // Can't be mapped to a frame number, the caller errors out
+            return;
+        }
+
+        // Drop frame timecodes only exist for NTSC frame rates
+        let mut number = if self.fps_d == 1 {
+            frame_number(
+                &TimeCode {
+                    drop_frame: false,
+                    ..tc.clone()
+                },
+                self.fps,
+            )
+        } else {
+            frame_number(tc, self.fps)
+        };
+
+        if let (Some(line_start), Some(next_frame)) = (self.line_start, self.next_frame) {
+            if number < next_frame {
+                let (kind, details) = if number >= line_start {
+                    (
+                        IssueKind::BandwidthOverrun,
+                        format!(
+                            "previous caption data extends {} frames past this timecode",
+                            next_frame - number
+                        ),
+                    )
+                } else {
+                    (
+                        IssueKind::TimecodeDiscontinuity,
+                        format!("timecode goes back by {} frames", next_frame - number),
+                    )
+                };
+
+                issues.push(Issue {
+                    kind,
+                    timecode: received,
+                    details,
+                    repaired: self.repair,
+                });
+
+                if self.repair {
+                    number = next_frame;
+                    *tc = timecode_from_frame_number(
+                        number,
+                        self.fps,
+                        tc.drop_frame && self.fps_d != 1,
+                    );
+                }
+            } else if number > next_frame && self.contiguous {
+                let repair = self.repair && self.fill_missing;
+
+                issues.push(Issue {
+                    kind: IssueKind::TimecodeDiscontinuity,
+                    timecode: received,
+                    details: format!("{} frames are missing", number - next_frame),
+                    repaired: repair,
+                });
+
+                if repair {
+                    self.missing = Some((next_frame..number, tc.drop_frame && self.fps_d != 1));
+                }
+            }
+        }
+
+        self.line_start = Some(number);
+        self.next_frame = Some(number + frames as u64);
+    }
+
+    /// Checks that the control codes of the byte pairs of a frame are
+    /// doubled. Repairing replaces the padding that follows a single control
+    /// code with its repetition, if the bandwidth of the frame allows it.
+    pub fn cea608(&mut self, tc: &TimeCode, slots: &mut [Cea608Slot], issues: &mut Vec<Issue>) {
+        let max = self.max_cea608_pairs();
+        let mut counts = [0, 0];
+
+        for slot in slots.iter_mut() {
+            let field = slot.field.min(1);
+
+            if slot.is_padding() {
+                if let Some(pair) = self.pending[field].take() {
+                    let repair = self.repair && counts[field] < max;
+                    issues.push(self.doubling_issue(tc, field, pair, repair));
+                    if repair {
+                        counts[field] += 1;
+                        slot.valid = true;
+                        slot.pair = pair;
+                    }
+                }
+                continue;
+            }
+
+            counts[field] += 1;
+            if let Some(pair) = self.pending[field].take() {
+                if pair == slot.pair {
+                    continue;
+                }
+                issues.push(self.doubling_issue(tc, field, pair, false));
+            }
+
+            if slot.is_control() {
+                self.pending[field] = Some(slot.pair);
+            }
+        }
+    }
+
+    /// Terminates a run of frames after which no data can follow, as an SCC
+    /// line, returning the repetition of a single control code of the first
+    /// field to append to the run when repairing
+    pub fn finish_cea608(&mut self, tc: &TimeCode, issues: &mut Vec<Issue>) -> Option<[u8; 2]> {
+        let pair = self.pending[0].take()?;
+        issues.push(self.doubling_issue(tc, 0, pair, self.repair));
+
+        if self.repair {
+            self.next_frame = self.next_frame.map(|next| next + 1);
+            Some(pair)
+        } else {
+            None
+        }
+    }
+
+    fn doubling_issue(&self, tc: &TimeCode, field: usize, pair: [u8; 2], repaired: bool) -> Issue {
+        Issue {
+            kind: IssueKind::ControlCodeDoubling,
+            timecode: tc.clone(),
+            details: format!(
+                "control code {:02x}{:02x} in field {} is not doubled",
+                pair[0],
+                pair[1],
+                field + 1
+            ),
+            repaired,
+        }
+    }
+
+    /// Most CEA-608 byte pairs per field in one frame
+    fn max_cea608_pairs(&self) -> usize {
+        ((30 + self.fps - 1) / self.fps) as usize
+    }
+
+    /// Invalidates the CEA-608 byte pairs above the bandwidth of a frame
+    fn cea608_bandwidth(&self, tc: &TimeCode, slots: &mut [Cea608Slot], issues: &mut Vec<Issue>) {
+        let max = self.max_cea608_pairs();
+
+        for field in 0..2 {
+            let mut count = 0;
+            let mut excess = 0;
+
+            for slot in slots.iter_mut().filter(|slot| slot.field == field) {
+                if slot.is_padding() {
+                    continue;
+                }
+
+                count += 1;
+                if count > max {
+                    excess += 1;
+                    if self.repair {
+                        slot.valid = false;
+                    }
+                }
+            }
+
+            if excess > 0 {
+                issues.push(Issue {
+                    kind: IssueKind::BandwidthOverrun,
+                    timecode: tc.clone(),
+                    details: format!(
+                        "{} CEA-608 byte pairs in field {}, at most {} per frame",
+                        count,
+                        field + 1,
+                        max
+                    ),
+                    repaired: self.repair,
+                });
+            }
+        }
+    }
+
+    /// Validates an SCC line, whose byte pairs are sent one per frame in
+    /// the first field
+    pub fn scc_line(&mut self, tc: &mut TimeCode, data: &mut Vec<u8>, issues: &mut Vec<Issue>) {
+        let received = tc.clone();
+        self.timecode(tc, data.len() / 2, issues);
+
+        for pair in data.chunks_exact_mut(2) {
+            let mut slots = [Cea608Slot {
+                field: 0,
+                valid: true,
+                pair: [pair[0], pair[1]],
+            }];
+            self.cea608(&received, &mut slots, issues);
+            pair.copy_from_slice(&slots[0].pair);
+        }
+
+        if let Some(pair) = self.finish_cea608(&received, issues) {
+            data.extend_from_slice(&pair);
+        }
+    }
+
+    /// Validates the byte pair of a single frame of the first field
+    pub fn cea608_frame(&mut self, tc: &mut TimeCode, pair: &mut [u8; 2], issues: &mut Vec<Issue>) {
+        let received = tc.clone();
+        self.timecode(tc, 1, issues);
+
+        let mut slots = [Cea608Slot {
+            field: 0,
+            valid: true,
+            pair: *pair,
+        }];
+        self.cea608(&received, &mut slots, issues);
+        *pair = slots[0].pair;
+    }
+
+    /// Validates the S334-1A triplets of a single frame
+    pub fn s334_1a_frame(
+        &mut self,
+        tc: &mut TimeCode,
+        data: &mut Vec<u8>,
+        issues: &mut Vec<Issue>,
+    ) {
+        let received = tc.clone();
+        self.timecode(tc, 1, issues);
+
+        let mut slots = data
+            .chunks_exact(3)
+            .map(|triplet| Cea608Slot {
+                field: if triplet[0] & 0x80 == 0x80 { 0 } else { 1 },
+                valid: true,
+                pair: [triplet[1], triplet[2]],
+            })
+            .collect::<Vec<_>>();
+        self.cea608(&received, &mut slots, issues);
+        self.cea608_bandwidth(&received, &mut slots, issues);
+
+        let mut repaired = Vec::with_capacity(data.len());
+        for (slot, triplet) in slots.iter().zip(data.chunks_exact(3)) {
+            if slot.valid {
+                repaired.extend_from_slice(&[triplet[0], slot.pair[0], slot.pair[1]]);
+            }
+        }
+        *data = repaired;
+    }
+
+    /// Validates the CDP packet of a single frame
+    pub fn cdp_frame(&mut self, tc: &mut TimeCode, cdp: &mut Vec<u8>, issues: &mut Vec<Issue>) {
+        let received = tc.clone();
+        self.timecode(tc, 1, issues);
+
+        let (start, end) = match cc_data_range(cdp) {
+            Some(range) => range,
+            None => return,
+        };
+
+        let mut cc_data = cdp[start..end].to_vec();
+        let mut changed = false;
+
+        if let Some((_, max)) = cdp_frame_rate(self.fps_n, self.fps_d) {
+            let count = cc_data.len() / 3;
+            if count > max {
+                if self.repair {
+                    // Drop invalid triplets first, then the last ones
+                    let mut invalid = count - max;
+                    let mut i = cc_data.len();
+                    while invalid > 0 && i >= 3 {
+                        i -= 3;
+                        if cc_data[i] & 0x04 == 0 {
+                            cc_data.drain(i..i + 3);
+                            invalid -= 1;
+                        }
+                    }
+                    cc_data.truncate(3 * max);
+                    changed = true;
+                }
+
+                issues.push(Issue {
+                    kind: IssueKind::BandwidthOverrun,
+                    timecode: received.clone(),
+                    details: format!("cc_count {} above {} for the frame rate", count, max),
+                    repaired: self.repair,
+                });
+            }
+        }
+
+        let cea608 = cc_data
+            .chunks_exact(3)
+            .enumerate()
+            .filter(|(_, triplet)| triplet[0] & 0x02 == 0)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let mut slots = cea608
+            .iter()
+            .map(|&i| Cea608Slot {
+                field: (cc_data[3 * i] & 0x01) as usize,
+                valid: cc_data[3 * i] & 0x04 == 0x04,
+                pair: [cc_data[3 * i + 1], cc_data[3 * i + 2]],
+            })
+            .collect::<Vec<_>>();
+        self.cea608(&received, &mut slots, issues);
+        self.cea608_bandwidth(&received, &mut slots, issues);
+
+        for (slot, &i) in slots.iter().zip(cea608.iter()) {
+            let triplet = &mut cc_data[3 * i..3 * i + 3];
+            let updated = [
+                if slot.valid {
+                    triplet[0] | 0x04
+                } else {
+                    triplet[0] & !0x04
+                },
+                slot.pair[0],
+                slot.pair[1],
+            ];
+            if triplet != updated {
+                triplet.copy_from_slice(&updated);
+                changed = true;
+            }
+        }
+
+        if changed {
+            *cdp = rewrite_cdp(cdp, start, end, &cc_data);
+        }
+    }
+}
+
+/// The range of the `cc_data` triplets in a CDP packet
+fn cc_data_range(cdp: &[u8]) -> Option<(usize, usize)> {
+    if cdp.len() < 11 || cdp[0..2] != [0x96, 0x69] || cdp[2] as usize != cdp.len() {
+        return None;
+    }
+
+    let flags = cdp[4];
+    let mut offset = 7;
+    if flags & 0x80 == 0x80 {
+        offset += 5;
+    }
+    if flags & 0x40 == 0 || cdp.len() < offset + 2 || cdp[offset] != 0x72 {
+        return None;
+    }
+
+    let start = offset + 2;
+    let end = start + 3 * (cdp[offset + 1] & 0x1f) as usize;
+    if end > cdp.len() {
+        return None;
+    }
+
+    Some((start, end))
+}
+
+/// Replaces the `cc_data` of a CDP packet, updating its length, `cc_count`
+/// and checksum
+fn rewrite_cdp(cdp: &[u8], start: usize, end: usize, cc_data: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(cdp.len() - (end - start) + cc_data.len());
+
+    packet.extend_from_slice(&cdp[..start]);
+    packet.extend_from_slice(cc_data);
+    packet.extend_from_slice(&cdp[end..cdp.len() - 1]);
+
+    packet[2] = (packet.len() + 1) as u8;
+    packet[start - 1] = 0xe0 | (cc_data.len() / 3) as u8;
+
+    let sum = packet.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    packet.push(sum.wrapping_neg());
+
+    packet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tc(hours: u32, minutes: u32, seconds: u32, frames: u32, drop_frame: bool) -> TimeCode {
+        TimeCode {
+            hours,
+            minutes,
+            seconds,
+            frames,
+            drop_frame,
+        }
+    }
+
+    fn kinds(issues: &[Issue]) -> Vec<(IssueKind, bool)> {
+        issues.iter().map(|i| (i.kind, i.repaired)).collect()
+    }
+
+    #[test]
+    fn test_frame_numbers() {
+        for &(fps, drop_frame) in &[(30, true), (30, false), (60, true), (25, false)] {
+            for number in (0..fps as u64 * 3600 * 2).step_by(7) {
+                let timecode = timecode_from_frame_number(number, fps, drop_frame);
+                assert_eq!(frame_number(&timecode, fps), number, "{}", timecode);
+            }
+        }
+
+        assert_eq!(
+            timecode_from_frame_number(1800, 30, true),
+            tc(0, 1, 0, 2, true)
+        );
+        assert_eq!(
+            timecode_from_frame_number(17982, 30, true),
+            tc(0, 10, 0, 0, true)
+        );
+    }
+
+    #[test]
+    fn test_timecodes() {
+        let mut issues = Vec::new();
+
+        // Dropped frame number, and overlap with the previous line
+        let mut validator = Validator::new(30000, 1001, false, true);
+        let mut timecode = tc(0, 0, 59, 28, true);
+        validator.timecode(&mut timecode, 4, &mut issues);
+        let mut timecode = tc(0, 1, 0, 0, true);
+        validator.timecode(&mut timecode, 1, &mut issues);
+        assert_eq!(
+            kinds(&issues),
+            vec![
+                (IssueKind::DropFrame, true),
+                (IssueKind::BandwidthOverrun, true)
+            ]
+        );
+        assert_eq!(timecode, tc(0, 1, 0, 4, true));
+
+        // Going back in time, and gaps in contiguous streams
+        issues.clear();
+        let mut validator = Validator::new(25, 1, true, false);
+        let mut timecode = tc(1, 0, 0, 10, false);
+        validator.timecode(&mut timecode, 1, &mut issues);
+        let mut timecode = tc(1, 0, 0, 5, false);
+        validator.timecode(&mut timecode, 1, &mut issues);
+        let mut timecode = tc(1, 0, 0, 8, false);
+        validator.timecode(&mut timecode, 1, &mut issues);
+        let mut timecode = tc(1, 0, 0, 8, true);
+        validator.timecode(&mut timecode, 1, &mut issues);
+        assert_eq!(
+            kinds(&issues),
+            vec![
+                (IssueKind::TimecodeDiscontinuity, false),
+                (IssueKind::TimecodeDiscontinuity, false),
+                (IssueKind::DropFrame, false),
+                (IssueKind::BandwidthOverrun, false),
+            ]
+        );
+        assert_eq!(timecode, tc(1, 0, 0, 8, true));
+    }
+
+    #[test]
+    fn test_missing_frames() {
+        let mut issues = Vec::new();
+        let mut validator = Validator::new(30000, 1001, true, true);
+        validator.set_fill_missing(true);
+
+        let mut timecode = tc(0, 0, 59, 28, true);
+        validator.timecode(&mut timecode, 1, &mut issues);
+        assert!(validator.take_missing_frames().is_empty());
+
+        // Skips 00:00:59;29 and the first frame of the minute
+        let mut timecode = tc(0, 1, 0, 3, true);
+        validator.timecode(&mut timecode, 1, &mut issues);
+        assert_eq!(
+            kinds(&issues),
+            vec![(IssueKind::TimecodeDiscontinuity, true)]
+        );
+        assert_eq!(timecode, tc(0, 1, 0, 3, true));
+        assert_eq!(
+            validator.take_missing_frames(),
+            vec![tc(0, 0, 59, 29, true), tc(0, 1, 0, 2, true)]
+        );
+        assert!(validator.take_missing_frames().is_empty());
+
+        // Not repairable when the caller doesn't fill the gaps
+        issues.clear();
+        validator.set_fill_missing(false);
+        let mut timecode = tc(0, 1, 0, 5, true);
+        validator.timecode(&mut timecode, 1, &mut issues);
+        assert_eq!(
+            kinds(&issues),
+            vec![(IssueKind::TimecodeDiscontinuity, false)]
+        );
+        assert!(validator.take_missing_frames().is_empty());
+    }
+
+    #[test]
+    fn test_scc_doubling() {
+        let mut issues = Vec::new();
+        let mut validator = Validator::new(30000, 1001, false, true);
+
+        // Doubled resume caption loading, single erase, then text and a
+        // single end of caption at the end of the line
+        let mut timecode = tc(0, 0, 0, 0, true);
+        let mut data = vec![
+            0x94, 0x20, 0x94, 0x20, 0x94, 0x2c, 0x80, 0x80, 0xc8, 0xe5, 0x94, 0x2f,
+        ];
+        validator.scc_line(&mut timecode, &mut data, &mut issues);
+        assert_eq!(
+            kinds(&issues),
+            vec![
+                (IssueKind::ControlCodeDoubling, true),
+                (IssueKind::ControlCodeDoubling, true)
+            ]
+        );
+        assert_eq!(
+            data,
+            vec![
+                0x94, 0x20, 0x94, 0x20, 0x94, 0x2c, 0x94, 0x2c, 0xc8, 0xe5, 0x94, 0x2f, 0x94, 0x2f
+            ]
+        );
+
+        // The appended pair is accounted for
+        issues.clear();
+        let mut timecode = tc(0, 0, 0, 6, true);
+        validator.scc_line(&mut timecode, &mut vec![0x80, 0x80], &mut issues);
+        assert_eq!(kinds(&issues), vec![(IssueKind::BandwidthOverrun, true)]);
+        assert_eq!(timecode, tc(0, 0, 0, 7, true));
+
+        // A single control code followed by text can't be repaired
+        issues.clear();
+        let mut validator = Validator::new(30, 1, false, true);
+        let mut pair = [0x94, 0x2c];
+        validator.cea608_frame(&mut tc(0, 0, 0, 0, false), &mut pair, &mut issues);
+        let mut pair = [0xc8, 0xe5];
+        validator.cea608_frame(&mut tc(0, 0, 0, 1, false), &mut pair, &mut issues);
+        assert_eq!(
+            kinds(&issues),
+            vec![(IssueKind::ControlCodeDoubling, false)]
+        );
+        assert_eq!(pair, [0xc8, 0xe5]);
+    }
+
+    #[test]
+    fn test_cdp() {
+        let mut issues = Vec::new();
+        let mut validator = Validator::new(30000, 1001, true, true);
+
+        let cdp = |cc_data: &[u8]| {
+            let mut cdp = vec![0x96, 0x69, 0, 0x4f, 0x43, 0x00, 0x01, 0x72, 0xe0];
+            cdp[8] |= (cc_data.len() / 3) as u8;
+            cdp.extend_from_slice(cc_data);
+            cdp.extend_from_slice(&[0x74, 0x00, 0x01]);
+            cdp[2] = cdp.len() as u8 + 1;
+            let sum = cdp.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+            cdp.push(sum.wrapping_neg());
+            cdp
+        };
+
+        // Two field 1 pairs in one frame and a single control code
+        let mut packet = cdp(&[0xfc, 0x94, 0x2c, 0xfc, 0xc8, 0xe5, 0xfd, 0x80, 0x80]);
+        validator.cdp_frame(&mut tc(0, 0, 0, 0, true), &mut packet, &mut issues);
+        assert_eq!(
+            kinds(&issues),
+            vec![
+                (IssueKind::ControlCodeDoubling, false),
+                (IssueKind::BandwidthOverrun, true),
+            ]
+        );
+        assert_eq!(
+            packet,
+            cdp(&[0xfc, 0x94, 0x2c, 0xf8, 0xc8, 0xe5, 0xfd, 0x80, 0x80])
+        );
+
+        // The repetition replaces the padding of the next frame
+        issues.clear();
+        let mut packet = cdp(&[0xfc, 0x94, 0x2f, 0xf9, 0x80, 0x80]);
+        validator.cdp_frame(&mut tc(0, 0, 0, 1, true), &mut packet, &mut issues);
+        let mut packet = cdp(&[0xf8, 0x80, 0x80, 0xf9, 0x80, 0x80]);
+        validator.cdp_frame(&mut tc(0, 0, 0, 2, true), &mut packet, &mut issues);
+        assert_eq!(kinds(&issues), vec![(IssueKind::ControlCodeDoubling, true)]);
+        assert_eq!(packet, cdp(&[0xfc, 0x94, 0x2f, 0xf9, 0x80, 0x80]));
+
+        // Too many triplets for the frame rate, invalid ones are dropped first
+        issues.clear();
+        let mut cc_data = Vec::new();
+        for i in 0..22 {
+            if i == 3 || i == 5 {
+                cc_data.extend_from_slice(&[0xfa, 0x00, 0x00]);
+            } else {
+                cc_data.extend_from_slice(&[0xfe, i, i]);
+            }
+        }
+        let mut packet = cdp(&cc_data);
+        validator.cdp_frame(&mut tc(0, 0, 0, 3, true), &mut packet, &mut issues);
+        assert_eq!(kinds(&issues), vec![(IssueKind::BandwidthOverrun, true)]);
+        cc_data.drain(15..18);
+        cc_data.drain(9..12);
+        assert_eq!(packet, cdp(&cc_data));
+    }
+}
@@ -0,0 +1,688 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+// Caption buffers are matched to the video frame whose running time range
+// they start in, and attached to it as a caption meta in their input format.
+//
+// When scheduling, the caption data is instead queued per CEA-608 field and
+// DTVCC, and drained at the bandwidth of the video frame rate: one byte pair
+// per field and per 1/30th of a second, and the remaining triplets of the
+// frame's cc_count for DTVCC. CDP captions are output for every frame once
+// the first one was received, with a continuous sequence counter and the
+// timecode of the video frame.
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::prelude::*;
+use gst_base::subclass::prelude::*;
+use gst_base::AGGREGATOR_FLOW_NEED_DATA;
+
+use crate::ccutils::{
+    caption_template_caps, caption_type_from_caps, cdp_frame_rate, cea608_pairs, extract_cdp,
+    write_cdp, CcDataQueue, CdpTimecode,
+};
+
+use once_cell::sync::Lazy;
+
+use std::sync::Mutex;
+
+const DEFAULT_SCHEDULE: bool = true;
+const DEFAULT_MAX_SCHEDULED: u32 = 30;
+
+// The maximum cc_count of cc_data, for frame rates without a CDP frame rate code
+const MAX_CC_COUNT: usize = 0x1f;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "rscccombiner",
+        gst::DebugColorFlags::empty(),
+        Some("Closed Caption combiner"),
+    )
+});
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    schedule: bool,
+    max_scheduled: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            schedule: DEFAULT_SCHEDULE,
+            max_scheduled: DEFAULT_MAX_SCHEDULED,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    framerate: Option<gst::Fraction>,
+    caption_type: Option<gst_video::VideoCaptionType>,
+    /// The video buffer waiting for its captions
+    current_video_buffer: Option<gst::Buffer>,
+    /// The caption buffers of the current video buffer, when not scheduling
+    captions: Vec<gst::Buffer>,
+    queue: CcDataQueue,
+    frame_no: u64,
+    cdp_sequence: u16,
+    cdp_started: bool,
+}
+
+pub struct CcCombiner {
+    video_pad: gst_base::AggregatorPad,
+    caption_pad: Mutex<Option<gst_base::AggregatorPad>>,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl CcCombiner {
+    /// The running time at which the video buffer ends, or `None` if the next
+    /// video buffer is needed to know it
+    fn video_buffer_end(
+        &self,
+        element: &super::CcCombiner,
+        state: &State,
+        buffer: &gst::Buffer,
+        pts: gst::ClockTime,
+        timeout: bool,
+    ) -> Option<gst::ClockTime> {
+        let duration = buffer
+            .duration()
+            .or_else(|| {
+                state.framerate.map(|framerate| {
+                    gst::ClockTime::SECOND
+                        .mul_div_floor(framerate.denom() as u64, framerate.numer() as u64)
+                        .unwrap()
+                })
+            })
+            .or_else(|| {
+                self.video_pad
+                    .peek_buffer()
+                    .and_then(|next_buffer| next_buffer.pts())
+                    .map(|next_pts| next_pts.saturating_sub(pts))
+            });
+
+        match duration {
+            Some(duration) => Some(pts + duration),
+            None if timeout || self.video_pad.is_eos() => {
+                gst::log!(
+                    CAT,
+                    obj: element,
+                    "Could not calculate duration for current video buffer"
+                );
+                Some(pts)
+            }
+            None => None,
+        }
+    }
+
+    /// Consumes the caption buffers starting before `end`, returns whether
+    /// all of them were received
+    fn consume_captions(
+        &self,
+        element: &super::CcCombiner,
+        state: &mut State,
+        settings: &Settings,
+        end: gst::ClockTime,
+        timeout: bool,
+    ) -> Result<bool, gst::FlowError> {
+        let caption_pad = match &*self.caption_pad.lock().unwrap() {
+            Some(caption_pad) => caption_pad.clone(),
+            None => return Ok(true),
+        };
+
+        while let Some(buffer) = caption_pad.peek_buffer() {
+            // Skip over gap buffers
+            if buffer.flags().contains(gst::BufferFlags::GAP) && buffer.size() == 0 {
+                caption_pad.drop_buffer();
+                continue;
+            }
+
+            let segment = match caption_pad
+                .segment()
+                .clone()
+                .downcast::<gst::ClockTime>()
+                .ok()
+            {
+                Some(segment) => segment,
+                None => {
+                    gst::error!(CAT, obj: &caption_pad, "Got buffer before segment");
+                    return Err(gst::FlowError::Error);
+                }
+            };
+
+            // Buffers without timestamps are output with the current video buffer
+            if let Some(pts) = buffer.pts() {
+                match segment.to_running_time(pts) {
+                    Some(running_time) if running_time >= end => return Ok(true),
+                    Some(_) => (),
+                    None => {
+                        gst::log!(CAT, obj: &caption_pad, "Dropping buffer outside segment");
+                        caption_pad.drop_buffer();
+                        continue;
+                    }
+                }
+            }
+
+            caption_pad.drop_buffer();
+
+            gst::log!(CAT, obj: &caption_pad, "Consuming {:?}", buffer);
+
+            if !settings.schedule {
+                state.captions.push(buffer);
+                continue;
+            }
+
+            let data = buffer.map_readable().map_err(|_| {
+                gst::error!(CAT, obj: &caption_pad, "Can't map buffer readable");
+
+                gst::FlowError::Error
+            })?;
+
+            match state.caption_type {
+                Some(gst_video::VideoCaptionType::Cea608Raw) => state.queue.push_cea608_raw(&data),
+                Some(gst_video::VideoCaptionType::Cea608S3341a) => state.queue.push_s334_1a(&data),
+                Some(gst_video::VideoCaptionType::Cea708Raw) => state.queue.push_cc_data(&data),
+                Some(gst_video::VideoCaptionType::Cea708Cdp) => match extract_cdp(&data) {
+                    Ok(cc_data) => state.queue.push_cc_data(cc_data),
+                    Err(err) => {
+                        gst::warning!(CAT, obj: &caption_pad, "{}", err);
+                        gst::element_warning!(
+                            element,
+                            gst::StreamError::Decode,
+                            [&err.to_string()]
+                        );
+                    }
+                },
+                _ => {
+                    gst::element_error!(
+                        element,
+                        gst::CoreError::Negotiation,
+                        ["Received caption buffer before caps"]
+                    );
+                    return Err(gst::FlowError::NotNegotiated);
+                }
+            }
+        }
+
+        Ok(caption_pad.is_eos() || timeout)
+    }
+
+    /// Drains the caption data of the current frame from the queue
+    fn scheduled_captions(
+        &self,
+        element: &super::CcCombiner,
+        state: &mut State,
+        settings: &Settings,
+        timecode: Option<CdpTimecode>,
+    ) -> Result<Option<(gst_video::VideoCaptionType, Vec<u8>)>, gst::FlowError> {
+        let caption_type = match state.caption_type {
+            Some(caption_type) => caption_type,
+            None => return Ok(None),
+        };
+
+        let frame_rate = state
+            .framerate
+            .and_then(|framerate| cdp_frame_rate(framerate.numer(), framerate.denom()));
+        let cc_count = frame_rate.map_or(MAX_CC_COUNT, |(_, cc_count)| cc_count);
+
+        if state
+            .queue
+            .truncate(settings.max_scheduled as usize, cc_count)
+        {
+            gst::warning!(CAT, obj: element, "Dropping scheduled captions over limit");
+        }
+
+        let pairs = state.framerate.map_or(1, |framerate| {
+            cea608_pairs(state.frame_no, framerate.numer(), framerate.denom())
+        });
+
+        let data = match caption_type {
+            gst_video::VideoCaptionType::Cea608Raw => state.queue.pop_cea608_raw(pairs, false),
+            gst_video::VideoCaptionType::Cea608S3341a => state.queue.pop_s334_1a(pairs, false),
+            gst_video::VideoCaptionType::Cea708Raw => {
+                state.queue.pop_cc_data(pairs, cc_count, false)
+            }
+            gst_video::VideoCaptionType::Cea708Cdp => {
+                let (frame_rate_code, cc_count) = frame_rate.ok_or_else(|| {
+                    gst::element_error!(
+                        element,
+                        gst::CoreError::Negotiation,
+                        [
+                            "Video frame rate {:?} can't be used with CDP captions",
+                            state.framerate
+                        ]
+                    );
+                    gst::FlowError::NotNegotiated
+                })?;
+
+                state.cdp_started |= !state.queue.is_empty();
+                if !state.cdp_started {
+                    return Ok(None);
+                }
+
+                let cc_data = state.queue.pop_cc_data(pairs, cc_count, true);
+                let cdp = write_cdp(
+                    frame_rate_code,
+                    state.cdp_sequence,
+                    timecode.as_ref(),
+                    &cc_data,
+                    cc_count,
+                );
+                state.cdp_sequence = state.cdp_sequence.wrapping_add(1);
+                cdp
+            }
+            _ => Vec::new(),
+        };
+
+        if data.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some((caption_type, data)))
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for CcCombiner {
+    const NAME: &'static str = "RsCcCombiner";
+    type Type = super::CcCombiner;
+    type ParentType = gst_base::Aggregator;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let video_pad =
+            gst::PadBuilder::<gst_base::AggregatorPad>::from_template(&templ, Some("sink")).build();
+
+        Self {
+            video_pad,
+            caption_pad: Mutex::default(),
+            settings: Mutex::default(),
+            state: Mutex::default(),
+        }
+    }
+}
+
+impl ObjectImpl for CcCombiner {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecBoolean::builder("schedule")
+                    .nick("Schedule")
+                    .blurb("Schedule caption data to respect the bandwidth of the video frame rate")
+                    .default_value(DEFAULT_SCHEDULE)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("max-scheduled")
+                    .nick("Max Scheduled")
+                    .blurb("Maximum number of frames worth of caption data to queue when scheduling, older data is dropped")
+                    .minimum(1)
+                    .default_value(DEFAULT_MAX_SCHEDULED)
+                    .mutable_playing()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.lock().unwrap();
+
+        match pspec.name() {
+            "schedule" => {
+                settings.schedule = value.get().expect("type checked upstream");
+            }
+            "max-scheduled" => {
+                settings.max_scheduled = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+
+        match pspec.name() {
+            "schedule" => settings.schedule.to_value(),
+            "max-scheduled" => settings.max_scheduled.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(&self.video_pad).unwrap();
+    }
+}
+
+impl GstObjectImpl for CcCombiner {}
+
+impl ElementImpl for CcCombiner {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Closed Caption Combiner",
+                "Filter",
+                "Combines closed captions with a video stream as caption meta",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let video_caps = gst::Caps::new_any();
+
+            let video_sink_pad_template = gst::PadTemplate::with_gtype(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &video_caps,
+                gst_base::AggregatorPad::static_type(),
+            )
+            .unwrap();
+
+            let caption_caps = caption_template_caps();
+
+            let caption_sink_pad_template = gst::PadTemplate::with_gtype(
+                "caption",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Request,
+                &caption_caps,
+                gst_base::AggregatorPad::static_type(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::with_gtype(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &video_caps,
+                gst_base::AggregatorPad::static_type(),
+            )
+            .unwrap();
+
+            vec![
+                video_sink_pad_template,
+                caption_sink_pad_template,
+                src_pad_template,
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn release_pad(&self, element: &Self::Type, pad: &gst::Pad) {
+        let mut caption_pad = self.caption_pad.lock().unwrap();
+        if caption_pad.as_ref().map(|p| p.upcast_ref::<gst::Pad>()) == Some(pad) {
+            *caption_pad = None;
+        }
+        drop(caption_pad);
+
+        self.parent_release_pad(element, pad);
+    }
+}
+
+impl AggregatorImpl for CcCombiner {
+    fn aggregate(
+        &self,
+        element: &Self::Type,
+        timeout: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::trace!(CAT, obj: element, "aggregate, timeout: {}", timeout);
+
+        let settings = *self.settings.lock().unwrap();
+        let mut state = self.state.lock().unwrap();
+
+        let buffer = match state
+            .current_video_buffer
+            .take()
+            .or_else(|| self.video_pad.pop_buffer())
+        {
+            Some(buffer) => buffer,
+            None if self.video_pad.is_eos() => return Err(gst::FlowError::Eos),
+            None => return Err(AGGREGATOR_FLOW_NEED_DATA),
+        };
+
+        let segment = match self
+            .video_pad
+            .segment()
+            .clone()
+            .downcast::<gst::ClockTime>()
+            .ok()
+        {
+            Some(segment) => segment,
+            None => {
+                gst::error!(CAT, obj: &self.video_pad, "Got buffer before segment");
+                return Err(gst::FlowError::Error);
+            }
+        };
+
+        let mut position = None;
+
+        // Video buffers without timestamps or outside the segment can't be
+        // matched with captions and are output as is
+        if let Some(pts) = buffer
+            .pts()
+            .filter(|pts| segment.to_running_time(*pts).is_some())
+        {
+            let end = match self.video_buffer_end(element, &state, &buffer, pts, timeout) {
+                Some(end) => end,
+                None => {
+                    gst::trace!(CAT, obj: element, "Waiting for next video buffer");
+                    state.current_video_buffer = Some(buffer);
+                    return Err(AGGREGATOR_FLOW_NEED_DATA);
+                }
+            };
+
+            // The start is in the segment, so the end can only be after its stop
+            let end_running_time = segment
+                .to_running_time(end)
+                .or_else(|| segment.to_running_time(segment.stop()))
+                .unwrap();
+
+            if !self.consume_captions(element, &mut state, &settings, end_running_time, timeout)? {
+                gst::trace!(CAT, obj: element, "Waiting for caption buffers");
+                state.current_video_buffer = Some(buffer);
+                return Err(AGGREGATOR_FLOW_NEED_DATA);
+            }
+
+            position = Some(end);
+        }
+
+        let mut captions = Vec::new();
+        if settings.schedule {
+            // CDP carries the timecode of the video frame, if any
+            let timecode = buffer
+                .meta::<gst_video::VideoTimeCodeMeta>()
+                .map(|meta| CdpTimecode::from(&meta.tc()));
+            captions.extend(self.scheduled_captions(element, &mut state, &settings, timecode)?);
+        } else if let Some(caption_type) = state.caption_type {
+            for caption in state.captions.drain(..) {
+                let data = caption.map_readable().map_err(|_| {
+                    gst::error!(CAT, obj: element, "Can't map buffer readable");
+
+                    gst::FlowError::Error
+                })?;
+                captions.push((caption_type, data.to_vec()));
+            }
+        }
+        state.frame_no += 1;
+
+        drop(state);
+
+        let mut buffer = buffer;
+        if !captions.is_empty() {
+            let buffer = buffer.make_mut();
+            for (caption_type, data) in captions {
+                gst_video::VideoCaptionMeta::add(buffer, caption_type, &data);
+            }
+        }
+
+        gst::log!(CAT, obj: element, "Updating position: {:?}", position);
+
+        element.set_position(position);
+
+        self.finish_buffer(element, buffer)
+    }
+
+    fn create_new_pad(
+        &self,
+        element: &Self::Type,
+        templ: &gst::PadTemplate,
+        _req_name: Option<&str>,
+        _caps: Option<&gst::Caps>,
+    ) -> Option<gst_base::AggregatorPad> {
+        let mut caption_pad = self.caption_pad.lock().unwrap();
+
+        if templ.name_template() != "caption" || caption_pad.is_some() {
+            gst::error!(CAT, obj: element, "Only one caption pad can be requested");
+            return None;
+        }
+
+        let pad = gst::PadBuilder::<gst_base::AggregatorPad>::from_template(templ, Some("caption"))
+            .build();
+        *caption_pad = Some(pad.clone());
+
+        Some(pad)
+    }
+
+    fn src_query(&self, aggregator: &Self::Type, query: &mut gst::QueryRef) -> bool {
+        use gst::QueryViewMut;
+
+        match query.view_mut() {
+            QueryViewMut::Position(..)
+            | QueryViewMut::Duration(..)
+            | QueryViewMut::Uri(..)
+            | QueryViewMut::Caps(..)
+            | QueryViewMut::Allocation(..) => self.video_pad.peer_query(query),
+            QueryViewMut::AcceptCaps(q) => {
+                let caps = q.caps_owned();
+                let class = aggregator.class();
+                let templ = class.pad_template("sink").unwrap();
+                let templ_caps = templ.caps();
+
+                q.set_result(caps.is_subset(templ_caps));
+
+                true
+            }
+            _ => self.parent_src_query(aggregator, query),
+        }
+    }
+
+    fn sink_event(
+        &self,
+        aggregator: &Self::Type,
+        aggregator_pad: &gst_base::AggregatorPad,
+        event: gst::Event,
+    ) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: aggregator_pad, "Handling event {:?}", event);
+
+        match event.view() {
+            EventView::Caps(e) => {
+                let caps = e.caps();
+                let mut state = self.state.lock().unwrap();
+
+                if aggregator_pad == &self.video_pad {
+                    state.framerate = caps
+                        .structure(0)
+                        .and_then(|s| s.get::<gst::Fraction>("framerate").ok())
+                        .filter(|framerate| framerate.numer() > 0);
+                    drop(state);
+
+                    gst::info!(CAT, obj: aggregator, "Pushing caps {}", caps);
+                    aggregator.set_src_caps(&e.caps_owned());
+                } else {
+                    state.caption_type = caption_type_from_caps(caps);
+                    if state.caption_type.is_none() {
+                        gst::error!(CAT, obj: aggregator_pad, "Unsupported caps {}", caps);
+                        return false;
+                    }
+                }
+
+                true
+            }
+            EventView::Segment(e) => {
+                if aggregator_pad == &self.video_pad {
+                    aggregator.update_segment(e.segment());
+                }
+                self.parent_sink_event(aggregator, aggregator_pad, event)
+            }
+            _ => self.parent_sink_event(aggregator, aggregator_pad, event),
+        }
+    }
+
+    fn sink_query(
+        &self,
+        aggregator: &Self::Type,
+        aggregator_pad: &gst_base::AggregatorPad,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        use gst::QueryViewMut;
+
+        match query.view_mut() {
+            QueryViewMut::Position(..)
+            | QueryViewMut::Duration(..)
+            | QueryViewMut::Uri(..)
+            | QueryViewMut::Caps(..)
+            | QueryViewMut::AcceptCaps(..)
+            | QueryViewMut::Allocation(..)
+                if aggregator_pad == &self.video_pad =>
+            {
+                aggregator.src_pad().peer_query(query)
+            }
+            _ => self.parent_sink_query(aggregator, aggregator_pad, query),
+        }
+    }
+
+    fn flush(&self, aggregator: &Self::Type) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state = self.state.lock().unwrap();
+        state.current_video_buffer = None;
+        state.captions.clear();
+        state.queue.clear();
+        state.frame_no = 0;
+        state.cdp_started = false;
+        drop(state);
+
+        self.parent_flush(aggregator)
+    }
+
+    fn start(&self, aggregator: &Self::Type) -> Result<(), gst::ErrorMessage> {
+        *self.state.lock().unwrap() = State::default();
+
+        self.parent_start(aggregator)
+    }
+
+    fn stop(&self, aggregator: &Self::Type) -> Result<(), gst::ErrorMessage> {
+        *self.state.lock().unwrap() = State::default();
+
+        self.parent_stop(aggregator)
+    }
+
+    fn next_time(&self, aggregator: &Self::Type) -> Option<gst::ClockTime> {
+        aggregator.simple_get_next_time()
+    }
+
+    fn negotiate(&self, _aggregator: &Self::Type) -> bool {
+        true
+    }
+}
@@ -0,0 +1,588 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+// The input caption data is queued per CEA-608 field and DTVCC, and output
+// frames are then generated at the output frame rate from the first input
+// timestamp on, each frame taking the caption data its bandwidth allows.
+//
+// Output timecodes are taken from the input timecode meta or CDP timecode
+// when the frame rates match, otherwise the first input timecode is
+// converted to the output frame rate and incremented with each frame.
+//
+// When neither the input nor the output caps have a frame rate, each input
+// buffer is converted to one output buffer instead.
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use crate::ccutils::{
+    caption_caps, caption_template_caps, caption_type_from_caps, cdp_frame_rate, cea608_pairs,
+    extract_cdp, extract_cdp_timecode, write_cdp, CcDataQueue, CdpTimecode,
+};
+
+use once_cell::sync::Lazy;
+
+use std::sync::Mutex;
+
+const DEFAULT_FPS_N: i32 = 30000;
+const DEFAULT_FPS_D: i32 = 1001;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "rsccconverter",
+        gst::DebugColorFlags::empty(),
+        Some("Closed Caption converter"),
+    )
+});
+
+/// Creates a timecode at `framerate` for the same time as `timecode`
+fn convert_timecode(
+    timecode: &CdpTimecode,
+    fps: gst::Fraction,
+    framerate: gst::Fraction,
+) -> Option<gst_video::ValidVideoTimeCode> {
+    let frames = timecode.frames(fps);
+    let mut frames = (frames as u64 * framerate.numer() as u64 * fps.denom() as u64
+        / (framerate.denom() as u64 * fps.numer() as u64)) as u32;
+
+    let drop_frame = timecode.drop_frame
+        && matches!(
+            (framerate.numer(), framerate.denom()),
+            (30000, 1001) | (60000, 1001)
+        );
+
+    // The first frames of each minute except every tenth are skipped with drop frame
+    if drop_frame && timecode.seconds == 0 && timecode.minutes % 10 != 0 && frames < 2 {
+        frames = 2;
+    }
+
+    let timecode = gst_video::VideoTimeCode::new(
+        framerate,
+        None,
+        if drop_frame {
+            gst_video::VideoTimeCodeFlags::DROP_FRAME
+        } else {
+            gst_video::VideoTimeCodeFlags::empty()
+        },
+        timecode.hours,
+        timecode.minutes,
+        timecode.seconds,
+        frames,
+        0,
+    );
+
+    timecode.try_into().ok()
+}
+
+#[derive(Default)]
+struct State {
+    in_type: Option<gst_video::VideoCaptionType>,
+    in_framerate: Option<gst::Fraction>,
+    out_type: Option<gst_video::VideoCaptionType>,
+    out_framerate: Option<gst::Fraction>,
+    queue: CcDataQueue,
+    /// The timestamp of the first output frame
+    first_pts: Option<gst::ClockTime>,
+    frame_no: u64,
+    cdp_sequence: u16,
+    /// The timecode of the next output frame
+    timecode: Option<gst_video::ValidVideoTimeCode>,
+}
+
+impl State {
+    fn reset(&mut self) {
+        self.queue.clear();
+        self.first_pts = None;
+        self.frame_no = 0;
+        self.timecode = None;
+    }
+
+    fn frame_pts(&self, frame_no: u64) -> gst::ClockTime {
+        let framerate = self.out_framerate.unwrap();
+
+        self.first_pts.unwrap()
+            + (frame_no * gst::ClockTime::SECOND)
+                .mul_div_round(framerate.denom() as u64, framerate.numer() as u64)
+                .unwrap()
+    }
+
+    fn queue_caption_data(&mut self, element: &super::CcConverter, data: &[u8]) {
+        match self.in_type {
+            Some(gst_video::VideoCaptionType::Cea608Raw) => self.queue.push_cea608_raw(data),
+            Some(gst_video::VideoCaptionType::Cea608S3341a) => self.queue.push_s334_1a(data),
+            Some(gst_video::VideoCaptionType::Cea708Raw) => self.queue.push_cc_data(data),
+            Some(gst_video::VideoCaptionType::Cea708Cdp) => match extract_cdp(data) {
+                Ok(cc_data) => self.queue.push_cc_data(cc_data),
+                Err(err) => {
+                    gst::warning!(CAT, obj: element, "{}", err);
+                    gst::element_warning!(element, gst::StreamError::Decode, [&err.to_string()]);
+                }
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// Takes the timecode of the input, if it can be used for the output
+    fn update_timecode(&mut self, buffer: &gst::BufferRef, data: &[u8]) {
+        let (timecode, fps) = match buffer.meta::<gst_video::VideoTimeCodeMeta>() {
+            Some(meta) => {
+                let tc = meta.tc();
+                (CdpTimecode::from(&tc), tc.fps())
+            }
+            None => match (self.in_type, self.in_framerate, extract_cdp_timecode(data)) {
+                (Some(gst_video::VideoCaptionType::Cea708Cdp), Some(fps), Some(timecode)) => {
+                    (timecode, fps)
+                }
+                _ => return,
+            },
+        };
+
+        let framerate = match self.out_framerate {
+            Some(framerate) => framerate,
+            None => return,
+        };
+
+        // Timecodes can only be converted once to another frame rate, as
+        // input and output frames don't match
+        if fps == framerate || self.timecode.is_none() {
+            self.timecode = convert_timecode(&timecode, fps, framerate);
+        }
+    }
+
+    /// Takes the caption data of the next output frame
+    fn take_frame(&mut self) -> Vec<u8> {
+        let framerate = self.out_framerate.unwrap();
+        let pairs = cea608_pairs(self.frame_no, framerate.numer(), framerate.denom());
+        let cc_count = cdp_frame_rate(framerate.numer(), framerate.denom()).map_or(
+            2 * pairs.max(1) + self.queue.dtvcc_len(),
+            |(_, cc_count)| cc_count,
+        );
+
+        match self.out_type.unwrap() {
+            gst_video::VideoCaptionType::Cea608Raw => self.queue.pop_cea608_raw(pairs, true),
+            gst_video::VideoCaptionType::Cea608S3341a => self.queue.pop_s334_1a(pairs, true),
+            gst_video::VideoCaptionType::Cea708Raw => self.queue.pop_cc_data(pairs, cc_count, true),
+            gst_video::VideoCaptionType::Cea708Cdp => {
+                // Checked during negotiation
+                let (frame_rate_code, _) =
+                    cdp_frame_rate(framerate.numer(), framerate.denom()).unwrap();
+                let timecode = self.timecode.as_ref().map(CdpTimecode::from);
+                let cc_data = self.queue.pop_cc_data(pairs, cc_count, true);
+
+                let cdp = write_cdp(
+                    frame_rate_code,
+                    self.cdp_sequence,
+                    timecode.as_ref(),
+                    &cc_data,
+                    cc_count,
+                );
+                self.cdp_sequence = self.cdp_sequence.wrapping_add(1);
+                cdp
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Generates the output frames starting before `end`, or until the queue
+    /// is empty if `end` is `None`
+    fn output_frames(&mut self, list: &mut gst::BufferListRef, end: Option<gst::ClockTime>) {
+        loop {
+            let pts = self.frame_pts(self.frame_no);
+            match end {
+                Some(end) if pts >= end => break,
+                None if self.queue.is_empty() => break,
+                _ => (),
+            }
+
+            let next_pts = self.frame_pts(self.frame_no + 1);
+            let data = self.take_frame();
+            self.frame_no += 1;
+
+            let timecode = self.timecode.clone();
+            if let Some(ref mut timecode) = self.timecode {
+                timecode.increment_frame();
+            }
+
+            // Frames without CEA-608 slot at high frame rates
+            if data.is_empty() {
+                continue;
+            }
+
+            let mut buffer = gst::Buffer::from_mut_slice(data);
+            {
+                let buffer = buffer.get_mut().unwrap();
+                buffer.set_pts(pts);
+                buffer.set_duration(next_pts - pts);
+
+                if let Some(ref timecode) = timecode {
+                    gst_video::VideoTimeCodeMeta::add(buffer, timecode);
+                }
+            }
+
+            list.add(buffer);
+        }
+    }
+
+    /// Converts all the queued caption data to one output buffer
+    fn convert_buffer(&mut self) -> Vec<u8> {
+        let pairs = self.queue.cea608_len();
+        let cc_count = 2 * pairs.max(1) + self.queue.dtvcc_len();
+
+        match self.out_type.unwrap() {
+            gst_video::VideoCaptionType::Cea608Raw => self.queue.pop_cea608_raw(pairs, true),
+            gst_video::VideoCaptionType::Cea608S3341a => self.queue.pop_s334_1a(pairs, true),
+            gst_video::VideoCaptionType::Cea708Raw => {
+                self.queue.pop_cc_data(pairs, cc_count, false)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+pub struct CcConverter {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    state: Mutex<State>,
+}
+
+impl CcConverter {
+    fn negotiate(
+        &self,
+        element: &super::CcConverter,
+        state: &mut State,
+        caps: &gst::CapsRef,
+    ) -> Result<gst::Caps, gst::FlowError> {
+        state.in_type = caption_type_from_caps(caps);
+        state.in_framerate = caps
+            .structure(0)
+            .and_then(|s| s.get::<gst::Fraction>("framerate").ok())
+            .filter(|framerate| framerate.numer() > 0);
+
+        let in_type = state.in_type.ok_or_else(|| {
+            gst::error!(CAT, obj: element, "Unsupported caps {}", caps);
+            gst::FlowError::NotNegotiated
+        })?;
+
+        let mut downstream_caps = match self.srcpad.allowed_caps() {
+            None => self.srcpad.pad_template_caps(),
+            Some(caps) => caps,
+        };
+
+        if downstream_caps.is_empty() {
+            gst::error!(CAT, obj: element, "Empty downstream caps");
+            return Err(gst::FlowError::NotNegotiated);
+        }
+
+        // Prefer the input caption format
+        let preferred = caption_caps(in_type, None).unwrap();
+        let intersection =
+            downstream_caps.intersect_with_mode(&preferred, gst::CapsIntersectMode::First);
+        if !intersection.is_empty() {
+            downstream_caps = intersection;
+        }
+
+        {
+            let caps = downstream_caps.make_mut();
+            let s = caps.structure_mut(0).unwrap();
+
+            if s.has_field("framerate") {
+                s.fixate_field_nearest_fraction(
+                    "framerate",
+                    state
+                        .in_framerate
+                        .unwrap_or_else(|| gst::Fraction::new(DEFAULT_FPS_N, DEFAULT_FPS_D)),
+                );
+            } else if let Some(framerate) = state.in_framerate {
+                s.set("framerate", framerate);
+            }
+        }
+        downstream_caps.fixate();
+
+        let s = downstream_caps.structure(0).unwrap();
+        let out_framerate = s
+            .get::<gst::Fraction>("framerate")
+            .ok()
+            .filter(|framerate| framerate.numer() > 0);
+
+        state.out_type = caption_type_from_caps(&downstream_caps);
+
+        if state.out_type == Some(gst_video::VideoCaptionType::Cea708Cdp)
+            && out_framerate.map_or(true, |framerate| {
+                cdp_frame_rate(framerate.numer(), framerate.denom()).is_none()
+            })
+        {
+            gst::error!(
+                CAT,
+                obj: element,
+                "Unsupported frame rate {:?} for CDP output",
+                out_framerate
+            );
+            return Err(gst::FlowError::NotNegotiated);
+        }
+
+        if state.out_framerate != out_framerate {
+            // Restart the output timeline at the next buffer
+            state.first_pts = None;
+            state.frame_no = 0;
+            state.timecode = None;
+        }
+        state.out_framerate = out_framerate;
+
+        gst::debug!(CAT, obj: element, "Negotiated caps {}", downstream_caps);
+
+        Ok(downstream_caps)
+    }
+
+    fn sink_chain(
+        &self,
+        pad: &gst::Pad,
+        element: &super::CcConverter,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::log!(CAT, obj: pad, "Handling buffer {:?}", buffer);
+
+        let data = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, obj: pad, "Can't map buffer readable");
+
+            gst::FlowError::Error
+        })?;
+
+        let mut state = self.state.lock().unwrap();
+
+        if state.in_type.is_none() || state.out_type.is_none() {
+            gst::element_error!(
+                element,
+                gst::CoreError::Negotiation,
+                ["Received buffer before caps"]
+            );
+            return Err(gst::FlowError::NotNegotiated);
+        }
+
+        state.queue_caption_data(element, &data);
+        state.update_timecode(&buffer, &data);
+        drop(data);
+
+        let mut bufferlist = gst::BufferList::new();
+        let mut_list = bufferlist.get_mut().unwrap();
+
+        match state.out_framerate {
+            Some(framerate) => {
+                let pts = buffer.pts().ok_or_else(|| {
+                    gst::element_error!(
+                        element,
+                        gst::StreamError::Format,
+                        ["Stream with timestamped buffers required"]
+                    );
+                    gst::FlowError::Error
+                })?;
+
+                let duration = buffer.duration().unwrap_or_else(|| {
+                    let framerate = state.in_framerate.unwrap_or(framerate);
+                    gst::ClockTime::SECOND
+                        .mul_div_floor(framerate.denom() as u64, framerate.numer() as u64)
+                        .unwrap()
+                });
+
+                state.first_pts.get_or_insert(pts);
+                state.output_frames(mut_list, Some(pts + duration));
+            }
+            None => {
+                let data = state.convert_buffer();
+                if !data.is_empty() {
+                    let mut outbuf = gst::Buffer::from_mut_slice(data);
+                    {
+                        let outbuf = outbuf.get_mut().unwrap();
+                        outbuf.set_pts(buffer.pts());
+                        outbuf.set_duration(buffer.duration());
+                        if let Some(meta) = buffer.meta::<gst_video::VideoTimeCodeMeta>() {
+                            gst_video::VideoTimeCodeMeta::add(outbuf, &meta.tc());
+                        }
+                    }
+                    mut_list.add(outbuf);
+                }
+            }
+        }
+
+        drop(state);
+
+        if bufferlist.is_empty() {
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        self.srcpad.push_list(bufferlist)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, element: &super::CcConverter, event: gst::Event) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad, "Handling event {:?}", event);
+
+        match event.view() {
+            EventView::Caps(e) => {
+                let mut state = self.state.lock().unwrap();
+                let caps = match self.negotiate(element, &mut state, e.caps()) {
+                    Ok(caps) => caps,
+                    Err(_) => return false,
+                };
+                drop(state);
+
+                self.srcpad.push_event(gst::event::Caps::new(&caps))
+            }
+            EventView::Gap(e) => {
+                let mut state = self.state.lock().unwrap();
+
+                if state.out_framerate.is_none() {
+                    drop(state);
+                    return pad.event_default(Some(element), event);
+                }
+
+                // Keep the output stream continuous with padding frames
+                let (timestamp, duration) = e.get();
+                let end = timestamp + duration.unwrap_or(gst::ClockTime::ZERO);
+
+                let mut bufferlist = gst::BufferList::new();
+                state.first_pts.get_or_insert(timestamp);
+                state.output_frames(bufferlist.get_mut().unwrap(), Some(end));
+                drop(state);
+
+                if !bufferlist.is_empty() {
+                    let _ = self.srcpad.push_list(bufferlist);
+                }
+
+                true
+            }
+            EventView::Eos(_) => {
+                let mut state = self.state.lock().unwrap();
+
+                // Output the remaining caption data
+                if state.out_framerate.is_some() && state.first_pts.is_some() {
+                    let mut bufferlist = gst::BufferList::new();
+                    state.output_frames(bufferlist.get_mut().unwrap(), None);
+                    drop(state);
+
+                    if !bufferlist.is_empty() {
+                        let _ = self.srcpad.push_list(bufferlist);
+                    }
+                } else {
+                    drop(state);
+                }
+
+                pad.event_default(Some(element), event)
+            }
+            EventView::FlushStop(_) => {
+                self.state.lock().unwrap().reset();
+
+                pad.event_default(Some(element), event)
+            }
+            _ => pad.event_default(Some(element), event),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for CcConverter {
+    const NAME: &'static str = "RsCcConverter";
+    type Type = super::CcConverter;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                CcConverter::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |converter, element| converter.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                CcConverter::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |converter, element| converter.sink_event(pad, element, event),
+                )
+            })
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src")).build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            state: Mutex::default(),
+        }
+    }
+}
+
+impl ObjectImpl for CcConverter {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+}
+
+impl GstObjectImpl for CcConverter {}
+
+impl ElementImpl for CcConverter {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Closed Caption Converter",
+                "Filter/ClosedCaption",
+                "Converts between CEA-608 and CEA-708 closed caption formats and frame rates",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = caption_template_caps();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        if let gst::StateChange::ReadyToPaused | gst::StateChange::PausedToReady = transition {
+            *self.state.lock().unwrap() = State::default();
+        }
+
+        self.parent_change_state(element, transition)
+    }
+}
@@ -0,0 +1,435 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use crate::ccutils::{caption_caps, caption_template_caps};
+
+use once_cell::sync::Lazy;
+
+use std::sync::Mutex;
+
+const DEFAULT_REMOVE_CAPTION_META: bool = false;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "rsccextractor",
+        gst::DebugColorFlags::empty(),
+        Some("Closed Caption extractor"),
+    )
+});
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    remove_caption_meta: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            remove_caption_meta: DEFAULT_REMOVE_CAPTION_META,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    framerate: Option<gst::Fraction>,
+    segment: Option<gst::Event>,
+    caption_type: Option<gst_video::VideoCaptionType>,
+}
+
+pub struct CcExtractor {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    caption_pad: Mutex<Option<gst::Pad>>,
+    flow_combiner: Mutex<gst_base::UniqueFlowCombiner>,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl CcExtractor {
+    fn create_caption_pad(&self, element: &super::CcExtractor, caps: &gst::Caps) -> gst::Pad {
+        let templ = element.element_class().pad_template("caption").unwrap();
+        let caption_pad = gst::Pad::builder_with_template(&templ, Some("caption"))
+            .query_function(|pad, parent, query| {
+                CcExtractor::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |extractor, element| extractor.caption_query(pad, element, query),
+                )
+            })
+            .build();
+
+        caption_pad.set_active(true).unwrap();
+
+        let stream_id = caption_pad.create_stream_id(element, Some("caption"));
+        let mut stream_start = gst::event::StreamStart::builder(&stream_id);
+        if let Some(group_id) = self
+            .sinkpad
+            .sticky_event::<gst::event::StreamStart>(0)
+            .and_then(|event| event.group_id())
+        {
+            stream_start = stream_start.group_id(group_id);
+        }
+        caption_pad.push_event(stream_start.build());
+        caption_pad.push_event(gst::event::Caps::new(caps));
+
+        let segment = self.state.lock().unwrap().segment.clone();
+        if let Some(segment) = segment {
+            caption_pad.push_event(segment);
+        }
+
+        self.flow_combiner.lock().unwrap().add_pad(&caption_pad);
+
+        element.add_pad(&caption_pad).unwrap();
+
+        caption_pad
+    }
+
+    fn sink_chain(
+        &self,
+        pad: &gst::Pad,
+        element: &super::CcExtractor,
+        mut buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::log!(CAT, obj: pad, "Handling buffer {:?}", buffer);
+
+        let settings = *self.settings.lock().unwrap();
+
+        let captions = buffer
+            .iter_meta::<gst_video::VideoCaptionMeta>()
+            .map(|meta| (meta.caption_type(), meta.data().to_vec()))
+            .collect::<Vec<_>>();
+
+        let pts = buffer.pts();
+        let duration = buffer.duration();
+
+        if settings.remove_caption_meta && !captions.is_empty() {
+            let buffer = buffer.make_mut();
+            while let Some(meta) = buffer.meta_mut::<gst_video::VideoCaptionMeta>() {
+                meta.remove().unwrap();
+            }
+        }
+
+        let mut caption_pad = self.caption_pad.lock().unwrap().clone();
+        let mut pushed_caption = false;
+
+        for (caption_type, data) in captions {
+            let caps = {
+                let mut state = self.state.lock().unwrap();
+
+                if state.caption_type == Some(caption_type) {
+                    None
+                } else if let Some(caps) = caption_caps(caption_type, state.framerate) {
+                    state.caption_type = Some(caption_type);
+                    Some(caps)
+                } else {
+                    gst::warning!(
+                        CAT,
+                        obj: element,
+                        "Ignoring unknown caption type {:?}",
+                        caption_type
+                    );
+                    continue;
+                }
+            };
+
+            let pad = match (&caption_pad, caps) {
+                (Some(pad), None) => pad.clone(),
+                (Some(pad), Some(caps)) => {
+                    gst::debug!(CAT, obj: pad, "Updating caps {}", caps);
+                    pad.push_event(gst::event::Caps::new(&caps));
+                    pad.clone()
+                }
+                (None, caps) => {
+                    let caps = caps.expect("caps are set with the first caption");
+                    gst::debug!(CAT, obj: element, "Adding caption pad with caps {}", caps);
+                    let pad = self.create_caption_pad(element, &caps);
+                    *self.caption_pad.lock().unwrap() = Some(pad.clone());
+                    caption_pad = Some(pad.clone());
+                    pad
+                }
+            };
+
+            let mut caption = gst::Buffer::from_mut_slice(data);
+            {
+                let caption = caption.get_mut().unwrap();
+                caption.set_pts(pts);
+                caption.set_duration(duration);
+            }
+
+            gst::log!(CAT, obj: &pad, "Pushing {:?}", caption);
+
+            pushed_caption = true;
+            let res = pad.push(caption);
+            self.flow_combiner
+                .lock()
+                .unwrap()
+                .update_pad_flow(&pad, res)?;
+        }
+
+        // Let downstream of the caption pad know that this frame has no captions
+        if let (Some(pad), Some(pts), false) = (&caption_pad, pts, pushed_caption) {
+            let gap = gst::event::Gap::builder(pts).duration(duration).build();
+            gst::log!(CAT, obj: pad, "Pushing {:?}", gap);
+            pad.push_event(gap);
+        }
+
+        let res = self.srcpad.push(buffer);
+        self.flow_combiner
+            .lock()
+            .unwrap()
+            .update_pad_flow(&self.srcpad, res)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, element: &super::CcExtractor, event: gst::Event) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad, "Handling event {:?}", event);
+
+        match event.view() {
+            EventView::Caps(e) => {
+                let mut state = self.state.lock().unwrap();
+                state.framerate = e
+                    .caps()
+                    .structure(0)
+                    .and_then(|s| s.get::<gst::Fraction>("framerate").ok());
+                let caption_caps = state
+                    .caption_type
+                    .and_then(|caption_type| caption_caps(caption_type, state.framerate));
+                drop(state);
+
+                let caption_pad = self.caption_pad.lock().unwrap().clone();
+                if let (Some(caption_pad), Some(caps)) = (caption_pad, caption_caps) {
+                    caption_pad.push_event(gst::event::Caps::new(&caps));
+                }
+
+                self.srcpad.push_event(event)
+            }
+            // The caption pad sends its own stream-start
+            EventView::StreamStart(_) => self.srcpad.push_event(event),
+            EventView::Segment(_) => {
+                self.state.lock().unwrap().segment = Some(event.clone());
+                pad.event_default(Some(element), event)
+            }
+            EventView::FlushStop(_) => {
+                self.flow_combiner.lock().unwrap().reset();
+                pad.event_default(Some(element), event)
+            }
+            _ => pad.event_default(Some(element), event),
+        }
+    }
+
+    fn caption_query(
+        &self,
+        pad: &gst::Pad,
+        element: &super::CcExtractor,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        use gst::QueryViewMut;
+
+        gst::log!(CAT, obj: pad, "Handling query {:?}", query);
+
+        match query.view_mut() {
+            QueryViewMut::Caps(q) => {
+                let state = self.state.lock().unwrap();
+                let caps = match state
+                    .caption_type
+                    .and_then(|caption_type| caption_caps(caption_type, state.framerate))
+                {
+                    Some(caps) => caps,
+                    None => pad.pad_template_caps(),
+                };
+                drop(state);
+
+                if let Some(filter) = q.filter() {
+                    q.set_result(&filter.intersect_with_mode(&caps, gst::CapsIntersectMode::First));
+                } else {
+                    q.set_result(&caps);
+                }
+
+                true
+            }
+            _ => pad.query_default(Some(element), query),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for CcExtractor {
+    const NAME: &'static str = "RsCcExtractor";
+    type Type = super::CcExtractor;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                CcExtractor::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |extractor, element| extractor.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                CcExtractor::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |extractor, element| extractor.sink_event(pad, element, event),
+                )
+            })
+            .flags(gst::PadFlags::PROXY_CAPS)
+            .flags(gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .flags(gst::PadFlags::PROXY_CAPS)
+            .flags(gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        let mut flow_combiner = gst_base::UniqueFlowCombiner::new();
+        flow_combiner.add_pad(&srcpad);
+
+        Self {
+            srcpad,
+            sinkpad,
+            caption_pad: Mutex::default(),
+            flow_combiner: Mutex::new(flow_combiner),
+            settings: Mutex::default(),
+            state: Mutex::default(),
+        }
+    }
+}
+
+impl ObjectImpl for CcExtractor {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![glib::ParamSpecBoolean::builder("remove-caption-meta")
+                .nick("Remove Caption Meta")
+                .blurb("Remove caption meta from the outgoing video buffers")
+                .default_value(DEFAULT_REMOVE_CAPTION_META)
+                .mutable_playing()
+                .build()]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "remove-caption-meta" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.remove_caption_meta = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "remove-caption-meta" => {
+                let settings = self.settings.lock().unwrap();
+                settings.remove_caption_meta.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+}
+
+impl GstObjectImpl for CcExtractor {}
+
+impl ElementImpl for CcExtractor {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Closed Caption Extractor",
+                "Filter",
+                "Extracts closed captions from the caption meta of a video stream",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::new_any();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let caption_caps = caption_template_caps();
+
+            let caption_pad_template = gst::PadTemplate::new(
+                "caption",
+                gst::PadDirection::Src,
+                gst::PadPresence::Sometimes,
+                &caption_caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template, caption_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        let ret = self.parent_change_state(element, transition)?;
+
+        if transition == gst::StateChange::PausedToReady {
+            *self.state.lock().unwrap() = State::default();
+            self.flow_combiner.lock().unwrap().reset();
+
+            if let Some(caption_pad) = self.caption_pad.lock().unwrap().take() {
+                self.flow_combiner.lock().unwrap().remove_pad(&caption_pad);
+                let _ = caption_pad.set_active(false);
+                let _ = element.remove_pad(&caption_pad);
+            }
+        }
+
+        Ok(ret)
+    }
+}
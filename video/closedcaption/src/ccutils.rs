@@ -7,6 +7,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use byteorder::{BigEndian, ByteOrder};
+use std::collections::VecDeque;
 use std::fmt;
 
 #[allow(clippy::enum_variant_names)]
@@ -134,6 +135,371 @@ pub fn extract_cdp(mut data: &[u8]) -> Result<&[u8], ParseError> {
     Ok(&data[..len])
 }
 
+/// Returns the timecode of a CDP packet, if it has a valid `time_code_section`
+pub fn extract_cdp_timecode(data: &[u8]) -> Option<CdpTimecode> {
+    if data.len() < 12 || data[0..2] != [0x96, 0x69] || data[4] & 0x80 == 0 || data[7] != 0x71 {
+        return None;
+    }
+
+    let bcd = |byte: u8, tens_mask: u8| (((byte >> 4) & tens_mask) * 10 + (byte & 0x0f)) as u32;
+
+    Some(CdpTimecode {
+        hours: bcd(data[8], 0x3),
+        minutes: bcd(data[9], 0x7),
+        seconds: bcd(data[10], 0x7),
+        frames: bcd(data[11], 0x3),
+        field: data[10] & 0x80 == 0x80,
+        drop_frame: data[11] & 0x80 == 0x80,
+    })
+}
+
+/// The caps of all the CEA-608 and CEA-708 caption formats
+pub fn caption_template_caps() -> gst::Caps {
+    let mut caps = gst::Caps::new_empty();
+    {
+        let caps = caps.get_mut().unwrap();
+
+        let s = gst::Structure::builder("closedcaption/x-cea-608")
+            .field("format", gst::List::new(["raw", "s334-1a"]))
+            .build();
+        caps.append_structure(s);
+
+        let s = gst::Structure::builder("closedcaption/x-cea-708")
+            .field("format", gst::List::new(["cc_data", "cdp"]))
+            .build();
+        caps.append_structure(s);
+    }
+
+    caps
+}
+
+/// The caption type of fixed caption caps
+pub fn caption_type_from_caps(caps: &gst::CapsRef) -> Option<gst_video::VideoCaptionType> {
+    let s = caps.structure(0)?;
+    let format = s.get::<&str>("format").ok()?;
+
+    match (s.name(), format) {
+        ("closedcaption/x-cea-608", "raw") => Some(gst_video::VideoCaptionType::Cea608Raw),
+        ("closedcaption/x-cea-608", "s334-1a") => Some(gst_video::VideoCaptionType::Cea608S3341a),
+        ("closedcaption/x-cea-708", "cc_data") => Some(gst_video::VideoCaptionType::Cea708Raw),
+        ("closedcaption/x-cea-708", "cdp") => Some(gst_video::VideoCaptionType::Cea708Cdp),
+        _ => None,
+    }
+}
+
+/// The caps of a caption type, with a frame rate if known
+pub fn caption_caps(
+    caption_type: gst_video::VideoCaptionType,
+    framerate: Option<gst::Fraction>,
+) -> Option<gst::Caps> {
+    let (name, format) = match caption_type {
+        gst_video::VideoCaptionType::Cea608Raw => ("closedcaption/x-cea-608", "raw"),
+        gst_video::VideoCaptionType::Cea608S3341a => ("closedcaption/x-cea-608", "s334-1a"),
+        gst_video::VideoCaptionType::Cea708Raw => ("closedcaption/x-cea-708", "cc_data"),
+        gst_video::VideoCaptionType::Cea708Cdp => ("closedcaption/x-cea-708", "cdp"),
+        _ => return None,
+    };
+
+    let mut builder = gst::Caps::builder(name).field("format", format);
+    if let Some(framerate) = framerate {
+        builder = builder.field("framerate", framerate);
+    }
+
+    Some(builder.build())
+}
+
+/// Returns the CDP frame rate code and the number of `cc_data` triplets
+/// per frame for a frame rate, SMPTE 334-2 Table 3 and CEA-708-E Table 3.
+pub fn cdp_frame_rate(fps_n: i32, fps_d: i32) -> Option<(u8, usize)> {
+    match (fps_n, fps_d) {
+        (24000, 1001) => Some((0x1, 25)),
+        (24, 1) => Some((0x2, 25)),
+        (25, 1) => Some((0x3, 24)),
+        (30000, 1001) => Some((0x4, 20)),
+        (30, 1) => Some((0x5, 20)),
+        (50, 1) => Some((0x6, 12)),
+        (60000, 1001) => Some((0x7, 10)),
+        (60, 1) => Some((0x8, 10)),
+        _ => None,
+    }
+}
+
+/// Timecode of a CDP `time_code_section`, SMPTE 334-2 Table 4
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CdpTimecode {
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    pub frames: u32,
+    pub field: bool,
+    pub drop_frame: bool,
+}
+
+impl CdpTimecode {
+    /// The frame number in the second at `fps`, above 30 frames per second
+    /// the frame pairs are counted and `field` tells the frame of the pair
+    pub fn frames(&self, fps: gst::Fraction) -> u32 {
+        if fps.numer() > 30 * fps.denom() {
+            2 * self.frames + self.field as u32
+        } else {
+            self.frames
+        }
+    }
+
+    fn write(&self, cdp: &mut Vec<u8>) {
+        let bcd =
+            |value: u32, tens_mask: u32| ((((value / 10) & tens_mask) << 4) | (value % 10)) as u8;
+
+        cdp.push(0x71);
+        cdp.push(0xc0 | bcd(self.hours, 0x3));
+        cdp.push(0x80 | bcd(self.minutes, 0x7));
+        cdp.push(if self.field { 0x80 } else { 0x00 } | bcd(self.seconds, 0x7));
+        cdp.push(if self.drop_frame { 0x80 } else { 0x00 } | bcd(self.frames, 0x3));
+    }
+}
+
+impl From<&gst_video::ValidVideoTimeCode> for CdpTimecode {
+    fn from(tc: &gst_video::ValidVideoTimeCode) -> Self {
+        let fps = tc.fps();
+        let (frames, field) = if fps.numer() > 30 * fps.denom() {
+            (tc.frames() / 2, tc.frames() % 2 == 1)
+        } else {
+            (tc.frames(), tc.field_count() == 2)
+        };
+
+        CdpTimecode {
+            hours: tc.hours(),
+            minutes: tc.minutes(),
+            seconds: tc.seconds(),
+            frames,
+            field,
+            drop_frame: tc
+                .flags()
+                .contains(gst_video::VideoTimeCodeFlags::DROP_FRAME),
+        }
+    }
+}
+
+/// Wraps `cc_data` in a CDP packet, with a `time_code_section` if `timecode`
+/// is set, padding `cc_data` to `cc_count` triplets.
+pub fn write_cdp(
+    frame_rate_code: u8,
+    sequence: u16,
+    timecode: Option<&CdpTimecode>,
+    cc_data: &[u8],
+    cc_count: usize,
+) -> Vec<u8> {
+    assert!(cc_data.len() % 3 == 0 && cc_data.len() <= 3 * cc_count && cc_count <= 0x1f);
+
+    let timecode_len = if timecode.is_some() { 5 } else { 0 };
+    let len = 7 + timecode_len + 2 + 3 * cc_count + 4;
+    let mut cdp = Vec::with_capacity(len);
+
+    cdp.extend_from_slice(&[0x96, 0x69, len as u8, (frame_rate_code << 4) | 0x0f]);
+    // time_code_present, ccdata_present, caption_service_active and the reserved bit
+    cdp.push(if timecode.is_some() { 0xc3 } else { 0x43 });
+    cdp.extend_from_slice(&sequence.to_be_bytes());
+
+    if let Some(timecode) = timecode {
+        timecode.write(&mut cdp);
+    }
+
+    cdp.push(0x72);
+    cdp.push(0xe0 | cc_count as u8);
+    cdp.extend_from_slice(cc_data);
+    for _ in cc_data.len() / 3..cc_count {
+        cdp.extend_from_slice(&[0xfa, 0x00, 0x00]);
+    }
+
+    cdp.push(0x74);
+    cdp.extend_from_slice(&sequence.to_be_bytes());
+    let sum = cdp.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    cdp.push(sum.wrapping_neg());
+
+    cdp
+}
+
+/// Queue of CEA-608 byte pairs per field and of DTVCC triplets, refilled from
+/// any caption format and drained at the bandwidth of a video frame.
+#[derive(Debug, Default)]
+pub struct CcDataQueue {
+    field1: VecDeque<[u8; 2]>,
+    field2: VecDeque<[u8; 2]>,
+    dtvcc: VecDeque<[u8; 3]>,
+}
+
+impl CcDataQueue {
+    pub fn is_empty(&self) -> bool {
+        self.field1.is_empty() && self.field2.is_empty() && self.dtvcc.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.field1.clear();
+        self.field2.clear();
+        self.dtvcc.clear();
+    }
+
+    /// Queues a CEA-608 byte pair for `field`, 0 or 1
+    pub fn push_cea608(&mut self, field: u8, pair: [u8; 2]) {
+        // Null pairs are only padding
+        if pair == [0x80, 0x80] {
+            return;
+        }
+
+        if field == 0 {
+            self.field1.push_back(pair);
+        } else {
+            self.field2.push_back(pair);
+        }
+    }
+
+    /// Queues raw CEA-608 byte pairs, which are always for field 1
+    pub fn push_cea608_raw(&mut self, data: &[u8]) {
+        for pair in data.chunks_exact(2) {
+            self.push_cea608(0, [pair[0], pair[1]]);
+        }
+    }
+
+    /// Queues SMPTE 334-1 Annex A triplets
+    pub fn push_s334_1a(&mut self, data: &[u8]) {
+        for triplet in data.chunks_exact(3) {
+            let field = if triplet[0] & 0x80 == 0x80 { 0 } else { 1 };
+            self.push_cea608(field, [triplet[1], triplet[2]]);
+        }
+    }
+
+    /// Queues the valid triplets of CEA-708 `cc_data`
+    pub fn push_cc_data(&mut self, data: &[u8]) {
+        for triplet in data.chunks_exact(3) {
+            let cc_valid = triplet[0] & 0x04 == 0x04;
+            let cc_type = triplet[0] & 0x03;
+
+            if !cc_valid {
+                continue;
+            }
+
+            match cc_type {
+                0x00 | 0x01 => self.push_cea608(cc_type, [triplet[1], triplet[2]]),
+                _ => self
+                    .dtvcc
+                    .push_back([0xf8 | triplet[0], triplet[1], triplet[2]]),
+            }
+        }
+    }
+
+    /// Drops the oldest data until the queue drains in at most `max_frames` frames,
+    /// returning whether anything was dropped
+    pub fn truncate(&mut self, max_frames: usize, cc_count: usize) -> bool {
+        let max_dtvcc = max_frames * (cc_count - 2);
+        let mut dropped = false;
+
+        for queue in [&mut self.field1, &mut self.field2] {
+            while queue.len() > max_frames {
+                queue.pop_front();
+                dropped = true;
+            }
+        }
+
+        // Drop whole DTVCC packets when possible
+        while self.dtvcc.len() > max_dtvcc {
+            self.dtvcc.pop_front();
+            while self
+                .dtvcc
+                .front()
+                .map_or(false, |triplet| triplet[0] & 0x01 == 0x00)
+            {
+                self.dtvcc.pop_front();
+            }
+            dropped = true;
+        }
+
+        dropped
+    }
+
+    /// The number of byte pairs queued for the fullest CEA-608 field
+    pub fn cea608_len(&self) -> usize {
+        self.field1.len().max(self.field2.len())
+    }
+
+    /// The number of DTVCC triplets queued
+    pub fn dtvcc_len(&self) -> usize {
+        self.dtvcc.len()
+    }
+
+    /// Takes the next `pairs` field 1 pairs as raw CEA-608, with `padding`
+    /// missing pairs are replaced by null pairs
+    pub fn pop_cea608_raw(&mut self, pairs: usize, padding: bool) -> Vec<u8> {
+        let mut data = Vec::with_capacity(2 * pairs);
+
+        for _ in 0..pairs {
+            match self.field1.pop_front() {
+                Some(pair) => data.extend_from_slice(&pair),
+                None if padding => data.extend_from_slice(&[0x80, 0x80]),
+                None => (),
+            }
+        }
+
+        data
+    }
+
+    /// Takes the next `pairs` pairs of each field as SMPTE 334-1 Annex A
+    /// triplets, with `padding` missing pairs are replaced by null pairs
+    pub fn pop_s334_1a(&mut self, pairs: usize, padding: bool) -> Vec<u8> {
+        let mut data = Vec::with_capacity(6 * pairs);
+
+        for _ in 0..pairs {
+            for (field, queue) in [(0x80, &mut self.field1), (0x00, &mut self.field2)] {
+                match queue.pop_front() {
+                    Some(pair) => data.extend_from_slice(&[field, pair[0], pair[1]]),
+                    None if padding => data.extend_from_slice(&[field, 0x80, 0x80]),
+                    None => (),
+                }
+            }
+        }
+
+        data
+    }
+
+    /// Takes the `cc_data` of one frame of at most `cc_count` triplets: the
+    /// next `pairs` pairs of each field, followed by DTVCC triplets in the
+    /// slots not reserved for CEA-608. With `padding`, missing CEA-608 pairs
+    /// are replaced by padding triplets so that at least one per field always
+    /// comes first, as in CDP.
+    pub fn pop_cc_data(&mut self, pairs: usize, cc_count: usize, padding: bool) -> Vec<u8> {
+        let mut data = Vec::with_capacity(3 * cc_count);
+        let cea608_slots = pairs.max(1);
+
+        for i in 0..cea608_slots {
+            for (cc_type, queue) in [(0xfc, &mut self.field1), (0xfd, &mut self.field2)] {
+                let pair = if i < pairs { queue.pop_front() } else { None };
+
+                match pair {
+                    Some(pair) => data.extend_from_slice(&[cc_type, pair[0], pair[1]]),
+                    None if padding => data.extend_from_slice(&[cc_type & !0x04, 0x80, 0x80]),
+                    None => (),
+                }
+            }
+        }
+
+        let max_dtvcc = cc_count.saturating_sub(2 * cea608_slots);
+        for triplet in self.dtvcc.drain(..max_dtvcc.min(self.dtvcc.len())) {
+            data.extend_from_slice(&triplet);
+        }
+
+        data
+    }
+}
+
+/// The number of CEA-608 byte pairs per field to transmit in the frame
+/// `frame_no` of a stream at `fps_n/fps_d`: CEA-608 is transmitted at 30
+/// byte pairs per nominal second, spread as evenly as possible over frames.
+pub fn cea608_pairs(frame_no: u64, fps_n: i32, fps_d: i32) -> usize {
+    let nominal_fps = (fps_n as u64 + fps_d as u64 - 1) / fps_d as u64;
+    let sent = |frame_no: u64| (frame_no * 30 + nominal_fps - 1) / nominal_fps;
+
+    (sent(frame_no + 1) - sent(frame_no)) as usize
+}
+
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?} at byte {}: {}", self.code, self.byte, self.msg)
@@ -0,0 +1,432 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+// Each sink pad takes CC1 data, as output by tttocea608, and moves it to the
+// caption channel its name refers to:
+//
+//  * CC2 and CC4 use the second data channel of their field, the first byte
+//    of their control codes has the 0x08 bit set.
+//
+//  * CC3 and CC4 are transmitted in field 2, where the miscellaneous control
+//    codes start with 0x15 instead of 0x14.
+//
+// One channel per field can be muxed, both fields are then output at the
+// CEA-608 bandwidth as SMPTE 334-1 Annex A triplets, one buffer per frame.
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::prelude::*;
+use gst_base::subclass::prelude::*;
+use gst_base::AGGREGATOR_FLOW_NEED_DATA;
+
+use crate::ccutils::{cea608_pairs, CcDataQueue};
+
+use once_cell::sync::Lazy;
+
+use std::sync::Mutex;
+
+const DEFAULT_FPS_N: i32 = 30;
+const DEFAULT_FPS_D: i32 = 1;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "cea608mux",
+        gst::DebugColorFlags::empty(),
+        Some("CEA-608 Mux Element"),
+    )
+});
+
+fn frame_pts(frame_no: u64, framerate: gst::Fraction) -> gst::ClockTime {
+    (frame_no * gst::ClockTime::SECOND)
+        .mul_div_round(framerate.denom() as u64, framerate.numer() as u64)
+        .unwrap()
+}
+
+/// The channel, from 1 to 4, of a pad named `ccN`
+fn pad_channel(name: &str) -> Option<u32> {
+    name.strip_prefix("cc")
+        .and_then(|channel| channel.parse::<u32>().ok())
+        .filter(|channel| (1..=4).contains(channel))
+}
+
+fn channel_field(channel: u32) -> u8 {
+    if channel > 2 {
+        1
+    } else {
+        0
+    }
+}
+
+fn with_parity(byte: u8) -> u8 {
+    let byte = byte & 0x7f;
+
+    if byte.count_ones() % 2 == 0 {
+        byte | 0x80
+    } else {
+        byte
+    }
+}
+
+/// Moves a CC1 byte pair to `channel`, only control codes differ
+fn cc1_to_channel(pair: [u8; 2], channel: u32) -> [u8; 2] {
+    let mut b1 = pair[0] & 0x7f;
+    let b2 = pair[1] & 0x7f;
+
+    if !(0x10..=0x17).contains(&b1) || b2 < 0x20 {
+        return pair;
+    }
+
+    if channel == 2 || channel == 4 {
+        b1 |= 0x08;
+    }
+
+    if channel_field(channel) == 1 && b1 & 0x07 == 0x04 && (0x20..=0x2f).contains(&b2) {
+        b1 |= 0x01;
+    }
+
+    [with_parity(b1), pair[1]]
+}
+
+#[derive(Default)]
+struct State {
+    framerate: Option<gst::Fraction>,
+    frame_no: Option<u64>,
+    queue: CcDataQueue,
+}
+
+#[derive(Default)]
+pub struct Cea608Mux {
+    state: Mutex<State>,
+}
+
+impl Cea608Mux {
+    fn negotiate_framerate(
+        &self,
+        element: &super::Cea608Mux,
+        state: &mut State,
+    ) -> Result<gst::Fraction, gst::FlowError> {
+        let srcpad = element.src_pad();
+        let mut caps = match srcpad.allowed_caps() {
+            None => srcpad.pad_template_caps(),
+            Some(caps) => caps,
+        };
+
+        if caps.is_empty() {
+            gst::error!(CAT, obj: element, "Empty downstream caps");
+            return Err(gst::FlowError::NotNegotiated);
+        }
+
+        {
+            let caps = caps.make_mut();
+            let s = caps.structure_mut(0).unwrap();
+
+            s.fixate_field_nearest_fraction(
+                "framerate",
+                gst::Fraction::new(DEFAULT_FPS_N, DEFAULT_FPS_D),
+            );
+        }
+        caps.fixate();
+
+        let framerate = caps
+            .structure(0)
+            .unwrap()
+            .get::<gst::Fraction>("framerate")
+            .unwrap();
+
+        gst::debug!(CAT, obj: element, "Negotiated caps {}", caps);
+
+        element.set_src_caps(&caps);
+
+        state.framerate = Some(framerate);
+
+        Ok(framerate)
+    }
+
+    /// Queues the pairs of the buffers of `pad` starting before `end`
+    fn consume_pad(
+        &self,
+        element: &super::Cea608Mux,
+        pad: &gst_base::AggregatorPad,
+        state: &mut State,
+        end: gst::ClockTime,
+    ) -> Result<(), gst::FlowError> {
+        let channel = pad_channel(&pad.name()).unwrap();
+        let field = channel_field(channel);
+
+        while let Some(buffer) = pad.peek_buffer() {
+            let pts = buffer.pts().ok_or_else(|| {
+                gst::element_error!(
+                    element,
+                    gst::StreamError::Format,
+                    ["Stream with timestamped buffers required"]
+                );
+                gst::FlowError::Error
+            })?;
+
+            if pts >= end {
+                break;
+            }
+
+            pad.drop_buffer();
+
+            // Gap events are queued as empty gap buffers
+            if buffer.flags().contains(gst::BufferFlags::GAP) && buffer.size() == 0 {
+                continue;
+            }
+
+            gst::log!(CAT, obj: pad, "Handling {:?}", buffer);
+
+            let data = buffer.map_readable().map_err(|_| {
+                gst::error!(CAT, obj: pad, "Can't map buffer readable");
+
+                gst::FlowError::Error
+            })?;
+
+            for pair in data.chunks_exact(2) {
+                state
+                    .queue
+                    .push_cea608(field, cc1_to_channel([pair[0], pair[1]], channel));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Cea608Mux {
+    const NAME: &'static str = "GstCea608Mux";
+    type Type = super::Cea608Mux;
+    type ParentType = gst_base::Aggregator;
+}
+
+impl ObjectImpl for Cea608Mux {}
+
+impl GstObjectImpl for Cea608Mux {}
+
+impl ElementImpl for Cea608Mux {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "CEA-608 Mux",
+                "Muxer",
+                "Combines CEA-608 streams into the channels of a single stream",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::builder("closedcaption/x-cea-608")
+                .field("format", "raw")
+                .build();
+
+            let sink_pad_template = gst::PadTemplate::with_gtype(
+                "cc%u",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Request,
+                &caps,
+                gst_base::AggregatorPad::static_type(),
+            )
+            .unwrap();
+
+            // At most 30 fps, so that each frame carries at least one pair
+            let framerates = gst::List::new([
+                gst::Fraction::new(24000, 1001),
+                gst::Fraction::new(24, 1),
+                gst::Fraction::new(25, 1),
+                gst::Fraction::new(30000, 1001),
+                gst::Fraction::new(30, 1),
+            ]);
+
+            let caps = gst::Caps::builder("closedcaption/x-cea-608")
+                .field("format", "s334-1a")
+                .field("framerate", framerates)
+                .build();
+
+            let src_pad_template = gst::PadTemplate::with_gtype(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+                gst_base::AggregatorPad::static_type(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl AggregatorImpl for Cea608Mux {
+    fn aggregate(
+        &self,
+        element: &Self::Type,
+        timeout: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::trace!(CAT, obj: element, "aggregate, timeout: {}", timeout);
+
+        let mut state = self.state.lock().unwrap();
+
+        let framerate = match state.framerate {
+            Some(framerate) => framerate,
+            None => self.negotiate_framerate(element, &mut state)?,
+        };
+
+        let pads = element
+            .sink_pads()
+            .into_iter()
+            .map(|pad| pad.downcast::<gst_base::AggregatorPad>().unwrap())
+            .collect::<Vec<_>>();
+
+        let all_eos = pads.iter().all(|pad| pad.is_eos());
+
+        let frame_no = match state.frame_no {
+            Some(frame_no) => frame_no,
+            None => {
+                let first_pts = pads
+                    .iter()
+                    .filter_map(|pad| pad.peek_buffer())
+                    .filter_map(|buffer| buffer.pts())
+                    .min();
+
+                match first_pts {
+                    Some(pts) => {
+                        let frame_no = pts
+                            .mul_div_floor(framerate.numer() as u64, framerate.denom() as u64)
+                            .unwrap()
+                            .seconds();
+                        gst::debug!(CAT, obj: element, "Initial skip to frame no {}", frame_no);
+                        frame_no
+                    }
+                    None if all_eos => return Err(gst::FlowError::Eos),
+                    None => return Err(AGGREGATOR_FLOW_NEED_DATA),
+                }
+            }
+        };
+
+        let pts = frame_pts(frame_no, framerate);
+        let end = frame_pts(frame_no + 1, framerate);
+
+        for pad in &pads {
+            self.consume_pad(element, pad, &mut state, end)?;
+        }
+
+        // Nothing left to output, and nothing will come anymore
+        if all_eos && state.queue.is_empty() {
+            return Err(gst::FlowError::Eos);
+        }
+
+        let pairs = cea608_pairs(frame_no, framerate.numer(), framerate.denom());
+        let data = state.queue.pop_s334_1a(pairs, true);
+
+        if state.queue.cea608_len() > pairs {
+            gst::debug!(CAT, obj: element, "More captions than bandwidth!");
+        }
+
+        state.frame_no = Some(frame_no + 1);
+
+        drop(state);
+
+        let mut buffer = gst::Buffer::from_mut_slice(data);
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(pts);
+            buffer.set_duration(end - pts);
+        }
+
+        gst::trace!(CAT, obj: element, "Outputting {:?}", buffer);
+
+        element.set_position(Some(end));
+
+        self.finish_buffer(element, buffer)
+    }
+
+    fn create_new_pad(
+        &self,
+        element: &Self::Type,
+        templ: &gst::PadTemplate,
+        req_name: Option<&str>,
+        _caps: Option<&gst::Caps>,
+    ) -> Option<gst_base::AggregatorPad> {
+        let used_fields = element
+            .sink_pads()
+            .iter()
+            .filter_map(|pad| pad_channel(&pad.name()))
+            .map(channel_field)
+            .collect::<Vec<_>>();
+
+        let name = match req_name {
+            Some(name) => {
+                let channel = match pad_channel(name) {
+                    Some(channel) => channel,
+                    None => {
+                        gst::error!(CAT, obj: element, "Invalid pad name {}", name);
+                        return None;
+                    }
+                };
+
+                if used_fields.contains(&channel_field(channel)) {
+                    gst::error!(
+                        CAT,
+                        obj: element,
+                        "Field {} of {} is already in use",
+                        channel_field(channel) + 1,
+                        name
+                    );
+                    return None;
+                }
+
+                name.to_string()
+            }
+            None => {
+                let channel = [1, 3]
+                    .into_iter()
+                    .find(|channel| !used_fields.contains(&channel_field(*channel)))?;
+
+                format!("cc{}", channel)
+            }
+        };
+
+        let pad =
+            gst::PadBuilder::<gst_base::AggregatorPad>::from_template(templ, Some(&name)).build();
+
+        Some(pad)
+    }
+
+    fn flush(&self, aggregator: &Self::Type) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state = self.state.lock().unwrap();
+        state.frame_no = None;
+        state.queue.clear();
+        drop(state);
+
+        self.parent_flush(aggregator)
+    }
+
+    fn start(&self, aggregator: &Self::Type) -> Result<(), gst::ErrorMessage> {
+        *self.state.lock().unwrap() = State::default();
+
+        self.parent_start(aggregator)
+    }
+
+    fn stop(&self, aggregator: &Self::Type) -> Result<(), gst::ErrorMessage> {
+        *self.state.lock().unwrap() = State::default();
+
+        self.parent_stop(aggregator)
+    }
+
+    fn negotiate(&self, _aggregator: &Self::Type) -> bool {
+        true
+    }
+}
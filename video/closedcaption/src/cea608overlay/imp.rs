@@ -19,6 +19,7 @@ use pango::prelude::*;
 
 use crate::caption_frame::{CaptionFrame, Status};
 use crate::ccutils::extract_cdp;
+use crate::cea708::{self, Justify, Opacity, Window};
 
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
@@ -30,12 +31,24 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
 
 const DEFAULT_FIELD: i32 = -1;
 const DEFAULT_BLACK_BACKGROUND: bool = false;
+const DEFAULT_SERVICE: i32 = 0;
+const DEFAULT_FONT_DESC: &str = "monospace";
+const DEFAULT_MARGIN: u32 = 0;
+const DEFAULT_ROLL_UP_ANIMATION: bool = false;
 
-#[derive(Debug)]
+/// Duration of the scrolling of roll-up captions, when animated
+const ROLL_UP_DURATION_MS: u64 = 300;
+
+#[derive(Debug, Clone)]
 struct Settings {
     field: i32,
     black_background: bool,
     timeout: Option<gst::ClockTime>,
+    service: i32,
+    font_desc: String,
+    horizontal_margin: u32,
+    vertical_margin: u32,
+    roll_up_animation: bool,
 }
 
 impl Default for Settings {
@@ -44,34 +57,78 @@ impl Default for Settings {
             field: DEFAULT_FIELD,
             black_background: DEFAULT_BLACK_BACKGROUND,
             timeout: gst::ClockTime::NONE,
+            service: DEFAULT_SERVICE,
+            font_desc: String::from(DEFAULT_FONT_DESC),
+            horizontal_margin: DEFAULT_MARGIN,
+            vertical_margin: DEFAULT_MARGIN,
+            roll_up_animation: DEFAULT_ROLL_UP_ANIMATION,
         }
     }
 }
 
+/// The area of the 32 x 15 characters grid, in pixels
+#[derive(Debug, Clone, Copy, Default)]
+struct Area {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+/// A rendered block of text: the whole CEA-608 grid, or a CEA-708 window
+struct Block {
+    buffer: gst::Buffer,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    /// The text of each row, to detect scrolling
+    rows: Vec<String>,
+    line_height: i32,
+}
+
+/// A block being scrolled up
+struct RollUp {
+    block: usize,
+    start: gst::ClockTime,
+    offset: i32,
+}
+
 struct State {
     video_info: Option<gst_video::VideoInfo>,
+    context: Option<pango::Context>,
+    font_desc: Option<pango::FontDescription>,
     layout: Option<pango::Layout>,
+    area: Area,
     caption_frame: CaptionFrame,
+    cea708_decoder: Option<cea708::Decoder>,
+    blocks: Vec<Block>,
+    roll_up: Option<RollUp>,
     composition: Option<gst_video::VideoOverlayComposition>,
-    left_alignment: i32,
     attach: bool,
     selected_field: Option<u8>,
     last_cc_pts: Option<gst::ClockTime>,
 }
 
-// SAFETY: Required because `pango::Layout` is not `Send` but the whole `State` needs to be.
-// We ensure that no additional references to the layout are ever created, which makes it safe
-// to send it to other threads as long as only a single thread uses it concurrently.
+// SAFETY: Required because `pango::Layout` and `pango::Context` are not `Send` but the whole
+// `State` needs to be. We ensure that no additional references to the layout and context are
+// ever created, which makes it safe to send them to other threads as long as only a single
+// thread uses them concurrently.
 unsafe impl Send for State {}
 
 impl Default for State {
     fn default() -> Self {
         Self {
             video_info: None,
+            context: None,
+            font_desc: None,
             layout: None,
+            area: Area::default(),
             caption_frame: CaptionFrame::default(),
+            cea708_decoder: None,
+            blocks: Vec::new(),
+            roll_up: None,
             composition: None,
-            left_alignment: 0,
             attach: false,
             selected_field: None,
             last_cc_pts: gst::ClockTime::NONE,
@@ -79,6 +136,247 @@ impl Default for State {
     }
 }
 
+impl State {
+    fn new(settings: &Settings) -> Self {
+        Self {
+            selected_field: match settings.field {
+                -1 => None,
+                val => Some(val as u8),
+            },
+            cea708_decoder: match settings.service {
+                0 => None,
+                service => Some(cea708::Decoder::new(service as u8)),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn clear(&mut self) {
+        self.blocks.clear();
+        self.roll_up = None;
+        self.composition = None;
+    }
+}
+
+/// Whether the rows scrolled up by one, as in roll-up mode
+fn rolled_up(old: &[String], new: &[String]) -> bool {
+    old.len() == new.len()
+        && new.len() > 1
+        && old[1..].iter().any(|row| !row.trim().is_empty())
+        && new[..new.len() - 1] == old[1..]
+}
+
+fn pango_color(value: f64) -> u16 {
+    (value * 65535.0) as u16
+}
+
+fn insert_attribute(attrs: &pango::AttrList, mut attr: pango::Attribute, start: usize, end: usize) {
+    attr.set_start_index(start as u32);
+    attr.set_end_index(end as u32);
+    attrs.insert(attr);
+}
+
+/// Applies the pen attributes and colors of a run of cells
+fn insert_cell_attributes(attrs: &pango::AttrList, cell: &cea708::Cell, start: usize, end: usize) {
+    let (r, g, b) = cell.color.foreground_color.to_rgb();
+    insert_attribute(
+        attrs,
+        pango::AttrColor::new_foreground(pango_color(r), pango_color(g), pango_color(b)).upcast(),
+        start,
+        end,
+    );
+    insert_attribute(
+        attrs,
+        pango::AttrInt::new_foreground_alpha(pango_color(cell.color.foreground_opacity.to_alpha()))
+            .upcast(),
+        start,
+        end,
+    );
+
+    if cell.color.background_opacity != Opacity::Transparent {
+        let (r, g, b) = cell.color.background_color.to_rgb();
+        insert_attribute(
+            attrs,
+            pango::AttrColor::new_background(pango_color(r), pango_color(g), pango_color(b))
+                .upcast(),
+            start,
+            end,
+        );
+        insert_attribute(
+            attrs,
+            pango::AttrInt::new_background_alpha(pango_color(
+                cell.color.background_opacity.to_alpha(),
+            ))
+            .upcast(),
+            start,
+            end,
+        );
+    }
+
+    if cell.attributes.italics {
+        insert_attribute(
+            attrs,
+            pango::AttrInt::new_style(pango::Style::Italic).upcast(),
+            start,
+            end,
+        );
+    }
+
+    if cell.attributes.underline {
+        insert_attribute(
+            attrs,
+            pango::AttrInt::new_underline(pango::Underline::Single).upcast(),
+            start,
+            end,
+        );
+    }
+}
+
+/// Returns the text of a window with its attributes, and the text of each
+/// row. Left justified rows keep their leading spaces, the others are
+/// aligned by pango.
+fn window_text(window: &Window) -> (String, pango::AttrList, Vec<String>) {
+    let attrs = pango::AttrList::new();
+    let mut text = String::new();
+    let mut rows = Vec::with_capacity(window.row_count());
+
+    let is_text =
+        |cell: &Option<cea708::Cell>| matches!(cell, Some(cell) if !cell.character.is_whitespace());
+
+    for (i, row) in window.rows.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        let row_start = text.len();
+
+        let first = match window.attributes.justify {
+            Justify::Left | Justify::Full => Some(0),
+            Justify::Center | Justify::Right => row.iter().position(is_text),
+        };
+        let last = row.iter().rposition(is_text);
+
+        if let (Some(first), Some(last)) = (first, last) {
+            // Runs of cells with the same pen share attributes
+            let mut run: Option<(cea708::Cell, usize)> = None;
+
+            for cell in &row[first..=last] {
+                let start = text.len();
+
+                match cell {
+                    Some(cell) => {
+                        match run {
+                            Some((run_cell, _))
+                                if run_cell.attributes == cell.attributes
+                                    && run_cell.color == cell.color => {}
+                            _ => {
+                                if let Some((run_cell, run_start)) = run {
+                                    insert_cell_attributes(&attrs, &run_cell, run_start, start);
+                                }
+                                run = Some((*cell, start));
+                            }
+                        }
+                        text.push(cell.character);
+                    }
+                    None => text.push(' '),
+                }
+            }
+
+            if let Some((run_cell, run_start)) = run {
+                insert_cell_attributes(&attrs, &run_cell, run_start, text.len());
+            }
+        }
+
+        rows.push(text[row_start..].to_string());
+    }
+
+    (text, attrs, rows)
+}
+
+/// Renders the layout into a premultiplied ARGB buffer of the given size,
+/// optionally filled with a background color first
+fn render_layout(
+    layout: &pango::Layout,
+    width: i32,
+    height: i32,
+    fill: Option<(f64, f64, f64, f64)>,
+    outline: bool,
+) -> Option<gst::Buffer> {
+    let mut buffer = gst::Buffer::with_size((width * height) as usize * 4).ok()?;
+
+    gst_video::VideoMeta::add(
+        buffer.get_mut().unwrap(),
+        gst_video::VideoFrameFlags::empty(),
+        #[cfg(target_endian = "little")]
+        gst_video::VideoFormat::Bgra,
+        #[cfg(target_endian = "big")]
+        gst_video::VideoFormat::Argb,
+        width as u32,
+        height as u32,
+    )
+    .ok()?;
+    let buffer = buffer.into_mapped_buffer_writable().unwrap();
+
+    // Pass ownership of the buffer to the cairo surface but keep around
+    // a raw pointer so we can later retrieve it again when the surface
+    // is done
+    let buffer_ptr = buffer.buffer().as_ptr();
+    let surface = cairo::ImageSurface::create_for_data(
+        buffer,
+        cairo::Format::ARgb32,
+        width,
+        height,
+        width * 4,
+    )
+    .ok()?;
+
+    let cr = cairo::Context::new(&surface).ok()?;
+
+    // Clear background
+    cr.set_operator(cairo::Operator::Source);
+    match fill {
+        Some((r, g, b, a)) => cr.set_source_rgba(r, g, b, a),
+        None => cr.set_source_rgba(0.0, 0.0, 0.0, 0.0),
+    }
+    cr.paint().ok()?;
+
+    // Render text outline
+    if outline {
+        cr.save().ok()?;
+        cr.set_operator(cairo::Operator::Over);
+
+        cr.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+
+        pangocairo::functions::layout_path(&cr, layout);
+        cr.stroke().ok()?;
+        cr.restore().ok()?;
+    }
+
+    // Render text
+    cr.save().ok()?;
+    cr.set_operator(cairo::Operator::Over);
+    cr.set_source_rgba(255.0, 255.0, 255.0, 1.0);
+
+    pangocairo::functions::show_layout(&cr, layout);
+
+    cr.restore().ok()?;
+    drop(cr);
+
+    // Safety: The surface still owns a mutable reference to the buffer but our reference
+    // to the surface here is the last one. After dropping the surface the buffer would be
+    // freed, so we keep an additional strong reference here before dropping the surface,
+    // which is then returned. As such it's guaranteed that nothing is using the buffer
+    // anymore mutably.
+    unsafe {
+        assert_eq!(
+            cairo::ffi::cairo_surface_get_reference_count(surface.to_raw_none()),
+            1
+        );
+        let buffer = glib::translate::from_glib_none(buffer_ptr);
+        drop(surface);
+        Some(buffer)
+    }
+}
+
 pub struct Cea608Overlay {
     srcpad: gst::Pad,
     sinkpad: gst::Pad,
@@ -100,6 +398,7 @@ impl Cea608Overlay {
         &self,
         element: &super::Cea608Overlay,
         state: &mut State,
+        settings: &Settings,
     ) -> Result<gst::FlowSuccess, gst::FlowError> {
         let video_info = state.video_info.as_ref().unwrap();
         let fontmap = match pangocairo::FontMap::new() {
@@ -128,42 +427,102 @@ impl Cea608Overlay {
         context.set_base_dir(pango::Direction::Ltr);
         let layout = pango::Layout::new(&context);
         layout.set_alignment(pango::Alignment::Left);
-        let mut font_desc = pango::FontDescription::from_string("monospace");
-
-        let mut font_size = 1;
-        let mut left_alignment = 0;
-        loop {
-            font_desc.set_size(font_size * pango::SCALE);
+        let mut font_desc = pango::FontDescription::from_string(&settings.font_desc);
+
+        let grid = "12345678901234567890123456789012\n2\n3\n4\n5\n6\n7\n8\n9\n0\n1\n2\n3\n4\n5";
+        let max_width = video_info.width() as i32 * (100 - 2 * settings.horizontal_margin as i32)
+            / 100
+            * pango::SCALE;
+        let max_height = video_info.height() as i32 * (100 - 2 * settings.vertical_margin as i32)
+            / 100
+            * pango::SCALE;
+
+        // The size is only computed when not part of the description
+        if font_desc.set_fields().contains(pango::FontMask::SIZE) {
             layout.set_font_description(Some(&font_desc));
-            layout.set_text(
-                "12345678901234567890123456789012\n2\n3\n4\n5\n6\n7\n8\n9\n0\n1\n2\n3\n4\n5",
-            );
-            let (_ink_rect, logical_rect) = layout.extents();
-            if logical_rect.width() > video_info.width() as i32 * pango::SCALE
-                || logical_rect.height() > video_info.height() as i32 * pango::SCALE
-            {
-                font_desc.set_size((font_size - 1) * pango::SCALE);
+        } else {
+            let mut font_size = 1;
+            loop {
+                font_desc.set_size(font_size * pango::SCALE);
                 layout.set_font_description(Some(&font_desc));
-                break;
+                layout.set_text(grid);
+                let (_ink_rect, logical_rect) = layout.extents();
+                if logical_rect.width() > max_width || logical_rect.height() > max_height {
+                    font_desc.set_size((font_size - 1) * pango::SCALE);
+                    layout.set_font_description(Some(&font_desc));
+                    break;
+                }
+                font_size += 1;
             }
-            left_alignment = (video_info.width() as i32 - logical_rect.width() / pango::SCALE) / 2;
-            font_size += 1;
         }
 
-        if self.settings.lock().unwrap().black_background {
+        layout.set_text(grid);
+        let (_ink_rect, logical_rect) = layout.extents();
+        let width = logical_rect.width() / pango::SCALE;
+        let height = logical_rect.height() / pango::SCALE;
+        state.area = Area {
+            x: (video_info.width() as i32 - width) / 2,
+            y: (video_info.height() as i32 - height) / 2,
+            width,
+            height,
+        };
+
+        if settings.black_background {
             let attrs = pango::AttrList::new();
             let attr = pango::AttrColor::new_background(0, 0, 0);
             attrs.insert(attr);
             layout.set_attributes(Some(&attrs));
         }
 
-        state.left_alignment = left_alignment;
+        state.context = Some(context);
+        state.font_desc = Some(font_desc);
         state.layout = Some(layout);
 
         Ok(gst::FlowSuccess::Ok)
     }
 
-    fn overlay_text(&self, element: &super::Cea608Overlay, text: &str, state: &mut State) {
+    /// Replaces the rendered blocks, starting a roll-up animation if one
+    /// of them scrolled
+    fn set_blocks(
+        &self,
+        element: &super::Cea608Overlay,
+        state: &mut State,
+        settings: &Settings,
+        blocks: Vec<Block>,
+        pts: gst::ClockTime,
+    ) {
+        if settings.roll_up_animation {
+            let scrolled = blocks
+                .iter()
+                .zip(state.blocks.iter())
+                .position(|(new, old)| rolled_up(&old.rows, &new.rows));
+
+            if let Some(index) = scrolled {
+                gst::debug!(CAT, obj: element, "Animating roll-up of block {}", index);
+                state.roll_up = Some(RollUp {
+                    block: index,
+                    start: pts,
+                    offset: blocks[index].line_height,
+                });
+            }
+        }
+
+        if blocks.is_empty() {
+            state.roll_up = None;
+        }
+
+        state.blocks = blocks;
+        state.composition = None;
+    }
+
+    fn overlay_text(
+        &self,
+        element: &super::Cea608Overlay,
+        text: &str,
+        state: &mut State,
+        settings: &Settings,
+        pts: gst::ClockTime,
+    ) {
         let video_info = state.video_info.as_ref().unwrap();
         let layout = state.layout.as_ref().unwrap();
         layout.set_text(text);
@@ -173,103 +532,189 @@ impl Cea608Overlay {
 
         // No text actually needs rendering
         if width == 0 || height == 0 {
-            state.composition = None;
+            self.set_blocks(element, state, settings, Vec::new(), pts);
             return;
         }
 
-        let render_buffer = || -> Option<gst::Buffer> {
-            let mut buffer = gst::Buffer::with_size((width * height) as usize * 4).ok()?;
+        let buffer = match render_layout(layout, width, height, None, true) {
+            Some(buffer) => buffer,
+            None => {
+                gst::error!(CAT, obj: element, "Failed to render buffer");
+                state.clear();
+                return;
+            }
+        };
 
-            gst_video::VideoMeta::add(
-                buffer.get_mut().unwrap(),
-                gst_video::VideoFrameFlags::empty(),
-                #[cfg(target_endian = "little")]
-                gst_video::VideoFormat::Bgra,
-                #[cfg(target_endian = "big")]
-                gst_video::VideoFormat::Argb,
-                width as u32,
-                height as u32,
-            )
-            .ok()?;
-            let buffer = buffer.into_mapped_buffer_writable().unwrap();
-
-            // Pass ownership of the buffer to the cairo surface but keep around
-            // a raw pointer so we can later retrieve it again when the surface
-            // is done
-            let buffer_ptr = buffer.buffer().as_ptr();
-            let surface = cairo::ImageSurface::create_for_data(
-                buffer,
-                cairo::Format::ARgb32,
-                width,
-                height,
-                width * 4,
-            )
-            .ok()?;
+        let rows = text.lines().map(String::from).collect::<Vec<_>>();
+        let block = Block {
+            buffer,
+            x: state.area.x,
+            y: (video_info.height() as i32 - height) / 2,
+            width,
+            height,
+            line_height: height / rows.len().max(1) as i32,
+            rows,
+        };
 
-            let cr = cairo::Context::new(&surface).ok()?;
+        self.set_blocks(element, state, settings, vec![block], pts);
+    }
 
-            // Clear background
-            cr.set_operator(cairo::Operator::Source);
-            cr.set_source_rgba(0.0, 0.0, 0.0, 0.0);
-            cr.paint().ok()?;
+    /// Renders a CEA-708 window at its anchor, relative to the characters
+    /// grid
+    fn render_window(&self, state: &State, window: &Window) -> Option<Block> {
+        let video_info = state.video_info.as_ref().unwrap();
+        let context = state.context.as_ref().unwrap();
+        let area = state.area;
 
-            // Render text outline
-            cr.save().ok()?;
-            cr.set_operator(cairo::Operator::Over);
+        let (text, attrs, rows) = window_text(window);
 
-            cr.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+        let layout = pango::Layout::new(context);
+        layout.set_font_description(state.font_desc.as_ref());
+        layout.set_text(&text);
+        layout.set_attributes(Some(&attrs));
 
-            pangocairo::functions::layout_path(&cr, layout);
-            cr.stroke().ok()?;
-            cr.restore().ok()?;
+        // The window is sized in cells, but can't be smaller than its text
+        let cell_width = area.width / 32;
+        let line_height = area.height / 15;
+        let (_ink_rect, logical_rect) = layout.extents();
+        let width = (window.column_count() as i32 * cell_width)
+            .max(logical_rect.width() / pango::SCALE)
+            .min(video_info.width() as i32);
+        let height = (window.row_count() as i32 * line_height)
+            .max(logical_rect.height() / pango::SCALE)
+            .min(video_info.height() as i32);
 
-            // Render text
-            cr.save().ok()?;
-            cr.set_source_rgba(255.0, 255.0, 255.0, 1.0);
+        if width == 0 || height == 0 {
+            return None;
+        }
 
-            pangocairo::functions::show_layout(&cr, layout);
+        layout.set_width(width * pango::SCALE);
+        match window.attributes.justify {
+            Justify::Left => layout.set_alignment(pango::Alignment::Left),
+            Justify::Right => layout.set_alignment(pango::Alignment::Right),
+            Justify::Center => layout.set_alignment(pango::Alignment::Center),
+            Justify::Full => {
+                layout.set_alignment(pango::Alignment::Left);
+                layout.set_justify(true);
+            }
+        }
 
-            cr.restore().ok()?;
-            drop(cr);
+        let (r, g, b) = window.attributes.fill_color.to_rgb();
+        let fill = (r, g, b, window.attributes.fill_opacity.to_alpha());
 
-            // Safety: The surface still owns a mutable reference to the buffer but our reference
-            // to the surface here is the last one. After dropping the surface the buffer would be
-            // freed, so we keep an additional strong reference here before dropping the surface,
-            // which is then returned. As such it's guaranteed that nothing is using the buffer
-            // anymore mutably.
-            unsafe {
-                assert_eq!(
-                    cairo::ffi::cairo_surface_get_reference_count(surface.to_raw_none()),
-                    1
-                );
-                let buffer = glib::translate::from_glib_none(buffer_ptr);
-                drop(surface);
-                buffer
-            }
+        let buffer = render_layout(&layout, width, height, Some(fill), false)?;
+
+        // Anchor points 0 to 8 are the corners, middles of the edges and
+        // center of the window, in reading order
+        let anchor_point = if window.anchor_point > 8 {
+            0
+        } else {
+            window.anchor_point as i32
         };
+        let anchor_x = area.x + area.width * window.horizontal_percent() as i32 / 100;
+        let anchor_y = area.y + area.height * window.vertical_percent() as i32 / 100;
+        let x = (anchor_x - width * (anchor_point % 3) / 2)
+            .min(video_info.width() as i32 - width)
+            .max(0);
+        let y = (anchor_y - height * (anchor_point / 3) / 2)
+            .min(video_info.height() as i32 - height)
+            .max(0);
+
+        Some(Block {
+            buffer,
+            x,
+            y,
+            width,
+            height,
+            line_height: height / rows.len().max(1) as i32,
+            rows,
+        })
+    }
 
-        let buffer = match render_buffer() {
-            Some(buffer) => buffer,
-            None => {
-                gst::error!(CAT, obj: element, "Failed to render buffer");
+    fn overlay_service(
+        &self,
+        element: &super::Cea608Overlay,
+        state: &mut State,
+        settings: &Settings,
+        pts: gst::ClockTime,
+    ) {
+        let decoder = match state.cea708_decoder.as_ref() {
+            Some(decoder) => decoder,
+            None => return,
+        };
+
+        let mut blocks = Vec::new();
+        for window in decoder.service().visible_windows() {
+            if !window.has_text() && window.attributes.fill_opacity == Opacity::Transparent {
+                continue;
+            }
+
+            match self.render_window(state, window) {
+                Some(block) => blocks.push(block),
+                None => gst::warning!(CAT, obj: element, "Failed to render window"),
+            }
+        }
+
+        self.set_blocks(element, state, settings, blocks, pts);
+    }
+
+    /// Builds the composition for the frame, with the scrolling block
+    /// offset while animating
+    fn composition(
+        &self,
+        state: &mut State,
+        pts: gst::ClockTime,
+    ) -> Option<gst_video::VideoOverlayComposition> {
+        let duration = gst::ClockTime::from_mseconds(ROLL_UP_DURATION_MS);
+
+        let mut animated = None;
+        if let Some(roll_up) = &state.roll_up {
+            let elapsed = pts.saturating_sub(roll_up.start);
+            if elapsed < duration {
+                let remaining = (duration - elapsed).nseconds() as i64 * roll_up.offset as i64
+                    / duration.nseconds() as i64;
+                animated = Some((roll_up.block, remaining as i32));
+            } else {
+                state.roll_up = None;
                 state.composition = None;
-                return;
             }
-        };
+        }
 
-        let rect = gst_video::VideoOverlayRectangle::new_raw(
-            &buffer,
-            state.left_alignment,
-            (video_info.height() as i32 - height) / 2,
-            width as u32,
-            height as u32,
-            gst_video::VideoOverlayFormatFlags::PREMULTIPLIED_ALPHA,
-        );
+        if animated.is_none() && state.composition.is_some() {
+            return state.composition.clone();
+        }
 
-        state.composition = match gst_video::VideoOverlayComposition::new(Some(&rect)) {
-            Ok(composition) => Some(composition),
-            Err(_) => None,
-        };
+        let rects = state
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| {
+                let offset = match animated {
+                    Some((index, offset)) if index == i => offset,
+                    _ => 0,
+                };
+
+                gst_video::VideoOverlayRectangle::new_raw(
+                    &block.buffer,
+                    block.x,
+                    block.y + offset,
+                    block.width as u32,
+                    block.height as u32,
+                    gst_video::VideoOverlayFormatFlags::PREMULTIPLIED_ALPHA,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if rects.is_empty() {
+            return None;
+        }
+
+        let composition = gst_video::VideoOverlayComposition::new(rects.iter()).ok();
+        if animated.is_none() {
+            state.composition = composition.clone();
+        }
+
+        composition
     }
 
     fn negotiate(
@@ -327,6 +772,7 @@ impl Cea608Overlay {
         pad: &gst::Pad,
         element: &super::Cea608Overlay,
         state: &mut State,
+        settings: &Settings,
         data: &[u8],
         pts: gst::ClockTime,
     ) {
@@ -334,6 +780,15 @@ impl Cea608Overlay {
             gst::warning!(CAT, "cc_data length is not a multiple of 3, truncating");
         }
 
+        if let Some(decoder) = state.cea708_decoder.as_mut() {
+            if decoder.decode(data) == cea708::Status::Changed {
+                self.overlay_service(element, state, settings, pts);
+            }
+            self.reset_timeout(state, pts);
+
+            return;
+        }
+
         for triple in data.chunks_exact(3) {
             let cc_valid = (triple[0] & 0x04) == 0x04;
             let cc_type = triple[0] & 0x03;
@@ -368,10 +823,10 @@ impl Cea608Overlay {
                                     }
                                 };
 
-                                self.overlay_text(element, &text, state);
+                                self.overlay_text(element, &text, state, settings, pts);
                             }
                             Ok(Status::Clear) => {
-                                self.overlay_text(element, "", state);
+                                self.overlay_text(element, "", state, settings, pts);
                             }
                             Ok(Status::Ok) => (),
                             Err(err) => {
@@ -398,6 +853,7 @@ impl Cea608Overlay {
         pad: &gst::Pad,
         element: &super::Cea608Overlay,
         state: &mut State,
+        settings: &Settings,
         data: &[u8],
         pts: gst::ClockTime,
     ) {
@@ -430,7 +886,7 @@ impl Cea608Overlay {
                         }
                     };
 
-                    self.overlay_text(element, &text, state);
+                    self.overlay_text(element, &text, state, settings, pts);
                 }
 
                 self.reset_timeout(state, pts);
@@ -455,6 +911,7 @@ impl Cea608Overlay {
             gst::FlowError::Error
         })?;
 
+        let settings = self.settings.lock().unwrap().clone();
         let mut state = self.state.lock().unwrap();
 
         if self.srcpad.check_reconfigure() {
@@ -462,14 +919,14 @@ impl Cea608Overlay {
         }
 
         if state.layout.is_none() {
-            self.recalculate_layout(element, &mut state)?;
+            self.recalculate_layout(element, &mut state, &settings)?;
         }
 
         for meta in buffer.iter_meta::<gst_video::VideoCaptionMeta>() {
             if meta.caption_type() == gst_video::VideoCaptionType::Cea708Cdp {
                 match extract_cdp(meta.data()) {
                     Ok(data) => {
-                        self.decode_cc_data(pad, element, &mut state, data, pts);
+                        self.decode_cc_data(pad, element, &mut state, &settings, data, pts);
                     }
                     Err(e) => {
                         gst::warning!(CAT, "{}", &e.to_string());
@@ -477,9 +934,12 @@ impl Cea608Overlay {
                     }
                 }
             } else if meta.caption_type() == gst_video::VideoCaptionType::Cea708Raw {
-                self.decode_cc_data(pad, element, &mut state, meta.data(), pts);
+                self.decode_cc_data(pad, element, &mut state, &settings, meta.data(), pts);
+            } else if state.cea708_decoder.is_some() {
+                // CEA-608 only meta doesn't contain the selected service
+                continue;
             } else if meta.caption_type() == gst_video::VideoCaptionType::Cea608S3341a {
-                self.decode_s334_1a(pad, element, &mut state, meta.data(), pts);
+                self.decode_s334_1a(pad, element, &mut state, &settings, meta.data(), pts);
             } else if meta.caption_type() == gst_video::VideoCaptionType::Cea608Raw {
                 let data = meta.data();
                 assert!(data.len() % 2 == 0);
@@ -500,7 +960,7 @@ impl Cea608Overlay {
                             }
                         };
 
-                        self.overlay_text(element, &text, &mut state);
+                        self.overlay_text(element, &text, &mut state, &settings, pts);
                     }
 
                     self.reset_timeout(&mut state, pts);
@@ -508,20 +968,20 @@ impl Cea608Overlay {
             }
         }
 
-        if let Some(timeout) = self.settings.lock().unwrap().timeout {
+        if let Some(timeout) = settings.timeout {
             if let Some(interval) = pts.opt_saturating_sub(state.last_cc_pts) {
                 if interval > timeout {
                     gst::info!(CAT, obj: element, "Reached timeout, clearing overlay");
-                    state.composition.take();
+                    state.clear();
                     state.last_cc_pts.take();
                 }
             }
         }
 
-        if let Some(composition) = &state.composition {
+        if let Some(composition) = self.composition(&mut state, pts) {
             let buffer = buffer.make_mut();
             if state.attach {
-                gst_video::VideoOverlayCompositionMeta::add(buffer, composition);
+                gst_video::VideoOverlayCompositionMeta::add(buffer, &composition);
             } else {
                 let mut frame = gst_video::VideoFrameRef::from_buffer_ref_writable(
                     buffer,
@@ -564,7 +1024,10 @@ impl Cea608Overlay {
             EventView::FlushStop(..) => {
                 let mut state = self.state.lock().unwrap();
                 state.caption_frame = CaptionFrame::default();
-                state.composition = None;
+                if let Some(decoder) = state.cea708_decoder.as_mut() {
+                    decoder.reset();
+                }
+                state.clear();
                 pad.event_default(Some(element), event)
             }
             _ => pad.event_default(Some(element), event),
@@ -639,6 +1102,40 @@ impl ObjectImpl for Cea608Overlay {
                     .default_value(u64::MAX)
                     .mutable_playing()
                     .build(),
+                glib::ParamSpecInt::builder("service")
+                    .nick("Service")
+                    .blurb("The CEA-708 service to render the windows of, (0=render CEA-608 instead)")
+                    .minimum(0)
+                    .maximum(cea708::MAX_SERVICE_NUMBER as i32)
+                    .default_value(DEFAULT_SERVICE)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecString::builder("font-desc")
+                    .nick("Font description")
+                    .blurb("Pango font description of the font to use, the size is computed to fit the video when not specified")
+                    .default_value(Some(DEFAULT_FONT_DESC))
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("horizontal-margin")
+                    .nick("Horizontal margin")
+                    .blurb("Margin on the left and on the right of the captions, in percent of the video width")
+                    .maximum(40)
+                    .default_value(DEFAULT_MARGIN)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("vertical-margin")
+                    .nick("Vertical margin")
+                    .blurb("Margin above and below the captions, in percent of the video height")
+                    .maximum(40)
+                    .default_value(DEFAULT_MARGIN)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("roll-up-animation")
+                    .nick("Roll-up animation")
+                    .blurb("Whether to animate the scrolling of roll-up captions")
+                    .default_value(DEFAULT_ROLL_UP_ANIMATION)
+                    .mutable_playing()
+                    .build(),
             ]
         });
 
@@ -680,6 +1177,46 @@ impl ObjectImpl for Cea608Overlay {
                     _ => Some(gst::ClockTime::from_nseconds(timeout)),
                 };
             }
+            "service" => {
+                let mut settings = self.settings.lock().unwrap();
+                let mut state = self.state.lock().unwrap();
+
+                settings.service = value.get().expect("type checked upstream");
+                state.cea708_decoder = match settings.service {
+                    0 => None,
+                    service => Some(cea708::Decoder::new(service as u8)),
+                };
+                state.clear();
+            }
+            "font-desc" => {
+                let mut settings = self.settings.lock().unwrap();
+                let mut state = self.state.lock().unwrap();
+
+                settings.font_desc = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_else(|| String::from(DEFAULT_FONT_DESC));
+                let _ = state.layout.take();
+            }
+            "horizontal-margin" => {
+                let mut settings = self.settings.lock().unwrap();
+                let mut state = self.state.lock().unwrap();
+
+                settings.horizontal_margin = value.get().expect("type checked upstream");
+                let _ = state.layout.take();
+            }
+            "vertical-margin" => {
+                let mut settings = self.settings.lock().unwrap();
+                let mut state = self.state.lock().unwrap();
+
+                settings.vertical_margin = value.get().expect("type checked upstream");
+                let _ = state.layout.take();
+            }
+            "roll-up-animation" => {
+                let mut settings = self.settings.lock().unwrap();
+
+                settings.roll_up_animation = value.get().expect("type checked upstream");
+            }
             _ => unimplemented!(),
         }
     }
@@ -702,6 +1239,26 @@ impl ObjectImpl for Cea608Overlay {
                     u64::MAX.to_value()
                 }
             }
+            "service" => {
+                let settings = self.settings.lock().unwrap();
+                settings.service.to_value()
+            }
+            "font-desc" => {
+                let settings = self.settings.lock().unwrap();
+                settings.font_desc.to_value()
+            }
+            "horizontal-margin" => {
+                let settings = self.settings.lock().unwrap();
+                settings.horizontal_margin.to_value()
+            }
+            "vertical-margin" => {
+                let settings = self.settings.lock().unwrap();
+                settings.vertical_margin.to_value()
+            }
+            "roll-up-animation" => {
+                let settings = self.settings.lock().unwrap();
+                settings.roll_up_animation.to_value()
+            }
             _ => unimplemented!(),
         }
     }
@@ -721,7 +1278,7 @@ impl ElementImpl for Cea608Overlay {
             gst::subclass::ElementMetadata::new(
                 "Cea 608 overlay",
                 "Video/Overlay/Subtitle",
-                "Renders CEA 608 closed caption meta over raw video frames",
+                "Renders CEA 608 or CEA 708 closed caption meta over raw video frames",
                 "Mathieu Duponchelle <mathieu@centricular.com>",
             )
         });
@@ -768,13 +1325,9 @@ impl ElementImpl for Cea608Overlay {
         match transition {
             gst::StateChange::ReadyToPaused | gst::StateChange::PausedToReady => {
                 // Reset the whole state
-                let mut state = self.state.lock().unwrap();
-                *state = State::default();
                 let settings = self.settings.lock().unwrap();
-                state.selected_field = match settings.field {
-                    -1 => None,
-                    val => Some(val as u8),
-                };
+                let mut state = self.state.lock().unwrap();
+                *state = State::new(&settings);
             }
             _ => (),
         }
@@ -782,3 +1335,107 @@ impl ElementImpl for Cea608Overlay {
         self.parent_change_state(element, transition)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    /// A visible window with 2 rows of 32 columns, after decoding `data`
+    fn window(window_style: u8, data: &[u8]) -> Window {
+        let mut service = cea708::Service::default();
+
+        // DF0: visible, 2 rows, 32 columns, pen style 1
+        let styles = (window_style << 3) | 0x01;
+        service.decode(&[0x98, 0x20, 0x00, 0x00, 0x01, 0x1f, styles]);
+        service.decode(data);
+
+        service.visible_windows()[0].clone()
+    }
+
+    /// The ranges of the attributes of a type
+    fn attr_ranges(attrs: &pango::AttrList, type_: pango::AttrType) -> Vec<(u32, u32)> {
+        let mut ranges = Vec::new();
+        attrs.filter(|attr| {
+            if attr.type_() == type_ {
+                ranges.push((attr.start_index(), attr.end_index()));
+            }
+            false
+        });
+        ranges
+    }
+
+    #[test]
+    fn test_rolled_up() {
+        assert!(rolled_up(&rows(&["one", "two"]), &rows(&["two", "three"])));
+        assert!(rolled_up(
+            &rows(&["", "one", "two"]),
+            &rows(&["one", "two", "three"])
+        ));
+
+        // Unchanged or replaced rows
+        assert!(!rolled_up(&rows(&["one", "two"]), &rows(&["one", "two"])));
+        assert!(!rolled_up(
+            &rows(&["one", "two"]),
+            &rows(&["three", "four"])
+        ));
+        // A single row can't scroll
+        assert!(!rolled_up(&rows(&["one"]), &rows(&["two"])));
+        // Nothing scrolled into view when only blank rows move up
+        assert!(!rolled_up(&rows(&["one", " "]), &rows(&[" ", "two"])));
+        // The window was resized
+        assert!(!rolled_up(&rows(&["one", "two"]), &rows(&["two"])));
+    }
+
+    #[test]
+    fn test_window_text_left() {
+        // Style 1: left justified
+        let (text, _, rows) = window(1, b"  Hello\x0dWorld");
+
+        assert_eq!(text, "  Hello\nWorld");
+        assert_eq!(rows, vec!["  Hello", "World"]);
+    }
+
+    #[test]
+    fn test_window_text_center() {
+        // Style 3: centered, the leading spaces are left to pango
+        let (text, _, rows) = window(3, b"  Hello\x0d   World  ");
+
+        assert_eq!(text, "Hello\nWorld");
+        assert_eq!(rows, vec!["Hello", "World"]);
+    }
+
+    #[test]
+    fn test_window_text_empty_row() {
+        let (text, attrs, rows) = window(1, b"Hello");
+
+        assert_eq!(text, "Hello\n");
+        assert_eq!(rows, vec!["Hello", ""]);
+        assert_eq!(
+            attr_ranges(&attrs, pango::AttrType::Foreground),
+            vec![(0, 5)]
+        );
+    }
+
+    #[test]
+    fn test_window_text_attributes() {
+        // SPA: italics, "red", SPA: regular, " ok"
+        let (text, attrs, _) = window(
+            1,
+            &[
+                0x90, 0x05, 0x80, b'r', b'e', b'd', 0x90, 0x05, 0x00, b' ', b'o', b'k',
+            ],
+        );
+
+        assert_eq!(text, "red ok\n");
+        assert_eq!(attr_ranges(&attrs, pango::AttrType::Style), vec![(0, 3)]);
+        // One run of cells per pen
+        assert_eq!(
+            attr_ranges(&attrs, pango::AttrType::Foreground),
+            vec![(0, 3), (3, 6)]
+        );
+    }
+}
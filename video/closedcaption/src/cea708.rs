@@ -0,0 +1,1650 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! CEA-708 (DTVCC) decoding.
+//!
+//! `cc_data` triplets are assembled into DTVCC packets by [`PacketParser`],
+//! which are then split into service blocks. The service blocks of one
+//! caption service are interpreted by a [`Service`], which maintains the
+//! state of the eight windows of the service as well as their pen state.
+//!
+//! In the other direction, the commands of a caption service are built by a
+//! [`ServiceWriter`] and packed into DTVCC packets and `cc_data` triplets by
+//! a [`PacketWriter`].
+
+use crate::ttutils::{Chunk, Line, TextStyle};
+use once_cell::sync::Lazy;
+
+use std::collections::VecDeque;
+
+pub const MAX_WINDOWS: usize = 8;
+pub const MAX_SERVICE_NUMBER: u8 = 63;
+pub const MAX_ROWS: u8 = 15;
+pub const MAX_COLUMNS: u8 = 42;
+
+/// A 2 bits per component color, as used by the pen and window attributes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color { r: 0, g: 0, b: 0 };
+    pub const WHITE: Color = Color { r: 2, g: 2, b: 2 };
+
+    fn from_bits(bits: u8) -> Self {
+        Color {
+            r: (bits >> 4) & 0x03,
+            g: (bits >> 2) & 0x03,
+            b: bits & 0x03,
+        }
+    }
+
+    fn to_bits(self) -> u8 {
+        ((self.r & 0x03) << 4) | ((self.g & 0x03) << 2) | (self.b & 0x03)
+    }
+
+    /// Returns the color and italics matching a CEA-608 text style.
+    pub fn from_text_style(style: TextStyle) -> (Self, bool) {
+        let (r, g, b) = match style {
+            TextStyle::Green => (0, 2, 0),
+            TextStyle::Blue => (0, 0, 2),
+            TextStyle::Cyan => (0, 2, 2),
+            TextStyle::Red => (2, 0, 0),
+            TextStyle::Yellow => (2, 2, 0),
+            TextStyle::Magenta => (2, 0, 2),
+            TextStyle::White | TextStyle::ItalicWhite => (2, 2, 2),
+        };
+
+        (Color { r, g, b }, style == TextStyle::ItalicWhite)
+    }
+
+    /// Returns the red, green and blue components, between 0 and 1.
+    pub fn to_rgb(self) -> (f64, f64, f64) {
+        let level = |c: u8| (c & 0x03) as f64 / 3.0;
+
+        (level(self.r), level(self.g), level(self.b))
+    }
+
+    /// Returns the closest CEA-608 text style.
+    pub fn to_text_style(self, italics: bool) -> TextStyle {
+        let style = match (self.r >= 2, self.g >= 2, self.b >= 2) {
+            (false, true, false) => TextStyle::Green,
+            (false, false, true) => TextStyle::Blue,
+            (false, true, true) => TextStyle::Cyan,
+            (true, false, false) => TextStyle::Red,
+            (true, true, false) => TextStyle::Yellow,
+            (true, false, true) => TextStyle::Magenta,
+            _ => TextStyle::White,
+        };
+
+        if italics && style == TextStyle::White {
+            TextStyle::ItalicWhite
+        } else {
+            style
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Opacity {
+    Solid,
+    Flash,
+    Translucent,
+    Transparent,
+}
+
+impl Opacity {
+    fn from_bits(bits: u8) -> Self {
+        match bits & 0x03 {
+            0 => Opacity::Solid,
+            1 => Opacity::Flash,
+            2 => Opacity::Translucent,
+            _ => Opacity::Transparent,
+        }
+    }
+
+    /// Returns the alpha value, between 0 and 1. Flashing is rendered solid.
+    pub fn to_alpha(self) -> f64 {
+        match self {
+            Opacity::Solid | Opacity::Flash => 1.0,
+            Opacity::Translucent => 0.5,
+            Opacity::Transparent => 0.0,
+        }
+    }
+
+    fn to_bits(self) -> u8 {
+        match self {
+            Opacity::Solid => 0,
+            Opacity::Flash => 1,
+            Opacity::Translucent => 2,
+            Opacity::Transparent => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Justify {
+    Left,
+    Right,
+    Center,
+    Full,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft,
+    TopToBottom,
+    BottomToTop,
+}
+
+impl Direction {
+    fn from_bits(bits: u8) -> Self {
+        match bits & 0x03 {
+            0 => Direction::LeftToRight,
+            1 => Direction::RightToLeft,
+            2 => Direction::TopToBottom,
+            _ => Direction::BottomToTop,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisplayEffect {
+    Snap,
+    Fade,
+    Wipe,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PenSize {
+    Small,
+    Standard,
+    Large,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PenOffset {
+    Subscript,
+    Normal,
+    Superscript,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowAttributes {
+    pub justify: Justify,
+    pub print_direction: Direction,
+    pub scroll_direction: Direction,
+    pub word_wrap: bool,
+    pub display_effect: DisplayEffect,
+    pub effect_direction: Direction,
+    pub effect_speed: u8,
+    pub fill_color: Color,
+    pub fill_opacity: Opacity,
+    pub border_type: u8,
+    pub border_color: Color,
+}
+
+impl WindowAttributes {
+    /// Predefined window styles, CEA-708-E 8.4.10
+    fn predefined(style_id: u8) -> Self {
+        let (justify, print_direction, scroll_direction, word_wrap, fill_opacity) = match style_id {
+            2 => (
+                Justify::Left,
+                Direction::LeftToRight,
+                Direction::BottomToTop,
+                false,
+                Opacity::Transparent,
+            ),
+            3 => (
+                Justify::Center,
+                Direction::LeftToRight,
+                Direction::BottomToTop,
+                false,
+                Opacity::Solid,
+            ),
+            4 => (
+                Justify::Left,
+                Direction::LeftToRight,
+                Direction::BottomToTop,
+                true,
+                Opacity::Solid,
+            ),
+            5 => (
+                Justify::Left,
+                Direction::LeftToRight,
+                Direction::BottomToTop,
+                true,
+                Opacity::Transparent,
+            ),
+            6 => (
+                Justify::Center,
+                Direction::LeftToRight,
+                Direction::BottomToTop,
+                true,
+                Opacity::Solid,
+            ),
+            7 => (
+                Justify::Left,
+                Direction::TopToBottom,
+                Direction::RightToLeft,
+                false,
+                Opacity::Solid,
+            ),
+            _ => (
+                Justify::Left,
+                Direction::LeftToRight,
+                Direction::BottomToTop,
+                false,
+                Opacity::Solid,
+            ),
+        };
+
+        WindowAttributes {
+            justify,
+            print_direction,
+            scroll_direction,
+            word_wrap,
+            display_effect: DisplayEffect::Snap,
+            effect_direction: Direction::LeftToRight,
+            effect_speed: 0,
+            fill_color: Color::BLACK,
+            fill_opacity,
+            border_type: 0,
+            border_color: Color::BLACK,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PenAttributes {
+    pub size: PenSize,
+    pub font_style: u8,
+    pub text_tag: u8,
+    pub offset: PenOffset,
+    pub italics: bool,
+    pub underline: bool,
+    pub edge_type: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PenColor {
+    pub foreground_color: Color,
+    pub foreground_opacity: Opacity,
+    pub background_color: Color,
+    pub background_opacity: Opacity,
+    pub edge_color: Color,
+}
+
+/// Predefined pen styles, CEA-708-E 8.4.11
+fn predefined_pen_style(style_id: u8) -> (PenAttributes, PenColor) {
+    let font_style = match style_id {
+        2 => 1,
+        3 => 2,
+        4 => 3,
+        5 => 4,
+        6 => 3,
+        7 => 4,
+        _ => 0,
+    };
+    let (edge_type, background_opacity) = match style_id {
+        6 => (3, Opacity::Transparent),
+        7 => (3, Opacity::Transparent),
+        _ => (0, Opacity::Solid),
+    };
+
+    (
+        PenAttributes {
+            size: PenSize::Standard,
+            font_style,
+            text_tag: 0,
+            offset: PenOffset::Normal,
+            italics: false,
+            underline: false,
+            edge_type,
+        },
+        PenColor {
+            foreground_color: Color::WHITE,
+            foreground_opacity: Opacity::Solid,
+            background_color: Color::BLACK,
+            background_opacity,
+            edge_color: Color::BLACK,
+        },
+    )
+}
+
+impl Default for PenAttributes {
+    fn default() -> Self {
+        predefined_pen_style(1).0
+    }
+}
+
+impl Default for PenColor {
+    fn default() -> Self {
+        predefined_pen_style(1).1
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub character: char,
+    pub attributes: PenAttributes,
+    pub color: PenColor,
+}
+
+#[derive(Clone, Debug)]
+pub struct Window {
+    pub defined: bool,
+    pub visible: bool,
+    pub priority: u8,
+    pub relative_positioning: bool,
+    pub anchor_vertical: u8,
+    pub anchor_horizontal: u8,
+    pub anchor_point: u8,
+    pub row_lock: bool,
+    pub column_lock: bool,
+    pub attributes: WindowAttributes,
+    pub pen_attributes: PenAttributes,
+    pub pen_color: PenColor,
+    pub pen_row: usize,
+    pub pen_column: usize,
+    pub rows: Vec<Vec<Option<Cell>>>,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        let (pen_attributes, pen_color) = predefined_pen_style(1);
+
+        Window {
+            defined: false,
+            visible: false,
+            priority: 0,
+            relative_positioning: false,
+            anchor_vertical: 0,
+            anchor_horizontal: 0,
+            anchor_point: 0,
+            row_lock: false,
+            column_lock: false,
+            attributes: WindowAttributes::predefined(1),
+            pen_attributes,
+            pen_color,
+            pen_row: 0,
+            pen_column: 0,
+            rows: Vec::new(),
+        }
+    }
+}
+
+impl Window {
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    /// Returns the vertical position of the anchor, in percent of the safe area.
+    pub fn vertical_percent(&self) -> u32 {
+        if self.relative_positioning {
+            (self.anchor_vertical as u32).min(99)
+        } else {
+            (self.anchor_vertical as u32).min(74) * 100 / 75
+        }
+    }
+
+    /// Returns the horizontal position of the anchor, in percent of the safe area.
+    pub fn horizontal_percent(&self) -> u32 {
+        if self.relative_positioning {
+            (self.anchor_horizontal as u32).min(99)
+        } else {
+            (self.anchor_horizontal as u32).min(209) * 100 / 210
+        }
+    }
+
+    /// Whether the window has any non-space characters.
+    pub fn has_text(&self) -> bool {
+        self.rows
+            .iter()
+            .flatten()
+            .flatten()
+            .any(|cell| !cell.character.is_whitespace())
+    }
+
+    fn resize(&mut self, rows: usize, columns: usize) {
+        self.rows.resize_with(rows, Vec::new);
+        for row in self.rows.iter_mut() {
+            row.resize(columns, None);
+        }
+
+        self.pen_row = self.pen_row.min(rows.saturating_sub(1));
+        self.pen_column = self.pen_column.min(columns.saturating_sub(1));
+    }
+
+    fn clear(&mut self) {
+        for cell in self.rows.iter_mut().flatten() {
+            *cell = None;
+        }
+    }
+
+    fn put_char(&mut self, c: char) {
+        let cell = Cell {
+            character: c,
+            attributes: self.pen_attributes,
+            color: self.pen_color,
+        };
+
+        if self.rows.is_empty() {
+            return;
+        }
+
+        let columns = self.column_count();
+        match self.attributes.print_direction {
+            Direction::LeftToRight | Direction::RightToLeft => {
+                if self.pen_column >= columns {
+                    if !self.attributes.word_wrap {
+                        // No room left on the row, CEA-708-E 7.1.4
+                        return;
+                    }
+                    self.carriage_return();
+                }
+            }
+            Direction::TopToBottom | Direction::BottomToTop => {
+                self.pen_row = self.pen_row.min(self.row_count() - 1);
+            }
+        }
+
+        let column = match self.attributes.print_direction {
+            Direction::RightToLeft => columns - 1 - self.pen_column,
+            _ => self.pen_column,
+        };
+        if let Some(slot) = self
+            .rows
+            .get_mut(self.pen_row)
+            .and_then(|row| row.get_mut(column))
+        {
+            *slot = Some(cell);
+        }
+
+        self.pen_column += 1;
+    }
+
+    fn backspace(&mut self) {
+        if self.pen_column == 0 || self.rows.is_empty() {
+            return;
+        }
+
+        self.pen_column -= 1;
+        let columns = self.column_count();
+        let column = match self.attributes.print_direction {
+            Direction::RightToLeft => columns - 1 - self.pen_column,
+            _ => self.pen_column,
+        };
+        if let Some(slot) = self
+            .rows
+            .get_mut(self.pen_row)
+            .and_then(|row| row.get_mut(column))
+        {
+            *slot = None;
+        }
+    }
+
+    fn carriage_return(&mut self) {
+        if self.rows.is_empty() {
+            return;
+        }
+
+        self.pen_column = 0;
+        if self.pen_row + 1 < self.row_count() {
+            self.pen_row += 1;
+        } else {
+            // Scroll the content up by one row
+            let columns = self.column_count();
+            self.rows.remove(0);
+            self.rows.push(vec![None; columns]);
+        }
+    }
+
+    fn horizontal_carriage_return(&mut self) {
+        if let Some(row) = self.rows.get_mut(self.pen_row) {
+            for cell in row.iter_mut() {
+                *cell = None;
+            }
+        }
+        self.pen_column = 0;
+    }
+
+    fn form_feed(&mut self) {
+        self.clear();
+        self.pen_row = 0;
+        self.pen_column = 0;
+    }
+
+    /// Returns the trimmed text of each row, leading and trailing blank rows excluded.
+    pub fn text_rows(&self) -> Vec<String> {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let text = row
+                    .iter()
+                    .map(|cell| cell.map_or(' ', |cell| cell.character))
+                    .collect::<String>();
+                text.trim().to_string()
+            })
+            .collect::<Vec<_>>();
+
+        let first = rows.iter().position(|row| !row.is_empty());
+        let last = rows.iter().rposition(|row| !row.is_empty());
+        match (first, last) {
+            (Some(first), Some(last)) => rows[first..=last].to_vec(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Nothing changed on screen
+    Ok,
+    /// The content of the visible windows changed
+    Changed,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "cea708decoder",
+        gst::DebugColorFlags::empty(),
+        Some("CEA-708 Decoder"),
+    )
+});
+
+/// The state of one caption service.
+#[derive(Debug, Default)]
+pub struct Service {
+    windows: [Window; MAX_WINDOWS],
+    current_window: Option<usize>,
+    // Kept across resets so the missing delay support is only reported once
+    warned_delay: bool,
+}
+
+impl Service {
+    /// Returns the visible windows, in display order: by priority then position.
+    pub fn visible_windows(&self) -> Vec<&Window> {
+        let mut windows = self
+            .windows
+            .iter()
+            .filter(|window| window.defined && window.visible)
+            .collect::<Vec<_>>();
+
+        windows.sort_by_key(|window| {
+            (
+                window.vertical_percent(),
+                window.horizontal_percent(),
+                window.priority,
+            )
+        });
+
+        windows
+    }
+
+    /// Returns the text of the visible windows, one line per row.
+    pub fn to_text(&self) -> String {
+        self.visible_windows()
+            .iter()
+            .flat_map(|window| window.text_rows())
+            .collect::<Vec<_>>()
+            .join("\r\n")
+    }
+
+    /// Returns the content of the visible windows as 608-like lines,
+    /// placing the windows on a 15 rows by 32 columns grid.
+    pub fn to_lines(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        for window in self.visible_windows() {
+            let base_row = window.vertical_percent() * 15 / 100;
+            let base_column = window.horizontal_percent() * 32 / 100;
+
+            for (idx, row) in window.rows.iter().enumerate() {
+                let mut chunks: Vec<Chunk> = Vec::new();
+                let mut indent = None;
+                let mut pending_spaces = 0;
+
+                for (column, cell) in row.iter().enumerate() {
+                    let cell = match cell {
+                        Some(cell) if !cell.character.is_whitespace() => cell,
+                        _ => {
+                            if indent.is_some() {
+                                pending_spaces += 1;
+                            }
+                            continue;
+                        }
+                    };
+
+                    if indent.is_none() {
+                        indent = Some(column as u32);
+                    }
+
+                    let style = cell
+                        .color
+                        .foreground_color
+                        .to_text_style(cell.attributes.italics);
+                    let underline = cell.attributes.underline;
+
+                    match chunks.last_mut() {
+                        Some(chunk) if chunk.style == style && chunk.underline == underline => {
+                            chunk.text.push_str(&" ".repeat(pending_spaces));
+                            chunk.text.push(cell.character);
+                        }
+                        _ => {
+                            let mut text = " ".repeat(pending_spaces);
+                            text.push(cell.character);
+                            chunks.push(Chunk {
+                                style,
+                                underline,
+                                text,
+                            });
+                        }
+                    }
+                    pending_spaces = 0;
+                }
+
+                if let Some(indent) = indent {
+                    lines.push(Line {
+                        column: Some((base_column + indent).min(31)),
+                        row: Some((base_row + idx as u32).min(14)),
+                        chunks,
+                        carriage_return: None,
+                    });
+                }
+            }
+        }
+
+        lines
+    }
+
+    pub fn reset(&mut self) {
+        *self = Service {
+            warned_delay: self.warned_delay,
+            ..Service::default()
+        };
+    }
+
+    fn current_window(&mut self) -> Option<&mut Window> {
+        self.current_window.map(move |idx| &mut self.windows[idx])
+    }
+
+    fn is_visible_window(&self, idx: usize) -> bool {
+        self.windows[idx].defined && self.windows[idx].visible
+    }
+
+    /// Decodes one service block, returning whether the visible
+    /// windows changed.
+    pub fn decode(&mut self, mut data: &[u8]) -> Status {
+        let mut changed = false;
+
+        while let Some((&code, rest)) = data.split_first() {
+            data = rest;
+
+            match code {
+                // C0
+                0x00..=0x1f => {
+                    let len = match code {
+                        0x10..=0x17 => 1,
+                        0x18..=0x1f => 2,
+                        _ => 0,
+                    };
+                    if data.len() < len {
+                        break;
+                    }
+                    let (params, rest) = data.split_at(len);
+                    data = rest;
+
+                    if code == 0x10 {
+                        let ext = params[0];
+                        match ext {
+                            // C2
+                            0x00..=0x1f => {
+                                let skip = (ext as usize >> 3).min(data.len());
+                                data = &data[skip..];
+                            }
+                            // G2
+                            0x20..=0x7f => {
+                                if let Some(c) = g2_to_char(ext) {
+                                    changed |= self.put_char(c);
+                                }
+                            }
+                            // C3
+                            0x80..=0x8f => {
+                                let skip = (if ext < 0x88 { 4 } else { 5 }).min(data.len());
+                                data = &data[skip..];
+                            }
+                            0x90..=0x9f => {
+                                // Variable length commands, their length is in
+                                // the header byte
+                                let skip = data
+                                    .first()
+                                    .map_or(0, |header| 1 + (*header as usize & 0x1f))
+                                    .min(data.len());
+                                data = &data[skip..];
+                            }
+                            // G3, only the CC icon is defined
+                            0xa0..=0xff => {
+                                if ext == 0xa0 {
+                                    changed |= self.put_char('㏄');
+                                }
+                            }
+                        }
+                    } else if code == 0x18 {
+                        let c = u16::from_be_bytes([params[0], params[1]]);
+                        if let Some(c) = char::from_u32(c as u32) {
+                            changed |= self.put_char(c);
+                        }
+                    } else {
+                        changed |= self.decode_c0(code);
+                    }
+                }
+                // G0
+                0x20..=0x7f => {
+                    let c = if code == 0x7f { '♪' } else { code as char };
+                    changed |= self.put_char(c);
+                }
+                // C1
+                0x80..=0x9f => {
+                    let len = c1_params_len(code);
+                    if data.len() < len {
+                        break;
+                    }
+                    let (params, rest) = data.split_at(len);
+                    data = rest;
+
+                    changed |= self.decode_c1(code, params);
+                }
+                // G1, ISO 8859-1
+                0xa0..=0xff => {
+                    changed |= self.put_char(code as char);
+                }
+            }
+        }
+
+        if changed {
+            Status::Changed
+        } else {
+            Status::Ok
+        }
+    }
+
+    fn put_char(&mut self, c: char) -> bool {
+        let idx = match self.current_window {
+            Some(idx) => idx,
+            None => return false,
+        };
+
+        self.windows[idx].put_char(c);
+        self.is_visible_window(idx)
+    }
+
+    fn decode_c0(&mut self, code: u8) -> bool {
+        let idx = match self.current_window {
+            Some(idx) => idx,
+            None => return false,
+        };
+
+        let window = &mut self.windows[idx];
+        match code {
+            // BS
+            0x08 => window.backspace(),
+            // FF
+            0x0c => window.form_feed(),
+            // CR
+            0x0d => window.carriage_return(),
+            // HCR
+            0x0e => window.horizontal_carriage_return(),
+            // NUL, ETX and the reserved codes
+            _ => return false,
+        }
+
+        self.is_visible_window(idx)
+    }
+
+    fn decode_c1(&mut self, code: u8, params: &[u8]) -> bool {
+        match code {
+            // CW0-CW7
+            0x80..=0x87 => {
+                let idx = (code & 0x07) as usize;
+                if self.windows[idx].defined {
+                    self.current_window = Some(idx);
+                }
+                false
+            }
+            // CLW
+            0x88 => self.for_windows(params[0], |window| {
+                window.clear();
+                window.visible
+            }),
+            // DSW
+            0x89 => self.for_windows(params[0], |window| {
+                let changed = !window.visible && window.has_text();
+                window.visible = true;
+                changed
+            }),
+            // HDW
+            0x8a => self.for_windows(params[0], |window| {
+                let changed = window.visible && window.has_text();
+                window.visible = false;
+                changed
+            }),
+            // TGW
+            0x8b => self.for_windows(params[0], |window| {
+                window.visible = !window.visible;
+                window.has_text()
+            }),
+            // DLW
+            0x8c => {
+                let mut changed = false;
+                for idx in 0..MAX_WINDOWS {
+                    if params[0] & (1 << idx) != 0 {
+                        changed |= self.is_visible_window(idx) && self.windows[idx].has_text();
+                        self.windows[idx] = Window::default();
+                        if self.current_window == Some(idx) {
+                            self.current_window = None;
+                        }
+                    }
+                }
+                changed
+            }
+            // DLY, DLC
+            //
+            // Delays are not supported, the commands following a DLY are
+            // applied right away.
+            0x8d | 0x8e => {
+                if !self.warned_delay {
+                    gst::fixme!(CAT, "DLY/DLC not supported, ignoring delay");
+                    self.warned_delay = true;
+                }
+                false
+            }
+            // RST
+            0x8f => {
+                let changed = self
+                    .windows
+                    .iter()
+                    .any(|window| window.defined && window.visible && window.has_text());
+                self.reset();
+                changed
+            }
+            // SPA
+            0x90 => {
+                if let Some(window) = self.current_window() {
+                    let attributes = &mut window.pen_attributes;
+                    attributes.text_tag = params[0] >> 4;
+                    attributes.offset = match (params[0] >> 2) & 0x03 {
+                        0 => PenOffset::Subscript,
+                        2 => PenOffset::Superscript,
+                        _ => PenOffset::Normal,
+                    };
+                    attributes.size = match params[0] & 0x03 {
+                        0 => PenSize::Small,
+                        2 => PenSize::Large,
+                        _ => PenSize::Standard,
+                    };
+                    attributes.italics = params[1] & 0x80 != 0;
+                    attributes.underline = params[1] & 0x40 != 0;
+                    attributes.edge_type = (params[1] >> 3) & 0x07;
+                    attributes.font_style = params[1] & 0x07;
+                }
+                false
+            }
+            // SPC
+            0x91 => {
+                if let Some(window) = self.current_window() {
+                    let color = &mut window.pen_color;
+                    color.foreground_opacity = Opacity::from_bits(params[0] >> 6);
+                    color.foreground_color = Color::from_bits(params[0]);
+                    color.background_opacity = Opacity::from_bits(params[1] >> 6);
+                    color.background_color = Color::from_bits(params[1]);
+                    color.edge_color = Color::from_bits(params[2]);
+                }
+                false
+            }
+            // SPL
+            0x92 => {
+                if let Some(window) = self.current_window() {
+                    let rows = window.row_count();
+                    let columns = window.column_count();
+                    window.pen_row = ((params[0] & 0x0f) as usize).min(rows.saturating_sub(1));
+                    window.pen_column =
+                        ((params[1] & 0x3f) as usize).min(columns.saturating_sub(1));
+                }
+                false
+            }
+            // SWA
+            0x97 => {
+                let idx = match self.current_window {
+                    Some(idx) => idx,
+                    None => return false,
+                };
+
+                let window = &mut self.windows[idx];
+                let attributes = &mut window.attributes;
+                attributes.fill_opacity = Opacity::from_bits(params[0] >> 6);
+                attributes.fill_color = Color::from_bits(params[0]);
+                attributes.border_color = Color::from_bits(params[1]);
+                attributes.border_type = (params[1] >> 6) | ((params[2] & 0x80) >> 5);
+                attributes.word_wrap = params[2] & 0x40 != 0;
+                attributes.print_direction = Direction::from_bits(params[2] >> 4);
+                attributes.scroll_direction = Direction::from_bits(params[2] >> 2);
+                attributes.justify = match params[2] & 0x03 {
+                    0 => Justify::Left,
+                    1 => Justify::Right,
+                    2 => Justify::Center,
+                    _ => Justify::Full,
+                };
+                attributes.effect_speed = params[3] >> 4;
+                attributes.effect_direction = Direction::from_bits(params[3] >> 2);
+                attributes.display_effect = match params[3] & 0x03 {
+                    1 => DisplayEffect::Fade,
+                    2 => DisplayEffect::Wipe,
+                    _ => DisplayEffect::Snap,
+                };
+
+                self.is_visible_window(idx)
+            }
+            // DF0-DF7
+            0x98..=0x9f => {
+                let idx = (code & 0x07) as usize;
+                self.define_window(idx, params)
+            }
+            // Reserved
+            _ => false,
+        }
+    }
+
+    fn for_windows(&mut self, bitmap: u8, mut func: impl FnMut(&mut Window) -> bool) -> bool {
+        let mut changed = false;
+
+        for (idx, window) in self.windows.iter_mut().enumerate() {
+            if bitmap & (1 << idx) != 0 && window.defined {
+                changed |= func(window);
+            }
+        }
+
+        changed
+    }
+
+    fn define_window(&mut self, idx: usize, params: &[u8]) -> bool {
+        let window = &mut self.windows[idx];
+        let was_visible = window.defined && window.visible && window.has_text();
+        let first_definition = !window.defined;
+
+        window.defined = true;
+        window.visible = params[0] & 0x20 != 0;
+        window.row_lock = params[0] & 0x10 != 0;
+        window.column_lock = params[0] & 0x08 != 0;
+        window.priority = params[0] & 0x07;
+        window.relative_positioning = params[1] & 0x80 != 0;
+        window.anchor_vertical = params[1] & 0x7f;
+        window.anchor_horizontal = params[2];
+        window.anchor_point = params[3] >> 4;
+
+        let rows = ((params[3] & 0x0f) + 1).min(MAX_ROWS) as usize;
+        let columns = ((params[4] & 0x3f) + 1).min(MAX_COLUMNS) as usize;
+        window.resize(rows, columns);
+
+        // A style of 0 selects style 1 for new windows and keeps the
+        // current style of existing windows, CEA-708-E 8.10.5.1
+        let window_style = (params[5] >> 3) & 0x07;
+        if window_style != 0 || first_definition {
+            window.attributes = WindowAttributes::predefined(window_style.max(1));
+        }
+
+        let pen_style = params[5] & 0x07;
+        if pen_style != 0 || first_definition {
+            let (attributes, color) = predefined_pen_style(pen_style.max(1));
+            window.pen_attributes = attributes;
+            window.pen_color = color;
+        }
+
+        self.current_window = Some(idx);
+
+        was_visible || (window.visible && window.has_text())
+    }
+}
+
+fn c1_params_len(code: u8) -> usize {
+    match code {
+        0x88..=0x8d => 1,
+        0x90 => 2,
+        0x91 => 3,
+        0x92 => 2,
+        0x97 => 4,
+        0x98..=0x9f => 6,
+        _ => 0,
+    }
+}
+
+fn g2_to_char(code: u8) -> Option<char> {
+    Some(match code {
+        // Transparent space and non-breaking transparent space
+        0x20 | 0x21 => ' ',
+        0x25 => '…',
+        0x2a => 'Š',
+        0x2c => 'Œ',
+        0x30 => '█',
+        0x31 => '‘',
+        0x32 => '’',
+        0x33 => '“',
+        0x34 => '”',
+        0x35 => '•',
+        0x39 => '™',
+        0x3a => 'š',
+        0x3c => 'œ',
+        0x3d => '℠',
+        0x3f => 'Ÿ',
+        0x76 => '⅛',
+        0x77 => '⅜',
+        0x78 => '⅝',
+        0x79 => '⅞',
+        0x7a => '│',
+        0x7b => '┐',
+        0x7c => '└',
+        0x7d => '─',
+        0x7e => '┘',
+        0x7f => '┌',
+        _ => return None,
+    })
+}
+
+/// Splits a DTVCC packet into its service blocks, returning
+/// the service number and data of each of them.
+pub fn service_blocks(packet: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut blocks = Vec::new();
+
+    // Skip the packet header
+    let mut data = match packet.split_first() {
+        Some((_, data)) => data,
+        None => return blocks,
+    };
+
+    while let Some((&header, rest)) = data.split_first() {
+        let mut service_number = header >> 5;
+        let block_size = (header & 0x1f) as usize;
+        data = rest;
+
+        // Null service block: padding up to the end of the packet
+        if service_number == 0 {
+            break;
+        }
+
+        if service_number == 7 {
+            // Extended service block header
+            match data.split_first() {
+                Some((&extended, rest)) => {
+                    service_number = extended & 0x3f;
+                    data = rest;
+                }
+                None => break,
+            }
+        }
+
+        if block_size > data.len() {
+            break;
+        }
+
+        let (block, rest) = data.split_at(block_size);
+        blocks.push((service_number, block));
+        data = rest;
+    }
+
+    blocks
+}
+
+/// Assembles DTVCC packets from `cc_data` triplets.
+#[derive(Debug, Default)]
+pub struct PacketParser {
+    packet: Vec<u8>,
+    packet_len: usize,
+}
+
+impl PacketParser {
+    /// Feeds `cc_data` triplets, returning the DTVCC packets completed by them.
+    ///
+    /// The CEA-608 triplets are ignored.
+    pub fn push(&mut self, cc_data: &[u8]) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+
+        for triplet in cc_data.chunks_exact(3) {
+            let cc_valid = triplet[0] & 0x04 != 0;
+            let cc_type = triplet[0] & 0x03;
+
+            match cc_type {
+                // CEA-608
+                0 | 1 => continue,
+                // DTVCC packet start
+                3 => {
+                    if !cc_valid {
+                        continue;
+                    }
+
+                    // Incomplete packet, CEA-708-E 5.1
+                    if !self.packet.is_empty() {
+                        packets.push(std::mem::take(&mut self.packet));
+                    }
+
+                    let size_code = (triplet[1] & 0x3f) as usize;
+                    self.packet_len = if size_code == 0 { 128 } else { size_code * 2 };
+                    self.packet.extend_from_slice(&triplet[1..]);
+                }
+                // DTVCC packet data
+                _ => {
+                    if !cc_valid || self.packet.is_empty() {
+                        continue;
+                    }
+
+                    self.packet.extend_from_slice(&triplet[1..]);
+                }
+            }
+
+            if !self.packet.is_empty() && self.packet.len() >= self.packet_len {
+                let mut packet = std::mem::take(&mut self.packet);
+                packet.truncate(self.packet_len);
+                packets.push(packet);
+            }
+        }
+
+        packets
+    }
+
+    pub fn reset(&mut self) {
+        self.packet.clear();
+        self.packet_len = 0;
+    }
+}
+
+/// Decodes one caption service out of `cc_data`.
+#[derive(Debug)]
+pub struct Decoder {
+    service_number: u8,
+    parser: PacketParser,
+    service: Service,
+}
+
+impl Decoder {
+    pub fn new(service_number: u8) -> Self {
+        Decoder {
+            service_number,
+            parser: PacketParser::default(),
+            service: Service::default(),
+        }
+    }
+
+    pub fn service(&self) -> &Service {
+        &self.service
+    }
+
+    /// Feeds `cc_data` triplets, returning whether the visible windows of
+    /// the selected service changed.
+    pub fn decode(&mut self, cc_data: &[u8]) -> Status {
+        let mut status = Status::Ok;
+
+        for packet in self.parser.push(cc_data) {
+            for (service_number, block) in service_blocks(&packet) {
+                if service_number == self.service_number
+                    && self.service.decode(block) == Status::Changed
+                {
+                    status = Status::Changed;
+                }
+            }
+        }
+
+        status
+    }
+
+    pub fn reset(&mut self) {
+        self.parser.reset();
+        self.service.reset();
+    }
+}
+
+/// The parameters of a DefineWindow command, CEA-708-E 8.10.5.1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowDefinition {
+    pub visible: bool,
+    pub row_lock: bool,
+    pub column_lock: bool,
+    pub priority: u8,
+    pub relative_positioning: bool,
+    pub anchor_vertical: u8,
+    pub anchor_horizontal: u8,
+    pub anchor_point: u8,
+    /// Number of rows, 1 to 15
+    pub rows: u8,
+    /// Number of columns, 1 to 42
+    pub columns: u8,
+    pub window_style: u8,
+    pub pen_style: u8,
+}
+
+/// Builds the commands of one caption service.
+///
+/// Commands are kept separate so that [`PacketWriter`] never splits
+/// one across service blocks, CEA-708-E 7.1.
+#[derive(Debug, Default)]
+pub struct ServiceWriter {
+    commands: VecDeque<Vec<u8>>,
+}
+
+impl ServiceWriter {
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    fn push(&mut self, command: &[u8]) {
+        self.commands.push_back(command.to_vec());
+    }
+
+    /// DSW
+    pub fn display_windows(&mut self, bitmap: u8) {
+        self.push(&[0x89, bitmap]);
+    }
+
+    /// DLW
+    pub fn delete_windows(&mut self, bitmap: u8) {
+        self.push(&[0x8c, bitmap]);
+    }
+
+    /// DFx, which also makes the window the current one
+    pub fn define_window(&mut self, window: u8, definition: &WindowDefinition) {
+        self.push(&[
+            0x98 | (window & 0x07),
+            ((definition.visible as u8) << 5)
+                | ((definition.row_lock as u8) << 4)
+                | ((definition.column_lock as u8) << 3)
+                | (definition.priority & 0x07),
+            ((definition.relative_positioning as u8) << 7) | (definition.anchor_vertical & 0x7f),
+            definition.anchor_horizontal,
+            ((definition.anchor_point & 0x0f) << 4) | (definition.rows.clamp(1, MAX_ROWS) - 1),
+            definition.columns.clamp(1, MAX_COLUMNS) - 1,
+            ((definition.window_style & 0x07) << 3) | (definition.pen_style & 0x07),
+        ]);
+    }
+
+    /// SPA
+    pub fn set_pen_attributes(&mut self, attributes: &PenAttributes) {
+        let offset = match attributes.offset {
+            PenOffset::Subscript => 0,
+            PenOffset::Normal => 1,
+            PenOffset::Superscript => 2,
+        };
+        let size = match attributes.size {
+            PenSize::Small => 0,
+            PenSize::Standard => 1,
+            PenSize::Large => 2,
+        };
+
+        self.push(&[
+            0x90,
+            ((attributes.text_tag & 0x0f) << 4) | (offset << 2) | size,
+            ((attributes.italics as u8) << 7)
+                | ((attributes.underline as u8) << 6)
+                | ((attributes.edge_type & 0x07) << 3)
+                | (attributes.font_style & 0x07),
+        ]);
+    }
+
+    /// SPC
+    pub fn set_pen_color(&mut self, color: &PenColor) {
+        self.push(&[
+            0x91,
+            (color.foreground_opacity.to_bits() << 6) | color.foreground_color.to_bits(),
+            (color.background_opacity.to_bits() << 6) | color.background_color.to_bits(),
+            color.edge_color.to_bits(),
+        ]);
+    }
+
+    /// SPL
+    pub fn set_pen_location(&mut self, row: u8, column: u8) {
+        self.push(&[0x92, row & 0x0f, column & 0x3f]);
+    }
+
+    /// CR
+    pub fn carriage_return(&mut self) {
+        self.push(&[0x0d]);
+    }
+
+    /// HCR, which also erases the current row
+    pub fn horizontal_carriage_return(&mut self) {
+        self.push(&[0x0e]);
+    }
+
+    /// Writes one character, using the G0, G1, G2 or G3 code sets
+    /// or a 16 bits P16 code. Returns `false` if `c` can't be encoded.
+    pub fn put_char(&mut self, c: char) -> bool {
+        match c {
+            '♪' => self.push(&[0x7f]),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => self.push(&[c as u8]),
+            '㏄' => self.push(&[0x10, 0xa0]),
+            _ => {
+                if let Some(code) = (0x22..=0x7f).find(|code| g2_to_char(*code) == Some(c)) {
+                    self.push(&[0x10, code]);
+                } else if let Ok(c) = u16::try_from(c as u32) {
+                    let [hi, lo] = c.to_be_bytes();
+                    self.push(&[0x18, hi, lo]);
+                } else {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Takes the next whole commands that fit in `max_len` bytes.
+    fn take_block(&mut self, max_len: usize) -> Vec<u8> {
+        let mut block = Vec::new();
+
+        while let Some(command) = self.commands.front() {
+            if block.len() + command.len() > max_len {
+                break;
+            }
+
+            block.extend(self.commands.pop_front().unwrap());
+        }
+
+        block
+    }
+}
+
+/// Packs the commands of caption services into DTVCC packets,
+/// and those into `cc_data` triplets.
+#[derive(Debug, Default)]
+pub struct PacketWriter {
+    sequence: u8,
+    triplets: VecDeque<[u8; 3]>,
+}
+
+impl PacketWriter {
+    /// Whether packet data is still waiting to be output.
+    pub fn is_empty(&self) -> bool {
+        self.triplets.is_empty()
+    }
+
+    /// Packs the pending commands of `services` into packets and returns
+    /// at most `max_triplets` `cc_data` triplets of them. The remaining
+    /// packet data is returned by the following calls.
+    pub fn write(
+        &mut self,
+        services: &mut [(u8, &mut ServiceWriter)],
+        max_triplets: usize,
+    ) -> Vec<u8> {
+        while self.triplets.len() < max_triplets {
+            let packet = match self.packet(services) {
+                Some(packet) => packet,
+                None => break,
+            };
+
+            for (idx, pair) in packet.chunks_exact(2).enumerate() {
+                let header = if idx == 0 { 0xff } else { 0xfe };
+                self.triplets.push_back([header, pair[0], pair[1]]);
+            }
+        }
+
+        let len = self.triplets.len().min(max_triplets);
+        self.triplets.drain(..len).flatten().collect()
+    }
+
+    /// Builds one packet, taking blocks from each service in turn.
+    fn packet(&mut self, services: &mut [(u8, &mut ServiceWriter)]) -> Option<Vec<u8>> {
+        // Including the packet header, CEA-708-E 5
+        const MAX_PACKET_LEN: usize = 128;
+        const MAX_BLOCK_LEN: usize = 31;
+
+        let mut packet = vec![0];
+
+        loop {
+            let mut progress = false;
+
+            for (service_number, writer) in services.iter_mut() {
+                let header_len = if *service_number < 7 { 1 } else { 2 };
+                let room = MAX_PACKET_LEN - packet.len();
+                if room <= header_len {
+                    break;
+                }
+
+                let block = writer.take_block((room - header_len).min(MAX_BLOCK_LEN));
+                if block.is_empty() {
+                    continue;
+                }
+
+                if *service_number < 7 {
+                    packet.push((*service_number << 5) | block.len() as u8);
+                } else {
+                    // Extended service block header
+                    packet.push(0xe0 | block.len() as u8);
+                    packet.push(*service_number & 0x3f);
+                }
+                packet.extend(block);
+                progress = true;
+            }
+
+            if !progress {
+                break;
+            }
+        }
+
+        if packet.len() == 1 {
+            return None;
+        }
+
+        // Pad with a null service block header to an even size
+        if packet.len() % 2 == 1 {
+            packet.push(0);
+        }
+
+        // A size code of 0 stands for 128 bytes
+        let size_code = (packet.len() / 2) as u8 & 0x3f;
+        packet[0] = (self.sequence << 6) | size_code;
+        self.sequence = (self.sequence + 1) % 4;
+
+        Some(packet)
+    }
+
+    pub fn reset(&mut self) {
+        self.sequence = 0;
+        self.triplets.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cc_data(packet: &[u8]) -> Vec<u8> {
+        let mut cc_data = Vec::new();
+        for (idx, pair) in packet.chunks(2).enumerate() {
+            cc_data.push(if idx == 0 { 0xff } else { 0xfe });
+            cc_data.push(pair[0]);
+            cc_data.push(pair.get(1).copied().unwrap_or(0));
+        }
+        cc_data
+    }
+
+    fn packet(seq: u8, blocks: &[(u8, &[u8])]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for (service, block) in blocks {
+            data.push((service << 5) | block.len() as u8);
+            data.extend_from_slice(block);
+        }
+        if data.len() % 2 == 0 {
+            data.push(0);
+        }
+        // The header byte makes the packet size even
+        let size_code = (data.len() + 1) as u8 / 2;
+        let mut packet = vec![(seq << 6) | size_code];
+        packet.extend(data);
+        packet
+    }
+
+    #[test]
+    fn test_packet_parser() {
+        let mut parser = PacketParser::default();
+
+        let packet = packet(0, &[(1, &[0x8c, 0xff])]);
+        let cc_data = cc_data(&packet);
+
+        // Split across two pushes
+        assert!(parser.push(&cc_data[..3]).is_empty());
+        let packets = parser.push(&cc_data[3..]);
+        assert_eq!(packets, vec![packet.clone()]);
+
+        assert_eq!(service_blocks(&packets[0]), vec![(1, &[0x8c, 0xff][..])]);
+    }
+
+    #[test]
+    fn test_extended_service() {
+        let packet = [0x02, 0xe2, 0x2a, 0x41, 0x42];
+        assert_eq!(service_blocks(&packet), vec![(42, &[0x41, 0x42][..])]);
+    }
+
+    #[test]
+    fn test_pop_on() {
+        let mut service = Service::default();
+
+        // DF0: hidden, 2 rows, 32 columns, style 1
+        assert_eq!(
+            service.decode(&[0x98, 0x00, 0x00, 0x00, 0x01, 0x1f, 0x09]),
+            Status::Ok
+        );
+        assert_eq!(service.decode(b"Hello\x0dWorld"), Status::Ok);
+        assert_eq!(service.to_text(), "");
+
+        // DSW 0
+        assert_eq!(service.decode(&[0x89, 0x01]), Status::Changed);
+        assert_eq!(service.to_text(), "Hello\r\nWorld");
+
+        // HDW 0
+        assert_eq!(service.decode(&[0x8a, 0x01]), Status::Changed);
+        assert_eq!(service.to_text(), "");
+    }
+
+    #[test]
+    fn test_roll_up() {
+        let mut service = Service::default();
+
+        // DF1: visible, 2 rows, 32 columns
+        service.decode(&[0x99, 0x20, 0x00, 0x00, 0x01, 0x1f, 0x09]);
+        assert_eq!(service.decode(b"one\x0dtwo"), Status::Changed);
+        assert_eq!(service.decode(b"\x0dthree"), Status::Changed);
+        assert_eq!(service.to_text(), "two\r\nthree");
+
+        // BS then HCR
+        service.decode(b"\x08");
+        assert_eq!(service.to_text(), "two\r\nthre");
+        service.decode(b"\x0e");
+        assert_eq!(service.to_text(), "two");
+    }
+
+    #[test]
+    fn test_pen_attributes() {
+        let mut service = Service::default();
+
+        service.decode(&[0x98, 0x20, 0x00, 0x00, 0x00, 0x1f, 0x09]);
+        // SPA: italics, SPC: red on black
+        service.decode(&[0x90, 0x05, 0x80, 0x91, 0x30, 0x00, 0x00]);
+        service.decode(b"red");
+
+        let window = &service.windows[0];
+        let cell = window.rows[0][0].unwrap();
+        assert!(cell.attributes.italics);
+        assert_eq!(cell.color.foreground_color, Color { r: 3, g: 0, b: 0 });
+        assert_eq!(cell.color.foreground_color.to_rgb(), (1.0, 0.0, 0.0));
+        assert_eq!(cell.color.background_opacity.to_alpha(), 1.0);
+
+        let lines = service.to_lines();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].chunks[0].style, TextStyle::Red);
+        assert_eq!(lines[0].chunks[0].text, "red");
+    }
+
+    #[test]
+    fn test_characters() {
+        let mut service = Service::default();
+
+        service.decode(&[0x98, 0x20, 0x00, 0x00, 0x00, 0x1f, 0x09]);
+        // Music note, G1 e acute, G2 ellipsis, P16 CJK
+        service.decode(&[0x7f, 0xe9, 0x10, 0x25, 0x18, 0x4e, 0x2d]);
+        assert_eq!(service.to_text(), "♪é…中");
+    }
+
+    #[test]
+    fn test_writer_roundtrip() {
+        let mut service1 = ServiceWriter::default();
+        let mut service42 = ServiceWriter::default();
+
+        let definition = WindowDefinition {
+            visible: true,
+            row_lock: true,
+            column_lock: true,
+            priority: 0,
+            relative_positioning: true,
+            anchor_vertical: 0,
+            anchor_horizontal: 0,
+            anchor_point: 0,
+            rows: 2,
+            columns: 32,
+            window_style: 2,
+            pen_style: 1,
+        };
+
+        for writer in [&mut service1, &mut service42] {
+            writer.define_window(0, &definition);
+            writer.set_pen_color(&PenColor {
+                foreground_color: Color::from_text_style(TextStyle::Yellow).0,
+                ..PenColor::default()
+            });
+        }
+        for c in "Hello, 中文…é♪".chars() {
+            assert!(service1.put_char(c));
+        }
+        for c in "A much longer line, to span packets".chars() {
+            assert!(service42.put_char(c));
+        }
+        assert!(!service1.put_char('🦀'));
+
+        let mut writer = PacketWriter::default();
+        let mut decoder1 = Decoder::new(1);
+        let mut decoder42 = Decoder::new(42);
+
+        let mut frames = 0;
+        loop {
+            let cc_data = writer.write(&mut [(1, &mut service1), (42, &mut service42)], 4);
+            if cc_data.is_empty() {
+                break;
+            }
+            assert!(cc_data.len() <= 12);
+            decoder1.decode(&cc_data);
+            decoder42.decode(&cc_data);
+            frames += 1;
+        }
+
+        assert!(frames > 1);
+        assert!(service1.is_empty() && service42.is_empty() && writer.is_empty());
+        assert_eq!(decoder1.service().to_text(), "Hello, 中文…é♪");
+        assert_eq!(
+            decoder42.service().to_text(),
+            "A much longer line, to span pack"
+        );
+
+        let lines = decoder1.service().to_lines();
+        assert_eq!(lines[0].chunks[0].style, TextStyle::Yellow);
+    }
+}
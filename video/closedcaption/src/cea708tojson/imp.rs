@@ -0,0 +1,404 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * element-cea708tojson:
+ *
+ * Decodes one CEA-708 caption service, selected with `service-number`, and
+ * outputs the state of its visible windows as JSON.
+ *
+ * The windows are laid out on a 608-like grid of rows and columns, as that
+ * is what the JSON format can represent. Window colors, borders and
+ * justification are not part of the output.
+ *
+ * The delay commands DLY and DLC are not supported: the commands following
+ * them are applied immediately instead of being held back.
+ */
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use crate::ccutils::extract_cdp;
+use crate::cea708::{Decoder, Status, MAX_SERVICE_NUMBER};
+use crate::ttutils::Lines;
+
+use atomic_refcell::AtomicRefCell;
+
+use once_cell::sync::Lazy;
+
+use std::sync::Mutex;
+
+const DEFAULT_SERVICE_NUMBER: u32 = 1;
+
+#[derive(Copy, Clone, Debug)]
+enum InputFormat {
+    Cdp,
+    CcData,
+}
+
+#[derive(Debug)]
+struct TimestampedLines {
+    lines: Lines,
+    pts: gst::ClockTime,
+    duration: Option<gst::ClockTime>,
+}
+
+#[derive(Clone)]
+struct Settings {
+    service_number: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            service_number: DEFAULT_SERVICE_NUMBER,
+        }
+    }
+}
+
+struct State {
+    input_format: Option<InputFormat>,
+    decoder: Decoder,
+    pending_lines: Option<TimestampedLines>,
+}
+
+impl State {
+    fn new(settings: &Settings) -> Self {
+        State {
+            input_format: None,
+            decoder: Decoder::new(settings.service_number as u8),
+            pending_lines: None,
+        }
+    }
+
+    /* Returns the lines previously on screen, if any, with their duration */
+    fn update(&mut self, pts: gst::ClockTime) -> Option<TimestampedLines> {
+        let lines = self.decoder.service().to_lines();
+
+        let previous = self.pending_lines.take().map(|mut pending| {
+            pending.duration = Some(pts.saturating_sub(pending.pts));
+            pending
+        });
+
+        if !lines.is_empty() {
+            self.pending_lines = Some(TimestampedLines {
+                lines: Lines {
+                    lines,
+                    mode: None,
+                    clear: None,
+                },
+                pts,
+                duration: None,
+            });
+        }
+
+        previous
+    }
+}
+
+pub struct Cea708ToJson {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+
+    state: AtomicRefCell<State>,
+    settings: Mutex<Settings>,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "cea708tojson",
+        gst::DebugColorFlags::empty(),
+        Some("CEA-708 to JSON Element"),
+    )
+});
+
+impl Cea708ToJson {
+    fn output(
+        &self,
+        element: &super::Cea708ToJson,
+        lines: TimestampedLines,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::debug!(CAT, obj: element, "outputting: {:?}", lines);
+
+        let json = serde_json::to_string(&lines.lines).map_err(|err| {
+            gst::element_error!(
+                element,
+                gst::ResourceError::Write,
+                ["Failed to serialize as json {}", err]
+            );
+
+            gst::FlowError::Error
+        })?;
+
+        let mut buf = gst::Buffer::from_mut_slice(json.into_bytes());
+        {
+            let buf_mut = buf.get_mut().unwrap();
+            buf_mut.set_pts(lines.pts);
+            buf_mut.set_duration(lines.duration);
+        }
+
+        gst::log!(CAT, obj: element, "Pushing {:?}", buf);
+
+        self.srcpad.push(buf)
+    }
+
+    fn sink_chain(
+        &self,
+        pad: &gst::Pad,
+        element: &super::Cea708ToJson,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::trace!(CAT, obj: pad, "Handling buffer {:?}", buffer);
+
+        let mut state = self.state.borrow_mut();
+
+        let pts = buffer.pts().ok_or_else(|| {
+            gst::error!(CAT, obj: pad, "Require timestamped buffers");
+            gst::FlowError::Error
+        })?;
+
+        let data = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, obj: pad, "Can't map buffer readable");
+
+            gst::FlowError::Error
+        })?;
+
+        let cc_data = match state.input_format {
+            Some(InputFormat::Cdp) => match extract_cdp(&data) {
+                Ok(cc_data) => cc_data,
+                Err(err) => {
+                    gst::warning!(CAT, obj: pad, "Invalid CDP packet: {}", err);
+                    return Ok(gst::FlowSuccess::Ok);
+                }
+            },
+            Some(InputFormat::CcData) => data.as_slice(),
+            None => {
+                gst::error!(CAT, obj: pad, "Not negotiated yet");
+                return Err(gst::FlowError::NotNegotiated);
+            }
+        };
+
+        if state.decoder.decode(cc_data) == Status::Ok {
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        if let Some(lines) = state.update(pts) {
+            drop(state);
+            self.output(element, lines)
+        } else {
+            Ok(gst::FlowSuccess::Ok)
+        }
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, element: &super::Cea708ToJson, event: gst::Event) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad, "Handling event {:?}", event);
+        match event.view() {
+            EventView::Caps(c) => {
+                let s = c.caps().structure(0).unwrap();
+                self.state.borrow_mut().input_format = match s.get::<&str>("format") {
+                    Ok("cdp") => Some(InputFormat::Cdp),
+                    Ok("cc_data") => Some(InputFormat::CcData),
+                    _ => {
+                        gst::error!(CAT, obj: pad, "Unsupported caps {}", c.caps());
+                        return false;
+                    }
+                };
+
+                // We send our own caps downstream
+                let caps = gst::Caps::builder("application/x-json")
+                    .field("format", "cea708")
+                    .build();
+                self.srcpad.push_event(gst::event::Caps::new(&caps))
+            }
+            EventView::FlushStop(..) => {
+                let mut state = self.state.borrow_mut();
+                state.decoder.reset();
+                state.pending_lines = None;
+                drop(state);
+                pad.event_default(Some(element), event)
+            }
+            EventView::Eos(..) => {
+                let pending = self.state.borrow_mut().pending_lines.take();
+                if let Some(lines) = pending {
+                    let _ = self.output(element, lines);
+                }
+
+                pad.event_default(Some(element), event)
+            }
+            _ => pad.event_default(Some(element), event),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Cea708ToJson {
+    const NAME: &'static str = "Cea708ToJson";
+    type Type = super::Cea708ToJson;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                Cea708ToJson::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this, element| this.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Cea708ToJson::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this, element| this.sink_event(pad, element, event),
+                )
+            })
+            .flags(gst::PadFlags::FIXED_CAPS)
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .flags(gst::PadFlags::FIXED_CAPS)
+            .build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            state: AtomicRefCell::new(State::new(&Settings::default())),
+            settings: Mutex::new(Settings::default()),
+        }
+    }
+}
+
+impl ObjectImpl for Cea708ToJson {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![glib::ParamSpecUInt::builder("service-number")
+                .nick("Service Number")
+                .blurb("The CEA-708 caption service to decode")
+                .minimum(1)
+                .maximum(MAX_SERVICE_NUMBER as u32)
+                .default_value(DEFAULT_SERVICE_NUMBER)
+                .mutable_ready()
+                .build()]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "service-number" => {
+                self.settings.lock().unwrap().service_number =
+                    value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "service-number" => {
+                let settings = self.settings.lock().unwrap();
+                settings.service_number.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for Cea708ToJson {}
+
+impl ElementImpl for Cea708ToJson {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "CEA-708 to JSON",
+                "Generic",
+                "Converts CEA-708 Closed Captions to JSON",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let caps = gst::Caps::builder("application/x-json").build();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let caps = gst::Caps::builder("closedcaption/x-cea-708")
+                .field("format", gst::List::new(["cc_data", "cdp"]))
+                .build();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    #[allow(clippy::single_match)]
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::ReadyToPaused => {
+                let mut state = self.state.borrow_mut();
+                *state = State::new(&self.settings.lock().unwrap());
+            }
+            _ => (),
+        }
+
+        let ret = self.parent_change_state(element, transition)?;
+
+        match transition {
+            gst::StateChange::PausedToReady => {
+                let mut state = self.state.borrow_mut();
+                *state = State::new(&self.settings.lock().unwrap());
+            }
+            _ => (),
+        }
+
+        Ok(ret)
+    }
+}
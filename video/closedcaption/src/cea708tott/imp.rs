@@ -0,0 +1,596 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * element-cea708tott:
+ *
+ * Decodes one CEA-708 caption service, selected with `service-number`, and
+ * outputs its visible text as SRT, WebVTT or raw UTF-8 timed text.
+ *
+ * The delay commands DLY and DLC are not supported: the commands following
+ * them are applied immediately instead of being held back.
+ */
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use crate::ccutils::extract_cdp;
+use crate::cea708::{Decoder, Status, MAX_SERVICE_NUMBER};
+use atomic_refcell::AtomicRefCell;
+
+use once_cell::sync::Lazy;
+
+use std::sync::Mutex;
+
+const DEFAULT_SERVICE_NUMBER: u32 = 1;
+
+#[derive(Copy, Clone, Debug)]
+enum Format {
+    Srt,
+    Vtt,
+    Raw,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum InputFormat {
+    Cdp,
+    CcData,
+}
+
+#[derive(Debug, Clone)]
+struct Settings {
+    service_number: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            service_number: DEFAULT_SERVICE_NUMBER,
+        }
+    }
+}
+
+struct State {
+    format: Option<Format>,
+    input_format: Option<InputFormat>,
+    wrote_header: bool,
+    decoder: Decoder,
+    previous_text: Option<(gst::ClockTime, String)>,
+    index: u64,
+}
+
+impl State {
+    fn new(settings: &Settings) -> Self {
+        State {
+            format: None,
+            input_format: None,
+            wrote_header: false,
+            decoder: Decoder::new(settings.service_number as u8),
+            previous_text: None,
+            index: 1,
+        }
+    }
+}
+
+pub struct Cea708ToTt {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+
+    state: AtomicRefCell<State>,
+    settings: Mutex<Settings>,
+}
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "cea708tott",
+        gst::DebugColorFlags::empty(),
+        Some("CEA-708 to TT Element"),
+    )
+});
+
+impl Cea708ToTt {
+    fn sink_chain(
+        &self,
+        pad: &gst::Pad,
+        _element: &super::Cea708ToTt,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::log!(CAT, obj: pad, "Handling buffer {:?}", buffer);
+
+        let mut state = self.state.borrow_mut();
+        let format = match state.format {
+            Some(format) => format,
+            None => {
+                gst::error!(CAT, obj: pad, "Not negotiated yet");
+                return Err(gst::FlowError::NotNegotiated);
+            }
+        };
+
+        let buffer_pts = buffer.pts().ok_or_else(|| {
+            gst::error!(CAT, obj: pad, "Require timestamped buffers");
+            gst::FlowError::Error
+        })?;
+
+        let data = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, obj: pad, "Can't map buffer readable");
+
+            gst::FlowError::Error
+        })?;
+
+        let cc_data = match state.input_format {
+            Some(InputFormat::Cdp) => match extract_cdp(&data) {
+                Ok(cc_data) => cc_data,
+                Err(err) => {
+                    gst::warning!(CAT, obj: pad, "Invalid CDP packet: {}", err);
+                    return Ok(gst::FlowSuccess::Ok);
+                }
+            },
+            Some(InputFormat::CcData) => data.as_slice(),
+            None => unreachable!(),
+        };
+
+        if state.decoder.decode(cc_data) == Status::Ok {
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let text = state.decoder.service().to_text();
+        if state.previous_text.as_ref().map(|(_, text)| text.as_str()) == Some(text.as_str()) {
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let previous_text = if text.is_empty() {
+            gst::debug!(CAT, obj: pad, "Clearing previous closed caption text");
+            state.previous_text.take()
+        } else {
+            gst::debug!(CAT, obj: pad, "Have new closed caption text");
+            state.previous_text.replace((buffer_pts, text))
+        };
+
+        let previous_text = match previous_text {
+            Some(previous_text) => previous_text,
+            None => {
+                gst::debug!(CAT, obj: pad, "Have no previous text");
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        };
+
+        let duration = buffer_pts.saturating_sub(previous_text.0);
+
+        let (timestamp, text) = previous_text;
+
+        let header_buffer = if !state.wrote_header {
+            state.wrote_header = true;
+
+            match format {
+                Format::Vtt => Some(Self::create_vtt_header(timestamp)),
+                Format::Srt | Format::Raw => None,
+            }
+        } else {
+            None
+        };
+
+        let buffer = match format {
+            Format::Vtt => Self::create_vtt_buffer(timestamp, duration, text),
+            Format::Srt => Self::create_srt_buffer(timestamp, duration, state.index, text),
+            Format::Raw => Self::create_raw_buffer(timestamp, duration, text),
+        };
+        state.index += 1;
+        drop(state);
+
+        if let Some(header_buffer) = header_buffer {
+            self.srcpad.push(header_buffer)?;
+        }
+
+        self.srcpad.push(buffer)
+    }
+
+    fn create_vtt_header(timestamp: gst::ClockTime) -> gst::Buffer {
+        use std::fmt::Write;
+
+        let mut headers = String::new();
+        writeln!(&mut headers, "WEBVTT\r").unwrap();
+        writeln!(&mut headers, "\r").unwrap();
+
+        let mut buffer = gst::Buffer::from_mut_slice(headers.into_bytes());
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(timestamp);
+        }
+
+        buffer
+    }
+
+    fn split_time(time: gst::ClockTime) -> (u64, u8, u8, u16) {
+        let time = time.nseconds();
+
+        let mut s = time / 1_000_000_000;
+        let mut m = s / 60;
+        let h = m / 60;
+        s %= 60;
+        m %= 60;
+        let ns = time % 1_000_000_000;
+
+        (h as u64, m as u8, s as u8, (ns / 1_000_000) as u16)
+    }
+
+    fn create_vtt_buffer(
+        timestamp: gst::ClockTime,
+        duration: gst::ClockTime,
+        text: String,
+    ) -> gst::Buffer {
+        use std::fmt::Write;
+
+        let mut data = String::new();
+
+        let (h1, m1, s1, ms1) = Self::split_time(timestamp);
+        let (h2, m2, s2, ms2) = Self::split_time(timestamp + duration);
+
+        writeln!(
+            &mut data,
+            "{:02}:{:02}:{:02}.{:03} --> {:02}:{:02}:{:02}.{:03}\r",
+            h1, m1, s1, ms1, h2, m2, s2, ms2
+        )
+        .unwrap();
+        writeln!(&mut data, "{}\r", text).unwrap();
+        writeln!(&mut data, "\r").unwrap();
+
+        let mut buffer = gst::Buffer::from_mut_slice(data.into_bytes());
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(timestamp);
+            buffer.set_duration(duration);
+        }
+
+        buffer
+    }
+
+    fn create_srt_buffer(
+        timestamp: gst::ClockTime,
+        duration: gst::ClockTime,
+        index: u64,
+        text: String,
+    ) -> gst::Buffer {
+        use std::fmt::Write;
+
+        let mut data = String::new();
+
+        let (h1, m1, s1, ms1) = Self::split_time(timestamp);
+        let (h2, m2, s2, ms2) = Self::split_time(timestamp + duration);
+
+        writeln!(&mut data, "{}\r", index).unwrap();
+        writeln!(
+            &mut data,
+            "{:02}:{:02}:{:02},{:03} --> {:02}:{:02}:{:02},{:03}\r",
+            h1, m1, s1, ms1, h2, m2, s2, ms2
+        )
+        .unwrap();
+        writeln!(&mut data, "{}\r", text).unwrap();
+        writeln!(&mut data, "\r").unwrap();
+
+        let mut buffer = gst::Buffer::from_mut_slice(data.into_bytes());
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(timestamp);
+            buffer.set_duration(duration);
+        }
+
+        buffer
+    }
+
+    fn create_raw_buffer(
+        timestamp: gst::ClockTime,
+        duration: gst::ClockTime,
+        text: String,
+    ) -> gst::Buffer {
+        let mut buffer = gst::Buffer::from_mut_slice(text.into_bytes());
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(timestamp);
+            buffer.set_duration(duration);
+        }
+
+        buffer
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, element: &super::Cea708ToTt, event: gst::Event) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad, "Handling event {:?}", event);
+        match event.view() {
+            EventView::Caps(c) => {
+                let mut state = self.state.borrow_mut();
+
+                let s = c.caps().structure(0).unwrap();
+                state.input_format = match s.get::<&str>("format") {
+                    Ok("cdp") => Some(InputFormat::Cdp),
+                    Ok("cc_data") => Some(InputFormat::CcData),
+                    _ => {
+                        gst::error!(CAT, obj: pad, "Unsupported caps {}", c.caps());
+                        return false;
+                    }
+                };
+
+                if state.format.is_some() {
+                    return true;
+                }
+
+                let mut downstream_caps = match self.srcpad.allowed_caps() {
+                    None => self.srcpad.pad_template_caps(),
+                    Some(caps) => caps,
+                };
+
+                if downstream_caps.is_empty() {
+                    gst::error!(CAT, obj: pad, "Empty downstream caps");
+                    return false;
+                }
+
+                downstream_caps.fixate();
+
+                gst::debug!(
+                    CAT,
+                    obj: pad,
+                    "Negotiating for downstream caps {}",
+                    downstream_caps
+                );
+
+                let s = downstream_caps.structure(0).unwrap();
+                let new_caps = if s.name() == "application/x-subtitle-vtt" {
+                    state.format = Some(Format::Vtt);
+                    gst::Caps::builder("application/x-subtitle-vtt").build()
+                } else if s.name() == "application/x-subtitle" {
+                    state.format = Some(Format::Srt);
+                    gst::Caps::builder("application/x-subtitle").build()
+                } else if s.name() == "text/x-raw" {
+                    state.format = Some(Format::Raw);
+                    gst::Caps::builder("text/x-raw")
+                        .field("format", "utf8")
+                        .build()
+                } else {
+                    unreachable!();
+                };
+
+                let new_event = gst::event::Caps::new(&new_caps);
+
+                return self.srcpad.push_event(new_event);
+            }
+            EventView::FlushStop(..) => {
+                let mut state = self.state.borrow_mut();
+                state.decoder.reset();
+                state.previous_text = None;
+            }
+            EventView::Eos(..) => {
+                let mut state = self.state.borrow_mut();
+                if let Some((timestamp, text)) = state.previous_text.take() {
+                    gst::debug!(CAT, obj: pad, "Outputting final text on EOS");
+
+                    let format = state.format.unwrap();
+
+                    let header_buffer = if !state.wrote_header {
+                        state.wrote_header = true;
+
+                        match format {
+                            Format::Vtt => Some(Self::create_vtt_header(timestamp)),
+                            Format::Srt | Format::Raw => None,
+                        }
+                    } else {
+                        None
+                    };
+
+                    let buffer = match format {
+                        Format::Vtt => {
+                            Self::create_vtt_buffer(timestamp, gst::ClockTime::ZERO, text)
+                        }
+                        Format::Srt => Self::create_srt_buffer(
+                            timestamp,
+                            gst::ClockTime::ZERO,
+                            state.index,
+                            text,
+                        ),
+                        Format::Raw => {
+                            Self::create_raw_buffer(timestamp, gst::ClockTime::ZERO, text)
+                        }
+                    };
+                    state.index += 1;
+                    drop(state);
+
+                    if let Some(header_buffer) = header_buffer {
+                        let _ = self.srcpad.push(header_buffer);
+                    }
+
+                    let _ = self.srcpad.push(buffer);
+                }
+            }
+            _ => (),
+        }
+
+        pad.event_default(Some(element), event)
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Cea708ToTt {
+    const NAME: &'static str = "Cea708ToTt";
+    type Type = super::Cea708ToTt;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                Cea708ToTt::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this, element| this.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Cea708ToTt::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this, element| this.sink_event(pad, element, event),
+                )
+            })
+            .flags(gst::PadFlags::FIXED_CAPS)
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .flags(gst::PadFlags::FIXED_CAPS)
+            .build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            state: AtomicRefCell::new(State::new(&Settings::default())),
+            settings: Mutex::new(Settings::default()),
+        }
+    }
+}
+
+impl ObjectImpl for Cea708ToTt {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![glib::ParamSpecUInt::builder("service-number")
+                .nick("Service Number")
+                .blurb("The CEA-708 caption service to decode")
+                .minimum(1)
+                .maximum(MAX_SERVICE_NUMBER as u32)
+                .default_value(DEFAULT_SERVICE_NUMBER)
+                .mutable_ready()
+                .build()]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "service-number" => {
+                self.settings.lock().unwrap().service_number =
+                    value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "service-number" => {
+                let settings = self.settings.lock().unwrap();
+                settings.service_number.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for Cea708ToTt {}
+
+impl ElementImpl for Cea708ToTt {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "CEA-708 to TT",
+                "Generic",
+                "Converts CEA-708 Closed Captions to SRT/VTT timed text",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let mut caps = gst::Caps::new_empty();
+            {
+                let caps = caps.get_mut().unwrap();
+
+                // WebVTT
+                let s = gst::Structure::builder("application/x-subtitle-vtt").build();
+                caps.append_structure(s);
+
+                // SRT
+                let s = gst::Structure::builder("application/x-subtitle").build();
+                caps.append_structure(s);
+
+                // Raw timed text
+                let s = gst::Structure::builder("text/x-raw")
+                    .field("format", "utf8")
+                    .build();
+                caps.append_structure(s);
+            }
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let caps = gst::Caps::builder("closedcaption/x-cea-708")
+                .field("format", gst::List::new(["cc_data", "cdp"]))
+                .build();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    #[allow(clippy::single_match)]
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::ReadyToPaused => {
+                let mut state = self.state.borrow_mut();
+                *state = State::new(&self.settings.lock().unwrap());
+            }
+            _ => (),
+        }
+
+        let ret = self.parent_change_state(element, transition)?;
+
+        match transition {
+            gst::StateChange::PausedToReady => {
+                let mut state = self.state.borrow_mut();
+                *state = State::new(&self.settings.lock().unwrap());
+            }
+            _ => (),
+        }
+
+        Ok(ret)
+    }
+}
@@ -23,11 +23,19 @@ use gst::prelude::*;
 mod ffi;
 
 mod caption_frame;
+mod caption_validator;
+mod cccombiner;
+mod ccconverter;
 mod ccdetect;
+mod ccextractor;
 mod ccutils;
+mod cea608mux;
 mod cea608overlay;
 mod cea608tojson;
 mod cea608tott;
+mod cea708;
+mod cea708tojson;
+mod cea708tott;
 mod jsontovtt;
 mod line_reader;
 mod mcc_enc;
@@ -35,14 +43,28 @@ mod mcc_parse;
 mod parser_utils;
 mod scc_enc;
 mod scc_parse;
+mod srtparse;
+mod subtitleparse_common;
 mod transcriberbin;
+mod ttml;
+mod ttmlenc;
+mod ttmlparse;
 mod tttocea608;
+mod tttocea708;
 mod tttojson;
 mod ttutils;
+mod vtt_srt_parser;
+mod vttparse;
 
 fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     #[cfg(feature = "doc")]
-    ttutils::Cea608Mode::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    {
+        ttutils::Cea608Mode::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
+        caption_validator::Validation::static_type()
+            .mark_as_plugin_api(gst::PluginAPIFlags::empty());
+        tttocea708::TtToCea708SinkPad::static_type()
+            .mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    }
     mcc_parse::register(plugin)?;
     mcc_enc::register(plugin)?;
     scc_parse::register(plugin)?;
@@ -50,10 +72,21 @@ fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     cea608tott::register(plugin)?;
     tttocea608::register(plugin)?;
     cea608overlay::register(plugin)?;
+    cea608mux::register(plugin)?;
     ccdetect::register(plugin)?;
     tttojson::register(plugin)?;
     cea608tojson::register(plugin)?;
+    cea708tott::register(plugin)?;
+    cea708tojson::register(plugin)?;
+    tttocea708::register(plugin)?;
     jsontovtt::register(plugin)?;
+    cccombiner::register(plugin)?;
+    ccextractor::register(plugin)?;
+    ccconverter::register(plugin)?;
+    vttparse::register(plugin)?;
+    srtparse::register(plugin)?;
+    ttmlenc::register(plugin)?;
+    ttmlparse::register(plugin)?;
     transcriberbin::register(plugin)?;
     Ok(())
 }
@@ -20,6 +20,11 @@ use std::io::Write;
 use std::sync::Mutex;
 
 use super::headers::*;
+use crate::caption_validator::{post_issues, set_timecode_meta, Issue, Validation, Validator};
+use crate::ccutils::{cdp_frame_rate, write_cdp};
+use crate::parser_utils::TimeCode;
+
+const DEFAULT_VALIDATION: Validation = Validation::Disabled;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Format {
@@ -30,22 +35,37 @@ enum Format {
 #[derive(Debug)]
 struct State {
     format: Option<Format>,
+    framerate: Option<gst::Fraction>,
     need_headers: bool,
+    validator: Option<Validator>,
 }
 
 impl Default for State {
     fn default() -> Self {
         Self {
             format: None,
+            framerate: None,
             need_headers: true,
+            validator: None,
         }
     }
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Debug, Clone)]
 struct Settings {
     uuid: Option<String>,
     creation_date: Option<glib::DateTime>,
+    validation: Validation,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            uuid: None,
+            creation_date: None,
+            validation: DEFAULT_VALIDATION,
+        }
+    }
 }
 
 pub struct MccEnc {
@@ -244,6 +264,114 @@ impl MccEnc {
         }
     }
 
+    // Empty caption data for a frame missing before `buffer`, with its
+    // timecode meta set to `tc`
+    fn missing_frame(
+        state: &State,
+        buffer: &gst::BufferRef,
+        tc: &TimeCode,
+        sequence: u16,
+    ) -> gst::Buffer {
+        let data = match state.format {
+            Some(Format::Cea708Cdp) => {
+                let (frame_rate_code, cc_count) = state
+                    .framerate
+                    .and_then(|framerate| cdp_frame_rate(framerate.numer(), framerate.denom()))
+                    .expect("Filling missing frames at a frame rate without CDP");
+                write_cdp(frame_rate_code, sequence, None, &[], cc_count)
+            }
+            // Field 1 padding
+            _ => vec![0x80, 0x80, 0x80],
+        };
+
+        let mut outbuf = gst::Buffer::from_mut_slice(data);
+        {
+            let outbuf = outbuf.get_mut().unwrap();
+            buffer
+                .copy_into(outbuf, gst::BUFFER_COPY_METADATA, 0, None)
+                .expect("Failed to copy buffer metadata");
+            set_timecode_meta(outbuf, tc);
+        }
+
+        outbuf
+    }
+
+    // Validate the timecode and caption data of the buffer, and replace them
+    // with the repaired ones if needed. When repairing, the frames missing
+    // before the buffer are returned first, as MCC files must be contiguous.
+    fn validate(
+        &self,
+        element: &super::MccEnc,
+        state: &mut State,
+        buffer: gst::Buffer,
+        issues: &mut Vec<Issue>,
+    ) -> Result<Vec<gst::Buffer>, gst::FlowError> {
+        let (validator, format) = match (state.validator.as_mut(), state.format) {
+            (Some(validator), Some(format)) => (validator, format),
+            _ => return Ok(vec![buffer]),
+        };
+
+        let received = match buffer.meta::<gst_video::VideoTimeCodeMeta>() {
+            Some(meta) => TimeCode::from(&meta.tc()),
+            // Errors out later
+            None => return Ok(vec![buffer]),
+        };
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::element_error!(
+                element,
+                gst::StreamError::Format,
+                ["Failed to map buffer readable"]
+            );
+
+            gst::FlowError::Error
+        })?;
+
+        let mut tc = received.clone();
+        let mut data = map.to_vec();
+        match format {
+            Format::Cea708Cdp => validator.cdp_frame(&mut tc, &mut data, issues),
+            Format::Cea608 => validator.s334_1a_frame(&mut tc, &mut data, issues),
+        }
+
+        // Continue the CDP sequence counter up to the buffer's
+        let missing = validator.take_missing_frames();
+        let sequence = match data.get(5..7) {
+            Some(sequence) if format == Format::Cea708Cdp => {
+                u16::from_be_bytes([sequence[0], sequence[1]])
+            }
+            _ => 0,
+        };
+        let state = &*state;
+        let mut frames = missing
+            .iter()
+            .enumerate()
+            .map(|(i, missing_tc)| {
+                let sequence = sequence.wrapping_sub((missing.len() - i) as u16);
+                Self::missing_frame(state, &buffer, missing_tc, sequence)
+            })
+            .collect::<Vec<_>>();
+
+        if tc == received && data[..] == map[..] {
+            drop(map);
+            frames.push(buffer);
+            return Ok(frames);
+        }
+        drop(map);
+
+        let mut outbuf = gst::Buffer::from_mut_slice(data);
+        {
+            let outbuf = outbuf.get_mut().unwrap();
+            buffer
+                .copy_into(outbuf, gst::BUFFER_COPY_METADATA, 0, None)
+                .expect("Failed to copy buffer metadata");
+            set_timecode_meta(outbuf, &tc);
+        }
+        frames.push(outbuf);
+
+        Ok(frames)
+    }
+
     fn generate_caption(
         &self,
         element: &super::MccEnc,
@@ -319,6 +447,15 @@ impl MccEnc {
     ) -> Result<gst::FlowSuccess, gst::FlowError> {
         gst::log!(CAT, obj: pad, "Handling buffer {:?}", buffer);
 
+        let mut issues = Vec::new();
+        let res = {
+            let mut state = self.state.lock().unwrap();
+            self.validate(element, &mut state, buffer, &mut issues)
+        };
+        post_issues(element, *CAT, issues);
+        let mut buffers = res?;
+        let buffer = buffers.pop().unwrap();
+
         let mut state = self.state.lock().unwrap();
 
         let mut outbuf = Vec::new();
@@ -327,6 +464,9 @@ impl MccEnc {
             self.generate_headers(&*state, &mut outbuf)?;
         }
 
+        for missing in &buffers {
+            self.generate_caption(element, &*state, missing, &mut outbuf)?;
+        }
         self.generate_caption(element, &*state, &buffer, &mut outbuf)?;
 
         let mut buf = gst::Buffer::from_mut_slice(outbuf);
@@ -356,12 +496,32 @@ impl MccEnc {
                     err => panic!("MccEnc::sink_event caps: {:?}", err),
                 };
 
+                let validation = self.settings.lock().unwrap().validation;
+
                 let mut state = self.state.lock().unwrap();
                 if s.name() == "closedcaption/x-cea-608" {
                     state.format = Some(Format::Cea608);
                 } else {
                     state.format = Some(Format::Cea708Cdp);
                 }
+                state.framerate = Some(framerate);
+                state.validator = match validation {
+                    Validation::Disabled => None,
+                    validation => {
+                        let mut validator = Validator::new(
+                            framerate.numer(),
+                            framerate.denom(),
+                            true,
+                            validation == Validation::Repair,
+                        );
+                        // Missing frames are filled with empty caption data
+                        validator.set_fill_missing(
+                            state.format == Some(Format::Cea608)
+                                || cdp_frame_rate(framerate.numer(), framerate.denom()).is_some(),
+                        );
+                        Some(validator)
+                    }
+                };
                 drop(state);
 
                 // We send our own caps downstream
@@ -486,6 +646,15 @@ impl ObjectImpl for MccEnc {
                     .blurb("Creation date for the output file")
                     .mutable_ready()
                     .build(),
+                glib::ParamSpecEnum::builder::<Validation>("validation", DEFAULT_VALIDATION)
+                    .nick("Validation")
+                    .blurb(
+                        "Whether to check the timecodes, control code doubling and bandwidth \
+                of the captions, posting a caption-validation element message for each \
+                issue, and whether to repair them",
+                    )
+                    .mutable_ready()
+                    .build(),
             ]
         });
 
@@ -508,6 +677,10 @@ impl ObjectImpl for MccEnc {
                 let mut settings = self.settings.lock().unwrap();
                 settings.creation_date = value.get().expect("type checked upstream");
             }
+            "validation" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.validation = value.get().expect("type checked upstream");
+            }
             _ => unimplemented!(),
         }
     }
@@ -522,6 +695,10 @@ impl ObjectImpl for MccEnc {
                 let settings = self.settings.lock().unwrap();
                 settings.creation_date.to_value()
             }
+            "validation" => {
+                let settings = self.settings.lock().unwrap();
+                settings.validation.to_value()
+            }
             _ => unimplemented!(),
         }
     }
@@ -18,9 +18,12 @@ use std::sync::{Mutex, MutexGuard};
 use once_cell::sync::Lazy;
 
 use super::parser::{MccLine, MccParser};
+use crate::caption_validator::{post_issues, Validation, Validator};
 use crate::line_reader::LineReader;
 use crate::parser_utils::TimeCode;
 
+const DEFAULT_VALIDATION: Validation = Validation::Disabled;
+
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
         "mccparse",
@@ -35,6 +38,19 @@ enum Format {
     Cea608,
 }
 
+#[derive(Debug, Clone)]
+struct Settings {
+    validation: Validation,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            validation: DEFAULT_VALIDATION,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct PullState {
     need_stream_start: bool,
@@ -65,6 +81,7 @@ struct State {
     last_position: Option<gst::ClockTime>,
     last_timecode: Option<gst_video::ValidVideoTimeCode>,
     timecode_rate: Option<(u8, bool)>,
+    validator: Option<Validator>,
     segment: gst::FormattedSegment<gst::ClockTime>,
 
     // Pull mode
@@ -91,6 +108,7 @@ impl Default for State {
             last_position: None,
             last_timecode: None,
             timecode_rate: None,
+            validator: None,
             segment: gst::FormattedSegment::new(),
             pull: None,
             seeking: false,
@@ -331,6 +349,7 @@ pub struct MccParse {
     srcpad: gst::Pad,
     sinkpad: gst::Pad,
     state: Mutex<State>,
+    settings: Mutex<Settings>,
 }
 
 #[derive(Debug)]
@@ -527,12 +546,44 @@ impl MccParse {
     fn handle_line(
         &self,
         element: &super::MccParse,
-        tc: TimeCode,
-        data: Vec<u8>,
+        mut tc: TimeCode,
+        mut data: Vec<u8>,
         format: Format,
         mut state: MutexGuard<State>,
     ) -> Result<MutexGuard<State>, gst::FlowError> {
         let (framerate, drop_frame) = parse_timecode_rate(state.timecode_rate)?;
+
+        let mut issues = Vec::new();
+        let validation = self.settings.lock().unwrap().validation;
+        if validation != Validation::Disabled {
+            // Frames missing from the file are only reported, the parser
+            // can't make up their caption data
+            let validator = state.validator.get_or_insert_with(|| {
+                Validator::new(
+                    framerate.numer(),
+                    framerate.denom(),
+                    true,
+                    validation == Validation::Repair,
+                )
+            });
+
+            // The drop frame flag is part of the timecode rate
+            tc.drop_frame = drop_frame;
+
+            let len = data[2] as usize;
+            let mut payload = data[3..3 + len].to_vec();
+            match format {
+                Format::Cea708Cdp => validator.cdp_frame(&mut tc, &mut payload, &mut issues),
+                Format::Cea608 => validator.s334_1a_frame(&mut tc, &mut payload, &mut issues),
+            }
+
+            if payload[..] != data[3..3 + len] {
+                data.truncate(2);
+                data.push(payload.len() as u8);
+                data.extend_from_slice(&payload);
+            }
+        }
+
         let events = state.create_events(element, Some(format), framerate);
         let timecode = state.handle_timecode(element, framerate, drop_frame, tc)?;
 
@@ -557,6 +608,8 @@ impl MccParse {
         // Drop our state mutex while we push out buffers or events
         drop(state);
 
+        post_issues(element, *CAT, issues);
+
         for event in events {
             gst::debug!(CAT, obj: element, "Pushing event {:?}", event);
             self.srcpad.push_event(event);
@@ -886,6 +939,7 @@ impl MccParse {
         state.last_position = None;
         state.last_timecode = None;
         state.timecode_rate = None;
+        state.validator = None;
         state.last_raw_line = [].to_vec();
 
         drop(state);
@@ -1163,6 +1217,7 @@ impl ObjectSubclass for MccParse {
             srcpad,
             sinkpad,
             state: Mutex::new(State::default()),
+            settings: Mutex::new(Settings::default()),
         }
     }
 }
@@ -1174,6 +1229,50 @@ impl ObjectImpl for MccParse {
         obj.add_pad(&self.sinkpad).unwrap();
         obj.add_pad(&self.srcpad).unwrap();
     }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecEnum::builder::<Validation>("validation", DEFAULT_VALIDATION)
+                    .nick("Validation")
+                    .blurb(
+                        "Whether to check the timecodes, control code doubling and bandwidth \
+                of the captions, posting a caption-validation element message for each \
+                issue, and whether to repair them",
+                    )
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "validation" => {
+                self.settings.lock().unwrap().validation =
+                    value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "validation" => {
+                let settings = self.settings.lock().unwrap();
+                settings.validation.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
 }
 
 impl GstObjectImpl for MccParse {}
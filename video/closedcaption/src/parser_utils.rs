@@ -7,6 +7,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use nom::IResult;
+use std::fmt;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TimeCode {
@@ -17,6 +18,20 @@ pub struct TimeCode {
     pub drop_frame: bool,
 }
 
+impl fmt::Display for TimeCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours,
+            self.minutes,
+            self.seconds,
+            if self.drop_frame { ';' } else { ':' },
+            self.frames
+        )
+    }
+}
+
 /// Parser for parsing a run of ASCII, decimal digits and converting them into a `u32`
 pub fn digits(s: &[u8]) -> IResult<&[u8], u32> {
     use nom::bytes::complete::take_while;
@@ -18,7 +18,11 @@ use once_cell::sync::Lazy;
 use std::io::Write;
 use std::sync::Mutex;
 
+use crate::caption_validator::{post_issues, set_timecode_meta, Issue, Validation, Validator};
+use crate::parser_utils::TimeCode;
+
 const DEFAULT_OUTPUT_PADDING: bool = true;
+const DEFAULT_VALIDATION: Validation = Validation::Disabled;
 
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
@@ -31,12 +35,14 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
 #[derive(Clone, Debug)]
 struct Settings {
     output_padding: bool,
+    validation: Validation,
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
             output_padding: DEFAULT_OUTPUT_PADDING,
+            validation: DEFAULT_VALIDATION,
         }
     }
 }
@@ -47,6 +53,7 @@ struct State {
     expected_timecode: Option<ValidVideoTimeCode>,
     internal_buffer: Vec<gst::Buffer>,
     framerate: Option<gst::Fraction>,
+    validator: Option<Validator>,
     settings: Settings,
 }
 
@@ -57,6 +64,7 @@ impl Default for State {
             expected_timecode: None,
             internal_buffer: Vec::with_capacity(64),
             framerate: None,
+            validator: None,
             settings: Settings::default(),
         }
     }
@@ -74,10 +82,57 @@ impl State {
         write!(outbuf, "{:02x}{:02x}", slice[0], slice[1]).unwrap();
     }
 
+    // Validate the pair and timecode of the buffer, and replace them with
+    // the repaired ones if needed
+    fn validate(
+        &mut self,
+        element: &super::SccEnc,
+        mut buffer: gst::Buffer,
+        issues: &mut Vec<Issue>,
+    ) -> Result<gst::Buffer, gst::FlowError> {
+        let validator = match self.validator.as_mut() {
+            Some(validator) => validator,
+            None => return Ok(buffer),
+        };
+
+        let received = match buffer.meta::<gst_video::VideoTimeCodeMeta>() {
+            Some(meta) => TimeCode::from(&meta.tc()),
+            // Errors out later
+            None => return Ok(buffer),
+        };
+
+        let received_pair = {
+            let map = buffer.map_readable().map_err(|_| {
+                gst::element_error!(
+                    element,
+                    gst::StreamError::Format,
+                    ["Failed to map buffer readable"]
+                );
+
+                gst::FlowError::Error
+            })?;
+
+            [map[0], map[1]]
+        };
+
+        let mut tc = received.clone();
+        let mut pair = received_pair;
+        validator.cea608_frame(&mut tc, &mut pair, issues);
+
+        if tc != received || pair != received_pair {
+            let buffer = buffer.make_mut();
+            buffer.copy_from_slice(0, &pair).unwrap();
+            set_timecode_meta(buffer, &tc);
+        }
+
+        Ok(buffer)
+    }
+
     fn generate_caption(
         &mut self,
         element: &super::SccEnc,
         buffer: gst::Buffer,
+        issues: &mut Vec<Issue>,
     ) -> Result<Option<gst::Buffer>, gst::FlowError> {
         // Arbitrary number that was chosen to keep in order
         // to batch pushes of smaller buffers
@@ -95,6 +150,8 @@ impl State {
             return Err(gst::FlowError::Error);
         };
 
+        let buffer = self.validate(element, buffer, issues)?;
+
         if !self.settings.output_padding {
             let map = buffer.map_readable().map_err(|_| {
                 gst::element_error!(
@@ -162,6 +219,22 @@ impl State {
         Ok(None)
     }
 
+    // Append the repetition of a trailing single control code before the
+    // last line is written
+    fn finish_validation(&mut self, issues: &mut Vec<Issue>) {
+        let (validator, timecode) = match (self.validator.as_mut(), self.expected_timecode.as_ref())
+        {
+            (Some(validator), Some(timecode)) => (validator, timecode),
+            _ => return,
+        };
+
+        if let Some(pair) = validator.finish_cea608(&TimeCode::from(timecode), issues) {
+            let mut buffer = gst::Buffer::from_mut_slice(pair.to_vec());
+            gst_video::VideoTimeCodeMeta::add(buffer.get_mut().unwrap(), timecode);
+            self.internal_buffer.push(buffer);
+        }
+    }
+
     // Flush the internal buffers into a line
     fn write_line(
         &mut self,
@@ -257,12 +330,15 @@ impl SccEnc {
         gst::log!(CAT, obj: pad, "Handling buffer {:?}", buffer);
 
         let mut state = self.state.lock().unwrap();
-        let res = state.generate_caption(element, buffer)?;
+        let mut issues = Vec::new();
+        let res = state.generate_caption(element, buffer, &mut issues);
+        drop(state);
+
+        post_issues(element, *CAT, issues);
 
-        if let Some(outbuf) = res {
+        if let Some(outbuf) = res? {
             gst::trace!(CAT, obj: pad, "Pushing buffer {:?} to the pad", &outbuf);
 
-            drop(state);
             self.srcpad.push(outbuf)?;
         };
 
@@ -289,6 +365,15 @@ impl SccEnc {
 
                 let mut state = self.state.lock().unwrap();
                 state.framerate = framerate;
+                state.validator = match (state.settings.validation, framerate) {
+                    (Validation::Disabled, _) | (_, None) => None,
+                    (validation, Some(framerate)) => Some(Validator::new(
+                        framerate.numer(),
+                        framerate.denom(),
+                        false,
+                        validation == Validation::Repair,
+                    )),
+                };
 
                 // We send our own caps downstream
                 let caps = gst::Caps::builder("application/x-scc").build();
@@ -297,7 +382,10 @@ impl SccEnc {
             EventView::Eos(_) => {
                 let mut state = self.state.lock().unwrap();
 
+                let mut issues = Vec::new();
+                state.finish_validation(&mut issues);
                 let outbuf = state.write_line(element);
+                post_issues(element, *CAT, issues);
 
                 if let Ok(Some(buffer)) = outbuf {
                     gst::trace!(CAT, obj: pad, "Pushing buffer {:?} to the pad", &buffer);
@@ -418,16 +506,27 @@ impl ObjectImpl for SccEnc {
 
     fn properties() -> &'static [glib::ParamSpec] {
         static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
-            vec![glib::ParamSpecBoolean::builder("output-padding")
-                .nick("Output padding")
-                .blurb(
-                    "Whether the encoder should output padding captions. \
+            vec![
+                glib::ParamSpecBoolean::builder("output-padding")
+                    .nick("Output padding")
+                    .blurb(
+                        "Whether the encoder should output padding captions. \
                 The element will never add padding, but will encode padding \
                 buffers it receives if this property is set to true.",
-                )
-                .default_value(DEFAULT_OUTPUT_PADDING)
-                .mutable_ready()
-                .build()]
+                    )
+                    .default_value(DEFAULT_OUTPUT_PADDING)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecEnum::builder::<Validation>("validation", DEFAULT_VALIDATION)
+                    .nick("Validation")
+                    .blurb(
+                        "Whether to check the timecodes, control code doubling and bandwidth \
+                of the captions, posting a caption-validation element message for each \
+                issue, and whether to repair them",
+                    )
+                    .mutable_ready()
+                    .build(),
+            ]
         });
 
         PROPERTIES.as_ref()
@@ -445,6 +544,10 @@ impl ObjectImpl for SccEnc {
                 self.settings.lock().unwrap().output_padding =
                     value.get().expect("type checked upstream");
             }
+            "validation" => {
+                self.settings.lock().unwrap().validation =
+                    value.get().expect("type checked upstream");
+            }
             _ => unimplemented!(),
         }
     }
@@ -455,6 +558,10 @@ impl ObjectImpl for SccEnc {
                 let settings = self.settings.lock().unwrap();
                 settings.output_padding.to_value()
             }
+            "validation" => {
+                let settings = self.settings.lock().unwrap();
+                settings.validation.to_value()
+            }
             _ => unimplemented!(),
         }
     }
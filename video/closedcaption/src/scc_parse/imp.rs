@@ -18,9 +18,12 @@ use std::sync::{Mutex, MutexGuard};
 use once_cell::sync::Lazy;
 
 use super::parser::{SccLine, SccParser};
+use crate::caption_validator::{post_issues, Validation, Validator};
 use crate::line_reader::LineReader;
 use crate::parser_utils::TimeCode;
 
+const DEFAULT_VALIDATION: Validation = Validation::Disabled;
+
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
         "sccparse",
@@ -29,6 +32,19 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     )
 });
 
+#[derive(Debug, Clone)]
+struct Settings {
+    validation: Validation,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            validation: DEFAULT_VALIDATION,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct PullState {
     need_stream_start: bool,
@@ -57,6 +73,7 @@ struct State {
     framerate: Option<gst::Fraction>,
     last_position: Option<gst::ClockTime>,
     last_timecode: Option<gst_video::ValidVideoTimeCode>,
+    validator: Option<Validator>,
     segment: gst::FormattedSegment<gst::ClockTime>,
 
     // Pull mode
@@ -79,6 +96,7 @@ impl Default for State {
             framerate: None,
             last_position: None,
             last_timecode: None,
+            validator: None,
             segment: gst::FormattedSegment::new(),
             pull: None,
             seeking: false,
@@ -277,6 +295,7 @@ pub struct SccParse {
     srcpad: gst::Pad,
     sinkpad: gst::Pad,
     state: Mutex<State>,
+    settings: Mutex<Settings>,
 }
 
 impl SccParse {
@@ -334,8 +353,8 @@ impl SccParse {
 
     fn handle_line(
         &self,
-        tc: TimeCode,
-        data: Vec<u8>,
+        mut tc: TimeCode,
+        mut data: Vec<u8>,
         element: &super::SccParse,
         mut state: MutexGuard<State>,
     ) -> Result<MutexGuard<State>, gst::FlowError> {
@@ -347,6 +366,21 @@ impl SccParse {
             data.len()
         );
 
+        let mut issues = Vec::new();
+        let validation = self.settings.lock().unwrap().validation;
+        if validation != Validation::Disabled && !state.seeking {
+            // The frame rate of the first line is used for the whole file
+            let validator = state.validator.get_or_insert_with(|| {
+                let (fps_n, fps_d) = if tc.drop_frame {
+                    (30000, 1001)
+                } else {
+                    (30, 1)
+                };
+                Validator::new(fps_n, fps_d, false, validation == Validation::Repair)
+            });
+            validator.scc_line(&mut tc, &mut data, &mut issues);
+        }
+
         // The framerate is defined as 30 or 30000/1001 according to:
         // http://www.theneitherworld.com/mcpoodle/SCC_TOOLS/DOCS/SCC_FORMAT.HTML
         let framerate = if tc.drop_frame {
@@ -448,6 +482,8 @@ impl SccParse {
         // Drop our state mutex while we push out buffers or events
         drop(state);
 
+        post_issues(element, *CAT, issues);
+
         for event in events {
             gst::debug!(CAT, obj: element, "Pushing event {:?}", event);
             self.srcpad.push_event(event);
@@ -765,6 +801,9 @@ impl SccParse {
         state.pending_events.clear();
         state.last_position = None;
         state.last_timecode = None;
+        if let Some(validator) = &mut state.validator {
+            validator.reset();
+        }
 
         drop(state);
 
@@ -1041,6 +1080,7 @@ impl ObjectSubclass for SccParse {
             srcpad,
             sinkpad,
             state: Mutex::new(State::default()),
+            settings: Mutex::new(Settings::default()),
         }
     }
 }
@@ -1052,6 +1092,50 @@ impl ObjectImpl for SccParse {
         obj.add_pad(&self.sinkpad).unwrap();
         obj.add_pad(&self.srcpad).unwrap();
     }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecEnum::builder::<Validation>("validation", DEFAULT_VALIDATION)
+                    .nick("Validation")
+                    .blurb(
+                        "Whether to check the timecodes, control code doubling and bandwidth \
+                of the captions, posting a caption-validation element message for each \
+                issue, and whether to repair them",
+                    )
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "validation" => {
+                self.settings.lock().unwrap().validation =
+                    value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "validation" => {
+                let settings = self.settings.lock().unwrap();
+                settings.validation.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
 }
 
 impl GstObjectImpl for SccParse {}
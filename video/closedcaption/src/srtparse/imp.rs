@@ -0,0 +1,182 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::loggable_error;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+use crate::subtitleparse_common::SubtitleParse;
+use crate::vtt_srt_parser::Format;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "srtparse",
+        gst::DebugColorFlags::empty(),
+        Some("SubRip Parser Element"),
+    )
+});
+
+pub struct SrtParse {
+    parse: SubtitleParse,
+}
+
+fn parse(element: &gst::Element) -> &SubtitleParse {
+    &element
+        .downcast_ref::<super::SrtParse>()
+        .unwrap()
+        .imp()
+        .parse
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for SrtParse {
+    const NAME: &'static str = "RsSrtParse";
+    type Type = super::SrtParse;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .activate_function(|pad, parent| {
+                SrtParse::catch_panic_pad_function(
+                    parent,
+                    || Err(loggable_error!(CAT, "Panic activating sink pad")),
+                    |imp, element| imp.parse.sink_activate(pad, element.upcast_ref()),
+                )
+            })
+            .activatemode_function(|pad, parent, mode, active| {
+                SrtParse::catch_panic_pad_function(
+                    parent,
+                    || Err(loggable_error!(CAT, "Panic activating sink pad with mode")),
+                    |imp, element| {
+                        imp.parse
+                            .sink_activatemode(pad, element.upcast_ref(), mode, active)
+                    },
+                )
+            })
+            .chain_function(|pad, parent, buffer| {
+                SrtParse::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |imp, element| imp.parse.sink_chain(pad, element.upcast_ref(), buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                SrtParse::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |imp, element| imp.parse.sink_event(pad, element.upcast_ref(), event),
+                )
+            })
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .event_function(|pad, parent, event| {
+                SrtParse::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |imp, element| imp.parse.src_event(pad, element.upcast_ref(), event),
+                )
+            })
+            .query_function(|pad, parent, query| {
+                SrtParse::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |imp, element| imp.parse.src_query(pad, element.upcast_ref(), query),
+                )
+            })
+            .build();
+
+        Self {
+            parse: SubtitleParse::new(*CAT, Format::Srt, parse, sinkpad, srcpad),
+        }
+    }
+}
+
+impl ObjectImpl for SrtParse {
+    fn properties() -> &'static [glib::ParamSpec] {
+        SubtitleParse::properties()
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(self.parse.sinkpad()).unwrap();
+        obj.add_pad(self.parse.srcpad()).unwrap();
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        self.parse.set_property(value, pspec)
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        self.parse.property(pspec)
+    }
+}
+
+impl GstObjectImpl for SrtParse {}
+
+impl ElementImpl for SrtParse {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "SubRip Parse",
+                "Parser/Subtitle",
+                "Parses SubRip subtitle files to timed text or CEA-608 JSON",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &SubtitleParse::src_caps(),
+            )
+            .unwrap();
+
+            let caps = gst::Caps::builder("application/x-subtitle").build();
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        self.parse.change_state(element.upcast_ref(), transition);
+
+        self.parent_change_state(element, transition)
+    }
+}
@@ -0,0 +1,930 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Shared implementation of the vttparse and srtparse elements.
+//!
+//! In push mode cues are output as soon as they are parsed. In pull mode the
+//! whole file is read first, which gives the duration and allows seeking.
+
+use gst::glib;
+use gst::prelude::*;
+use gst::{element_error, loggable_error};
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::line_reader::LineReader;
+use crate::ttutils::Cea608Mode;
+use crate::vtt_srt_parser::{cue_lines, cue_text, Cue, Format, SubtitleParser};
+
+const DEFAULT_MODE: Cea608Mode = Cea608Mode::PopOn;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    mode: Cea608Mode,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings { mode: DEFAULT_MODE }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Output {
+    Text,
+    Json,
+}
+
+#[derive(Debug)]
+struct PullState {
+    need_stream_start: bool,
+    stream_id: String,
+    /// All the cues of the file, sorted by start time
+    cues: Option<Vec<Cue>>,
+    index: usize,
+    duration: Option<gst::ClockTime>,
+}
+
+impl PullState {
+    fn new(element: &gst::Element, pad: &gst::Pad) -> Self {
+        Self {
+            need_stream_start: true,
+            stream_id: pad.create_stream_id(element, Some("src")).to_string(),
+            cues: None,
+            index: 0,
+            duration: gst::ClockTime::NONE,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    reader: LineReader<gst::MappedBuffer<gst::buffer::Readable>>,
+    parser: SubtitleParser,
+    output: Option<Output>,
+    need_caps: bool,
+    need_segment: bool,
+    pending_events: Vec<gst::Event>,
+    last_position: Option<gst::ClockTime>,
+    segment: gst::FormattedSegment<gst::ClockTime>,
+
+    // Pull mode
+    pull: Option<PullState>,
+
+    // seeking
+    discont: bool,
+    seek_seqnum: Option<gst::Seqnum>,
+    need_flush_stop: bool,
+}
+
+impl State {
+    fn new(format: Format) -> Self {
+        Self {
+            reader: LineReader::new(),
+            parser: SubtitleParser::new(format),
+            output: None,
+            need_caps: true,
+            need_segment: true,
+            pending_events: Vec::new(),
+            last_position: None,
+            segment: gst::FormattedSegment::new(),
+            pull: None,
+            discont: false,
+            seek_seqnum: None,
+            need_flush_stop: false,
+        }
+    }
+
+    fn create_events(
+        &mut self,
+        element: &gst::Element,
+        cat: gst::DebugCategory,
+    ) -> Vec<gst::Event> {
+        let mut events = Vec::new();
+
+        if self.need_flush_stop {
+            let mut b = gst::event::FlushStop::builder(true);
+
+            if let Some(seek_seqnum) = self.seek_seqnum {
+                b = b.seqnum(seek_seqnum);
+            }
+
+            events.push(b.build());
+            self.need_flush_stop = false;
+        }
+
+        if let Some(pull) = &mut self.pull {
+            if pull.need_stream_start {
+                events.push(gst::event::StreamStart::new(&pull.stream_id));
+                pull.need_stream_start = false;
+            }
+        }
+
+        if self.need_caps {
+            if let Some(output) = self.output {
+                let caps = match output {
+                    Output::Text => gst::Caps::builder("text/x-raw")
+                        .field("format", "utf8")
+                        .build(),
+                    Output::Json => gst::Caps::builder("application/x-json")
+                        .field("format", "cea608")
+                        .build(),
+                };
+
+                gst::info!(cat, obj: element, "Caps changed to {:?}", &caps);
+                events.push(gst::event::Caps::new(&caps));
+                self.need_caps = false;
+            }
+        }
+
+        if self.need_segment {
+            let mut b = gst::event::Segment::builder(&self.segment);
+
+            if let Some(seek_seqnum) = self.seek_seqnum {
+                b = b.seqnum(seek_seqnum);
+            }
+
+            events.push(b.build());
+            self.need_segment = false;
+        }
+
+        events.append(&mut self.pending_events);
+        events
+    }
+
+    fn flush(&mut self) {
+        self.reader.clear();
+        self.parser.reset();
+        if let Some(pull) = &mut self.pull {
+            // Keep the cues, they don't need to be parsed again
+            pull.index = 0;
+        }
+        self.segment = gst::FormattedSegment::new();
+        self.need_segment = true;
+        self.pending_events.clear();
+        self.last_position = None;
+    }
+}
+
+pub struct SubtitleParse {
+    cat: gst::DebugCategory,
+    format: Format,
+    /// Gets back to this from the element, for the pull mode task
+    imp: for<'a> fn(&'a gst::Element) -> &'a SubtitleParse,
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    state: Mutex<State>,
+    settings: Mutex<Settings>,
+}
+
+impl SubtitleParse {
+    pub fn new(
+        cat: gst::DebugCategory,
+        format: Format,
+        imp: for<'a> fn(&'a gst::Element) -> &'a SubtitleParse,
+        sinkpad: gst::Pad,
+        srcpad: gst::Pad,
+    ) -> Self {
+        Self {
+            cat,
+            format,
+            imp,
+            srcpad,
+            sinkpad,
+            state: Mutex::new(State::new(format)),
+            settings: Mutex::new(Settings::default()),
+        }
+    }
+
+    pub fn srcpad(&self) -> &gst::Pad {
+        &self.srcpad
+    }
+
+    pub fn sinkpad(&self) -> &gst::Pad {
+        &self.sinkpad
+    }
+
+    /// The caps of the source pad template
+    pub fn src_caps() -> gst::Caps {
+        let mut caps = gst::Caps::new_empty();
+        {
+            let caps = caps.get_mut().unwrap();
+
+            let s = gst::Structure::builder("text/x-raw")
+                .field("format", "utf8")
+                .build();
+            caps.append_structure(s);
+
+            let s = gst::Structure::builder("application/x-json")
+                .field("format", "cea608")
+                .build();
+            caps.append_structure(s);
+        }
+
+        caps
+    }
+
+    /// Picks the output format preferred downstream
+    fn negotiate(&self, element: &gst::Element) -> Result<(), gst::FlowError> {
+        if self.state.lock().unwrap().output.is_some() {
+            return Ok(());
+        }
+
+        let caps = self
+            .srcpad
+            .peer_query_caps(Some(&self.srcpad.pad_template_caps()));
+
+        let output = match caps.structure(0) {
+            Some(s) if s.name() == "application/x-json" => Output::Json,
+            Some(_) => Output::Text,
+            None => {
+                element_error!(
+                    element,
+                    gst::CoreError::Negotiation,
+                    ["Downstream accepts neither text nor JSON"]
+                );
+                return Err(gst::FlowError::NotNegotiated);
+            }
+        };
+
+        gst::debug!(self.cat, obj: element, "Outputting {:?}", output);
+
+        let mut state = self.state.lock().unwrap();
+        state.output = Some(output);
+        state.need_caps = true;
+
+        Ok(())
+    }
+
+    /// Parses all the complete lines, or all the remaining data when draining
+    fn parse_cues(&self, element: &gst::Element, state: &mut State, drain: bool) -> Vec<Cue> {
+        let State {
+            ref mut reader,
+            ref mut parser,
+            ..
+        } = *state;
+        let mut cues = Vec::new();
+
+        while let Some(line) = reader.line_with_drain(drain) {
+            match parser.parse_line(line) {
+                Ok(Some(cue)) => cues.push(cue),
+                Ok(None) => (),
+                Err(err) => {
+                    gst::warning!(self.cat, obj: element, "Skipping block: {}", err);
+                }
+            }
+        }
+
+        if drain {
+            cues.extend(parser.drain());
+        }
+
+        cues
+    }
+
+    fn create_buffer(
+        &self,
+        element: &gst::Element,
+        output: Output,
+        cue: &Cue,
+    ) -> Result<gst::Buffer, gst::FlowError> {
+        let data = match output {
+            Output::Text => cue_text(self.format, cue).into_bytes(),
+            Output::Json => {
+                let mode = self.settings.lock().unwrap().mode;
+                let lines = cue_lines(self.format, cue, mode);
+
+                serde_json::to_vec(&lines).map_err(|err| {
+                    element_error!(
+                        element,
+                        gst::ResourceError::Write,
+                        ["Failed to serialize as json {}", err]
+                    );
+
+                    gst::FlowError::Error
+                })?
+            }
+        };
+
+        let mut buffer = gst::Buffer::from_mut_slice(data);
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(cue.start);
+            buffer.set_duration(cue.end - cue.start);
+        }
+
+        Ok(buffer)
+    }
+
+    /// Outputs the cues inside the segment
+    fn handle_cues(
+        &self,
+        element: &gst::Element,
+        cues: Vec<Cue>,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        self.negotiate(element)?;
+
+        let mut state = self.state.lock().unwrap();
+        let output = state.output.unwrap();
+        let segment_start = state.segment.start();
+        let segment_stop = state.segment.stop();
+
+        let mut buffers = gst::BufferList::new();
+        let mut send_eos = false;
+
+        for cue in &cues {
+            if segment_start.map_or(false, |start| cue.start < start && cue.end <= start) {
+                gst::trace!(self.cat, obj: element, "Skip segment clipped cue {:?}", cue);
+                continue;
+            }
+
+            if segment_stop.map_or(false, |stop| cue.start >= stop) {
+                send_eos = true;
+                break;
+            }
+
+            gst::trace!(self.cat, obj: element, "Outputting cue {:?}", cue);
+
+            let mut buffer = self.create_buffer(element, output, cue)?;
+            if state.discont {
+                buffer
+                    .get_mut()
+                    .unwrap()
+                    .set_flags(gst::BufferFlags::DISCONT);
+                state.discont = false;
+            }
+
+            if state.last_position.map_or(true, |last| cue.start > last) {
+                state.last_position = Some(cue.start);
+            }
+
+            buffers.get_mut().unwrap().add(buffer);
+        }
+
+        let events = state.create_events(element, self.cat);
+
+        // Drop our state mutex while we push out buffers or events
+        drop(state);
+
+        for event in events {
+            gst::debug!(self.cat, obj: element, "Pushing event {:?}", event);
+            self.srcpad.push_event(event);
+        }
+
+        if !buffers.is_empty() {
+            self.srcpad.push_list(buffers).map_err(|err| {
+                if err != gst::FlowError::Flushing && err != gst::FlowError::Eos {
+                    gst::error!(self.cat, obj: element, "Pushing buffer returned {:?}", err);
+                }
+                err
+            })?;
+        }
+
+        if send_eos {
+            return Err(gst::FlowError::Eos);
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn handle_buffer(
+        &self,
+        element: &gst::Element,
+        buffer: Option<gst::Buffer>,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state = self.state.lock().unwrap();
+
+        let drain = if let Some(buffer) = buffer {
+            let buffer = buffer.into_mapped_buffer_readable().map_err(|_| {
+                element_error!(
+                    element,
+                    gst::ResourceError::Read,
+                    ["Failed to map buffer readable"]
+                );
+
+                gst::FlowError::Error
+            })?;
+
+            state.reader.push(buffer);
+            false
+        } else {
+            true
+        };
+
+        let cues = self.parse_cues(element, &mut state, drain);
+        drop(state);
+
+        if cues.is_empty() {
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        self.handle_cues(element, cues)
+    }
+
+    pub fn sink_activate(
+        &self,
+        pad: &gst::Pad,
+        element: &gst::Element,
+    ) -> Result<(), gst::LoggableError> {
+        let mode = {
+            let mut query = gst::query::Scheduling::new();
+            let mut state = self.state.lock().unwrap();
+
+            state.pull = None;
+
+            if !pad.peer_query(&mut query) {
+                gst::debug!(self.cat, obj: pad, "Scheduling query failed on peer");
+                gst::PadMode::Push
+            } else if query
+                .has_scheduling_mode_with_flags(gst::PadMode::Pull, gst::SchedulingFlags::SEEKABLE)
+            {
+                gst::debug!(self.cat, obj: pad, "Activating in Pull mode");
+
+                state.pull = Some(PullState::new(element, &self.srcpad));
+
+                gst::PadMode::Pull
+            } else {
+                gst::debug!(self.cat, obj: pad, "Activating in Push mode");
+                gst::PadMode::Push
+            }
+        };
+
+        pad.activate_mode(mode, true)?;
+        Ok(())
+    }
+
+    fn start_task(&self, element: &gst::Element) -> Result<(), gst::LoggableError> {
+        let element_weak = element.downgrade();
+        let pad_weak = self.sinkpad.downgrade();
+        let imp = self.imp;
+        let res = self.sinkpad.start_task(move || {
+            let element = match element_weak.upgrade() {
+                Some(element) => element,
+                None => {
+                    if let Some(pad) = pad_weak.upgrade() {
+                        let _ = pad.pause_task();
+                    }
+                    return;
+                }
+            };
+
+            imp(&element).loop_fn(&element);
+        });
+        if res.is_err() {
+            return Err(loggable_error!(self.cat, "Failed to start pad task"));
+        }
+        Ok(())
+    }
+
+    pub fn sink_activatemode(
+        &self,
+        _pad: &gst::Pad,
+        element: &gst::Element,
+        mode: gst::PadMode,
+        active: bool,
+    ) -> Result<(), gst::LoggableError> {
+        if mode == gst::PadMode::Pull {
+            if active {
+                self.start_task(element)?;
+            } else {
+                let _ = self.sinkpad.stop_task();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads and parses the whole file
+    fn read_cues(&self, element: &gst::Element) -> Result<Vec<Cue>, gst::FlowError> {
+        gst::debug!(self.cat, obj: element, "Reading all cues");
+
+        let mut reader = LineReader::new();
+        let mut parser = SubtitleParser::new(self.format);
+        let mut cues = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            let drain = match self.sinkpad.pull_range(offset, 4096) {
+                Ok(buffer) => {
+                    offset += buffer.size() as u64;
+                    let buffer = buffer.into_mapped_buffer_readable().map_err(|_| {
+                        element_error!(
+                            element,
+                            gst::ResourceError::Read,
+                            ["Failed to map buffer readable"]
+                        );
+
+                        gst::FlowError::Error
+                    })?;
+                    reader.push(buffer);
+                    false
+                }
+                Err(gst::FlowError::Eos) => true,
+                Err(flow) => return Err(flow),
+            };
+
+            while let Some(line) = reader.line_with_drain(drain) {
+                match parser.parse_line(line) {
+                    Ok(Some(cue)) => cues.push(cue),
+                    Ok(None) => (),
+                    Err(err) => {
+                        gst::warning!(self.cat, obj: element, "Skipping block: {}", err);
+                    }
+                }
+            }
+
+            if drain {
+                cues.extend(parser.drain());
+                break;
+            }
+        }
+
+        cues.sort_by_key(|cue| cue.start);
+
+        gst::debug!(self.cat, obj: element, "Read {} cues", cues.len());
+
+        Ok(cues)
+    }
+
+    fn push_eos(&self, element: &gst::Element) {
+        let mut state = self.state.lock().unwrap();
+
+        let mut events = state.create_events(element, self.cat);
+        let mut eos_event = gst::event::Eos::builder();
+
+        if let Some(seek_seqnum) = state.seek_seqnum {
+            eos_event = eos_event.seqnum(seek_seqnum);
+        }
+
+        events.push(eos_event.build());
+
+        // Drop our state mutex while we push out events
+        drop(state);
+
+        for event in events {
+            gst::debug!(self.cat, obj: element, "Pushing event {:?}", event);
+            self.srcpad.push_event(event);
+        }
+    }
+
+    fn loop_fn(&self, element: &gst::Element) {
+        let needs_cues = self
+            .state
+            .lock()
+            .unwrap()
+            .pull
+            .as_ref()
+            .unwrap()
+            .cues
+            .is_none();
+
+        if needs_cues {
+            match self.read_cues(element) {
+                Ok(cues) => {
+                    let mut state = self.state.lock().unwrap();
+                    let pull = state.pull.as_mut().unwrap();
+                    pull.duration = Some(
+                        cues.iter()
+                            .map(|cue| cue.end)
+                            .max()
+                            .unwrap_or(gst::ClockTime::ZERO),
+                    );
+                    pull.cues = Some(cues);
+                }
+                Err(gst::FlowError::Flushing) => {
+                    gst::debug!(self.cat, obj: &self.sinkpad, "Pausing after pulling buffer, reason: flushing");
+
+                    let _ = self.sinkpad.pause_task();
+                    return;
+                }
+                Err(flow) => {
+                    gst::error!(self.cat, obj: &self.sinkpad, "Failed to pull, reason: {:?}", flow);
+
+                    element_error!(
+                        element,
+                        gst::StreamError::Failed,
+                        ["Streaming stopped, failed to pull buffer"]
+                    );
+
+                    let _ = self.sinkpad.pause_task();
+                    return;
+                }
+            }
+        }
+
+        let cue = {
+            let mut state = self.state.lock().unwrap();
+            let pull = state.pull.as_mut().unwrap();
+            let cue = pull.cues.as_ref().unwrap().get(pull.index).cloned();
+            pull.index += 1;
+            cue
+        };
+
+        let res = match cue {
+            Some(cue) => self.handle_cues(element, vec![cue]),
+            None => Err(gst::FlowError::Eos),
+        };
+
+        if let Err(flow) = res {
+            match flow {
+                gst::FlowError::Flushing => {
+                    gst::debug!(self.cat, obj: element, "Pausing after flow {:?}", flow);
+                }
+                gst::FlowError::Eos => {
+                    self.push_eos(element);
+
+                    gst::debug!(self.cat, obj: element, "Pausing after flow {:?}", flow);
+                }
+                _ => {
+                    self.push_eos(element);
+
+                    gst::error!(self.cat, obj: element, "Pausing after flow {:?}", flow);
+
+                    element_error!(
+                        element,
+                        gst::StreamError::Failed,
+                        ["Streaming stopped, reason: {:?}", flow]
+                    );
+                }
+            }
+
+            let _ = self.sinkpad.pause_task();
+        }
+    }
+
+    pub fn sink_chain(
+        &self,
+        pad: &gst::Pad,
+        element: &gst::Element,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::log!(self.cat, obj: pad, "Handling buffer {:?}", buffer);
+
+        self.handle_buffer(element, Some(buffer))
+    }
+
+    pub fn sink_event(&self, pad: &gst::Pad, element: &gst::Element, event: gst::Event) -> bool {
+        use gst::EventView;
+
+        gst::log!(self.cat, obj: pad, "Handling event {:?}", event);
+
+        match event.view() {
+            EventView::Caps(_) => {
+                // We send a proper caps event from the chain function later
+                gst::log!(self.cat, obj: pad, "Dropping caps event");
+                true
+            }
+            EventView::Segment(_) => {
+                // We send a gst::Format::Time segment event later when needed
+                gst::log!(self.cat, obj: pad, "Dropping segment event");
+                true
+            }
+            EventView::FlushStop(_) => {
+                self.state.lock().unwrap().flush();
+
+                pad.event_default(Some(element), event)
+            }
+            EventView::Eos(_) => {
+                gst::log!(self.cat, obj: pad, "Draining");
+                if let Err(err) = self.handle_buffer(element, None) {
+                    gst::error!(self.cat, obj: pad, "Failed to drain parser: {:?}", err);
+                }
+
+                // Make sure downstream has caps and a segment before EOS
+                let _ = self.negotiate(element);
+                let events = self.state.lock().unwrap().create_events(element, self.cat);
+                for event in events {
+                    self.srcpad.push_event(event);
+                }
+
+                pad.event_default(Some(element), event)
+            }
+            _ => {
+                if event.is_sticky()
+                    && !self.srcpad.has_current_caps()
+                    && event.type_() > gst::EventType::Caps
+                {
+                    gst::log!(self.cat, obj: pad, "Deferring sticky event until we have caps");
+                    let mut state = self.state.lock().unwrap();
+                    state.pending_events.push(event);
+                    true
+                } else {
+                    pad.event_default(Some(element), event)
+                }
+            }
+        }
+    }
+
+    fn perform_seek(&self, event: &gst::event::Seek, element: &gst::Element) -> bool {
+        if self.state.lock().unwrap().pull.is_none() {
+            gst::error!(self.cat, obj: element, "seeking is only supported in pull mode");
+            return false;
+        }
+
+        let (rate, flags, start_type, start, stop_type, stop) = event.get();
+
+        let mut start: Option<gst::ClockTime> = match start.try_into() {
+            Ok(start) => start,
+            Err(_) => {
+                gst::error!(self.cat, obj: element, "seek has invalid format");
+                return false;
+            }
+        };
+
+        let mut stop: Option<gst::ClockTime> = match stop.try_into() {
+            Ok(stop) => stop,
+            Err(_) => {
+                gst::error!(self.cat, obj: element, "seek has invalid format");
+                return false;
+            }
+        };
+
+        if !flags.contains(gst::SeekFlags::FLUSH) {
+            gst::error!(self.cat, obj: element, "only flushing seeks are supported");
+            return false;
+        }
+
+        if start_type == gst::SeekType::End || stop_type == gst::SeekType::End {
+            gst::error!(self.cat, obj: element, "Relative seeks are not supported");
+            return false;
+        }
+
+        let seek_seqnum = event.seqnum();
+
+        let event = gst::event::FlushStart::builder()
+            .seqnum(seek_seqnum)
+            .build();
+
+        gst::debug!(self.cat, obj: element, "Sending event {:?} upstream", event);
+        self.sinkpad.push_event(event);
+
+        let event = gst::event::FlushStart::builder()
+            .seqnum(seek_seqnum)
+            .build();
+
+        gst::debug!(self.cat, obj: element, "Pushing event {:?}", event);
+        self.srcpad.push_event(event);
+
+        let _ = self.sinkpad.pause_task();
+
+        let mut state = self.state.lock().unwrap();
+        let pull = state.pull.as_ref().unwrap();
+
+        if start_type == gst::SeekType::Set {
+            start = start.opt_min(pull.duration).or(start);
+        }
+
+        if stop_type == gst::SeekType::Set {
+            stop = stop.opt_min(pull.duration).or(stop);
+        }
+
+        state.seek_seqnum = Some(seek_seqnum);
+        state.discont = true;
+        state.need_flush_stop = true;
+        state.flush();
+
+        let event = gst::event::FlushStop::builder(true)
+            .seqnum(seek_seqnum)
+            .build();
+
+        /* Drop our state while we push a serialized event upstream */
+        drop(state);
+
+        gst::debug!(self.cat, obj: element, "Sending event {:?} upstream", event);
+        self.sinkpad.push_event(event);
+
+        let mut state = self.state.lock().unwrap();
+
+        state
+            .segment
+            .do_seek(rate, flags, start_type, start, stop_type, stop);
+
+        drop(state);
+
+        match self.start_task(element) {
+            Err(error) => {
+                error.log();
+                false
+            }
+            _ => true,
+        }
+    }
+
+    pub fn src_event(&self, pad: &gst::Pad, element: &gst::Element, event: gst::Event) -> bool {
+        use gst::EventView;
+
+        gst::log!(self.cat, obj: pad, "Handling event {:?}", event);
+        match event.view() {
+            EventView::Seek(e) => self.perform_seek(e, element),
+            _ => pad.event_default(Some(element), event),
+        }
+    }
+
+    pub fn src_query(
+        &self,
+        pad: &gst::Pad,
+        element: &gst::Element,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        use gst::QueryViewMut;
+
+        gst::log!(self.cat, obj: pad, "Handling query {:?}", query);
+
+        match query.view_mut() {
+            QueryViewMut::Seeking(q) => {
+                let state = self.state.lock().unwrap();
+
+                if q.format() == gst::Format::Time {
+                    if let Some(pull) = state.pull.as_ref() {
+                        q.set(true, gst::ClockTime::ZERO, pull.duration);
+                        true
+                    } else {
+                        false
+                    }
+                } else {
+                    false
+                }
+            }
+            QueryViewMut::Position(q) => {
+                // For Time answer ourselfs, otherwise forward
+                if q.format() == gst::Format::Time {
+                    let state = self.state.lock().unwrap();
+                    q.set(state.last_position);
+                    true
+                } else {
+                    self.sinkpad.peer_query(query)
+                }
+            }
+            QueryViewMut::Duration(q) => {
+                // For Time answer ourselfs, otherwise forward
+                if q.format() == gst::Format::Time {
+                    let state = self.state.lock().unwrap();
+                    match state.pull.as_ref().and_then(|pull| pull.duration) {
+                        Some(duration) => {
+                            q.set(duration);
+                            true
+                        }
+                        None => false,
+                    }
+                } else {
+                    self.sinkpad.peer_query(query)
+                }
+            }
+            _ => pad.query_default(Some(element), query),
+        }
+    }
+
+    pub fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecEnum::builder::<Cea608Mode>("mode", DEFAULT_MODE)
+                    .nick("Mode")
+                    .blurb("CEA-608 mode of the JSON output")
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    pub fn set_property(&self, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "mode" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.mode = value.get::<Cea608Mode>().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    pub fn property(&self, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "mode" => {
+                let settings = self.settings.lock().unwrap();
+                settings.mode.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    pub fn change_state(&self, element: &gst::Element, transition: gst::StateChange) {
+        gst::trace!(self.cat, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::ReadyToPaused | gst::StateChange::PausedToReady => {
+                // Reset the whole state
+                let mut state = self.state.lock().unwrap();
+                *state = State::new(self.format);
+            }
+            _ => (),
+        }
+    }
+}
@@ -6,8 +6,73 @@
 //
 // SPDX-License-Identifier: MPL-2.0
 
+/**
+ * element-transcriberbin:
+ *
+ * Transcribes its audio input and injects the result as closed captions
+ * into its video output.
+ *
+ * Any element can be used as the `transcriber` as long as it implements the
+ * following contract, `awstranscriber` and `vosktranscriber` both do:
+ *
+ * - an always `sink` pad accepting `audio/x-raw` (an `audioconvert` is
+ *   placed in front of it) and an always `src` pad producing
+ *   `text/x-raw, format=utf8`.
+ *
+ * - each output buffer holds one item, usually a word or a punctuation
+ *   mark, timestamped with the PTS and duration of the matching speech in
+ *   the input timeline. Progress without items is signalled with gap events.
+ *
+ * - stable items are never taken back. Items that may still be replaced by
+ *   a later hypothesis are flagged with %GST_BUFFER_FLAG_DELTA_UNIT,
+ *   transcriberbin only captions stable ones.
+ *
+ * - if the element has a `latency` property, it is a `guint` in
+ *   milliseconds and the element guarantees that each stable item is output
+ *   before the running time of its PTS plus that latency. transcriberbin
+ *   sets it to its own `latency`. Without such a property, the element must
+ *   stay within the `latency` configured on transcriberbin.
+ *
+ * - errors posted by the element make transcriberbin go back to
+ *   passthrough.
+ *
+ * When no `transcriber` is set, the first available of `awstranscriber`
+ * and `vosktranscriber` is used. If neither is available, a `transcriber`
+ * has to be set before going to PAUSED.
+ *
+ * By default the transcript is output on CEA-608 channel CC1. The
+ * `translation-languages` property maps CEA-608 channels or CEA-708
+ * services to languages, for example
+ * `languages, cc1=transcript, cc3=fr` outputs the untranslated transcript on
+ * CC1 and its french translation on CC3. Each entry is a branch with its
+ * own `textwrap` and CEA-608 or CEA-708 encoder, CEA-608 branches are then
+ * combined with `cea608mux` while CEA-708 ones are all services of the same
+ * `tttocea708`.
+ *
+ * Branches with a language other than `transcript` translate the stable
+ * items with an instance of the `translator` element, which has to
+ * implement the following contract:
+ *
+ * - an always `sink` pad accepting `text/x-raw, format=utf8` and an always
+ *   `src` pad producing it, with the same one item per buffer and gap
+ *   semantics as transcribers. Only stable items are output.
+ *
+ * - a `target-language-code` string property, and optionally a
+ *   `source-language-code` one, which transcriberbin sets to its
+ *   `language-code` when provided.
+ *
+ * - if the element has a `latency` property, it is a `guint` in
+ *   milliseconds and transcriberbin sets it to its `translate-latency`.
+ *   Otherwise, the element must stay within that latency.
+ *
+ * - errors posted by the element make transcriberbin go back to
+ *   passthrough.
+ *
+ * Translators can be configured further from the `deep-element-added`
+ * signal.
+ */
 use crate::ttutils::Cea608Mode;
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use gst::glib;
 use gst::prelude::*;
 use gst::subclass::prelude::*;
@@ -30,6 +95,121 @@ const DEFAULT_LATENCY: gst::ClockTime = gst::ClockTime::from_seconds(4);
 const DEFAULT_ACCUMULATE: gst::ClockTime = gst::ClockTime::ZERO;
 const DEFAULT_MODE: Cea608Mode = Cea608Mode::RollUp2;
 const DEFAULT_CAPTION_SOURCE: CaptionSource = CaptionSource::Both;
+const DEFAULT_TRANSLATE_LATENCY: gst::ClockTime = gst::ClockTime::from_mseconds(500);
+/* Language of branches outputting the untranslated transcript */
+const TRANSCRIPT_LANGUAGE: &str = "transcript";
+/* Tried in order when building our default transcriber */
+const DEFAULT_TRANSCRIBERS: &[&str] = &["awstranscriber", "vosktranscriber"];
+
+/* Where the captions of a branch end up */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaptionTarget {
+    Cea608Channel(u32),
+    Cea708Service(u32),
+}
+
+impl CaptionTarget {
+    fn from_key(key: &str) -> Option<Self> {
+        if let Some(channel) = key.strip_prefix("cc") {
+            channel
+                .parse::<u32>()
+                .ok()
+                .filter(|channel| (1..=4).contains(channel))
+                .map(CaptionTarget::Cea608Channel)
+        } else if let Some(service) = key.strip_prefix("service-") {
+            service
+                .parse::<u32>()
+                .ok()
+                .filter(|service| (1..=63).contains(service))
+                .map(CaptionTarget::Cea708Service)
+        } else {
+            None
+        }
+    }
+
+    /* The field the channel is transmitted in, CC1 and CC2 in field 1,
+     * CC3 and CC4 in field 2 */
+    fn field(&self) -> Option<u32> {
+        match self {
+            CaptionTarget::Cea608Channel(channel) => Some(if *channel <= 2 { 1 } else { 2 }),
+            CaptionTarget::Cea708Service(_) => None,
+        }
+    }
+}
+
+/* A target with its language, None for the untranslated transcript */
+type BranchConfig = (CaptionTarget, Option<String>);
+
+fn parse_translation_languages(s: &gst::StructureRef) -> Result<Vec<BranchConfig>, Error> {
+    let mut ret: Vec<BranchConfig> = vec![];
+
+    for (key, value) in s.iter() {
+        let target = CaptionTarget::from_key(key).ok_or_else(|| {
+            anyhow!(
+                "Invalid target {}, expected cc1 to cc4 or service-1 to service-63",
+                key
+            )
+        })?;
+        let language = value
+            .get::<String>()
+            .map_err(|_| anyhow!("Language for {} is not a string", key))?;
+
+        if let Some((other, _)) = ret.first() {
+            if target.field().is_some() != other.field().is_some() {
+                return Err(anyhow!("Can't mix CEA-608 channels and CEA-708 services"));
+            }
+        }
+
+        if ret.iter().any(|(other, _)| {
+            *other == target || (target.field().is_some() && other.field() == target.field())
+        }) {
+            return Err(anyhow!(
+                "{} conflicts with another target, only one CEA-608 channel per field can be used",
+                key
+            ));
+        }
+
+        let language = if language == TRANSCRIPT_LANGUAGE {
+            None
+        } else {
+            Some(language)
+        };
+
+        ret.push((target, language));
+    }
+
+    if ret.is_empty() {
+        return Err(anyhow!("No target"));
+    }
+
+    Ok(ret)
+}
+
+enum Encoder {
+    Cea608(gst::Element),
+    /* Sink pad of the tttocea708 shared by all branches */
+    Cea708(gst::Pad),
+}
+
+struct Branch {
+    target: CaptionTarget,
+    queue: gst::Element,
+    translator: Option<gst::Element>,
+    textwrap: gst::Element,
+    encoder: Encoder,
+}
+
+impl Branch {
+    fn elements(&self) -> Vec<&gst::Element> {
+        let mut ret = vec![&self.queue];
+        ret.extend(self.translator.as_ref());
+        ret.push(&self.textwrap);
+        if let Encoder::Cea608(ref tttocea608) = self.encoder {
+            ret.push(tttocea608);
+        }
+        ret
+    }
+}
 
 struct State {
     framerate: Option<gst::Fraction>,
@@ -39,16 +219,28 @@ struct State {
     video_queue: gst::Element,
     audio_tee: gst::Element,
     transcriber_aconv: gst::Element,
-    transcriber: gst::Element,
-    transcriber_queue: gst::Element,
+    /* None until set when no default transcriber is available */
+    transcriber: Option<gst::Element>,
+    transcriber_tee: gst::Element,
     cccombiner: gst::Element,
     transcription_bin: gst::Bin,
-    textwrap: gst::Element,
-    tttocea608: gst::Element,
+    branches: Vec<Branch>,
+    /* cea608mux or tttocea708 combining the branches, None when
+     * outputting the transcript on CC1 only */
+    cc_mux: Option<gst::Element>,
+    ccconverter: gst::Element,
     cccapsfilter: gst::Element,
     transcription_valve: gst::Element,
 }
 
+impl State {
+    fn translates(&self) -> bool {
+        self.branches
+            .iter()
+            .any(|branch| branch.translator.is_some())
+    }
+}
+
 struct Settings {
     cc_caps: gst::Caps,
     latency: gst::ClockTime,
@@ -56,6 +248,94 @@ struct Settings {
     accumulate_time: gst::ClockTime,
     mode: Cea608Mode,
     caption_source: CaptionSource,
+    translation_languages: Option<gst::Structure>,
+    branch_configs: Vec<BranchConfig>,
+    translator: Option<String>,
+    language_code: Option<String>,
+    translate_latency: gst::ClockTime,
+}
+
+/* Checks the parts of the translator contract that can be checked
+ * up front, see the element documentation */
+fn check_translator(translator: &gst::Element) -> Result<(), Error> {
+    let text_caps = gst::Caps::builder("text/x-raw")
+        .field("format", "utf8")
+        .build();
+
+    for pad_name in ["sink", "src"] {
+        let pad = translator
+            .static_pad(pad_name)
+            .ok_or_else(|| anyhow!("Translator {} has no {} pad", translator.name(), pad_name))?;
+
+        if !pad.pad_template_caps().can_intersect(&text_caps) {
+            return Err(anyhow!(
+                "{} pad of translator {} does not handle utf8 text",
+                pad_name,
+                translator.name()
+            ));
+        }
+    }
+
+    match translator.find_property("target-language-code") {
+        Some(pspec) if pspec.value_type() == String::static_type() => (),
+        _ => {
+            return Err(anyhow!(
+                "Translator {} has no target-language-code string property",
+                translator.name()
+            ))
+        }
+    }
+
+    if let Some(pspec) = translator.find_property("latency") {
+        if pspec.value_type() != u32::static_type() {
+            return Err(anyhow!(
+                "Latency of translator {} is not in milliseconds",
+                translator.name()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/* Checks the parts of the transcriber contract that can be checked
+ * up front, see the element documentation */
+fn check_transcriber(transcriber: &gst::Element) -> Result<(), Error> {
+    let sinkpad = transcriber
+        .static_pad("sink")
+        .ok_or_else(|| anyhow!("Transcriber {} has no sink pad", transcriber.name()))?;
+    let srcpad = transcriber
+        .static_pad("src")
+        .ok_or_else(|| anyhow!("Transcriber {} has no src pad", transcriber.name()))?;
+
+    let audio_caps = gst::Caps::builder("audio/x-raw").build();
+    if !sinkpad.pad_template_caps().can_intersect(&audio_caps) {
+        return Err(anyhow!(
+            "Transcriber {} does not accept raw audio",
+            transcriber.name()
+        ));
+    }
+
+    let text_caps = gst::Caps::builder("text/x-raw")
+        .field("format", "utf8")
+        .build();
+    if !srcpad.pad_template_caps().can_intersect(&text_caps) {
+        return Err(anyhow!(
+            "Transcriber {} does not produce utf8 text",
+            transcriber.name()
+        ));
+    }
+
+    if let Some(pspec) = transcriber.find_property("latency") {
+        if pspec.value_type() != u32::static_type() {
+            return Err(anyhow!(
+                "Latency of transcriber {} is not in milliseconds",
+                transcriber.name()
+            ));
+        }
+    }
+
+    Ok(())
 }
 
 impl Default for Settings {
@@ -69,6 +349,11 @@ impl Default for Settings {
             accumulate_time: DEFAULT_ACCUMULATE,
             mode: DEFAULT_MODE,
             caption_source: DEFAULT_CAPTION_SOURCE,
+            translation_languages: None,
+            branch_configs: vec![(CaptionTarget::Cea608Channel(1), None)],
+            translator: None,
+            language_code: None,
+            translate_latency: DEFAULT_TRANSLATE_LATENCY,
         }
     }
 }
@@ -97,28 +382,27 @@ impl TranscriberBin {
         aqueue_transcription.set_property("max-size-bytes", 0u32);
         aqueue_transcription.set_property("max-size-time", 5_000_000_000u64);
         aqueue_transcription.set_property_from_str("leaky", "downstream");
-        let ccconverter = gst::ElementFactory::make("ccconverter", None)?;
 
         state.transcription_bin.add_many(&[
             &aqueue_transcription,
             &state.transcriber_aconv,
-            &state.transcriber,
-            &state.transcriber_queue,
-            &state.textwrap,
-            &state.tttocea608,
-            &ccconverter,
+            &state.transcriber_tee,
+            &state.ccconverter,
             &state.cccapsfilter,
             &state.transcription_valve,
         ])?;
 
+        aqueue_transcription.link(&state.transcriber_aconv)?;
+        if let Some(ref transcriber) = state.transcriber {
+            state.transcription_bin.add(transcriber)?;
+            gst::Element::link_many(&[
+                &state.transcriber_aconv,
+                transcriber,
+                &state.transcriber_tee,
+            ])?;
+        }
         gst::Element::link_many(&[
-            &aqueue_transcription,
-            &state.transcriber_aconv,
-            &state.transcriber,
-            &state.transcriber_queue,
-            &state.textwrap,
-            &state.tttocea608,
-            &ccconverter,
+            &state.ccconverter,
             &state.cccapsfilter,
             &state.transcription_valve,
         ])?;
@@ -139,20 +423,151 @@ impl TranscriberBin {
             .transcription_bin
             .add_pad(&transcription_audio_srcpad)?;
 
-        state
-            .transcriber_queue
-            .set_property("max-size-buffers", 0u32);
-        state.transcriber_queue.set_property("max-size-time", 0u64);
-
         state.internal_bin.add(&state.transcription_bin)?;
 
-        state.textwrap.set_property("lines", 2u32);
+        // Unstable items may still be replaced by the transcriber, but
+        // neither translators nor tttocea608 can take back what they
+        // already output
+        let transcriber_tee_sinkpad = state.transcriber_tee.static_pad("sink").unwrap();
+        transcriber_tee_sinkpad.add_probe(gst::PadProbeType::BUFFER, |_, probe_info| {
+            match probe_info.data {
+                Some(gst::PadProbeData::Buffer(ref buffer))
+                    if buffer.flags().contains(gst::BufferFlags::DELTA_UNIT) =>
+                {
+                    gst::trace!(CAT, "Dropping unstable item {:?}", buffer);
+                    gst::PadProbeReturn::Drop
+                }
+                _ => gst::PadProbeReturn::Ok,
+            }
+        });
 
         state.transcription_bin.set_locked_state(true);
 
         Ok(())
     }
 
+    /* Builds one branch per target after the transcriber tee, the
+     * branches are built lazily as their configuration can only be
+     * changed in READY */
+    fn build_branches(
+        &self,
+        element: &super::TranscriberBin,
+        state: &mut State,
+        configs: &[BranchConfig],
+        translator_factory: Option<&str>,
+    ) -> Result<(), Error> {
+        gst::debug!(CAT, obj: element, "Building branches {:?}", configs);
+
+        let cc_mux = match configs {
+            [(CaptionTarget::Cea608Channel(1), _)] => None,
+            [(CaptionTarget::Cea708Service(_), _), ..] => {
+                Some(gst::ElementFactory::make("tttocea708", None)?)
+            }
+            _ => Some(gst::ElementFactory::make("cea608mux", None)?),
+        };
+
+        if let Some(ref cc_mux) = cc_mux {
+            state.transcription_bin.add(cc_mux)?;
+            cc_mux.link(&state.ccconverter)?;
+        }
+        state.cc_mux = cc_mux;
+
+        for (target, language) in configs {
+            let queue = gst::ElementFactory::make("queue", None)?;
+            queue.set_property("max-size-buffers", 0u32);
+            queue.set_property("max-size-time", 0u64);
+
+            let translator = match language {
+                Some(language) => {
+                    let factory_name = translator_factory
+                        .ok_or_else(|| anyhow!("No translator set to translate to {}", language))?;
+                    let translator = gst::ElementFactory::make(factory_name, None)?;
+                    check_translator(&translator)?;
+                    translator.set_property("target-language-code", language.as_str());
+                    Some(translator)
+                }
+                None => None,
+            };
+
+            let textwrap = gst::ElementFactory::make("textwrap", None)?;
+            textwrap.set_property("lines", 2u32);
+
+            let encoder = match (target, &state.cc_mux) {
+                (CaptionTarget::Cea708Service(service), Some(cc_mux)) => {
+                    let pad = cc_mux
+                        .request_pad_simple("sink_%u")
+                        .ok_or_else(|| anyhow!("Failed to request tttocea708 pad"))?;
+                    pad.set_property("service-number", *service);
+                    Encoder::Cea708(pad)
+                }
+                _ => Encoder::Cea608(gst::ElementFactory::make("tttocea608", None)?),
+            };
+
+            let branch = Branch {
+                target: *target,
+                queue,
+                translator,
+                textwrap,
+                encoder,
+            };
+
+            state.transcription_bin.add_many(&branch.elements())?;
+            // Tracked right away for remove_branches() to clean up after errors
+            state.branches.push(branch);
+            let branch = state.branches.last().unwrap();
+
+            gst::Element::link_many(&branch.elements())?;
+
+            match (&branch.encoder, &state.cc_mux) {
+                (Encoder::Cea708(pad), _) => {
+                    branch.textwrap.static_pad("src").unwrap().link(pad)?;
+                }
+                (Encoder::Cea608(tttocea608), Some(cc_mux)) => {
+                    if let CaptionTarget::Cea608Channel(channel) = target {
+                        tttocea608.link_pads(
+                            Some("src"),
+                            cc_mux,
+                            Some(&format!("cc{}", channel)),
+                        )?;
+                    }
+                }
+                (Encoder::Cea608(tttocea608), None) => {
+                    tttocea608.link(&state.ccconverter)?;
+                }
+            }
+
+            state
+                .transcriber_tee
+                .link_pads(Some("src_%u"), &branch.queue, Some("sink"))?;
+        }
+
+        Ok(())
+    }
+
+    /* Can only be called in READY, see build_branches() */
+    fn remove_branches(&self, element: &super::TranscriberBin, state: &mut State) {
+        gst::debug!(CAT, obj: element, "Removing branches");
+
+        for branch in state.branches.drain(..) {
+            if let Some(tee_pad) = branch.queue.static_pad("sink").unwrap().peer() {
+                tee_pad
+                    .unlink(&branch.queue.static_pad("sink").unwrap())
+                    .unwrap();
+                state.transcriber_tee.release_request_pad(&tee_pad);
+            }
+
+            for branch_element in branch.elements() {
+                state.transcription_bin.remove(branch_element).unwrap();
+                branch_element.set_state(gst::State::Null).unwrap();
+            }
+        }
+
+        if let Some(cc_mux) = state.cc_mux.take() {
+            state.transcription_bin.remove(&cc_mux).unwrap();
+            cc_mux.set_state(gst::State::Null).unwrap();
+        }
+    }
+
     fn construct_internal_bin(
         &self,
         element: &super::TranscriberBin,
@@ -258,7 +673,25 @@ impl TranscriberBin {
 
         state.cccapsfilter.set_property("caps", &cc_caps);
 
-        let max_size_time = settings.latency + settings.accumulate_time;
+        if cc_caps.structure(0).unwrap().name() == "closedcaption/x-cea-608" {
+            // Only field 1 survives the conversion to CEA-608 caps
+            for branch in &state.branches {
+                if branch.target.field() != Some(1) {
+                    gst::warning!(
+                        CAT,
+                        obj: element,
+                        "{:?} can't be carried by {}, its captions will be lost",
+                        branch.target,
+                        cc_caps
+                    );
+                }
+            }
+        }
+
+        let mut max_size_time = settings.latency + settings.accumulate_time;
+        if state.translates() {
+            max_size_time += settings.translate_latency;
+        }
 
         for queue in &[&state.audio_queue_passthrough, &state.video_queue] {
             queue.set_property("max-size-bytes", 0u32);
@@ -266,8 +699,42 @@ impl TranscriberBin {
             queue.set_property("max-size-time", max_size_time);
         }
 
-        let latency_ms = settings.latency.mseconds() as u32;
-        state.transcriber.set_property("latency", latency_ms);
+        if let Some(ref transcriber) = state.transcriber {
+            if transcriber.find_property("latency").is_some() {
+                let latency_ms = settings.latency.mseconds() as u32;
+                transcriber.set_property("latency", latency_ms);
+            } else {
+                gst::info!(
+                    CAT,
+                    obj: element,
+                    "Transcriber has no latency property, expecting it to stay within {}",
+                    settings.latency
+                );
+            }
+
+            if let Some(ref language_code) = settings.language_code {
+                if transcriber.find_property("language-code").is_some() {
+                    transcriber.set_property("language-code", language_code.as_str());
+                }
+            }
+        }
+
+        for translator in state
+            .branches
+            .iter()
+            .filter_map(|branch| branch.translator.as_ref())
+        {
+            if let Some(ref language_code) = settings.language_code {
+                if translator.find_property("source-language-code").is_some() {
+                    translator.set_property("source-language-code", language_code.as_str());
+                }
+            }
+
+            if translator.find_property("latency").is_some() {
+                let latency_ms = settings.translate_latency.mseconds() as u32;
+                translator.set_property("latency", latency_ms);
+            }
+        }
 
         if !settings.passthrough {
             let audio_tee_pad = state.audio_tee.request_pad_simple("src_%u").unwrap();
@@ -368,14 +835,19 @@ impl TranscriberBin {
 
         gst::debug!(CAT, obj: element, "setting CC mode {:?}", mode);
 
-        state.tttocea608.set_property("mode", mode);
-
-        if mode.is_rollup() {
-            state.textwrap.set_property("accumulate-time", 0u64);
+        let accumulate_time = if mode.is_rollup() {
+            gst::ClockTime::ZERO
         } else {
-            let accumulate_time = self.settings.lock().unwrap().accumulate_time;
+            self.settings.lock().unwrap().accumulate_time
+        };
 
-            state
+        for branch in &state.branches {
+            match branch.encoder {
+                Encoder::Cea608(ref tttocea608) => tttocea608.set_property("mode", mode),
+                Encoder::Cea708(ref pad) => pad.set_property("mode", mode),
+            }
+
+            branch
                 .textwrap
                 .set_property("accumulate-time", accumulate_time);
         }
@@ -387,7 +859,7 @@ impl TranscriberBin {
         &self,
         state: &mut State,
         element: &super::TranscriberBin,
-        old_transcriber: &gst::Element,
+        old_transcriber: Option<&gst::Element>,
     ) -> Result<(), Error> {
         gst::error!(
             CAT,
@@ -397,18 +869,22 @@ impl TranscriberBin {
             state.transcriber
         );
 
-        state.transcriber_aconv.unlink(old_transcriber);
-        old_transcriber.unlink(&state.transcriber_queue);
-        state.transcription_bin.remove(old_transcriber).unwrap();
-        old_transcriber.set_state(gst::State::Null).unwrap();
+        if let Some(old_transcriber) = old_transcriber {
+            state.transcriber_aconv.unlink(old_transcriber);
+            old_transcriber.unlink(&state.transcriber_tee);
+            state.transcription_bin.remove(old_transcriber).unwrap();
+            old_transcriber.set_state(gst::State::Null).unwrap();
+        }
 
-        state.transcription_bin.add(&state.transcriber)?;
-        state.transcriber.sync_state_with_parent().unwrap();
-        gst::Element::link_many(&[
-            &state.transcriber_aconv,
-            &state.transcriber,
-            &state.transcriber_queue,
-        ])?;
+        if let Some(ref transcriber) = state.transcriber {
+            state.transcription_bin.add(transcriber)?;
+            transcriber.sync_state_with_parent().unwrap();
+            gst::Element::link_many(&[
+                &state.transcriber_aconv,
+                transcriber,
+                &state.transcriber_tee,
+            ])?;
+        }
 
         Ok(())
     }
@@ -432,18 +908,21 @@ impl TranscriberBin {
 
                 if ret {
                     let (_, mut min, _) = upstream_query.result();
-                    let received_framerate = {
+                    let (received_framerate, translates) = {
                         let state = self.state.lock().unwrap();
                         if let Some(state) = state.as_ref() {
-                            state.framerate.is_some()
+                            (state.framerate.is_some(), state.translates())
                         } else {
-                            false
+                            (false, false)
                         }
                     };
 
                     let settings = self.settings.lock().unwrap();
                     if settings.passthrough || !received_framerate {
                         min += settings.latency + settings.accumulate_time;
+                        if translates {
+                            min += settings.translate_latency;
+                        }
                     } else if settings.mode.is_rollup() {
                         min += settings.accumulate_time;
                     }
@@ -461,12 +940,21 @@ impl TranscriberBin {
         let internal_bin = gst::Bin::new(Some("internal"));
         let transcription_bin = gst::Bin::new(Some("transcription-bin"));
         let audio_tee = gst::ElementFactory::make("tee", None)?;
-        let cccombiner = gst::ElementFactory::make("cccombiner", Some("cccombiner"))?;
-        let textwrap = gst::ElementFactory::make("textwrap", Some("textwrap"))?;
-        let tttocea608 = gst::ElementFactory::make("tttocea608", Some("tttocea608"))?;
+        let cccombiner = gst::ElementFactory::make("rscccombiner", Some("cccombiner"))?;
         let transcriber_aconv = gst::ElementFactory::make("audioconvert", None)?;
-        let transcriber = gst::ElementFactory::make("awstranscriber", Some("transcriber"))?;
-        let transcriber_queue = gst::ElementFactory::make("queue", None)?;
+        let transcriber = DEFAULT_TRANSCRIBERS
+            .iter()
+            .find_map(|name| gst::ElementFactory::make(name, Some("transcriber")).ok());
+        match transcriber {
+            Some(ref transcriber) => check_transcriber(transcriber)?,
+            None => gst::info!(
+                CAT,
+                "No default transcriber available, tried {}",
+                DEFAULT_TRANSCRIBERS.join(", ")
+            ),
+        }
+        let transcriber_tee = gst::ElementFactory::make("tee", None)?;
+        let ccconverter = gst::ElementFactory::make("rsccconverter", None)?;
         let audio_queue_passthrough = gst::ElementFactory::make("queue", None)?;
         let video_queue = gst::ElementFactory::make("queue", None)?;
         let cccapsfilter = gst::ElementFactory::make("capsfilter", None)?;
@@ -484,12 +972,13 @@ impl TranscriberBin {
             video_queue,
             transcriber_aconv,
             transcriber,
-            transcriber_queue,
+            transcriber_tee,
             audio_tee,
             cccombiner,
             transcription_bin,
-            textwrap,
-            tttocea608,
+            branches: vec![],
+            cc_mux: None,
+            ccconverter,
             cccapsfilter,
             transcription_valve,
             tearing_down: false,
@@ -638,6 +1127,32 @@ impl ObjectImpl for TranscriberBin {
                     of the other source will be dropped by transcriberbin")
                     .mutable_playing()
                     .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("translation-languages")
+                    .nick("Translation languages")
+                    .blurb("A map of CEA-608 channels (cc1 to cc4) or CEA-708 services \
+                    (service-1 to service-63) to language codes, \"transcript\" \
+                    standing for the untranslated transcript, \
+                    e.g. \"languages, cc1=transcript, cc3=fr\"")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecString::builder("translator")
+                    .nick("Translator")
+                    .blurb("Factory name of the translator element to use for \
+                    translated languages")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecString::builder("language-code")
+                    .nick("Language Code")
+                    .blurb("Language of the transcript, set on the transcriber and used \
+                    as the source language of the translators if they support it")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("translate-latency")
+                    .nick("Translation Latency")
+                    .blurb("Amount of milliseconds to allow the translators")
+                    .default_value(DEFAULT_TRANSLATE_LATENCY.mseconds() as u32)
+                    .mutable_ready()
+                    .build(),
             ]
         });
 
@@ -695,10 +1210,15 @@ impl ObjectImpl for TranscriberBin {
             "transcriber" => {
                 let mut s = self.state.lock().unwrap();
                 if let Some(ref mut state) = s.as_mut() {
-                    let old_transcriber = state.transcriber.clone();
-                    state.transcriber = value.get().expect("type checked upstream");
+                    let transcriber: gst::Element = value.get().expect("type checked upstream");
+                    if let Err(err) = check_transcriber(&transcriber) {
+                        gst::error!(CAT, obj: obj, "invalid transcriber: {}", err);
+                        return;
+                    }
+
+                    let old_transcriber = state.transcriber.replace(transcriber);
                     if old_transcriber != state.transcriber {
-                        match self.relink_transcriber(state, obj, &old_transcriber) {
+                        match self.relink_transcriber(state, obj, old_transcriber.as_ref()) {
                             Ok(()) => (),
                             Err(err) => {
                                 gst::error!(CAT, "invalid transcriber: {}", err);
@@ -724,6 +1244,49 @@ impl ObjectImpl for TranscriberBin {
                     }
                 }
             }
+            "translation-languages" => {
+                let mut settings = self.settings.lock().unwrap();
+                let translation_languages: Option<gst::Structure> =
+                    value.get().expect("type checked upstream");
+
+                let branch_configs = match translation_languages {
+                    Some(ref s) => match parse_translation_languages(s) {
+                        Ok(branch_configs) => branch_configs,
+                        Err(err) => {
+                            gst::error!(CAT, obj: obj, "invalid translation languages: {}", err);
+                            return;
+                        }
+                    },
+                    None => Settings::default().branch_configs,
+                };
+
+                settings.translation_languages = translation_languages;
+                settings.branch_configs = branch_configs;
+                drop(settings);
+
+                if let Some(ref mut state) = self.state.lock().unwrap().as_mut() {
+                    self.remove_branches(obj, state);
+                }
+            }
+            "translator" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.translator = value.get().expect("type checked upstream");
+                drop(settings);
+
+                if let Some(ref mut state) = self.state.lock().unwrap().as_mut() {
+                    self.remove_branches(obj, state);
+                }
+            }
+            "language-code" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.language_code = value.get().expect("type checked upstream");
+            }
+            "translate-latency" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.translate_latency = gst::ClockTime::from_mseconds(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
             _ => unimplemented!(),
         }
     }
@@ -763,6 +1326,22 @@ impl ObjectImpl for TranscriberBin {
                 let settings = self.settings.lock().unwrap();
                 settings.caption_source.to_value()
             }
+            "translation-languages" => {
+                let settings = self.settings.lock().unwrap();
+                settings.translation_languages.to_value()
+            }
+            "translator" => {
+                let settings = self.settings.lock().unwrap();
+                settings.translator.to_value()
+            }
+            "language-code" => {
+                let settings = self.settings.lock().unwrap();
+                settings.language_code.to_value()
+            }
+            "translate-latency" => {
+                let settings = self.settings.lock().unwrap();
+                (settings.translate_latency.mseconds() as u32).to_value()
+            }
             _ => unimplemented!(),
         }
     }
@@ -865,6 +1444,40 @@ impl ElementImpl for TranscriberBin {
                 let mut state = self.state.lock().unwrap();
 
                 if let Some(ref mut state) = state.as_mut() {
+                    if state.transcriber.is_none() {
+                        gst::element_error!(
+                            element,
+                            gst::StreamError::Failed,
+                            [
+                                "No transcriber set and none of {} available",
+                                DEFAULT_TRANSCRIBERS.join(", ")
+                            ]
+                        );
+                        return Err(gst::StateChangeError);
+                    }
+
+                    if state.branches.is_empty() {
+                        let (branch_configs, translator) = {
+                            let settings = self.settings.lock().unwrap();
+                            (settings.branch_configs.clone(), settings.translator.clone())
+                        };
+
+                        if let Err(err) = self.build_branches(
+                            element,
+                            state,
+                            &branch_configs,
+                            translator.as_deref(),
+                        ) {
+                            self.remove_branches(element, state);
+                            gst::element_error!(
+                                element,
+                                gst::StreamError::Failed,
+                                ["Failed to build transcription branches: {}", err]
+                            );
+                            return Err(gst::StateChangeError);
+                        }
+                    }
+
                     if state.framerate.is_some() {
                         gst::info!(
                             CAT,
@@ -899,11 +1512,24 @@ impl BinImpl for TranscriberBin {
                 let s = self.state.lock().unwrap();
 
                 if let Some(state) = s.as_ref() {
-                    if msg.src().as_ref() == Some(state.transcriber.upcast_ref()) {
+                    let src = msg.src();
+                    let from_translator = state.branches.iter().any(|branch| {
+                        branch.translator.as_ref().map_or(false, |translator| {
+                            src.as_ref() == Some(translator.upcast_ref())
+                        })
+                    });
+
+                    let from_transcriber =
+                        state.transcriber.as_ref().map_or(false, |transcriber| {
+                            src.as_ref() == Some(transcriber.upcast_ref())
+                        });
+
+                    if from_translator || from_transcriber {
                         gst::error!(
                             CAT,
                             obj: bin,
-                            "Transcriber has posted an error ({:?}), going back to passthrough",
+                            "{} has posted an error ({:?}), going back to passthrough",
+                            if from_translator { "Translator" } else { "Transcriber" },
                             m
                         );
                         drop(s);
@@ -928,3 +1554,88 @@ impl BinImpl for TranscriberBin {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn languages(fields: &[(&str, &str)]) -> gst::Structure {
+        let mut s = gst::Structure::new_empty("languages");
+        for (key, language) in fields {
+            s.set(key, *language);
+        }
+        s
+    }
+
+    fn parse_error(fields: &[(&str, &str)]) -> String {
+        parse_translation_languages(&languages(fields))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_parse_translation_languages() {
+        gst::init().unwrap();
+
+        assert_eq!(
+            parse_translation_languages(&languages(&[("cc1", "transcript"), ("cc3", "fr")]))
+                .unwrap(),
+            vec![
+                (CaptionTarget::Cea608Channel(1), None),
+                (CaptionTarget::Cea608Channel(3), Some("fr".to_string())),
+            ]
+        );
+
+        assert_eq!(
+            parse_translation_languages(&languages(&[
+                ("service-1", "transcript"),
+                ("service-2", "fr"),
+                ("service-63", "transcript"),
+            ]))
+            .unwrap(),
+            vec![
+                (CaptionTarget::Cea708Service(1), None),
+                (CaptionTarget::Cea708Service(2), Some("fr".to_string())),
+                (CaptionTarget::Cea708Service(63), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_translation_languages_conflicts() {
+        gst::init().unwrap();
+
+        // CC1 and CC2 are both transmitted in field 1
+        assert!(parse_error(&[("cc1", "transcript"), ("cc2", "fr")]).contains("one CEA-608"));
+        assert!(parse_error(&[("cc4", "fr"), ("cc3", "de")]).contains("one CEA-608"));
+
+        assert!(parse_error(&[("cc1", "transcript"), ("service-1", "fr")]).contains("mix"));
+        assert!(parse_error(&[("service-1", "transcript"), ("cc3", "fr")]).contains("mix"));
+    }
+
+    #[test]
+    fn test_parse_translation_languages_invalid() {
+        gst::init().unwrap();
+
+        for key in [
+            "cc0",
+            "cc5",
+            "service-0",
+            "service-64",
+            "service",
+            "ccx",
+            "en",
+        ] {
+            assert!(parse_error(&[(key, "transcript")]).contains("Invalid target"));
+        }
+
+        let mut s = languages(&[]);
+        s.set("cc1", 1i32);
+        assert!(parse_translation_languages(&s)
+            .unwrap_err()
+            .to_string()
+            .contains("not a string"));
+
+        assert_eq!(parse_error(&[]), "No target");
+    }
+}
@@ -0,0 +1,1127 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Writing and parsing of IMSC1 text profile TTML documents.
+//!
+//! The CEA-608 grid of 15 rows by 32 columns is mapped to the 80% by 80%
+//! title safe area centered in the video, every line of a paragraph gets its
+//! own region.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+use crate::ttutils::{Cea608Mode, Chunk, Line, Lines, TextStyle};
+
+const TTML_NS: &str = "http://www.w3.org/ns/ttml";
+const TTP_NS: &str = "http://www.w3.org/ns/ttml#parameter";
+const TTS_NS: &str = "http://www.w3.org/ns/ttml#styling";
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+const IMSC1_TEXT_PROFILE: &str = "http://www.w3.org/ns/ttml/profile/imsc1/text";
+
+const COLUMN_WIDTH: f64 = 80.0 / 32.0;
+const ROW_HEIGHT: f64 = 80.0 / 15.0;
+
+/// Timed lines, the content of the `<p>` elements active at the same time
+/// when parsing
+#[derive(Clone, Debug)]
+pub struct Paragraph {
+    pub begin: gst::ClockTime,
+    pub end: gst::ClockTime,
+    pub lines: Lines,
+}
+
+fn format_time(time: gst::ClockTime) -> String {
+    let ms = time.mseconds();
+
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1000) % 60,
+        ms % 1000
+    )
+}
+
+fn format_percent(value: f64) -> String {
+    let s = format!("{:.3}", value);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+
+    format!("{}%", s)
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn color_name(style: TextStyle) -> &'static str {
+    match style {
+        TextStyle::White | TextStyle::ItalicWhite => "white",
+        TextStyle::Green => "lime",
+        TextStyle::Blue => "blue",
+        TextStyle::Cyan => "cyan",
+        TextStyle::Red => "red",
+        TextStyle::Yellow => "yellow",
+        TextStyle::Magenta => "magenta",
+    }
+}
+
+fn line_len(line: &Line) -> u32 {
+    (line
+        .chunks
+        .iter()
+        .map(|chunk| chunk.text.chars().count() + 1)
+        .sum::<usize>() as u32)
+        .saturating_sub(1)
+}
+
+/// The row of the first line when lines have no row, at the bottom like
+/// `tttojson` does
+fn default_first_row(n_lines: usize) -> u32 {
+    15u32.saturating_sub(n_lines as u32).min(13)
+}
+
+/// Region of a line, `None` column means centered
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Region {
+    row: u32,
+    column: Option<u32>,
+}
+
+impl Region {
+    fn id(&self) -> String {
+        match self.column {
+            Some(column) => format!("r{}c{}", self.row, column),
+            None => format!("r{}", self.row),
+        }
+    }
+
+    fn write(&self, doc: &mut String) {
+        let column = self.column.unwrap_or(0);
+
+        let _ = write!(
+            doc,
+            "<region xml:id=\"{}\" tts:origin=\"{} {}\" tts:extent=\"{} {}\" tts:displayAlign=\"before\" tts:textAlign=\"{}\"/>",
+            self.id(),
+            format_percent(10.0 + column as f64 * COLUMN_WIDTH),
+            format_percent(10.0 + self.row as f64 * ROW_HEIGHT),
+            format_percent(80.0 - column as f64 * COLUMN_WIDTH),
+            format_percent(ROW_HEIGHT),
+            if self.column.is_some() { "start" } else { "center" },
+        );
+    }
+}
+
+/// Chunks are separated by spaces, except before punctuation
+fn needs_space(index: usize, chunk: &Chunk) -> bool {
+    index > 0 && !chunk.text.starts_with([',', '.', ';', ':', '!', '?', ')'])
+}
+
+fn write_line(doc: &mut String, line: &Line, region: &Region, begin: &str, end: &str) {
+    let _ = write!(
+        doc,
+        "<p begin=\"{}\" end=\"{}\" region=\"{}\">",
+        begin,
+        end,
+        region.id()
+    );
+
+    for (i, chunk) in line.chunks.iter().enumerate() {
+        doc.push_str("<span style=\"s0\"");
+        if chunk.style != TextStyle::White && chunk.style != TextStyle::ItalicWhite {
+            let _ = write!(doc, " tts:color=\"{}\"", color_name(chunk.style));
+        }
+        if chunk.style == TextStyle::ItalicWhite {
+            doc.push_str(" tts:fontStyle=\"italic\"");
+        }
+        if chunk.underline {
+            doc.push_str(" tts:textDecoration=\"underline\"");
+        }
+        doc.push('>');
+
+        if needs_space(i, chunk) {
+            doc.push(' ');
+        }
+        doc.push_str(&escape(&chunk.text));
+        doc.push_str("</span>");
+    }
+
+    doc.push_str("</p>\n");
+}
+
+/// Writes an IMSC1 text profile document containing the paragraphs
+pub fn write_document(paragraphs: &[Paragraph], lang: &str) -> String {
+    let mut regions = BTreeSet::new();
+    let mut body = String::new();
+
+    for paragraph in paragraphs {
+        let lines = &paragraph.lines.lines;
+        let begin = format_time(paragraph.begin);
+        let end = format_time(paragraph.end);
+        let mut row = default_first_row(lines.len());
+
+        for line in lines {
+            if let Some(line_row) = line.row {
+                row = line_row;
+            }
+            if row > 14 || line.chunks.is_empty() {
+                row += 1;
+                continue;
+            }
+
+            let region = Region {
+                row,
+                column: line.column.map(|column| column.min(31)),
+            };
+            write_line(&mut body, line, &region, &begin, &end);
+            regions.insert(region);
+            row += 1;
+        }
+    }
+
+    let mut doc = String::new();
+    doc.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        doc,
+        "<tt xmlns=\"{}\" xmlns:ttp=\"{}\" xmlns:tts=\"{}\" xml:lang=\"{}\" ttp:profile=\"{}\" ttp:timeBase=\"media\" ttp:cellResolution=\"32 15\">",
+        TTML_NS,
+        TTP_NS,
+        TTS_NS,
+        escape(lang),
+        IMSC1_TEXT_PROFILE
+    );
+    doc.push_str("<head>\n<styling>\n");
+    doc.push_str("<style xml:id=\"s0\" tts:fontFamily=\"monospaceSansSerif\" tts:fontSize=\"100%\" tts:color=\"white\" tts:backgroundColor=\"black\"/>\n");
+    doc.push_str("</styling>\n<layout>\n");
+    for region in &regions {
+        region.write(&mut doc);
+        doc.push('\n');
+    }
+    doc.push_str("</layout>\n</head>\n<body>\n<div>\n");
+    doc.push_str(&body);
+    doc.push_str("</div>\n</body>\n</tt>\n");
+
+    doc
+}
+
+/// The plain text of the paragraph, one line of text per line
+pub fn paragraph_text(paragraph: &Paragraph) -> String {
+    let mut text = String::new();
+
+    for (i, line) in paragraph.lines.lines.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        for (j, chunk) in line.chunks.iter().enumerate() {
+            if needs_space(j, chunk) {
+                text.push(' ');
+            }
+            text.push_str(&chunk.text);
+        }
+    }
+
+    text
+}
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    /// Namespace, local name and value
+    attributes: Vec<(String, String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn attribute(&self, ns: &str, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attr_ns, attr_name, _)| attr_ns == ns && attr_name == name)
+            .map(|(_, _, value)| value.as_str())
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+}
+
+/// Replaces the entity and character references
+fn unescape(s: &str) -> String {
+    let mut text = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(pos) = rest.find('&') {
+        text.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        let reference = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let c = reference.and_then(|reference| match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => {
+                let code = match reference.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => reference.strip_prefix('#')?.parse::<u32>().ok(),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+
+        match (c, reference) {
+            (Some(c), Some(reference)) => {
+                text.push(c);
+                rest = &rest[reference.len() + 2..];
+            }
+            _ => {
+                text.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    text.push_str(rest);
+    text
+}
+
+/// Parses the document into a tree of elements, with resolved attribute
+/// namespaces
+fn parse_tree(data: &str) -> Result<Element, String> {
+    struct Open {
+        element: Element,
+        namespaces: HashMap<String, String>,
+        prefixed_attributes: Vec<(String, String, String)>,
+    }
+
+    let mut stack: Vec<Open> = Vec::new();
+    let mut root = None;
+
+    for token in xmlparser::Tokenizer::from(data) {
+        let token = token.map_err(|err| err.to_string())?;
+
+        match token {
+            xmlparser::Token::ElementStart { local, .. } => {
+                let namespaces = stack
+                    .last()
+                    .map(|open| open.namespaces.clone())
+                    .unwrap_or_default();
+                stack.push(Open {
+                    element: Element {
+                        name: local.as_str().to_string(),
+                        ..Default::default()
+                    },
+                    namespaces,
+                    prefixed_attributes: Vec::new(),
+                });
+            }
+            xmlparser::Token::Attribute {
+                prefix,
+                local,
+                value,
+                ..
+            } => {
+                let open = stack.last_mut().ok_or("Attribute outside of element")?;
+                let value = unescape(value.as_str());
+
+                if prefix.as_str() == "xmlns" {
+                    open.namespaces.insert(local.as_str().to_string(), value);
+                } else if prefix.is_empty() && local.as_str() == "xmlns" {
+                    open.namespaces.insert(String::new(), value);
+                } else if prefix.is_empty() {
+                    open.element.attributes.push((
+                        String::new(),
+                        local.as_str().to_string(),
+                        value,
+                    ));
+                } else {
+                    open.prefixed_attributes.push((
+                        prefix.as_str().to_string(),
+                        local.as_str().to_string(),
+                        value,
+                    ));
+                }
+            }
+            xmlparser::Token::ElementEnd { end, .. } => {
+                // Attributes are complete once the start tag ends
+                if !matches!(end, xmlparser::ElementEnd::Close(..)) {
+                    let open = stack.last_mut().ok_or("Unbalanced element end")?;
+                    for (prefix, local, value) in open.prefixed_attributes.drain(..) {
+                        let ns = match prefix.as_str() {
+                            "xml" => XML_NS.to_string(),
+                            _ => open.namespaces.get(&prefix).cloned().unwrap_or(prefix),
+                        };
+                        open.element.attributes.push((ns, local, value));
+                    }
+                }
+
+                if let xmlparser::ElementEnd::Open = end {
+                    continue;
+                }
+
+                let open = stack.pop().ok_or("Unbalanced element end")?;
+                match stack.last_mut() {
+                    Some(parent) => parent.element.children.push(Node::Element(open.element)),
+                    None => root = Some(open.element),
+                }
+            }
+            xmlparser::Token::Text { text } => {
+                if let Some(open) = stack.last_mut() {
+                    open.element
+                        .children
+                        .push(Node::Text(unescape(text.as_str())));
+                }
+            }
+            xmlparser::Token::Cdata { text, .. } => {
+                if let Some(open) = stack.last_mut() {
+                    open.element
+                        .children
+                        .push(Node::Text(text.as_str().to_string()));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    root.ok_or_else(|| "Incomplete document".to_string())
+}
+
+/// The frame, sub-frame and tick rates of the document
+#[derive(Clone, Copy, Debug)]
+struct TimeParameters {
+    frame_rate: f64,
+    sub_frame_rate: f64,
+    tick_rate: f64,
+}
+
+impl TimeParameters {
+    fn new(tt: &Element) -> Self {
+        let parse = |name| {
+            tt.attribute(TTP_NS, name)
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|value| *value > 0.0)
+        };
+
+        let multiplier = tt
+            .attribute(TTP_NS, "frameRateMultiplier")
+            .and_then(|value| {
+                let mut parts = value.split_whitespace().map(|v| v.parse::<f64>().ok());
+                Some(parts.next()?? / parts.next()??)
+            })
+            .filter(|value| value.is_finite() && *value > 0.0)
+            .unwrap_or(1.0);
+
+        let frame_rate = parse("frameRate").unwrap_or(30.0) * multiplier;
+        let sub_frame_rate = parse("subFrameRate").unwrap_or(1.0);
+        let tick_rate = parse("tickRate").unwrap_or_else(|| {
+            if tt.attribute(TTP_NS, "frameRate").is_some() {
+                frame_rate * sub_frame_rate
+            } else {
+                1.0
+            }
+        });
+
+        Self {
+            frame_rate,
+            sub_frame_rate,
+            tick_rate,
+        }
+    }
+
+    /// Parses a clock time or offset time expression
+    fn parse(&self, s: &str) -> Option<gst::ClockTime> {
+        let s = s.trim();
+
+        let seconds = if s.contains(':') {
+            let parts = s.split(':').collect::<Vec<_>>();
+            let (hours, minutes, seconds, frames) = match parts.as_slice() {
+                [h, m, s] => (h, m, *s, None),
+                [h, m, s, f] => (h, m, *s, Some(*f)),
+                _ => return None,
+            };
+
+            let frames = match frames {
+                Some(frames) => {
+                    let (frames, sub_frames) = frames.split_once('.').unwrap_or((frames, "0"));
+                    (frames.parse::<f64>().ok()?
+                        + sub_frames.parse::<f64>().ok()? / self.sub_frame_rate)
+                        / self.frame_rate
+                }
+                None => 0.0,
+            };
+
+            hours.parse::<u64>().ok()? as f64 * 3600.0
+                + minutes.parse::<u64>().ok()? as f64 * 60.0
+                + seconds.parse::<f64>().ok()?
+                + frames
+        } else {
+            let split = s.find(|c: char| c.is_ascii_alphabetic())?;
+            let value = s[..split].parse::<f64>().ok()?;
+
+            match &s[split..] {
+                "h" => value * 3600.0,
+                "m" => value * 60.0,
+                "s" => value,
+                "ms" => value / 1000.0,
+                "f" => value / self.frame_rate,
+                "t" => value / self.tick_rate,
+                _ => return None,
+            }
+        };
+
+        if seconds.is_finite() && seconds >= 0.0 {
+            Some(gst::ClockTime::from_nseconds(
+                (seconds * 1_000_000_000.0).round() as u64,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// The styling properties that map to CEA-608
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Style {
+    color: Option<TextStyle>,
+    italic: Option<bool>,
+    underline: Option<bool>,
+    text_align: Option<Align>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Align {
+    Start,
+    Center,
+    End,
+}
+
+impl Style {
+    /// Overrides the properties set in `other`
+    fn merge(&mut self, other: &Style) {
+        self.color = other.color.or(self.color);
+        self.italic = other.italic.or(self.italic);
+        self.underline = other.underline.or(self.underline);
+        self.text_align = other.text_align.or(self.text_align);
+    }
+
+    fn text_style(&self) -> TextStyle {
+        // CEA-608 can't combine italics with colors
+        if self.italic == Some(true) {
+            TextStyle::ItalicWhite
+        } else {
+            self.color.unwrap_or(TextStyle::White)
+        }
+    }
+}
+
+/// Maps a TTML color to the closest CEA-608 color
+fn color(value: &str) -> Option<TextStyle> {
+    let value = value.trim().to_ascii_lowercase();
+
+    let rgb = match value.as_str() {
+        "white" | "silver" | "gray" => return Some(TextStyle::White),
+        "lime" | "green" => return Some(TextStyle::Green),
+        "blue" | "navy" => return Some(TextStyle::Blue),
+        "cyan" | "aqua" | "teal" => return Some(TextStyle::Cyan),
+        "red" | "maroon" => return Some(TextStyle::Red),
+        "yellow" | "olive" => return Some(TextStyle::Yellow),
+        "magenta" | "fuchsia" | "purple" => return Some(TextStyle::Magenta),
+        _ => {
+            if let Some(hex) = value.strip_prefix('#') {
+                if hex.len() != 6 && hex.len() != 8 {
+                    return None;
+                }
+                let component = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+                [component(0)?, component(2)?, component(4)?]
+            } else {
+                let args = value
+                    .strip_prefix("rgba(")
+                    .or_else(|| value.strip_prefix("rgb("))?
+                    .strip_suffix(')')?;
+                let mut components = args.split(',').map(|c| c.trim().parse::<u8>().ok());
+                [
+                    components.next()??,
+                    components.next()??,
+                    components.next()??,
+                ]
+            }
+        }
+    };
+
+    // Dark colors have no CEA-608 foreground equivalent
+    if rgb.iter().all(|c| *c < 64) {
+        return None;
+    }
+
+    [
+        (TextStyle::White, [255, 255, 255]),
+        (TextStyle::Green, [0, 255, 0]),
+        (TextStyle::Blue, [0, 0, 255]),
+        (TextStyle::Cyan, [0, 255, 255]),
+        (TextStyle::Red, [255, 0, 0]),
+        (TextStyle::Yellow, [255, 255, 0]),
+        (TextStyle::Magenta, [255, 0, 255]),
+    ]
+    .iter()
+    .min_by_key(|(_, palette)| {
+        palette
+            .iter()
+            .zip(rgb.iter())
+            .map(|(a, b)| (*a - *b as i32).pow(2))
+            .sum::<i32>()
+    })
+    .map(|(style, _)| *style)
+}
+
+/// The inline styling attributes of an element
+fn inline_style(element: &Element) -> Style {
+    Style {
+        color: element.attribute(TTS_NS, "color").and_then(color),
+        italic: element
+            .attribute(TTS_NS, "fontStyle")
+            .map(|value| value.trim() != "normal"),
+        underline: element
+            .attribute(TTS_NS, "textDecoration")
+            .map(|value| value.split_whitespace().any(|v| v == "underline")),
+        text_align: element
+            .attribute(TTS_NS, "textAlign")
+            .and_then(|value| match value.trim() {
+                "left" | "start" => Some(Align::Start),
+                "center" => Some(Align::Center),
+                "right" | "end" => Some(Align::End),
+                _ => None,
+            }),
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct RegionLayout {
+    /// Origin in percent of the video
+    origin: Option<(f64, f64)>,
+    style: Style,
+}
+
+struct Document<'a> {
+    time: TimeParameters,
+    styles: HashMap<&'a str, &'a Element>,
+    regions: HashMap<&'a str, RegionLayout>,
+    paragraphs: Vec<Paragraph>,
+    mode: Cea608Mode,
+}
+
+fn percent_pair(value: &str) -> Option<(f64, f64)> {
+    let mut parts = value
+        .split_whitespace()
+        .map(|part| part.strip_suffix('%')?.parse::<f64>().ok());
+
+    Some((parts.next()??, parts.next()??))
+}
+
+impl<'a> Document<'a> {
+    /// The style referenced by the `style` attribute of an element, and its
+    /// inline style
+    fn style(&self, element: &Element, depth: usize) -> Style {
+        let mut style = Style::default();
+
+        if depth < 16 {
+            for id in element
+                .attribute("", "style")
+                .unwrap_or("")
+                .split_whitespace()
+            {
+                if let Some(referenced) = self.styles.get(id) {
+                    style.merge(&self.style(referenced, depth + 1));
+                }
+            }
+        }
+
+        style.merge(&inline_style(element));
+        style
+    }
+
+    fn read_head(&mut self, head: &'a Element) {
+        for section in head.elements() {
+            match section.name.as_str() {
+                "styling" => {
+                    for style in section.elements().filter(|e| e.name == "style") {
+                        if let Some(id) = style.attribute(XML_NS, "id") {
+                            self.styles.insert(id, style);
+                        }
+                    }
+                }
+                "layout" => {
+                    for region in section.elements().filter(|e| e.name == "region") {
+                        if let Some(id) = region.attribute(XML_NS, "id") {
+                            let layout = RegionLayout {
+                                origin: region.attribute(TTS_NS, "origin").and_then(percent_pair),
+                                style: self.style(region, 0),
+                            };
+                            self.regions.insert(id, layout);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Computes the active interval of an element, relative to its parent's
+    fn interval(
+        &self,
+        element: &Element,
+        parent_begin: gst::ClockTime,
+        parent_end: Option<gst::ClockTime>,
+    ) -> (gst::ClockTime, Option<gst::ClockTime>) {
+        let attribute = |name| {
+            element
+                .attribute("", name)
+                .and_then(|value| self.time.parse(value))
+        };
+
+        let begin = parent_begin + attribute("begin").unwrap_or(gst::ClockTime::ZERO);
+        let end = match (attribute("end"), attribute("dur")) {
+            (Some(end), Some(dur)) => Some((parent_begin + end).min(begin + dur)),
+            (Some(end), None) => Some(parent_begin + end),
+            (None, Some(dur)) => Some(begin + dur),
+            (None, None) => parent_end,
+        };
+        let end = match (end, parent_end) {
+            (Some(end), Some(parent_end)) => Some(end.min(parent_end)),
+            (end, _) => end,
+        };
+
+        (begin, end)
+    }
+
+    /// Collects the styled text of a paragraph, split on `<br/>`
+    fn collect_text(&self, element: &Element, style: Style, lines: &mut Vec<Vec<(Style, String)>>) {
+        for child in &element.children {
+            match child {
+                Node::Text(text) => {
+                    let line = lines.last_mut().unwrap();
+                    // Default XML whitespace handling, collapsing runs
+                    let mut collapsed = String::with_capacity(text.len());
+                    for c in text.chars() {
+                        if c.is_whitespace() {
+                            let previous_space = collapsed.ends_with(' ')
+                                || (collapsed.is_empty()
+                                    && !matches!(line.last(), Some((_, t)) if !t.ends_with(' ')));
+                            if !previous_space {
+                                collapsed.push(' ');
+                            }
+                        } else {
+                            collapsed.push(c);
+                        }
+                    }
+
+                    if collapsed.is_empty() {
+                        continue;
+                    }
+
+                    match line.last_mut() {
+                        Some((last, text)) if *last == style => text.push_str(&collapsed),
+                        _ => line.push((style, collapsed)),
+                    }
+                }
+                Node::Element(child) => match child.name.as_str() {
+                    "br" => lines.push(Vec::new()),
+                    "span" => {
+                        let mut child_style = style;
+                        child_style.merge(&self.style(child, 0));
+                        self.collect_text(child, child_style, lines);
+                    }
+                    _ => (),
+                },
+            }
+        }
+    }
+
+    fn read_paragraph(
+        &mut self,
+        p: &Element,
+        style: Style,
+        region: Option<&str>,
+        begin: gst::ClockTime,
+        end: gst::ClockTime,
+    ) {
+        let region = p.attribute("", "region").or(region);
+        let layout = region
+            .and_then(|region| self.regions.get(region))
+            .copied()
+            .unwrap_or_default();
+
+        let mut p_style = layout.style;
+        p_style.merge(&style);
+        p_style.merge(&self.style(p, 0));
+
+        let mut styled_lines = vec![Vec::new()];
+        self.collect_text(p, p_style, &mut styled_lines);
+
+        let lines = styled_lines
+            .into_iter()
+            .map(|runs| {
+                runs.into_iter()
+                    .filter_map(|(style, text)| {
+                        let text = text.trim();
+                        if text.is_empty() {
+                            None
+                        } else {
+                            Some(Chunk {
+                                style: style.text_style(),
+                                underline: style.underline.unwrap_or(false),
+                                text: text.to_string(),
+                            })
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|chunks| !chunks.is_empty())
+            .collect::<Vec<_>>();
+
+        if lines.is_empty() {
+            return;
+        }
+
+        let first_row = match layout.origin {
+            Some((_, y)) => ((y - 10.0) / ROW_HEIGHT)
+                .round()
+                .clamp(0.0, 15.0 - lines.len().min(15) as f64) as u32,
+            None => default_first_row(lines.len()),
+        };
+        let origin_column = layout.origin.map_or(0.0, |(x, _)| {
+            ((x - 10.0) / COLUMN_WIDTH).round().clamp(0.0, 31.0)
+        }) as u32;
+        let align = p_style.text_align.unwrap_or(if layout.origin.is_some() {
+            Align::Start
+        } else {
+            Align::Center
+        });
+
+        let lines = lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunks)| {
+                let mut line = Line {
+                    column: None,
+                    row: Some((first_row + i as u32).min(14)),
+                    chunks,
+                    carriage_return: Some(true),
+                };
+
+                let len = line_len(&line).min(32);
+                line.column = Some(match align {
+                    Align::Start => origin_column.min(32 - len),
+                    Align::Center => (32 - len) / 2,
+                    Align::End => 32 - len,
+                });
+
+                line
+            })
+            .collect();
+
+        self.paragraphs.push(Paragraph {
+            begin,
+            end,
+            lines: Lines {
+                lines,
+                mode: Some(self.mode),
+                clear: Some(false),
+            },
+        });
+    }
+
+    fn read_content(
+        &mut self,
+        element: &'a Element,
+        style: Style,
+        region: Option<&'a str>,
+        parent_begin: gst::ClockTime,
+        parent_end: Option<gst::ClockTime>,
+    ) {
+        let (begin, end) = self.interval(element, parent_begin, parent_end);
+        let region = element.attribute("", "region").or(region);
+
+        match element.name.as_str() {
+            "p" => {
+                // Paragraphs without end are active until the end of the
+                // document, which is unknown here
+                if let Some(end) = end.filter(|end| *end > begin) {
+                    self.read_paragraph(element, style, region, begin, end);
+                }
+            }
+            "body" | "div" => {
+                let mut style = style;
+                style.merge(&self.style(element, 0));
+
+                for child in element.elements() {
+                    self.read_content(child, style, region, begin, end);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Parses the paragraphs of a TTML document, ordered by begin time
+pub fn parse_document(data: &str, mode: Cea608Mode) -> Result<Vec<Paragraph>, String> {
+    let tt = parse_tree(data)?;
+    if tt.name != "tt" {
+        return Err(format!("Unexpected root element '{}'", tt.name));
+    }
+
+    let mut doc = Document {
+        time: TimeParameters::new(&tt),
+        styles: HashMap::new(),
+        regions: HashMap::new(),
+        paragraphs: Vec::new(),
+        mode,
+    };
+
+    if let Some(head) = tt.elements().find(|e| e.name == "head") {
+        doc.read_head(head);
+    }
+
+    if let Some(body) = tt.elements().find(|e| e.name == "body") {
+        doc.read_content(body, Style::default(), None, gst::ClockTime::ZERO, None);
+    }
+
+    let mut paragraphs = doc.paragraphs;
+    paragraphs.sort_by_key(|paragraph| paragraph.begin);
+
+    // Lines in different regions are separate paragraphs, but displayed
+    // together
+    let mut merged: Vec<Paragraph> = Vec::with_capacity(paragraphs.len());
+    for paragraph in paragraphs {
+        match merged.last_mut() {
+            Some(last) if last.begin == paragraph.begin && last.end == paragraph.end => {
+                last.lines.lines.extend(paragraph.lines.lines);
+            }
+            _ => merged.push(paragraph),
+        }
+    }
+
+    Ok(merged)
+}
+
+/// Whether the data ends with the end tag of the root element, checked
+/// before trying to parse a document that is received in several buffers
+pub fn is_document_complete(data: &[u8]) -> bool {
+    let end = data
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(&data[..0], |pos| &data[..pos + 1]);
+
+    end.ends_with(b"tt>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(row: Option<u32>, column: Option<u32>, chunks: &[(TextStyle, bool, &str)]) -> Line {
+        Line {
+            column,
+            row,
+            chunks: chunks
+                .iter()
+                .map(|(style, underline, text)| Chunk {
+                    style: *style,
+                    underline: *underline,
+                    text: text.to_string(),
+                })
+                .collect(),
+            carriage_return: Some(true),
+        }
+    }
+
+    /// Begin and end in milliseconds, then row, column and styled text of
+    /// each line
+    type Summary = Vec<(u64, u64, Vec<(u32, u32, String)>)>;
+
+    fn summary(paragraphs: &[Paragraph]) -> Summary {
+        paragraphs
+            .iter()
+            .map(|paragraph| {
+                (
+                    paragraph.begin.mseconds(),
+                    paragraph.end.mseconds(),
+                    paragraph
+                        .lines
+                        .lines
+                        .iter()
+                        .map(|line| {
+                            (
+                                line.row.unwrap(),
+                                line.column.unwrap(),
+                                line.chunks
+                                    .iter()
+                                    .map(|chunk| {
+                                        format!(
+                                            "{:?}{}:{}",
+                                            chunk.style,
+                                            if chunk.underline { "_" } else { "" },
+                                            chunk.text
+                                        )
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .join("|"),
+                            )
+                        })
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let paragraphs = vec![
+            Paragraph {
+                begin: gst::ClockTime::from_mseconds(1500),
+                end: gst::ClockTime::from_mseconds(3250),
+                lines: Lines {
+                    lines: vec![
+                        line(
+                            Some(13),
+                            Some(4),
+                            &[
+                                (TextStyle::Yellow, false, "Hello"),
+                                (TextStyle::White, true, "<world>"),
+                                (TextStyle::White, false, "!"),
+                            ],
+                        ),
+                        line(None, Some(8), &[(TextStyle::ItalicWhite, false, "& more")]),
+                    ],
+                    mode: Some(Cea608Mode::PopOn),
+                    clear: Some(false),
+                },
+            },
+            Paragraph {
+                begin: gst::ClockTime::from_seconds(3700),
+                end: gst::ClockTime::from_seconds(3701),
+                lines: Lines {
+                    lines: vec![line(None, None, &[(TextStyle::Cyan, false, "centered")])],
+                    mode: Some(Cea608Mode::PopOn),
+                    clear: Some(false),
+                },
+            },
+        ];
+
+        let doc = write_document(&paragraphs, "en");
+        assert!(doc.contains(
+            "<region xml:id=\"r13c4\" tts:origin=\"20% 79.333%\" tts:extent=\"70% 5.333%\" tts:displayAlign=\"before\" tts:textAlign=\"start\"/>"
+        ));
+        assert!(doc.contains("begin=\"01:01:40.000\" end=\"01:01:41.000\" region=\"r13\""));
+        assert!(doc
+            .contains("<span style=\"s0\" tts:textDecoration=\"underline\"> &lt;world&gt;</span>"));
+
+        let parsed = parse_document(&doc, Cea608Mode::PopOn).unwrap();
+        assert_eq!(paragraph_text(&parsed[0]), "Hello <world>!\n& more");
+        assert_eq!(
+            summary(&parsed),
+            vec![
+                (
+                    1500,
+                    3250,
+                    vec![
+                        (13, 4, "Yellow:Hello|White_:<world>|White:!".to_string()),
+                        (14, 8, "ItalicWhite:& more".to_string()),
+                    ]
+                ),
+                (
+                    3_700_000,
+                    3_701_000,
+                    vec![(13, 12, "Cyan:centered".to_string())]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_timing_and_styles() {
+        let doc = r##"<?xml version="1.0" encoding="UTF-8"?>
+<!-- A comment -->
+<tt:tt xmlns:tt="http://www.w3.org/ns/ttml" xmlns:s="http://www.w3.org/ns/ttml#styling"
+       xmlns:p="http://www.w3.org/ns/ttml#parameter" p:frameRate="25" p:tickRate="10000000">
+  <tt:head>
+    <tt:styling>
+      <tt:style xml:id="base" s:color="#FFFF00"/>
+      <tt:style xml:id="it" style="base" s:fontStyle="italic"/>
+    </tt:styling>
+    <tt:layout>
+      <tt:region xml:id="top" s:origin="10% 10%" s:textAlign="center"/>
+    </tt:layout>
+  </tt:head>
+  <tt:body style="base">
+    <tt:div begin="10s">
+      <tt:p begin="00:00:01:05" dur="20000000t" region="top">
+        Hello
+        <tt:span s:color="rgb(0, 250, 10)">green</tt:span><tt:br/>
+        <tt:span style="it">  italic  </tt:span>
+      </tt:p>
+      <tt:p begin="500ms" end="1.2s">Yellow &amp; <![CDATA[<cdata>]]></tt:p>
+      <tt:p begin="1s">No end</tt:p>
+    </tt:div>
+  </tt:body>
+</tt:tt>"##;
+
+        let parsed = parse_document(doc, Cea608Mode::RollUp2).unwrap();
+        assert_eq!(
+            summary(&parsed),
+            vec![
+                (
+                    10_500,
+                    11_200,
+                    vec![(13, 8, "Yellow:Yellow & <cdata>".to_string())]
+                ),
+                (
+                    11_200,
+                    13_200,
+                    vec![
+                        (0, 10, "Yellow:Hello|Green:green".to_string()),
+                        (1, 13, "ItalicWhite:italic".to_string()),
+                    ]
+                ),
+            ]
+        );
+        assert_eq!(parsed[0].lines.mode, Some(Cea608Mode::RollUp2));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_document(
+            "<tt xmlns=\"http://www.w3.org/ns/ttml\"><body>",
+            Cea608Mode::PopOn
+        )
+        .is_err());
+        assert!(parse_document("<html></html>", Cea608Mode::PopOn).is_err());
+        assert!(is_document_complete(b"<tt>\n</tt>\n\n"));
+        assert!(is_document_complete(b"<tt:tt></tt:tt>"));
+        assert!(!is_document_complete(b"<tt><body>"));
+    }
+}
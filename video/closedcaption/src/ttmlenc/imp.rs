@@ -0,0 +1,584 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::element_error;
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::ttml::{write_document, Paragraph};
+use crate::ttutils::{Cea608Mode, Chunk, Line, Lines, TextStyle};
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ttmlenc",
+        gst::DebugColorFlags::empty(),
+        Some("TTML Encoder Element"),
+    )
+});
+
+const DEFAULT_SEGMENT_DURATION: u64 = 0;
+const DEFAULT_LANGUAGE: &str = "en";
+
+#[derive(Debug, Clone)]
+struct Settings {
+    segment_duration: Option<gst::ClockTime>,
+    language: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            segment_duration: None,
+            language: String::from(DEFAULT_LANGUAGE),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Input {
+    Text,
+    Json,
+}
+
+struct State {
+    input: Input,
+    /// Paragraphs not completely output yet, in segmented mode
+    pending: VecDeque<Paragraph>,
+    /// Start of the next document, in segmented mode
+    document_start: Option<gst::ClockTime>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            input: Input::Text,
+            pending: VecDeque::new(),
+            document_start: None,
+        }
+    }
+}
+
+/// Positions plain text lines at the bottom of the screen, like `tttojson`
+fn text_lines(text: &str) -> Lines {
+    let n_lines = text.lines().count();
+    let mut row = 15u32.saturating_sub(n_lines as u32).min(13);
+    let mut lines = Vec::with_capacity(n_lines);
+
+    for phrase in text.lines() {
+        lines.push(Line {
+            carriage_return: Some(true),
+            column: None,
+            row: Some(row),
+            chunks: vec![Chunk {
+                // Default CEA 608 styling
+                style: TextStyle::White,
+                underline: false,
+                text: phrase.to_string(),
+            }],
+        });
+
+        row = (row + 1).min(14);
+    }
+
+    Lines {
+        lines,
+        mode: Some(Cea608Mode::PopOn),
+        clear: Some(false),
+    }
+}
+
+/// Restricts the paragraph to the interval, if it overlaps it
+fn clip(paragraph: &Paragraph, start: gst::ClockTime, end: gst::ClockTime) -> Option<Paragraph> {
+    if paragraph.end <= start || paragraph.begin >= end {
+        return None;
+    }
+
+    Some(Paragraph {
+        begin: paragraph.begin.max(start),
+        end: paragraph.end.min(end),
+        lines: paragraph.lines.clone(),
+    })
+}
+
+fn create_buffer(
+    paragraphs: &[Paragraph],
+    language: &str,
+    pts: gst::ClockTime,
+    duration: gst::ClockTime,
+) -> gst::Buffer {
+    let doc = write_document(paragraphs, language);
+
+    let mut buffer = gst::Buffer::from_mut_slice(doc.into_bytes());
+    {
+        let buffer = buffer.get_mut().unwrap();
+        buffer.set_pts(pts);
+        buffer.set_duration(duration);
+    }
+
+    buffer
+}
+
+impl State {
+    /// Outputs the documents that end before `until`, or all the documents
+    /// needed for the pending paragraphs when `until` is `None`
+    fn drain(
+        &mut self,
+        settings: &Settings,
+        segment_duration: gst::ClockTime,
+        until: Option<gst::ClockTime>,
+    ) -> Vec<gst::Buffer> {
+        let mut buffers = Vec::new();
+
+        let mut start = match self.document_start {
+            Some(start) => start,
+            None => return buffers,
+        };
+
+        loop {
+            let end = start + segment_duration;
+
+            match until {
+                Some(until) if end > until => break,
+                None if self.pending.is_empty() => break,
+                _ => (),
+            }
+
+            let paragraphs = self
+                .pending
+                .iter()
+                .filter_map(|paragraph| clip(paragraph, start, end))
+                .collect::<Vec<_>>();
+
+            gst::trace!(
+                CAT,
+                "Outputting document {} - {} with {} paragraphs",
+                start,
+                end,
+                paragraphs.len()
+            );
+
+            buffers.push(create_buffer(
+                &paragraphs,
+                &settings.language,
+                start,
+                segment_duration,
+            ));
+
+            self.pending.retain(|paragraph| paragraph.end > end);
+            start = end;
+        }
+
+        self.document_start = Some(start);
+
+        buffers
+    }
+
+    /// Aligns the first document on a multiple of the segment duration
+    fn start_document(&mut self, segment_duration: gst::ClockTime, pts: gst::ClockTime) {
+        if self.document_start.is_none() {
+            let duration = segment_duration.nseconds();
+            self.document_start = Some(gst::ClockTime::from_nseconds(
+                pts.nseconds() / duration * duration,
+            ));
+        }
+    }
+
+    fn handle_buffer(
+        &mut self,
+        element: &super::TtmlEnc,
+        settings: &Settings,
+        buffer: gst::Buffer,
+    ) -> Result<Vec<gst::Buffer>, gst::FlowError> {
+        let pts = buffer.pts().ok_or_else(|| {
+            element_error!(
+                element,
+                gst::StreamError::Format,
+                ["Require timestamped buffers"]
+            );
+            gst::FlowError::Error
+        })?;
+
+        let duration = buffer.duration().ok_or_else(|| {
+            element_error!(
+                element,
+                gst::StreamError::Format,
+                ["Require buffers with duration"]
+            );
+            gst::FlowError::Error
+        })?;
+
+        let data = buffer.map_readable().map_err(|_| {
+            element_error!(
+                element,
+                gst::ResourceError::Read,
+                ["Failed to map buffer readable"]
+            );
+
+            gst::FlowError::Error
+        })?;
+
+        let lines = match self.input {
+            Input::Json => serde_json::from_slice::<Lines>(&data).map_err(|err| {
+                element_error!(
+                    element,
+                    gst::StreamError::Decode,
+                    ["Failed to parse input as json: {}", err]
+                );
+
+                gst::FlowError::Error
+            })?,
+            Input::Text => {
+                let text = std::str::from_utf8(&data).map_err(|err| {
+                    element_error!(
+                        element,
+                        gst::StreamError::Decode,
+                        ["Failed to decode as utf8: {}", err]
+                    );
+
+                    gst::FlowError::Error
+                })?;
+
+                text_lines(text)
+            }
+        };
+
+        let paragraph = Paragraph {
+            begin: pts,
+            end: pts + duration,
+            lines,
+        };
+
+        let segment_duration = match settings.segment_duration {
+            Some(segment_duration) => segment_duration,
+            None => {
+                // One document per buffer
+                return Ok(vec![create_buffer(
+                    &[paragraph],
+                    &settings.language,
+                    pts,
+                    duration,
+                )]);
+            }
+        };
+
+        self.start_document(segment_duration, pts);
+        let buffers = self.drain(settings, segment_duration, Some(pts));
+        self.pending.push_back(paragraph);
+
+        Ok(buffers)
+    }
+
+    fn handle_gap(&mut self, settings: &Settings, gap: &gst::event::Gap) -> Vec<gst::Buffer> {
+        let segment_duration = match settings.segment_duration {
+            Some(segment_duration) => segment_duration,
+            None => return vec![],
+        };
+
+        let (pts, duration) = gap.get();
+        let end = pts.opt_add(duration).unwrap_or(pts);
+
+        self.start_document(segment_duration, pts);
+        self.drain(settings, segment_duration, Some(end))
+    }
+
+    fn handle_eos(&mut self, settings: &Settings) -> Vec<gst::Buffer> {
+        match settings.segment_duration {
+            Some(segment_duration) => self.drain(settings, segment_duration, None),
+            None => vec![],
+        }
+    }
+}
+
+pub struct TtmlEnc {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    state: Mutex<State>,
+    settings: Mutex<Settings>,
+}
+
+impl TtmlEnc {
+    fn output(&self, buffers: Vec<gst::Buffer>) -> Result<gst::FlowSuccess, gst::FlowError> {
+        for buffer in buffers {
+            self.srcpad.push(buffer)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn sink_chain(
+        &self,
+        pad: &gst::Pad,
+        element: &super::TtmlEnc,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::log!(CAT, obj: pad, "Handling buffer {:?}", buffer);
+
+        let settings = self.settings.lock().unwrap().clone();
+        let mut state = self.state.lock().unwrap();
+        let buffers = state.handle_buffer(element, &settings, buffer)?;
+        drop(state);
+
+        self.output(buffers)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, element: &super::TtmlEnc, event: gst::Event) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad, "Handling event {:?}", event);
+
+        match event.view() {
+            EventView::Caps(ev) => {
+                let input = match ev.caps().structure(0) {
+                    Some(s) if s.name() == "application/x-json" => Input::Json,
+                    _ => Input::Text,
+                };
+                self.state.lock().unwrap().input = input;
+
+                // We send our own caps downstream
+                let caps = gst::Caps::builder("application/ttml+xml").build();
+                self.srcpad.push_event(gst::event::Caps::new(&caps))
+            }
+            EventView::Segment(ev) => {
+                if ev.segment().format() != gst::Format::Time {
+                    element_error!(
+                        element,
+                        gst::StreamError::Failed,
+                        ["Time segment needed: {:?}", ev.segment()]
+                    );
+                    return false;
+                }
+
+                pad.event_default(Some(element), event)
+            }
+            EventView::Gap(ev) => {
+                let settings = self.settings.lock().unwrap().clone();
+                let mut state = self.state.lock().unwrap();
+                let buffers = state.handle_gap(&settings, ev);
+                drop(state);
+
+                if settings.segment_duration.is_some() {
+                    // Gaps are filled with empty documents
+                    let _ = self.output(buffers);
+                    true
+                } else {
+                    pad.event_default(Some(element), event)
+                }
+            }
+            EventView::FlushStop(_) => {
+                let mut state = self.state.lock().unwrap();
+                state.pending.clear();
+                state.document_start = None;
+                drop(state);
+
+                pad.event_default(Some(element), event)
+            }
+            EventView::Eos(_) => {
+                let settings = self.settings.lock().unwrap().clone();
+                let mut state = self.state.lock().unwrap();
+                let buffers = state.handle_eos(&settings);
+                drop(state);
+
+                let _ = self.output(buffers);
+
+                pad.event_default(Some(element), event)
+            }
+            _ => pad.event_default(Some(element), event),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for TtmlEnc {
+    const NAME: &'static str = "RsTtmlEnc";
+    type Type = super::TtmlEnc;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                TtmlEnc::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |enc, element| enc.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                TtmlEnc::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |enc, element| enc.sink_event(pad, element, event),
+                )
+            })
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .flags(gst::PadFlags::FIXED_CAPS)
+            .build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            state: Mutex::new(State::default()),
+            settings: Mutex::new(Settings::default()),
+        }
+    }
+}
+
+impl ObjectImpl for TtmlEnc {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecUInt64::builder("segment-duration")
+                    .nick("Segment Duration")
+                    .blurb("Duration of the output documents in nanoseconds, for example for fMP4 stpp tracks, 0 outputs one document per input buffer")
+                    .default_value(DEFAULT_SEGMENT_DURATION)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecString::builder("language")
+                    .nick("Language")
+                    .blurb("Language of the documents, as xml:lang")
+                    .default_value(Some(DEFAULT_LANGUAGE))
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "segment-duration" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.segment_duration = match value.get::<u64>().expect("type checked upstream")
+                {
+                    0 => None,
+                    duration => Some(gst::ClockTime::from_nseconds(duration)),
+                };
+            }
+            "language" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.language = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_else(|| String::from(DEFAULT_LANGUAGE));
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "segment-duration" => {
+                let settings = self.settings.lock().unwrap();
+                settings
+                    .segment_duration
+                    .map_or(0, |duration| duration.nseconds())
+                    .to_value()
+            }
+            "language" => {
+                let settings = self.settings.lock().unwrap();
+                settings.language.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for TtmlEnc {}
+
+impl ElementImpl for TtmlEnc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "TTML Encoder",
+                "Encoder/Subtitle",
+                "Encodes timed text or CEA-608 JSON to IMSC1 TTML documents",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let mut caps = gst::Caps::new_empty();
+            {
+                let caps = caps.get_mut().unwrap();
+
+                let s = gst::Structure::builder("text/x-raw")
+                    .field("format", "utf8")
+                    .build();
+                caps.append_structure(s);
+
+                let s = gst::Structure::builder("application/x-json")
+                    .field("format", "cea608")
+                    .build();
+                caps.append_structure(s);
+            }
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let caps = gst::Caps::builder("application/ttml+xml").build();
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        if transition == gst::StateChange::ReadyToPaused {
+            let mut state = self.state.lock().unwrap();
+            *state = State::default();
+        }
+
+        self.parent_change_state(element, transition)
+    }
+}
@@ -0,0 +1,543 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::element_error;
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+use std::sync::Mutex;
+
+use crate::ttml::{is_document_complete, paragraph_text, parse_document, Paragraph};
+use crate::ttutils::Cea608Mode;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ttmlparse",
+        gst::DebugColorFlags::empty(),
+        Some("TTML Parser Element"),
+    )
+});
+
+const DEFAULT_MODE: Cea608Mode = Cea608Mode::PopOn;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    mode: Cea608Mode,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings { mode: DEFAULT_MODE }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Output {
+    Text,
+    Json,
+}
+
+#[derive(Debug)]
+struct State {
+    /// Data of the document being received
+    data: Vec<u8>,
+    /// Timestamp and duration of the first buffer of the document, the
+    /// paragraphs are clipped to them when set
+    document_pts: Option<gst::ClockTime>,
+    document_duration: Option<gst::ClockTime>,
+    output: Option<Output>,
+    need_caps: bool,
+    need_segment: bool,
+    pending_events: Vec<gst::Event>,
+    segment: gst::FormattedSegment<gst::ClockTime>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            data: Vec::new(),
+            document_pts: None,
+            document_duration: None,
+            output: None,
+            need_caps: true,
+            need_segment: true,
+            pending_events: Vec::new(),
+            segment: gst::FormattedSegment::new(),
+        }
+    }
+}
+
+impl State {
+    fn create_events(&mut self, element: &super::TtmlParse) -> Vec<gst::Event> {
+        let mut events = Vec::new();
+
+        if self.need_caps {
+            if let Some(output) = self.output {
+                let caps = match output {
+                    Output::Text => gst::Caps::builder("text/x-raw")
+                        .field("format", "utf8")
+                        .build(),
+                    Output::Json => gst::Caps::builder("application/x-json")
+                        .field("format", "cea608")
+                        .build(),
+                };
+
+                gst::info!(CAT, obj: element, "Caps changed to {:?}", &caps);
+                events.push(gst::event::Caps::new(&caps));
+                self.need_caps = false;
+            }
+        }
+
+        if self.need_segment {
+            events.push(gst::event::Segment::new(&self.segment));
+            self.need_segment = false;
+        }
+
+        events.append(&mut self.pending_events);
+        events
+    }
+}
+
+pub struct TtmlParse {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    state: Mutex<State>,
+    settings: Mutex<Settings>,
+}
+
+impl TtmlParse {
+    /// Picks the output format preferred downstream
+    fn negotiate(&self, element: &super::TtmlParse) -> Result<(), gst::FlowError> {
+        if self.state.lock().unwrap().output.is_some() {
+            return Ok(());
+        }
+
+        let caps = self
+            .srcpad
+            .peer_query_caps(Some(&self.srcpad.pad_template_caps()));
+
+        let output = match caps.structure(0) {
+            Some(s) if s.name() == "application/x-json" => Output::Json,
+            Some(_) => Output::Text,
+            None => {
+                element_error!(
+                    element,
+                    gst::CoreError::Negotiation,
+                    ["Downstream accepts neither text nor JSON"]
+                );
+                return Err(gst::FlowError::NotNegotiated);
+            }
+        };
+
+        gst::debug!(CAT, obj: element, "Outputting {:?}", output);
+
+        let mut state = self.state.lock().unwrap();
+        state.output = Some(output);
+        state.need_caps = true;
+
+        Ok(())
+    }
+
+    fn create_buffer(
+        &self,
+        element: &super::TtmlParse,
+        output: Output,
+        paragraph: &Paragraph,
+    ) -> Result<gst::Buffer, gst::FlowError> {
+        let data = match output {
+            Output::Text => paragraph_text(paragraph).into_bytes(),
+            Output::Json => serde_json::to_vec(&paragraph.lines).map_err(|err| {
+                element_error!(
+                    element,
+                    gst::ResourceError::Write,
+                    ["Failed to serialize as json {}", err]
+                );
+
+                gst::FlowError::Error
+            })?,
+        };
+
+        let mut buffer = gst::Buffer::from_mut_slice(data);
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(paragraph.begin);
+            buffer.set_duration(paragraph.end - paragraph.begin);
+        }
+
+        Ok(buffer)
+    }
+
+    /// Parses the document received so far, and outputs its paragraphs
+    fn handle_document(
+        &self,
+        element: &super::TtmlParse,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        self.negotiate(element)?;
+
+        let mode = self.settings.lock().unwrap().mode;
+        let mut state = self.state.lock().unwrap();
+        let data = std::mem::take(&mut state.data);
+        let start = state.document_pts.take();
+        let end = start.opt_add(state.document_duration.take());
+
+        let mut buffers = gst::BufferList::new();
+
+        if !data.iter().all(|b| b.is_ascii_whitespace()) {
+            let doc = std::str::from_utf8(&data).map_err(|err| {
+                element_error!(
+                    element,
+                    gst::StreamError::Decode,
+                    ["Failed to decode as utf8: {}", err]
+                );
+
+                gst::FlowError::Error
+            })?;
+
+            let paragraphs = parse_document(doc, mode).map_err(|err| {
+                element_error!(
+                    element,
+                    gst::StreamError::Decode,
+                    ["Failed to parse TTML document: {}", err]
+                );
+
+                gst::FlowError::Error
+            })?;
+
+            let output = state.output.unwrap();
+
+            for mut paragraph in paragraphs {
+                // A document is only active during its sample
+                if let (Some(start), Some(end)) = (start, end) {
+                    paragraph.begin = paragraph.begin.max(start);
+                    paragraph.end = paragraph.end.min(end);
+                }
+
+                let clipped = if paragraph.end > paragraph.begin {
+                    state
+                        .segment
+                        .clip(paragraph.begin, paragraph.end)
+                        .and_then(|(begin, end)| Some((begin?, end?)))
+                        .filter(|(begin, end)| end > begin)
+                } else {
+                    None
+                };
+
+                match clipped {
+                    Some((begin, end)) => {
+                        paragraph.begin = begin;
+                        paragraph.end = end;
+                    }
+                    None => {
+                        gst::trace!(
+                            CAT,
+                            obj: element,
+                            "Skipping clipped paragraph {:?}",
+                            paragraph
+                        );
+                        continue;
+                    }
+                }
+
+                gst::trace!(CAT, obj: element, "Outputting paragraph {:?}", paragraph);
+
+                let buffer = self.create_buffer(element, output, &paragraph)?;
+                buffers.get_mut().unwrap().add(buffer);
+            }
+        }
+
+        let events = state.create_events(element);
+
+        // Drop our state mutex while we push out buffers or events
+        drop(state);
+
+        for event in events {
+            gst::debug!(CAT, obj: element, "Pushing event {:?}", event);
+            self.srcpad.push_event(event);
+        }
+
+        if !buffers.is_empty() {
+            self.srcpad.push_list(buffers)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn sink_chain(
+        &self,
+        pad: &gst::Pad,
+        element: &super::TtmlParse,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::log!(CAT, obj: pad, "Handling buffer {:?}", buffer);
+
+        let mut state = self.state.lock().unwrap();
+
+        let data = buffer.map_readable().map_err(|_| {
+            element_error!(
+                element,
+                gst::ResourceError::Read,
+                ["Failed to map buffer readable"]
+            );
+
+            gst::FlowError::Error
+        })?;
+
+        if state.data.is_empty() {
+            state.document_pts = buffer.pts();
+            state.document_duration = buffer.duration();
+        } else {
+            state.document_duration = state
+                .document_duration
+                .opt_add(buffer.duration())
+                .or(state.document_duration);
+        }
+        state.data.extend_from_slice(&data);
+
+        if !is_document_complete(&state.data) {
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        drop(state);
+
+        self.handle_document(element)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, element: &super::TtmlParse, event: gst::Event) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: pad, "Handling event {:?}", event);
+
+        match event.view() {
+            EventView::Caps(_) => {
+                // We send a proper caps event from the chain function later
+                gst::log!(CAT, obj: pad, "Dropping caps event");
+                true
+            }
+            EventView::Segment(ev) => {
+                let mut state = self.state.lock().unwrap();
+
+                // Documents in other formats are timed like the stream
+                // starts at 0
+                state.segment = ev
+                    .segment()
+                    .clone()
+                    .downcast::<gst::format::Time>()
+                    .unwrap_or_else(|_| gst::FormattedSegment::new());
+                state.need_segment = true;
+
+                // We send the segment event after the caps event
+                if !self.srcpad.has_current_caps() {
+                    return true;
+                }
+
+                let events = state.create_events(element);
+                drop(state);
+
+                for event in events {
+                    self.srcpad.push_event(event);
+                }
+
+                true
+            }
+            EventView::FlushStop(_) => {
+                let mut state = self.state.lock().unwrap();
+                state.data.clear();
+                state.document_pts = None;
+                state.document_duration = None;
+                drop(state);
+
+                pad.event_default(Some(element), event)
+            }
+            EventView::Eos(_) => {
+                gst::log!(CAT, obj: pad, "Draining");
+                if let Err(err) = self.handle_document(element) {
+                    gst::error!(CAT, obj: pad, "Failed to drain parser: {:?}", err);
+                }
+
+                pad.event_default(Some(element), event)
+            }
+            _ => {
+                if event.is_sticky()
+                    && !self.srcpad.has_current_caps()
+                    && event.type_() > gst::EventType::Caps
+                {
+                    gst::log!(CAT, obj: pad, "Deferring sticky event until we have caps");
+                    let mut state = self.state.lock().unwrap();
+                    state.pending_events.push(event);
+                    true
+                } else {
+                    pad.event_default(Some(element), event)
+                }
+            }
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for TtmlParse {
+    const NAME: &'static str = "RsTtmlParse";
+    type Type = super::TtmlParse;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                TtmlParse::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |parse, element| parse.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                TtmlParse::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |parse, element| parse.sink_event(pad, element, event),
+                )
+            })
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src")).build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            state: Mutex::new(State::default()),
+            settings: Mutex::new(Settings::default()),
+        }
+    }
+}
+
+impl ObjectImpl for TtmlParse {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecEnum::builder::<Cea608Mode>("mode", DEFAULT_MODE)
+                    .nick("Mode")
+                    .blurb("CEA-608 mode of the JSON output")
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "mode" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.mode = value.get::<Cea608Mode>().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "mode" => {
+                let settings = self.settings.lock().unwrap();
+                settings.mode.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for TtmlParse {}
+
+impl ElementImpl for TtmlParse {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "TTML Parse",
+                "Parser/Subtitle",
+                "Parses TTML documents to timed text or CEA-608 JSON",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let mut caps = gst::Caps::new_empty();
+            {
+                let caps = caps.get_mut().unwrap();
+
+                let s = gst::Structure::builder("text/x-raw")
+                    .field("format", "utf8")
+                    .build();
+                caps.append_structure(s);
+
+                let s = gst::Structure::builder("application/x-json")
+                    .field("format", "cea608")
+                    .build();
+                caps.append_structure(s);
+            }
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let caps = gst::Caps::builder("application/ttml+xml").build();
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, obj: element, "Changing state {:?}", transition);
+
+        match transition {
+            gst::StateChange::ReadyToPaused | gst::StateChange::PausedToReady => {
+                let mut state = self.state.lock().unwrap();
+                *state = State::default();
+            }
+            _ => (),
+        }
+
+        self.parent_change_state(element, transition)
+    }
+}
@@ -0,0 +1,1007 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+// The CEA-608 modes are mapped to windows as follows:
+//
+//  * Pop-on captions are composed in a hidden window covering the 608 grid,
+//    which is then displayed while the previously displayed one is deleted.
+//
+//  * Paint-on captions are written directly in a visible window covering
+//    the 608 grid, each written row being erased first.
+//
+//  * Roll-up captions are written at the bottom row of a visible window of
+//    2, 3 or 4 rows, and scroll up with carriage returns.
+//
+// Each sink pad encodes one caption service, the services of all pads are
+// then packed in the same DTVCC packets and output as CDP, one per frame.
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::prelude::*;
+use gst_base::subclass::prelude::*;
+use gst_base::AGGREGATOR_FLOW_NEED_DATA;
+
+use crate::ccutils::{cdp_frame_rate, write_cdp};
+use crate::cea708::{
+    Color, PacketWriter, PenAttributes, PenColor, ServiceWriter, WindowDefinition,
+    MAX_SERVICE_NUMBER,
+};
+use crate::ttutils::{Cea608Mode, Chunk, Line, Lines, TextStyle};
+
+use once_cell::sync::Lazy;
+
+use std::sync::Mutex;
+
+const DEFAULT_FPS_N: i32 = 30;
+const DEFAULT_FPS_D: i32 = 1;
+
+const DEFAULT_SERVICE_NUMBER: u32 = 1;
+const DEFAULT_MODE: Cea608Mode = Cea608Mode::RollUp2;
+const DEFAULT_ORIGIN_ROW: i32 = -1;
+const DEFAULT_ORIGIN_COLUMN: u32 = 0;
+
+// The CEA-608 grid rows and columns are expressed in
+const ROWS: u32 = 15;
+const COLUMNS: u32 = 32;
+
+// Unused CEA-608 field 1 and field 2 slots, placed before the DTVCC data
+const CEA608_PADDING: [u8; 6] = [0xf8, 0x80, 0x80, 0xf9, 0x80, 0x80];
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "tttocea708",
+        gst::DebugColorFlags::empty(),
+        Some("TT CEA 708 Element"),
+    )
+});
+
+fn is_punctuation(word: &str) -> bool {
+    word == "." || word == "," || word == "?" || word == "!" || word == ";" || word == ":"
+}
+
+fn frame_pts(frame_no: u64, framerate: gst::Fraction) -> gst::ClockTime {
+    (frame_no * gst::ClockTime::SECOND)
+        .mul_div_round(framerate.denom() as u64, framerate.numer() as u64)
+        .unwrap()
+}
+
+fn frame_count(time: gst::ClockTime, framerate: gst::Fraction) -> u64 {
+    time.mul_div_round(framerate.numer() as u64, framerate.denom() as u64)
+        .unwrap()
+        .seconds()
+}
+
+#[derive(Debug, Clone)]
+struct PadSettings {
+    service_number: u32,
+    mode: Cea608Mode,
+    origin_row: i32,
+    origin_column: u32,
+    roll_up_timeout: Option<gst::ClockTime>,
+}
+
+impl Default for PadSettings {
+    fn default() -> Self {
+        PadSettings {
+            service_number: DEFAULT_SERVICE_NUMBER,
+            mode: DEFAULT_MODE,
+            origin_row: DEFAULT_ORIGIN_ROW,
+            origin_column: DEFAULT_ORIGIN_COLUMN,
+            roll_up_timeout: gst::ClockTime::NONE,
+        }
+    }
+}
+
+impl PadSettings {
+    /// The number of rows of the roll-up window, and the grid row of its bottom row
+    fn roll_up_rows(&self, mode: Cea608Mode) -> (u32, u32) {
+        let rows = match mode {
+            Cea608Mode::RollUp2 => 2,
+            Cea608Mode::RollUp3 => 3,
+            _ => 4,
+        };
+        let bottom_row = match self.origin_row {
+            -1 => ROWS - 1,
+            row => (row as u32).max(rows - 1),
+        };
+
+        (rows, bottom_row)
+    }
+
+    fn window_definition(&self, mode: Cea608Mode, visible: bool) -> WindowDefinition {
+        let (rows, top_row) = if mode.is_rollup() {
+            let (rows, bottom_row) = self.roll_up_rows(mode);
+            (rows, bottom_row + 1 - rows)
+        } else {
+            (ROWS, 0)
+        };
+
+        WindowDefinition {
+            visible,
+            row_lock: true,
+            column_lock: true,
+            priority: 0,
+            relative_positioning: true,
+            // Rounded up so that the row is found back from the percentage
+            anchor_vertical: ((top_row * 100 + ROWS - 1) / ROWS) as u8,
+            anchor_horizontal: 0,
+            anchor_point: 0,
+            rows: rows as u8,
+            columns: COLUMNS as u8,
+            // Transparent window with the default pen
+            window_style: 2,
+            pen_style: 1,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PadState {
+    json_input: bool,
+    writer: ServiceWriter,
+    mode: Option<Cea608Mode>,
+    /// The window being displayed, if any
+    window: Option<u8>,
+    pen: Option<(TextStyle, bool)>,
+    column: u32,
+    erase_frame_no: Option<u64>,
+    force_clear: bool,
+}
+
+impl PadState {
+    fn lines(
+        &self,
+        pad: &super::TtToCea708SinkPad,
+        settings: &PadSettings,
+        data: &[u8],
+    ) -> Result<Lines, gst::FlowError> {
+        if self.json_input {
+            return serde_json::from_slice(data).map_err(|err| {
+                gst::error!(CAT, obj: pad, "Failed to parse input as json: {}", err);
+
+                gst::FlowError::Error
+            });
+        }
+
+        let data = std::str::from_utf8(data).map_err(|err| {
+            gst::error!(CAT, obj: pad, "Can't decode utf8: {}", err);
+
+            gst::FlowError::Error
+        })?;
+
+        let phrases: Vec<&str> = data.split('\n').collect();
+        let mut row = match settings.origin_row {
+            -1 => ROWS.saturating_sub(phrases.len() as u32),
+            _ => settings.origin_row as u32,
+        };
+
+        let mut lines = Lines {
+            lines: Vec::new(),
+            mode: Some(settings.mode),
+            clear: None,
+        };
+
+        for phrase in phrases {
+            lines.lines.push(Line {
+                carriage_return: None,
+                column: None,
+                row: Some(row),
+                chunks: vec![Chunk {
+                    style: TextStyle::White,
+                    underline: false,
+                    text: phrase.to_string(),
+                }],
+            });
+            row += 1;
+        }
+
+        Ok(lines)
+    }
+
+    /// Deletes the windows, the next caption defines them again.
+    fn erase(&mut self, settings: &PadSettings) {
+        if self.window.take().is_some() {
+            self.writer.delete_windows(0x03);
+        }
+        self.pen = None;
+        self.column = settings.origin_column;
+        self.erase_frame_no = None;
+    }
+
+    fn define_window(&mut self, settings: &PadSettings, mode: Cea608Mode, window: u8) {
+        let visible = mode != Cea608Mode::PopOn;
+
+        self.writer
+            .define_window(window, &settings.window_definition(mode, visible));
+        self.pen = Some((TextStyle::White, false));
+
+        if mode.is_rollup() {
+            let (rows, _) = settings.roll_up_rows(mode);
+            self.writer
+                .set_pen_location(rows as u8 - 1, settings.origin_column as u8);
+        }
+    }
+
+    fn set_pen(&mut self, style: TextStyle, underline: bool) {
+        if self.pen == Some((style, underline)) {
+            return;
+        }
+
+        let (foreground_color, italics) = Color::from_text_style(style);
+        self.writer.set_pen_attributes(&PenAttributes {
+            italics,
+            underline,
+            ..PenAttributes::default()
+        });
+        self.writer.set_pen_color(&PenColor {
+            foreground_color,
+            ..PenColor::default()
+        });
+        self.pen = Some((style, underline));
+    }
+
+    fn roll_up_carriage_return(&mut self, settings: &PadSettings, mode: Cea608Mode) {
+        self.writer.carriage_return();
+        if settings.origin_column > 0 {
+            let (rows, _) = settings.roll_up_rows(mode);
+            self.writer
+                .set_pen_location(rows as u8 - 1, settings.origin_column as u8);
+        }
+        self.column = settings.origin_column;
+    }
+
+    fn write_chunk(
+        &mut self,
+        pad: &super::TtToCea708SinkPad,
+        settings: &PadSettings,
+        mode: Cea608Mode,
+        chunk: &Chunk,
+        prepend_space: bool,
+    ) {
+        self.set_pen(chunk.style, chunk.underline);
+
+        let mut text = String::new();
+        if prepend_space
+            && !is_punctuation(&chunk.text)
+            && !chunk.text.starts_with(char::is_whitespace)
+        {
+            text.push(' ');
+        }
+        text.push_str(&chunk.text);
+
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c == '\r' || c == '\n' {
+                continue;
+            }
+
+            if mode.is_rollup() {
+                /* Like tttocea608, look ahead and check whether the following
+                 * word will fit on the current row, and if it won't, carry it
+                 * over to the next row unless it won't fit on a full row either,
+                 * in which case it will need to be broken up.
+                 */
+                if c.is_whitespace() {
+                    let next_word_length =
+                        chars.clone().take_while(|c| !c.is_whitespace()).count() as u32;
+
+                    if next_word_length <= COLUMNS - settings.origin_column
+                        && self.column + 1 + next_word_length > COLUMNS
+                    {
+                        self.roll_up_carriage_return(settings, mode);
+                        continue;
+                    }
+                }
+
+                if self.column >= COLUMNS {
+                    self.roll_up_carriage_return(settings, mode);
+                }
+            } else if self.column >= COLUMNS {
+                gst::warning!(
+                    CAT,
+                    obj: pad,
+                    "Dropping characters after 32nd column: {}",
+                    c
+                );
+                break;
+            }
+
+            if !self.writer.put_char(c) {
+                gst::warning!(CAT, obj: pad, "Not translating UTF8: {}", c);
+                self.writer.put_char(' ');
+            }
+            self.column += 1;
+        }
+    }
+
+    fn generate(&mut self, pad: &super::TtToCea708SinkPad, settings: &PadSettings, lines: &Lines) {
+        let mode = lines.mode.unwrap_or(settings.mode);
+        let clear = lines.clear.unwrap_or(false) || std::mem::take(&mut self.force_clear);
+
+        if self.mode != Some(mode) || clear {
+            self.erase(settings);
+            self.mode = Some(mode);
+        }
+
+        // Pop-on captions are composed in the hidden window, then swapped
+        // with the displayed one
+        let hidden_window = if self.window == Some(0) { 1 } else { 0 };
+
+        if mode == Cea608Mode::PopOn {
+            self.define_window(settings, mode, hidden_window);
+        } else if self.window.is_none() {
+            self.define_window(settings, mode, 0);
+            self.window = Some(0);
+            self.column = settings.origin_column;
+        }
+
+        let mut row = ROWS - 2;
+
+        for (idx, line) in lines.lines.iter().enumerate() {
+            gst::log!(CAT, obj: pad, "Processing {:?}", line);
+
+            if mode.is_rollup() {
+                if line.carriage_return.unwrap_or(idx > 0) {
+                    self.roll_up_carriage_return(settings, mode);
+                }
+
+                if let Some(column) = line.column {
+                    let (rows, _) = settings.roll_up_rows(mode);
+                    self.column = column.min(COLUMNS - 1);
+                    self.writer
+                        .set_pen_location(rows as u8 - 1, self.column as u8);
+                }
+            } else {
+                if let Some(line_row) = line.row {
+                    row = line_row;
+                }
+
+                if row >= ROWS {
+                    gst::warning!(CAT, obj: pad, "Dropping line after 15th row: {:?}", line);
+                    continue;
+                }
+
+                if mode == Cea608Mode::PaintOn {
+                    self.writer.set_pen_location(row as u8, 0);
+                    self.writer.horizontal_carriage_return();
+                }
+
+                self.column = line
+                    .column
+                    .unwrap_or(settings.origin_column)
+                    .min(COLUMNS - 1);
+                self.writer.set_pen_location(row as u8, self.column as u8);
+                row += 1;
+            }
+
+            // Continuing a roll-up row, or following another chunk
+            let mut prepend_space =
+                mode.is_rollup() && line.column.is_none() && self.column > settings.origin_column;
+
+            for chunk in &line.chunks {
+                self.write_chunk(pad, settings, mode, chunk, prepend_space);
+                prepend_space = true;
+            }
+        }
+
+        if mode == Cea608Mode::PopOn {
+            self.writer.display_windows(1 << hidden_window);
+            if let Some(previous) = self.window {
+                self.writer.delete_windows(1 << previous);
+            }
+            self.window = Some(hidden_window);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TtToCea708SinkPad {
+    settings: Mutex<PadSettings>,
+    state: Mutex<PadState>,
+}
+
+impl TtToCea708SinkPad {
+    fn from_pad(pad: &gst::Pad) -> &Self {
+        pad.downcast_ref::<super::TtToCea708SinkPad>()
+            .expect("not a tttocea708 sink pad")
+            .imp()
+    }
+
+    fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        let json_input = state.json_input;
+
+        *state = PadState {
+            json_input,
+            ..PadState::default()
+        };
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for TtToCea708SinkPad {
+    const NAME: &'static str = "GstTtToCea708SinkPad";
+    type Type = super::TtToCea708SinkPad;
+    type ParentType = gst_base::AggregatorPad;
+}
+
+impl ObjectImpl for TtToCea708SinkPad {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("service-number")
+                    .nick("Service Number")
+                    .blurb("The CEA-708 caption service to encode to, defaults to the lowest unused one")
+                    .minimum(1)
+                    .maximum(MAX_SERVICE_NUMBER as u32)
+                    .default_value(DEFAULT_SERVICE_NUMBER)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecEnum::builder::<Cea608Mode>("mode", DEFAULT_MODE)
+                    .nick("Mode")
+                    .blurb("Which CEA-608 mode to emulate")
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecInt::builder("origin-row")
+                    .nick("Origin row")
+                    .blurb("Origin row, (-1=automatic)")
+                    .minimum(-1)
+                    .maximum(14)
+                    .default_value(DEFAULT_ORIGIN_ROW)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("origin-column")
+                    .nick("Origin column")
+                    .blurb("Origin column")
+                    .maximum(31)
+                    .default_value(DEFAULT_ORIGIN_COLUMN)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt64::builder("roll-up-timeout")
+                    .nick("Roll-Up Timeout")
+                    .blurb("Duration after which to erase the window in roll-up and paint-on modes")
+                    .default_value(u64::MAX)
+                    .mutable_playing()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(
+        &self,
+        obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            "service-number" => {
+                let service_number = value.get().expect("type checked upstream");
+
+                // A service can only be encoded from a single pad
+                let in_use = obj
+                    .parent()
+                    .and_then(|parent| parent.downcast::<gst::Element>().ok())
+                    .map_or(false, |element| {
+                        element
+                            .sink_pads()
+                            .iter()
+                            .filter(|pad| *pad != obj.upcast_ref::<gst::Pad>())
+                            .any(|pad| {
+                                TtToCea708SinkPad::from_pad(pad)
+                                    .settings
+                                    .lock()
+                                    .unwrap()
+                                    .service_number
+                                    == service_number
+                            })
+                    });
+
+                let mut settings = self.settings.lock().unwrap();
+                if in_use {
+                    gst::warning!(
+                        CAT,
+                        obj: obj,
+                        "Service {} is already used by another pad, keeping service {}",
+                        service_number,
+                        settings.service_number
+                    );
+                } else {
+                    settings.service_number = service_number;
+                }
+            }
+            "mode" => {
+                let mut state = self.state.lock().unwrap();
+                let mut settings = self.settings.lock().unwrap();
+                settings.mode = value.get::<Cea608Mode>().expect("type checked upstream");
+                state.force_clear = true;
+            }
+            "origin-row" => {
+                let mut state = self.state.lock().unwrap();
+                let mut settings = self.settings.lock().unwrap();
+                settings.origin_row = value.get().expect("type checked upstream");
+                state.force_clear = true;
+            }
+            "origin-column" => {
+                let mut state = self.state.lock().unwrap();
+                let mut settings = self.settings.lock().unwrap();
+                settings.origin_column = value.get().expect("type checked upstream");
+                state.force_clear = true;
+            }
+            "roll-up-timeout" => {
+                let mut settings = self.settings.lock().unwrap();
+
+                let timeout = value.get().expect("type checked upstream");
+
+                settings.roll_up_timeout = match timeout {
+                    u64::MAX => gst::ClockTime::NONE,
+                    _ => Some(gst::ClockTime::from_nseconds(timeout)),
+                };
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+
+        match pspec.name() {
+            "service-number" => settings.service_number.to_value(),
+            "mode" => settings.mode.to_value(),
+            "origin-row" => settings.origin_row.to_value(),
+            "origin-column" => settings.origin_column.to_value(),
+            "roll-up-timeout" => {
+                if let Some(timeout) = settings.roll_up_timeout {
+                    timeout.nseconds().to_value()
+                } else {
+                    u64::MAX.to_value()
+                }
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for TtToCea708SinkPad {}
+
+impl PadImpl for TtToCea708SinkPad {}
+
+impl AggregatorPadImpl for TtToCea708SinkPad {}
+
+#[derive(Default)]
+struct State {
+    framerate: Option<gst::Fraction>,
+    frame_rate_code: u8,
+    cc_count: usize,
+    frame_no: Option<u64>,
+    packet_writer: PacketWriter,
+    cdp_sequence: u16,
+}
+
+#[derive(Default)]
+pub struct TtToCea708 {
+    state: Mutex<State>,
+}
+
+impl TtToCea708 {
+    fn negotiate_framerate(
+        &self,
+        element: &super::TtToCea708,
+        state: &mut State,
+    ) -> Result<gst::Fraction, gst::FlowError> {
+        let srcpad = element.src_pad();
+        let mut caps = match srcpad.allowed_caps() {
+            None => srcpad.pad_template_caps(),
+            Some(caps) => caps,
+        };
+
+        if caps.is_empty() {
+            gst::error!(CAT, obj: element, "Empty downstream caps");
+            return Err(gst::FlowError::NotNegotiated);
+        }
+
+        {
+            let caps = caps.make_mut();
+            let s = caps.structure_mut(0).unwrap();
+
+            s.fixate_field_nearest_fraction(
+                "framerate",
+                gst::Fraction::new(DEFAULT_FPS_N, DEFAULT_FPS_D),
+            );
+        }
+        caps.fixate();
+
+        let framerate = caps
+            .structure(0)
+            .unwrap()
+            .get::<gst::Fraction>("framerate")
+            .unwrap();
+
+        let (frame_rate_code, cc_count) = cdp_frame_rate(framerate.numer(), framerate.denom())
+            .ok_or_else(|| {
+                gst::error!(CAT, obj: element, "Unsupported framerate {}", framerate);
+                gst::FlowError::NotNegotiated
+            })?;
+
+        gst::debug!(CAT, obj: element, "Negotiated caps {}", caps);
+
+        element.set_src_caps(&caps);
+
+        state.framerate = Some(framerate);
+        state.frame_rate_code = frame_rate_code;
+        state.cc_count = cc_count;
+
+        Ok(framerate)
+    }
+
+    /// Generates the commands of the buffers of `pad` starting before `end`,
+    /// and erases its windows if the current caption expired.
+    fn consume_pad(
+        &self,
+        element: &super::TtToCea708,
+        pad: &super::TtToCea708SinkPad,
+        framerate: gst::Fraction,
+        frame_no: u64,
+        end: gst::ClockTime,
+    ) -> Result<(), gst::FlowError> {
+        let pad_imp = pad.imp();
+        let settings = pad_imp.settings.lock().unwrap().clone();
+        let mut state = pad_imp.state.lock().unwrap();
+
+        if state
+            .erase_frame_no
+            .map_or(false, |erase_frame_no| erase_frame_no <= frame_no)
+        {
+            gst::debug!(CAT, obj: pad, "Erasing display");
+            state.erase(&settings);
+        }
+
+        while let Some(buffer) = pad.peek_buffer() {
+            let pts = buffer.pts().ok_or_else(|| {
+                gst::element_error!(
+                    element,
+                    gst::StreamError::Format,
+                    ["Stream with timestamped buffers required"]
+                );
+                gst::FlowError::Error
+            })?;
+
+            if pts >= end {
+                break;
+            }
+
+            pad.drop_buffer();
+
+            // Gap events are queued as empty gap buffers
+            if buffer.flags().contains(gst::BufferFlags::GAP) && buffer.size() == 0 {
+                continue;
+            }
+
+            gst::log!(CAT, obj: pad, "Handling {:?}", buffer);
+
+            let duration = buffer.duration().ok_or_else(|| {
+                gst::element_error!(
+                    element,
+                    gst::StreamError::Format,
+                    ["Buffers of stream need to have a duration"]
+                );
+                gst::FlowError::Error
+            })?;
+
+            let data = buffer.map_readable().map_err(|_| {
+                gst::error!(CAT, obj: pad, "Can't map buffer readable");
+
+                gst::FlowError::Error
+            })?;
+
+            let lines = state.lines(pad, &settings, &data)?;
+            state.generate(pad, &settings, &lines);
+
+            state.erase_frame_no = if state.mode == Some(Cea608Mode::PopOn) {
+                Some(frame_count(pts + duration, framerate).max(frame_no + 1))
+            } else {
+                settings
+                    .roll_up_timeout
+                    .map(|timeout| frame_no + frame_count(timeout, framerate))
+            };
+        }
+
+        Ok(())
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for TtToCea708 {
+    const NAME: &'static str = "TtToCea708";
+    type Type = super::TtToCea708;
+    type ParentType = gst_base::Aggregator;
+}
+
+impl ObjectImpl for TtToCea708 {}
+
+impl GstObjectImpl for TtToCea708 {}
+
+impl ElementImpl for TtToCea708 {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "TT to CEA-708",
+                "Generic",
+                "Converts timed text to CEA-708 Closed Captions",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let mut caps = gst::Caps::new_empty();
+            {
+                let caps = caps.get_mut().unwrap();
+
+                let s = gst::Structure::builder("text/x-raw").build();
+                caps.append_structure(s);
+
+                let s = gst::Structure::builder("application/x-json")
+                    .field("format", "cea608")
+                    .build();
+                caps.append_structure(s);
+            }
+
+            let sink_pad_template = gst::PadTemplate::with_gtype(
+                "sink_%u",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Request,
+                &caps,
+                super::TtToCea708SinkPad::static_type(),
+            )
+            .unwrap();
+
+            let framerates = gst::List::new([
+                gst::Fraction::new(24000, 1001),
+                gst::Fraction::new(24, 1),
+                gst::Fraction::new(25, 1),
+                gst::Fraction::new(30000, 1001),
+                gst::Fraction::new(30, 1),
+                gst::Fraction::new(50, 1),
+                gst::Fraction::new(60000, 1001),
+                gst::Fraction::new(60, 1),
+            ]);
+
+            let caps = gst::Caps::builder("closedcaption/x-cea-708")
+                .field("format", "cdp")
+                .field("framerate", framerates)
+                .build();
+
+            let src_pad_template = gst::PadTemplate::with_gtype(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+                gst_base::AggregatorPad::static_type(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn request_new_pad(
+        &self,
+        element: &Self::Type,
+        templ: &gst::PadTemplate,
+        name: Option<String>,
+        caps: Option<&gst::Caps>,
+    ) -> Option<gst::Pad> {
+        let used_services = element
+            .sink_pads()
+            .iter()
+            .map(|pad| {
+                TtToCea708SinkPad::from_pad(pad)
+                    .settings
+                    .lock()
+                    .unwrap()
+                    .service_number
+            })
+            .collect::<Vec<_>>();
+
+        let pad = self.parent_request_new_pad(element, templ, name, caps)?;
+
+        if let Some(service_number) =
+            (1..=MAX_SERVICE_NUMBER as u32).find(|number| !used_services.contains(number))
+        {
+            TtToCea708SinkPad::from_pad(&pad)
+                .settings
+                .lock()
+                .unwrap()
+                .service_number = service_number;
+        }
+
+        Some(pad)
+    }
+}
+
+impl AggregatorImpl for TtToCea708 {
+    fn aggregate(
+        &self,
+        element: &Self::Type,
+        timeout: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst::trace!(CAT, obj: element, "aggregate, timeout: {}", timeout);
+
+        let mut state = self.state.lock().unwrap();
+
+        let framerate = match state.framerate {
+            Some(framerate) => framerate,
+            None => self.negotiate_framerate(element, &mut state)?,
+        };
+
+        let pads = element
+            .sink_pads()
+            .into_iter()
+            .map(|pad| pad.downcast::<super::TtToCea708SinkPad>().unwrap())
+            .collect::<Vec<_>>();
+
+        let all_eos = pads.iter().all(|pad| pad.is_eos());
+
+        let frame_no = match state.frame_no {
+            Some(frame_no) => frame_no,
+            None => {
+                let first_pts = pads
+                    .iter()
+                    .filter_map(|pad| pad.peek_buffer())
+                    .filter_map(|buffer| buffer.pts())
+                    .min();
+
+                match first_pts {
+                    Some(pts) => {
+                        let frame_no = pts
+                            .mul_div_floor(framerate.numer() as u64, framerate.denom() as u64)
+                            .unwrap()
+                            .seconds();
+                        gst::debug!(CAT, obj: element, "Initial skip to frame no {}", frame_no);
+                        frame_no
+                    }
+                    None if all_eos => return Err(gst::FlowError::Eos),
+                    None => return Err(AGGREGATOR_FLOW_NEED_DATA),
+                }
+            }
+        };
+
+        let pts = frame_pts(frame_no, framerate);
+        let end = frame_pts(frame_no + 1, framerate);
+
+        for pad in &pads {
+            self.consume_pad(element, pad, framerate, frame_no, end)?;
+        }
+
+        let mut pad_states = pads
+            .iter()
+            .map(|pad| {
+                let pad_imp = pad.imp();
+                let service_number = pad_imp.settings.lock().unwrap().service_number as u8;
+                (service_number, pad_imp.state.lock().unwrap())
+            })
+            .collect::<Vec<_>>();
+
+        // Nothing left to output, and nothing will come anymore
+        if all_eos
+            && state.packet_writer.is_empty()
+            && pad_states
+                .iter()
+                .all(|(_, state)| state.writer.is_empty() && state.erase_frame_no.is_none())
+        {
+            return Err(gst::FlowError::Eos);
+        }
+
+        let mut services = pad_states
+            .iter_mut()
+            .map(|(service_number, state)| (*service_number, &mut state.writer))
+            .collect::<Vec<_>>();
+
+        let max_triplets = state.cc_count - CEA608_PADDING.len() / 3;
+        let mut cc_data = CEA608_PADDING.to_vec();
+        cc_data.extend(state.packet_writer.write(&mut services, max_triplets));
+
+        if services.iter().any(|(_, writer)| !writer.is_empty()) {
+            gst::debug!(CAT, obj: element, "More text than bandwidth!");
+        }
+
+        drop(services);
+        drop(pad_states);
+
+        let cdp = write_cdp(
+            state.frame_rate_code,
+            state.cdp_sequence,
+            None,
+            &cc_data,
+            state.cc_count,
+        );
+        state.cdp_sequence = state.cdp_sequence.wrapping_add(1);
+        state.frame_no = Some(frame_no + 1);
+
+        drop(state);
+
+        let mut buffer = gst::Buffer::from_mut_slice(cdp);
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(pts);
+            buffer.set_duration(end - pts);
+        }
+
+        gst::trace!(CAT, obj: element, "Outputting {:?}", buffer);
+
+        let position = Some(end);
+        element.set_position(position);
+
+        self.finish_buffer(element, buffer)
+    }
+
+    fn sink_event(
+        &self,
+        aggregator: &Self::Type,
+        aggregator_pad: &gst_base::AggregatorPad,
+        event: gst::Event,
+    ) -> bool {
+        use gst::EventView;
+
+        gst::log!(CAT, obj: aggregator_pad, "Handling event {:?}", event);
+
+        if let EventView::Caps(e) = event.view() {
+            let s = e.caps().structure(0).unwrap();
+            TtToCea708SinkPad::from_pad(aggregator_pad.upcast_ref())
+                .state
+                .lock()
+                .unwrap()
+                .json_input = s.name() == "application/x-json";
+        }
+
+        self.parent_sink_event(aggregator, aggregator_pad, event)
+    }
+
+    fn flush(&self, aggregator: &Self::Type) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state = self.state.lock().unwrap();
+        state.frame_no = None;
+        state.packet_writer.reset();
+        drop(state);
+
+        for pad in aggregator.sink_pads() {
+            TtToCea708SinkPad::from_pad(&pad).reset();
+        }
+
+        self.parent_flush(aggregator)
+    }
+
+    fn start(&self, aggregator: &Self::Type) -> Result<(), gst::ErrorMessage> {
+        *self.state.lock().unwrap() = State::default();
+
+        for pad in aggregator.sink_pads() {
+            TtToCea708SinkPad::from_pad(&pad).reset();
+        }
+
+        self.parent_start(aggregator)
+    }
+
+    fn stop(&self, aggregator: &Self::Type) -> Result<(), gst::ErrorMessage> {
+        *self.state.lock().unwrap() = State::default();
+
+        self.parent_stop(aggregator)
+    }
+
+    fn negotiate(&self, _aggregator: &Self::Type) -> bool {
+        true
+    }
+}
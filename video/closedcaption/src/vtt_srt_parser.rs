@@ -0,0 +1,786 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Line based parser for WebVTT and SubRip cues, and conversion of the cue
+//! text markup to CEA-608 styled lines
+
+use crate::ttutils::{Cea608Mode, Chunk, Line, Lines, TextStyle};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Vtt,
+    Srt,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LinePosition {
+    /// Line number, negative values count from the bottom
+    Number(i32),
+    Percent(f64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+}
+
+/// The WebVTT cue settings that map to CEA-608 positioning
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CueSettings {
+    pub line: Option<LinePosition>,
+    pub position: Option<f64>,
+    pub align: Option<Align>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cue {
+    pub start: gst::ClockTime,
+    pub end: gst::ClockTime,
+    pub settings: CueSettings,
+    /// The payload lines, with markup
+    pub text: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Header,
+    Idle,
+    Timing,
+    Payload,
+    Skip,
+}
+
+#[derive(Debug)]
+pub struct SubtitleParser {
+    format: Format,
+    state: State,
+    cue: Option<Cue>,
+}
+
+/// Parses a timestamp in the form `[hh:]mm:ss.ttt`, also accepting a comma
+/// as decimal separator as used by SubRip
+fn timestamp(s: &str) -> Option<gst::ClockTime> {
+    let (hms, fraction) = s.split_once(['.', ','])?;
+
+    let parse = |s: &str| {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            None
+        } else {
+            s.parse::<u64>().ok()
+        }
+    };
+
+    let mut parts = hms.split(':').map(parse).collect::<Option<Vec<_>>>()?;
+    let seconds = parts.pop()?;
+    let minutes = parts.pop()?;
+    let hours = parts.pop().unwrap_or(0);
+    if !parts.is_empty() || minutes > 59 || seconds > 59 || fraction.len() > 3 {
+        return None;
+    }
+
+    let millis = parse(fraction)? * 10u64.pow(3 - fraction.len() as u32);
+
+    Some(gst::ClockTime::from_mseconds(
+        ((hours * 60 + minutes) * 60 + seconds) * 1000 + millis,
+    ))
+}
+
+fn percentage(s: &str) -> Option<f64> {
+    let value = s.strip_suffix('%')?.parse::<f64>().ok()?;
+
+    if (0.0..=100.0).contains(&value) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Parses the WebVTT cue settings, ignoring unknown and invalid ones
+fn cue_settings(s: &str) -> CueSettings {
+    let mut settings = CueSettings::default();
+
+    for setting in s.split_whitespace() {
+        let (name, value) = match setting.split_once(':') {
+            Some(setting) => setting,
+            None => continue,
+        };
+        // Drop the line and position alignments
+        let value = value.split(',').next().unwrap();
+
+        match name {
+            "line" => {
+                settings.line = percentage(value)
+                    .map(LinePosition::Percent)
+                    .or_else(|| value.parse::<i32>().ok().map(LinePosition::Number));
+            }
+            "position" => settings.position = percentage(value),
+            "align" => {
+                settings.align = match value {
+                    "start" | "left" => Some(Align::Start),
+                    "center" | "middle" => Some(Align::Center),
+                    "end" | "right" => Some(Align::End),
+                    _ => None,
+                }
+            }
+            _ => (),
+        }
+    }
+
+    settings
+}
+
+/// Parses a timing line in the form `start --> end [settings]`
+fn timing(format: Format, s: &str) -> Option<(gst::ClockTime, gst::ClockTime, CueSettings)> {
+    let (start, rest) = s.split_once("-->")?;
+    let start = timestamp(start.trim())?;
+
+    let rest = rest.trim_start();
+    let (end, settings) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let end = timestamp(end)?;
+
+    let settings = match format {
+        Format::Vtt => cue_settings(settings),
+        // SubRip coordinates are not supported
+        Format::Srt => CueSettings::default(),
+    };
+
+    Some((start, end, settings))
+}
+
+impl SubtitleParser {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            state: match format {
+                Format::Vtt => State::Header,
+                Format::Srt => State::Idle,
+            },
+            cue: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.format);
+    }
+
+    /// Parses the next line, and returns the cue it completes if any.
+    ///
+    /// Errors describe a malformed block that is skipped, the parser can
+    /// be used further.
+    pub fn parse_line(&mut self, line: &[u8]) -> Result<Option<Cue>, String> {
+        let line = String::from_utf8_lossy(line);
+        let mut line = line.trim_end_matches(['\r', '\n']);
+        let blank = line.trim().is_empty();
+
+        match self.state {
+            State::Header => {
+                line = line.trim_start_matches('\u{feff}');
+                self.state = State::Skip;
+
+                if line != "WEBVTT" && !line.starts_with("WEBVTT ") && !line.starts_with("WEBVTT\t")
+                {
+                    return Err(format!("Invalid WebVTT header '{}'", line));
+                }
+
+                Ok(None)
+            }
+            State::Skip => {
+                if blank {
+                    self.state = State::Idle;
+                }
+
+                Ok(None)
+            }
+            State::Idle => {
+                if blank {
+                    return Ok(None);
+                }
+
+                if self.format == Format::Srt {
+                    line = line.trim_start_matches('\u{feff}');
+                }
+
+                if line.contains("-->") {
+                    self.timing_line(line)
+                } else if self.format == Format::Vtt
+                    && ["NOTE", "STYLE", "REGION"].iter().any(|block| {
+                        matches!(line.strip_prefix(block), Some(rest) if rest.is_empty() || rest.starts_with(' '))
+                    })
+                {
+                    self.state = State::Skip;
+                    Ok(None)
+                } else if self.format == Format::Srt && line.trim().parse::<u32>().is_err() {
+                    self.state = State::Skip;
+                    Err(format!("Invalid cue number '{}'", line))
+                } else {
+                    // Cue identifier
+                    self.state = State::Timing;
+                    Ok(None)
+                }
+            }
+            State::Timing => {
+                if blank {
+                    self.state = State::Idle;
+                    Err("Cue without timing".into())
+                } else {
+                    self.timing_line(line)
+                }
+            }
+            State::Payload => {
+                if blank {
+                    self.state = State::Idle;
+                    Ok(self.cue.take())
+                } else {
+                    self.cue.as_mut().unwrap().text.push(line.to_string());
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    fn timing_line(&mut self, line: &str) -> Result<Option<Cue>, String> {
+        match timing(self.format, line) {
+            Some((start, end, settings)) => {
+                self.state = State::Payload;
+                self.cue = Some(Cue {
+                    start,
+                    end: end.max(start),
+                    settings,
+                    text: Vec::new(),
+                });
+                Ok(None)
+            }
+            None => {
+                self.state = State::Skip;
+                Err(format!("Invalid cue timing '{}'", line))
+            }
+        }
+    }
+
+    /// Returns the last cue if the input ended without a blank line
+    pub fn drain(&mut self) -> Option<Cue> {
+        let cue = if self.state == State::Payload {
+            self.cue.take()
+        } else {
+            None
+        };
+
+        self.state = State::Idle;
+        cue
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Style {
+    italic: bool,
+    underline: bool,
+    color: Option<TextStyle>,
+}
+
+impl Style {
+    fn text_style(&self) -> TextStyle {
+        // CEA-608 can't combine italics with colors
+        if self.italic {
+            TextStyle::ItalicWhite
+        } else {
+            self.color.unwrap_or(TextStyle::White)
+        }
+    }
+}
+
+fn color(name: &str) -> Option<TextStyle> {
+    match name.to_ascii_lowercase().as_str() {
+        "white" | "#ffffff" => Some(TextStyle::White),
+        "lime" | "green" | "#00ff00" => Some(TextStyle::Green),
+        "blue" | "#0000ff" => Some(TextStyle::Blue),
+        "cyan" | "aqua" | "#00ffff" => Some(TextStyle::Cyan),
+        "red" | "#ff0000" => Some(TextStyle::Red),
+        "yellow" | "#ffff00" => Some(TextStyle::Yellow),
+        "magenta" | "fuchsia" | "#ff00ff" => Some(TextStyle::Magenta),
+        _ => None,
+    }
+}
+
+/// Replaces the character references of the cue text
+fn unescape(s: &str) -> String {
+    let mut text = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(pos) = rest.find('&') {
+        text.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        let reference = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let c = reference.and_then(|reference| match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "lrm" => Some('\u{200e}'),
+            "rlm" => Some('\u{200f}'),
+            _ => {
+                let code = match reference
+                    .strip_prefix("#x")
+                    .or_else(|| reference.strip_prefix("#X"))
+                {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => reference.strip_prefix('#')?.parse::<u32>().ok(),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+
+        match (c, reference) {
+            (Some(c), Some(reference)) => {
+                // Direction marks have no representation in captions
+                if c != '\u{200e}' && c != '\u{200f}' {
+                    text.push(c);
+                }
+                rest = &rest[reference.len() + 2..];
+            }
+            _ => {
+                text.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    text.push_str(rest);
+    text
+}
+
+/// Removes the SubRip override blocks, e.g. `{\an8}`
+fn strip_overrides(s: &str) -> String {
+    let mut text = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(pos) = rest.find("{\\") {
+        text.push_str(&rest[..pos]);
+        match rest[pos..].find('}') {
+            Some(end) => rest = &rest[pos + end + 1..],
+            None => {
+                rest = &rest[pos..];
+                break;
+            }
+        }
+    }
+
+    text.push_str(rest);
+    text
+}
+
+/// Converts the markup of the cue text to lines of styled chunks. Tags can
+/// span several lines, unknown tags are dropped but their text is kept.
+fn styled_lines(format: Format, text: &[String]) -> Vec<Vec<(Style, String)>> {
+    let mut stack: Vec<(String, Style)> = Vec::new();
+    let mut lines = Vec::new();
+
+    for line in text {
+        let line = match format {
+            Format::Vtt => line.clone(),
+            Format::Srt => strip_overrides(line),
+        };
+
+        let mut runs: Vec<(Style, String)> = Vec::new();
+        let mut rest = line.as_str();
+
+        loop {
+            let (text, tag) = match rest.find('<') {
+                Some(pos) => match rest[pos..].find('>') {
+                    Some(end) => (&rest[..pos], Some(&rest[pos + 1..pos + end])),
+                    None => (rest, None),
+                },
+                None => (rest, None),
+            };
+
+            let style = stack.last().map(|(_, style)| *style).unwrap_or_default();
+            let text = unescape(text);
+            if !text.is_empty() {
+                match runs.last_mut() {
+                    Some((last, last_text)) if *last == style => last_text.push_str(&text),
+                    _ => runs.push((style, text)),
+                }
+            }
+
+            let tag = match tag {
+                Some(tag) => tag,
+                None => break,
+            };
+            rest = &rest[rest.find('>').unwrap() + 1..];
+
+            if let Some(name) = tag.strip_prefix('/') {
+                let name = name.trim().to_ascii_lowercase();
+                if let Some(pos) = stack.iter().rposition(|(open, _)| *open == name) {
+                    stack.truncate(pos);
+                }
+                continue;
+            }
+
+            let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            let mut classes = name.split('.');
+            let name = classes.next().unwrap().to_ascii_lowercase();
+
+            // Timestamp tags
+            if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+                continue;
+            }
+
+            let mut new_style = style;
+            match name.as_str() {
+                "i" => new_style.italic = true,
+                "u" => new_style.underline = true,
+                "c" => {
+                    if let Some(color) = classes.filter_map(color).next_back() {
+                        new_style.color = Some(color);
+                    }
+                }
+                "font" if format == Format::Srt => {
+                    let value = attributes
+                        .split_once("color=")
+                        .map(|(_, value)| {
+                            value
+                                .trim_start_matches(['"', '\''])
+                                .split(['"', '\'', ' '])
+                                .next()
+                                .unwrap()
+                        })
+                        .and_then(color);
+                    if let Some(color) = value {
+                        new_style.color = Some(color);
+                    }
+                }
+                // Bold has no CEA-608 representation, other tags don't style
+                _ => (),
+            }
+
+            stack.push((name, new_style));
+        }
+
+        lines.push(runs);
+    }
+
+    lines
+}
+
+/// The text of the cue without markup
+pub fn cue_text(format: Format, cue: &Cue) -> String {
+    styled_lines(format, &cue.text)
+        .iter()
+        .map(|runs| {
+            runs.iter()
+                .map(|(_, text)| text.as_str())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Converts the cue to CEA-608 lines, positioned according to the cue
+/// settings on the 15 rows by 32 columns grid
+pub fn cue_lines(format: Format, cue: &Cue, mode: Cea608Mode) -> Lines {
+    let lines = styled_lines(format, &cue.text)
+        .into_iter()
+        .map(|runs| {
+            runs.into_iter()
+                .filter_map(|(style, text)| {
+                    // Chunks are separated by spaces when encoding
+                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    if text.is_empty() {
+                        None
+                    } else {
+                        Some(Chunk {
+                            style: style.text_style(),
+                            underline: style.underline,
+                            text,
+                        })
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|chunks| !chunks.is_empty())
+        .collect::<Vec<_>>();
+
+    let n_lines = lines.len().min(15) as i32;
+    let last_row = 15 - n_lines;
+    let first_row = match cue.settings.line {
+        Some(LinePosition::Number(line)) if line >= 0 => line,
+        Some(LinePosition::Number(line)) => 15 + line + 1 - n_lines,
+        Some(LinePosition::Percent(percent)) => (percent * 15.0 / 100.0).round() as i32,
+        None => last_row.min(13),
+    }
+    .clamp(0, last_row.max(0)) as u32;
+
+    let align = cue.settings.align.unwrap_or(Align::Center);
+    let position = cue.settings.position.unwrap_or(match align {
+        Align::Start => 0.0,
+        Align::Center => 50.0,
+        Align::End => 100.0,
+    });
+    let anchor = (position * 32.0 / 100.0).round() as i32;
+
+    Lines {
+        lines: lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunks)| {
+                let len = chunks
+                    .iter()
+                    .map(|chunk| chunk.text.chars().count() + 1)
+                    .sum::<usize>() as i32
+                    - 1;
+                let column = match align {
+                    Align::Start => anchor,
+                    Align::Center => anchor - len / 2,
+                    Align::End => anchor - len,
+                }
+                .clamp(0, (32 - len).max(0)) as u32;
+
+                Line {
+                    column: Some(column),
+                    row: Some((first_row + i as u32).min(14)),
+                    chunks,
+                    carriage_return: Some(true),
+                }
+            })
+            .collect(),
+        mode: Some(mode),
+        clear: Some(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(format: Format, input: &str) -> (Vec<Cue>, usize) {
+        let mut parser = SubtitleParser::new(format);
+        let mut cues = Vec::new();
+        let mut errors = 0;
+
+        for line in input.split_inclusive('\n') {
+            match parser.parse_line(line.as_bytes()) {
+                Ok(Some(cue)) => cues.push(cue),
+                Ok(None) => (),
+                Err(_) => errors += 1,
+            }
+        }
+        cues.extend(parser.drain());
+
+        (cues, errors)
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(
+            timestamp("01:02:03.004"),
+            Some(gst::ClockTime::from_mseconds(3_723_004))
+        );
+        assert_eq!(
+            timestamp("02:03,4"),
+            Some(gst::ClockTime::from_mseconds(123_400))
+        );
+        assert_eq!(timestamp("02:60.000"), None);
+        assert_eq!(timestamp("02:03"), None);
+        assert_eq!(timestamp("a:02:03.000"), None);
+    }
+
+    #[test]
+    fn test_parse_vtt() {
+        let (cues, errors) = parse(
+            Format::Vtt,
+            "\u{feff}WEBVTT - Some title\r\n\
+             Kind: captions\r\n\
+             \r\n\
+             NOTE a comment\r\n\
+             with two lines\r\n\
+             \r\n\
+             1\r\n\
+             00:01.000 --> 00:02.500 line:0 position:10% align:start\r\n\
+             <i>Hello</i>\r\n\
+             world\r\n\
+             \r\n\
+             00:00:03.000 --> 00:00:04.000\r\n\
+             Last",
+        );
+
+        assert_eq!(errors, 0);
+        assert_eq!(
+            cues,
+            vec![
+                Cue {
+                    start: gst::ClockTime::from_mseconds(1000),
+                    end: gst::ClockTime::from_mseconds(2500),
+                    settings: CueSettings {
+                        line: Some(LinePosition::Number(0)),
+                        position: Some(10.0),
+                        align: Some(Align::Start),
+                    },
+                    text: vec!["<i>Hello</i>".into(), "world".into()],
+                },
+                Cue {
+                    start: gst::ClockTime::from_mseconds(3000),
+                    end: gst::ClockTime::from_mseconds(4000),
+                    settings: CueSettings::default(),
+                    text: vec!["Last".into()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_srt() {
+        let (cues, errors) = parse(
+            Format::Srt,
+            "1\n\
+             00:00:01,000 --> 00:00:02,000 X1:10 X2:20 Y1:10 Y2:20\n\
+             Hello\n\
+             \n\
+             garbage\n\
+             00:00:02,000 --> 00:00:03,000\n\
+             \n\
+             3\n\
+             00:00:04,000 --> 00:00:05,000\n\
+             World\n\
+             \n",
+        );
+
+        assert_eq!(errors, 1);
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].text, vec!["Hello".to_string()]);
+        assert_eq!(cues[0].settings, CueSettings::default());
+        assert_eq!(cues[1].start, gst::ClockTime::from_seconds(4));
+        assert_eq!(cues[1].text, vec!["World".to_string()]);
+    }
+
+    #[test]
+    fn test_invalid_vtt() {
+        let (cues, errors) = parse(
+            Format::Vtt,
+            "WEBVTT\n\nid\n00:01.000 -> 00:02.000\ntext\n\n00:03.000 --> 00:04.000\nok\n",
+        );
+
+        assert_eq!(errors, 1);
+        assert_eq!(cues.len(), 1);
+        assert_eq!(cues[0].text, vec!["ok".to_string()]);
+    }
+
+    #[test]
+    fn test_markup() {
+        let cue = Cue {
+            start: gst::ClockTime::ZERO,
+            end: gst::ClockTime::SECOND,
+            settings: CueSettings::default(),
+            text: vec![
+                "<v Bob>Hello <c.yellow.bg_blue>big <u>bad</u></c> &amp; <b>bold</b>".into(),
+                "<i>wide</i> <00:00:01.000>world&lrm;".into(),
+            ],
+        };
+
+        assert_eq!(
+            cue_text(Format::Vtt, &cue),
+            "Hello big bad & bold\nwide world"
+        );
+
+        let lines = cue_lines(Format::Vtt, &cue, Cea608Mode::PopOn);
+        let chunks = lines
+            .lines
+            .iter()
+            .map(|line| {
+                line.chunks
+                    .iter()
+                    .map(|chunk| (chunk.style, chunk.underline, chunk.text.as_str()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            chunks,
+            vec![
+                vec![
+                    (TextStyle::White, false, "Hello"),
+                    (TextStyle::Yellow, false, "big"),
+                    (TextStyle::Yellow, true, "bad"),
+                    (TextStyle::White, false, "& bold"),
+                ],
+                vec![
+                    (TextStyle::ItalicWhite, false, "wide"),
+                    (TextStyle::White, false, "world"),
+                ],
+            ]
+        );
+
+        // Centered at the bottom
+        assert_eq!(lines.lines[0].row, Some(13));
+        assert_eq!(lines.lines[0].column, Some(6));
+        assert_eq!(lines.lines[1].row, Some(14));
+        assert_eq!(lines.lines[1].column, Some(11));
+    }
+
+    #[test]
+    fn test_srt_markup() {
+        let cue = Cue {
+            start: gst::ClockTime::ZERO,
+            end: gst::ClockTime::SECOND,
+            settings: CueSettings::default(),
+            text: vec![r##"{\an8}<font color="#00FF00">green</font> <i>text"##.into()],
+        };
+
+        assert_eq!(cue_text(Format::Srt, &cue), "green text");
+
+        let lines = cue_lines(Format::Srt, &cue, Cea608Mode::RollUp2);
+        assert_eq!(lines.mode, Some(Cea608Mode::RollUp2));
+        assert_eq!(lines.lines[0].chunks[0].style, TextStyle::Green);
+        assert_eq!(lines.lines[0].chunks[1].style, TextStyle::ItalicWhite);
+    }
+
+    #[test]
+    fn test_positioning() {
+        let cue = |line, position, align| Cue {
+            start: gst::ClockTime::ZERO,
+            end: gst::ClockTime::SECOND,
+            settings: CueSettings {
+                line,
+                position,
+                align,
+            },
+            text: vec!["0123456789".into(), "01234".into()],
+        };
+        let positions = |cue| {
+            cue_lines(Format::Vtt, &cue, Cea608Mode::PopOn)
+                .lines
+                .iter()
+                .map(|line| (line.row.unwrap(), line.column.unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            positions(cue(Some(LinePosition::Number(0)), None, Some(Align::Start))),
+            vec![(0, 0), (1, 0)]
+        );
+        assert_eq!(
+            positions(cue(Some(LinePosition::Number(-1)), None, Some(Align::End))),
+            vec![(13, 22), (14, 27)]
+        );
+        assert_eq!(
+            positions(cue(Some(LinePosition::Percent(50.0)), Some(25.0), None)),
+            vec![(8, 3), (9, 6)]
+        );
+        assert_eq!(
+            positions(cue(
+                Some(LinePosition::Number(20)),
+                Some(100.0),
+                Some(Align::Start)
+            )),
+            vec![(13, 22), (14, 27)]
+        );
+    }
+}
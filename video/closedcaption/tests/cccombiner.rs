@@ -0,0 +1,229 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+use gst::ClockTime;
+use pretty_assertions::assert_eq;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+    });
+}
+
+fn new_timed_buffer<T: AsRef<[u8]> + Send + 'static>(
+    slice: T,
+    timestamp: ClockTime,
+    duration: ClockTime,
+) -> gst::buffer::Buffer {
+    let mut buf = gst::Buffer::from_slice(slice);
+    let buf_ref = buf.get_mut().unwrap();
+    buf_ref.set_pts(timestamp);
+    buf_ref.set_duration(duration);
+    buf
+}
+
+fn frame_duration() -> ClockTime {
+    ClockTime::SECOND / 30
+}
+
+/// Wraps `cc_data` in a 30fps CDP without timecode
+fn cdp(cc_data: &[u8]) -> Vec<u8> {
+    let cc_count = cc_data.len() / 3;
+    let len = 7 + 2 + cc_data.len() + 4;
+
+    let mut cdp = vec![0x96, 0x69, len as u8, 0x5f, 0x43, 0x00, 0x00];
+    cdp.extend_from_slice(&[0x72, 0xe0 | cc_count as u8]);
+    cdp.extend_from_slice(cc_data);
+    cdp.extend_from_slice(&[0x74, 0x00, 0x00]);
+    let sum = cdp.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    cdp.push(sum.wrapping_neg());
+
+    cdp
+}
+
+/// Creates harnesses for the video and caption pads of a combiner
+fn new_harnesses(caption_caps: &str) -> (gst_check::Harness, gst_check::Harness) {
+    let mut h = gst_check::Harness::with_padnames("rscccombiner", Some("sink"), Some("src"));
+    h.set_src_caps_str("video/x-raw, format=I420, width=320, height=240, framerate=30/1");
+
+    let mut h_caption =
+        gst_check::Harness::with_element(&h.element().unwrap(), Some("caption"), None);
+    h_caption.set_src_caps_str(caption_caps);
+
+    (h, h_caption)
+}
+
+fn push_video_frames(h: &mut gst_check::Harness, count: u64) {
+    for i in 0..count {
+        let buf = new_timed_buffer([0u8; 16], i * frame_duration(), frame_duration());
+        assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+    }
+}
+
+fn captions(buffer: &gst::Buffer) -> Vec<(gst_video::VideoCaptionType, Vec<u8>)> {
+    buffer
+        .iter_meta::<gst_video::VideoCaptionMeta>()
+        .map(|meta| (meta.caption_type(), meta.data().to_vec()))
+        .collect()
+}
+
+/* Check that CEA-608 byte pairs are scheduled one per frame */
+#[test]
+fn test_schedule_cea608_raw() {
+    init();
+
+    let (mut h, mut h_caption) = new_harnesses("closedcaption/x-cea-608, format=raw");
+
+    let buf = new_timed_buffer([0x94, 0x20, 0xc1, 0xc2], ClockTime::ZERO, frame_duration());
+    assert_eq!(h_caption.push(buf), Ok(gst::FlowSuccess::Ok));
+    h_caption.push_event(gst::event::Eos::new());
+
+    push_video_frames(&mut h, 3);
+
+    let expected: [&[u8]; 3] = [&[0x94, 0x20], &[0xc1, 0xc2], &[]];
+    for (i, data) in expected.iter().enumerate() {
+        let buf = h.pull().unwrap();
+        assert_eq!(buf.pts(), Some(i as u64 * frame_duration()));
+
+        let expected = if data.is_empty() {
+            vec![]
+        } else {
+            vec![(gst_video::VideoCaptionType::Cea608Raw, data.to_vec())]
+        };
+        assert_eq!(captions(&buf), expected);
+    }
+}
+
+/* Check that DTVCC data is split over frames according to the CDP cc_count,
+ * and that CDP is output for every frame with a continuous sequence counter */
+#[test]
+fn test_schedule_cdp() {
+    init();
+
+    let (mut h, mut h_caption) =
+        new_harnesses("closedcaption/x-cea-708, format=cdp, framerate=30/1");
+
+    let mut cc_data = vec![0xfc, 0x94, 0x20, 0xff, 0x02, 0x21];
+    for i in 0..29 {
+        cc_data.extend_from_slice(&[0xfe, i, i]);
+    }
+
+    let buf = new_timed_buffer(cdp(&cc_data), ClockTime::ZERO, frame_duration());
+    assert_eq!(h_caption.push(buf), Ok(gst::FlowSuccess::Ok));
+    h_caption.push_event(gst::event::Eos::new());
+
+    push_video_frames(&mut h, 3);
+
+    let mut dtvcc = Vec::new();
+    for i in 0..3u16 {
+        let buf = h.pull().unwrap();
+        let captions = captions(&buf);
+        assert_eq!(captions.len(), 1);
+
+        let (caption_type, data) = &captions[0];
+        assert_eq!(*caption_type, gst_video::VideoCaptionType::Cea708Cdp);
+        // 20 triplets at 30fps
+        assert_eq!(data.len(), 73);
+        assert_eq!(&data[5..7], &i.to_be_bytes());
+        assert_eq!(&data[7..9], &[0x72, 0xe0 | 20]);
+
+        let cc_data = &data[9..69];
+        if i == 0 {
+            assert_eq!(&cc_data[..6], &[0xfc, 0x94, 0x20, 0xf9, 0x80, 0x80]);
+        } else {
+            assert_eq!(&cc_data[..6], &[0xf8, 0x80, 0x80, 0xf9, 0x80, 0x80]);
+        }
+
+        let frame_dtvcc = cc_data[6..]
+            .chunks_exact(3)
+            .filter(|triplet| triplet[0] & 0x04 == 0x04)
+            .map(|triplet| triplet.to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(frame_dtvcc.len(), [18, 12, 0][i as usize]);
+        dtvcc.extend(frame_dtvcc);
+    }
+
+    assert_eq!(dtvcc.concat(), cc_data[3..].to_vec());
+}
+
+/* Check that caption buffers are attached as is to the frame they start in
+ * when not scheduling */
+#[test]
+fn test_no_schedule() {
+    init();
+
+    let (mut h, mut h_caption) = new_harnesses("closedcaption/x-cea-708, format=cc_data");
+    h.element().unwrap().set_property("schedule", false);
+
+    let first = [0xfc, 0x94, 0x20, 0xfd, 0x80, 0x80];
+    let second = [0xfc, 0xc1, 0xc2];
+    let third = [0xfc, 0xc3, 0xc4];
+
+    let buf = new_timed_buffer(first, ClockTime::ZERO, ClockTime::from_mseconds(10));
+    assert_eq!(h_caption.push(buf), Ok(gst::FlowSuccess::Ok));
+    let buf = new_timed_buffer(
+        second,
+        ClockTime::from_mseconds(10),
+        ClockTime::from_mseconds(10),
+    );
+    assert_eq!(h_caption.push(buf), Ok(gst::FlowSuccess::Ok));
+    let buf = new_timed_buffer(third, 2 * frame_duration(), frame_duration());
+    assert_eq!(h_caption.push(buf), Ok(gst::FlowSuccess::Ok));
+    h_caption.push_event(gst::event::Eos::new());
+
+    push_video_frames(&mut h, 3);
+
+    let buf = h.pull().unwrap();
+    assert_eq!(
+        captions(&buf),
+        vec![
+            (gst_video::VideoCaptionType::Cea708Raw, first.to_vec()),
+            (gst_video::VideoCaptionType::Cea708Raw, second.to_vec()),
+        ]
+    );
+
+    let buf = h.pull().unwrap();
+    assert_eq!(captions(&buf), vec![]);
+
+    let buf = h.pull().unwrap();
+    assert_eq!(
+        captions(&buf),
+        vec![(gst_video::VideoCaptionType::Cea708Raw, third.to_vec())]
+    );
+}
+
+/* Check that video frames are output without captions when no caption pad
+ * was requested, and that video EOS is forwarded */
+#[test]
+fn test_video_only() {
+    init();
+
+    let mut h = gst_check::Harness::with_padnames("rscccombiner", Some("sink"), Some("src"));
+    h.set_src_caps_str("video/x-raw, format=I420, width=320, height=240, framerate=30/1");
+
+    push_video_frames(&mut h, 2);
+    h.push_event(gst::event::Eos::new());
+
+    for i in 0..2 {
+        let buf = h.pull().unwrap();
+        assert_eq!(buf.pts(), Some(i * frame_duration()));
+        assert_eq!(captions(&buf), vec![]);
+    }
+
+    loop {
+        let event = h.pull_event().unwrap();
+        if event.type_() == gst::EventType::Eos {
+            break;
+        }
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::ClockTime;
+use pretty_assertions::assert_eq;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+    });
+}
+
+fn new_timed_buffer<T: AsRef<[u8]> + Send + 'static>(
+    slice: T,
+    timestamp: ClockTime,
+    duration: ClockTime,
+) -> gst::buffer::Buffer {
+    let mut buf = gst::Buffer::from_slice(slice);
+    let buf_ref = buf.get_mut().unwrap();
+    buf_ref.set_pts(timestamp);
+    buf_ref.set_duration(duration);
+    buf
+}
+
+/* Check that CEA-608 byte pairs are wrapped in CDPs with a continuous
+ * sequence counter, and that input timecodes are carried over */
+#[test]
+fn test_cea608_raw_to_cdp() {
+    init();
+
+    let mut h = gst_check::Harness::new("rsccconverter");
+    h.set_src_caps_str("closedcaption/x-cea-608, format=raw, framerate=30/1");
+    h.set_sink_caps_str("closedcaption/x-cea-708, format=cdp");
+
+    let frame_duration = ClockTime::SECOND / 30;
+    let timecode = gst_video::ValidVideoTimeCode::new(
+        gst::Fraction::new(30, 1),
+        None,
+        gst_video::VideoTimeCodeFlags::empty(),
+        1,
+        2,
+        3,
+        4,
+        0,
+    )
+    .unwrap();
+
+    let inputs: [[u8; 2]; 2] = [[0x94, 0x20], [0xc1, 0xc2]];
+    for (i, data) in inputs.iter().enumerate() {
+        let mut buf = new_timed_buffer(*data, i as u64 * frame_duration, frame_duration);
+        if i == 0 {
+            gst_video::VideoTimeCodeMeta::add(buf.get_mut().unwrap(), &timecode);
+        }
+        assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+    }
+
+    for (i, data) in inputs.iter().enumerate() {
+        let buf = h.pull().unwrap();
+        assert_eq!(buf.pts(), Some(i as u64 * frame_duration));
+
+        let cdp = buf.map_readable().unwrap();
+        // 5 bytes of timecode and 20 triplets at 30fps
+        assert_eq!(cdp.len(), 78);
+        assert_eq!(&cdp[..5], &[0x96, 0x69, 78, 0x5f, 0xc3]);
+        assert_eq!(&cdp[5..7], &(i as u16).to_be_bytes());
+        assert_eq!(&cdp[7..12], &[0x71, 0xc1, 0x82, 0x03, 4 + i as u8]);
+        assert_eq!(&cdp[12..14], &[0x72, 0xe0 | 20]);
+        assert_eq!(&cdp[14..17], &[0xfc, data[0], data[1]]);
+        assert_eq!(&cdp[17..20], &[0xf9, 0x80, 0x80]);
+        assert_eq!(&cdp[74..77], &[0x74, 0x00, i as u8]);
+        assert_eq!(cdp.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)), 0);
+
+        let meta = buf.meta::<gst_video::VideoTimeCodeMeta>().unwrap();
+        assert_eq!(meta.tc().frames(), 4 + i as u32);
+    }
+}
+
+/* Check that CEA-608 byte pairs are spread over every other frame when
+ * converting from 30fps cc_data to 60fps, and flushed on EOS */
+#[test]
+fn test_cc_data_to_cea608_raw_framerate() {
+    init();
+
+    let mut h = gst_check::Harness::new("rsccconverter");
+    h.set_src_caps_str("closedcaption/x-cea-708, format=cc_data, framerate=30/1");
+    h.set_sink_caps_str("closedcaption/x-cea-608, format=raw, framerate=60/1");
+
+    let buf = new_timed_buffer(
+        [0xfc, 0x94, 0x20, 0xfc, 0xc1, 0xc2],
+        ClockTime::ZERO,
+        ClockTime::SECOND / 30,
+    );
+    assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+
+    let buf = h.pull().unwrap();
+    assert_eq!(buf.pts(), Some(ClockTime::ZERO));
+    assert_eq!(buf.duration(), Some(ClockTime::from_nseconds(16_666_667)));
+    assert_eq!(&*buf.map_readable().unwrap(), &[0x94, 0x20]);
+    assert_eq!(h.buffers_in_queue(), 0);
+
+    h.push_event(gst::event::Eos::new());
+
+    let buf = h.pull().unwrap();
+    assert_eq!(buf.pts(), Some(ClockTime::SECOND / 30));
+    assert_eq!(&*buf.map_readable().unwrap(), &[0xc1, 0xc2]);
+}
@@ -0,0 +1,180 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+use gst::ClockTime;
+
+use std::sync::{Arc, Mutex};
+
+use pretty_assertions::assert_eq;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+    });
+}
+
+fn frame_duration() -> ClockTime {
+    ClockTime::SECOND / 30
+}
+
+fn new_video_buffer(frame_no: u64, captions: &[&[u8]]) -> gst::Buffer {
+    let mut buf = gst::Buffer::from_slice([0u8; 16]);
+    {
+        let buf = buf.get_mut().unwrap();
+        buf.set_pts(frame_no * frame_duration());
+        buf.set_duration(frame_duration());
+
+        for data in captions {
+            gst_video::VideoCaptionMeta::add(buf, gst_video::VideoCaptionType::Cea708Raw, data);
+        }
+    }
+
+    buf
+}
+
+#[derive(Debug, PartialEq)]
+enum Item {
+    Buffer(ClockTime, Vec<u8>),
+    Gap(ClockTime),
+}
+
+#[derive(Default)]
+struct Received {
+    caps: Option<gst::Caps>,
+    items: Vec<Item>,
+}
+
+/// Links the caption pad to a pad collecting its buffers, caps and gaps
+fn collect_caption_pad(
+    element: &gst::Element,
+) -> (Arc<Mutex<Received>>, Arc<Mutex<Vec<gst::Pad>>>) {
+    let received = Arc::new(Mutex::new(Received::default()));
+    let sink_pads = Arc::new(Mutex::new(Vec::new()));
+
+    let received_clone = received.clone();
+    let sink_pads_clone = sink_pads.clone();
+    element.connect_pad_added(move |_, pad| {
+        let received_chain = received_clone.clone();
+        let received_event = received_clone.clone();
+        let sink_pad = gst::Pad::builder(Some("sink"), gst::PadDirection::Sink)
+            .chain_function(move |_, _, buffer| {
+                let data = buffer.map_readable().unwrap();
+                received_chain
+                    .lock()
+                    .unwrap()
+                    .items
+                    .push(Item::Buffer(buffer.pts().unwrap(), data.to_vec()));
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .event_function(move |_, _, event| {
+                use gst::EventView;
+
+                let mut received = received_event.lock().unwrap();
+                match event.view() {
+                    EventView::Caps(e) => received.caps = Some(e.caps_owned()),
+                    EventView::Gap(e) => received.items.push(Item::Gap(e.get().0)),
+                    _ => (),
+                }
+
+                true
+            })
+            .build();
+        sink_pad.set_active(true).unwrap();
+        pad.link(&sink_pad).unwrap();
+        sink_pads_clone.lock().unwrap().push(sink_pad);
+    });
+
+    (received, sink_pads)
+}
+
+/* Check that captions are pushed on the caption pad with the timestamps of
+ * their frame, and gaps for frames without captions */
+#[test]
+fn test_extract() {
+    init();
+
+    let mut h = gst_check::Harness::with_padnames("rsccextractor", Some("sink"), Some("src"));
+    let (received, _sink_pads) = collect_caption_pad(&h.element().unwrap());
+    h.set_src_caps_str("video/x-raw, format=I420, width=320, height=240, framerate=30/1");
+
+    // No caption pad until the first caption
+    assert_eq!(h.push(new_video_buffer(0, &[])), Ok(gst::FlowSuccess::Ok));
+    assert!(h.element().unwrap().static_pad("caption").is_none());
+
+    let first: &[u8] = &[0xfc, 0x94, 0x20];
+    let second: &[u8] = &[0xfc, 0xc1, 0xc2];
+    assert_eq!(
+        h.push(new_video_buffer(1, &[first])),
+        Ok(gst::FlowSuccess::Ok)
+    );
+    assert_eq!(h.push(new_video_buffer(2, &[])), Ok(gst::FlowSuccess::Ok));
+    assert_eq!(
+        h.push(new_video_buffer(3, &[second])),
+        Ok(gst::FlowSuccess::Ok)
+    );
+
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received.caps,
+        Some(
+            gst::Caps::builder("closedcaption/x-cea-708")
+                .field("format", "cc_data")
+                .field("framerate", gst::Fraction::new(30, 1))
+                .build()
+        )
+    );
+    assert_eq!(
+        received.items,
+        vec![
+            Item::Buffer(frame_duration(), first.to_vec()),
+            Item::Gap(2 * frame_duration()),
+            Item::Buffer(3 * frame_duration(), second.to_vec()),
+        ]
+    );
+
+    // The video buffers keep their meta by default
+    for i in 0..4 {
+        let buf = h.pull().unwrap();
+        assert_eq!(
+            buf.iter_meta::<gst_video::VideoCaptionMeta>().count(),
+            (i % 2) as usize
+        );
+    }
+}
+
+/* Check that the caption meta can be removed from the video buffers */
+#[test]
+fn test_remove_caption_meta() {
+    init();
+
+    let mut h = gst_check::Harness::with_padnames("rsccextractor", Some("sink"), Some("src"));
+    let (received, _sink_pads) = collect_caption_pad(&h.element().unwrap());
+    h.element()
+        .unwrap()
+        .set_property("remove-caption-meta", true);
+    h.set_src_caps_str("video/x-raw, format=I420, width=320, height=240, framerate=30/1");
+
+    let data: &[u8] = &[0xfc, 0x94, 0x20];
+    assert_eq!(
+        h.push(new_video_buffer(0, &[data])),
+        Ok(gst::FlowSuccess::Ok)
+    );
+
+    let buf = h.pull().unwrap();
+    assert_eq!(buf.iter_meta::<gst_video::VideoCaptionMeta>().count(), 0);
+
+    assert_eq!(
+        received.lock().unwrap().items,
+        vec![Item::Buffer(ClockTime::ZERO, data.to_vec())]
+    );
+}
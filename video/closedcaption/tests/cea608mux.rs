@@ -0,0 +1,131 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+use gst::ClockTime;
+use pretty_assertions::assert_eq;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+    });
+}
+
+fn new_timed_buffer<T: AsRef<[u8]> + Send + 'static>(
+    slice: T,
+    timestamp: ClockTime,
+    duration: ClockTime,
+) -> gst::buffer::Buffer {
+    let mut buf = gst::Buffer::from_slice(slice);
+    let buf_ref = buf.get_mut().unwrap();
+    buf_ref.set_pts(timestamp);
+    buf_ref.set_duration(duration);
+    buf
+}
+
+fn frame_pts(frame_no: u64) -> ClockTime {
+    (frame_no * ClockTime::SECOND).mul_div_round(1, 30).unwrap()
+}
+
+fn pull_until_eos(h: &mut gst_check::Harness) -> Vec<Vec<u8>> {
+    loop {
+        let event = h.pull_event().unwrap();
+        if event.type_() == gst::EventType::Eos {
+            break;
+        }
+    }
+
+    let mut frames = Vec::new();
+    while let Some(buffer) = h.try_pull() {
+        frames.push(buffer.map_readable().unwrap().to_vec());
+    }
+
+    frames
+}
+
+/* The control codes of CC1 input are rewritten for CC3, in field 2 */
+#[test]
+fn test_cc3() {
+    init();
+
+    let mut h = gst_check::Harness::with_padnames("cea608mux", Some("cc3"), Some("src"));
+    h.set_src_caps_str("closedcaption/x-cea-608, format=raw, framerate=(fraction)30/1");
+    h.set_sink_caps_str("closedcaption/x-cea-608, format=s334-1a, framerate=(fraction)30/1");
+
+    /* RCL, then "He" */
+    for (i, pair) in [[0x94, 0x20], [0xc8, 0xe5]].iter().enumerate() {
+        let inbuf = new_timed_buffer(*pair, frame_pts(i as u64), frame_pts(1));
+        assert_eq!(h.push(inbuf), Ok(gst::FlowSuccess::Ok));
+    }
+    h.push_event(gst::event::Eos::new());
+
+    let frames = pull_until_eos(&mut h);
+    assert_eq!(
+        frames,
+        vec![
+            vec![0x80, 0x80, 0x80, 0x00, 0x15, 0x20],
+            vec![0x80, 0x80, 0x80, 0x00, 0xc8, 0xe5],
+        ]
+    );
+}
+
+/* Two channels end up in the two fields of the same frames */
+#[test]
+fn test_cc1_cc3() {
+    init();
+
+    let mut h1 = gst_check::Harness::with_padnames("cea608mux", Some("cc1"), Some("src"));
+    let mut h2 = gst_check::Harness::with_element(&h1.element().unwrap(), Some("cc3"), None);
+
+    h1.set_src_caps_str("closedcaption/x-cea-608, format=raw, framerate=(fraction)30/1");
+    h2.set_src_caps_str("closedcaption/x-cea-608, format=raw, framerate=(fraction)30/1");
+    h1.set_sink_caps_str("closedcaption/x-cea-608, format=s334-1a, framerate=(fraction)30/1");
+
+    /* RCL, then "He" on CC1 and "Ho" on CC3 */
+    for (i, (pair1, pair2)) in [([0x94, 0x20], [0x94, 0x20]), ([0xc8, 0xe5], [0xc8, 0xef])]
+        .iter()
+        .enumerate()
+    {
+        let inbuf = new_timed_buffer(*pair1, frame_pts(i as u64), frame_pts(1));
+        assert_eq!(h1.push(inbuf), Ok(gst::FlowSuccess::Ok));
+        let inbuf = new_timed_buffer(*pair2, frame_pts(i as u64), frame_pts(1));
+        assert_eq!(h2.push(inbuf), Ok(gst::FlowSuccess::Ok));
+    }
+    h1.push_event(gst::event::Eos::new());
+    h2.push_event(gst::event::Eos::new());
+
+    let frames = pull_until_eos(&mut h1);
+    assert_eq!(
+        frames,
+        vec![
+            vec![0x80, 0x94, 0x20, 0x00, 0x15, 0x20],
+            vec![0x80, 0xc8, 0xe5, 0x00, 0xc8, 0xef],
+        ]
+    );
+}
+
+/* Only one channel per field, and only CC1 to CC4 */
+#[test]
+fn test_request_pads() {
+    init();
+
+    let mux = gst::ElementFactory::make("cea608mux", None).unwrap();
+
+    let pad = mux.request_pad_simple("cc%u").unwrap();
+    assert_eq!(pad.name(), "cc1");
+    assert!(mux.request_pad_simple("cc2").is_none());
+    assert!(mux.request_pad_simple("cc5").is_none());
+
+    let pad = mux.request_pad_simple("cc%u").unwrap();
+    assert_eq!(pad.name(), "cc3");
+    assert!(mux.request_pad_simple("cc%u").is_none());
+}
@@ -0,0 +1,114 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+use gst::ClockTime;
+
+use pretty_assertions::assert_eq;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+    });
+}
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+
+/// The CDP packets of the MCC file, one per frame
+fn parse_mcc() -> Vec<gst::Buffer> {
+    let data = include_bytes!("captions-test_708.mcc").as_ref();
+
+    let mut h = gst_check::Harness::new("mccparse");
+    h.set_src_caps_str("application/x-mcc, version=(int) 1");
+
+    let buf = gst::Buffer::from_mut_slice(Vec::from(data));
+    assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+    h.push_event(gst::event::Eos::new());
+
+    let mut buffers = Vec::new();
+    while let Some(buf) = h.try_pull() {
+        buffers.push(buf);
+    }
+    buffers
+}
+
+/// Checks that the windows of a CEA-708 service are attached as overlay
+/// composition when downstream supports it
+#[test]
+fn test_cea708_service() {
+    init();
+
+    let cdps = parse_mcc();
+    assert!(!cdps.is_empty());
+
+    let mut h = gst_check::Harness::new("cea608overlay");
+    h.element().unwrap().set_property("service", 1i32);
+    h.set_src_caps_str(&format!(
+        "video/x-raw, format=BGRA, width={}, height={}, framerate=30000/1001",
+        WIDTH, HEIGHT
+    ));
+    h.set_sink_caps_str(&format!(
+        "video/x-raw(meta:GstVideoOverlayComposition), format=BGRA, width={}, height={}, framerate=30000/1001",
+        WIDTH, HEIGHT
+    ));
+
+    let mut first_overlay = None;
+
+    for cdp in &cdps {
+        let mut buf = gst::Buffer::with_size((WIDTH * HEIGHT * 4) as usize).unwrap();
+        {
+            let buf = buf.get_mut().unwrap();
+            buf.set_pts(cdp.pts());
+            buf.set_duration(cdp.duration());
+
+            let data = cdp.map_readable().unwrap();
+            gst_video::VideoCaptionMeta::add(buf, gst_video::VideoCaptionType::Cea708Cdp, &data);
+        }
+        assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+
+        let buf = h.pull().unwrap();
+        assert_eq!(buf.pts(), cdp.pts());
+
+        let meta = match buf.meta::<gst_video::VideoOverlayCompositionMeta>() {
+            Some(meta) => meta,
+            None => continue,
+        };
+
+        let composition = meta.overlay();
+        assert!(composition.n_rectangles() > 0);
+
+        for idx in 0..composition.n_rectangles() {
+            let rectangle = composition.rectangle(idx).unwrap();
+            let (x, y, width, height) = rectangle.render_rectangle();
+            assert!(x >= 0 && y >= 0);
+            assert!(width > 0 && height > 0);
+            assert!(x as u32 + width <= WIDTH && y as u32 + height <= HEIGHT);
+        }
+
+        first_overlay.get_or_insert(buf.pts().unwrap());
+    }
+
+    // The first caption, "These are 708 captions\r\n(top left)", is
+    // displayed at the same time as cea708tott outputs it
+    assert_eq!(first_overlay, Some(ClockTime::from_nseconds(166_833_333)));
+
+    let caps = h
+        .sinkpad()
+        .expect("harness has no sinkpad")
+        .current_caps()
+        .expect("pad has no caps");
+    assert!(caps
+        .features(0)
+        .unwrap()
+        .contains(&gst_video::CAPS_FEATURE_META_GST_VIDEO_OVERLAY_COMPOSITION));
+}
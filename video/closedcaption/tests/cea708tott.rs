@@ -0,0 +1,87 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+use gst::ClockTime;
+
+use pretty_assertions::assert_eq;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+    });
+}
+
+#[test]
+fn test_parse() {
+    init();
+    let data = include_bytes!("captions-test_708.mcc").as_ref();
+
+    let mut h = gst_check::Harness::new_parse("mccparse ! cea708tott");
+    h.set_src_caps_str("application/x-mcc, version=(int) 1");
+    h.set_sink_caps_str("text/x-raw");
+
+    let buf = gst::Buffer::from_mut_slice(Vec::from(data));
+    assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+
+    let expected: [(ClockTime, ClockTime, &'static str); 3] = [
+        (
+            ClockTime::from_nseconds(166_833_333),
+            ClockTime::from_nseconds(4_738_066_667),
+            "These are 708 captions\r\n(top left)",
+        ),
+        (
+            ClockTime::from_nseconds(5_238_566_666),
+            ClockTime::from_nseconds(6_673_333_334),
+            "These are 708 captions\r\n(middle)",
+        ),
+        (
+            ClockTime::from_nseconds(12_245_566_666),
+            ClockTime::from_nseconds(7_007_000_000),
+            "These are 708 captions\r\n(bottom left)",
+        ),
+    ];
+
+    for (i, e) in expected.iter().enumerate() {
+        let buf = h.try_pull().unwrap();
+
+        assert_eq!(
+            e.0,
+            buf.pts().unwrap(),
+            "Unexpected PTS for {}th buffer",
+            i + 1
+        );
+        assert_eq!(
+            e.1,
+            buf.duration().unwrap(),
+            "Unexpected duration for {}th buffer",
+            i + 1
+        );
+
+        let data = buf.map_readable().unwrap();
+        let s = std::str::from_utf8(&*data)
+            .unwrap_or_else(|_| panic!("Non-UTF8 data for {}th buffer", i + 1));
+        assert_eq!(e.2, s, "Unexpected data for {}th buffer", i + 1);
+    }
+
+    let caps = h
+        .sinkpad()
+        .expect("harness has no sinkpad")
+        .current_caps()
+        .expect("pad has no caps");
+    assert_eq!(
+        caps,
+        gst::Caps::builder("text/x-raw")
+            .field("format", "utf8")
+            .build()
+    );
+}
@@ -134,3 +134,119 @@ Time Code Rate=30DF\r\n\
         Ok(expected_output.as_str()),
     );
 }
+
+/// A CDP packet at 29.97 fps without timecode, `cc_data` being padded to the
+/// 20 triplets of the frame rate
+fn cdp(sequence: u16, cc_data: &[u8]) -> Vec<u8> {
+    let mut cdp = vec![0x96, 0x69, 0x00, 0x4f, 0x43];
+    cdp.extend_from_slice(&sequence.to_be_bytes());
+    cdp.extend_from_slice(&[0x72, 0xf4]);
+    cdp.extend_from_slice(cc_data);
+    for _ in cc_data.len() / 3..20 {
+        cdp.extend_from_slice(&[0xfa, 0x00, 0x00]);
+    }
+    cdp.push(0x74);
+    cdp.extend_from_slice(&sequence.to_be_bytes());
+    cdp[2] = cdp.len() as u8 + 1;
+    let sum = cdp.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    cdp.push(sum.wrapping_neg());
+    cdp
+}
+
+/// Check that frames missing from the stream are reported, and filled
+/// with empty CDP packets when repairing as MCC files must be contiguous
+#[test]
+fn test_encode_validation() {
+    init();
+
+    let frames = [0u32, 1, 4];
+
+    for &(validation, repaired) in &[("report", false), ("repair", true)] {
+        let mut h =
+            gst_check::Harness::new_parse(&format!("mccenc validation={} ! mccparse", validation));
+        h.set_src_caps_str(
+            "closedcaption/x-cea-708, format=(string)cdp, framerate=(fraction)30000/1001",
+        );
+        let bus = gst::Bus::new();
+        h.element().unwrap().set_bus(Some(&bus));
+
+        for &frame in &frames {
+            let tc = gst_video::ValidVideoTimeCode::new(
+                gst::Fraction::new(30000, 1001),
+                None,
+                gst_video::VideoTimeCodeFlags::DROP_FRAME,
+                0,
+                0,
+                0,
+                frame,
+                0,
+            )
+            .unwrap();
+
+            let mut buf = gst::Buffer::from_mut_slice(cdp(frame as u16, &[0xfc, 0x80, 0x80]));
+            let buf_ref = buf.get_mut().unwrap();
+            gst_video::VideoTimeCodeMeta::add(buf_ref, &tc);
+            buf_ref.set_pts(gst::ClockTime::from_nseconds(
+                frame as u64 * 1_001_000_000 / 30,
+            ));
+
+            assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+        }
+        h.push_event(gst::event::Eos::new());
+
+        let mut output = Vec::new();
+        while let Some(buf) = h.try_pull() {
+            let tc = buf
+                .meta::<gst_video::VideoTimeCodeMeta>()
+                .expect("No timecode for buffer")
+                .tc();
+            let map = buf.map_readable().unwrap();
+            output.push((tc.to_string(), map.to_vec()));
+        }
+
+        let expected = if repaired {
+            vec![
+                ("00:00:00;00", cdp(0, &[0xfc, 0x80, 0x80])),
+                ("00:00:00;01", cdp(1, &[0xfc, 0x80, 0x80])),
+                ("00:00:00;02", cdp(2, &[])),
+                ("00:00:00;03", cdp(3, &[])),
+                ("00:00:00;04", cdp(4, &[0xfc, 0x80, 0x80])),
+            ]
+        } else {
+            vec![
+                ("00:00:00;00", cdp(0, &[0xfc, 0x80, 0x80])),
+                ("00:00:00;01", cdp(1, &[0xfc, 0x80, 0x80])),
+                ("00:00:00;04", cdp(4, &[0xfc, 0x80, 0x80])),
+            ]
+        };
+        assert_eq!(
+            output,
+            expected
+                .into_iter()
+                .map(|(tc, data)| (tc.to_string(), data))
+                .collect::<Vec<_>>()
+        );
+
+        let mut issues = Vec::new();
+        while let Some(msg) = bus.pop() {
+            if let gst::MessageView::Element(msg) = msg.view() {
+                let s = msg.structure().unwrap();
+                if s.name() == "caption-validation" {
+                    issues.push((
+                        s.get::<String>("issue").unwrap(),
+                        s.get::<String>("timecode").unwrap(),
+                        s.get::<bool>("repaired").unwrap(),
+                    ));
+                }
+            }
+        }
+        assert_eq!(
+            issues,
+            vec![(
+                String::from("timecode-discontinuity"),
+                String::from("00:00:00;04"),
+                repaired
+            )]
+        );
+    }
+}
@@ -202,3 +202,124 @@ fn test_pull() {
         }
     }
 }
+
+/// A CDP packet at 29.97 fps without timecode, `cc_data` being padded to the
+/// 20 triplets of the frame rate
+fn cdp(sequence: u16, cc_data: &[u8]) -> Vec<u8> {
+    let mut cdp = vec![0x96, 0x69, 0x00, 0x4f, 0x43];
+    cdp.extend_from_slice(&sequence.to_be_bytes());
+    cdp.extend_from_slice(&[0x72, 0xf4]);
+    cdp.extend_from_slice(cc_data);
+    for _ in cc_data.len() / 3..20 {
+        cdp.extend_from_slice(&[0xfa, 0x00, 0x00]);
+    }
+    cdp.push(0x74);
+    cdp.extend_from_slice(&sequence.to_be_bytes());
+    cdp[2] = cdp.len() as u8 + 1;
+    let sum = cdp.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    cdp.push(sum.wrapping_neg());
+    cdp
+}
+
+/// An MCC caption line carrying a CDP packet
+fn caption_line(timecode: &str, cdp: &[u8]) -> String {
+    let checksum = cdp.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    let payload = cdp
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<String>();
+    format!(
+        "{}\tT{:02X}{}{:02X}\r\n",
+        timecode,
+        cdp.len(),
+        payload,
+        checksum
+    )
+}
+
+fn validation_messages(bus: &gst::Bus) -> Vec<(String, String, bool)> {
+    let mut messages = Vec::new();
+
+    while let Some(msg) = bus.pop() {
+        if let gst::MessageView::Element(msg) = msg.view() {
+            let s = msg.structure().unwrap();
+            if s.name() == "caption-validation" {
+                messages.push((
+                    s.get::<String>("issue").unwrap(),
+                    s.get::<String>("timecode").unwrap(),
+                    s.get::<bool>("repaired").unwrap(),
+                ));
+            }
+        }
+    }
+
+    messages
+}
+
+/// Check that a single control code and a missing frame are reported, and
+/// that the control code is repeated in the padding of the next frame on
+/// request. Missing frames can't be repaired when parsing.
+#[test]
+fn test_validation() {
+    init();
+
+    let data = format!(
+        "File Format=MacCaption_MCC V1.0\r\n\r\n\r\nTime Code Rate=30DF\r\n\r\n{}{}{}",
+        caption_line("00:00:00;00", &cdp(0, &[0xfc, 0x94, 0x2c])),
+        caption_line("00:00:00;01", &cdp(1, &[0xf8, 0x80, 0x80])),
+        caption_line("00:00:00;03", &cdp(3, &[0xf8, 0x80, 0x80])),
+    );
+
+    for &(validation, repaired) in &[("report", false), ("repair", true)] {
+        let mut h = gst_check::Harness::new_parse(&format!("mccparse validation={}", validation));
+        h.set_src_caps_str("application/x-mcc, version=(int) 1");
+        let bus = gst::Bus::new();
+        h.element().unwrap().set_bus(Some(&bus));
+
+        assert_eq!(
+            h.push(gst::Buffer::from_slice(data.clone().into_bytes())),
+            Ok(gst::FlowSuccess::Ok)
+        );
+        h.push_event(gst::event::Eos::new());
+
+        let mut output = Vec::new();
+        while let Some(buf) = h.try_pull() {
+            let tc = buf
+                .meta::<gst_video::VideoTimeCodeMeta>()
+                .expect("No timecode for buffer")
+                .tc();
+            let map = buf.map_readable().unwrap();
+            output.push((tc.to_string(), map.to_vec()));
+        }
+
+        let second = if repaired {
+            cdp(1, &[0xfc, 0x94, 0x2c])
+        } else {
+            cdp(1, &[0xf8, 0x80, 0x80])
+        };
+        assert_eq!(
+            output,
+            vec![
+                ("00:00:00;00".to_string(), cdp(0, &[0xfc, 0x94, 0x2c])),
+                ("00:00:00;01".to_string(), second),
+                ("00:00:00;03".to_string(), cdp(3, &[0xf8, 0x80, 0x80])),
+            ]
+        );
+
+        assert_eq!(
+            validation_messages(&bus),
+            vec![
+                (
+                    "control-code-doubling".to_string(),
+                    "00:00:00;01".to_string(),
+                    repaired
+                ),
+                (
+                    "timecode-discontinuity".to_string(),
+                    "00:00:00;03".to_string(),
+                    false
+                ),
+            ]
+        );
+    }
+}
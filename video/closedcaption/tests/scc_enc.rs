@@ -7,6 +7,7 @@
 //
 // SPDX-License-Identifier: MPL-2.0
 
+use gst::prelude::*;
 use pretty_assertions::assert_eq;
 
 fn init() {
@@ -215,3 +216,58 @@ fn test_encode_multiple_packets() {
         std::str::from_utf8(expected_output3.as_ref())
     );
 }
+
+/// Check that a single control code is doubled by replacing the padding
+/// that follows it, and that a repeated timecode is moved to the next frame
+#[test]
+fn test_encode_validation() {
+    init();
+
+    let inputs = [([0x94, 0x2c], 0), ([0x80, 0x80], 1), ([0xc1, 0xc2], 1)];
+    let expected_output = b"Scenarist_SCC V1.0\r\n\r\n00:00:00;00\t942c 942c c1c2\r\n\r\n";
+
+    let mut h = gst_check::Harness::new_parse("sccenc validation=repair");
+    h.set_src_caps_str("closedcaption/x-cea-608, format=raw, framerate=(fraction)30000/1001");
+    let bus = gst::Bus::new();
+    h.element().unwrap().set_bus(Some(&bus));
+
+    for (input, frame) in inputs {
+        let tc = gst_video::ValidVideoTimeCode::new(
+            gst::Fraction::new(30000, 1001),
+            None,
+            gst_video::VideoTimeCodeFlags::DROP_FRAME,
+            0,
+            0,
+            0,
+            frame,
+            0,
+        )
+        .unwrap();
+
+        let mut buf = gst::Buffer::from_mut_slice(Vec::from(&input[..]));
+        let buf_ref = buf.get_mut().unwrap();
+        gst_video::VideoTimeCodeMeta::add(buf_ref, &tc);
+        buf_ref.set_pts(gst::ClockTime::ZERO);
+
+        assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+    }
+    h.push_event(gst::event::Eos::new());
+
+    let buf = h.pull().expect("Couldn't pull buffer");
+    let map = buf.map_readable().expect("Couldn't map buffer readable");
+    assert_eq!(
+        std::str::from_utf8(map.as_ref()),
+        std::str::from_utf8(expected_output.as_ref())
+    );
+
+    let mut issues = Vec::new();
+    while let Some(msg) = bus.pop() {
+        if let gst::MessageView::Element(msg) = msg.view() {
+            let s = msg.structure().unwrap();
+            assert_eq!(s.name(), "caption-validation");
+            assert!(s.get::<bool>("repaired").unwrap());
+            issues.push(s.get::<String>("issue").unwrap());
+        }
+    }
+    assert_eq!(issues, vec!["control-code-doubling", "bandwidth-overrun"]);
+}
@@ -273,3 +273,96 @@ fn test_pull() {
         }
     }
 }
+
+fn validation_messages(bus: &gst::Bus) -> Vec<(String, String, bool)> {
+    let mut messages = Vec::new();
+
+    while let Some(msg) = bus.pop() {
+        if let gst::MessageView::Element(msg) = msg.view() {
+            let s = msg.structure().unwrap();
+            if s.name() == "caption-validation" {
+                messages.push((
+                    s.get::<String>("issue").unwrap(),
+                    s.get::<String>("timecode").unwrap(),
+                    s.get::<bool>("repaired").unwrap(),
+                ));
+            }
+        }
+    }
+
+    messages
+}
+
+/// Check that a single control code at the end of a line and a line
+/// overlapping the previous one are reported, and repaired on request
+#[test]
+fn test_validation() {
+    init();
+
+    let data = b"Scenarist_SCC V1.0\r\n\r\n00:00:00;00\t9420 9420 942c\r\n\r\n00:00:00;02\t942f 942f\r\n\r\n";
+
+    for &(validation, repaired) in &[("report", false), ("repair", true)] {
+        let mut h = gst_check::Harness::new_parse(&format!("sccparse validation={}", validation));
+        h.set_src_caps_str("application/x-scc");
+        let bus = gst::Bus::new();
+        h.element().unwrap().set_bus(Some(&bus));
+
+        assert_eq!(
+            h.push(gst::Buffer::from_slice(&data[..])),
+            Ok(gst::FlowSuccess::Ok)
+        );
+        h.push_event(gst::event::Eos::new());
+
+        let mut pairs = Vec::new();
+        while let Some(buf) = h.try_pull() {
+            let tc = buf
+                .meta::<gst_video::VideoTimeCodeMeta>()
+                .expect("No timecode for buffer")
+                .tc();
+            let map = buf.map_readable().unwrap();
+            pairs.push((tc.to_string(), format!("{:02x}{:02x}", map[0], map[1])));
+        }
+
+        let expected: &[(&str, &str)] = if repaired {
+            &[
+                ("00:00:00;00", "9420"),
+                ("00:00:00;01", "9420"),
+                ("00:00:00;02", "942c"),
+                ("00:00:00;03", "942c"),
+                ("00:00:00;04", "942f"),
+                ("00:00:00;05", "942f"),
+            ]
+        } else {
+            &[
+                ("00:00:00;00", "9420"),
+                ("00:00:00;01", "9420"),
+                ("00:00:00;02", "942c"),
+                ("00:00:00;02", "942f"),
+                ("00:00:00;03", "942f"),
+            ]
+        };
+        assert_eq!(
+            pairs,
+            expected
+                .iter()
+                .map(|(tc, pair)| (tc.to_string(), pair.to_string()))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            validation_messages(&bus),
+            vec![
+                (
+                    "control-code-doubling".to_string(),
+                    "00:00:00;00".to_string(),
+                    repaired
+                ),
+                (
+                    "bandwidth-overrun".to_string(),
+                    "00:00:00;02".to_string(),
+                    repaired
+                ),
+            ]
+        );
+    }
+}
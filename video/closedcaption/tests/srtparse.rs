@@ -0,0 +1,105 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::ClockTime;
+use pretty_assertions::assert_eq;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+    });
+}
+
+const CAPTIONS: &str = "1\r\n\
+    00:00:01,000 --> 00:00:02,500\r\n\
+    <font color=\"yellow\">Hello</font> <i>world</i>\r\n\
+    \r\n\
+    2\r\n\
+    00:00:03,000 --> 00:00:04,000\r\n\
+    Second &amp; last\r\n";
+
+fn push_captions(h: &mut gst_check::Harness) -> Vec<gst::Buffer> {
+    h.set_src_caps_str("application/x-subtitle");
+
+    let buf = gst::Buffer::from_slice(CAPTIONS.as_bytes());
+    assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+    h.push_event(gst::event::Eos::new());
+
+    let mut buffers = Vec::new();
+    while let Some(buf) = h.try_pull() {
+        buffers.push(buf);
+    }
+
+    buffers
+}
+
+/* Check that cues are output as plain text, the last one on EOS */
+#[test]
+fn test_parse_text() {
+    init();
+
+    let mut h = gst_check::Harness::new("srtparse");
+    let buffers = push_captions(&mut h);
+
+    let buffers = buffers
+        .iter()
+        .map(|buf| {
+            (
+                buf.pts().unwrap(),
+                buf.duration().unwrap(),
+                std::str::from_utf8(&buf.map_readable().unwrap())
+                    .unwrap()
+                    .to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        buffers,
+        vec![
+            (
+                ClockTime::SECOND,
+                ClockTime::from_mseconds(1500),
+                "Hello world".to_string()
+            ),
+            (
+                3 * ClockTime::SECOND,
+                ClockTime::SECOND,
+                "Second & last".to_string()
+            ),
+        ]
+    );
+}
+
+/* Check that the font colors and italics map to CEA-608 styles, and that the
+ * mode property is used */
+#[test]
+fn test_parse_json() {
+    init();
+
+    let mut h = gst_check::Harness::new_parse("srtparse mode=roll-up2");
+    h.set_sink_caps_str("application/x-json, format=cea608");
+
+    let buffers = push_captions(&mut h);
+    assert_eq!(buffers.len(), 2);
+
+    let lines: serde_json::Value =
+        serde_json::from_slice(&buffers[0].map_readable().unwrap()).unwrap();
+    assert_eq!(lines["mode"], "RollUp2");
+    assert_eq!(
+        lines["lines"][0]["chunks"],
+        serde_json::json!([
+            { "style": "Yellow", "underline": false, "text": "Hello" },
+            { "style": "ItalicWhite", "underline": false, "text": "world" },
+        ])
+    );
+}
@@ -0,0 +1,439 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+use pretty_assertions::assert_eq;
+
+use std::sync::Mutex;
+
+const WORD: &str = "Hello";
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+        gst::Element::register(
+            None,
+            "testtranslator",
+            gst::Rank::None,
+            TestTranslator::static_type(),
+        )
+        .unwrap();
+    });
+}
+
+// Transcriber outputting a single word for the first audio buffer, then
+// gaps for the following ones
+mod imp_transcriber {
+    use super::*;
+
+    pub struct TestTranscriber {
+        srcpad: gst::Pad,
+        sinkpad: gst::Pad,
+        word_sent: Mutex<bool>,
+    }
+
+    impl TestTranscriber {
+        fn sink_chain(&self, buffer: gst::Buffer) -> Result<gst::FlowSuccess, gst::FlowError> {
+            let pts = buffer.pts().unwrap();
+            let duration = buffer.duration().unwrap();
+
+            let mut word_sent = self.word_sent.lock().unwrap();
+            if *word_sent {
+                drop(word_sent);
+                self.srcpad
+                    .push_event(gst::event::Gap::builder(pts).duration(duration).build());
+                return Ok(gst::FlowSuccess::Ok);
+            }
+            *word_sent = true;
+            drop(word_sent);
+
+            let mut outbuf = gst::Buffer::from_slice(WORD);
+            {
+                let outbuf = outbuf.get_mut().unwrap();
+                outbuf.set_pts(pts);
+                outbuf.set_duration(duration);
+            }
+
+            self.srcpad.push(outbuf)
+        }
+
+        fn sink_event(
+            &self,
+            pad: &gst::Pad,
+            element: &super::TestTranscriber,
+            event: gst::Event,
+        ) -> bool {
+            match event.view() {
+                gst::EventView::Caps(_) => {
+                    let caps = gst::Caps::builder("text/x-raw")
+                        .field("format", "utf8")
+                        .build();
+                    self.srcpad.push_event(gst::event::Caps::new(&caps))
+                }
+                gst::EventView::FlushStop(_) => {
+                    *self.word_sent.lock().unwrap() = false;
+                    pad.event_default(Some(element), event)
+                }
+                _ => pad.event_default(Some(element), event),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TestTranscriber {
+        const NAME: &'static str = "CcTestTranscriber";
+        type Type = super::TestTranscriber;
+        type ParentType = gst::Element;
+
+        fn with_class(klass: &Self::Class) -> Self {
+            let templ = klass.pad_template("sink").unwrap();
+            let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+                .chain_function(|_pad, parent, buffer| {
+                    TestTranscriber::catch_panic_pad_function(
+                        parent,
+                        || Err(gst::FlowError::Error),
+                        |this, _element| this.sink_chain(buffer),
+                    )
+                })
+                .event_function(|pad, parent, event| {
+                    TestTranscriber::catch_panic_pad_function(
+                        parent,
+                        || false,
+                        |this, element| this.sink_event(pad, element, event),
+                    )
+                })
+                .build();
+
+            let templ = klass.pad_template("src").unwrap();
+            let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+                .flags(gst::PadFlags::FIXED_CAPS)
+                .build();
+
+            Self {
+                srcpad,
+                sinkpad,
+                word_sent: Mutex::new(false),
+            }
+        }
+    }
+
+    impl ObjectImpl for TestTranscriber {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+
+            obj.add_pad(&self.sinkpad).unwrap();
+            obj.add_pad(&self.srcpad).unwrap();
+        }
+    }
+
+    impl GstObjectImpl for TestTranscriber {}
+
+    impl ElementImpl for TestTranscriber {
+        fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+            static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+                gst::subclass::ElementMetadata::new(
+                    "Test Transcriber",
+                    "Audio/Text/Filter",
+                    "Outputs a single word for transcriberbin tests",
+                    "agent <agent@local>",
+                )
+            });
+
+            Some(&*ELEMENT_METADATA)
+        }
+
+        fn pad_templates() -> &'static [gst::PadTemplate] {
+            static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+                let src_caps = gst::Caps::builder("text/x-raw")
+                    .field("format", "utf8")
+                    .build();
+                let src_pad_template = gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &src_caps,
+                )
+                .unwrap();
+
+                let sink_caps = gst::Caps::builder("audio/x-raw").build();
+                let sink_pad_template = gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &sink_caps,
+                )
+                .unwrap();
+
+                vec![src_pad_template, sink_pad_template]
+            });
+
+            PAD_TEMPLATES.as_ref()
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct TestTranscriber(ObjectSubclass<imp_transcriber::TestTranscriber>) @extends gst::Element, gst::Object;
+}
+
+// Translator passing its input through unchanged
+mod imp_translator {
+    use super::*;
+
+    pub struct TestTranslator {
+        srcpad: gst::Pad,
+        sinkpad: gst::Pad,
+        target_language_code: Mutex<Option<String>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TestTranslator {
+        const NAME: &'static str = "CcTestTranslator";
+        type Type = super::TestTranslator;
+        type ParentType = gst::Element;
+
+        fn with_class(klass: &Self::Class) -> Self {
+            let templ = klass.pad_template("src").unwrap();
+            let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+                .flags(gst::PadFlags::PROXY_CAPS)
+                .build();
+
+            let templ = klass.pad_template("sink").unwrap();
+            let sinkpad_srcpad = srcpad.clone();
+            let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink"))
+                .chain_function(move |_pad, _parent, buffer| sinkpad_srcpad.push(buffer))
+                .flags(gst::PadFlags::PROXY_CAPS)
+                .build();
+
+            Self {
+                srcpad,
+                sinkpad,
+                target_language_code: Mutex::new(None),
+            }
+        }
+    }
+
+    impl ObjectImpl for TestTranslator {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+
+            obj.add_pad(&self.sinkpad).unwrap();
+            obj.add_pad(&self.srcpad).unwrap();
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecString::builder("target-language-code")
+                    .nick("Target Language Code")
+                    .blurb("The language to translate to")
+                    .build()]
+            });
+
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(
+            &self,
+            _obj: &Self::Type,
+            _id: usize,
+            value: &glib::Value,
+            pspec: &glib::ParamSpec,
+        ) {
+            match pspec.name() {
+                "target-language-code" => {
+                    *self.target_language_code.lock().unwrap() =
+                        value.get().expect("type checked upstream");
+                }
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "target-language-code" => self.target_language_code.lock().unwrap().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+    }
+
+    impl GstObjectImpl for TestTranslator {}
+
+    impl ElementImpl for TestTranslator {
+        fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+            static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+                gst::subclass::ElementMetadata::new(
+                    "Test Translator",
+                    "Text/Filter",
+                    "Passes text through for transcriberbin tests",
+                    "agent <agent@local>",
+                )
+            });
+
+            Some(&*ELEMENT_METADATA)
+        }
+
+        fn pad_templates() -> &'static [gst::PadTemplate] {
+            static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+                let caps = gst::Caps::builder("text/x-raw")
+                    .field("format", "utf8")
+                    .build();
+                let src_pad_template = gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &caps,
+                )
+                .unwrap();
+
+                let sink_pad_template = gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &caps,
+                )
+                .unwrap();
+
+                vec![src_pad_template, sink_pad_template]
+            });
+
+            PAD_TEMPLATES.as_ref()
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct TestTranslator(ObjectSubclass<imp_translator::TestTranslator>) @extends gst::Element, gst::Object;
+}
+
+fn frame_pts(frame_no: u64) -> gst::ClockTime {
+    (frame_no * gst::ClockTime::SECOND)
+        .mul_div_round(1, 30)
+        .unwrap()
+}
+
+/// The text of the byte pairs of a field, without parity and control codes
+fn field_text(pairs: &[[u8; 2]]) -> String {
+    pairs
+        .iter()
+        .filter(|pair| pair[0] & 0x7f >= 0x20)
+        .flat_map(|pair| pair.iter())
+        .map(|byte| byte & 0x7f)
+        .filter(|byte| *byte >= 0x20)
+        .map(char::from)
+        .collect()
+}
+
+/// The transcript on CC1 and its translation on CC3 end up in both fields
+/// of the caption meta of the video buffers
+#[test]
+fn test_translation_cc1_cc3() {
+    init();
+
+    let bin = gst::ElementFactory::make("transcriberbin", None).unwrap();
+    bin.set_property("transcriber", glib::Object::new::<TestTranscriber>(&[]));
+    bin.set_property("translator", "testtranslator");
+    bin.set_property(
+        "translation-languages",
+        gst::Structure::builder("languages")
+            .field("cc1", "transcript")
+            .field("cc3", "fr")
+            .build(),
+    );
+    bin.set_property(
+        "cc-caps",
+        gst::Caps::builder("closedcaption/x-cea-608")
+            .field("format", "s334-1a")
+            .build(),
+    );
+    bin.set_property("latency", 1000u32);
+
+    let mut hv = gst_check::Harness::with_element(&bin, Some("sink_video"), Some("src_video"));
+    let mut ha = gst_check::Harness::with_element(&bin, Some("sink_audio"), Some("src_audio"));
+    hv.use_systemclock();
+    ha.use_systemclock();
+
+    hv.set_src_caps_str("video/x-raw, format=GRAY8, width=16, height=16, framerate=30/1");
+    ha.set_src_caps_str("audio/x-raw, format=S16LE, layout=interleaved, rate=16000, channels=1");
+
+    // 3 seconds of silence and video, interleaved as the video is queued
+    // for the duration of the transcription latency only
+    for i in 0..30u64 {
+        let mut buf = gst::Buffer::from_mut_slice(vec![0u8; 3200]);
+        {
+            let buf = buf.get_mut().unwrap();
+            buf.set_pts(i * 100 * gst::ClockTime::MSECOND);
+            buf.set_duration(100 * gst::ClockTime::MSECOND);
+        }
+        assert_eq!(ha.push(buf), Ok(gst::FlowSuccess::Ok));
+
+        for frame_no in 3 * i..3 * (i + 1) {
+            let mut buf = gst::Buffer::from_mut_slice(vec![0u8; 16 * 16]);
+            {
+                let buf = buf.get_mut().unwrap();
+                buf.set_pts(frame_pts(frame_no));
+                buf.set_duration(frame_pts(1));
+            }
+            assert_eq!(hv.push(buf), Ok(gst::FlowSuccess::Ok));
+        }
+    }
+
+    hv.push_event(gst::event::Eos::new());
+    ha.push_event(gst::event::Eos::new());
+
+    loop {
+        let event = hv.pull_event().unwrap();
+        if event.type_() == gst::EventType::Eos {
+            break;
+        }
+    }
+
+    let mut fields = [Vec::new(), Vec::new()];
+    while let Some(buf) = hv.try_pull() {
+        for meta in buf.iter_meta::<gst_video::VideoCaptionMeta>() {
+            assert_eq!(
+                meta.caption_type(),
+                gst_video::VideoCaptionType::Cea608S3341a
+            );
+            for triplet in meta.data().chunks_exact(3) {
+                let field = if triplet[0] & 0x80 == 0x80 { 0 } else { 1 };
+                fields[field].push([triplet[1], triplet[2]]);
+            }
+        }
+    }
+
+    assert!(field_text(&fields[0]).contains(WORD));
+    assert_eq!(field_text(&fields[0]), field_text(&fields[1]));
+
+    let mut translators = Vec::new();
+    bin.downcast_ref::<gst::Bin>()
+        .unwrap()
+        .iterate_recurse()
+        .foreach(|element| {
+            if element
+                .factory()
+                .map_or(false, |factory| factory.name() == "testtranslator")
+            {
+                translators.push(element);
+            }
+        })
+        .unwrap();
+    assert_eq!(translators.len(), 1);
+    assert_eq!(
+        translators[0].property::<Option<String>>("target-language-code"),
+        Some("fr".to_string())
+    );
+}
@@ -0,0 +1,176 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::ClockTime;
+use pretty_assertions::assert_eq;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+    });
+}
+
+fn new_timed_buffer<T: AsRef<[u8]> + Send + 'static>(
+    slice: T,
+    timestamp: ClockTime,
+    duration: ClockTime,
+) -> gst::buffer::Buffer {
+    let mut buf = gst::Buffer::from_slice(slice);
+    let buf_ref = buf.get_mut().unwrap();
+    buf_ref.set_pts(timestamp);
+    buf_ref.set_duration(duration);
+    buf
+}
+
+fn document(buf: &gst::Buffer) -> String {
+    std::str::from_utf8(&buf.map_readable().unwrap())
+        .unwrap()
+        .to_string()
+}
+
+/* Check that every text buffer is encoded to its own document, with the
+ * lines at the bottom of the screen and the configured language */
+#[test]
+fn test_encode_text() {
+    init();
+
+    let mut h = gst_check::Harness::new_parse("ttmlenc language=fr");
+    h.set_src_caps_str("text/x-raw, format=utf8");
+
+    let buf = new_timed_buffer(
+        "Bonjour\nle monde",
+        ClockTime::SECOND,
+        2 * ClockTime::SECOND,
+    );
+    assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+
+    let buf = h.pull().unwrap();
+    assert_eq!(buf.pts(), Some(ClockTime::SECOND));
+    assert_eq!(buf.duration(), Some(2 * ClockTime::SECOND));
+
+    let doc = document(&buf);
+    assert!(doc.contains("xml:lang=\"fr\""));
+    assert!(doc.contains("ttp:profile=\"http://www.w3.org/ns/ttml/profile/imsc1/text\""));
+    assert!(doc.contains(
+        "<p begin=\"00:00:01.000\" end=\"00:00:03.000\" region=\"r13\"><span style=\"s0\">Bonjour</span></p>"
+    ));
+    assert!(doc.contains(
+        "<p begin=\"00:00:01.000\" end=\"00:00:03.000\" region=\"r14\"><span style=\"s0\">le monde</span></p>"
+    ));
+
+    let caps = h
+        .sinkpad()
+        .expect("harness has no sinkpad")
+        .current_caps()
+        .expect("pad has no caps");
+    assert_eq!(caps, gst::Caps::builder("application/ttml+xml").build());
+}
+
+/* Check that the JSON positions and styles are carried over to regions and
+ * spans */
+#[test]
+fn test_encode_json() {
+    init();
+
+    let mut h = gst_check::Harness::new("ttmlenc");
+    h.set_src_caps_str("application/x-json, format=cea608");
+
+    let json = serde_json::json!({
+        "lines": [{
+            "column": 4,
+            "row": 2,
+            "carriage_return": true,
+            "chunks": [
+                { "style": "Yellow", "underline": true, "text": "Hello" },
+                { "style": "ItalicWhite", "underline": false, "text": "world" },
+            ],
+        }],
+        "mode": "PopOn",
+        "clear": false,
+    });
+    let buf = new_timed_buffer(
+        serde_json::to_vec(&json).unwrap(),
+        ClockTime::ZERO,
+        ClockTime::SECOND,
+    );
+    assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+
+    let doc = document(&h.pull().unwrap());
+    assert!(doc.contains(
+        "<region xml:id=\"r2c4\" tts:origin=\"20% 20.667%\" tts:extent=\"70% 5.333%\" tts:displayAlign=\"before\" tts:textAlign=\"start\"/>"
+    ));
+    assert!(doc.contains(
+        "<span style=\"s0\" tts:color=\"yellow\" tts:textDecoration=\"underline\">Hello</span><span style=\"s0\" tts:fontStyle=\"italic\"> world</span>"
+    ));
+}
+
+/* Check that in segmented mode documents of a fixed duration are output,
+ * with the paragraphs clipped to them, and that EOS flushes the last one */
+#[test]
+fn test_encode_segmented() {
+    init();
+
+    let mut h = gst_check::Harness::new_parse("ttmlenc segment-duration=2000000000");
+    h.set_src_caps_str("text/x-raw, format=utf8");
+
+    let inputs = [
+        ("one", ClockTime::SECOND, 2 * ClockTime::SECOND),
+        ("two", 5 * ClockTime::SECOND, ClockTime::from_mseconds(500)),
+    ];
+    for (text, pts, duration) in inputs {
+        let buf = new_timed_buffer(text, pts, duration);
+        assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+    }
+
+    // The first two documents are complete when the second buffer arrives
+    assert_eq!(h.buffers_in_queue(), 2);
+    h.push_event(gst::event::Eos::new());
+
+    let expected = [
+        "<p begin=\"00:00:01.000\" end=\"00:00:02.000\" region=\"r13\"><span style=\"s0\">one</span></p>",
+        "<p begin=\"00:00:02.000\" end=\"00:00:03.000\" region=\"r13\"><span style=\"s0\">one</span></p>",
+        "<p begin=\"00:00:05.000\" end=\"00:00:05.500\" region=\"r13\"><span style=\"s0\">two</span></p>",
+    ];
+    for (i, paragraph) in expected.iter().enumerate() {
+        let buf = h.pull().unwrap();
+        assert_eq!(buf.pts(), Some(i as u64 * 2 * ClockTime::SECOND));
+        assert_eq!(buf.duration(), Some(2 * ClockTime::SECOND));
+
+        let doc = document(&buf);
+        assert!(doc.contains(paragraph));
+        assert_eq!(doc.matches("<p ").count(), 1);
+    }
+
+    assert_eq!(h.buffers_in_queue(), 0);
+}
+
+/* Check that gaps are filled with empty documents in segmented mode */
+#[test]
+fn test_encode_segmented_gap() {
+    init();
+
+    let mut h = gst_check::Harness::new_parse("ttmlenc segment-duration=1000000000");
+    h.set_src_caps_str("text/x-raw, format=utf8");
+
+    assert!(h.push_event(
+        gst::event::Gap::builder(ClockTime::ZERO)
+            .duration(2 * ClockTime::SECOND)
+            .build()
+    ));
+
+    for i in 0..2u64 {
+        let buf = h.pull().unwrap();
+        assert_eq!(buf.pts(), Some(i * ClockTime::SECOND));
+        assert_eq!(buf.duration(), Some(ClockTime::SECOND));
+        assert!(!document(&buf).contains("<p "));
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::ClockTime;
+use pretty_assertions::assert_eq;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+    });
+}
+
+const DOCUMENT: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<tt xmlns="http://www.w3.org/ns/ttml" xmlns:tts="http://www.w3.org/ns/ttml#styling" xml:lang="en">
+  <head>
+    <styling>
+      <style xml:id="yellow" tts:color="yellow"/>
+    </styling>
+    <layout>
+      <region xml:id="top" tts:origin="10% 10%" tts:extent="80% 10%"/>
+    </layout>
+  </head>
+  <body>
+    <div>
+      <p begin="00:00:01.000" end="00:00:02.500" region="top"><span style="yellow">Hello</span> <span tts:fontStyle="italic">world</span></p>
+      <p begin="3s" end="4s">First line<br/>Second &amp; last</p>
+    </div>
+  </body>
+</tt>
+"##;
+
+fn pull_all(h: &mut gst_check::Harness) -> Vec<(ClockTime, ClockTime, Vec<u8>)> {
+    let mut buffers = Vec::new();
+
+    while let Some(buf) = h.try_pull() {
+        buffers.push((
+            buf.pts().unwrap(),
+            buf.duration().unwrap(),
+            buf.map_readable().unwrap().to_vec(),
+        ));
+    }
+
+    buffers
+}
+
+/* Check that a document received in several buffers is parsed once
+ * complete, and output as plain text */
+#[test]
+fn test_parse_text() {
+    init();
+
+    let mut h = gst_check::Harness::new("ttmlparse");
+    h.set_src_caps_str("application/ttml+xml");
+
+    let (first, second) = DOCUMENT.as_bytes().split_at(100);
+    assert_eq!(
+        h.push(gst::Buffer::from_slice(first)),
+        Ok(gst::FlowSuccess::Ok)
+    );
+    assert_eq!(h.buffers_in_queue(), 0);
+    assert_eq!(
+        h.push(gst::Buffer::from_slice(second)),
+        Ok(gst::FlowSuccess::Ok)
+    );
+
+    let buffers = pull_all(&mut h)
+        .into_iter()
+        .map(|(pts, duration, data)| (pts, duration, String::from_utf8(data).unwrap()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        buffers,
+        vec![
+            (
+                ClockTime::SECOND,
+                ClockTime::from_mseconds(1500),
+                "Hello world".to_string()
+            ),
+            (
+                3 * ClockTime::SECOND,
+                ClockTime::SECOND,
+                "First line\nSecond & last".to_string()
+            ),
+        ]
+    );
+}
+
+/* Check that regions and styles are mapped to CEA-608 positions and styles,
+ * and that paragraphs are clipped to the timestamp and duration of the
+ * document buffer */
+#[test]
+fn test_parse_json() {
+    init();
+
+    let mut h = gst_check::Harness::new_parse("ttmlparse mode=paint-on");
+    h.set_src_caps_str("application/ttml+xml");
+    h.set_sink_caps_str("application/x-json, format=cea608");
+
+    let mut buf = gst::Buffer::from_slice(DOCUMENT.as_bytes());
+    {
+        let buf = buf.get_mut().unwrap();
+        buf.set_pts(ClockTime::from_mseconds(2000));
+        buf.set_duration(ClockTime::from_mseconds(1500));
+    }
+    assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+
+    let buffers = pull_all(&mut h);
+    assert_eq!(buffers.len(), 2);
+
+    assert_eq!(buffers[0].0, ClockTime::from_mseconds(2000));
+    assert_eq!(buffers[0].1, ClockTime::from_mseconds(500));
+    let lines: serde_json::Value = serde_json::from_slice(&buffers[0].2).unwrap();
+    assert_eq!(lines["mode"], "PaintOn");
+    assert_eq!(lines["lines"][0]["row"], 0);
+    assert_eq!(lines["lines"][0]["column"], 0);
+    assert_eq!(
+        lines["lines"][0]["chunks"],
+        serde_json::json!([
+            { "style": "Yellow", "underline": false, "text": "Hello" },
+            { "style": "ItalicWhite", "underline": false, "text": "world" },
+        ])
+    );
+
+    assert_eq!(buffers[1].0, ClockTime::from_mseconds(3000));
+    assert_eq!(buffers[1].1, ClockTime::from_mseconds(500));
+    let lines: serde_json::Value = serde_json::from_slice(&buffers[1].2).unwrap();
+    assert_eq!(lines["lines"][0]["row"], 13);
+    assert_eq!(lines["lines"][1]["row"], 14);
+}
+
+/* Check that the output of ttmlenc parses back to the same text */
+#[test]
+fn test_roundtrip() {
+    init();
+
+    let mut h = gst_check::Harness::new_parse("ttmlenc segment-duration=1000000000 ! ttmlparse");
+    h.set_src_caps_str("text/x-raw, format=utf8");
+
+    let mut buf = gst::Buffer::from_slice("Hello\nworld");
+    {
+        let buf = buf.get_mut().unwrap();
+        buf.set_pts(ClockTime::from_mseconds(500));
+        buf.set_duration(ClockTime::SECOND);
+    }
+    assert_eq!(h.push(buf), Ok(gst::FlowSuccess::Ok));
+    h.push_event(gst::event::Eos::new());
+
+    let buffers = pull_all(&mut h)
+        .into_iter()
+        .map(|(pts, duration, data)| (pts, duration, String::from_utf8(data).unwrap()))
+        .collect::<Vec<_>>();
+
+    // The paragraph is split over two documents
+    assert_eq!(
+        buffers,
+        vec![
+            (
+                ClockTime::from_mseconds(500),
+                ClockTime::from_mseconds(500),
+                "Hello\nworld".to_string()
+            ),
+            (
+                ClockTime::SECOND,
+                ClockTime::from_mseconds(500),
+                "Hello\nworld".to_string()
+            ),
+        ]
+    );
+}
@@ -0,0 +1,200 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+use gst::ClockTime;
+use pretty_assertions::assert_eq;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+    });
+}
+
+fn new_timed_buffer<T: AsRef<[u8]> + Send + 'static>(
+    slice: T,
+    timestamp: ClockTime,
+    duration: ClockTime,
+) -> gst::buffer::Buffer {
+    let mut buf = gst::Buffer::from_slice(slice);
+    let buf_ref = buf.get_mut().unwrap();
+    buf_ref.set_pts(timestamp);
+    buf_ref.set_duration(duration);
+    buf
+}
+
+/* Decodes the output of tttocea708 with cea708tott */
+fn decode(buffers: Vec<gst::Buffer>, service_number: u32) -> Vec<(ClockTime, ClockTime, String)> {
+    let mut h = gst_check::Harness::new("cea708tott");
+    h.element()
+        .unwrap()
+        .set_property("service-number", service_number);
+    h.set_src_caps_str("closedcaption/x-cea-708, format=cdp, framerate=(fraction)30/1");
+
+    for buffer in buffers {
+        assert_eq!(h.push(buffer), Ok(gst::FlowSuccess::Ok));
+    }
+    h.push_event(gst::event::Eos::new());
+
+    let mut captions = Vec::new();
+    while let Some(buf) = h.try_pull() {
+        let data = buf.map_readable().unwrap();
+        captions.push((
+            buf.pts().unwrap(),
+            buf.duration().unwrap(),
+            std::str::from_utf8(&data).unwrap().to_string(),
+        ));
+    }
+
+    captions
+}
+
+fn pull_until_eos(h: &mut gst_check::Harness) -> Vec<gst::Buffer> {
+    let mut buffers = Vec::new();
+
+    loop {
+        let event = h.pull_event().unwrap();
+        if event.type_() == gst::EventType::Eos {
+            break;
+        }
+    }
+
+    while let Some(buffer) = h.try_pull() {
+        buffers.push(buffer);
+    }
+
+    buffers
+}
+
+/* Check translation of a simple string in pop-on mode */
+#[test]
+fn test_pop_on() {
+    init();
+
+    let mut h = gst_check::Harness::with_padnames("tttocea708", Some("sink_%u"), Some("src"));
+    let element = h.element().unwrap();
+    let pad = element.static_pad("sink_0").unwrap();
+    assert_eq!(pad.property::<u32>("service-number"), 1);
+    pad.set_property_from_str("mode", "pop-on");
+
+    h.set_src_caps_str("text/x-raw");
+    h.set_sink_caps_str("closedcaption/x-cea-708, format=cdp, framerate=(fraction)30/1");
+
+    let inbuf = new_timed_buffer(&"Hello", ClockTime::SECOND, ClockTime::SECOND);
+    assert_eq!(h.push(inbuf), Ok(gst::FlowSuccess::Ok));
+    h.push_event(gst::event::Eos::new());
+
+    let buffers = pull_until_eos(&mut h);
+
+    /* One CDP per frame, from the start of the caption until it is erased */
+    assert_eq!(buffers.len(), 31);
+    for (i, buffer) in buffers.iter().enumerate() {
+        assert_eq!(
+            buffer.pts().unwrap(),
+            ClockTime::SECOND + (i as u64 * ClockTime::SECOND).mul_div_round(1, 30).unwrap()
+        );
+        /* 20 cc_data triplets at 30 fps */
+        assert_eq!(buffer.size(), 73);
+    }
+
+    let caps = h
+        .sinkpad()
+        .expect("harness has no sinkpad")
+        .current_caps()
+        .expect("pad has no caps");
+    assert_eq!(
+        caps,
+        gst::Caps::builder("closedcaption/x-cea-708")
+            .field("format", "cdp")
+            .field("framerate", gst::Fraction::new(30, 1))
+            .build()
+    );
+
+    assert_eq!(
+        decode(buffers, 1),
+        vec![(ClockTime::SECOND, ClockTime::SECOND, "Hello".to_string())]
+    );
+}
+
+/* Check that two sink pads are encoded to two services of the same stream */
+#[test]
+fn test_multiple_services() {
+    init();
+
+    let mut h1 = gst_check::Harness::with_padnames("tttocea708", Some("sink_%u"), Some("src"));
+    let mut h2 = gst_check::Harness::with_element(&h1.element().unwrap(), Some("sink_%u"), None);
+
+    let element = h1.element().unwrap();
+    for pad in element.sink_pads() {
+        pad.set_property_from_str("mode", "pop-on");
+    }
+    assert_eq!(
+        element
+            .static_pad("sink_1")
+            .unwrap()
+            .property::<u32>("service-number"),
+        2
+    );
+
+    h1.set_src_caps_str("text/x-raw");
+    h1.set_sink_caps_str("closedcaption/x-cea-708, format=cdp, framerate=(fraction)30/1");
+    h2.set_src_caps_str("text/x-raw");
+
+    let inbuf = new_timed_buffer(&"Hi", ClockTime::SECOND, ClockTime::SECOND);
+    assert_eq!(h1.push(inbuf), Ok(gst::FlowSuccess::Ok));
+    let inbuf = new_timed_buffer(&"Salut", ClockTime::SECOND, 2 * ClockTime::SECOND);
+    assert_eq!(h2.push(inbuf), Ok(gst::FlowSuccess::Ok));
+
+    h1.push_event(gst::event::Eos::new());
+    h2.push_event(gst::event::Eos::new());
+
+    /* Both captions fit in the DTVCC packet of the first frame */
+    let buffers = pull_until_eos(&mut h1);
+    assert_eq!(buffers.len(), 61);
+
+    assert_eq!(
+        decode(buffers.clone(), 1),
+        vec![(ClockTime::SECOND, ClockTime::SECOND, "Hi".to_string())]
+    );
+    assert_eq!(
+        decode(buffers, 2),
+        vec![(
+            ClockTime::SECOND,
+            2 * ClockTime::SECOND,
+            "Salut".to_string()
+        )]
+    );
+}
+
+/* Check that a service already used by another pad is rejected */
+#[test]
+fn test_duplicate_service() {
+    init();
+
+    let h1 = gst_check::Harness::with_padnames("tttocea708", Some("sink_%u"), Some("src"));
+    let _h2 = gst_check::Harness::with_element(&h1.element().unwrap(), Some("sink_%u"), None);
+
+    let element = h1.element().unwrap();
+    let sink_0 = element.static_pad("sink_0").unwrap();
+    let sink_1 = element.static_pad("sink_1").unwrap();
+
+    sink_1.set_property("service-number", 1u32);
+    assert_eq!(sink_0.property::<u32>("service-number"), 1);
+    assert_eq!(sink_1.property::<u32>("service-number"), 2);
+
+    sink_1.set_property("service-number", 3u32);
+    assert_eq!(sink_1.property::<u32>("service-number"), 3);
+
+    /* The service released by sink_1 can now be used by sink_0 */
+    sink_0.set_property("service-number", 2u32);
+    assert_eq!(sink_0.property::<u32>("service-number"), 2);
+}
@@ -0,0 +1,225 @@
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#![allow(clippy::single_match)]
+
+use gst::prelude::*;
+use gst::ClockTime;
+use gst::EventView;
+use pretty_assertions::assert_eq;
+use std::path::PathBuf;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsclosedcaption::plugin_register_static().unwrap();
+    });
+}
+
+/// Pushes the sample file in small buffers and pulls all output buffers
+fn push_captions(h: &mut gst_check::Harness) -> Vec<(ClockTime, ClockTime, String)> {
+    let data = include_bytes!("captions.vtt");
+    let mut buffers = Vec::new();
+
+    for chunk in data.chunks(7) {
+        assert_eq!(
+            h.push(gst::Buffer::from_slice(chunk.to_vec())),
+            Ok(gst::FlowSuccess::Ok)
+        );
+    }
+    h.push_event(gst::event::Eos::new());
+
+    while let Some(buf) = h.try_pull() {
+        let text = std::str::from_utf8(&buf.map_readable().unwrap())
+            .unwrap()
+            .to_string();
+        buffers.push((buf.pts().unwrap(), buf.duration().unwrap(), text));
+    }
+
+    buffers
+}
+
+/* Check that cues are output as plain text with their timing */
+#[test]
+fn test_parse_text() {
+    init();
+
+    let mut h = gst_check::Harness::new("vttparse");
+    h.set_src_caps_str("application/x-subtitle-vtt");
+
+    let buffers = push_captions(&mut h);
+
+    assert_eq!(
+        buffers,
+        vec![
+            (
+                ClockTime::from_mseconds(500),
+                ClockTime::from_mseconds(1500),
+                "Hello world".to_string()
+            ),
+            (
+                ClockTime::from_mseconds(2500),
+                ClockTime::from_mseconds(1500),
+                "Second cue\non two lines".to_string()
+            ),
+            (
+                ClockTime::from_seconds(5),
+                ClockTime::from_seconds(2),
+                "Third cue".to_string()
+            ),
+            (
+                ClockTime::from_seconds(8),
+                ClockTime::from_mseconds(1500),
+                "Fourth cue".to_string()
+            ),
+        ]
+    );
+
+    let caps = h
+        .sinkpad()
+        .expect("harness has no sinkpad")
+        .current_caps()
+        .expect("pad has no caps");
+    assert_eq!(
+        caps,
+        gst::Caps::builder("text/x-raw")
+            .field("format", "utf8")
+            .build()
+    );
+}
+
+/* Check that styling and positioning is kept in the JSON output */
+#[test]
+fn test_parse_json() {
+    init();
+
+    let mut h = gst_check::Harness::new("vttparse");
+    h.set_src_caps_str("application/x-subtitle-vtt");
+    h.set_sink_caps_str("application/x-json, format=cea608");
+
+    let buffers = push_captions(&mut h);
+    assert_eq!(buffers.len(), 4);
+
+    let lines: serde_json::Value = serde_json::from_str(&buffers[0].2).unwrap();
+    assert_eq!(
+        lines,
+        serde_json::json!({
+            "lines": [{
+                "column": 0,
+                "row": 0,
+                "chunks": [
+                    { "style": "ItalicWhite", "underline": false, "text": "Hello" },
+                    { "style": "White", "underline": false, "text": "world" },
+                ],
+                "carriage_return": true,
+            }],
+            "mode": "PopOn",
+            "clear": false,
+        })
+    );
+
+    let lines: serde_json::Value = serde_json::from_str(&buffers[1].2).unwrap();
+    assert_eq!(lines["lines"][0]["row"], 13);
+    assert_eq!(lines["lines"][0]["chunks"][0]["style"], "Yellow");
+    assert_eq!(lines["lines"][1]["row"], 14);
+    assert_eq!(lines["lines"][1]["column"], 10);
+}
+
+#[test]
+fn test_pull() {
+    init();
+
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests/captions.vtt");
+
+    let mut h = gst_check::Harness::new_parse(&format!("filesrc location={:?} ! vttparse", path));
+
+    h.play();
+
+    /* Let's first pull until EOS */
+    let mut n_buffers = 0;
+    loop {
+        let mut done = false;
+
+        while h.events_in_queue() != 0 {
+            let event = h.pull_event();
+
+            if let Ok(event) = event {
+                match event.view() {
+                    EventView::Eos(_) => {
+                        done = true;
+                        break;
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        while h.buffers_in_queue() != 0 {
+            let _ = h.pull();
+            n_buffers += 1;
+        }
+
+        if done {
+            break;
+        }
+    }
+    assert_eq!(n_buffers, 4);
+
+    let mut q = gst::query::Duration::new(gst::Format::Time);
+    assert!(h.sinkpad().unwrap().peer_query(&mut q));
+    let duration: Option<ClockTime> = q.result().try_into().unwrap();
+    assert_eq!(duration, Some(ClockTime::from_mseconds(9500)));
+
+    /* Now seek and check that we only receive the cues inside the segment */
+    h.push_upstream_event(gst::event::Seek::new(
+        1.0,
+        gst::SeekFlags::FLUSH,
+        gst::SeekType::Set,
+        3 * gst::ClockTime::SECOND,
+        gst::SeekType::Set,
+        6 * gst::ClockTime::SECOND,
+    ));
+
+    let mut pts = Vec::new();
+    loop {
+        let mut done = false;
+
+        while h.buffers_in_queue() != 0 {
+            if let Ok(buffer) = h.pull() {
+                pts.push(buffer.pts().unwrap());
+            }
+        }
+
+        while h.events_in_queue() != 0 {
+            let event = h.pull_event();
+
+            if let Ok(event) = event {
+                match event.view() {
+                    EventView::Eos(_) => {
+                        done = true;
+                        break;
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        if done {
+            break;
+        }
+    }
+
+    assert_eq!(
+        pts,
+        vec![ClockTime::from_mseconds(2500), ClockTime::from_seconds(5)]
+    );
+}